@@ -0,0 +1,92 @@
+//! Embeds build metadata for `mdmd info` and `mdmd --version --verbose`.
+//!
+//! - `MDMD_GIT_COMMIT`: `git rev-parse HEAD` of the source tree, or
+//!   `unknown` when building outside a git checkout (e.g. from crates.io).
+//! - `MDMD_BUILD_DATE`: UTC build date as `YYYY-MM-DD`.  Honours
+//!   `SOURCE_DATE_EPOCH` for reproducible builds.
+//! - `MDMD_FEATURES`: comma-separated Cargo features enabled for this build.
+//!
+//! It also writes brotli (`.br`) and gzip (`.gz`) copies of the served
+//! stylesheet and script to `OUT_DIR`, which `web_assets` embeds so `serve`
+//! does not compress them on every request.
+
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+
+    let commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=MDMD_GIT_COMMIT={commit}");
+
+    let epoch_secs = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+        });
+    println!("cargo:rustc-env=MDMD_BUILD_DATE={}", utc_date(epoch_secs));
+
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(k, _)| k.strip_prefix("CARGO_FEATURE_").map(str::to_string))
+        .map(|f| f.to_lowercase().replace('_', "-"))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=MDMD_FEATURES={}", features.join(","));
+
+    let out_dir = env::var_os("OUT_DIR").expect("cargo sets OUT_DIR");
+    for name in ["mdmd.css", "mdmd.js"] {
+        precompress(name, Path::new(&out_dir));
+    }
+}
+
+/// Write `<name>.br` and `<name>.gz` to `out_dir`, compressed at maximum
+/// quality from `src/assets/<name>`.
+fn precompress(name: &str, out_dir: &Path) {
+    let src = format!("src/assets/{name}");
+    println!("cargo:rerun-if-changed={src}");
+    let data = fs::read(&src).unwrap_or_else(|e| panic!("read {src}: {e}"));
+
+    let mut br = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+    br.write_all(&data).expect("brotli to memory");
+    fs::write(out_dir.join(format!("{name}.br")), br.into_inner()).expect("write brotli asset");
+
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    gz.write_all(&data).expect("gzip to memory");
+    let gz = gz.finish().expect("gzip to memory");
+    fs::write(out_dir.join(format!("{name}.gz")), gz).expect("write gzip asset");
+}
+
+/// Format Unix-epoch seconds as a UTC `YYYY-MM-DD` date.
+///
+/// Uses Howard Hinnant's `civil_from_days` algorithm so the build script
+/// needs no date-handling dependency.
+fn utc_date(epoch_secs: u64) -> String {
+    let z = (epoch_secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
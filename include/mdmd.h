@@ -0,0 +1,47 @@
+/*
+ * mdmd.h — C interface to the mdmd HTML renderer.
+ *
+ * Link against the mdmd cdylib (libmdmd.so / libmdmd.dylib / mdmd.dll),
+ * built with `cargo build --release --lib`.
+ *
+ * All strings are NUL-terminated UTF-8.  Strings returned by the
+ * mdmd_render_* functions are owned by the caller and must be released with
+ * mdmd_string_free().  NULL is returned when `markdown` is NULL, an argument
+ * is not valid UTF-8, `target` is unknown, or rendering fails.
+ */
+#ifndef MDMD_H
+#define MDMD_H
+
+#include <stdbool.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Rewrite local links to root-relative hrefs under serve_root (mdmd serve). */
+#define MDMD_TARGET_SERVE 0
+/* Keep links as authored; inline assets into page shells (mdmd html). */
+#define MDMD_TARGET_HTML 1
+
+/*
+ * Render markdown to an HTML fragment with heading anchors.
+ * file_path and serve_root may be NULL; they only affect MDMD_TARGET_SERVE.
+ */
+char *mdmd_render_markdown(const char *markdown, const char *file_path,
+                           const char *serve_root, int target);
+
+/*
+ * Render markdown to a complete HTML page (frontmatter, TOC, content),
+ * identical to mdmd serve / mdmd html output minus backlinks.
+ */
+char *mdmd_render_page(const char *markdown, const char *file_path,
+                       const char *serve_root, int target, bool full_width);
+
+/* Free a string returned by mdmd_render_*.  NULL is ignored. */
+void mdmd_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MDMD_H */
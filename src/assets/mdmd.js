@@ -46,8 +46,10 @@
             var id = href.charAt(0) === '#' ? href.slice(1) : null;
             if (id !== null && id === activeId) {
                 a.classList.add('active');
+                a.setAttribute('aria-current', 'location');
             } else {
                 a.classList.remove('active');
+                a.removeAttribute('aria-current');
             }
         });
     }
@@ -108,13 +110,18 @@
 (function () {
     var btn = document.getElementById('theme-toggle');
     if (!btn) { return; }
-    btn.addEventListener('click', function () {
+    // Effective current theme (accounts for the system default).
+    function effectivelyDark() {
         var current = document.documentElement.getAttribute('data-theme');
-        // Determine effective current theme (account for system default)
-        var effectivelyDark = current === 'dark' ||
+        return current === 'dark' ||
             (!current && window.matchMedia('(prefers-color-scheme: dark)').matches);
-        var next = effectivelyDark ? 'light' : 'dark';
+    }
+    // aria-pressed reports dark mode, so screen readers announce the state.
+    btn.setAttribute('aria-pressed', effectivelyDark() ? 'true' : 'false');
+    btn.addEventListener('click', function () {
+        var next = effectivelyDark() ? 'light' : 'dark';
         document.documentElement.setAttribute('data-theme', next);
+        btn.setAttribute('aria-pressed', next === 'dark' ? 'true' : 'false');
         try { localStorage.setItem('mdmd-theme', next); } catch (_) {}
     });
 }());
@@ -470,23 +477,24 @@ window.mdmd = window.mdmd || {};
 }());
 
 /* --------------------------------------------------------------------- *
- * File-change detection: poll /_mdmd/freshness and reveal notice div   *
+ * File-change detection: poll /_mdmd/v1/freshness and reveal notice    *
  * when the server-side mtime changes (bd-38z).                         *
  * --------------------------------------------------------------------- */
 (function () {
     var meta_mtime = document.querySelector('meta[name="mdmd-mtime"]');
     var meta_path = document.querySelector('meta[name="mdmd-path"]');
     if (!meta_mtime || !meta_path) { return; }
-    var initial_mtime = parseInt(meta_mtime.content, 10);
+    // Read on every poll: the page's own edits (task toggles) update it.
+    var known_mtime = function () { return parseInt(meta_mtime.content, 10); };
     var page_path = meta_path.content; // norm_display WITHOUT leading slash
     var failures = 0;
     var MAX_FAILURES = 3;
     var interval = setInterval(function () {
-        fetch('/_mdmd/freshness?path=' + encodeURIComponent(page_path))
+        fetch('/_mdmd/v1/freshness?path=' + encodeURIComponent(page_path))
             .then(function (r) { return r.ok ? r.json() : Promise.reject('non-200'); })
             .then(function (data) {
                 failures = 0;
-                if (data.mtime !== initial_mtime) {
+                if (data.mtime !== known_mtime()) {
                     clearInterval(interval);
                     var notice = document.getElementById('mdmd-change-notice');
                     if (notice) { notice.removeAttribute('hidden'); }
@@ -498,3 +506,728 @@ window.mdmd = window.mdmd || {};
             });
     }, 4000);
 }());
+
+/* --------------------------------------------------------------------- *
+ * "Load latest" keeps the reader's place.  A plain reload restores the *
+ * old pixel offset, which drifts when the edit is above the reader, so *
+ * the click remembers the last heading above the top of the viewport   *
+ * and the distance below it, and the reloaded page scrolls back there. *
+ * Without that heading the old offset is used.  The click also saves   *
+ * the text of each block, and blocks of the new version whose text is  *
+ * not among them are briefly highlighted.                              *
+ * --------------------------------------------------------------------- */
+(function () {
+    var meta_path = document.querySelector('meta[name="mdmd-path"]');
+    if (!meta_path) { return; }
+    var KEY = 'mdmd-reload-position';
+    var HEADINGS =
+        'main.content h1[id], main.content h2[id], main.content h3[id],' +
+        'main.content h4[id], main.content h5[id], main.content h6[id]';
+    var INNER = 'p, li, h1, h2, h3, h4, h5, h6, td, th, pre, blockquote';
+    var CHANGED_CLASS = 'mdmd-changed';
+    var CHANGED_MS = 3000;
+
+    /* Innermost text blocks: a list item holding paragraphs is left out
+     * so an edit marks the paragraph, not the whole item. */
+    function blocks() {
+        return Array.from(document.querySelectorAll('main.content :is(' + INNER + ')'))
+            .filter(function (el) { return !el.querySelector(INNER); });
+    }
+
+    function blockText(el) {
+        return el.textContent.replace(/\s+/g, ' ').trim();
+    }
+
+    /* Capture phase: runs before the button's own location.reload(). */
+    document.addEventListener('click', function (e) {
+        var button = e.target;
+        if (!button.classList || !button.classList.contains('change-notice-reload')) { return; }
+        var anchor = null;
+        document.querySelectorAll(HEADINGS).forEach(function (h) {
+            if (h.getBoundingClientRect().top <= 1) { anchor = h; }
+        });
+        var saved = {
+            path: meta_path.content,
+            id: anchor ? anchor.id : null,
+            below: anchor ? -anchor.getBoundingClientRect().top : 0,
+            scroll: window.scrollY,
+            blocks: blocks().map(blockText)
+        };
+        try { sessionStorage.setItem(KEY, JSON.stringify(saved)); } catch (_) { return; }
+        if ('scrollRestoration' in history) { history.scrollRestoration = 'manual'; }
+    }, true);
+
+    var saved = null;
+    try {
+        saved = JSON.parse(sessionStorage.getItem(KEY));
+        sessionStorage.removeItem(KEY);
+    } catch (_) {}
+    if ('scrollRestoration' in history) { history.scrollRestoration = 'auto'; }
+    if (!saved || saved.path !== meta_path.content) { return; }
+    var heading = saved.id ? document.getElementById(saved.id) : null;
+    window.scrollTo(0, heading
+        ? heading.getBoundingClientRect().top + window.scrollY + saved.below
+        : saved.scroll);
+
+    var before = {};
+    (saved.blocks || []).forEach(function (text) { before[text] = (before[text] || 0) + 1; });
+    blocks().forEach(function (el) {
+        var text = blockText(el);
+        if (!text) { return; }
+        if (before[text]) { before[text]--; return; }
+        el.classList.add(CHANGED_CLASS);
+        setTimeout(function () { el.classList.remove(CHANGED_CLASS); }, CHANGED_MS);
+    });
+}());
+
+/* --------------------------------------------------------------------- *
+ * Editor scroll sync: long-poll /_mdmd/v1/scroll for positions an      *
+ * editor plugin posts for this file, and centre each one in the        *
+ * viewport.  A position names the headings around the editor's line   *
+ * and how far between them it is; the page scrolls the same fraction  *
+ * of the way between the rendered headings.  A `reload` sent to the    *
+ * serve --control socket arrives the same way and reloads the page     *
+ * through the change notice, keeping the reading position.             *
+ * --------------------------------------------------------------------- */
+(function () {
+    var meta_path = document.querySelector('meta[name="mdmd-path"]');
+    var main = document.querySelector('main.content');
+    if (!meta_path || !main) { return; }
+    // Unset until the first command: the server then waits for new ones.
+    var seq = null;
+    var failures = 0;
+    var MAX_FAILURES = 3;
+
+    function top(el) { return el.getBoundingClientRect().top + window.scrollY; }
+
+    function scrollTo(target) {
+        var start = (target.anchor && document.getElementById(target.anchor)) || main;
+        var next = target.next && document.getElementById(target.next);
+        var from = start === main ? top(main) : top(start);
+        var to = next ? top(next) : top(main) + main.offsetHeight;
+        var y = from + target.fraction * Math.max(0, to - from);
+        window.scrollTo(0, Math.max(0, y - window.innerHeight / 2));
+    }
+
+    function reload() {
+        var button = document.querySelector('.change-notice-reload');
+        if (button) { button.click(); } else { location.reload(); }
+    }
+
+    function wait() {
+        var after = seq === null ? '' : '&after=' + seq;
+        fetch('/_mdmd/v1/scroll?path=' + encodeURIComponent(meta_path.content) + after)
+            .then(function (r) {
+                if (r.status === 204) { return null; }
+                return r.ok ? r.json() : Promise.reject('non-200');
+            })
+            .then(function (target) {
+                failures = 0;
+                if (target) {
+                    seq = target.seq;
+                    if (target.reload) { reload(); return; }
+                    scrollTo(target);
+                }
+                wait();
+            })
+            .catch(function () {
+                failures++;
+                if (failures < MAX_FAILURES) { setTimeout(wait, 4000); }
+            });
+    }
+    wait();
+}());
+
+/* --------------------------------------------------------------------- *
+ * Task toggling (serve --allow-edit): ticking a checkbox posts its      *
+ * source line to /_mdmd/v1/toggle-task.  On success the page adopts    *
+ * the new mtime so the change notice stays hidden; on failure the box  *
+ * is reverted and the notice shown, since the file changed under us.   *
+ * --------------------------------------------------------------------- */
+(function () {
+    var meta_path = document.querySelector('meta[name="mdmd-path"]');
+    var meta_mtime = document.querySelector('meta[name="mdmd-mtime"]');
+    if (!meta_path) { return; }
+    document.addEventListener('change', function (e) {
+        var box = e.target;
+        if (!box.classList || !box.classList.contains('task-toggle')) { return; }
+        var was_checked = !box.checked;
+        box.disabled = true;
+        fetch('/_mdmd/v1/toggle-task?path=' + encodeURIComponent(meta_path.content), {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ line: parseInt(box.dataset.line, 10), checked: was_checked })
+        })
+            .then(function (r) { return r.ok ? r.json() : Promise.reject('non-200'); })
+            .then(function (data) {
+                box.checked = data.checked;
+                if (meta_mtime) { meta_mtime.content = String(data.mtime); }
+            })
+            .catch(function () {
+                box.checked = was_checked;
+                var notice = document.getElementById('mdmd-change-notice');
+                if (notice) { notice.removeAttribute('hidden'); }
+            })
+            .then(function () { box.disabled = false; });
+    });
+}());
+
+/* --------------------------------------------------------------------- *
+ * Mention linking (serve --allow-edit): a mention's Link button posts   *
+ * it to /_mdmd/v1/link-mention, which rewrites the mentioning file.    *
+ * On success the button is replaced by its "linked" label; on failure  *
+ * it stays enabled with the server's reason as its tooltip.            *
+ * --------------------------------------------------------------------- */
+(function () {
+    var meta_path = document.querySelector('meta[name="mdmd-path"]');
+    if (!meta_path) { return; }
+    document.addEventListener('click', function (e) {
+        var button = e.target;
+        if (!button.classList || !button.classList.contains('mention-link')) { return; }
+        button.disabled = true;
+        var source = button.dataset.source.replace(/^\//, '');
+        fetch('/_mdmd/v1/link-mention?path=' + encodeURIComponent(source), {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({
+                target: '/' + meta_path.content.replace(/^\//, ''),
+                line: parseInt(button.dataset.line, 10),
+                column: parseInt(button.dataset.column, 10),
+                text: button.dataset.text
+            })
+        })
+            .then(function (r) {
+                return r.json().then(function (data) {
+                    return r.ok ? data : Promise.reject(data.error);
+                });
+            })
+            .then(function () {
+                var done = document.createElement('span');
+                done.className = 'mention-linked';
+                done.textContent = button.dataset.done;
+                button.replaceWith(done);
+            })
+            .catch(function (reason) {
+                button.disabled = false;
+                button.title = String(reason);
+            });
+    });
+}());
+
+/* --------------------------------------------------------------------- *
+ * Anchor-aware deep links                                               *
+ *                                                                       *
+ * Navigating to #fragment (on load, via in-page links such as the TOC, *
+ * or on hashchange) smooth-scrolls to the target and briefly flashes   *
+ * it.  Fragments that miss an element id are retried in slug form so  *
+ * GitHub-style anchors (#My_Heading) still land on the heading.        *
+ *                                                                       *
+ * #:~:text= directives are handled natively where supported (the       *
+ * browser strips them from location.hash); otherwise the first block  *
+ * element containing the text is scrolled to and flashed.              *
+ * --------------------------------------------------------------------- */
+(function () {
+    'use strict';
+    var FLASH_CLASS = 'mdmd-flash';
+    var FLASH_MS = 1600;
+    var BLOCKS = 'main.content p, main.content li, main.content h1, main.content h2,' +
+        'main.content h3, main.content h4, main.content h5, main.content h6,' +
+        'main.content td, main.content th, main.content pre, main.content blockquote';
+    var reduceMotion = window.matchMedia &&
+        window.matchMedia('(prefers-reduced-motion: reduce)').matches;
+
+    /* Mirror of html::slugify so authored anchors match generated ids. */
+    function slugify(text) {
+        var slug = '';
+        var lower = text.toLowerCase();
+        for (var i = 0; i < lower.length; i++) {
+            var c = lower.charAt(i);
+            if (/[\p{L}\p{N}]/u.test(c)) {
+                slug += c;
+            } else if ((c === ' ' || c === '-' || c === '_') && slug.slice(-1) !== '-') {
+                slug += '-';
+            }
+        }
+        return slug.replace(/^-+|-+$/g, '');
+    }
+
+    function decode(s) {
+        try { return decodeURIComponent(s); } catch (_) { return s; }
+    }
+
+    function normalize(s) {
+        return s.replace(/\s+/g, ' ').trim().toLowerCase();
+    }
+
+    /* textStart of a "[prefix-,]textStart[,textEnd][,-suffix]" directive. */
+    function directiveText(hash) {
+        var at = hash.indexOf(':~:text=');
+        if (at < 0) { return null; }
+        var parts = hash.slice(at + 8).split('&')[0].split(',').filter(function (p) {
+            return p && p.slice(-1) !== '-' && p.charAt(0) !== '-';
+        });
+        return parts.length ? decode(parts[0]) : null;
+    }
+
+    function findByText(text) {
+        var needle = normalize(text);
+        var blocks = document.querySelectorAll(BLOCKS);
+        var best = null;
+        for (var i = 0; i < blocks.length; i++) {
+            if (normalize(blocks[i].textContent).indexOf(needle) >= 0) {
+                /* Prefer the innermost match (li > p, blockquote > p). */
+                if (!best || best.contains(blocks[i])) { best = blocks[i]; }
+                else if (!blocks[i].contains(best)) { break; }
+            }
+        }
+        return best;
+    }
+
+    function findTarget(hash) {
+        if (!hash || hash === '#') { return null; }
+        var text = directiveText(hash);
+        if (text !== null) { return findByText(text); }
+        var id = decode(hash.slice(1));
+        return document.getElementById(id) || document.getElementById(slugify(id));
+    }
+
+    function flash(el) {
+        el.classList.remove(FLASH_CLASS);
+        void el.offsetWidth; /* restart the animation on repeat visits */
+        el.classList.add(FLASH_CLASS);
+        setTimeout(function () { el.classList.remove(FLASH_CLASS); }, FLASH_MS);
+    }
+
+    function reveal(hash, smooth) {
+        var el = findTarget(hash);
+        if (!el) { return false; }
+        el.scrollIntoView({
+            behavior: smooth && !reduceMotion ? 'smooth' : 'auto',
+            block: 'start'
+        });
+        flash(el);
+        return true;
+    }
+
+    /* Same-page fragment links: animate instead of jumping. */
+    document.addEventListener('click', function (e) {
+        if (e.defaultPrevented || e.button !== 0 ||
+            e.metaKey || e.ctrlKey || e.shiftKey || e.altKey) { return; }
+        var a = e.target.closest && e.target.closest('a[href^="#"]');
+        if (!a) { return; }
+        var hash = a.getAttribute('href');
+        if (reveal(hash, true)) {
+            e.preventDefault();
+            if (location.hash !== hash) { history.pushState(null, '', hash); }
+        }
+    });
+
+    window.addEventListener('hashchange', function () { reveal(location.hash, true); });
+
+    if (location.hash) { reveal(location.hash, false); }
+}());
+
+/* --------------------------------------------------------------------- *
+ * Site search (serve mode): query /_mdmd/v1/search across every file.  *
+ *                                                                       *
+ * Keys mirror the TUI: "/" focuses the box, Up/Down (or Ctrl-P/Ctrl-N) *
+ * move through results, Enter opens the selected result (the first if  *
+ * none is selected), and Escape clears the query and leaves the box.   *
+ * --------------------------------------------------------------------- */
+(function () {
+    'use strict';
+    var input = document.getElementById('mdmd-search');
+    var list = document.getElementById('mdmd-search-results');
+    if (!input || !list) { return; }
+    var DEBOUNCE_MS = 150;
+    var timer = null;
+    var seq = 0;
+    var results = [];
+    var selected = -1;
+
+    function isEditable(el) {
+        return el && (el.isContentEditable ||
+            /^(INPUT|TEXTAREA|SELECT)$/.test(el.tagName));
+    }
+
+    function select(idx) {
+        var items = list.children;
+        if (selected >= 0 && items[selected]) {
+            items[selected].setAttribute('aria-selected', 'false');
+        }
+        selected = idx;
+        if (selected >= 0 && items[selected]) {
+            items[selected].setAttribute('aria-selected', 'true');
+            items[selected].scrollIntoView({ block: 'nearest' });
+            input.setAttribute('aria-activedescendant', items[selected].id);
+        } else {
+            input.removeAttribute('aria-activedescendant');
+        }
+    }
+
+    function render(data) {
+        results = data.results || [];
+        selected = -1;
+        input.removeAttribute('aria-activedescendant');
+        list.textContent = '';
+        if (!input.value.trim()) {
+            list.hidden = true;
+            return;
+        }
+        results.forEach(function (r, i) {
+            var li = document.createElement('li');
+            li.id = 'mdmd-search-result-' + i;
+            li.setAttribute('role', 'option');
+            li.setAttribute('aria-selected', 'false');
+            var a = document.createElement('a');
+            a.href = r.href;
+            a.tabIndex = -1;
+            var title = document.createElement('span');
+            title.className = 'site-search-title';
+            title.textContent = r.title;
+            var loc = document.createElement('span');
+            loc.className = 'site-search-path';
+            loc.textContent = r.path.slice(1) + ':' + r.line;
+            var snippet = document.createElement('span');
+            snippet.className = 'site-search-snippet';
+            snippet.textContent = r.snippet;
+            a.appendChild(title);
+            a.appendChild(loc);
+            a.appendChild(snippet);
+            li.appendChild(a);
+            li.addEventListener('mousemove', function () {
+                if (selected !== i) { select(i); }
+            });
+            list.appendChild(li);
+        });
+        var footer = null;
+        if (!results.length) {
+            footer = 'No matches';
+        } else if (data.truncated) {
+            footer = 'Showing first ' + results.length + ' matches';
+        }
+        if (footer) {
+            var note = document.createElement('li');
+            note.className = 'site-search-note';
+            note.textContent = footer;
+            list.appendChild(note);
+        }
+        list.hidden = false;
+    }
+
+    function search() {
+        var q = input.value.trim();
+        var mine = ++seq;
+        if (!q) {
+            render({ results: [] });
+            return;
+        }
+        fetch('/_mdmd/v1/search?q=' + encodeURIComponent(q))
+            .then(function (r) { return r.ok ? r.json() : Promise.reject('non-200'); })
+            .then(function (data) {
+                /* Drop responses that arrive after a newer query was sent. */
+                if (mine === seq) { render(data); }
+            })
+            .catch(function () {});
+    }
+
+    function open(idx) {
+        var r = results[idx];
+        if (r) { location.href = r.href; }
+    }
+
+    function close() {
+        input.value = '';
+        render({ results: [] });
+        input.blur();
+    }
+
+    input.addEventListener('input', function () {
+        clearTimeout(timer);
+        timer = setTimeout(search, DEBOUNCE_MS);
+    });
+
+    input.addEventListener('keydown', function (e) {
+        var down = e.key === 'ArrowDown' || (e.ctrlKey && e.key === 'n');
+        var up = e.key === 'ArrowUp' || (e.ctrlKey && e.key === 'p');
+        if (down || up) {
+            e.preventDefault();
+            if (!results.length) { return; }
+            var next = down ? selected + 1 : selected - 1;
+            select(Math.max(0, Math.min(results.length - 1, next)));
+        } else if (e.key === 'Enter') {
+            e.preventDefault();
+            open(selected >= 0 ? selected : 0);
+        } else if (e.key === 'Escape') {
+            e.preventDefault();
+            close();
+        }
+    });
+
+    input.addEventListener('focus', function () {
+        if (input.value.trim() && list.children.length) { list.hidden = false; }
+    });
+
+    input.addEventListener('blur', function () {
+        /* Delay so a click on a result lands before the list hides. */
+        setTimeout(function () { list.hidden = true; }, 150);
+    });
+
+    document.addEventListener('keydown', function (e) {
+        if (e.key !== '/' || e.ctrlKey || e.metaKey || e.altKey) { return; }
+        if (isEditable(document.activeElement)) { return; }
+        e.preventDefault();
+        input.focus();
+        input.select();
+    });
+}());
+
+/* --------------------------------------------------------------------- *
+ * Site file tree (serve mode): load the /_mdmd/tree fragment the first *
+ * time the sidebar disclosure opens, mark the current page, and expand *
+ * its ancestor directories.  The open state persists across pages.     *
+ * --------------------------------------------------------------------- */
+(function () {
+    'use strict';
+    var KEY = 'mdmd-site-tree';
+    var tree = document.getElementById('mdmd-site-tree');
+    if (!tree) { return; }
+    var body = tree.querySelector('.site-tree-body');
+    var loaded = false;
+
+    function markCurrent() {
+        var links = body.querySelectorAll('a[href]');
+        for (var i = 0; i < links.length; i++) {
+            if (links[i].pathname !== location.pathname) { continue; }
+            links[i].setAttribute('aria-current', 'page');
+            var el = links[i].parentElement;
+            while (el && el !== body) {
+                if (el.tagName === 'DETAILS') { el.open = true; }
+                el = el.parentElement;
+            }
+            links[i].scrollIntoView({ block: 'nearest' });
+            return;
+        }
+    }
+
+    function load() {
+        if (loaded) { return; }
+        loaded = true;
+        fetch(body.getAttribute('data-src'))
+            .then(function (r) { return r.ok ? r.text() : Promise.reject('non-200'); })
+            .then(function (html) {
+                body.innerHTML = html;
+                markCurrent();
+            })
+            .catch(function () { loaded = false; });
+    }
+
+    tree.addEventListener('toggle', function () {
+        try { localStorage.setItem(KEY, tree.open ? 'open' : 'closed'); } catch (_) {}
+        if (tree.open) { load(); }
+    });
+
+    var saved = null;
+    try { saved = localStorage.getItem(KEY); } catch (_) {}
+    if (saved === 'open') { tree.open = true; }
+}());
+
+/* --------------------------------------------------------------------- *
+ * Find in page                                                          *
+ *                                                                       *
+ * Ctrl/Cmd-F opens a find bar that highlights every match in the       *
+ * document and reports "[current/total]" like the TUI search status.  *
+ * Enter / Shift-Enter (or n / N once the bar loses focus) step through *
+ * matches; Escape closes the bar.  Pressing Ctrl/Cmd-F while the bar   *
+ * is focused falls through to the browser's native find.               *
+ * --------------------------------------------------------------------- */
+(function () {
+    'use strict';
+    var root = document.querySelector('main.content');
+    if (!root) { return; }
+    var MATCH_CLASS = 'mdmd-find-match';
+    var CURRENT_CLASS = 'mdmd-find-current';
+    var DEBOUNCE_MS = 100;
+    var marks = [];
+    var current = -1;
+    var timer = null;
+
+    var bar = document.createElement('div');
+    bar.className = 'find-bar';
+    bar.setAttribute('role', 'search');
+    bar.hidden = true;
+    var input = document.createElement('input');
+    input.type = 'search';
+    input.className = 'find-bar-input';
+    input.placeholder = 'Find in page';
+    input.setAttribute('aria-label', 'Find in page');
+    input.autocomplete = 'off';
+    var status = document.createElement('span');
+    status.className = 'find-bar-status';
+    status.setAttribute('aria-live', 'polite');
+
+    function button(label, text, onClick) {
+        var b = document.createElement('button');
+        b.type = 'button';
+        b.className = 'find-bar-button';
+        b.setAttribute('aria-label', label);
+        b.textContent = text;
+        b.addEventListener('click', onClick);
+        return b;
+    }
+
+    bar.appendChild(input);
+    bar.appendChild(status);
+    bar.appendChild(button('Previous match', '↑', function () { step(false); }));
+    bar.appendChild(button('Next match', '↓', function () { step(true); }));
+    bar.appendChild(button('Close find bar', '×', function () { close(); }));
+    document.body.appendChild(bar);
+
+    function isEditable(el) {
+        return el && (el.isContentEditable ||
+            /^(INPUT|TEXTAREA|SELECT)$/.test(el.tagName));
+    }
+
+    function clear() {
+        marks.forEach(function (m) {
+            var parent = m.parentNode;
+            if (!parent) { return; }
+            parent.replaceChild(document.createTextNode(m.textContent), m);
+            parent.normalize();
+        });
+        marks = [];
+        current = -1;
+    }
+
+    /* Case-insensitive, per text node, like the TUI's per-line matching. */
+    function highlight(query) {
+        var needle = query.toLowerCase();
+        var walker = document.createTreeWalker(root, NodeFilter.SHOW_TEXT, {
+            acceptNode: function (n) {
+                var p = n.parentNode;
+                if (p.closest('script, style, .mermaid')) {
+                    return NodeFilter.FILTER_REJECT;
+                }
+                return n.nodeValue.toLowerCase().indexOf(needle) >= 0
+                    ? NodeFilter.FILTER_ACCEPT : NodeFilter.FILTER_SKIP;
+            }
+        });
+        var nodes = [];
+        while (walker.nextNode()) { nodes.push(walker.currentNode); }
+        nodes.forEach(function (node) {
+            var text = node.nodeValue;
+            var lower = text.toLowerCase();
+            var frag = document.createDocumentFragment();
+            var pos = 0;
+            var at;
+            while ((at = lower.indexOf(needle, pos)) >= 0) {
+                frag.appendChild(document.createTextNode(text.slice(pos, at)));
+                var mark = document.createElement('mark');
+                mark.className = MATCH_CLASS;
+                mark.textContent = text.slice(at, at + needle.length);
+                frag.appendChild(mark);
+                marks.push(mark);
+                pos = at + needle.length;
+            }
+            frag.appendChild(document.createTextNode(text.slice(pos)));
+            node.parentNode.replaceChild(frag, node);
+        });
+    }
+
+    function updateStatus() {
+        if (!input.value) {
+            status.textContent = '';
+        } else if (!marks.length) {
+            status.textContent = '[No matches]';
+        } else {
+            status.textContent = '[' + (current + 1) + '/' + marks.length + ']';
+        }
+    }
+
+    function focusMatch(idx) {
+        if (current >= 0 && marks[current]) { marks[current].classList.remove(CURRENT_CLASS); }
+        current = idx;
+        if (current >= 0 && marks[current]) {
+            marks[current].classList.add(CURRENT_CLASS);
+            marks[current].scrollIntoView({ block: 'center' });
+        }
+        updateStatus();
+    }
+
+    /* Start at the first match at or below the viewport top, as the TUI
+     * starts from the nearest match after the saved scroll position. */
+    function firstVisible() {
+        for (var i = 0; i < marks.length; i++) {
+            if (marks[i].getBoundingClientRect().top >= 0) { return i; }
+        }
+        return marks.length ? 0 : -1;
+    }
+
+    function run() {
+        clear();
+        if (input.value) { highlight(input.value); }
+        focusMatch(firstVisible());
+    }
+
+    function step(forward) {
+        if (!marks.length) { return; }
+        var n = marks.length;
+        focusMatch(current < 0 ? (forward ? 0 : n - 1) : (current + (forward ? 1 : n - 1)) % n);
+    }
+
+    function open() {
+        bar.hidden = false;
+        input.focus();
+        input.select();
+    }
+
+    function close() {
+        clearTimeout(timer);
+        timer = null;
+        clear();
+        input.value = '';
+        updateStatus();
+        bar.hidden = true;
+    }
+
+    input.addEventListener('input', function () {
+        clearTimeout(timer);
+        timer = setTimeout(function () { timer = null; run(); }, DEBOUNCE_MS);
+    });
+
+    input.addEventListener('keydown', function (e) {
+        if (e.key === 'Enter') {
+            e.preventDefault();
+            if (timer !== null) {
+                /* Apply a pending query instead of stepping past its first match. */
+                clearTimeout(timer);
+                timer = null;
+                run();
+            } else {
+                step(!e.shiftKey);
+            }
+        } else if (e.key === 'Escape') {
+            e.preventDefault();
+            close();
+        }
+    });
+
+    document.addEventListener('keydown', function (e) {
+        var findKey = (e.ctrlKey || e.metaKey) && !e.altKey && (e.key === 'f' || e.key === 'F');
+        if (findKey) {
+            /* A second press inside the bar hands over to native find. */
+            if (document.activeElement === input) { return; }
+            e.preventDefault();
+            open();
+            return;
+        }
+        if (bar.hidden || e.ctrlKey || e.metaKey || e.altKey) { return; }
+        if (isEditable(document.activeElement)) { return; }
+        if (e.key === 'n' || e.key === 'N') {
+            e.preventDefault();
+            step(e.key === 'n');
+        } else if (e.key === 'Escape') {
+            close();
+        }
+    });
+}());
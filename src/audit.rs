@@ -0,0 +1,240 @@
+//! Audit log of the requests `mdmd serve` answers.
+//!
+//! When the server binds to an address other machines can reach, every
+//! request is appended to a log file (`$XDG_STATE_HOME/mdmd/audit.log` unless
+//! `--audit-log` names another) as one line:
+//!
+//! ```text
+//! 2026-10-16T09:14:03Z 100.101.7.12 GET /docs/guide.md 200 5120
+//! ```
+//!
+//! with the UTC time, peer address, method, path and query, status, and
+//! response body bytes before compression (`-` when unknown).  Each server
+//! run starts with a `#` line naming the root and bind address, and at
+//! shutdown a summary of who read what is printed to stderr.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::serve::{format_listing_time, format_size};
+
+/// Paths listed under each peer in the shutdown summary.
+const SUMMARY_PATHS: usize = 10;
+
+/// One answered request.
+pub struct Entry<'a> {
+    pub time: SystemTime,
+    pub peer: Option<IpAddr>,
+    pub method: &'a str,
+    /// Request path and query, as sent.
+    pub target: &'a str,
+    pub status: u16,
+    pub bytes: Option<u64>,
+}
+
+impl Entry<'_> {
+    fn line(&self) -> String {
+        format!(
+            "{} {} {} {} {} {}",
+            timestamp(self.time),
+            self.peer.map_or("-".to_owned(), |ip| ip.to_string()),
+            self.method,
+            self.target,
+            self.status,
+            self.bytes.map_or("-".to_owned(), |b| b.to_string()),
+        )
+    }
+
+    /// Whether the request read a page or file, as opposed to mdmd's own
+    /// assets and API, or failed.
+    fn read_content(&self) -> bool {
+        (200..300).contains(&self.status)
+            && !self.target.starts_with("/_mdmd/")
+            && self.target != "/favicon.ico"
+    }
+}
+
+fn timestamp(time: SystemTime) -> String {
+    format_listing_time(time).map_or("-".to_owned(), |(iso, _)| iso)
+}
+
+/// What one peer fetched during the run.
+#[derive(Default)]
+struct Peer {
+    requests: u64,
+    bytes: u64,
+    /// Pages and files read, without their query, first read first.
+    paths: Vec<String>,
+}
+
+struct Inner {
+    file: File,
+    peers: BTreeMap<String, Peer>,
+}
+
+/// An open audit log and the tally for the shutdown summary.
+pub struct AuditLog {
+    path: PathBuf,
+    inner: Mutex<Inner>,
+}
+
+impl AuditLog {
+    /// Open `path` for appending, creating it and its directory, and mark
+    /// the start of a run serving `root` on `bind_addr`.
+    pub fn open(path: &Path, root: &Path, bind_addr: &str) -> io::Result<AuditLog> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(
+            file,
+            "# {} mdmd serve {} on {bind_addr}",
+            timestamp(SystemTime::now()),
+            root.display()
+        )?;
+        Ok(AuditLog {
+            path: path.to_path_buf(),
+            inner: Mutex::new(Inner {
+                file,
+                peers: BTreeMap::new(),
+            }),
+        })
+    }
+
+    /// Append `entry` to the log and count it towards its peer.
+    pub fn record(&self, entry: &Entry) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(e) = writeln!(inner.file, "{}", entry.line()) {
+            eprintln!(
+                "Warning: cannot write the audit log '{}': {e}",
+                self.path.display()
+            );
+        }
+        let peer = inner
+            .peers
+            .entry(entry.peer.map_or("-".to_owned(), |ip| ip.to_string()))
+            .or_default();
+        peer.requests += 1;
+        peer.bytes += entry.bytes.unwrap_or(0);
+        if entry.read_content() {
+            let path = entry.target.split('?').next().unwrap_or_default();
+            if !peer.paths.iter().any(|p| p == path) {
+                peer.paths.push(path.to_owned());
+            }
+        }
+    }
+
+    /// Who read what: each peer's request count and bytes, and the pages
+    /// and files it read.
+    pub fn summary(&self) -> String {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let requests: u64 = inner.peers.values().map(|p| p.requests).sum();
+        let mut out = match (requests, inner.peers.len()) {
+            (0, _) => "audit: no requests".to_owned(),
+            (1, _) => "audit: 1 request from 1 address".to_owned(),
+            (n, 1) => format!("audit: {n} requests from 1 address"),
+            (n, peers) => format!("audit: {n} requests from {peers} addresses"),
+        };
+        out.push_str(&format!(", logged to {}\n", self.path.display()));
+        for (addr, peer) in &inner.peers {
+            let noun = if peer.requests == 1 {
+                "request"
+            } else {
+                "requests"
+            };
+            out.push_str(&format!(
+                "  {addr}: {} {noun}, {}\n",
+                peer.requests,
+                format_size(peer.bytes)
+            ));
+            for path in peer.paths.iter().take(SUMMARY_PATHS) {
+                out.push_str(&format!("    {path}\n"));
+            }
+            if peer.paths.len() > SUMMARY_PATHS {
+                out.push_str(&format!(
+                    "    ... and {} more\n",
+                    peer.paths.len() - SUMMARY_PATHS
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// The audit log used when serving on a reachable address without
+/// `--audit-log`.  `None` when no state directory can be found.
+pub fn default_path() -> Option<PathBuf> {
+    Some(crate::workspace::state_dir()?.join("audit.log"))
+}
+
+/// Whether binding to `bind_addr` lets other machines connect: anything but
+/// a loopback address or `localhost`.
+pub fn binds_remote(bind_addr: &str) -> bool {
+    match bind_addr.parse::<IpAddr>() {
+        Ok(ip) => !ip.is_loopback(),
+        Err(_) => !bind_addr.eq_ignore_ascii_case("localhost"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn entry(peer: &str, target: &'static str, status: u16, bytes: u64) -> Entry<'static> {
+        Entry {
+            time: SystemTime::UNIX_EPOCH + Duration::from_secs(1_718_000_000),
+            peer: Some(peer.parse().unwrap()),
+            method: "GET",
+            target,
+            status,
+            bytes: Some(bytes),
+        }
+    }
+
+    #[test]
+    fn only_loopback_binds_are_local() {
+        assert!(binds_remote("0.0.0.0"));
+        assert!(binds_remote("::"));
+        assert!(binds_remote("100.101.7.12"));
+        assert!(!binds_remote("127.0.0.1"));
+        assert!(!binds_remote("::1"));
+        assert!(!binds_remote("localhost"));
+    }
+
+    #[test]
+    fn requests_are_logged_and_summarized_per_peer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state/audit.log");
+        let log = AuditLog::open(&path, Path::new("/notes"), "0.0.0.0").unwrap();
+        log.record(&entry("100.64.0.2", "/guide.md", 200, 2048));
+        log.record(&entry("100.64.0.2", "/guide.md?raw=1", 200, 512));
+        log.record(&entry("100.64.0.2", "/_mdmd/mdmd.css", 200, 100));
+        log.record(&entry("100.64.0.9", "/.env", 404, 20));
+
+        let text = fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(
+            lines[0].ends_with(" mdmd serve /notes on 0.0.0.0"),
+            "{text}"
+        );
+        assert_eq!(
+            lines[1],
+            "2024-06-10T06:13:20Z 100.64.0.2 GET /guide.md 200 2048"
+        );
+        assert_eq!(lines.len(), 5);
+
+        let summary = log.summary();
+        assert!(summary.starts_with("audit: 4 requests from 2 addresses, logged to "));
+        assert!(summary.contains("  100.64.0.2: 3 requests, 2.6 KiB\n    /guide.md\n  100.64.0.9"));
+        assert!(
+            summary.ends_with("  100.64.0.9: 1 request, 20 B\n"),
+            "{summary}"
+        );
+    }
+}
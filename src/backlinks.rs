@@ -1,19 +1,52 @@
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 
+use crate::normalize;
+use crate::summary;
+
 /// A reference to this document from another document (a "backlink").
 #[derive(Debug, Clone)]
 pub struct BacklinkRef {
     /// Root-relative URL path to the source document, with leading slash.
     /// Example: `/docs/a.md`
     pub source_url_path: String,
-    /// Display name: first H1 title if available, else rel path without leading slash.
+    /// Display name: the document's [`summary::title`] if available, else rel
+    /// path without leading slash.
     pub source_display: String,
-    /// Short context snippet around the link (~80 chars before/after, whitespace-collapsed,
-    /// max 200 chars).
+    /// Plain text of the sentence containing the link, with up to
+    /// [`CONTEXT_MAX_CHARS`] on each side of the link text and `…` where it
+    /// was cut.
     pub snippet: String,
+    /// Byte range of the link text within `snippet`, for highlighting.
+    /// `None` when the link has no text.
+    pub snippet_link: Option<Range<usize>>,
     /// Optional fragment (without `#`) from the original link, for display and href construction.
     pub target_fragment: Option<String>,
+    /// Plain text of the link in the source document, used to build a
+    /// `#:~:text=` deep link to the passage that references this page.
+    pub link_text: String,
+}
+
+/// A plain-text mention of this document's title in another document that
+/// does not link here (an "unlinked mention").
+#[derive(Debug, Clone)]
+pub struct MentionRef {
+    /// Root-relative URL path to the mentioning document, with leading slash.
+    pub source_url_path: String,
+    /// Display name of the mentioning document, as for [`BacklinkRef`].
+    pub source_display: String,
+    /// Plain text of the sentence containing the mention (see
+    /// [`BacklinkRef::snippet`]).
+    pub snippet: String,
+    /// Byte range of the mention within `snippet`.
+    pub snippet_mention: Option<Range<usize>>,
+    /// 1-based source line of the mention, frontmatter included.
+    pub line: usize,
+    /// Byte offset of the mention within its line.
+    pub column: usize,
+    /// The mention exactly as written in the source.
+    pub text: String,
 }
 
 /// An outbound link discovered in a source document during index build.
@@ -24,15 +57,27 @@ pub(crate) struct OutboundRef {
     pub target_url_path: String,
     /// Optional fragment from the original link (without `#`).
     pub target_fragment: Option<String>,
-    /// Context snippet around the link text (~80 chars before/after,
-    /// whitespace-collapsed, max 200 chars).
+    /// Plain text of the sentence containing the link (see
+    /// [`BacklinkRef::snippet`]).
     pub snippet: String,
+    /// Byte range of the link text within `snippet`.
+    pub snippet_link: Option<Range<usize>>,
+    /// Plain text of the link itself (whitespace-collapsed).
+    pub link_text: String,
 }
 
+/// Titles shorter than this many characters are not looked for as mentions;
+/// they match too much ordinary prose.
+pub const MENTION_MIN_CHARS: usize = 3;
+
+/// Most characters of context kept on either side of the link text in a
+/// backlink snippet.
+pub const CONTEXT_MAX_CHARS: usize = 80;
+
 /// Result of extracting outbound links and metadata from a single document.
 #[derive(Debug, Default)]
 pub(crate) struct DocExtractResult {
-    /// First H1 heading text found in the document, if any.
+    /// The document's title by the [`summary`] rules, if any.
     pub title: Option<String>,
     /// Outbound local markdown links discovered in the document.
     pub outbound_refs: Vec<OutboundRef>,
@@ -61,6 +106,9 @@ pub fn url_key_from_rel_path(rel: &str) -> String {
 /// Values are all [`BacklinkRef`]s from other documents that link to that target.
 pub type BacklinksIndex = HashMap<String, Vec<BacklinkRef>>;
 
+/// In-memory unlinked mentions index, keyed like [`BacklinksIndex`].
+pub type MentionsIndex = HashMap<String, Vec<MentionRef>>;
+
 /// Build the in-memory backlinks index by traversing `serve_root` and
 /// extracting outbound links from all markdown files.
 ///
@@ -82,16 +130,309 @@ pub type BacklinksIndex = HashMap<String, Vec<BacklinkRef>>;
 /// After the full traversal emits:
 /// - `eprintln!("[backlinks] indexed files={} edges={}", …)` to stderr
 pub fn build_backlinks_index(serve_root: &Path, verbose: bool) -> BacklinksIndex {
+    let mut index: BacklinksIndex = HashMap::new();
+    let mut file_count: usize = 0;
+    let mut edge_count: usize = 0;
+
+    for_each_markdown_file(serve_root, |path, src| {
+        file_count += 1;
+
+        // Extract outbound links and title.
+        let extracted = extract_outbound_links(src, path, serve_root);
+
+        // Compute the source URL key.
+        let source_rel = rel_path(path, serve_root);
+        let source_url_path = url_key_from_rel_path(&source_rel);
+
+        // Display name: title when present, else rel path without leading slash.
+        let source_display = extracted
+            .title
+            .clone()
+            .unwrap_or_else(|| source_rel.clone());
+
+        // Invert edges into the index, filtering self-links and duplicate
+        // (source → target) pairs.  When a source file contains multiple
+        // links to the same target we emit only the first one so the
+        // backlinks panel shows each source document at most once.
+        let mut seen_targets: std::collections::HashSet<&str> = std::collections::HashSet::new();
+        for outbound in &extracted.outbound_refs {
+            if outbound.target_url_path == source_url_path {
+                continue; // self-link – skip
+            }
+            if !seen_targets.insert(outbound.target_url_path.as_str()) {
+                continue; // duplicate source→target – skip
+            }
+            edge_count += 1;
+            index
+                .entry(outbound.target_url_path.clone())
+                .or_default()
+                .push(BacklinkRef {
+                    source_url_path: source_url_path.clone(),
+                    source_display: source_display.clone(),
+                    snippet: outbound.snippet.clone(),
+                    snippet_link: outbound.snippet_link.clone(),
+                    target_fragment: outbound.target_fragment.clone(),
+                    link_text: outbound.link_text.clone(),
+                });
+        }
+    });
+
+    if verbose {
+        eprintln!(
+            "[backlinks] indexed files={} edges={}",
+            file_count, edge_count
+        );
+    }
+
+    index
+}
+
+/// Build the unlinked mentions index for the markdown files under
+/// `serve_root`, traversed as by [`build_backlinks_index`].
+///
+/// A mention is a whole-word, ASCII case-insensitive occurrence of a page's
+/// [`summary::title`] in the text of another page: outside links, code,
+/// HTML, and frontmatter.  Titles shorter than [`MENTION_MIN_CHARS`] are
+/// ignored.  Pages that already link to the target (per `backlinks`) are
+/// skipped, and each source contributes at most its first mention of each
+/// target.  Entries are ordered by source path.
+///
+/// # Output
+///
+/// After the full traversal emits:
+/// - `eprintln!("[mentions] indexed titles={} mentions={}", …)` to stderr
+pub fn build_mentions_index(
+    serve_root: &Path,
+    backlinks: &BacklinksIndex,
+    verbose: bool,
+) -> MentionsIndex {
+    struct Doc {
+        url_path: String,
+        display: String,
+        src: String,
+    }
+    let mut docs = Vec::new();
+    let mut titles: Vec<(String, String)> = Vec::new();
+    for_each_markdown_file(serve_root, |path, src| {
+        let rel = rel_path(path, serve_root);
+        let url_path = url_key_from_rel_path(&rel);
+        let title = summary::extract(src).title;
+        if let Some(title) = &title {
+            if title.chars().count() >= MENTION_MIN_CHARS {
+                titles.push((title.to_ascii_lowercase(), url_path.clone()));
+            }
+        }
+        docs.push(Doc {
+            url_path,
+            display: title.unwrap_or(rel),
+            src: src.to_owned(),
+        });
+    });
+
+    let mut index = MentionsIndex::new();
+    let mut mention_count: usize = 0;
+    for doc in &docs {
+        let wanted: Vec<(&str, &str)> = titles
+            .iter()
+            .filter(|(_, target)| *target != doc.url_path)
+            .filter(|(_, target)| {
+                !backlinks
+                    .get(target)
+                    .is_some_and(|refs| refs.iter().any(|r| r.source_url_path == doc.url_path))
+            })
+            .map(|(title, target)| (title.as_str(), target.as_str()))
+            .collect();
+        for (target, mention) in find_mentions(&doc.src, &wanted) {
+            mention_count += 1;
+            index
+                .entry(target.to_owned())
+                .or_default()
+                .push(MentionRef {
+                    source_url_path: doc.url_path.clone(),
+                    source_display: doc.display.clone(),
+                    ..mention
+                });
+        }
+    }
+    for mentions in index.values_mut() {
+        mentions.sort_by(|a, b| a.source_url_path.cmp(&b.source_url_path));
+    }
+
+    if verbose {
+        eprintln!(
+            "[mentions] indexed titles={} mentions={}",
+            titles.len(),
+            mention_count
+        );
+    }
+
+    index
+}
+
+/// The first mention in `src` of each `(lowercase title, target)` pair in
+/// `titles`, by the rules of [`build_mentions_index`].  The returned
+/// mentions leave the source fields empty.
+fn find_mentions<'t>(src: &str, titles: &[(&str, &'t str)]) -> Vec<(&'t str, MentionRef)> {
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS;
+    let mut found: Vec<(&'t str, MentionRef)> = Vec::new();
+    let mut block: Option<Range<usize>> = None;
+    // Depth inside links, images, code blocks, and metadata, where text is
+    // never a mention.
+    let mut skip_depth: usize = 0;
+
+    for (event, range) in Parser::new_ext(src, options).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Paragraph | Tag::Heading { .. } | Tag::TableCell) => {
+                block = Some(range);
+            }
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::TableCell) => {
+                block = None;
+            }
+            Event::Start(
+                Tag::Link { .. } | Tag::Image { .. } | Tag::CodeBlock(_) | Tag::MetadataBlock(_),
+            ) => skip_depth += 1,
+            Event::End(
+                TagEnd::Link | TagEnd::Image | TagEnd::CodeBlock | TagEnd::MetadataBlock(_),
+            ) => skip_depth = skip_depth.saturating_sub(1),
+            // Only text that appears verbatim in the source can be located
+            // (and later rewritten) by offset.
+            Event::Text(text) if skip_depth == 0 && src.get(range.clone()) == Some(&*text) => {
+                let lower = text.to_ascii_lowercase();
+                for &(title, target) in titles {
+                    if found.iter().any(|(t, _)| *t == target) {
+                        continue;
+                    }
+                    let Some(at) = find_word(&lower, title) else {
+                        continue;
+                    };
+                    let start = range.start + at;
+                    let end = start + title.len();
+                    let context = context_range(src, block.clone(), start, end);
+                    let mention = &src[start..end];
+                    let (snippet, snippet_mention) =
+                        link_snippet(&src[context.start..start], mention, &src[end..context.end]);
+                    let line_start = src[..start].rfind('\n').map_or(0, |i| i + 1);
+                    found.push((
+                        target,
+                        MentionRef {
+                            source_url_path: String::new(),
+                            source_display: String::new(),
+                            snippet,
+                            snippet_mention,
+                            line: src[..start].matches('\n').count() + 1,
+                            column: start - line_start,
+                            text: mention.to_owned(),
+                        },
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Byte offset of the first whole-word occurrence of `word` in `text`.
+fn find_word(text: &str, word: &str) -> Option<usize> {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(word).map(|(i, _)| i).find(|&i| {
+        !text[..i].chars().next_back().is_some_and(is_word)
+            && !text[i + word.len()..].chars().next().is_some_and(is_word)
+    })
+}
+
+/// Source range to read a snippet from for the span `start..end`: the
+/// enclosing paragraph, heading, or table cell, else (in a tight list item)
+/// the span's own lines.
+fn context_range(src: &str, block: Option<Range<usize>>, start: usize, end: usize) -> Range<usize> {
+    let range = block.unwrap_or_else(|| {
+        let line_start = src[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = src[end..].find('\n').map_or(src.len(), |i| end + i);
+        line_start..line_end
+    });
+    range.start.min(start)..range.end.max(end)
+}
+
+/// Turn the mention of `text` at byte `column` of 1-based `line` in `source`
+/// into a link to `href`.  Fails when the source no longer has `text` there.
+pub fn link_mention_in_source(
+    source: &str,
+    line: usize,
+    column: usize,
+    text: &str,
+    href: &str,
+) -> Result<String, String> {
+    let changed = || format!("the mention on line {line} was changed on disk");
+    let line_start = match line {
+        0 => return Err(changed()),
+        // Columns are counted after the byte order mark is stripped.
+        1 if source.starts_with('\u{feff}') => '\u{feff}'.len_utf8(),
+        1 => 0,
+        _ => source
+            .match_indices('\n')
+            .nth(line - 2)
+            .map(|(i, _)| i + 1)
+            .ok_or_else(changed)?,
+    };
+    let start = line_start + column;
+    let end = start + text.len();
+    if text.is_empty() || text.contains('\n') || source.get(start..end) != Some(text) {
+        return Err(changed());
+    }
+
+    let label = text.replace('[', "\\[").replace(']', "\\]");
+    let href = if href.contains([' ', '(', ')', '<', '>']) {
+        format!("<{href}>")
+    } else {
+        href.to_owned()
+    };
+    Ok(format!(
+        "{}[{label}]({href}){}",
+        &source[..start],
+        &source[end..]
+    ))
+}
+
+/// Relative link from the page at URL key `from` to the page at URL key
+/// `to`, e.g. `../b.md` from `/docs/a.md` to `/b.md`.
+pub fn relative_href(from: &str, to: &str) -> String {
+    let from_dirs: Vec<&str> = from.trim_start_matches('/').split('/').collect();
+    let from_dirs = &from_dirs[..from_dirs.len() - 1];
+    let to_parts: Vec<&str> = to.trim_start_matches('/').split('/').collect();
+    let common = from_dirs
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(to_parts.len() - 1);
+    let mut href = "../".repeat(from_dirs.len() - common);
+    href.push_str(&to_parts[common..].join("/"));
+    href
+}
+
+/// Root-relative path of `path` under `serve_root`, with `/` separators and
+/// no leading slash.
+fn rel_path(path: &Path, serve_root: &Path) -> String {
+    path.strip_prefix(serve_root)
+        .ok()
+        .map(|r| r.to_string_lossy().replace('\\', "/"))
+        .unwrap_or_default()
+}
+
+/// Call `visit` with the path and contents of every markdown file under
+/// `serve_root`, following the traversal rules of [`build_backlinks_index`].
+fn for_each_markdown_file(serve_root: &Path, mut visit: impl FnMut(&Path, &str)) {
     use std::collections::VecDeque;
     use std::fs;
 
-    let mut index: BacklinksIndex = HashMap::new();
     let mut queue: VecDeque<PathBuf> = VecDeque::new();
     queue.push_back(serve_root.to_path_buf());
 
-    let mut file_count: usize = 0;
-    let mut edge_count: usize = 0;
-
     while let Some(dir) = queue.pop_front() {
         let entries = match fs::read_dir(&dir) {
             Ok(e) => e,
@@ -141,60 +482,9 @@ pub fn build_backlinks_index(serve_root: &Path, verbose: bool) -> BacklinksIndex
                 }
             };
 
-            file_count += 1;
-
-            // Extract outbound links and title.
-            let extracted = extract_outbound_links(&src, &path, serve_root);
-
-            // Compute the source URL key.
-            let source_rel = path
-                .strip_prefix(serve_root)
-                .ok()
-                .map(|r| r.to_string_lossy().replace('\\', "/"))
-                .unwrap_or_default();
-            let source_url_path = url_key_from_rel_path(&source_rel);
-
-            // Display name: H1 title when present, else rel path without leading slash.
-            let source_display = extracted
-                .title
-                .clone()
-                .unwrap_or_else(|| source_rel.clone());
-
-            // Invert edges into the index, filtering self-links and duplicate
-            // (source → target) pairs.  When a source file contains multiple
-            // links to the same target we emit only the first one so the
-            // backlinks panel shows each source document at most once.
-            let mut seen_targets: std::collections::HashSet<&str> =
-                std::collections::HashSet::new();
-            for outbound in &extracted.outbound_refs {
-                if outbound.target_url_path == source_url_path {
-                    continue; // self-link – skip
-                }
-                if !seen_targets.insert(outbound.target_url_path.as_str()) {
-                    continue; // duplicate source→target – skip
-                }
-                edge_count += 1;
-                index
-                    .entry(outbound.target_url_path.clone())
-                    .or_default()
-                    .push(BacklinkRef {
-                        source_url_path: source_url_path.clone(),
-                        source_display: source_display.clone(),
-                        snippet: outbound.snippet.clone(),
-                        target_fragment: outbound.target_fragment.clone(),
-                    });
-            }
+            visit(&path, &normalize::source(&src));
         }
     }
-
-    if verbose {
-        eprintln!(
-            "[backlinks] indexed files={} edges={}",
-            file_count, edge_count
-        );
-    }
-
-    index
 }
 
 /// Normalize an absolute file-system path by resolving `.` and `..` components
@@ -234,7 +524,7 @@ fn normalize_abs_path(path: &Path) -> Option<PathBuf> {
     Some(result)
 }
 
-/// Extract outbound local links and the first H1 title from a markdown source.
+/// Extract outbound local links and the title from a markdown source.
 ///
 /// # Arguments
 ///
@@ -247,7 +537,8 @@ fn normalize_abs_path(path: &Path) -> Option<PathBuf> {
 /// # Returns
 ///
 /// A [`DocExtractResult`] containing:
-/// - `title`: the plain-text of the first H1 heading, or `None`.
+/// - `title`: the frontmatter title, else the plain text of the first H1
+///   heading, or `None` (see [`summary::extract`]).
 /// - `outbound_refs`: all local links whose resolved targets lie inside
 ///   `serve_root`, keyed by root-relative URL path.
 ///
@@ -258,46 +549,41 @@ pub(crate) fn extract_outbound_links(
     source_path: &Path,
     serve_root: &Path,
 ) -> DocExtractResult {
-    use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+    use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
 
     let source_parent = source_path.parent().unwrap_or(source_path);
-    let src_len = src.len();
 
     let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
     let parser = Parser::new_ext(src, options).into_offset_iter();
 
-    let mut result = DocExtractResult::default();
-    let mut in_h1 = false;
-    let mut h1_done = false;
-    let mut title_buf = String::new();
+    let mut result = DocExtractResult {
+        title: summary::extract(src).title,
+        outbound_refs: Vec::new(),
+    };
 
     // Pending state for the link currently being processed.
     let mut link_byte_start: Option<usize> = None;
     let mut link_dest: Option<String> = None;
+    let mut link_text = String::new();
+    // Source range of the paragraph, heading, or table cell being read.
+    let mut block: Option<Range<usize>> = None;
 
     for (event, range) in parser {
         match event {
-            // --- H1 title extraction ---
-            Event::Start(Tag::Heading {
-                level: HeadingLevel::H1,
-                ..
-            }) if !h1_done => {
-                in_h1 = true;
-                title_buf.clear();
+            Event::Start(Tag::Paragraph | Tag::Heading { .. } | Tag::TableCell) => {
+                block = Some(range);
             }
-            Event::Text(ref text) if in_h1 => {
-                title_buf.push_str(text);
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::TableCell) => {
+                block = None;
             }
-            Event::End(TagEnd::Heading(HeadingLevel::H1)) if in_h1 => {
-                result.title = Some(title_buf.trim().to_owned());
-                in_h1 = false;
-                h1_done = true;
-            }
-
             // --- Link extraction ---
             Event::Start(Tag::Link { ref dest_url, .. }) => {
                 link_byte_start = Some(range.start);
                 link_dest = Some(dest_url.to_string());
+                link_text.clear();
+            }
+            Event::Text(ref text) | Event::Code(ref text) if link_byte_start.is_some() => {
+                link_text.push_str(text);
             }
             Event::End(TagEnd::Link) => {
                 let ls = match link_byte_start.take() {
@@ -363,24 +649,20 @@ pub(crate) fn extract_outbound_links(
                 let rel_str = rel.to_string_lossy().replace('\\', "/");
                 let target_url_path = url_key_from_rel_path(&rel_str);
 
-                // Build the context snippet: ~80 bytes before/after the link,
-                // rendered to plain text (strips markdown syntax), capped at 200 chars.
-                // Adjust to char boundaries so we never slice mid-multibyte-char.
-                let mut snippet_start = ls.saturating_sub(80);
-                while snippet_start > 0 && !src.is_char_boundary(snippet_start) {
-                    snippet_start -= 1;
-                }
-                let mut snippet_end = le.saturating_add(80).min(src_len);
-                while snippet_end < src_len && !src.is_char_boundary(snippet_end) {
-                    snippet_end += 1;
-                }
-                let raw_snippet = &src[snippet_start..snippet_end];
-                let snippet = strip_markdown_to_plain(raw_snippet, 200);
+                // The snippet is the sentence around the link, read from the
+                // enclosing block, or from the link's own lines in a tight
+                // list item.
+                let context = context_range(src, block.clone(), ls, le);
+                let link_text = summary::collapse_whitespace(&link_text);
+                let (snippet, snippet_link) =
+                    link_snippet(&src[context.start..ls], &link_text, &src[le..context.end]);
 
                 result.outbound_refs.push(OutboundRef {
                     target_url_path,
                     target_fragment: fragment,
                     snippet,
+                    snippet_link,
+                    link_text,
                 });
             }
 
@@ -391,45 +673,65 @@ pub(crate) fn extract_outbound_links(
     result
 }
 
-/// Render a raw markdown fragment to plain text, stripping all markdown syntax.
-///
-/// Uses pulldown_cmark to parse the fragment and collect only text/code leaf
-/// events, so headings, link syntax, table pipes, emphasis markers, etc. are
-/// all silently dropped.  The result is whitespace-collapsed and capped at
-/// `max_chars` characters.
-fn strip_markdown_to_plain(raw: &str, max_chars: usize) -> String {
-    use pulldown_cmark::{Event, Options, Parser};
-
-    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
-    let mut plain = String::new();
-    for event in Parser::new_ext(raw, options) {
-        match event {
-            Event::Text(t) | Event::Code(t) => {
-                if !plain.is_empty() {
-                    plain.push(' ');
-                }
-                plain.push_str(&t);
-            }
-            Event::SoftBreak | Event::HardBreak => {
-                plain.push(' ');
-            }
-            _ => {}
-        }
+/// Build a backlink snippet from the markdown on either side of a link in
+/// its block: the plain text of the sentence containing the link, with each
+/// side cut to [`CONTEXT_MAX_CHARS`] (marked by `…`).  Returns the snippet and the byte range
+/// of `link_text` within it.
+fn link_snippet(
+    before_md: &str,
+    link_text: &str,
+    after_md: &str,
+) -> (String, Option<Range<usize>>) {
+    // A space after the text before the link is trimmed by `plain_text`,
+    // but may be what ends the previous sentence.
+    let mut before = summary::plain_text(before_md);
+    if before_md.ends_with(char::is_whitespace) {
+        before.push(' ');
     }
+    let before = match sentence_start(&before) {
+        Some(start) => &before[start..],
+        None => &before,
+    };
+    let before = summary::truncate_start(before, CONTEXT_MAX_CHARS);
+    let after = summary::plain_text(after_md);
+    let after = match sentence_end(&after) {
+        Some(end) => &after[..end],
+        None => &after,
+    };
+    let after = summary::truncate(after, CONTEXT_MAX_CHARS);
+
+    // Plain text is trimmed; put back the spaces that separated the link
+    // from its neighbours in the source.
+    let mut snippet = before;
+    if !snippet.is_empty() && before_md.ends_with(char::is_whitespace) {
+        snippet.push(' ');
+    }
+    let link_start = snippet.len();
+    snippet.push_str(link_text);
+    let link_end = snippet.len();
+    if !after.is_empty() && after_md.starts_with(char::is_whitespace) {
+        snippet.push(' ');
+    }
+    snippet.push_str(&after);
 
-    // Collapse runs of whitespace.
-    let collapsed: String = plain.split_ascii_whitespace().collect::<Vec<_>>().join(" ");
+    let span = (link_start < link_end).then_some(link_start..link_end);
+    (snippet, span)
+}
 
-    if collapsed.len() > max_chars {
-        // Truncate at a char boundary.
-        let mut end = max_chars;
-        while end > 0 && !collapsed.is_char_boundary(end) {
-            end -= 1;
-        }
-        collapsed[..end].to_owned()
-    } else {
-        collapsed
-    }
+/// Byte offset where the last sentence of `text` begins, if an earlier
+/// sentence ends inside it.
+fn sentence_start(text: &str) -> Option<usize> {
+    text.rmatch_indices(['.', '!', '?'])
+        .find(|&(i, _)| text[i + 1..].starts_with(' '))
+        .map(|(i, _)| i + 2)
+}
+
+/// Byte offset just past the end of the first sentence in `text`, if one
+/// ends inside it.
+fn sentence_end(text: &str) -> Option<usize> {
+    text.match_indices(['.', '!', '?'])
+        .find(|&(i, _)| text[i + 1..].is_empty() || text[i + 1..].starts_with(' '))
+        .map(|(i, _)| i + 1)
 }
 
 #[cfg(test)]
@@ -454,7 +756,9 @@ mod tests {
             source_url_path: "/a.md".to_string(),
             source_display: "A Doc".to_string(),
             snippet: "some context".to_string(),
+            snippet_link: None,
             target_fragment: Some("section".to_string()),
+            link_text: String::new(),
         };
         assert_eq!(r.source_url_path, "/a.md");
         assert_eq!(r.source_display, "A Doc");
@@ -468,7 +772,9 @@ mod tests {
             source_url_path: "/b.md".to_string(),
             source_display: "b.md".to_string(),
             snippet: "".to_string(),
+            snippet_link: None,
             target_fragment: None,
+            link_text: String::new(),
         };
         assert!(r.target_fragment.is_none());
     }
@@ -482,7 +788,9 @@ mod tests {
                 source_url_path: "/source.md".to_string(),
                 source_display: "Source".to_string(),
                 snippet: "see [target](target.md)".to_string(),
+                snippet_link: None,
                 target_fragment: None,
+                link_text: "target".to_string(),
             }],
         );
         assert_eq!(idx["/target.md"].len(), 1);
@@ -899,6 +1207,45 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extract_snippet_is_the_sentence_around_the_link() {
+        let src = "# Guide\n\nSetup comes first. The steps are\nas described in [this page](b.md) for\ndeployment. Then *relax*.\n\n- tight item with [this page](b.md)!\n";
+        let result = extract_outbound_links(src, Path::new("/root/a.md"), Path::new("/root"));
+        let r = &result.outbound_refs[0];
+        assert_eq!(
+            r.snippet,
+            "The steps are as described in this page for deployment."
+        );
+        assert_eq!(&r.snippet[r.snippet_link.clone().unwrap()], "this page");
+        let r = &result.outbound_refs[1];
+        assert_eq!(r.snippet, "tight item with this page!");
+        assert_eq!(&r.snippet[r.snippet_link.clone().unwrap()], "this page");
+    }
+
+    #[test]
+    fn extract_snippet_cuts_long_sentences_with_ellipses() {
+        let src = format!(
+            "{} as described in [this page](b.md) for deployment{}\n",
+            "word ".repeat(40),
+            " more".repeat(40)
+        );
+        let result = extract_outbound_links(&src, Path::new("/root/a.md"), Path::new("/root"));
+        let r = &result.outbound_refs[0];
+        assert!(r.snippet.starts_with('\u{2026}'), "{}", r.snippet);
+        assert!(r.snippet.ends_with("more\u{2026}"), "{}", r.snippet);
+        assert!(r
+            .snippet
+            .contains(" as described in this page for deployment more"));
+        assert_eq!(&r.snippet[r.snippet_link.clone().unwrap()], "this page");
+    }
+
+    #[test]
+    fn extract_link_text_is_plain_text_of_link() {
+        let src = "See the [**setup**   `guide`](b.md) for details.\n";
+        let result = extract_outbound_links(src, Path::new("/root/a.md"), Path::new("/root"));
+        assert_eq!(result.outbound_refs[0].link_text, "setup guide");
+    }
+
     #[test]
     fn extract_snippet_truncated_to_200() {
         // Case 11: 500+ chars on each side of the link → snippet.len() <= 200.
@@ -948,7 +1295,7 @@ mod tests {
     #[test]
     fn extract_title_after_link() {
         // Case 15: '[link](./a.md)\n\n# Late Title' → title = Some("Late Title").
-        // Both the link and the H1 are collected in a single pass.
+        // The title is the first H1 wherever it appears.
         let src = "[link](./a.md)\n\n# Late Title\n";
         let result = extract_outbound_links(src, Path::new("/root/docs/a.md"), Path::new("/root"));
         assert_eq!(
@@ -994,4 +1341,69 @@ mod tests {
             "outside-root link must be silently dropped"
         );
     }
+
+    // -----------------------------------------------------------------------
+    // Unlinked mentions
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn mentions_skip_links_code_and_linking_pages() {
+        let tmp = TempDir::new().unwrap();
+        write_fixture(&tmp, "deploy.md", "# Deploy Guide\n\nSteps.\n");
+        write_fixture(
+            &tmp,
+            "notes.md",
+            "---\ntitle: Notes\n---\n`deploy guide` and [deploy guide](x.md) aside,\n\nsee the Deploy guide first. The deploy guide again.\n",
+        );
+        write_fixture(
+            &tmp,
+            "linked.md",
+            "The Deploy Guide, [linked](deploy.md).\n",
+        );
+        write_fixture(&tmp, "other.md", "Redeploy guidelines are unrelated.\n");
+
+        let backlinks = build_backlinks_index(tmp.path(), false);
+        let mentions = build_mentions_index(tmp.path(), &backlinks, false);
+        let refs = &mentions["/deploy.md"];
+        assert_eq!(refs.len(), 1, "{refs:?}");
+        let m = &refs[0];
+        assert_eq!(m.source_url_path, "/notes.md");
+        assert_eq!(m.source_display, "Notes");
+        assert_eq!(m.text, "Deploy guide");
+        assert_eq!((m.line, m.column), (6, 8));
+        assert_eq!(m.snippet, "see the Deploy guide first.");
+        assert_eq!(
+            &m.snippet[m.snippet_mention.clone().unwrap()],
+            "Deploy guide"
+        );
+    }
+
+    #[test]
+    fn link_mention_rewrites_only_a_matching_span() {
+        let src = "# N\n\nsee the Deploy guide first.\n";
+        assert_eq!(
+            link_mention_in_source(src, 3, 8, "Deploy guide", "../deploy.md").unwrap(),
+            "# N\n\nsee the [Deploy guide](../deploy.md) first.\n"
+        );
+        assert!(link_mention_in_source(src, 3, 9, "Deploy guide", "d.md")
+            .unwrap_err()
+            .contains("changed on disk"));
+        assert!(link_mention_in_source(src, 9, 0, "N", "d.md").is_err());
+        assert_eq!(
+            link_mention_in_source("a [b] c\n", 1, 2, "[b]", "my page.md").unwrap(),
+            "a [\\[b\\]](<my page.md>) c\n"
+        );
+        assert_eq!(
+            link_mention_in_source("\u{feff}see Deploy\r\n", 1, 4, "Deploy", "d.md").unwrap(),
+            "\u{feff}see [Deploy](d.md)\r\n"
+        );
+    }
+
+    #[test]
+    fn relative_href_walks_between_directories() {
+        assert_eq!(relative_href("/docs/a.md", "/docs/b.md"), "b.md");
+        assert_eq!(relative_href("/a.md", "/docs/b.md"), "docs/b.md");
+        assert_eq!(relative_href("/docs/x/a.md", "/docs/b.md"), "../b.md");
+        assert_eq!(relative_href("/docs/a.md", "/guide/b.md"), "../guide/b.md");
+    }
 }
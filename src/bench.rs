@@ -0,0 +1,384 @@
+//! Synthetic stress fixtures and the `mdmd bench` throughput report.
+//!
+//! The fixtures are generated deterministically so numbers are comparable
+//! across machines and commits.  Each one stresses a different axis: a huge
+//! table, deeply nested lists and quotes, thousands of links, thousands of
+//! (partly duplicate) headings, and a mix of highlighted code blocks.
+//!
+//! Every fixture is run through four stages:
+//!
+//! | stage   | work measured                                            |
+//! |---------|----------------------------------------------------------|
+//! | parse   | TUI parse (`parse::parse`)                               |
+//! | render  | TUI render to styled lines (`render::render_document`)   |
+//! | html    | serve-mode HTML render with link rewriting               |
+//! | serve   | `html` plus full page shell assembly, as sent to clients |
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::html::{self, PageShellContext, RenderTarget};
+use crate::i18n::Locale;
+use crate::{parse, render};
+
+/// A generated stress document.
+pub struct Fixture {
+    pub name: &'static str,
+    pub source: String,
+}
+
+/// Timing for one fixture and stage.
+#[derive(Debug, Clone)]
+pub struct StageResult {
+    pub fixture: &'static str,
+    pub stage: &'static str,
+    /// Source size in bytes.
+    pub bytes: usize,
+    /// Median wall time over all iterations.
+    pub median: Duration,
+}
+
+impl StageResult {
+    /// Source megabytes processed per second at the median time.
+    pub fn mb_per_sec(&self) -> f64 {
+        let secs = self.median.as_secs_f64();
+        if secs == 0.0 {
+            return f64::INFINITY;
+        }
+        self.bytes as f64 / (1024.0 * 1024.0) / secs
+    }
+}
+
+/// A table with `rows` body rows and `cols` columns of mixed inline content.
+pub fn huge_table(rows: usize, cols: usize) -> String {
+    let mut s = String::from("# Huge table\n\n|");
+    for c in 0..cols {
+        let _ = write!(s, " Column {c} |");
+    }
+    s.push_str("\n|");
+    for _ in 0..cols {
+        s.push_str(" --- |");
+    }
+    s.push('\n');
+    for r in 0..rows {
+        s.push('|');
+        for c in 0..cols {
+            match (r + c) % 4 {
+                0 => {
+                    let _ = write!(s, " cell {r}.{c} |");
+                }
+                1 => {
+                    let _ = write!(s, " **bold {r}** |");
+                }
+                2 => {
+                    let _ = write!(s, " `code {c}` |");
+                }
+                _ => {
+                    let _ = write!(s, " [link](row-{r}.md) |");
+                }
+            }
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// Lists and block quotes nested `depth` levels deep, repeated `repeats` times.
+pub fn deep_nesting(depth: usize, repeats: usize) -> String {
+    let mut s = String::from("# Deep nesting\n\n");
+    for rep in 0..repeats {
+        for d in 0..depth {
+            let _ = writeln!(s, "{}- item {rep}.{d} with *emphasis*", "  ".repeat(d));
+        }
+        s.push('\n');
+        for d in 1..=depth {
+            let _ = writeln!(s, "{} quote level {d}", ">".repeat(d));
+        }
+        s.push('\n');
+    }
+    s
+}
+
+/// `count` links split between local markdown targets (with and without
+/// fragments) and external URLs.
+pub fn many_links(count: usize) -> String {
+    let mut s = String::from("# Many links\n\n");
+    for i in 0..count {
+        match i % 3 {
+            0 => {
+                let _ = write!(s, "See [page {i}](docs/page-{i}.md). ");
+            }
+            1 => {
+                let _ = write!(s, "Jump to [section {i}](../guide.md#section-{i}). ");
+            }
+            _ => {
+                let _ = write!(s, "Visit [site {i}](https://example.com/{i}). ");
+            }
+        }
+        if i % 10 == 9 {
+            s.push_str("\n\n");
+        }
+    }
+    s.push('\n');
+    s
+}
+
+/// `count` headings cycling through levels 1–6, with every fourth heading
+/// repeating an earlier title to exercise anchor de-duplication.
+pub fn many_headings(count: usize) -> String {
+    let mut s = String::new();
+    for i in 0..count {
+        let level = i % 6 + 1;
+        let title = if i % 4 == 3 {
+            format!("Repeated title {}", i % 8)
+        } else {
+            format!("Heading {i}")
+        };
+        let _ = writeln!(s, "{} {title}\n\nShort paragraph {i}.\n", "#".repeat(level));
+    }
+    s
+}
+
+/// Prose interleaved with syntax-highlighted code blocks in several languages.
+pub fn mixed_code(blocks: usize) -> String {
+    const SAMPLES: &[(&str, &str)] = &[
+        (
+            "rust",
+            "fn main() {\n    let v: Vec<u32> = (0..10).collect();\n    println!(\"{v:?}\");\n}",
+        ),
+        (
+            "python",
+            "def fib(n):\n    return n if n < 2 else fib(n - 1) + fib(n - 2)",
+        ),
+        (
+            "js",
+            "const xs = [1, 2, 3].map((x) => x * 2);\nconsole.log(xs);",
+        ),
+        ("sh", "for f in *.md; do\n  echo \"$f\"\ndone"),
+    ];
+    let mut s = String::from("# Mixed code\n\n");
+    for i in 0..blocks {
+        let (lang, code) = SAMPLES[i % SAMPLES.len()];
+        let _ = writeln!(
+            s,
+            "Paragraph {i} introduces the **{lang}** sample below.\n\n```{lang}\n{code}\n```\n"
+        );
+    }
+    s
+}
+
+/// The standard benchmark corpus.
+pub fn fixtures() -> Vec<Fixture> {
+    vec![
+        Fixture {
+            name: "huge-table",
+            source: huge_table(2000, 12),
+        },
+        Fixture {
+            name: "deep-nesting",
+            source: deep_nesting(40, 25),
+        },
+        Fixture {
+            name: "many-links",
+            source: many_links(5000),
+        },
+        Fixture {
+            name: "many-headings",
+            source: many_headings(3000),
+        },
+        Fixture {
+            name: "mixed-code",
+            source: mixed_code(400),
+        },
+    ]
+}
+
+/// Write every fixture to `dir` as `<name>.md`, creating the directory.
+pub fn write_fixtures(dir: &Path) -> std::io::Result<Vec<std::path::PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    fixtures()
+        .into_iter()
+        .map(|f| {
+            let path = dir.join(format!("{}.md", f.name));
+            std::fs::write(&path, f.source)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Median wall time of `iterations` runs of `f`.
+fn median_time(iterations: usize, mut f: impl FnMut()) -> Duration {
+    let mut times: Vec<Duration> = (0..iterations.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort_unstable();
+    times[times.len() / 2]
+}
+
+/// Time every stage on `fixture`.
+pub fn run_fixture(fixture: &Fixture, iterations: usize) -> Vec<StageResult> {
+    let src = fixture.source.as_str();
+    let file_path = Path::new("/bench").join(format!("{}.md", fixture.name));
+    let serve_root = Path::new("/bench");
+    let result = |stage, median| StageResult {
+        fixture: fixture.name,
+        stage,
+        bytes: src.len(),
+        median,
+    };
+
+    let parse = median_time(iterations, || {
+        std::hint::black_box(parse::parse(src));
+    });
+    let doc = parse::parse(src);
+    let render = median_time(iterations, || {
+        std::hint::black_box(render::render_document(&doc));
+    });
+    let html = median_time(iterations, || {
+        std::hint::black_box(html::render_markdown(
+            src,
+            &file_path,
+            serve_root,
+            RenderTarget::Serve,
+            false,
+        ));
+    });
+    let serve = median_time(iterations, || {
+        let (body, headings) =
+            html::render_markdown(src, &file_path, serve_root, RenderTarget::Serve, false);
+        let ctx = PageShellContext {
+            frontmatter: None,
+            backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width: false,
+            locale: Locale::En,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: html::RenderOptions::default(),
+        };
+        std::hint::black_box(html::build_page_shell(
+            &body,
+            &headings,
+            &file_path,
+            &ctx,
+            RenderTarget::Serve,
+        ));
+    });
+
+    vec![
+        result("parse", parse),
+        result("render", render),
+        result("html", html),
+        result("serve", serve),
+    ]
+}
+
+/// Format results as an aligned plain-text table.
+pub fn format_table(results: &[StageResult]) -> String {
+    let mut out = format!(
+        "{:<14} {:<7} {:>10} {:>11} {:>10}\n",
+        "fixture", "stage", "size_kb", "median_ms", "mb_per_s"
+    );
+    for r in results {
+        let _ = writeln!(
+            out,
+            "{:<14} {:<7} {:>10.1} {:>11.2} {:>10.1}",
+            r.fixture,
+            r.stage,
+            r.bytes as f64 / 1024.0,
+            r.median.as_secs_f64() * 1000.0,
+            r.mb_per_sec()
+        );
+    }
+    out
+}
+
+/// Format results as a JSON array for CI consumption.
+pub fn format_json(results: &[StageResult]) -> String {
+    let rows: Vec<serde_json::Value> = results
+        .iter()
+        .map(|r| {
+            serde_json::json!({
+                "fixture": r.fixture,
+                "stage": r.stage,
+                "bytes": r.bytes,
+                "median_ms": r.median.as_secs_f64() * 1000.0,
+                "mb_per_s": r.mb_per_sec(),
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&rows).unwrap_or_else(|_| "[]".to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixtures_are_deterministic_and_named_uniquely() {
+        let a = fixtures();
+        let b = fixtures();
+        let names: std::collections::HashSet<_> = a.iter().map(|f| f.name).collect();
+        assert_eq!(names.len(), a.len());
+        for (x, y) in a.iter().zip(&b) {
+            assert_eq!(x.source, y.source, "{} must be deterministic", x.name);
+        }
+    }
+
+    #[test]
+    fn generators_produce_requested_shapes() {
+        let table = huge_table(3, 2);
+        assert_eq!(table.lines().filter(|l| l.starts_with('|')).count(), 5);
+
+        let doc = parse::parse(&many_headings(12));
+        assert_eq!(doc.headings.len(), 12);
+
+        let links = many_links(9);
+        assert_eq!(links.matches("](").count(), 9);
+        assert!(links.contains("#section-1)"));
+
+        let nested = deep_nesting(5, 1);
+        assert!(nested.contains(&format!("{}- item 0.4", "  ".repeat(4))));
+        assert!(nested.contains(">>>>> quote level 5"));
+    }
+
+    #[test]
+    fn run_fixture_reports_every_stage() {
+        let fixture = Fixture {
+            name: "tiny",
+            source: mixed_code(2),
+        };
+        let results = run_fixture(&fixture, 1);
+        let stages: Vec<_> = results.iter().map(|r| r.stage).collect();
+        assert_eq!(stages, vec!["parse", "render", "html", "serve"]);
+        assert!(results.iter().all(|r| r.bytes == fixture.source.len()));
+
+        let table = format_table(&results);
+        assert!(table.starts_with("fixture"));
+        assert_eq!(table.lines().count(), 5);
+        let json: serde_json::Value = serde_json::from_str(&format_json(&results)).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 4);
+        assert_eq!(json[0]["fixture"], "tiny");
+    }
+
+    #[test]
+    fn write_fixtures_creates_markdown_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let written = write_fixtures(&dir.path().join("corpus")).unwrap();
+        assert_eq!(written.len(), fixtures().len());
+        assert!(written
+            .iter()
+            .all(|p| p.extension().unwrap() == "md" && p.is_file()));
+    }
+}
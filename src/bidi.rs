@@ -0,0 +1,57 @@
+//! Basic bidirectional text support.
+//!
+//! A paragraph's direction is that of its first strong character, as in
+//! rule P2 of the Unicode Bidirectional Algorithm and HTML's `dir="auto"`:
+//! Hebrew, Arabic, and the other right-to-left scripts make it
+//! right-to-left, any other letter left-to-right.  The TUI right-aligns
+//! right-to-left lines and leaves reordering within a line to the terminal;
+//! HTML output marks text blocks `dir="auto"` so the browser does the rest.
+
+/// Whether `c` belongs to a right-to-left script.
+fn is_rtl_char(c: char) -> bool {
+    matches!(
+        c,
+        // Hebrew, Arabic, Syriac, Thaana, NKo, Samaritan, Mandaic, and
+        // Arabic supplements and extensions.
+        '\u{0590}'..='\u{08FF}'
+            // Hebrew and Arabic presentation forms.
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+            // Historic scripts and Arabic mathematical symbols.
+            | '\u{10800}'..='\u{10FFF}'
+            | '\u{1E800}'..='\u{1EFFF}'
+    )
+}
+
+/// Whether the first strong character of `text` is right-to-left.  Text
+/// without letters (numbers, punctuation, symbols) is left-to-right.
+pub fn is_rtl(text: &str) -> bool {
+    text.chars()
+        .find(|&c| c.is_alphabetic())
+        .is_some_and(is_rtl_char)
+}
+
+/// Whether any character of `text` is from a right-to-left script.
+pub fn has_rtl(text: &str) -> bool {
+    text.chars().any(is_rtl_char)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_strong_character_decides() {
+        assert!(is_rtl("\u{05e9}\u{05dc}\u{05d5}\u{05dd} world"));
+        assert!(is_rtl("1. \u{0645}\u{0631}\u{062d}\u{0628}\u{0627}"));
+        assert!(!is_rtl("hello \u{05e9}\u{05dc}\u{05d5}\u{05dd}"));
+        assert!(!is_rtl("123 -- !"));
+        assert!(!is_rtl(""));
+    }
+
+    #[test]
+    fn has_rtl_finds_any_rtl_character() {
+        assert!(has_rtl("see \u{05e9}"));
+        assert!(!has_rtl("caf\u{e9} \u{65e5}\u{672c}"));
+    }
+}
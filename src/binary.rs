@@ -0,0 +1,55 @@
+//! Binary file detection.
+//!
+//! Links and requests can point at images, archives, and other files that
+//! are not text.  Like git, a file is taken to be binary when its first few
+//! kilobytes contain a NUL byte, unless it is UTF-16 text (see
+//! [`crate::encoding`]); such files are shown as a placeholder by the TUI
+//! and downloaded by `mdmd serve` rather than rendered.
+
+use std::path::Path;
+
+use crate::encoding;
+
+/// Number of leading bytes inspected for NUL bytes.
+const SNIFF_LEN: usize = 8000;
+
+/// Whether `bytes` look like the contents of a binary file.
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    bytes[..bytes.len().min(SNIFF_LEN)].contains(&0) && !encoding::is_utf16(bytes)
+}
+
+/// Markdown shown in place of the binary file at `path`, `size` bytes long.
+pub fn placeholder(path: &Path, size: usize) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_else(|| path.to_string_lossy());
+    format!("# {name}\n\n*Binary file ({size} bytes) is not shown.*\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_not_binary() {
+        assert!(!looks_binary(b"# Title\n\nSome text.\n"));
+        assert!(!looks_binary("caf\u{e9} \u{1f600}\n".as_bytes()));
+        assert!(!looks_binary(b"Latin-1 caf\xe9\n"));
+        assert!(!looks_binary(b"\xff\xfe#\0 \0T\0"));
+        assert!(!looks_binary(b""));
+    }
+
+    #[test]
+    fn nul_bytes_are_binary() {
+        assert!(looks_binary(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert!(looks_binary(b"text\0with a NUL"));
+    }
+
+    #[test]
+    fn placeholder_names_the_file_and_its_size() {
+        let text = placeholder(Path::new("/docs/logo.png"), 1234);
+        assert!(text.starts_with("# logo.png\n"), "got: {text}");
+        assert!(text.contains("1234 bytes"), "got: {text}");
+    }
+}
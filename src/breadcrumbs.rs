@@ -0,0 +1,240 @@
+//! Breadcrumb header for the TUI viewer (`--breadcrumbs`).
+//!
+//! The header shows the viewed file's path relative to its project root,
+//! with segments separated by ` / ` as in serve's directory-index
+//! breadcrumbs.  Clicking a directory segment opens that directory the way
+//! serve resolves a directory URL: its `README.md`, else its `index.md`.
+
+use std::path::{Path, PathBuf};
+
+use ratatui::text::Span;
+
+/// Separator drawn between segments.
+const SEPARATOR: &str = " / ";
+/// Drawn in place of leading segments that do not fit.
+const ELLIPSIS: &str = "\u{2026}";
+
+/// One path component of the trail.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub label: String,
+    /// Directory the segment stands for; `None` for the viewed file itself.
+    pub dir: Option<PathBuf>,
+}
+
+/// A piece of the drawn header: a segment (by index) or filler text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Piece<'a> {
+    Segment(usize, &'a str),
+    Text(&'static str),
+}
+
+/// Breadcrumb trail from the project root to the viewed file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trail {
+    /// The file the trail was built for.
+    pub file: PathBuf,
+    pub segments: Vec<Segment>,
+}
+
+impl Trail {
+    pub fn for_file(file: &Path) -> Trail {
+        let root = project_root(file);
+        let mut segments = vec![Segment {
+            label: root.file_name().map_or_else(
+                || root.display().to_string(),
+                |n| n.to_string_lossy().into_owned(),
+            ),
+            dir: Some(root.clone()),
+        }];
+        let mut dir = root.clone();
+        let rel = file.strip_prefix(&root).unwrap_or(file);
+        let components: Vec<_> = rel.components().collect();
+        for (i, component) in components.iter().enumerate() {
+            let label = component.as_os_str().to_string_lossy().into_owned();
+            if i + 1 == components.len() {
+                segments.push(Segment { label, dir: None });
+            } else {
+                dir.push(component);
+                segments.push(Segment {
+                    label,
+                    dir: Some(dir.clone()),
+                });
+            }
+        }
+        Trail {
+            file: file.to_path_buf(),
+            segments,
+        }
+    }
+
+    /// The header as drawn into `width` columns, after a one-column margin.
+    /// When the full trail does not fit, leading segments are replaced by an
+    /// ellipsis so the file name stays visible.
+    pub fn pieces(&self, width: usize) -> Vec<Piece<'_>> {
+        let seg_width = |s: &Segment| Span::raw(s.label.as_str()).width();
+        let total: usize = self.segments.iter().map(seg_width).sum::<usize>()
+            + SEPARATOR.len() * self.segments.len().saturating_sub(1)
+            + 1;
+
+        let mut first = 0;
+        let mut used = total;
+        while used > width && first + 1 < self.segments.len() {
+            // Dropping a segment removes it and its separator; the ellipsis
+            // (one column) takes the place of the first dropped one.
+            used -= seg_width(&self.segments[first]) + SEPARATOR.len();
+            if first == 0 {
+                used += 1 + SEPARATOR.len();
+            }
+            first += 1;
+        }
+
+        let mut pieces = vec![Piece::Text(" ")];
+        if first > 0 {
+            pieces.push(Piece::Text(ELLIPSIS));
+            pieces.push(Piece::Text(SEPARATOR));
+        }
+        for (i, seg) in self.segments.iter().enumerate().skip(first) {
+            if i > first {
+                pieces.push(Piece::Text(SEPARATOR));
+            }
+            pieces.push(Piece::Segment(i, &seg.label));
+        }
+        pieces
+    }
+
+    /// The directory segment drawn at `column` (0-based) of a header
+    /// `width` columns wide, if any.
+    pub fn dir_at(&self, column: usize, width: usize) -> Option<&Path> {
+        let mut x = 0;
+        for piece in self.pieces(width) {
+            let (text, idx) = match piece {
+                Piece::Segment(idx, label) => (label, Some(idx)),
+                Piece::Text(text) => (text, None),
+            };
+            let w = Span::raw(text).width();
+            if column < x + w {
+                return idx.and_then(|i| self.segments[i].dir.as_deref());
+            }
+            x += w;
+        }
+        None
+    }
+}
+
+/// The project root shown at the start of the trail: the nearest ancestor
+/// of `file` containing `.git`, else the nearest containing a `README.md`
+/// (other than the file itself), else the file's own directory.
+pub fn project_root(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(Path::new("/"));
+    if let Some(git) = parent.ancestors().find(|d| d.join(".git").exists()) {
+        return git.to_path_buf();
+    }
+    parent
+        .ancestors()
+        .find(|d| {
+            let readme = d.join("README.md");
+            readme != file && readme.is_file()
+        })
+        .unwrap_or(parent)
+        .to_path_buf()
+}
+
+/// The file opened for a directory segment: `README.md`, then `index.md`.
+pub fn index_file(dir: &Path) -> Option<PathBuf> {
+    ["README.md", "index.md"]
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|p| p.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn labels(trail: &Trail) -> Vec<&str> {
+        trail.segments.iter().map(|s| s.label.as_str()).collect()
+    }
+
+    #[test]
+    fn root_is_git_root_then_nearest_readme() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("proj");
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::create_dir_all(root.join("docs/guide")).unwrap();
+        fs::write(root.join("docs/README.md"), "").unwrap();
+        let file = root.join("docs/guide/setup.md");
+        fs::write(&file, "").unwrap();
+
+        let trail = Trail::for_file(&file);
+        assert_eq!(labels(&trail), ["proj", "docs", "guide", "setup.md"]);
+        assert_eq!(trail.segments[1].dir.as_deref(), Some(&*root.join("docs")));
+        assert_eq!(trail.segments[3].dir, None);
+
+        fs::remove_dir(root.join(".git")).unwrap();
+        assert_eq!(project_root(&file), root.join("docs"));
+        // A README does not root its own trail.
+        assert_eq!(
+            project_root(&root.join("docs/README.md")),
+            root.join("docs")
+        );
+        fs::write(root.join("README.md"), "").unwrap();
+        assert_eq!(project_root(&root.join("docs/README.md")), root);
+    }
+
+    #[test]
+    fn pieces_elide_leading_segments_to_fit() {
+        let trail = Trail {
+            file: PathBuf::from("/p/docs/guide/setup.md"),
+            segments: vec![
+                Segment {
+                    label: "p".into(),
+                    dir: Some("/p".into()),
+                },
+                Segment {
+                    label: "docs".into(),
+                    dir: Some("/p/docs".into()),
+                },
+                Segment {
+                    label: "guide".into(),
+                    dir: Some("/p/docs/guide".into()),
+                },
+                Segment {
+                    label: "setup.md".into(),
+                    dir: None,
+                },
+            ],
+        };
+        let text = |width| {
+            trail
+                .pieces(width)
+                .iter()
+                .map(|p| match p {
+                    Piece::Segment(_, s) | Piece::Text(s) => *s,
+                })
+                .collect::<String>()
+        };
+        assert_eq!(text(80), " p / docs / guide / setup.md");
+        assert_eq!(text(21), " \u{2026} / guide / setup.md");
+        assert_eq!(text(20), " \u{2026} / setup.md");
+        assert_eq!(text(5), " \u{2026} / setup.md");
+
+        // " p / docs / ..." — column 1 is "p", 5..9 is "docs".
+        assert_eq!(trail.dir_at(1, 80), Some(Path::new("/p")));
+        assert_eq!(trail.dir_at(2, 80), None);
+        assert_eq!(trail.dir_at(6, 80), Some(Path::new("/p/docs")));
+        assert_eq!(trail.dir_at(22, 80), None, "file segment");
+        assert_eq!(trail.dir_at(6, 21), Some(Path::new("/p/docs/guide")));
+    }
+
+    #[test]
+    fn index_file_prefers_readme() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(index_file(dir.path()), None);
+        fs::write(dir.path().join("index.md"), "").unwrap();
+        assert_eq!(index_file(dir.path()), Some(dir.path().join("index.md")));
+        fs::write(dir.path().join("README.md"), "").unwrap();
+        assert_eq!(index_file(dir.path()), Some(dir.path().join("README.md")));
+    }
+}
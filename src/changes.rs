@@ -0,0 +1,96 @@
+//! Highlighting what changed when the viewed document is re-rendered.
+//!
+//! After the document is rewritten under the reader, the TUI briefly marks
+//! the rendered lines that were not there before, so an author can see what
+//! their edit touched.  Lines are compared by text: a line that merely moved
+//! is not marked, and blank lines never are.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::render::RenderedDocument;
+
+/// How long changed lines stay highlighted.
+pub const HIGHLIGHT_FOR: Duration = Duration::from_secs(3);
+
+/// Changed lines of the document at `path`, highlighted until `until`.
+pub struct Highlight {
+    pub path: PathBuf,
+    /// 0-based rendered line indices in the new rendering.
+    pub lines: Vec<usize>,
+    pub until: Instant,
+}
+
+impl Highlight {
+    /// Highlight the lines of `new` that `old` does not have, for
+    /// [`HIGHLIGHT_FOR`] from now.
+    pub fn between(path: PathBuf, old: &RenderedDocument, new: &RenderedDocument) -> Highlight {
+        Highlight {
+            path,
+            lines: changed_lines(old, new),
+            until: Instant::now() + HIGHLIGHT_FOR,
+        }
+    }
+
+    /// Time until the highlight expires; zero once it has.
+    pub fn remaining(&self) -> Duration {
+        self.until.saturating_duration_since(Instant::now())
+    }
+}
+
+/// Indices of the non-blank lines of `new` whose text `old` lacks, counting
+/// repeats: a third copy of a line `old` had twice is changed.
+pub fn changed_lines(old: &RenderedDocument, new: &RenderedDocument) -> Vec<usize> {
+    let mut before: HashMap<String, usize> = HashMap::new();
+    for line in &old.text.lines {
+        *before.entry(line.to_string()).or_default() += 1;
+    }
+    new.text
+        .lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let text = line.to_string();
+            if text.trim().is_empty() {
+                return None;
+            }
+            match before.get_mut(&text) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    None
+                }
+                _ => Some(i),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::render::render_document;
+
+    fn changed(old: &str, new: &str) -> Vec<usize> {
+        let old = render_document(&parse::parse(old));
+        let new = render_document(&parse::parse(new));
+        changed_lines(&old, &new)
+    }
+
+    #[test]
+    fn unchanged_document_has_no_changes() {
+        assert!(changed("# A\n\none\n\ntwo\n", "# A\n\none\n\ntwo\n").is_empty());
+    }
+
+    #[test]
+    fn edited_and_added_lines_are_changed() {
+        // "one" became "uno" (line 2) and "three" was added (line 6).
+        assert_eq!(changed("# A\n\none\n\ntwo\n", "# A\n\nuno\n\ntwo\n\nthree\n"), [2, 6]);
+    }
+
+    #[test]
+    fn repeated_lines_are_counted() {
+        assert_eq!(changed("same\n\nsame\n", "same\n\nsame\n\nsame\n"), [4]);
+    }
+}
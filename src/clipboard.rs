@@ -0,0 +1,167 @@
+//! Copying text to the clipboard, for `serve --copy-url` and the TUI's `y`.
+//!
+//! Locally, the text is piped to a platform command: `pbcopy` on macOS,
+//! `clip` on Windows, and `wl-copy`, `xclip`, or `xsel` on Linux, whichever
+//! is installed for the running display server.  Over SSH those would fill the remote machine's
+//! clipboard, so the text is instead written to the terminal as an OSC 52
+//! escape sequence, which most terminal emulators copy to the clipboard of
+//! the machine the user is sitting at.  OSC 52 is also the fallback when no
+//! command works.  `MDMD_COPY_CMD` replaces the platform command.
+
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+use crate::serve::{EnvSnapshot, RuntimePlatform};
+
+/// Clipboard commands to try in order, each reading the text on stdin.
+/// Empty over SSH, where only OSC 52 reaches the user's clipboard.
+pub fn commands_for(
+    platform: RuntimePlatform,
+    env: &EnvSnapshot,
+) -> &'static [&'static [&'static str]] {
+    if env.ssh_connection || env.ssh_tty {
+        return &[];
+    }
+    match platform {
+        RuntimePlatform::MacOs => &[&["pbcopy"]],
+        RuntimePlatform::Windows => &[&["clip"]],
+        RuntimePlatform::Linux if env.wayland_display => &[&["wl-copy"]],
+        RuntimePlatform::Linux if env.display => &[
+            &["xclip", "-selection", "clipboard"],
+            &["xsel", "--clipboard", "--input"],
+        ],
+        _ => &[],
+    }
+}
+
+/// The OSC 52 sequence that asks a terminal to put `text` on the clipboard.
+pub fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+/// Put `text` on the clipboard.  Returns how it was copied: the command's
+/// name, or `"osc52"`.
+pub fn copy(text: &str, env: &EnvSnapshot) -> Result<String, String> {
+    let override_cmd = std::env::var("MDMD_COPY_CMD").ok();
+    let override_argv: Vec<&str> = override_cmd
+        .as_deref()
+        .map(|c| c.split_whitespace().collect())
+        .unwrap_or_default();
+    let candidates: Vec<&[&str]> = if override_cmd.is_some() {
+        vec![&override_argv]
+    } else {
+        commands_for(RuntimePlatform::current(), env).to_vec()
+    };
+    let mut failures = Vec::new();
+    for argv in candidates.into_iter().filter(|a| !a.is_empty()) {
+        match pipe_to(argv, text) {
+            Ok(()) => return Ok(argv[0].to_owned()),
+            Err(e) => failures.push(format!("{}: {e}", argv[0])),
+        }
+    }
+
+    let mut stderr = io::stderr();
+    if !stderr.is_terminal() {
+        failures.push("osc52: stderr is not a terminal".to_owned());
+        return Err(failures.join("; "));
+    }
+    stderr
+        .write_all(osc52(text).as_bytes())
+        .and_then(|()| stderr.flush())
+        .map_err(|e| format!("osc52: {e}"))?;
+    Ok("osc52".to_owned())
+}
+
+/// Run `argv` with `text` on its stdin and wait for it to exit.
+fn pipe_to(argv: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .expect("stdin is piped")
+        .write_all(text.as_bytes())?;
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("exited with {status}")))
+    }
+}
+
+/// Standard base64 with padding.
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env() -> EnvSnapshot {
+        EnvSnapshot {
+            ssh_connection: false,
+            ssh_tty: false,
+            display: false,
+            wayland_display: false,
+            ci: false,
+            github_actions: false,
+        }
+    }
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(
+            osc52("http://127.0.0.1:3333/"),
+            "\x1b]52;c;aHR0cDovLzEyNy4wLjAuMTozMzMzLw==\x07"
+        );
+    }
+
+    #[test]
+    fn commands_follow_platform_and_display_server() {
+        assert_eq!(commands_for(RuntimePlatform::MacOs, &env())[0], ["pbcopy"]);
+        let wayland = EnvSnapshot {
+            wayland_display: true,
+            display: true,
+            ..env()
+        };
+        assert_eq!(
+            commands_for(RuntimePlatform::Linux, &wayland)[0],
+            ["wl-copy"]
+        );
+        let x11 = EnvSnapshot {
+            display: true,
+            ..env()
+        };
+        assert_eq!(commands_for(RuntimePlatform::Linux, &x11)[0][0], "xclip");
+        assert!(commands_for(RuntimePlatform::Linux, &env()).is_empty());
+        let ssh = EnvSnapshot {
+            ssh_tty: true,
+            ..env()
+        };
+        assert!(commands_for(RuntimePlatform::MacOs, &ssh).is_empty());
+    }
+}
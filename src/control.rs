@@ -0,0 +1,240 @@
+//! Driving a running viewer or server from an editor over a Unix socket.
+//!
+//! Clients connect and write one command per line.  `--control <socket>`
+//! takes the full command set:
+//!
+//! - `open FILE` shows FILE (the TUI opens it like a followed link; `serve`
+//!   opens its page in the browser),
+//! - `scroll LINE` or `scroll FILE:LINE` centres a 1-based source line,
+//! - `reload` re-reads the document from disk, and
+//! - `quit` exits.
+//!
+//! `mdmd view --listen <socket>` takes bare scroll targets instead (`42` or
+//! `/path/to/doc.md:42`), for editor plugins that stream the cursor line.
+//! A line that does not parse is answered with `error: <reason>`; accepted
+//! commands get no reply, so clients that never read do not stall.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+/// One command read from a control socket.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Open(PathBuf),
+    /// Centre `line` of `path`, or of the file being shown when `None`.
+    /// Requests for other files than the one shown are ignored.
+    Scroll {
+        path: Option<PathBuf>,
+        line: usize,
+    },
+    Reload,
+    Quit,
+}
+
+/// Parser for the lines of one socket protocol.
+pub type Parser = fn(&str) -> Result<Command, String>;
+
+/// A bound socket and the commands read from its connections.  The socket
+/// file is removed when the listener is dropped.
+pub struct Listener {
+    commands: mpsc::Receiver<Command>,
+    socket: PathBuf,
+}
+
+impl Listener {
+    /// Listen on `socket`, reading its lines with `parse` and replacing a
+    /// socket file left behind by an mdmd that is no longer running.
+    #[cfg(unix)]
+    pub fn bind(socket: &Path, parse: Parser) -> io::Result<Listener> {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::{UnixListener, UnixStream};
+        use std::thread;
+
+        let listener = match UnixListener::bind(socket) {
+            Err(e)
+                if e.kind() == io::ErrorKind::AddrInUse && UnixStream::connect(socket).is_err() =>
+            {
+                fs::remove_file(socket)?;
+                UnixListener::bind(socket)?
+            }
+            other => other?,
+        };
+        let (tx, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().map_while(Result::ok) {
+                let tx = tx.clone();
+                thread::spawn(move || {
+                    let mut replies = stream.try_clone().ok();
+                    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+                        if line.trim().is_empty() {
+                            continue;
+                        }
+                        match parse(&line) {
+                            Ok(command) => {
+                                if tx.send(command).is_err() {
+                                    return;
+                                }
+                            }
+                            Err(reason) => {
+                                if let Some(out) = replies.as_mut() {
+                                    let _ = writeln!(out, "error: {reason}");
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+        Ok(Listener {
+            commands,
+            socket: socket.to_path_buf(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind(_socket: &Path, _parse: Parser) -> io::Result<Listener> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "control sockets need Unix domain sockets",
+        ))
+    }
+
+    /// Commands received since the last call, oldest first.
+    pub fn pending(&self) -> mpsc::TryIter<'_, Command> {
+        self.commands.try_iter()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.socket);
+    }
+}
+
+/// Whether a scroll request for `path` applies to `current`, the canonical
+/// path of the file being shown.
+pub fn is_current(path: Option<&Path>, current: &Path) -> bool {
+    path.is_none_or(|p| fs::canonicalize(p).is_ok_and(|p| p == current))
+}
+
+/// Parse a `--control` command line.
+pub fn parse_command(text: &str) -> Result<Command, String> {
+    let text = text.trim();
+    let (verb, arg) = text.split_once(' ').unwrap_or((text, ""));
+    let arg = arg.trim();
+    match (verb, arg) {
+        ("open", "") => Err("open needs a FILE".to_owned()),
+        ("open", file) => Ok(Command::Open(PathBuf::from(file))),
+        ("scroll", target) => parse_scroll(target),
+        ("reload", "") => Ok(Command::Reload),
+        ("quit", "") => Ok(Command::Quit),
+        ("reload" | "quit", _) => Err(format!("{verb} takes no arguments")),
+        _ => Err(format!(
+            "unknown command '{verb}', expected open, scroll, reload, or quit"
+        )),
+    }
+}
+
+/// Parse a scroll target, `42` or `path/to/doc.md:42`: the `--listen`
+/// protocol and the argument of `scroll`.
+pub fn parse_scroll(text: &str) -> Result<Command, String> {
+    let text = text.trim();
+    let (path, line) = match text.rsplit_once(':') {
+        Some((path, line)) => (Some(PathBuf::from(path)), line),
+        None => (None, text),
+    };
+    let line = line
+        .trim()
+        .parse()
+        .ok()
+        .filter(|&l| l > 0)
+        .ok_or_else(|| format!("expected LINE or FILE:LINE, got '{text}'"))?;
+    Ok(Command::Scroll { path, line })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_targets_name_a_line_and_optionally_a_file() {
+        assert_eq!(
+            parse_scroll("42\n"),
+            Ok(Command::Scroll {
+                path: None,
+                line: 42
+            })
+        );
+        assert_eq!(
+            parse_scroll("/notes/a:b.md:7"),
+            Ok(Command::Scroll {
+                path: Some(PathBuf::from("/notes/a:b.md")),
+                line: 7
+            })
+        );
+        assert!(parse_scroll("0").is_err());
+        assert!(parse_scroll("doc.md:").is_err());
+        assert!(parse_scroll("hello").is_err());
+    }
+
+    #[test]
+    fn commands_parse_with_their_arguments() {
+        assert_eq!(
+            parse_command("open docs/My Notes.md"),
+            Ok(Command::Open(PathBuf::from("docs/My Notes.md")))
+        );
+        assert_eq!(
+            parse_command("scroll README.md:12"),
+            Ok(Command::Scroll {
+                path: Some(PathBuf::from("README.md")),
+                line: 12
+            })
+        );
+        assert_eq!(parse_command(" reload "), Ok(Command::Reload));
+        assert_eq!(parse_command("quit"), Ok(Command::Quit));
+        assert!(parse_command("open").is_err());
+        assert!(parse_command("quit now").is_err());
+        assert!(parse_command("scroll").is_err());
+        assert!(parse_command("jump 3").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_written_to_the_socket_are_received() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::os::unix::net::UnixStream;
+        use std::time::{Duration, Instant};
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("mdmd.sock");
+        let listener = Listener::bind(&socket, parse_command).unwrap();
+        let mut client = UnixStream::connect(&socket).unwrap();
+        client.write_all(b"scroll 3\nfly\nquit\n").unwrap();
+
+        let mut reply = String::new();
+        BufReader::new(&client).read_line(&mut reply).unwrap();
+        assert!(reply.starts_with("error: unknown command 'fly'"), "{reply}");
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut received = Vec::new();
+        while received.len() < 2 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+            received.extend(listener.pending());
+        }
+        assert_eq!(
+            received,
+            [
+                Command::Scroll {
+                    path: None,
+                    line: 3
+                },
+                Command::Quit
+            ]
+        );
+
+        drop(listener);
+        assert!(!socket.exists());
+    }
+}
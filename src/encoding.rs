@@ -0,0 +1,163 @@
+//! Character encoding detection for markdown that is not UTF-8.
+//!
+//! Files are read as bytes and decoded with `encoding_rs`.  A byte order
+//! mark decides the encoding when present; otherwise text whose every other
+//! byte is NUL is BOM-less UTF-16, text that is valid UTF-8 is UTF-8, and
+//! anything else is taken to be Windows-1252, the superset of Latin-1 that
+//! browsers also assume for unlabelled legacy text.
+//!
+//! Edits (task toggles, find-and-replace) are written back as UTF-8, so
+//! [`read_utf8`] refuses files in any other encoding instead of silently
+//! converting them.
+
+use std::fs;
+use std::path::Path;
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+
+/// Number of leading bytes inspected for BOM-less UTF-16.
+const SNIFF_LEN: usize = 8000;
+
+/// Text decoded from a file's bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub text: String,
+    /// Name of the encoding the text was decoded from, or `None` for UTF-8.
+    pub encoding: Option<&'static str>,
+}
+
+/// The encoding of `bytes`, as described in the module docs.
+pub fn detect(bytes: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return encoding;
+    }
+    // Before UTF-8: ASCII-only UTF-16 is valid UTF-8 full of NULs.
+    if let Some(encoding) = utf16_without_bom(bytes) {
+        return encoding;
+    }
+    if std::str::from_utf8(bytes).is_ok() {
+        return UTF_8;
+    }
+    WINDOWS_1252
+}
+
+/// Whether `bytes` are UTF-16 text, with or without a byte order mark.
+pub fn is_utf16(bytes: &[u8]) -> bool {
+    let encoding = detect(bytes);
+    encoding == UTF_16LE || encoding == UTF_16BE
+}
+
+/// Name of the encoding of `bytes` when it is not UTF-8.
+pub fn non_utf8(bytes: &[u8]) -> Option<&'static str> {
+    let encoding = detect(bytes);
+    (encoding != UTF_8).then(|| encoding.name())
+}
+
+/// Decode `bytes` to text, dropping any byte order mark.
+pub fn decode(bytes: &[u8]) -> Decoded {
+    let encoding = detect(bytes);
+    let (text, _, _) = encoding.decode(bytes);
+    Decoded {
+        text: text.into_owned(),
+        encoding: (encoding != UTF_8).then(|| encoding.name()),
+    }
+}
+
+/// The text of the file at `path` for editing in place.  Fails, asking for
+/// the file to be re-saved, when it is not UTF-8.
+pub fn read_utf8(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    if let Some(name) = non_utf8(&bytes) {
+        return Err(format!(
+            "the file is {name}; re-save it as UTF-8 to edit it"
+        ));
+    }
+    String::from_utf8(bytes)
+        .map_err(|_| "the file is not valid UTF-8; re-save it as UTF-8 to edit it".to_string())
+}
+
+/// UTF-16 byte order of BOM-less text in which the high byte of every code
+/// unit sniffed is NUL, as it is for text in Latin scripts.
+fn utf16_without_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sniffed = &bytes[..bytes.len().min(SNIFF_LEN)];
+    if sniffed.len() < 2 || !sniffed.len().is_multiple_of(2) {
+        return None;
+    }
+    let units = || sniffed.chunks_exact(2);
+    if units().all(|u| u[1] == 0 && u[0] != 0) {
+        Some(UTF_16LE)
+    } else if units().all(|u| u[0] == 0 && u[1] != 0) {
+        Some(UTF_16BE)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str, bom: bool) -> Vec<u8> {
+        let mut bytes = if bom { vec![0xFF, 0xFE] } else { Vec::new() };
+        bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        bytes
+    }
+
+    #[test]
+    fn utf8_is_left_alone() {
+        let decoded = decode("# Caf\u{e9}\n".as_bytes());
+        assert_eq!(decoded.text, "# Caf\u{e9}\n");
+        assert_eq!(decoded.encoding, None);
+    }
+
+    #[test]
+    fn utf8_bom_is_dropped() {
+        let decoded = decode(b"\xEF\xBB\xBF# Title\n");
+        assert_eq!(decoded.text, "# Title\n");
+        assert_eq!(decoded.encoding, None);
+    }
+
+    #[test]
+    fn latin1_decodes_as_windows_1252() {
+        let decoded = decode(b"# Caf\xe9 \x93quoted\x94\n");
+        assert_eq!(decoded.text, "# Caf\u{e9} \u{201c}quoted\u{201d}\n");
+        assert_eq!(decoded.encoding, Some("windows-1252"));
+    }
+
+    #[test]
+    fn utf16_with_and_without_bom() {
+        for bom in [true, false] {
+            let bytes = utf16le("# Titel \u{fc}ber\n", bom);
+            assert!(is_utf16(&bytes), "bom: {bom}");
+            let decoded = decode(&bytes);
+            assert_eq!(decoded.text, "# Titel \u{fc}ber\n", "bom: {bom}");
+            assert_eq!(decoded.encoding, Some("UTF-16LE"), "bom: {bom}");
+        }
+        let be: Vec<u8> = "# Hi\n".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode(&be).encoding, Some("UTF-16BE"));
+    }
+
+    #[test]
+    fn read_utf8_refuses_other_encodings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("doc.md");
+
+        std::fs::write(&path, "\u{feff}- [ ] caf\u{e9}\n").unwrap();
+        assert_eq!(read_utf8(&path).unwrap(), "\u{feff}- [ ] caf\u{e9}\n");
+
+        std::fs::write(&path, b"- [ ] caf\xe9\n").unwrap();
+        let err = read_utf8(&path).unwrap_err();
+        assert!(
+            err.contains("windows-1252") && err.contains("re-save"),
+            "{err}"
+        );
+
+        std::fs::write(&path, utf16le("- [ ] milk\n", false)).unwrap();
+        assert!(read_utf8(&path).unwrap_err().contains("UTF-16LE"));
+    }
+
+    #[test]
+    fn binary_is_not_utf16() {
+        assert!(!is_utf16(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+    }
+}
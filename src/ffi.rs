@@ -0,0 +1,246 @@
+//! C ABI for the HTML renderer.
+//!
+//! Editor extensions and other non-Rust hosts can link the `mdmd` cdylib and
+//! call these functions to get byte-for-byte the same HTML that `mdmd serve`
+//! and `mdmd html` produce.  The matching declarations are in
+//! `include/mdmd.h`.
+//!
+//! All strings are NUL-terminated UTF-8.  Returned strings are owned by the
+//! caller and must be released with [`mdmd_string_free`].  Functions return
+//! NULL when a required argument is NULL or not valid UTF-8, or if rendering
+//! panics.
+
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::frontmatter;
+use crate::html::{self, PageShellContext, RenderTarget};
+use crate::i18n::Locale;
+
+/// `target` value selecting [`RenderTarget::Serve`]: local links are
+/// rewritten to root-relative hrefs under `serve_root`.
+pub const MDMD_TARGET_SERVE: c_int = 0;
+/// `target` value selecting [`RenderTarget::Html`]: links are kept as
+/// authored and assets are inlined into page shells.
+pub const MDMD_TARGET_HTML: c_int = 1;
+
+/// Borrow a C string as `&str`, treating NULL as `default`.
+///
+/// # Safety
+/// `ptr` must be NULL or point to a NUL-terminated string that outlives the
+/// returned borrow.
+unsafe fn str_arg(ptr: *const c_char, default: Option<&str>) -> Option<&str> {
+    if ptr.is_null() {
+        return default;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+fn target_from(target: c_int) -> Option<RenderTarget> {
+    match target {
+        MDMD_TARGET_SERVE => Some(RenderTarget::Serve),
+        MDMD_TARGET_HTML => Some(RenderTarget::Html),
+        _ => None,
+    }
+}
+
+/// Run `f` and hand its output to the caller, mapping failures to NULL.
+fn into_c_string(f: impl FnOnce() -> Option<String>) -> *mut c_char {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Some(s)) => CString::new(s).map_or(std::ptr::null_mut(), CString::into_raw),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Render markdown to an HTML fragment with heading anchors, as it appears
+/// inside a served page's `<main>` element.
+///
+/// `file_path` and `serve_root` may be NULL; they are only used to rewrite
+/// relative links for [`MDMD_TARGET_SERVE`].
+///
+/// # Safety
+/// Each non-NULL pointer must reference a NUL-terminated string that stays
+/// valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn mdmd_render_markdown(
+    markdown: *const c_char,
+    file_path: *const c_char,
+    serve_root: *const c_char,
+    target: c_int,
+) -> *mut c_char {
+    let args = (
+        str_arg(markdown, None),
+        str_arg(file_path, Some("")),
+        str_arg(serve_root, Some("")),
+        target_from(target),
+    );
+    into_c_string(|| {
+        let (Some(markdown), Some(file_path), Some(serve_root), Some(target)) = args else {
+            return None;
+        };
+        let (body, headings) = html::render_markdown(
+            markdown,
+            Path::new(file_path),
+            Path::new(serve_root),
+            target,
+            false,
+        );
+        Some(html::inject_heading_ids(&body, &headings))
+    })
+}
+
+/// Render markdown to a complete HTML page: frontmatter panel, TOC sidebar,
+/// and content, exactly as `mdmd serve` (`MDMD_TARGET_SERVE`) or
+/// `mdmd html` (`MDMD_TARGET_HTML`) would emit it, without backlinks.
+///
+/// # Safety
+/// Each non-NULL pointer must reference a NUL-terminated string that stays
+/// valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn mdmd_render_page(
+    markdown: *const c_char,
+    file_path: *const c_char,
+    serve_root: *const c_char,
+    target: c_int,
+    full_width: bool,
+) -> *mut c_char {
+    let args = (
+        str_arg(markdown, None),
+        str_arg(file_path, Some("")),
+        str_arg(serve_root, Some("")),
+        target_from(target),
+    );
+    into_c_string(|| {
+        let (Some(markdown), Some(file_path), Some(serve_root), Some(target)) = args else {
+            return None;
+        };
+        let (file_path, serve_root) = (Path::new(file_path), Path::new(serve_root));
+        let extracted = frontmatter::extract(markdown);
+        let (body, headings) = html::render_markdown(
+            extracted.render_body.as_ref(),
+            file_path,
+            serve_root,
+            target,
+            false,
+        );
+        let ctx = PageShellContext {
+            frontmatter: extracted.meta.as_ref(),
+            backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width,
+            locale: Locale::En,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: html::RenderOptions::default(),
+        };
+        Some(html::build_page_shell(
+            &body, &headings, file_path, &ctx, target,
+        ))
+    })
+}
+
+/// Release a string returned by this library.  NULL is ignored.
+///
+/// # Safety
+/// `s` must be NULL or a pointer returned by an `mdmd_render_*` function
+/// that has not already been freed.
+#[no_mangle]
+pub unsafe extern "C" fn mdmd_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(f: impl FnOnce() -> *mut c_char) -> Option<String> {
+        let ptr = f();
+        if ptr.is_null() {
+            return None;
+        }
+        let s = unsafe { CStr::from_ptr(ptr) }.to_str().unwrap().to_owned();
+        unsafe { mdmd_string_free(ptr) };
+        Some(s)
+    }
+
+    #[test]
+    fn fragment_matches_library_rendering() {
+        let md = CString::new("# Title\n\n[next](next.md)\n").unwrap();
+        let html = call(|| unsafe {
+            mdmd_render_markdown(
+                md.as_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+                MDMD_TARGET_HTML,
+            )
+        })
+        .unwrap();
+        assert_eq!(html, crate::render_to_html("# Title\n\n[next](next.md)\n"));
+        assert!(html.contains("<h1 id=\"title\">"));
+    }
+
+    #[test]
+    fn serve_target_rewrites_links_relative_to_root() {
+        let md = CString::new("[b](b.md)\n").unwrap();
+        let file = CString::new("/root/docs/a.md").unwrap();
+        let root = CString::new("/root").unwrap();
+        let html = call(|| unsafe {
+            mdmd_render_markdown(md.as_ptr(), file.as_ptr(), root.as_ptr(), MDMD_TARGET_SERVE)
+        })
+        .unwrap();
+        assert!(html.contains("href=\"/docs/b.md\""), "got: {html}");
+    }
+
+    #[test]
+    fn page_includes_shell_and_frontmatter() {
+        let md = CString::new("---\ntitle: Hello\n---\n# Body\n").unwrap();
+        let page = call(|| unsafe {
+            mdmd_render_page(
+                md.as_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+                MDMD_TARGET_HTML,
+                false,
+            )
+        })
+        .unwrap();
+        assert!(page.starts_with("<!DOCTYPE html>"));
+        assert!(page.contains("Hello"));
+        assert!(page.contains("<h1 id=\"body\">"));
+    }
+
+    #[test]
+    fn invalid_arguments_return_null() {
+        let md = CString::new("# x").unwrap();
+        let bad_utf8 = CString::new(vec![0xff, 0xfe]).unwrap();
+        unsafe {
+            assert!(mdmd_render_markdown(
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+                MDMD_TARGET_HTML
+            )
+            .is_null());
+            assert!(mdmd_render_markdown(
+                bad_utf8.as_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+                MDMD_TARGET_HTML
+            )
+            .is_null());
+            assert!(
+                mdmd_render_markdown(md.as_ptr(), std::ptr::null(), std::ptr::null(), 7).is_null()
+            );
+            mdmd_string_free(std::ptr::null_mut());
+        }
+    }
+}
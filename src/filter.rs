@@ -0,0 +1,299 @@
+//! External filter commands (`--filter STAGE=CMD`).
+//!
+//! A filter reads a document on stdin and writes its replacement to stdout,
+//! like a pandoc filter.  `markdown` filters see the markdown body after
+//! includes and `{{var.name}}` substitution, before it is parsed; `html`
+//! filters see the rendered HTML fragment before it goes into the page
+//! shell.  Filters of a stage run in the order given, each fed the output
+//! of the one before.
+//!
+//! The command line is split on whitespace and run without a shell, in the
+//! document's directory.  Its environment is cleared except for
+//! [`PASSED_ENV`], plus `MDMD_FILE` (the document's path) and `MDMD_STAGE`.
+//! A filter that exits unsuccessfully, writes invalid UTF-8, or outlives
+//! its timeout is killed and its input is kept as if it had not run.
+//!
+//! ```
+//! use mdmd::filter::{parse_filter, Stage};
+//! let filter = parse_filter("html=tidy -q").unwrap();
+//! assert_eq!(filter.stage, Stage::Html);
+//! assert_eq!(filter.argv, ["tidy", "-q"]);
+//! ```
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Environment variables a filter inherits; everything else is cleared.
+pub const PASSED_ENV: &[&str] = &["PATH", "HOME", "LANG", "LC_ALL", "LC_CTYPE", "TMPDIR"];
+
+/// How long a filter may run when no timeout is given.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a filter is fed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// The markdown body, before parsing.
+    Markdown,
+    /// The rendered HTML fragment, before the page shell.
+    Html,
+}
+
+impl Stage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Stage::Markdown => "markdown",
+            Stage::Html => "html",
+        }
+    }
+}
+
+/// One external filter command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub stage: Stage,
+    /// Command line split on whitespace.
+    pub argv: Vec<String>,
+}
+
+/// The filters of a render, with the timeout each one gets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filters {
+    pub commands: Vec<Filter>,
+    pub timeout: Duration,
+}
+
+impl Default for Filters {
+    fn default() -> Filters {
+        Filters {
+            commands: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// Why a filter's output was not used.
+#[derive(Debug)]
+pub enum FilterError {
+    /// The command could not be started or talked to.
+    Io(std::io::Error),
+    /// The command exited unsuccessfully; carries its trimmed stderr.
+    Failed(String),
+    /// The command ran longer than the timeout and was killed.
+    TimedOut(Duration),
+    /// The command's output was not UTF-8.
+    InvalidOutput,
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::Io(e) => write!(f, "{e}"),
+            FilterError::Failed(stderr) if stderr.is_empty() => write!(f, "exited unsuccessfully"),
+            FilterError::Failed(stderr) => write!(f, "exited unsuccessfully: {stderr}"),
+            FilterError::TimedOut(timeout) => {
+                write!(f, "killed after {} ms", timeout.as_millis())
+            }
+            FilterError::InvalidOutput => write!(f, "wrote output that is not UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// Parse a `--filter` value of the form `STAGE=CMD`, where STAGE is
+/// `markdown` or `html`.
+pub fn parse_filter(spec: &str) -> Result<Filter, String> {
+    let (stage, command) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("expected STAGE=CMD, got '{spec}'"))?;
+    let stage = match stage.trim().to_ascii_lowercase().as_str() {
+        "markdown" | "md" => Stage::Markdown,
+        "html" => Stage::Html,
+        other => {
+            return Err(format!(
+                "unknown stage '{other}', expected markdown or html"
+            ))
+        }
+    };
+    let argv: Vec<String> = command.split_whitespace().map(str::to_owned).collect();
+    if argv.is_empty() {
+        return Err(format!("no command given in '{spec}'"));
+    }
+    Ok(Filter { stage, argv })
+}
+
+impl Filters {
+    /// `text` passed through each filter of `stage` in turn.  A failing
+    /// filter is skipped, and `report` is called with it and the error.
+    pub fn apply<'a>(
+        &self,
+        stage: Stage,
+        text: &'a str,
+        file: &Path,
+        mut report: impl FnMut(&Filter, &FilterError),
+    ) -> Cow<'a, str> {
+        let mut text = Cow::Borrowed(text);
+        for filter in self.commands.iter().filter(|f| f.stage == stage) {
+            match filter.run(&text, file, self.timeout) {
+                Ok(out) => text = Cow::Owned(out),
+                Err(e) => report(filter, &e),
+            }
+        }
+        text
+    }
+}
+
+impl Filter {
+    /// Run the filter on `input` for the document at `file`.
+    pub fn run(&self, input: &str, file: &Path, timeout: Duration) -> Result<String, FilterError> {
+        let mut command = Command::new(&self.argv[0]);
+        command
+            .args(&self.argv[1..])
+            .env_clear()
+            .envs(
+                PASSED_ENV
+                    .iter()
+                    .filter_map(|&k| Some((k, std::env::var_os(k)?))),
+            )
+            .env("MDMD_FILE", file)
+            .env("MDMD_STAGE", self.stage.as_str())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = file.parent().filter(|d| d.is_dir()) {
+            command.current_dir(dir);
+        }
+        let mut child = command.spawn().map_err(FilterError::Io)?;
+
+        // Feed stdin and drain both outputs on their own threads so a large
+        // document cannot deadlock on a full pipe.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let input = input.to_owned();
+        thread::spawn(move || stdin.write_all(input.as_bytes()));
+        let stdout = drain(child.stdout.take().expect("stdout is piped"));
+        let stderr = drain(child.stderr.take().expect("stderr is piped"));
+
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait().map_err(FilterError::Io)? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(FilterError::TimedOut(timeout));
+            }
+            thread::sleep(Duration::from_millis(5));
+        };
+
+        // A process the filter left behind may hold the pipes open; give up
+        // on them at the deadline rather than wait.
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+        let out = stdout
+            .recv_timeout(remaining())
+            .map_err(|_| FilterError::TimedOut(timeout))?
+            .map_err(FilterError::Io)?;
+        if !status.success() {
+            let err = stderr
+                .recv_timeout(remaining())
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            return Err(FilterError::Failed(
+                String::from_utf8_lossy(&err).trim().to_owned(),
+            ));
+        }
+        String::from_utf8(out).map_err(|_| FilterError::InvalidOutput)
+    }
+}
+
+/// Read `pipe` to its end on a new thread.
+fn drain(mut pipe: impl Read + Send + 'static) -> mpsc::Receiver<std::io::Result<Vec<u8>>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = tx.send(pipe.read_to_end(&mut buf).map(|_| buf));
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filters(specs: &[&str], timeout: Duration) -> Filters {
+        Filters {
+            commands: specs.iter().map(|s| parse_filter(s).unwrap()).collect(),
+            timeout,
+        }
+    }
+
+    #[test]
+    fn parses_filter_specs() {
+        assert_eq!(
+            parse_filter("markdown= pandoc -t gfm").unwrap(),
+            Filter {
+                stage: Stage::Markdown,
+                argv: vec!["pandoc".into(), "-t".into(), "gfm".into()],
+            }
+        );
+        assert!(parse_filter("pandoc").is_err());
+        assert!(parse_filter("pdf=pandoc").is_err());
+        assert!(parse_filter("html= ").is_err());
+    }
+
+    #[test]
+    fn chains_filters_of_one_stage_and_skips_failures() {
+        let f = filters(
+            &[
+                "markdown=tr a-z A-Z",
+                "html=rev",
+                "markdown=false",
+                "md=tr D X",
+            ],
+            DEFAULT_TIMEOUT,
+        );
+        let mut failed = Vec::new();
+        let out = f.apply(Stage::Markdown, "# doc\n", Path::new("a.md"), |f, _| {
+            failed.push(f.argv[0].clone())
+        });
+        assert_eq!(out, "# XOC\n");
+        assert_eq!(failed, ["false"]);
+    }
+
+    #[test]
+    fn kills_filters_that_run_too_long() {
+        let f = parse_filter("markdown=sleep 5").unwrap();
+        let started = Instant::now();
+        let err = f
+            .run("", Path::new("a.md"), Duration::from_millis(100))
+            .unwrap_err();
+        assert!(matches!(err, FilterError::TimedOut(_)), "{err}");
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[test]
+    fn filters_see_only_passed_environment() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("doc.md");
+        let out = parse_filter("html=env")
+            .unwrap()
+            .run("", &file, DEFAULT_TIMEOUT)
+            .unwrap();
+        let names: Vec<&str> = out.lines().filter_map(|l| l.split('=').next()).collect();
+        assert!(names.contains(&"MDMD_FILE") && names.contains(&"MDMD_STAGE"));
+        assert!(
+            names
+                .iter()
+                .all(|n| PASSED_ENV.contains(n) || n.starts_with("MDMD_")),
+            "{names:?}"
+        );
+        assert!(out.contains(&format!("MDMD_FILE={}", file.display())));
+    }
+}
@@ -0,0 +1,259 @@
+//! Fuzzy matching for the TUI's go-to-heading finder (`Ctrl-k`) and file
+//! finder (`Ctrl-p`).
+//!
+//! A heading or path matches when the query's characters appear in it in
+//! order, ignoring case, so `inst` finds "Installation" and `gsc` finds
+//! "Getting started: configuration".  Matches rank higher the more of the
+//! query lands on word starts and in runs of consecutive characters, and
+//! lower the later the first character and the wider the gaps.
+//!
+//! [`FileFinder`] lists the files for `Ctrl-p` on a background thread, so a
+//! large project does not freeze the viewer while it is walked.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+/// Longest gap between matched characters that costs more the wider it is.
+const MAX_GAP_PENALTY: usize = 10;
+
+/// Score of `text` for `query`, higher being better, or `None` when the
+/// query's characters do not all appear in `text` in order.  The best of
+/// all the ways to place the query is taken, so `cf` in "Config files"
+/// lands on the `f` of "files" rather than the one in "Config".
+pub fn score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().collect();
+    let gain = |i: usize| {
+        let word_start = i == 0 || !text[i - 1].is_alphanumeric();
+        if word_start {
+            9
+        } else {
+            1
+        }
+    };
+    let gap = |n: usize| n.min(MAX_GAP_PENALTY) as i64;
+    // best[i]: the best score of the query so far with its last character
+    // placed on text[i].
+    let mut best: Option<Vec<Option<i64>>> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let placed = |i: usize| same_letter(text[i], q);
+        best = Some(match &best {
+            None => (0..text.len())
+                .map(|i| placed(i).then(|| gain(i) - gap(i)))
+                .collect(),
+            Some(prev) => {
+                // Best of prev[..=i - 2 - MAX_GAP_PENALTY], all far enough
+                // back to cost the most.
+                let mut far: Option<i64> = None;
+                (0..text.len())
+                    .map(|i| {
+                        if let Some(p) = i.checked_sub(MAX_GAP_PENALTY + 2) {
+                            far = far.max(prev[p]);
+                        }
+                        if !placed(i) {
+                            return None;
+                        }
+                        let near = (i.saturating_sub(MAX_GAP_PENALTY + 1)..i).filter_map(|p| {
+                            let bonus = if p + 1 == i { 5 } else { -gap(i - p - 1) };
+                            Some(prev[p]? + bonus)
+                        });
+                        let before = near.chain(far.map(|f| f - MAX_GAP_PENALTY as i64)).max()?;
+                        Some(before + gain(i))
+                    })
+                    .collect()
+            }
+        });
+    }
+    match best {
+        None => Some(0),
+        Some(last) => last.into_iter().flatten().max(),
+    }
+}
+
+/// Indices of the `texts` matching `query`, best first; equal scores keep
+/// their order.  An empty query matches everything in order.
+pub fn rank<'a>(query: &str, texts: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut scored: Vec<(usize, i64)> = texts
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, text)| Some((i, score(query, text)?)))
+        .collect();
+    scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    scored.into_iter().map(|(i, _)| i).collect()
+}
+
+fn same_letter(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// State for the file finder (`Ctrl-p`): the markdown files under the
+/// project root and those matching the query typed so far.
+pub struct FileFinder {
+    pub root: PathBuf,
+    /// Paths relative to `root`, sorted.
+    pub files: Vec<String>,
+    pub query: String,
+    /// Indices into `files` of the matching paths, best first.
+    pub matches: Vec<usize>,
+    /// Index into `matches` of the selected path.
+    pub selected: usize,
+    /// The listing of `files`, while it is still being made.
+    listing: Option<mpsc::Receiver<Vec<String>>>,
+}
+
+impl FileFinder {
+    /// Start listing the markdown files of the project holding `file`;
+    /// [`poll`](Self::poll) takes them once they are listed.
+    pub fn open(file: &Path) -> FileFinder {
+        let root = crate::breadcrumbs::project_root(file);
+        let (tx, rx) = mpsc::channel();
+        let walked = root.clone();
+        thread::spawn(move || {
+            let files = crate::replace::collect_markdown_files(&walked)
+                .iter()
+                .filter_map(|path| path.strip_prefix(&walked).ok())
+                .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+                .collect();
+            // The finder may have been closed meanwhile.
+            let _ = tx.send(files);
+        });
+        FileFinder {
+            listing: Some(rx),
+            ..FileFinder::new(root, Vec::new())
+        }
+    }
+
+    /// A finder over `files`, paths relative to `root`.
+    pub fn new(root: PathBuf, files: Vec<String>) -> FileFinder {
+        let mut finder = FileFinder {
+            root,
+            files,
+            query: String::new(),
+            matches: Vec::new(),
+            selected: 0,
+            listing: None,
+        };
+        finder.refilter();
+        finder
+    }
+
+    /// Whether the files are still being listed.
+    pub fn listing(&self) -> bool {
+        self.listing.is_some()
+    }
+
+    /// Take the files if they have been listed, matching them against the
+    /// query typed meanwhile.  Returns whether they changed.
+    pub fn poll(&mut self) -> bool {
+        let Some(listing) = &self.listing else {
+            return false;
+        };
+        match listing.try_recv() {
+            Ok(files) => self.files = files,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            // The walk died; there is nothing to list.
+            Err(mpsc::TryRecvError::Disconnected) => {}
+        }
+        self.listing = None;
+        self.refilter();
+        true
+    }
+
+    /// Match the files against the query again and select the best match.
+    pub fn refilter(&mut self) {
+        self.matches = rank(&self.query, self.files.iter().map(String::as_str));
+        self.selected = 0;
+    }
+
+    /// Path of the selected file; `None` when nothing matches.
+    pub fn target(&self) -> Option<PathBuf> {
+        let &i = self.matches.get(self.selected)?;
+        Some(self.root.join(&self.files[i]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn characters_must_appear_in_order() {
+        assert!(score("inst", "Installation").is_some());
+        assert!(score("gsc", "Getting started: configuration").is_some());
+        assert!(score("", "Anything").is_some());
+        assert!(score("tsni", "Installation").is_none());
+        assert!(score("x", "Installation").is_none());
+    }
+
+    #[test]
+    fn word_starts_and_runs_rank_first() {
+        let headings = [
+            "Contributing",
+            "Configuration",
+            "API reference",
+            "Config files",
+        ];
+        assert_eq!(rank("conf", headings), [1, 3]);
+        assert_eq!(rank("cf", headings), [3, 1]);
+        assert_eq!(rank("f", headings), [3, 1, 2]);
+        assert_eq!(rank("", headings), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn path_segments_count_as_word_starts() {
+        let paths = ["docs/api/readme.md", "docs/guide.md", "README.md"];
+        assert_eq!(rank("dg", paths), [1]);
+        assert_eq!(rank("readme", paths), [2, 0]);
+        assert_eq!(rank("dar", paths), [0]);
+    }
+
+    #[test]
+    fn file_finder_targets_the_selected_match() {
+        let files = ["README.md", "docs/api/readme.md", "docs/guide.md"];
+        let mut finder = FileFinder::new(
+            PathBuf::from("/project"),
+            files.iter().map(|f| f.to_string()).collect(),
+        );
+        assert_eq!(finder.matches, [0, 1, 2]);
+        assert_eq!(finder.target(), Some(PathBuf::from("/project/README.md")));
+
+        finder.query = "dg".to_owned();
+        finder.refilter();
+        assert_eq!(finder.matches, [2]);
+        assert_eq!(
+            finder.target(),
+            Some(PathBuf::from("/project/docs/guide.md"))
+        );
+
+        finder.query = "readme".to_owned();
+        finder.refilter();
+        finder.selected = 1;
+        assert_eq!(
+            finder.target(),
+            Some(PathBuf::from("/project/docs/api/readme.md"))
+        );
+
+        finder.query = "zzz".to_owned();
+        finder.refilter();
+        assert_eq!(finder.target(), None);
+    }
+
+    #[test]
+    fn file_finder_lists_in_the_background() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join(".git")).unwrap();
+        std::fs::create_dir(dir.path().join("docs")).unwrap();
+        std::fs::write(dir.path().join("docs/guide.md"), "# Guide\n").unwrap();
+        let file = dir.path().join("README.md");
+        std::fs::write(&file, "# Readme\n").unwrap();
+
+        let mut finder = FileFinder::open(&file);
+        finder.query = "guide".to_owned();
+        while !finder.poll() {
+            thread::sleep(std::time::Duration::from_millis(5));
+        }
+        assert!(!finder.listing());
+        assert_eq!(finder.files, ["README.md", "docs/guide.md"]);
+        assert_eq!(finder.target(), Some(dir.path().join("docs/guide.md")));
+    }
+}
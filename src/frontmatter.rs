@@ -19,6 +19,30 @@ pub struct FrontmatterMeta {
     pub title: Option<String>,
 }
 
+impl FrontmatterMeta {
+    /// Top-level string value of field `key`, trimmed, if non-empty.
+    pub fn scalar(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|field| field.key == key)
+            .and_then(|field| match &field.value {
+                MetaValue::Scalar(text) => Some(text.trim()),
+                _ => None,
+            })
+            .filter(|text| !text.is_empty())
+    }
+
+    /// Whether the page is marked `draft: true`.
+    pub fn is_draft(&self) -> bool {
+        self.scalar("draft") == Some("true")
+    }
+}
+
+/// Whether `source` has front matter marking it `draft: true`.
+pub fn is_draft(source: &str) -> bool {
+    extract(source).meta.is_some_and(|meta| meta.is_draft())
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct FrontmatterField {
     pub key: String,
@@ -34,6 +58,8 @@ pub enum MetaValue {
 }
 
 pub fn extract(source: &str) -> ExtractResult<'_> {
+    // A byte order mark would hide the opening `---`.
+    let source = source.strip_prefix('\u{feff}').unwrap_or(source);
     let Some((first_line, after_open)) = logical_line_at(source, 0) else {
         return unchanged(source);
     };
@@ -449,6 +475,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn draft_flag_is_read_from_frontmatter() {
+        eprintln!("scenario: draft flag");
+        assert!(is_draft("---\ndraft: true\n---\n# Wip\n"));
+        assert!(!is_draft("---\ndraft: false\n---\n"));
+        assert!(!is_draft("---\ntitle: Done\n---\n"));
+        assert!(!is_draft("draft: true\n"));
+    }
+
     #[test]
     fn non_string_title_is_ignored() {
         eprintln!("scenario: non-string title");
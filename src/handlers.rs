@@ -0,0 +1,279 @@
+//! External handlers for links to local non-markdown files.
+//!
+//! When a followed link points at a PDF, image, source file, or anything else
+//! that mdmd cannot render itself, the file's extension is looked up in a
+//! [`HandlerTable`].  User rules from `--open-with EXT=CMD` take precedence
+//! over the built-in defaults: common source and text files open in
+//! `$EDITOR`, and everything else goes to the platform opener
+//! (`xdg-open` / `open` / `cmd /C start`).
+//!
+//! A command prefixed with `!` runs in the terminal: the TUI is suspended
+//! until it exits, as needed for editors and pagers.  Other commands are
+//! spawned detached.  The file path replaces a `{}` argument when one is
+//! present and is otherwise appended.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Extensions opened in `$EDITOR` when no user rule matches.
+const EDITOR_EXTENSIONS: &[&str] = &[
+    "c", "cc", "cpp", "css", "go", "h", "hpp", "java", "js", "json", "jsx", "lua", "py", "rb",
+    "rs", "sh", "toml", "ts", "tsx", "txt", "yaml", "yml",
+];
+
+/// How a handler command interacts with the terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandlerMode {
+    /// Spawned in the background; the TUI keeps running.
+    Detached,
+    /// Runs in the foreground with the TUI suspended until it exits.
+    Terminal,
+}
+
+/// A command that opens files, e.g. `feh` or `!$EDITOR`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Handler {
+    /// Command line split on whitespace; `$VAR` words are expanded at
+    /// dispatch time.
+    pub argv: Vec<String>,
+    pub mode: HandlerMode,
+}
+
+/// A user rule mapping one or more extensions to a handler.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerRule {
+    /// Lowercase extensions without the leading dot.
+    pub extensions: Vec<String>,
+    pub handler: Handler,
+}
+
+/// Ordered handler lookup for local files.
+#[derive(Debug, Clone, Default)]
+pub struct HandlerTable {
+    rules: Vec<HandlerRule>,
+}
+
+impl Handler {
+    /// Parse a command string, where a leading `!` selects
+    /// [`HandlerMode::Terminal`].
+    pub fn parse(command: &str) -> Option<Self> {
+        let (mode, command) = match command.trim().strip_prefix('!') {
+            Some(rest) => (HandlerMode::Terminal, rest),
+            None => (HandlerMode::Detached, command),
+        };
+        let argv: Vec<String> = command.split_whitespace().map(str::to_owned).collect();
+        if argv.is_empty() {
+            return None;
+        }
+        Some(Self { argv, mode })
+    }
+
+    /// Build the process invocation for `file`, expanding `$VAR` words with
+    /// `env` and substituting `{}` with the file path.
+    ///
+    /// Returns `None` when the program word expands to nothing (e.g. an unset
+    /// `$EDITOR` with no fallback).
+    pub fn command_line(
+        &self,
+        file: &Path,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<Vec<String>> {
+        let file = file.to_string_lossy().into_owned();
+        let mut substituted = false;
+        let mut out = Vec::with_capacity(self.argv.len() + 1);
+        for (i, word) in self.argv.iter().enumerate() {
+            if i == 0 && word == "{}" {
+                return None;
+            }
+            if word == "{}" {
+                out.push(file.clone());
+                substituted = true;
+            } else if let Some(var) = word.strip_prefix('$') {
+                // `$EDITOR` may itself carry arguments, e.g. "code --wait".
+                let value = env(var).unwrap_or_default();
+                out.extend(value.split_whitespace().map(str::to_owned));
+            } else {
+                out.push(word.clone());
+            }
+            if out.is_empty() {
+                return None;
+            }
+        }
+        if !substituted {
+            out.push(file);
+        }
+        Some(out)
+    }
+}
+
+/// Parse an `--open-with` value of the form `EXT[,EXT...]=CMD`.
+pub fn parse_rule(spec: &str) -> Result<HandlerRule, String> {
+    let (exts, command) = spec
+        .split_once('=')
+        .ok_or_else(|| format!("expected EXT=CMD, got '{spec}'"))?;
+    let extensions: Vec<String> = exts
+        .split(',')
+        .map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect();
+    if extensions.is_empty() {
+        return Err(format!("no extension given in '{spec}'"));
+    }
+    let handler = Handler::parse(command).ok_or_else(|| format!("no command given in '{spec}'"))?;
+    Ok(HandlerRule {
+        extensions,
+        handler,
+    })
+}
+
+impl HandlerTable {
+    pub fn new(rules: Vec<HandlerRule>) -> Self {
+        Self { rules }
+    }
+
+    /// The handler for `file`: the last matching user rule, else the
+    /// built-in default for its extension.
+    pub fn handler_for(&self, file: &Path) -> Handler {
+        let ext = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if let Some(rule) = self
+            .rules
+            .iter()
+            .rev()
+            .find(|r| r.extensions.contains(&ext))
+        {
+            return rule.handler.clone();
+        }
+        if EDITOR_EXTENSIONS.contains(&ext.as_str()) {
+            Handler {
+                argv: vec!["$EDITOR".to_owned()],
+                mode: HandlerMode::Terminal,
+            }
+        } else {
+            Handler {
+                argv: system_opener().iter().map(|&w| w.to_owned()).collect(),
+                mode: HandlerMode::Detached,
+            }
+        }
+    }
+}
+
+/// The platform's default "open with associated application" command.
+fn system_opener() -> &'static [&'static str] {
+    if cfg!(target_os = "macos") {
+        &["open"]
+    } else if cfg!(windows) {
+        // `start` is a `cmd` builtin that takes its first quoted argument as
+        // the window title.
+        &["cmd", "/C", "start", ""]
+    } else {
+        &["xdg-open"]
+    }
+}
+
+/// Look up an environment variable for handler expansion, falling back to
+/// `vi` for an unset or empty `$EDITOR`.
+pub fn handler_env(var: &str) -> Option<String> {
+    match std::env::var(var) {
+        Ok(v) if !v.trim().is_empty() => Some(v),
+        _ if var == "EDITOR" => Some("vi".to_owned()),
+        _ => None,
+    }
+}
+
+/// Spawn a detached handler process with its stdio discarded.
+pub fn spawn_detached(argv: &[String]) -> std::io::Result<()> {
+    Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(|_| ())
+}
+
+/// Run a terminal handler in the foreground and wait for it to exit.
+pub fn run_foreground(argv: &[String]) -> std::io::Result<std::process::ExitStatus> {
+    Command::new(&argv[0]).args(&argv[1..]).status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn parse_rule_accepts_extension_lists_and_terminal_prefix() {
+        let rule = parse_rule(".PNG,jpg=feh --scale-down").unwrap();
+        assert_eq!(rule.extensions, vec!["png", "jpg"]);
+        assert_eq!(rule.handler.argv, vec!["feh", "--scale-down"]);
+        assert_eq!(rule.handler.mode, HandlerMode::Detached);
+
+        let rule = parse_rule("log=!less +G").unwrap();
+        assert_eq!(rule.handler.mode, HandlerMode::Terminal);
+        assert_eq!(rule.handler.argv, vec!["less", "+G"]);
+    }
+
+    #[test]
+    fn parse_rule_rejects_malformed_specs() {
+        assert!(parse_rule("feh").is_err());
+        assert!(parse_rule("=feh").is_err());
+        assert!(parse_rule("png=").is_err());
+        assert!(parse_rule("png=!").is_err());
+    }
+
+    #[test]
+    fn user_rules_override_defaults() {
+        let table = HandlerTable::new(vec![
+            parse_rule("png=feh").unwrap(),
+            parse_rule("png,rs=!nvim").unwrap(),
+        ]);
+        assert_eq!(table.handler_for(Path::new("a.PNG")).argv, vec!["nvim"]);
+        assert_eq!(table.handler_for(Path::new("a.rs")).argv, vec!["nvim"]);
+    }
+
+    #[test]
+    fn defaults_use_editor_for_source_and_opener_otherwise() {
+        let table = HandlerTable::default();
+        let code = table.handler_for(Path::new("src/main.rs"));
+        assert_eq!(code.argv, vec!["$EDITOR"]);
+        assert_eq!(code.mode, HandlerMode::Terminal);
+
+        let pdf = table.handler_for(Path::new("paper.pdf"));
+        assert_eq!(pdf.argv, system_opener());
+        assert_eq!(pdf.mode, HandlerMode::Detached);
+    }
+
+    #[test]
+    fn command_line_substitutes_placeholder_or_appends_path() {
+        let file = Path::new("/docs/a b.pdf");
+        let appended = Handler::parse("zathura --fork").unwrap();
+        assert_eq!(
+            appended.command_line(file, no_env).unwrap(),
+            vec!["zathura", "--fork", "/docs/a b.pdf"]
+        );
+
+        let placeholder = Handler::parse("open -a Preview {} --fresh").unwrap();
+        assert_eq!(
+            placeholder.command_line(file, no_env).unwrap(),
+            vec!["open", "-a", "Preview", "/docs/a b.pdf", "--fresh"]
+        );
+    }
+
+    #[test]
+    fn command_line_expands_variables() {
+        let handler = Handler::parse("!$EDITOR").unwrap();
+        let env = |v: &str| (v == "EDITOR").then(|| "code --wait".to_owned());
+        assert_eq!(
+            handler.command_line(Path::new("x.rs"), env).unwrap(),
+            vec!["code", "--wait", "x.rs"]
+        );
+        assert_eq!(handler.command_line(Path::new("x.rs"), no_env), None);
+    }
+}
@@ -0,0 +1,413 @@
+//! Extra `<head>` tags for pages served by `mdmd serve` and exported by
+//! `mdmd html`.
+//!
+//! `--inject-head FILE` adds the file's tags to every page, say analytics on
+//! an internal docs server or a web font.  The file may hold only `<meta>`,
+//! `<link>`, `<script>`, `<style>`, and `<noscript>` elements and comments;
+//! anything else (text, `<title>`, `<base>`, a closing `</head>`) is refused
+//! when the file is loaded, so a stray tag cannot break or retarget the
+//! page.  Within those elements the file is trusted: its scripts run as
+//! written.
+//!
+//! With `--allow-page-head`, a page's frontmatter adds tags of its own:
+//!
+//! ```yaml
+//! scripts: [https://cdn.example.com/chart.js, ./widget.js]
+//! head:
+//!   - <link rel="stylesheet" href="https://fonts.example.com/inter.css">
+//!   - <meta name="robots" content="noindex">
+//! ```
+//!
+//! Frontmatter is trusted less than the file.  `scripts:` takes URLs, each
+//! loaded with `<script src defer>`; `head:` takes only `<link>` and
+//! `<meta>` tags, rebuilt from their attributes with event handlers and
+//! `http-equiv` dropped.  URLs with a scheme other than `http` or `https`
+//! (`javascript:`, `data:`) are dropped from both.  Without the flag these
+//! fields are ignored.
+//!
+//! ```
+//! use mdmd::head::PageHead;
+//! let head = PageHead {
+//!     inject: Some(mdmd::head::check("<meta name=\"team\" content=\"docs\">").unwrap()),
+//!     allow_page_head: false,
+//! };
+//! assert_eq!(head.tags(None), "<meta name=\"team\" content=\"docs\">\n");
+//! assert!(mdmd::head::check("</head><body>").is_err());
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use crate::frontmatter::{FrontmatterMeta, MetaValue};
+use crate::html::html_escape;
+
+/// Elements an injected file may hold.
+const FILE_ELEMENTS: &[&str] = &["link", "meta", "noscript", "script", "style"];
+
+/// Elements whose content is text up to their closing tag.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Elements frontmatter `head:` may add.
+const PAGE_ELEMENTS: &[&str] = &["link", "meta"];
+
+/// Attributes of frontmatter `head:` tags that hold a URL.
+const URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+/// Tags added to the `<head>` of every page.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageHead {
+    /// The `--inject-head` file's tags, as accepted by [`check`].
+    pub inject: Option<String>,
+    /// Add the tags of frontmatter `head:` and `scripts:` fields
+    /// (`--allow-page-head`).
+    pub allow_page_head: bool,
+}
+
+impl PageHead {
+    /// The tags for the page with `frontmatter`, one per line: the injected
+    /// file's, then the page's own when allowed.  Empty when there are none.
+    pub fn tags(&self, frontmatter: Option<&FrontmatterMeta>) -> String {
+        let mut out = String::new();
+        if let Some(inject) = self.inject.as_deref().map(str::trim) {
+            if !inject.is_empty() {
+                out.push_str(inject);
+                out.push('\n');
+            }
+        }
+        let Some(frontmatter) = frontmatter.filter(|_| self.allow_page_head) else {
+            return out;
+        };
+        for url in field_strings(frontmatter, "scripts") {
+            if safe_url(url) {
+                out.push_str(&format!(
+                    "<script src=\"{}\" defer></script>\n",
+                    html_escape(url)
+                ));
+            }
+        }
+        for tag in field_strings(frontmatter, "head") {
+            if let Some(tag) = page_tag(tag) {
+                out.push_str(&tag);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// Read the `--inject-head` file at `path` and [`check`] it.
+pub fn load(path: &Path) -> Result<String, String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    check(&source).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// `source` as the tags of an `--inject-head` file, or why it cannot be
+/// one: it holds something other than comments and the elements listed in
+/// the [module docs](self).
+pub fn check(source: &str) -> Result<String, String> {
+    let mut rest = source;
+    let mut open_noscript = 0usize;
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            break;
+        }
+        if let Some(after) = rest.strip_prefix("<!--") {
+            let end = after.find("-->").ok_or("unclosed comment")?;
+            rest = &after[end + "-->".len()..];
+            continue;
+        }
+        let Some(after) = rest.strip_prefix('<') else {
+            return Err(format!("text outside a tag: {}", excerpt(rest)));
+        };
+        let (closing, after) = match after.strip_prefix('/') {
+            Some(after) => (true, after),
+            None => (false, after),
+        };
+        let name_len = after
+            .find(|c: char| !c.is_ascii_alphanumeric())
+            .unwrap_or(after.len());
+        let name = after[..name_len].to_ascii_lowercase();
+        let shown = if closing {
+            format!("</{name}>")
+        } else {
+            format!("<{name}>")
+        };
+        if !FILE_ELEMENTS.contains(&name.as_str()) {
+            return Err(format!(
+                "{shown} is not allowed; only <meta>, <link>, <script>, <style>, and <noscript> are"
+            ));
+        }
+        let end = tag_end(after).ok_or_else(|| format!("unclosed tag {shown}"))?;
+        rest = &after[end + 1..];
+        if closing {
+            if name != "noscript" || open_noscript == 0 {
+                return Err(format!("{shown} closes nothing"));
+            }
+            open_noscript -= 1;
+        } else if name == "noscript" {
+            open_noscript += 1;
+        } else if RAW_TEXT_ELEMENTS.contains(&name.as_str()) {
+            // Offsets in the lowercased copy are those of `rest`.
+            let close = format!("</{name}");
+            let at = rest
+                .to_ascii_lowercase()
+                .find(&close)
+                .ok_or_else(|| format!("{shown} is never closed"))?;
+            let close_end =
+                tag_end(&rest[at..]).ok_or_else(|| format!("{shown} is never closed"))?;
+            rest = &rest[at + close_end + 1..];
+        }
+    }
+    if open_noscript > 0 {
+        return Err("<noscript> is never closed".to_owned());
+    }
+    Ok(source.to_owned())
+}
+
+/// The non-empty strings of frontmatter field `key`, a string or a list of
+/// them.
+fn field_strings<'a>(frontmatter: &'a FrontmatterMeta, key: &str) -> Vec<&'a str> {
+    let Some(field) = frontmatter.fields.iter().find(|field| field.key == key) else {
+        return Vec::new();
+    };
+    let values: Vec<&MetaValue> = match &field.value {
+        MetaValue::Sequence(items) => items.iter().collect(),
+        value => vec![value],
+    };
+    values
+        .into_iter()
+        .filter_map(|value| match value {
+            MetaValue::Scalar(text) => Some(text.trim()),
+            _ => None,
+        })
+        .filter(|text| !text.is_empty())
+        .collect()
+}
+
+/// Whether `url` is relative or uses `http` or `https`.
+fn safe_url(url: &str) -> bool {
+    let url = url.trim();
+    match url.find([':', '/', '?', '#']) {
+        Some(at) if url[at..].starts_with(':') => {
+            let scheme = url[..at].to_ascii_lowercase();
+            scheme == "http" || scheme == "https"
+        }
+        _ => true,
+    }
+}
+
+/// Frontmatter `head:` entry `source` rebuilt as a `<link>` or `<meta>`
+/// tag, or `None` when it is not one of those or does not parse.
+fn page_tag(source: &str) -> Option<String> {
+    let inner = source.trim().strip_prefix('<')?.strip_suffix('>')?;
+    let inner = inner.strip_suffix('/').unwrap_or(inner);
+    let name_len = inner
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(inner.len());
+    let name = inner[..name_len].to_ascii_lowercase();
+    if !PAGE_ELEMENTS.contains(&name.as_str()) {
+        return None;
+    }
+    let mut out = format!("<{name}");
+    for (attr, value) in attributes(&inner[name_len..])? {
+        if attr.starts_with("on") || attr == "http-equiv" {
+            continue;
+        }
+        if URL_ATTRIBUTES.contains(&attr.as_str()) && !value.as_deref().is_some_and(safe_url) {
+            continue;
+        }
+        out.push(' ');
+        out.push_str(&attr);
+        if let Some(value) = value {
+            out.push_str(&format!("=\"{}\"", html_escape(&value)));
+        }
+    }
+    out.push('>');
+    Some(out)
+}
+
+/// The attributes of a tag after its name, names lowercased and values
+/// unescaped, or `None` when they do not parse.
+fn attributes(mut rest: &str) -> Option<Vec<(String, Option<String>)>> {
+    let mut attrs = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some(attrs);
+        }
+        let name_len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')))
+            .unwrap_or(rest.len());
+        if name_len == 0 {
+            return None;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        rest = rest[name_len..].trim_start();
+        let mut value = None;
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (raw, remaining) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let end = after[1..].find(quote)? + 1;
+                    (&after[1..end], &after[end + 1..])
+                }
+                _ => {
+                    let end = after
+                        .find(|c: char| c.is_ascii_whitespace())
+                        .unwrap_or(after.len());
+                    if end == 0 {
+                        return None;
+                    }
+                    (&after[..end], &after[end..])
+                }
+            };
+            value = Some(unescape(raw));
+            rest = remaining;
+        }
+        attrs.push((name, value));
+    }
+}
+
+/// Undo the entity escaping [`html_escape`] does.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Offset of the `>` ending the tag `tag` starts, skipping quoted
+/// attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in tag.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return Some(i),
+            None => {}
+        }
+    }
+    None
+}
+
+/// The start of `text` for an error message.
+fn excerpt(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default();
+    match line.char_indices().nth(40) {
+        Some((at, _)) => format!("{}…", &line[..at]),
+        None => line.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter::FrontmatterField;
+
+    fn page(fields: Vec<(&str, MetaValue)>) -> FrontmatterMeta {
+        FrontmatterMeta {
+            fields: fields
+                .into_iter()
+                .map(|(key, value)| FrontmatterField {
+                    key: key.to_owned(),
+                    value,
+                })
+                .collect(),
+            title: None,
+        }
+    }
+
+    fn scalar(text: &str) -> MetaValue {
+        MetaValue::Scalar(text.to_owned())
+    }
+
+    #[test]
+    fn injected_files_hold_only_head_elements() {
+        let analytics = "<!-- analytics -->\n\
+<script async src=\"https://stats.example.com/a.js\"></script>\n\
+<script>window.track = (a) => a < 1 && '</b>';</script>\n\
+<link rel=\"preconnect\" href=\"https://fonts.example.com\">\n\
+<style>body > main { font-family: Inter; }</style>\n\
+<noscript><link rel=\"stylesheet\" href=\"/plain.css\"></noscript>\n";
+        assert_eq!(check(analytics).as_deref(), Ok(analytics));
+        for (source, why) in [
+            ("</head><body>", "</head> is not allowed"),
+            (
+                "<base href=\"https://evil.example\">",
+                "<base> is not allowed",
+            ),
+            ("<title>x</title>", "<title> is not allowed"),
+            ("<meta name=\"a\"> hello", "text outside a tag: hello"),
+            ("<script>alert(1)", "<script> is never closed"),
+            ("<noscript>", "<noscript> is never closed"),
+            ("</noscript>", "</noscript> closes nothing"),
+            ("<meta content=\"a>b", "unclosed tag <meta>"),
+        ] {
+            let err = check(source).unwrap_err();
+            assert!(err.starts_with(why), "{source}: {err}");
+        }
+    }
+
+    #[test]
+    fn page_tags_need_the_flag() {
+        let fm = page(vec![("scripts", scalar("/widget.js"))]);
+        let mut head = PageHead {
+            inject: Some("<meta name=\"site\" content=\"docs\">\n".to_owned()),
+            allow_page_head: false,
+        };
+        assert_eq!(
+            head.tags(Some(&fm)),
+            "<meta name=\"site\" content=\"docs\">\n"
+        );
+        head.allow_page_head = true;
+        assert_eq!(
+            head.tags(Some(&fm)),
+            "<meta name=\"site\" content=\"docs\">\n<script src=\"/widget.js\" defer></script>\n"
+        );
+        assert_eq!(PageHead::default().tags(Some(&fm)), "");
+    }
+
+    #[test]
+    fn page_tags_are_rebuilt_without_handlers_or_unsafe_urls() {
+        let fm = page(vec![
+            (
+                "scripts",
+                MetaValue::Sequence(vec![
+                    scalar("https://cdn.example.com/chart.js?v=1&x=2"),
+                    scalar("javascript:alert(1)"),
+                    scalar(" JavaScript:alert(1)"),
+                    scalar("data:text/javascript,alert(1)"),
+                ]),
+            ),
+            (
+                "head",
+                MetaValue::Sequence(vec![
+                    scalar("<link rel=stylesheet href='https://fonts.example.com/inter.css' onload=\"alert(1)\">"),
+                    scalar("<meta name=\"robots\" content=\"noindex\" />"),
+                    scalar("<meta http-equiv=\"refresh\" content=\"0;url=javascript:alert(1)\">"),
+                    scalar("<link rel=\"icon\" href=\"javascript:alert(1)\">"),
+                    scalar("<script src=\"/x.js\"></script>"),
+                    scalar("<meta name=\"a\"><script>alert(1)</script>"),
+                    scalar("<meta name=\"q\" content=\"a &amp; &quot;b&quot;\">"),
+                ]),
+            ),
+        ]);
+        let head = PageHead {
+            inject: None,
+            allow_page_head: true,
+        };
+        assert_eq!(
+            head.tags(Some(&fm)),
+            "<script src=\"https://cdn.example.com/chart.js?v=1&amp;x=2\" defer></script>\n\
+<link rel=\"stylesheet\" href=\"https://fonts.example.com/inter.css\">\n\
+<meta name=\"robots\" content=\"noindex\">\n\
+<meta content=\"0;url=javascript:alert(1)\">\n\
+<link rel=\"icon\">\n\
+<meta name=\"q\" content=\"a &amp; &quot;b&quot;\">\n"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+//! Visited-file history for the TUI viewer.
+//!
+//! Every document opened in a session (the initial file, followed links,
+//! back/forward navigation, jump list switches) is recorded with the time it
+//! was visited.  The `H` modal lists these newest first so any earlier file
+//! can be reopened directly.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::i18n::Locale;
+
+/// Maximum number of visits retained; the oldest entry is dropped first.
+const MAX_VISITS: usize = 200;
+
+/// A single visit to a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Visit {
+    /// Canonical path of the document.
+    pub file_path: PathBuf,
+    /// When the document was opened.
+    pub visited_at: SystemTime,
+}
+
+/// Chronological list of visited documents, oldest first.
+#[derive(Debug, Default)]
+pub struct VisitHistory {
+    entries: Vec<Visit>,
+}
+
+impl VisitHistory {
+    /// Rebuild a history from `visits`, oldest first, keeping the newest
+    /// [`MAX_VISITS`].
+    pub fn from_visits(mut visits: Vec<Visit>) -> Self {
+        let excess = visits.len().saturating_sub(MAX_VISITS);
+        visits.drain(..excess);
+        Self { entries: visits }
+    }
+
+    /// Visits in the order they happened, oldest first.
+    pub fn visits(&self) -> &[Visit] {
+        &self.entries
+    }
+
+    /// Record a visit to `path` at `at`.
+    ///
+    /// Revisiting the most recent file (e.g. reloading it) only refreshes its
+    /// timestamp rather than adding a duplicate row.
+    pub fn record(&mut self, path: &Path, at: SystemTime) {
+        if let Some(last) = self.entries.last_mut() {
+            if last.file_path == path {
+                last.visited_at = at;
+                return;
+            }
+        }
+        self.entries.push(Visit {
+            file_path: path.to_path_buf(),
+            visited_at: at,
+        });
+        if self.entries.len() > MAX_VISITS {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Visits ordered newest first, as shown in the history modal.
+    pub fn newest_first(&self) -> impl Iterator<Item = &Visit> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The visit at `idx` in newest-first order.
+    pub fn get_newest_first(&self, idx: usize) -> Option<&Visit> {
+        self.entries
+            .len()
+            .checked_sub(idx + 1)
+            .map(|i| &self.entries[i])
+    }
+}
+
+/// Human-readable age of a visit, e.g. `just now`, `42s ago`, `3m ago`.
+pub fn format_age(elapsed: Duration, locale: Locale) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 5 {
+        return locale.tr("just now").to_owned();
+    }
+    let age = if secs < 60 {
+        format!("{secs}s")
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else if secs < 86_400 {
+        format!("{}h", secs / 3600)
+    } else {
+        format!("{}d", secs / 86_400)
+    };
+    locale.trf("{age} ago", &[("age", &age)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn newest_first_reverses_visit_order() {
+        let mut h = VisitHistory::default();
+        h.record(Path::new("/a.md"), at(1));
+        h.record(Path::new("/b.md"), at(2));
+        let paths: Vec<_> = h.newest_first().map(|v| v.file_path.clone()).collect();
+        assert_eq!(paths, vec![PathBuf::from("/b.md"), PathBuf::from("/a.md")]);
+        assert_eq!(h.get_newest_first(1).unwrap().file_path, Path::new("/a.md"));
+        assert!(h.get_newest_first(2).is_none());
+    }
+
+    #[test]
+    fn revisiting_latest_refreshes_timestamp() {
+        let mut h = VisitHistory::default();
+        h.record(Path::new("/a.md"), at(1));
+        h.record(Path::new("/a.md"), at(5));
+        assert_eq!(h.len(), 1);
+        assert_eq!(h.get_newest_first(0).unwrap().visited_at, at(5));
+
+        // Non-consecutive revisits are kept as separate rows.
+        h.record(Path::new("/b.md"), at(6));
+        h.record(Path::new("/a.md"), at(7));
+        assert_eq!(h.len(), 3);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut h = VisitHistory::default();
+        for i in 0..(MAX_VISITS + 10) {
+            h.record(&PathBuf::from(format!("/{i}.md")), at(i as u64));
+        }
+        assert_eq!(h.len(), MAX_VISITS);
+        assert_eq!(
+            h.newest_first().last().unwrap().file_path,
+            PathBuf::from("/10.md")
+        );
+    }
+
+    #[test]
+    fn format_age_buckets() {
+        assert_eq!(format_age(Duration::from_secs(2), Locale::En), "just now");
+        assert_eq!(format_age(Duration::from_secs(42), Locale::En), "42s ago");
+        assert_eq!(format_age(Duration::from_secs(180), Locale::En), "3m ago");
+        assert_eq!(format_age(Duration::from_secs(7200), Locale::En), "2h ago");
+        assert_eq!(
+            format_age(Duration::from_secs(3 * 86_400), Locale::En),
+            "3d ago"
+        );
+        assert_eq!(format_age(Duration::from_secs(180), Locale::De), "vor 3m");
+    }
+}
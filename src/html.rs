@@ -5,11 +5,25 @@
 //!
 //! The TUI parse/render path (`parse.rs`, `render.rs`) is not touched here.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use crate::backlinks::BacklinkRef;
+use crate::backlinks::{BacklinkRef, MentionRef};
+use crate::bidi;
+use crate::filter::{Filter, FilterError, Filters, Stage};
 use crate::frontmatter::{FrontmatterField, FrontmatterMeta, MetaValue};
+use crate::head::PageHead;
+use crate::i18n::Locale;
+use crate::normalize;
+use crate::numbering;
+use crate::outgoing::{LinkStatus, OutgoingLink};
+use crate::render::Layout;
+#[cfg(feature = "shortcodes")]
+use crate::shortcode::{self, Shortcodes};
+use crate::summary;
+use crate::template::{PageParts, PageTemplate};
+use crate::toc;
 
 use comrak::{
     arena_tree::NodeEdge,
@@ -47,6 +61,224 @@ pub struct HeadingEntry {
     /// The first occurrence of a heading slug is bare (e.g. `my-heading`);
     /// subsequent occurrences receive a numeric suffix (`my-heading-1`, `my-heading-2`).
     pub anchor_id: String,
+    /// Hierarchical number (`1.2`) when rendered with
+    /// [`RenderOptions::numbered_headings`].
+    pub number: Option<String>,
+}
+
+impl HeadingEntry {
+    /// Text shown for the heading in tables of contents: its number, if
+    /// any, then its text.
+    pub fn label(&self) -> String {
+        match &self.number {
+            Some(number) => format!("{number} {}", self.text),
+            None => self.text.clone(),
+        }
+    }
+}
+
+/// Everything that decides how markdown becomes a page, besides the
+/// [`RenderTarget`]: parser extensions, raw HTML handling, Mermaid, the base
+/// path links are rewritten against, transformations applied by
+/// [`render_markdown_with`], and the per-page view choices
+/// [`build_page_shell`] reads.
+///
+/// `mdmd serve` builds one in `run_serve` and overrides it per request from
+/// the query string; `mdmd html` builds one from its flags.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Markdown extensions enabled on top of CommonMark.
+    pub extensions: Extensions,
+    /// What happens to raw HTML in the markdown.
+    pub sanitize: Sanitize,
+    /// Whether `mermaid` code blocks become diagrams.
+    pub mermaid: MermaidMode,
+    /// Root directory local links are rewritten against in
+    /// [`RenderTarget::Serve`] output, yielding root-relative hrefs.  Serve
+    /// sets it to the canonical serve root; it is unused for
+    /// [`RenderTarget::Html`].
+    pub base_path: PathBuf,
+    /// Prefix headings and their TOC entries with hierarchical numbers
+    /// (`--numbered-headings`); see [`crate::numbering`].
+    pub numbered_headings: bool,
+    /// Levels to demote every heading by (`--heading-offset`), for content
+    /// embedded under a site's own headings.  Levels stop at 6.
+    pub heading_offset: u8,
+    /// Take the first H1 out of the body and use it as the page title
+    /// (`--title-from-h1`); see [`RenderedMarkdown::title`].
+    pub title_from_h1: bool,
+    /// Smart punctuation (`--smart`): curly quotes, en and em dashes for
+    /// `--` and `---`, and ellipses for `...`.  The TUI does the same
+    /// through [`crate::parse::parse_smart`].
+    pub smart_punctuation: bool,
+    /// Show the table of contents in the sidebar (`?toc=0` hides it).
+    pub sidebar_toc: bool,
+    /// Theme the page opens in regardless of the reader's saved choice
+    /// (`?theme=dark`).  `None` follows the saved choice or the system.
+    pub theme: Option<Theme>,
+    /// External commands the markdown and rendered HTML are piped through
+    /// (`--filter`); see [`crate::filter`].
+    pub filters: Filters,
+    /// Lua handlers for `{{< name >}}` shortcodes (`--shortcodes`); see
+    /// [`crate::shortcode`].  Needs the `shortcodes` feature.
+    #[cfg(feature = "shortcodes")]
+    pub shortcodes: Shortcodes,
+    /// Paragraph wrapping of the TUI (`--text-width`, `--justify`,
+    /// `--hyphenate`).  HTML output leaves wrapping to the browser.
+    pub layout: Layout,
+    /// How headings become anchor ids (`--slug-style`).  The TUI follows
+    /// fragment links with the same style.
+    pub slug_style: SlugStyle,
+    /// Extra `<head>` tags (`--inject-head`, `--allow-page-head`); see
+    /// [`crate::head`].
+    pub head: PageHead,
+    /// Template replacing the built-in page shell (`--template`); see
+    /// [`crate::template`].
+    pub template: Option<PageTemplate>,
+    /// Tag task checkboxes and record their lines in
+    /// [`RenderedMarkdown::task_lines`], for [`editable_task_checkboxes`]
+    /// (`--allow-edit`).
+    pub editable_tasks: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            extensions: Extensions::default(),
+            sanitize: Sanitize::Omit,
+            mermaid: MermaidMode::Diagrams,
+            base_path: PathBuf::new(),
+            numbered_headings: false,
+            heading_offset: 0,
+            title_from_h1: false,
+            smart_punctuation: false,
+            sidebar_toc: true,
+            theme: None,
+            filters: Filters::default(),
+            #[cfg(feature = "shortcodes")]
+            shortcodes: Shortcodes::default(),
+            layout: Layout::default(),
+            slug_style: SlugStyle::default(),
+            head: PageHead::default(),
+            template: None,
+            editable_tasks: false,
+        }
+    }
+}
+
+/// GFM extensions, all enabled by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extensions {
+    /// `~~strikethrough~~`.
+    pub strikethrough: bool,
+    /// Pipe tables.
+    pub table: bool,
+    /// Bare URLs and email addresses become links.
+    pub autolink: bool,
+    /// `- [ ]` / `- [x]` task list items.
+    pub tasklist: bool,
+}
+
+impl Default for Extensions {
+    fn default() -> Extensions {
+        Extensions {
+            strikethrough: true,
+            table: true,
+            autolink: true,
+            tasklist: true,
+        }
+    }
+}
+
+/// Handling of raw HTML in markdown.  It is never passed through (R3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitize {
+    /// Replace it with `<!-- raw HTML omitted -->`.
+    Omit,
+    /// Show it as text.
+    Escape,
+}
+
+/// Handling of fenced `mermaid` code blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MermaidMode {
+    /// Render placeholders that the page's Mermaid script turns into
+    /// diagrams (bd-2se).
+    Diagrams,
+    /// Leave them as code blocks and do not load Mermaid.
+    Code,
+}
+
+/// Colour theme of a rendered page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Light,
+    Dark,
+}
+
+impl Theme {
+    /// Parse `light` or `dark`.
+    pub fn parse(name: &str) -> Option<Theme> {
+        match name {
+            "light" => Some(Theme::Light),
+            "dark" => Some(Theme::Dark),
+            _ => None,
+        }
+    }
+
+    /// Lowercase name, as used in the `data-theme` attribute.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+        }
+    }
+}
+
+/// How heading text becomes an anchor id, so that fragment links written
+/// for another platform's renderer land on the same headings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SlugStyle {
+    /// mdmd's own: like [`SlugStyle::Gitlab`], with underscores also
+    /// becoming hyphens and hyphens trimmed from both ends.
+    #[default]
+    Mdmd,
+    /// GitHub: lowercase, punctuation dropped, and every space a hyphen,
+    /// so `A -- B` becomes `a----b`.
+    Github,
+    /// GitLab: as GitHub, with runs of hyphens collapsed into one.
+    Gitlab,
+    /// kramdown (Jekyll, GitHub Pages): ASCII letters, digits, and hyphens
+    /// from the first letter on, or `section` when none are left.
+    Kramdown,
+}
+
+impl SlugStyle {
+    /// Parse `github`, `gitlab`, or `kramdown`.
+    pub fn parse(name: &str) -> Option<SlugStyle> {
+        match name {
+            "github" => Some(SlugStyle::Github),
+            "gitlab" => Some(SlugStyle::Gitlab),
+            "kramdown" => Some(SlugStyle::Kramdown),
+            _ => None,
+        }
+    }
+}
+
+/// Output of [`render_markdown_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedMarkdown {
+    /// The HTML fragment.
+    pub html: String,
+    /// Headings left in the fragment, for TOC construction.
+    pub headings: Vec<HeadingEntry>,
+    /// Plain text of the first H1, when [`RenderOptions::title_from_h1`]
+    /// removed it from the fragment.
+    pub title: Option<String>,
+    /// With [`RenderOptions::editable_tasks`], the 1-based line in the input
+    /// of each task checkbox, indexed by its `data-task` attribute.  `None`
+    /// when a markdown filter rewrote the input.
+    pub task_lines: Vec<Option<usize>>,
 }
 
 /// Context passed to [`build_page_shell`] to avoid repeated signature churn as
@@ -64,6 +296,12 @@ pub struct PageShellContext<'a> {
     /// Inbound backlinks for this page from the startup index.
     /// Pass `&[]` for non-markdown pages, static assets, and error responses.
     pub backlinks: &'a [BacklinkRef],
+    /// Unlinked mentions of this page from the startup index, listed under
+    /// the backlinks.  Pass `&[]` where backlinks are not shown.
+    pub mentions: &'a [MentionRef],
+    /// This page's own links with their resolution status, listed after the
+    /// backlinks.  Pass `&[]` to omit the section.
+    pub outgoing: &'a [OutgoingLink],
     /// Unix timestamp (seconds) of the file's last modification, for freshness
     /// polling (bd-38z).  `None` disables change detection on this page.
     pub file_mtime_secs: Option<u64>,
@@ -75,46 +313,135 @@ pub struct PageShellContext<'a> {
     /// In serve mode this is controlled by localStorage; for html export this
     /// bakes the choice into the document. `false` = constrained width.
     pub full_width: bool,
+    /// Language of the shell's own labels and `<html lang>`.
+    pub locale: Locale,
+    /// Absolute URL of this page (e.g. `http://host:3333/docs/guide.md`), for
+    /// `<link rel="canonical">`, `og:url`, and resolving a relative
+    /// frontmatter `image`.  `None` omits the canonical link and `og:url`.
+    pub canonical_url: Option<&'a str>,
+    /// Site name (`--site-title`): replaces the `mdmd` suffix in `<title>`
+    /// and heads the page header.  `None` keeps the default suffix.
+    pub site_title: Option<&'a str>,
+    /// Whether the page may edit files (`serve --allow-edit`): adds the
+    /// buttons that link unlinked mentions.
+    pub allow_edit: bool,
+    /// Title taken out of the body by [`RenderOptions::title_from_h1`]:
+    /// shown in the page header and preferred over the headings for
+    /// `<title>`.  `None` leaves the body's headings as they are.
+    pub page_title: Option<&'a str>,
+    /// Name of the encoding the source was decoded from when it was not
+    /// UTF-8 (e.g. `windows-1252`), shown in a warning banner above the
+    /// content.  `None` omits the banner.
+    pub encoding: Option<&'a str>,
+    /// Options the page was rendered with; the shell honours
+    /// [`RenderOptions::sidebar_toc`] and [`RenderOptions::theme`].
+    pub render: RenderOptions,
 }
 
 // ---------------------------------------------------------------------------
 // Private helpers
 // ---------------------------------------------------------------------------
 
-/// Build comrak render options with GFM extensions and secure defaults.
+/// Build comrak render options from `render`, with secure defaults.
 ///
-/// - GFM extensions: strikethrough, tables, autolinks, task lists.
+/// - GFM extensions: strikethrough, tables, autolinks, task lists, as
+///   enabled in [`RenderOptions::extensions`].
 /// - R3 mitigation: `render.unsafe_ = false` (default) — raw HTML from input is
-///   stripped and replaced with `<!-- raw HTML omitted -->`.
-fn make_options() -> Options<'static> {
+///   stripped and replaced with `<!-- raw HTML omitted -->`, or escaped with
+///   [`Sanitize::Escape`].
+fn make_options(render: &RenderOptions) -> Options<'static> {
     let mut options = Options::default();
     // GFM extensions — only what is required (R10)
-    options.extension.strikethrough = true;
-    options.extension.table = true;
-    options.extension.autolink = true;
-    options.extension.tasklist = true;
+    options.extension.strikethrough = render.extensions.strikethrough;
+    options.extension.table = render.extensions.table;
+    options.extension.autolink = render.extensions.autolink;
+    options.extension.tasklist = render.extensions.tasklist;
+    options.parse.smart = render.smart_punctuation;
     // Explicit: raw HTML is unsafe — do not pass through (R3).
     // This is already the default (false), but stated clearly for auditability.
     options.render.unsafe_ = false;
+    options.render.escape = render.sanitize == Sanitize::Escape;
     options
 }
 
-/// Convert heading text to a URL-safe anchor slug.
+/// Convert heading text to an anchor slug in `style`.
 ///
-/// Algorithm: lowercase the text, map spaces/hyphens/underscores to `-`,
-/// strip all other non-alphanumeric characters, collapse consecutive hyphens,
-/// and trim leading/trailing hyphens.
-fn slugify(text: &str) -> String {
+/// The default algorithm: lowercase the text, map spaces/hyphens/underscores
+/// to `-`, strip all other non-alphanumeric characters, collapse consecutive
+/// hyphens, and trim leading/trailing hyphens.
+fn slugify(text: &str, style: SlugStyle) -> String {
     let mut slug = String::new();
-    for c in text.to_lowercase().chars() {
-        if c.is_alphanumeric() {
-            slug.push(c);
-        } else if (c == ' ' || c == '-' || c == '_') && !slug.ends_with('-') {
-            slug.push('-');
+    match style {
+        SlugStyle::Mdmd => {
+            for c in text.to_lowercase().chars() {
+                if c.is_alphanumeric() {
+                    slug.push(c);
+                } else if (c == ' ' || c == '-' || c == '_') && !slug.ends_with('-') {
+                    slug.push('-');
+                }
+                // all other characters are dropped
+            }
+            slug = slug.trim_matches('-').to_owned();
+        }
+        SlugStyle::Github | SlugStyle::Gitlab => {
+            for c in text.trim().to_lowercase().chars() {
+                let c = if c == ' ' { '-' } else { c };
+                if c == '-' && style == SlugStyle::Gitlab && slug.ends_with('-') {
+                    continue;
+                }
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    slug.push(c);
+                }
+            }
+        }
+        SlugStyle::Kramdown => {
+            let from_letter = text.trim_start_matches(|c: char| !c.is_ascii_alphabetic());
+            for c in from_letter.chars() {
+                match c {
+                    ' ' => slug.push('-'),
+                    'a'..='z' | '0'..='9' | '-' => slug.push(c),
+                    'A'..='Z' => slug.push(c.to_ascii_lowercase()),
+                    _ => {}
+                }
+            }
+            if slug.is_empty() {
+                slug.push_str("section");
+            }
+        }
+    }
+    slug
+}
+
+/// Anchor ids for the headings of one document, handed out in document
+/// order: the first heading with a slug gets it bare, later ones `slug-1`,
+/// `slug-2`, and so on (R4).
+#[derive(Debug, Default)]
+pub struct AnchorIds {
+    style: SlugStyle,
+    /// Maps base slug → number of times it has been seen so far.
+    seen: HashMap<String, usize>,
+}
+
+impl AnchorIds {
+    pub fn new(style: SlugStyle) -> AnchorIds {
+        AnchorIds {
+            style,
+            seen: HashMap::new(),
+        }
+    }
+
+    /// The anchor id of the next heading, whose text is `text`.
+    pub fn next(&mut self, text: &str) -> String {
+        let base_slug = slugify(text, self.style);
+        let count = self.seen.entry(base_slug.clone()).or_insert(0);
+        *count += 1;
+        match *count {
+            // First occurrence: bare slug.
+            1 => base_slug,
+            // Subsequent occurrences: slug-N where N starts at 1.
+            n => format!("{}-{}", base_slug, n - 1),
         }
-        // all other characters are dropped
     }
-    slug.trim_matches('-').to_owned()
 }
 
 /// Recursively collect plain-text content of a heading AST node.
@@ -136,7 +463,7 @@ fn collect_heading_text<'a>(node: &'a AstNode<'a>) -> String {
 // ---------------------------------------------------------------------------
 
 /// Minimal HTML entity escaping for text content and attribute values.
-fn html_escape(s: &str) -> String {
+pub(crate) fn html_escape(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for c in s.chars() {
         match c {
@@ -153,20 +480,120 @@ fn html_escape(s: &str) -> String {
 
 /// Inject `id` attributes into heading elements in the rendered HTML fragment.
 ///
-/// Performs sequential first-occurrence replacements: `<hN>` → `<hN id="...">`.
-/// Because `render.unsafe_ = false` is set, comrak will never emit bare `<hN>`
-/// tags from raw-HTML inputs in the markdown source, so replacements only hit
+/// Performs sequential first-occurrence replacements: `<hN>` → `<hN id="...">`,
+/// keeping the `dir="auto"` of documents with right-to-left text.  Because
+/// `render.unsafe_ = false` is set, comrak will never emit bare `<hN>` tags
+/// from raw-HTML inputs in the markdown source, so replacements only hit
 /// genuine heading elements generated from markdown headings.
-fn inject_heading_ids(html: &str, headings: &[HeadingEntry]) -> String {
+pub fn inject_heading_ids(html: &str, headings: &[HeadingEntry]) -> String {
     let mut result = html.to_owned();
     for heading in headings {
-        let tag = format!("<h{}>", heading.level);
-        let with_id = format!("<h{} id=\"{}\">", heading.level, heading.anchor_id);
-        result = result.replacen(&tag, &with_id, 1);
+        let level = heading.level;
+        let found = [format!("<h{level}>"), format!("<h{level} dir=\"auto\">")]
+            .into_iter()
+            .filter_map(|tag| result.find(&tag).map(|at| (at, tag)))
+            .min_by_key(|(at, _)| *at);
+        if let Some((at, tag)) = found {
+            let with_id = format!("{} id=\"{}\">", &tag[..tag.len() - 1], heading.anchor_id);
+            result.replace_range(at..at + tag.len(), &with_id);
+        }
     }
     result
 }
 
+/// `html` with `dir="auto"` on its paragraphs, headings, list items, block
+/// quotes, and table cells, so each follows the direction of its own text
+/// (see [`crate::bidi`]).
+fn mark_dir_auto(html: &str) -> String {
+    let mut out = html.to_owned();
+    for tag in ["p", "li", "blockquote", "h1", "h2", "h3", "h4", "h5", "h6"] {
+        out = out.replace(&format!("<{tag}>"), &format!("<{tag} dir=\"auto\">"));
+    }
+    for tag in ["td", "th"] {
+        out = out
+            .replace(&format!("<{tag}>"), &format!("<{tag} dir=\"auto\">"))
+            .replace(&format!("<{tag} align="), &format!("<{tag} dir=\"auto\" align="));
+    }
+    out
+}
+
+/// Opening of the task checkboxes comrak writes.  Raw HTML is never passed
+/// through, so every one in its output is a task item.
+const TASK_CHECKBOX: &str = "<input type=\"checkbox\"";
+
+/// `html`, formatted from `root`, with each task checkbox tagged
+/// `data-task="<index>"`, and the checkboxes' source lines by index;
+/// `lines_kept` says whether `root`'s lines are those of the input.
+fn tag_task_checkboxes<'a>(
+    html: &str,
+    root: &'a AstNode<'a>,
+    lines_kept: bool,
+) -> (String, Vec<Option<usize>>) {
+    let lines = root
+        .descendants()
+        .filter_map(|node| {
+            let data = node.data.borrow();
+            matches!(data.value, NodeValue::TaskItem(_)).then(|| data.sourcepos.start.line)
+        })
+        .map(|line| lines_kept.then_some(line))
+        .collect();
+    let mut out = String::with_capacity(html.len());
+    for (i, part) in html.split(TASK_CHECKBOX).enumerate() {
+        if i > 0 {
+            out.push_str(&format!("{TASK_CHECKBOX} data-task=\"{}\"", i - 1));
+        }
+        out.push_str(part);
+    }
+    (out, lines)
+}
+
+/// Make the task list checkboxes in `html`, rendered by
+/// [`render_markdown_with`] with [`RenderOptions::editable_tasks`],
+/// clickable for serve's `--allow-edit`.
+///
+/// Each tagged checkbox loses `disabled` and gains `class="task-toggle"` and
+/// a `data-line` attribute holding its 1-based line in the source file,
+/// which `source_line` maps from its entry in `task_lines`.  Checkboxes
+/// without a line there or that `source_line` maps to `None` (from an
+/// included file, or a filter's output) stay disabled, as do untagged ones
+/// that shortcodes or HTML filters added.
+pub fn editable_task_checkboxes(
+    html: &str,
+    task_lines: &[Option<usize>],
+    source_line: impl Fn(usize) -> Option<usize>,
+) -> String {
+    let open = format!("{TASK_CHECKBOX} data-task=\"");
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = rest.find(&open) {
+        let Some(len) = rest[start..].find("/>") else {
+            break;
+        };
+        let tag = &rest[start..start + len];
+        let checked = if tag.contains("checked=\"\"") {
+            " checked=\"\""
+        } else {
+            ""
+        };
+        let line = tag[open.len()..]
+            .split('"')
+            .next()
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| task_lines.get(index).copied().flatten())
+            .and_then(&source_line);
+        out.push_str(&rest[..start]);
+        match line {
+            Some(line) => out.push_str(&format!(
+                "{TASK_CHECKBOX} class=\"task-toggle\" data-line=\"{line}\"{checked} />"
+            )),
+            None => out.push_str(&format!("{TASK_CHECKBOX}{checked} disabled=\"\" />")),
+        }
+        rest = &rest[start + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
 /// Build the `<ul>…</ul>` HTML for the TOC sidebar.
 ///
 /// Returns an empty string when `headings` is empty (the sidebar will still be
@@ -179,7 +606,7 @@ fn build_toc_html(headings: &[HeadingEntry]) -> String {
     for heading in headings {
         let class = format!("toc-h{}", heading.level);
         let anchor = heading.anchor_id.as_str(); // anchor_id is already a URL-safe slug
-        let text = html_escape(&heading.text);
+        let text = html_escape(&heading.label());
         html.push_str(&format!(
             "<li class=\"{class}\"><a href=\"#{anchor}\">{text}</a></li>\n",
         ));
@@ -188,7 +615,7 @@ fn build_toc_html(headings: &[HeadingEntry]) -> String {
     html
 }
 
-fn render_frontmatter_html(frontmatter: Option<&FrontmatterMeta>) -> String {
+fn render_frontmatter_html(frontmatter: Option<&FrontmatterMeta>, locale: Locale) -> String {
     let Some(frontmatter) = frontmatter else {
         return String::new();
     };
@@ -202,9 +629,11 @@ fn render_frontmatter_html(frontmatter: Option<&FrontmatterMeta>) -> String {
         return String::new();
     }
 
+    let label = html_escape(locale.tr("Document metadata"));
+    let summary = html_escape(locale.tr("Properties"));
     format!(
-        "<details class=\"frontmatter-panel\" aria-label=\"Document metadata\">\n\
-<summary>Properties</summary>\n\
+        "<details class=\"frontmatter-panel\" aria-label=\"{label}\">\n\
+<summary>{summary}</summary>\n\
 <dl class=\"frontmatter-fields\">\n\
 {body}</dl>\n\
 </details>\n"
@@ -486,12 +915,55 @@ pub fn render_markdown(
     target: RenderTarget,
     verbose: bool,
 ) -> (String, Vec<HeadingEntry>) {
+    let render = RenderOptions {
+        base_path: serve_root.to_path_buf(),
+        ..RenderOptions::default()
+    };
+    let rendered = render_markdown_with(input, file_path, target, verbose, &render);
+    (rendered.html, rendered.headings)
+}
+
+/// [`render_markdown`] configured by `render`, whose
+/// [`base_path`](RenderOptions::base_path) takes the place of `serve_root`.
+pub fn render_markdown_with(
+    input: &str,
+    file_path: &Path,
+    target: RenderTarget,
+    verbose: bool,
+    render: &RenderOptions,
+) -> RenderedMarkdown {
+    let report = |filter: &Filter, e: &FilterError| {
+        eprintln!(
+            "mdmd: filter '{}' failed for {}: {e}",
+            filter.argv.join(" "),
+            file_path.display()
+        );
+    };
+    let input = normalize::source(input);
+    let filtered = render
+        .filters
+        .apply(Stage::Markdown, &input, file_path, report);
+    // Shortcodes keep every line where it was; a markdown filter may not.
+    let lines_kept = filtered == input;
+
+    #[cfg(feature = "shortcodes")]
+    let expanded = render
+        .shortcodes
+        .expand(&filtered, file_path, shortcode::Output::Html);
+    #[cfg(feature = "shortcodes")]
+    let text = &expanded.text;
+    #[cfg(not(feature = "shortcodes"))]
+    let text = &filtered;
+
     let arena = Arena::new();
-    let options = make_options();
-    let root = parse_document(&arena, input, &options);
+    let options = make_options(render);
+    let root = parse_document(&arena, text, &options);
 
     // --- Mermaid fenced blocks: SSR placeholders for client hydration (bd-2se) ---
-    let mermaid_rewritten = rewrite_mermaid_code_blocks(root);
+    let mermaid_rewritten = match render.mermaid {
+        MermaidMode::Diagrams => rewrite_mermaid_code_blocks(root),
+        MermaidMode::Code => 0,
+    };
     if verbose {
         eprintln!(
             "[mermaid] file={} rewritten={}",
@@ -503,7 +975,7 @@ pub fn render_markdown(
     // --- Rewrite local relative links to root-relative hrefs (bd-1p6) ---
     // Only for Serve mode; Html preserves authored relative URLs.
     if target == RenderTarget::Serve {
-        let (rewritten, skipped) = rewrite_local_links(root, file_path, serve_root);
+        let (rewritten, skipped) = rewrite_local_links(root, file_path, &render.base_path);
         if verbose {
             eprintln!(
                 "[rewrite] file={} rewritten={} skipped={}",
@@ -514,43 +986,52 @@ pub fn render_markdown(
         }
     }
 
+    // --- Page title and heading demotion (--title-from-h1, --heading-offset) ---
+    let title = render.title_from_h1.then(|| take_title(root)).flatten();
+    if render.heading_offset > 0 {
+        demote_headings(root, render.heading_offset);
+    }
+
     // --- Extract headings with per-document slug deduplication (R4) ---
-    let mut entries: Vec<HeadingEntry> = Vec::new();
-    // Maps base slug → number of times it has been seen so far.
-    let mut slug_counter: HashMap<String, usize> = HashMap::new();
+    let mut entries: Vec<HeadingEntry> = extract_headings(root, render.slug_style)
+        .into_iter()
+        .map(|(entry, _)| entry)
+        .collect();
+
+    // --- Hierarchical heading numbers (--numbered-headings) ---
+    if render.numbered_headings {
+        number_headings(&arena, root, &mut entries);
+    }
 
-    for edge in root.traverse() {
-        if let NodeEdge::Start(node) = edge {
-            if let NodeValue::Heading(nh) = &node.data.borrow().value {
-                let level = nh.level;
-                let text = collect_heading_text(node);
-                let base_slug = slugify(&text);
-
-                let count = slug_counter.entry(base_slug.clone()).or_insert(0);
-                let anchor_id = if *count == 0 {
-                    // First occurrence: bare slug.
-                    *count = 1;
-                    base_slug.clone()
-                } else {
-                    // Subsequent occurrences: slug-N where N starts at 1.
-                    let n = *count;
-                    *count += 1;
-                    format!("{}-{}", base_slug, n)
-                };
-
-                entries.push(HeadingEntry {
-                    level,
-                    text,
-                    anchor_id,
-                });
-            }
-        }
+    // --- Inline [TOC] / <!-- toc --> markers ---
+    let tocs = replace_toc_markers(root, &entries);
+    if verbose && tocs > 0 {
+        eprintln!("[toc] file={} markers={tocs}", file_path.display());
     }
 
     // --- Render to HTML ---
     let mut html_bytes = Vec::new();
     format_html(root, &options, &mut html_bytes).expect("comrak HTML formatting should not fail");
     let html = String::from_utf8(html_bytes).expect("comrak output must be valid UTF-8");
+    // Tagged before shortcode fragments and HTML filters can add checkboxes.
+    let (html, task_lines) = if render.editable_tasks {
+        tag_task_checkboxes(&html, root, lines_kept)
+    } else {
+        (html, Vec::new())
+    };
+    #[cfg(feature = "shortcodes")]
+    let html = expanded.restore(html);
+    // Only documents with right-to-left text are marked, leaving the output
+    // for others unchanged.
+    let html = if bidi::has_rtl(text) {
+        mark_dir_auto(&html)
+    } else {
+        html
+    };
+    let html = match render.filters.apply(Stage::Html, &html, file_path, report) {
+        Cow::Borrowed(_) => html,
+        Cow::Owned(filtered) => filtered,
+    };
 
     if verbose {
         eprintln!(
@@ -560,7 +1041,312 @@ pub fn render_markdown(
         );
     }
 
-    (html, entries)
+    RenderedMarkdown {
+        html,
+        headings: entries,
+        title,
+        task_lines,
+    }
+}
+
+/// Remove the first H1 from `root`, returning its plain text.
+fn take_title<'a>(root: &'a AstNode<'a>) -> Option<String> {
+    let heading = root
+        .descendants()
+        .find(|node| matches!(node.data.borrow().value, NodeValue::Heading(h) if h.level == 1))?;
+    let title = collect_heading_text(heading);
+    heading.detach();
+    Some(title)
+}
+
+/// Lower every heading in `root` by `offset` levels, stopping at level 6.
+fn demote_headings<'a>(root: &'a AstNode<'a>, offset: u8) {
+    for node in root.descendants() {
+        if let NodeValue::Heading(heading) = &mut node.data.borrow_mut().value {
+            heading.level = heading.level.saturating_add(offset).min(6);
+        }
+    }
+}
+
+/// Collect every heading in document order with its deduplicated anchor id
+/// and 1-based source line.
+fn extract_headings<'a>(root: &'a AstNode<'a>, style: SlugStyle) -> Vec<(HeadingEntry, usize)> {
+    let mut entries = Vec::new();
+    let mut ids = AnchorIds::new(style);
+
+    for edge in root.traverse() {
+        if let NodeEdge::Start(node) = edge {
+            let data = node.data.borrow();
+            if let NodeValue::Heading(nh) = &data.value {
+                let level = nh.level;
+                let line = data.sourcepos.start.line;
+                let text = collect_heading_text(node);
+                let anchor_id = ids.next(&text);
+
+                entries.push((
+                    HeadingEntry {
+                        level,
+                        text,
+                        anchor_id,
+                        number: None,
+                    },
+                    line,
+                ));
+            }
+        }
+    }
+    entries
+}
+
+/// Number `headings`, the entries [`extract_headings`] found in `root`, and
+/// prefix each heading element with a `<span class="heading-number">`.
+fn number_headings<'a>(
+    arena: &'a Arena<AstNode<'a>>,
+    root: &'a AstNode<'a>,
+    headings: &mut [HeadingEntry],
+) {
+    let levels: Vec<u8> = headings.iter().map(|h| h.level).collect();
+    let nodes = root
+        .descendants()
+        .filter(|node| matches!(node.data.borrow().value, NodeValue::Heading(_)));
+    for ((heading, number), node) in headings
+        .iter_mut()
+        .zip(numbering::heading_numbers(&levels))
+        .zip(nodes)
+    {
+        let span = format!("<span class=\"heading-number\">{number}</span> ");
+        node.prepend(arena.alloc(NodeValue::Raw(span).into()));
+        heading.number = Some(number);
+    }
+}
+
+/// Replace top-level inline TOC markers (see [`crate::toc`]) with a nested
+/// list of links to `headings`.  Returns how many were replaced.
+fn replace_toc_markers<'a>(root: &'a AstNode<'a>, headings: &[HeadingEntry]) -> usize {
+    let mut replaced = 0;
+    for node in root.children() {
+        let depth = match &node.data.borrow().value {
+            NodeValue::Paragraph => toc::marker_depth(&collect_heading_text(node)),
+            NodeValue::HtmlBlock(block) => toc::marker_depth(&block.literal),
+            _ => None,
+        };
+        if let Some(depth) = depth {
+            let children: Vec<_> = node.children().collect();
+            children.into_iter().for_each(|child| child.detach());
+            node.data.borrow_mut().value = NodeValue::Raw(inline_toc_html(headings, depth));
+            replaced += 1;
+        }
+    }
+    replaced
+}
+
+/// `<nav class="inline-toc">` with nested lists of the headings up to
+/// level `depth`, or an empty string when there are none.
+fn inline_toc_html(headings: &[HeadingEntry], depth: u8) -> String {
+    let shown: Vec<&HeadingEntry> = headings.iter().filter(|h| h.level <= depth).collect();
+    if shown.is_empty() {
+        return String::new();
+    }
+    let levels: Vec<u8> = shown.iter().map(|h| h.level).collect();
+    let mut html = String::from("<nav class=\"inline-toc\">\n<ul>\n");
+    let mut open = 0;
+    for (i, (heading, indent)) in shown.iter().zip(toc::nesting(&levels)).enumerate() {
+        if i > 0 && indent > open {
+            html.push_str("\n<ul>\n");
+        } else if i > 0 {
+            html.push_str("</li>\n");
+            (indent..open).for_each(|_| html.push_str("</ul>\n</li>\n"));
+        }
+        open = indent;
+        html.push_str(&format!(
+            "<li><a href=\"#{}\">{}</a>",
+            heading.anchor_id,
+            html_escape(&heading.label())
+        ));
+    }
+    html.push_str("</li>\n");
+    (0..open).for_each(|_| html.push_str("</ul>\n</li>\n"));
+    html.push_str("</ul>\n</nav>\n");
+    html
+}
+
+/// Headings of `input` with the anchor ids [`render_markdown`] assigns and
+/// their 1-based source lines.
+pub fn heading_anchors(input: &str) -> Vec<(HeadingEntry, usize)> {
+    heading_anchors_with(input, SlugStyle::default())
+}
+
+/// [`heading_anchors`] with anchor ids in `style`.
+pub fn heading_anchors_with(input: &str, style: SlugStyle) -> Vec<(HeadingEntry, usize)> {
+    let arena = Arena::new();
+    let input = normalize::source(input);
+    let root = parse_document(&arena, &input, &make_options(&RenderOptions::default()));
+    extract_headings(root, style)
+}
+
+/// Page header with the frontmatter `logo` image, the site title, and the
+/// page title, or an empty string when none is set.  In serve mode the site
+/// title links to the directory index at `/`.
+fn build_site_header_html(
+    site_title: Option<&str>,
+    logo: Option<&str>,
+    page_title: Option<&str>,
+    target: RenderTarget,
+) -> String {
+    if site_title.is_none() && logo.is_none() && page_title.is_none() {
+        return String::new();
+    }
+    let mut html = String::from("<header class=\"site-header\">");
+    if let Some(logo) = logo {
+        // Decorative: the logo only brands the page, it carries no content.
+        html.push_str(&format!(
+            "<img class=\"site-logo\" src=\"{}\" alt=\"\">",
+            html_escape(logo)
+        ));
+    }
+    if let Some(site_title) = site_title {
+        let site_title = html_escape(site_title);
+        html.push_str(&match target {
+            RenderTarget::Serve => format!("<a class=\"site-title\" href=\"/\">{site_title}</a>"),
+            RenderTarget::Html => format!("<span class=\"site-title\">{site_title}</span>"),
+        });
+    }
+    if let Some(page_title) = page_title {
+        html.push_str(&format!(
+            "<h1 class=\"page-title\">{}</h1>",
+            html_escape(page_title)
+        ));
+    }
+    html.push_str("</header>\n");
+    html
+}
+
+/// `data:` URI for an SVG document, percent-encoding everything but ASCII
+/// alphanumerics so it is safe inside a double-quoted attribute.
+fn svg_data_uri(svg: &str) -> String {
+    let mut uri = String::from("data:image/svg+xml,");
+    for b in svg.trim_end().bytes() {
+        if b.is_ascii_alphanumeric() {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{b:02X}"));
+        }
+    }
+    uri
+}
+
+/// Top-level string value of frontmatter field `key`, if non-empty.
+fn frontmatter_scalar<'a>(frontmatter: Option<&'a FrontmatterMeta>, key: &str) -> Option<&'a str> {
+    frontmatter?.scalar(key)
+}
+
+/// Plain-text page summary by the [`summary`](crate::summary) rules, with
+/// the first `<p>` of the rendered body standing in for the first paragraph.
+fn page_description(frontmatter: Option<&FrontmatterMeta>, body_html: &str) -> Option<String> {
+    let first_paragraph = ["<p>", "<p dir=\"auto\">"]
+        .iter()
+        .filter_map(|tag| body_html.find(tag).map(|at| at + tag.len()))
+        .min()
+        .and_then(|start| {
+            let end = start + body_html[start..].find("</p>")?;
+            Some(html_to_text(&body_html[start..end]))
+        });
+    summary::summary(frontmatter, first_paragraph.as_deref())
+}
+
+/// Strip tags from a comrak HTML fragment and undo its entity escaping.
+fn html_to_text(fragment: &str) -> String {
+    let mut text = String::with_capacity(fragment.len());
+    let mut in_tag = false;
+    for c in fragment.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// Resolve `href` against the absolute page URL `base`, the way a browser
+/// would for `http(s)` URLs.  Absolute and scheme-relative hrefs keep their
+/// host; root-relative hrefs keep `base`'s origin.
+fn resolve_url(base: &str, href: &str) -> String {
+    if href.contains("://") || href.starts_with("data:") {
+        return href.to_owned();
+    }
+    let scheme_end = base.find("://").map_or(0, |i| i + "://".len());
+    if let Some(rest) = href.strip_prefix("//") {
+        return format!("{}{rest}", &base[..scheme_end]);
+    }
+    let origin_end = base[scheme_end..]
+        .find('/')
+        .map_or(base.len(), |i| scheme_end + i);
+    if href.starts_with('/') {
+        return format!("{}{href}", &base[..origin_end]);
+    }
+    let dir_end = base[origin_end..]
+        .rfind('/')
+        .map_or(origin_end, |i| origin_end + i);
+    format!("{}/{}", &base[..dir_end], href.trim_start_matches("./"))
+}
+
+/// `<meta>`/`<link>` tags that let chat tools and search engines unfurl the
+/// page: Open Graph title, description, URL and image, the matching Twitter
+/// card, and the canonical link when the page URL is known.
+fn build_social_meta(
+    title: &str,
+    site_title: Option<&str>,
+    description: Option<&str>,
+    canonical_url: Option<&str>,
+    image: Option<&str>,
+) -> String {
+    let mut meta = format!(
+        "<meta property=\"og:type\" content=\"article\">\n\
+<meta property=\"og:title\" content=\"{}\">\n",
+        html_escape(title)
+    );
+    if let Some(site_title) = site_title {
+        meta.push_str(&format!(
+            "<meta property=\"og:site_name\" content=\"{}\">\n",
+            html_escape(site_title)
+        ));
+    }
+    if let Some(description) = description {
+        let description = html_escape(description);
+        meta.push_str(&format!(
+            "<meta name=\"description\" content=\"{description}\">\n\
+<meta property=\"og:description\" content=\"{description}\">\n"
+        ));
+    }
+    if let Some(url) = canonical_url {
+        let url = html_escape(url);
+        meta.push_str(&format!(
+            "<link rel=\"canonical\" href=\"{url}\">\n\
+<meta property=\"og:url\" content=\"{url}\">\n"
+        ));
+    }
+    if let Some(image) = image {
+        let image = canonical_url.map_or_else(|| image.to_owned(), |base| resolve_url(base, image));
+        meta.push_str(&format!(
+            "<meta property=\"og:image\" content=\"{}\">\n",
+            html_escape(&image)
+        ));
+    }
+    let card = if image.is_some() {
+        "summary_large_image"
+    } else {
+        "summary"
+    };
+    meta.push_str(&format!(
+        "<meta name=\"twitter:card\" content=\"{card}\">\n"
+    ));
+    meta
 }
 
 /// Build the full HTML page shell: `<!DOCTYPE html>` with header, sticky TOC
@@ -570,9 +1356,8 @@ pub fn render_markdown(
 /// - `body_html`: the raw HTML fragment produced by `render_markdown`.
 /// - `headings`: ordered heading entries for the TOC (from `render_markdown`).
 /// - `file_path`: absolute path to the source `.md` file (for display).
-/// - `serve_root`: root directory of the serve tree (used to compute the
-///   relative display path shown in the header).
-/// - `ctx`: per-page metadata including backlinks, mtime, and URL path.
+/// - `ctx`: per-page metadata including backlinks, mtime, URL path, and the
+///   [`RenderOptions`] the body was rendered with.
 ///   Pass `&PageShellContext { backlinks: &[], .. }` for pages without backlinks.
 /// - `target`: controls asset inlining and serve-only UI inclusion.
 ///
@@ -582,31 +1367,45 @@ pub fn build_page_shell(
     body_html: &str,
     headings: &[HeadingEntry],
     file_path: &Path,
-    _serve_root: &Path,
     ctx: &PageShellContext,
     target: RenderTarget,
 ) -> String {
-    // Page title precedence: frontmatter title, then first H1, then file stem.
-    let title_raw = ctx
-        .frontmatter
-        .and_then(|meta| meta.title.as_deref())
-        .filter(|title| !title.is_empty())
-        .or_else(|| {
-            headings
-                .iter()
-                .find(|h| h.level == 1)
-                .map(|h| h.text.as_str())
-        })
+    // Page title precedence: the summary title rule (frontmatter title, then
+    // first H1), then file stem.
+    let first_h1 = ctx.page_title.or_else(|| {
+        headings
+            .iter()
+            .find(|h| h.level == 1)
+            .map(|h| h.text.as_str())
+    });
+    let title_owned = summary::title(ctx.frontmatter, first_h1);
+    let title_raw = title_owned
+        .as_deref()
         .or_else(|| file_path.file_stem().and_then(|s| s.to_str()))
         .unwrap_or("Document");
 
     let title = html_escape(title_raw);
-    let frontmatter_html = render_frontmatter_html(ctx.frontmatter);
+    let social_meta = build_social_meta(
+        title_raw,
+        ctx.site_title,
+        page_description(ctx.frontmatter, body_html).as_deref(),
+        ctx.canonical_url,
+        frontmatter_scalar(ctx.frontmatter, "image"),
+    );
+    let frontmatter_html = render_frontmatter_html(ctx.frontmatter, ctx.locale);
     let content_html = inject_heading_ids(body_html, headings);
-    let toc_html = build_toc_html(headings);
-    let backlinks_html = build_backlinks_html(ctx.backlinks);
+    let toc_html = if ctx.render.sidebar_toc {
+        build_toc_html(headings)
+    } else {
+        String::new()
+    };
+    let backlinks_html =
+        build_backlinks_html(ctx.backlinks, ctx.mentions, ctx.allow_edit, ctx.locale);
+    let outgoing_html = build_outgoing_html(ctx.outgoing, ctx.locale);
+    let tr = |msgid| html_escape(ctx.locale.tr(msgid));
 
-    // Mermaid is loaded unconditionally to keep shell logic simple.
+    // Mermaid is loaded on every page (unless `MermaidMode::Code`) to keep shell
+    // logic simple.
     // Version is pinned (not @latest) for reproducibility and to avoid silent
     // breakage from upstream CDN updates.
     const MERMAID_CDN_URL: &str = "https://cdn.jsdelivr.net/npm/mermaid@10.9.3/dist/mermaid.min.js";
@@ -646,11 +1445,26 @@ if(s==='on')document.documentElement.classList.add('indent-hierarchy-on');\
 
     // --- Target-conditional sections ---
 
-    let title_suffix = match target {
-        RenderTarget::Serve => "mdmd serve",
-        RenderTarget::Html => "mdmd",
+    let title_suffix = match (ctx.site_title, target) {
+        (Some(site_title), _) => html_escape(site_title),
+        (None, RenderTarget::Serve) => "mdmd serve".to_owned(),
+        (None, RenderTarget::Html) => "mdmd".to_owned(),
+    };
+
+    // Favicon: served by the server, inlined for html so the file stays
+    // self-contained.
+    let favicon_href = match target {
+        RenderTarget::Serve => "/favicon.ico".to_owned(),
+        RenderTarget::Html => svg_data_uri(crate::web_assets::FAVICON_SVG),
     };
 
+    let site_header_html = build_site_header_html(
+        ctx.site_title,
+        frontmatter_scalar(ctx.frontmatter, "logo"),
+        ctx.page_title,
+        target,
+    );
+
     // Freshness meta tags: serve-only (used by JS polling).
     let mtime_meta = match (target, ctx.file_mtime_secs) {
         (RenderTarget::Serve, Some(secs)) => {
@@ -667,86 +1481,250 @@ if(s==='on')document.documentElement.classList.add('indent-hierarchy-on');\
 
     // CSS: linked for serve, inlined for html.
     let css_fragment = match target {
-        RenderTarget::Serve => "<link rel=\"stylesheet\" href=\"/assets/mdmd.css\">".to_owned(),
+        RenderTarget::Serve => format!(
+            "<link rel=\"stylesheet\" href=\"{}\">",
+            crate::web_assets::css_url()
+        ),
         RenderTarget::Html => format!("<style>\n{}\n</style>", crate::web_assets::CSS),
     };
+    // After the stylesheet, so injected styles override it.
+    let head_tags = ctx.render.head.tags(ctx.frontmatter);
 
-    // Serve-only controls: raw source link, change notice.
+    // Serve-only controls: raw source link, site search, change notice.
     let raw_link_html = match target {
         RenderTarget::Serve => format!(
-            "<a href=\"?raw=1\" class=\"raw-source-link\" aria-label=\"View raw markdown\" target=\"_blank\">{ICON_RAW}</a>\n"
+            "<a href=\"?raw=1\" class=\"raw-source-link\" aria-label=\"{}\" target=\"_blank\">{ICON_RAW}</a>\n",
+            tr("View raw markdown")
+        ),
+        RenderTarget::Html => String::new(),
+    };
+    let search_html = match target {
+        RenderTarget::Serve => format!(
+            "\
+<div class=\"site-search\" role=\"search\">\n\
+<input type=\"search\" id=\"mdmd-search\" class=\"site-search-input\" placeholder=\"{}\" aria-label=\"{}\" autocomplete=\"off\" aria-controls=\"mdmd-search-results\">\n\
+<ul id=\"mdmd-search-results\" class=\"site-search-results\" role=\"listbox\" hidden></ul>\n\
+</div>\n",
+            tr("Search (/)"),
+            tr("Search all files")
+        ),
+        RenderTarget::Html => String::new(),
+    };
+    // Site-wide file tree; the fragment is fetched from /_mdmd/tree when the
+    // disclosure is first opened.
+    let site_tree_html = match target {
+        RenderTarget::Serve => format!(
+            "\
+<details class=\"site-tree\" id=\"mdmd-site-tree\">\n\
+<summary>{}</summary>\n\
+<div class=\"site-tree-body\" data-src=\"/_mdmd/tree?format=html\"></div>\n\
+</details>\n",
+            tr("All files")
         ),
         RenderTarget::Html => String::new(),
     };
     let change_notice_html = match target {
-        RenderTarget::Serve => "\
+        RenderTarget::Serve => format!(
+            "\
 <div id=\"mdmd-change-notice\" class=\"change-notice\" hidden>\n\
-This file has changed on disk.\n\
-<button class=\"change-notice-reload\" onclick=\"location.reload()\">Load latest</button>\n\
-</div>\n"
-            .to_owned(),
+{}\n\
+<button class=\"change-notice-reload\" onclick=\"location.reload()\">{}</button>\n\
+</div>\n",
+            tr("This file has changed on disk."),
+            tr("Load latest")
+        ),
         RenderTarget::Html => String::new(),
     };
 
+    let encoding_notice_html = match ctx.encoding {
+        Some(encoding) => format!(
+            "<div class=\"encoding-notice\" role=\"note\">{}</div>\n",
+            html_escape(&ctx.locale.trf(
+                "This file is not UTF-8; it was decoded as {encoding}.",
+                &[("encoding", &encoding)],
+            ))
+        ),
+        None => String::new(),
+    };
+
+    let draft_notice_html = if ctx.frontmatter.is_some_and(FrontmatterMeta::is_draft) {
+        format!(
+            "<div class=\"draft-notice\" role=\"note\">{}</div>\n",
+            tr("Draft: this page is not listed in indexes or search.")
+        )
+    } else {
+        String::new()
+    };
+
+    let mermaid_script = match ctx.render.mermaid {
+        MermaidMode::Diagrams => format!("<script src=\"{MERMAID_CDN_URL}\"></script>\n"),
+        MermaidMode::Code => String::new(),
+    };
+
     // JS: external for serve, inlined for html.
     let js_fragment = match target {
-        RenderTarget::Serve => "<script src=\"/assets/mdmd.js\"></script>".to_owned(),
+        RenderTarget::Serve => format!("<script src=\"{}\"></script>", crate::web_assets::js_url()),
         RenderTarget::Html => format!("<script>\n{}\n</script>", crate::web_assets::JS),
     };
 
-    let html_class = if ctx.full_width { " class=\"full-width-on\"" } else { "" };
+    let mut html_class = if ctx.full_width {
+        " class=\"full-width-on\""
+    } else {
+        ""
+    }
+    .to_owned();
+    // A forced theme replaces the script that restores the saved one.
+    let theme_init = match ctx.render.theme {
+        Some(theme) => {
+            html_class.push_str(&format!(" data-theme=\"{}\"", theme.as_str()));
+            ""
+        }
+        None => THEME_INIT_SCRIPT,
+    };
+    let lang = ctx.locale.tag();
+    let (dark_label, indent_label, fullwidth_label) = (
+        tr("Toggle dark mode"),
+        tr("Toggle indentation hierarchy"),
+        tr("Toggle full width"),
+    );
+    let (skip_label, toc_label) = (tr("Skip to content"), tr("Table of contents"));
+
+    // Shared by the built-in shell and `--template` pages.
+    let head_html = format!(
+        "{social_meta}\
+{mtime_meta}\
+{path_meta}\
+{theme_init}\n\
+{INDENT_INIT_SCRIPT}\n\
+{FULLWIDTH_INIT_SCRIPT}\n\
+<link rel=\"icon\" href=\"{favicon_href}\">\n\
+{css_fragment}\n\
+{head_tags}"
+    );
+    let controls_html = format!(
+        "<button id=\"theme-toggle\" class=\"theme-toggle\" aria-label=\"{dark_label}\" aria-pressed=\"false\">{ICON_MOON}{ICON_SUN}</button>\n\
+<button id=\"indent-toggle\" class=\"indent-toggle\" aria-label=\"{indent_label}\" aria-pressed=\"false\">{ICON_INDENT}</button>\n\
+<button id=\"fullwidth-toggle\" class=\"fullwidth-toggle\" aria-label=\"{fullwidth_label}\" aria-pressed=\"false\">{ICON_FULLWIDTH}</button>\n\
+{raw_link_html}\
+{search_html}\
+{change_notice_html}"
+    );
+    let scripts_html = format!("{mermaid_script}{js_fragment}\n");
+    let mut notices_html = format!("{draft_notice_html}{encoding_notice_html}");
+
+    if let Some(template) = &ctx.render.template {
+        let parts = PageParts {
+            title: title_raw,
+            site_title: ctx.site_title,
+            lang,
+            path: ctx.page_url_path,
+            head: &head_html,
+            scripts: &scripts_html,
+            controls: &controls_html,
+            header: &site_header_html,
+            notices: &notices_html,
+            properties: &frontmatter_html,
+            body: &content_html,
+            toc: &toc_html,
+            site_tree: &site_tree_html,
+            backlinks: &backlinks_html,
+            links: &outgoing_html,
+            headings,
+            frontmatter: ctx.frontmatter,
+        };
+        // A template that fails on this page falls back to the built-in
+        // shell, saying why.
+        match template.render(&parts) {
+            Ok(page) => return page,
+            Err(e) => {
+                let message = ctx
+                    .locale
+                    .trf("Page template failed: {error}", &[("error", &e)]);
+                notices_html.insert_str(
+                    0,
+                    &format!(
+                        "<div class=\"template-notice\" role=\"alert\">{}</div>\n",
+                        html_escape(&message)
+                    ),
+                );
+            }
+        }
+    }
 
     format!(
         "<!DOCTYPE html>\n\
-<html lang=\"en\"{html_class}>\n\
+<html lang=\"{lang}\"{html_class}>\n\
 <head>\n\
 <meta charset=\"utf-8\">\n\
 <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
 <title>{title} · {title_suffix}</title>\n\
-{mtime_meta}\
-{path_meta}\
-{THEME_INIT_SCRIPT}\n\
-{INDENT_INIT_SCRIPT}\n\
-{FULLWIDTH_INIT_SCRIPT}\n\
-{css_fragment}\n\
+{head_html}\
 </head>\n\
 <body>\n\
-<button id=\"theme-toggle\" class=\"theme-toggle\" aria-label=\"Toggle dark mode\">{ICON_MOON}{ICON_SUN}</button>\n\
-<button id=\"indent-toggle\" class=\"indent-toggle\" aria-label=\"Toggle indentation hierarchy\" aria-pressed=\"false\">{ICON_INDENT}</button>\n\
-<button id=\"fullwidth-toggle\" class=\"fullwidth-toggle\" aria-label=\"Toggle full width\" aria-pressed=\"false\">{ICON_FULLWIDTH}</button>\n\
-{raw_link_html}\
-{change_notice_html}\
+<a class=\"skip-link\" href=\"#mdmd-content\">{skip_label}</a>\n\
+{controls_html}\
 <div class=\"layout\">\n\
-<nav class=\"toc-sidebar\">\n\
+<nav class=\"toc-sidebar\" aria-label=\"{toc_label}\">\n\
+{site_tree_html}\
 {toc_html}</nav>\n\
-<main class=\"content\">\n\
+<main id=\"mdmd-content\" class=\"content\" tabindex=\"-1\">\n\
+{site_header_html}\
+{notices_html}\
 {frontmatter_html}\
 {content_html}\
-{backlinks_html}</main>\n\
+{backlinks_html}{outgoing_html}</main>\n\
 </div>\n\
-<script src=\"{MERMAID_CDN_URL}\"></script>\n\
-{js_fragment}\n\
+{scripts_html}\
 </body>\n\
 </html>\n"
     )
 }
 
+/// Build a `:~:text=` fragment directive that scrolls to and highlights the
+/// first occurrence of `text` (append after `#`).
+///
+/// Everything except ASCII alphanumerics and `._~` is percent-encoded, which
+/// covers the `-`, `,` and `&` characters that carry meaning in the directive
+/// syntax.
+pub fn text_fragment(text: &str) -> String {
+    let mut out = String::from(":~:text=");
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
 /// Build the HTML fragment for the backlinks section.
 ///
-/// Returns an empty string when there are no backlinks (section is omitted).
-/// Otherwise renders a bordered footnote-style section below the document
-/// content with one entry per source document and a count in the header.
-fn build_backlinks_html(backlinks: &[BacklinkRef]) -> String {
-    if backlinks.is_empty() {
+/// Returns an empty string when there are no backlinks or mentions (section
+/// is omitted).  Otherwise renders a bordered footnote-style section below the
+/// document content with one entry per source document and a count in the
+/// header, followed by a "Mentions" list of unlinked mentions.  With
+/// `allow_edit`, each mention gets a button that turns it into a link.
+fn build_backlinks_html(
+    backlinks: &[BacklinkRef],
+    mentions: &[MentionRef],
+    allow_edit: bool,
+    locale: Locale,
+) -> String {
+    if backlinks.is_empty() && mentions.is_empty() {
         return String::new();
     }
 
-    let count = backlinks.len();
-    let mut html = format!(
-        "<section class=\"backlinks-panel\" aria-label=\"Backlinks\">\n\
-<h2 class=\"backlinks-header\">Backlinks ({count})</h2>\n\
+    let label = html_escape(locale.tr("Backlinks"));
+    let mut html = format!("<section class=\"backlinks-panel\" aria-label=\"{label}\">\n");
+    if !backlinks.is_empty() {
+        let header =
+            html_escape(&locale.trf("Backlinks ({count})", &[("count", &backlinks.len())]));
+        html.push_str(&format!(
+            "<h2 class=\"backlinks-header\">{header}</h2>\n\
 <ul class=\"backlinks-list\">\n",
-    );
+        ));
+    }
     for bl in backlinks {
         let base_href = html_escape(&bl.source_url_path);
         let href = match &bl.target_fragment {
@@ -754,7 +1732,12 @@ fn build_backlinks_html(backlinks: &[BacklinkRef]) -> String {
             None => base_href,
         };
         let label = html_escape(&bl.source_display);
-        let snippet = html_escape(&bl.snippet);
+        let snippet = snippet_html(
+            &bl.source_url_path,
+            &bl.snippet,
+            bl.snippet_link.clone(),
+            &bl.link_text,
+        );
         let fragment_span = match &bl.target_fragment {
             Some(frag) => format!(
                 "<span class=\"backlinks-fragment\"> \u{00a7} {}</span>",
@@ -766,6 +1749,91 @@ fn build_backlinks_html(backlinks: &[BacklinkRef]) -> String {
             "<li class=\"backlinks-item\">\n\
 <a class=\"backlinks-source\" href=\"{href}\">{label}</a>{fragment_span}\n\
 <p class=\"backlinks-snippet\">{snippet}</p>\n\
+</li>\n"
+        ));
+    }
+    if !backlinks.is_empty() {
+        html.push_str("</ul>\n");
+    }
+
+    if !mentions.is_empty() {
+        let header = html_escape(&locale.trf("Mentions ({count})", &[("count", &mentions.len())]));
+        html.push_str(&format!(
+            "<h2 class=\"backlinks-header\">{header}</h2>\n\
+<ul class=\"backlinks-list mentions-list\">\n",
+        ));
+    }
+    for mention in mentions {
+        let href = html_escape(&mention.source_url_path);
+        let label = html_escape(&mention.source_display);
+        let snippet = snippet_html(
+            &mention.source_url_path,
+            &mention.snippet,
+            mention.snippet_mention.clone(),
+            &mention.text,
+        );
+        let button = if allow_edit {
+            format!(
+                " <button type=\"button\" class=\"mention-link\" data-source=\"{href}\" \
+data-line=\"{}\" data-column=\"{}\" data-text=\"{}\" data-done=\"{}\" title=\"{}\">{}</button>",
+                mention.line,
+                mention.column,
+                html_escape(&mention.text),
+                html_escape(locale.tr("Linked")),
+                html_escape(locale.tr("Turn this mention into a link")),
+                html_escape(locale.tr("Link")),
+            )
+        } else {
+            String::new()
+        };
+        html.push_str(&format!(
+            "<li class=\"backlinks-item\">\n\
+<a class=\"backlinks-source\" href=\"{href}\">{label}</a>{button}\n\
+<p class=\"backlinks-snippet\">{snippet}</p>\n\
+</li>\n"
+        ));
+    }
+    if !mentions.is_empty() {
+        html.push_str("</ul>\n");
+    }
+    html.push_str("</section>\n");
+    html
+}
+
+/// Build the HTML fragment for the outgoing links section, styled like the
+/// backlinks panel: one entry per link with its status and destination.
+///
+/// Returns an empty string when the page has no links.
+fn build_outgoing_html(links: &[OutgoingLink], locale: Locale) -> String {
+    if links.is_empty() {
+        return String::new();
+    }
+
+    let label = html_escape(locale.tr("Outgoing links"));
+    let header = html_escape(&locale.trf("Outgoing links ({count})", &[("count", &links.len())]));
+    let mut html = format!(
+        "<section class=\"backlinks-panel outgoing-panel\" aria-label=\"{label}\">\n\
+<h2 class=\"backlinks-header\">{header}</h2>\n\
+<ul class=\"backlinks-list\">\n",
+    );
+    for link in links {
+        let href = html_escape(&link.url);
+        let text = if link.text.trim().is_empty() {
+            href.clone()
+        } else {
+            html_escape(&link.text)
+        };
+        let status = link.status.as_str();
+        let status_label = html_escape(locale.tr(match link.status {
+            LinkStatus::Ok => "ok",
+            LinkStatus::Broken => "broken",
+            LinkStatus::External => "external",
+        }));
+        html.push_str(&format!(
+            "<li class=\"backlinks-item outgoing-{status}\">\n\
+<a class=\"backlinks-source\" href=\"{href}\">{text}</a> \
+<span class=\"outgoing-status\">{status_label}</span>\n\
+<p class=\"backlinks-snippet\">{href}</p>\n\
 </li>\n"
         ));
     }
@@ -773,6 +1841,36 @@ fn build_backlinks_html(backlinks: &[BacklinkRef]) -> String {
     html
 }
 
+/// A backlinks panel snippet with the byte range `span` highlighted,
+/// deep-linked to the passage in `source_url_path` containing `deep_text`
+/// when that is non-empty.
+fn snippet_html(
+    source_url_path: &str,
+    snippet: &str,
+    span: Option<std::ops::Range<usize>>,
+    deep_text: &str,
+) -> String {
+    let context = match span.filter(|span| snippet.get(span.clone()).is_some()) {
+        Some(span) => format!(
+            "{}<mark class=\"backlinks-link-text\">{}</mark>{}",
+            html_escape(&snippet[..span.start]),
+            html_escape(&snippet[span.clone()]),
+            html_escape(&snippet[span.end..])
+        ),
+        None => html_escape(snippet),
+    };
+    if deep_text.is_empty() {
+        context
+    } else {
+        // Deep-link the snippet to the passage containing the link.
+        format!(
+            "<a class=\"backlinks-snippet-link\" href=\"{}#{}\">{context}</a>",
+            html_escape(source_url_path),
+            html_escape(&text_fragment(deep_text)),
+        )
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -786,10 +1884,9 @@ mod tests {
         body_html: &str,
         headings: &[HeadingEntry],
         file_path: &Path,
-        serve_root: &Path,
         ctx: &PageShellContext,
     ) -> String {
-        build_page_shell(body_html, headings, file_path, serve_root, ctx, RenderTarget::Serve)
+        build_page_shell(body_html, headings, file_path, ctx, RenderTarget::Serve)
     }
 
     /// Convenience wrapper: render with dummy paths (Serve target for backward compat).
@@ -851,8 +1948,216 @@ mod tests {
     fn task_list_renders() {
         let (html, _) = render("- [ ] todo\n- [x] done\n");
         assert!(
-            html.contains("<input") && html.contains("checkbox"),
-            "expected checkbox input"
+            html.contains("<input") && html.contains("checkbox"),
+            "expected checkbox input"
+        );
+    }
+
+    #[test]
+    fn toc_marker_becomes_a_nested_list() {
+        let (html, _) = render(
+            "# Guide\n\n[TOC]\n\n## Install\n\n### From source\n\n#### Deep\n\n## Use & abuse\n\n<!-- toc depth=1 -->\n",
+        );
+        assert!(
+            html.contains(
+                "<nav class=\"inline-toc\">\n<ul>\n<li><a href=\"#guide\">Guide</a>\n<ul>\n\
+<li><a href=\"#install\">Install</a>\n<ul>\n<li><a href=\"#from-source\">From source</a></li>\n\
+</ul>\n</li>\n<li><a href=\"#use-abuse\">Use &amp; abuse</a></li>\n</ul>\n</li>\n</ul>\n</nav>\n"
+            ),
+            "{html}"
+        );
+        assert!(
+            !html.contains("[TOC]") && !html.contains("Deep</a>"),
+            "{html}"
+        );
+        assert!(
+            html.contains("<nav class=\"inline-toc\">\n<ul>\n<li><a href=\"#guide\">Guide</a></li>\n</ul>\n</nav>"),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn numbered_headings_prefix_headings_and_tocs() {
+        let options = RenderOptions {
+            numbered_headings: true,
+            ..RenderOptions::default()
+        };
+        let RenderedMarkdown { html, headings, .. } = render_markdown_with(
+            "# Guide\n\n[TOC]\n\n## Install\n\n## Use\n",
+            Path::new("/root/a.md"),
+            RenderTarget::Html,
+            false,
+            &options,
+        );
+        assert!(
+            html.contains("<h2><span class=\"heading-number\">1.2</span> Use</h2>"),
+            "{html}"
+        );
+        assert!(html.contains("<a href=\"#install\">1.1 Install</a>"), "{html}");
+        let labels: Vec<String> = headings.iter().map(HeadingEntry::label).collect();
+        assert_eq!(labels, ["1. Guide", "1.1 Install", "1.2 Use"]);
+        // Anchors and heading text stay unnumbered.
+        assert_eq!(headings[1].anchor_id, "install");
+        assert_eq!(headings[1].text, "Install");
+    }
+
+    #[test]
+    fn title_from_h1_and_heading_offset() {
+        let options = RenderOptions {
+            heading_offset: 1,
+            title_from_h1: true,
+            ..RenderOptions::default()
+        };
+        let rendered = render_markdown_with(
+            "# Widget\n\nIntro.\n\n## Install\n\n##### Deep\n\n# Second\n",
+            Path::new("/root/a.md"),
+            RenderTarget::Html,
+            false,
+            &options,
+        );
+        assert_eq!(rendered.title.as_deref(), Some("Widget"));
+        assert!(!rendered.html.contains("Widget"), "{}", rendered.html);
+        assert!(
+            rendered.html.contains("<h3>Install</h3>")
+                && rendered.html.contains("<h6>Deep</h6>")
+                && rendered.html.contains("<h2>Second</h2>"),
+            "{}",
+            rendered.html
+        );
+        let levels: Vec<u8> = rendered.headings.iter().map(|h| h.level).collect();
+        assert_eq!(levels, [3, 6, 2]);
+
+        let ctx = PageShellContext {
+            frontmatter: None,
+            backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width: false,
+            locale: Locale::En,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: rendered.title.as_deref(),
+            encoding: None,
+            render: RenderOptions::default(),
+        };
+        let page = build_page_shell(
+            &rendered.html,
+            &rendered.headings,
+            Path::new("/root/a.md"),
+            &ctx,
+            RenderTarget::Html,
+        );
+        assert!(page.contains("<title>Widget · mdmd</title>"), "{page}");
+        assert!(
+            page.contains(
+                "<header class=\"site-header\"><h1 class=\"page-title\">Widget</h1></header>"
+            ),
+            "{page}"
+        );
+    }
+
+    #[test]
+    fn render_options_set_extensions_sanitizing_and_mermaid() {
+        let input = "~~old~~ <b>bold</b>\n\n```mermaid\ngraph TD\n```\n";
+        let render = |options: &RenderOptions| {
+            render_markdown_with(
+                input,
+                Path::new("/root/a.md"),
+                RenderTarget::Html,
+                false,
+                options,
+            )
+            .html
+        };
+        let html = render(&RenderOptions::default());
+        assert!(
+            html.contains("<del>old</del> <!-- raw HTML omitted -->bold"),
+            "{html}"
+        );
+        assert!(html.contains("class=\"mermaid\""), "{html}");
+
+        let options = RenderOptions {
+            extensions: Extensions {
+                strikethrough: false,
+                ..Extensions::default()
+            },
+            sanitize: Sanitize::Escape,
+            mermaid: MermaidMode::Code,
+            ..RenderOptions::default()
+        };
+        let html = render(&options);
+        assert!(html.contains("~~old~~ &lt;b&gt;bold&lt;/b&gt;"), "{html}");
+        assert!(html.contains("<code class=\"language-mermaid\">"), "{html}");
+    }
+
+    #[test]
+    fn smart_punctuation_matches_the_tui() {
+        let input = "\"Quoted\" and 'single' -- it's 1990---2000... `don't`\n";
+        let options = RenderOptions {
+            smart_punctuation: true,
+            ..RenderOptions::default()
+        };
+        let rendered = render_markdown_with(
+            input,
+            Path::new("/root/a.md"),
+            RenderTarget::Html,
+            false,
+            &options,
+        );
+        assert_eq!(
+            rendered.html,
+            "<p>“Quoted” and ‘single’ – it’s 1990—2000… <code>don't</code></p>\n"
+        );
+        let doc = crate::parse::parse_smart(input);
+        assert_eq!(
+            doc.blocks[0].content,
+            "“Quoted” and ‘single’ – it’s 1990—2000… don't"
+        );
+    }
+
+    #[test]
+    fn editable_task_checkboxes_carry_source_lines() {
+        let input = "- [ ] todo\n- plain\n\n  - [x] done\n\n`<input type=\"checkbox\" />`\n";
+        let options = RenderOptions {
+            editable_tasks: true,
+            ..RenderOptions::default()
+        };
+        let rendered = render_markdown_with(
+            input,
+            Path::new("/root/a.md"),
+            RenderTarget::Html,
+            false,
+            &options,
+        );
+        assert_eq!(rendered.task_lines, [Some(1), Some(4)]);
+        // A checkbox added after rendering, as by a shortcode, has no tag.
+        let generated = "<input type=\"checkbox\" disabled=\"\" />";
+        let html = format!("{}{generated}", rendered.html);
+        let html = editable_task_checkboxes(&html, &rendered.task_lines, |line| Some(line + 3));
+        assert!(
+            html.contains("<input type=\"checkbox\" class=\"task-toggle\" data-line=\"4\" />"),
+            "{html}"
+        );
+        assert!(
+            html.contains(
+                "<input type=\"checkbox\" class=\"task-toggle\" data-line=\"7\" checked=\"\" />"
+            ),
+            "{html}"
+        );
+        assert!(html.ends_with(generated), "{html}");
+        assert!(!html.contains("data-task"), "{html}");
+        assert!(html.contains("&lt;input"), "code span untouched: {html}");
+
+        // Lines that map nowhere, such as an included file's, stay disabled.
+        let html = editable_task_checkboxes(&rendered.html, &rendered.task_lines, |line| {
+            (line == 1).then_some(1)
+        });
+        assert!(
+            html.contains("<input type=\"checkbox\" checked=\"\" disabled=\"\" /> done"),
+            "{html}"
         );
     }
 
@@ -976,6 +2281,36 @@ mod tests {
         assert_eq!(h1, h2, "heading entries must be identical across renders");
     }
 
+    #[test]
+    fn slug_styles_follow_their_platforms() {
+        let slugs = |style| {
+            ["C++ & Rust", "snake_case -- API", "1. Über uns"]
+                .iter()
+                .map(|text| AnchorIds::new(style).next(text))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            slugs(SlugStyle::Mdmd),
+            ["c-rust", "snake-case-api", "1-über-uns"]
+        );
+        assert_eq!(
+            slugs(SlugStyle::Github),
+            ["c--rust", "snake_case----api", "1-über-uns"]
+        );
+        assert_eq!(
+            slugs(SlugStyle::Gitlab),
+            ["c-rust", "snake_case-api", "1-über-uns"]
+        );
+        assert_eq!(
+            slugs(SlugStyle::Kramdown),
+            ["c--rust", "snakecase----api", "ber-uns"]
+        );
+
+        let mut ids = AnchorIds::new(SlugStyle::Kramdown);
+        assert_eq!(ids.next("?"), "section");
+        assert_eq!(ids.next("!"), "section-1");
+    }
+
     // --- inject_heading_ids ---
 
     #[test]
@@ -986,11 +2321,13 @@ mod tests {
                 level: 1,
                 text: "Title".into(),
                 anchor_id: "title".into(),
+                number: None,
             },
             HeadingEntry {
                 level: 2,
                 text: "Section".into(),
                 anchor_id: "section".into(),
+                number: None,
             },
         ];
         let result = inject_heading_ids(html, &headings);
@@ -1007,11 +2344,13 @@ mod tests {
                 level: 2,
                 text: "Alpha".into(),
                 anchor_id: "alpha".into(),
+                number: None,
             },
             HeadingEntry {
                 level: 2,
                 text: "Beta".into(),
                 anchor_id: "beta".into(),
+                number: None,
             },
         ];
         let result = inject_heading_ids(html, &headings);
@@ -1025,6 +2364,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rtl_documents_mark_text_blocks_dir_auto() {
+        let src = "# \u{05e9}\u{05dc}\u{05d5}\u{05dd}\n\nHello\n\n- a\n\n> q\n";
+        let (html, headings) = render(src);
+        let html = inject_heading_ids(&html, &headings);
+        assert!(html.contains("<h1 dir=\"auto\" id=\""), "{html}");
+        assert!(html.contains("<p dir=\"auto\">Hello</p>"), "{html}");
+        assert!(html.contains("<li dir=\"auto\">a</li>"), "{html}");
+        assert!(html.contains("<blockquote dir=\"auto\">"), "{html}");
+
+        let (ltr, _) = render("# Title\n\nHello\n");
+        assert!(!ltr.contains("dir="), "{ltr}");
+    }
+
     // --- build_page_shell ---
 
     #[test]
@@ -1035,17 +2388,25 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/root/doc.md"),
-            Path::new("/root"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
-            page.contains("<nav class=\"toc-sidebar\">"),
+            page.contains("<nav class=\"toc-sidebar\" aria-label=\"Table of contents\">"),
             "nav element present"
         );
         assert!(page.contains("href=\"#title\""), "toc link to h1");
@@ -1059,17 +2420,25 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
-            page.contains("<script src=\"/assets/mdmd.js\">"),
+            page.contains(&format!("<script src=\"{}\">", crate::web_assets::js_url())),
             "script tag present"
         );
     }
@@ -1081,13 +2450,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -1105,17 +2482,25 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
-            page.contains("href=\"/assets/mdmd.css\""),
+            page.contains(&format!("href=\"{}\"", crate::web_assets::css_url())),
             "css link present"
         );
     }
@@ -1128,13 +2513,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -1283,6 +2676,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn rewrite_preserves_fragment_on_relative_and_image_links() {
+        let html = render_abs(
+            "[t](../guide.md#install-steps) ![i](img/d.svg#layer)\n",
+            "/root",
+            "docs/sub/page.md",
+        );
+        assert!(
+            html.contains("href=\"/docs/guide.md#install-steps\""),
+            "heading fragment must survive rewriting, got: {html}"
+        );
+        assert!(
+            html.contains("src=\"/docs/sub/img/d.svg#layer\""),
+            "image fragment must survive rewriting, got: {html}"
+        );
+    }
+
+    #[test]
+    fn text_fragment_encodes_directive_delimiters() {
+        assert_eq!(text_fragment("setup guide"), ":~:text=setup%20guide");
+        assert_eq!(text_fragment("a-b, c&d"), ":~:text=a%2Db%2C%20c%26d");
+        assert_eq!(text_fragment("café"), ":~:text=caf%C3%A9");
+    }
+
     // --- rewrite_url unit tests ---
 
     #[test]
@@ -1494,13 +2911,17 @@ mod tests {
                 source_url_path: "/docs/a.md".to_owned(),
                 source_display: "Doc A".to_owned(),
                 snippet: "see <also> here".to_owned(),
+                snippet_link: None,
                 target_fragment: None,
+                link_text: "also, here".to_owned(),
             },
             BacklinkRef {
                 source_url_path: "/docs/b.md".to_owned(),
                 source_display: "Doc B".to_owned(),
                 snippet: "another ref".to_owned(),
+                snippet_link: Some(8..11),
                 target_fragment: Some("section-1".to_owned()),
+                link_text: String::new(),
             },
         ];
         let (html_body, headings) = render("# Hi\n");
@@ -1508,13 +2929,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &bls,
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         // Header label with count (2 backlink refs supplied)
@@ -1542,6 +2971,21 @@ mod tests {
             page.contains("see &lt;also&gt; here"),
             "snippet must be html-escaped, got: {page}"
         );
+        // The link text is highlighted within its sentence
+        assert!(
+            page.contains("another <mark class=\"backlinks-link-text\">ref</mark>"),
+            "link text must be highlighted in the snippet, got: {page}"
+        );
+        // Snippet deep-links to the referencing passage via a text fragment
+        assert!(
+            page.contains("href=\"/docs/a.md#:~:text=also%2C%20here\""),
+            "snippet must link to the source passage, got: {page}"
+        );
+        // No text fragment link when the link text is unknown
+        assert!(
+            !page.contains("/docs/b.md#:~:text="),
+            "empty link text must not produce a text fragment, got: {page}"
+        );
         // Section element
         assert!(
             page.contains("<section class=\"backlinks-panel\""),
@@ -1556,13 +3000,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -1579,6 +3031,70 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mentions_listed_with_link_buttons_only_when_editable() {
+        let mentions = [MentionRef {
+            source_url_path: "/notes.md".to_owned(),
+            source_display: "Notes".to_owned(),
+            snippet: "see the Deploy guide first.".to_owned(),
+            snippet_mention: Some(8..20),
+            line: 6,
+            column: 8,
+            text: "Deploy guide".to_owned(),
+        }];
+        let html = build_backlinks_html(&[], &mentions, false, Locale::En);
+        assert!(html.contains(">Mentions (1)<"), "{html}");
+        assert!(!html.contains(">Backlinks ("), "{html}");
+        assert!(
+            html.contains("see the <mark class=\"backlinks-link-text\">Deploy guide</mark> first."),
+            "{html}"
+        );
+        assert!(!html.contains("mention-link"), "{html}");
+
+        let html = build_backlinks_html(&[], &mentions, true, Locale::En);
+        assert!(
+            html.contains(
+                "<button type=\"button\" class=\"mention-link\" data-source=\"/notes.md\" \
+data-line=\"6\" data-column=\"8\" data-text=\"Deploy guide\""
+            ),
+            "{html}"
+        );
+    }
+
+    #[test]
+    fn outgoing_links_section_lists_status() {
+        assert_eq!(build_outgoing_html(&[], Locale::En), "");
+        let links = [
+            OutgoingLink {
+                text: "Guide".to_owned(),
+                url: "guide.md#a&b".to_owned(),
+                line: 3,
+                status: LinkStatus::Broken,
+            },
+            OutgoingLink {
+                text: String::new(),
+                url: "https://example.com".to_owned(),
+                line: 4,
+                status: LinkStatus::External,
+            },
+        ];
+        let html = build_outgoing_html(&links, Locale::De);
+        assert!(html.contains(">Ausgehende Links (2)<"), "{html}");
+        assert!(
+            html.contains(
+                "<li class=\"backlinks-item outgoing-broken\">\n\
+<a class=\"backlinks-source\" href=\"guide.md#a&amp;b\">Guide</a> \
+<span class=\"outgoing-status\">defekt</span>"
+            ),
+            "{html}"
+        );
+        // Links without text are labelled with their destination.
+        assert!(
+            html.contains("href=\"https://example.com\">https://example.com</a>"),
+            "{html}"
+        );
+    }
+
     #[test]
     fn change_notice_present_and_hidden() {
         let (html_body, headings) = render("# Hi\n");
@@ -1586,13 +3102,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -1605,6 +3129,99 @@ mod tests {
         );
     }
 
+    #[test]
+    fn head_tags_follow_the_stylesheet() {
+        let (html_body, headings) = render("# Hi\n");
+        let meta = fm_meta(
+            vec![fm_field(
+                "scripts",
+                MetaValue::Scalar("/widget.js".to_owned()),
+            )],
+            None,
+        );
+        let page = shell(
+            &html_body,
+            &headings,
+            Path::new("/r/f.md"),
+            &PageShellContext {
+                frontmatter: Some(&meta),
+                backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
+                file_mtime_secs: None,
+                page_url_path: None,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions {
+                    head: PageHead {
+                        inject: Some("<meta name=\"team\" content=\"docs\">".to_owned()),
+                        allow_page_head: true,
+                    },
+                    ..RenderOptions::default()
+                },
+            },
+        );
+        let stylesheet = page.find("<link rel=\"stylesheet\"").unwrap();
+        let injected = page
+            .find("<meta name=\"team\" content=\"docs\">\n<script src=\"/widget.js\" defer></script>\n</head>")
+            .expect("head tags before </head>");
+        assert!(stylesheet < injected, "{page}");
+    }
+
+    #[test]
+    fn template_replaces_the_shell_and_falls_back_when_it_fails() {
+        let (html_body, headings) = render("# Hi\n\n## Setup\n");
+        let page_with = |source: &str| {
+            shell(
+                &html_body,
+                &headings,
+                Path::new("/r/docs/f.md"),
+                &PageShellContext {
+                    frontmatter: None,
+                    backlinks: &[],
+                    mentions: &[],
+                    outgoing: &[],
+                    file_mtime_secs: None,
+                    page_url_path: Some("docs/f.md"),
+                    full_width: false,
+                    locale: Locale::En,
+                    canonical_url: None,
+                    site_title: None,
+                    allow_edit: false,
+                    page_title: None,
+                    encoding: None,
+                    render: RenderOptions {
+                        template: Some(PageTemplate::from_source("page.html", source).unwrap()),
+                        ..RenderOptions::default()
+                    },
+                },
+            )
+        };
+
+        let page = page_with(
+            "<title>{{ title }}</title>{{ head }}\
+{% for c in breadcrumbs %}[{{ c.url }}]{% endfor %}\
+<article>{{ body }}</article>{{ scripts }}",
+        );
+        assert!(page.starts_with("<title>Hi</title>"), "{page}");
+        assert!(page.contains("<link rel=\"stylesheet\""), "{page}");
+        assert!(page.contains("[/][/docs/]<article>"), "{page}");
+        assert!(page.contains("<h2 id=\"setup\">Setup</h2>"), "{page}");
+        assert!(!page.contains("theme-toggle"), "{page}");
+
+        let page = page_with("{{ title | no_such_filter }}");
+        assert!(page.starts_with("<!DOCTYPE html>"), "{page}");
+        assert!(
+            page.contains("<div class=\"template-notice\" role=\"alert\">Page template failed:"),
+            "{page}"
+        );
+    }
+
     // -----------------------------------------------------------------------
     // bd-3oh.2: PageShellContext / meta tag / backlinks HTML contract tests
     // -----------------------------------------------------------------------
@@ -1617,15 +3234,23 @@ mod tests {
         let ctx = PageShellContext {
             frontmatter: None,
             backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
             file_mtime_secs: Some(12345),
             page_url_path: Some("docs/test.md"),
-        full_width: false,
+            full_width: false,
+            locale: Locale::En,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: RenderOptions::default(),
         };
         let page = shell(
             &html_body,
             &headings,
             Path::new("/r/docs/test.md"),
-            Path::new("/r"),
             &ctx,
         );
         assert!(
@@ -1649,15 +3274,23 @@ mod tests {
         let ctx = PageShellContext {
             frontmatter: None,
             backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
             file_mtime_secs: None,
             page_url_path: None,
-        full_width: false,
+            full_width: false,
+            locale: Locale::En,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: RenderOptions::default(),
         };
         let page = shell(
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &ctx,
         );
         assert!(
@@ -1673,20 +3306,30 @@ mod tests {
             source_url_path: "/a.md".to_owned(),
             source_display: "My Title".to_owned(),
             snippet: "some context".to_owned(),
+            snippet_link: None,
             target_fragment: None,
+            link_text: String::new(),
         }];
         let (html_body, headings) = render("# Hi\n");
         let page = shell(
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &bls,
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -1702,20 +3345,30 @@ mod tests {
             source_url_path: "/docs/a.md".to_owned(),
             source_display: "docs/a.md".to_owned(),
             snippet: "context".to_owned(),
+            snippet_link: None,
             target_fragment: None,
+            link_text: String::new(),
         }];
         let (html_body, headings) = render("# Hi\n");
         let page = shell(
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &bls,
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -1732,20 +3385,30 @@ mod tests {
             source_url_path: "/a.md".to_owned(),
             source_display: "<script>xss</script>".to_owned(),
             snippet: "&amp;".to_owned(), // & → &amp;amp; after html_escape
+            snippet_link: None,
             target_fragment: None,
+            link_text: String::new(),
         }];
         let (html_body, headings) = render("# Hi\n");
         let page = shell(
             &html_body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &bls,
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         // source_display: <script>xss</script> → &lt;script&gt;xss&lt;/script&gt;
@@ -1796,13 +3459,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/doc.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: Some(&meta),
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
 
@@ -1825,13 +3496,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/doc.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: Some(&meta),
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
 
@@ -1847,13 +3526,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/fallback-name.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
 
@@ -1872,20 +3559,30 @@ mod tests {
             source_url_path: "/docs/ref.md".to_owned(),
             source_display: "Ref".to_owned(),
             snippet: "context".to_owned(),
+            snippet_link: None,
             target_fragment: None,
+            link_text: String::new(),
         }];
 
         let page = shell(
             &html_body,
             &headings,
             Path::new("/r/doc.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: Some(&meta),
                 backlinks: &backlinks,
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
 
@@ -1908,7 +3605,7 @@ mod tests {
             None,
         );
 
-        let html = render_frontmatter_html(Some(&meta));
+        let html = render_frontmatter_html(Some(&meta), Locale::En);
 
         assert!(html.contains("&lt;script&gt;"));
         assert!(html.contains("&quot;quoted&quot; &amp; &lt;tag&gt;"));
@@ -1920,8 +3617,8 @@ mod tests {
         eprintln!("scenario: frontmatter omission");
         let empty = fm_meta(vec![], None);
 
-        assert!(render_frontmatter_html(None).is_empty());
-        assert!(render_frontmatter_html(Some(&empty)).is_empty());
+        assert!(render_frontmatter_html(None, Locale::En).is_empty());
+        assert!(render_frontmatter_html(Some(&empty), Locale::En).is_empty());
     }
 
     #[test]
@@ -1929,7 +3626,7 @@ mod tests {
         eprintln!("scenario: frontmatter null");
         let meta = fm_meta(vec![fm_field("empty", MetaValue::Null)], None);
 
-        let html = render_frontmatter_html(Some(&meta));
+        let html = render_frontmatter_html(Some(&meta), Locale::En);
 
         assert!(html.contains("<span class=\"val-null\">null</span>"));
     }
@@ -1948,7 +3645,7 @@ mod tests {
             None,
         );
 
-        let html = render_frontmatter_html(Some(&meta));
+        let html = render_frontmatter_html(Some(&meta), Locale::En);
 
         assert!(html.contains("<span class=\"meta-tag\">alpha</span>"));
         assert!(html.contains("<span class=\"meta-tag\">beta</span>"));
@@ -1977,7 +3674,7 @@ mod tests {
             None,
         );
 
-        let html = render_frontmatter_html(Some(&meta));
+        let html = render_frontmatter_html(Some(&meta), Locale::En);
 
         assert!(html.contains("<dl class=\"frontmatter-fields\">"));
         assert!(html.contains("<dt>details</dt>"));
@@ -1995,7 +3692,7 @@ mod tests {
             Some("Doc"),
         );
 
-        let html = render_frontmatter_html(Some(&meta));
+        let html = render_frontmatter_html(Some(&meta), Locale::En);
 
         for tag in ["<h1", "<h2", "<h3", "<h4", "<h5", "<h6"] {
             assert!(!html.contains(tag), "frontmatter panel must not emit {tag}");
@@ -2011,13 +3708,21 @@ mod tests {
             &html_body,
             &headings,
             Path::new("/r/existing.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
 
@@ -2033,10 +3738,9 @@ mod tests {
         body_html: &str,
         headings: &[HeadingEntry],
         file_path: &Path,
-        serve_root: &Path,
         ctx: &PageShellContext,
     ) -> String {
-        build_page_shell(body_html, headings, file_path, serve_root, ctx, RenderTarget::Html)
+        build_page_shell(body_html, headings, file_path, ctx, RenderTarget::Html)
     }
 
     #[test]
@@ -2046,18 +3750,26 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(page.contains("<style>"), "CSS should be inlined");
         assert!(
-            !page.contains("href=\"/assets/mdmd.css\""),
+            !page.contains("href=\"/assets/"),
             "should not link to external CSS"
         );
     }
@@ -2069,13 +3781,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -2083,7 +3803,7 @@ mod tests {
             "JS should be inlined"
         );
         assert!(
-            !page.contains("src=\"/assets/mdmd.js\""),
+            !page.contains("src=\"/assets/"),
             "should not link to external JS"
         );
     }
@@ -2095,13 +3815,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -2117,13 +3845,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -2139,13 +3875,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -2154,6 +3898,115 @@ mod tests {
         );
     }
 
+    #[test]
+    fn search_box_is_serve_only() {
+        let (body, headings) = render("# Hello\n");
+        let ctx = PageShellContext {
+            frontmatter: None,
+            backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width: false,
+            locale: Locale::En,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: RenderOptions::default(),
+        };
+        let file = Path::new("/r/f.md");
+        let served = shell(&body, &headings, file, &ctx);
+        assert!(
+            served.contains("id=\"mdmd-search\""),
+            "search input: {served}"
+        );
+        assert!(served.contains(
+            "id=\"mdmd-search-results\" class=\"site-search-results\" role=\"listbox\" hidden"
+        ));
+        let exported = shell_html(&body, &headings, file, &ctx);
+        assert!(
+            !exported.contains("id=\"mdmd-search\""),
+            "search box should be absent"
+        );
+    }
+
+    #[test]
+    fn site_tree_is_serve_only() {
+        let (body, headings) = render("# Hello\n");
+        let ctx = PageShellContext {
+            frontmatter: None,
+            backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width: false,
+            locale: Locale::De,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: RenderOptions::default(),
+        };
+        let file = Path::new("/r/f.md");
+        let served = shell(&body, &headings, file, &ctx);
+        assert!(served.contains(
+            "<details class=\"site-tree\" id=\"mdmd-site-tree\">\n<summary>Alle Dateien</summary>\n\
+             <div class=\"site-tree-body\" data-src=\"/_mdmd/tree?format=html\"></div>"
+        ));
+        let exported = shell_html(&body, &headings, file, &ctx);
+        assert!(
+            !exported.contains("<details class=\"site-tree\""),
+            "tree should be absent"
+        );
+    }
+
+    #[test]
+    fn shell_labels_follow_locale() {
+        let (body, headings) = render("# Hallo\n");
+        let bls = [BacklinkRef {
+            source_url_path: "/a.md".to_owned(),
+            source_display: "a.md".to_owned(),
+            snippet: "see hallo".to_owned(),
+            snippet_link: None,
+            link_text: String::new(),
+            target_fragment: None,
+        }];
+        let ctx = PageShellContext {
+            frontmatter: None,
+            backlinks: &bls,
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width: false,
+            locale: Locale::De,
+            canonical_url: None,
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: RenderOptions::default(),
+        };
+        let page = shell(
+            &body,
+            &headings,
+            Path::new("/r/f.md"),
+            &ctx,
+        );
+        assert!(page.contains("<html lang=\"de\">"), "{page}");
+        assert!(page.contains("aria-label=\"Dunkelmodus umschalten\""));
+        assert!(page.contains("placeholder=\"Suchen (/)\""));
+        assert!(page.contains(">Rückverweise (1)</h2>"));
+        assert!(page.contains("href=\"#mdmd-content\">Zum Inhalt springen</a>"));
+        assert!(page.contains("aria-label=\"Inhaltsverzeichnis\""));
+        assert!(!page.contains("Toggle dark mode"));
+    }
+
     #[test]
     fn html_target_no_mtime_meta() {
         let (body, headings) = render("# Hello\n");
@@ -2161,13 +4014,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: Some(1234567890),
                 page_url_path: Some("/f.md"),
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -2187,13 +4048,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
-            full_width: false,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
         );
         assert!(
@@ -2252,13 +4121,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
                 full_width: true,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
             RenderTarget::Html,
         );
@@ -2276,13 +4153,21 @@ mod tests {
             &body,
             &headings,
             Path::new("/r/f.md"),
-            Path::new("/r"),
             &PageShellContext {
                 frontmatter: None,
                 backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
                 file_mtime_secs: None,
                 page_url_path: None,
                 full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
             },
             RenderTarget::Html,
         );
@@ -2292,4 +4177,157 @@ mod tests {
             &page[..200]
         );
     }
+
+    #[test]
+    fn social_meta_uses_first_paragraph_and_canonical_url() {
+        let source = "---\ntitle: Guide\nimage: img/cover.png\n---\n# Heading\n\n\
+Read *this* \"first\" &amp;\nthen more.\n\nSecond paragraph.\n";
+        let extracted = crate::frontmatter::extract(source);
+        let (body, headings) = render(extracted.render_body.as_ref());
+        let ctx = PageShellContext {
+            frontmatter: extracted.meta.as_ref(),
+            backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width: false,
+            locale: Locale::En,
+            canonical_url: Some("http://box:3333/docs/guide.md"),
+            site_title: None,
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: RenderOptions::default(),
+        };
+        let page = shell(
+            &body,
+            &headings,
+            Path::new("/r/docs/guide.md"),
+            &ctx,
+        );
+        assert!(page.contains("<meta property=\"og:title\" content=\"Guide\">"));
+        assert!(page.contains(
+            "<meta property=\"og:description\" content=\"Read this &quot;first&quot; &amp; then more.\">"
+        ), "{page}");
+        assert!(page.contains("<link rel=\"canonical\" href=\"http://box:3333/docs/guide.md\">"));
+        assert!(page.contains(
+            "<meta property=\"og:image\" content=\"http://box:3333/docs/img/cover.png\">"
+        ));
+        assert!(page.contains("content=\"summary_large_image\""));
+    }
+
+    #[test]
+    fn social_meta_without_url_omits_canonical_and_cuts_long_descriptions() {
+        let long = "word ".repeat(80);
+        let (body, headings) = render(&format!("# T\n\n{long}\n"));
+        let page = shell_html(
+            &body,
+            &headings,
+            Path::new("/r/f.md"),
+            &PageShellContext {
+                frontmatter: None,
+                backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
+                file_mtime_secs: None,
+                page_url_path: None,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
+            },
+        );
+        assert!(!page.contains("rel=\"canonical\""));
+        assert!(!page.contains("og:url"));
+        let description = page_description(None, &body).unwrap();
+        assert!(description.chars().count() <= crate::summary::SUMMARY_MAX_CHARS);
+        assert!(description.ends_with("word\u{2026}"), "{description}");
+        assert!(page.contains("content=\"summary\""));
+    }
+
+    #[test]
+    fn resolve_url_follows_browser_rules() {
+        let base = "http://h:1/docs/a.md";
+        assert_eq!(resolve_url(base, "b.png"), "http://h:1/docs/b.png");
+        assert_eq!(resolve_url(base, "./b.png"), "http://h:1/docs/b.png");
+        assert_eq!(resolve_url(base, "/b.png"), "http://h:1/b.png");
+        assert_eq!(resolve_url(base, "//cdn/b.png"), "http://cdn/b.png");
+        assert_eq!(resolve_url(base, "https://x/b.png"), "https://x/b.png");
+    }
+
+    #[test]
+    fn site_title_and_logo_brand_the_page() {
+        let extracted = crate::frontmatter::extract("---\nlogo: img/logo.svg\n---\n# Page\n");
+        let (body, headings) = render(extracted.render_body.as_ref());
+        let ctx = PageShellContext {
+            frontmatter: extracted.meta.as_ref(),
+            backlinks: &[],
+            mentions: &[],
+            outgoing: &[],
+            file_mtime_secs: None,
+            page_url_path: None,
+            full_width: false,
+            locale: Locale::En,
+            canonical_url: None,
+            site_title: Some("Handbook"),
+            allow_edit: false,
+            page_title: None,
+            encoding: None,
+            render: RenderOptions::default(),
+        };
+        let page = shell(
+            &body,
+            &headings,
+            Path::new("/r/f.md"),
+            &ctx,
+        );
+        assert!(page.contains("<title>Page · Handbook</title>"), "{page}");
+        assert!(page.contains("<meta property=\"og:site_name\" content=\"Handbook\">"));
+        assert!(page.contains(
+            "<header class=\"site-header\"><img class=\"site-logo\" src=\"img/logo.svg\" alt=\"\">\
+<a class=\"site-title\" href=\"/\">Handbook</a></header>"
+        ));
+
+        let exported = shell_html(
+            &body,
+            &headings,
+            Path::new("/r/f.md"),
+            &ctx,
+        );
+        assert!(exported.contains("<link rel=\"icon\" href=\"data:image/svg+xml,%3Csvg"));
+        assert!(exported.contains("<span class=\"site-title\">Handbook</span>"));
+    }
+
+    #[test]
+    fn page_without_branding_has_no_header() {
+        let (body, headings) = render("# Page\n");
+        let page = shell(
+            &body,
+            &headings,
+            Path::new("/r/f.md"),
+            &PageShellContext {
+                frontmatter: None,
+                backlinks: &[],
+                mentions: &[],
+                outgoing: &[],
+                file_mtime_secs: None,
+                page_url_path: None,
+                full_width: false,
+                locale: Locale::En,
+                canonical_url: None,
+                site_title: None,
+                allow_edit: false,
+                page_title: None,
+                encoding: None,
+                render: RenderOptions::default(),
+            },
+        );
+        assert!(page.contains("<title>Page · mdmd serve</title>"));
+        assert!(!page.contains("site-header"));
+    }
 }
@@ -1,10 +1,21 @@
 //! `mdmd html` subcommand: export a markdown file as a self-contained HTML page.
+//!
+//! With `--single-file` the argument is a directory instead, and every
+//! markdown file under it is written into one long page, as serve's
+//! `/_mdmd/combined` shows it.
 
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::{fs, io, process};
 
 use crate::frontmatter;
-use crate::html::{self, PageShellContext, RenderTarget};
+use crate::html::{self, HeadingEntry, PageShellContext, RenderTarget};
+use crate::i18n::Locale;
+use crate::include;
+use crate::queue::natural_cmp;
+use crate::tree::TreeNode;
+use crate::vars::{self, Vars};
 
 /// Run the `html` subcommand: read a markdown file and write a standalone HTML page.
 ///
@@ -12,7 +23,19 @@ use crate::html::{self, PageShellContext, RenderTarget};
 /// - `file`: path to the source markdown file.
 /// - `output`: optional explicit output path; defaults to `<stem>.html` next to the input.
 /// - `full_width`: whether to render in full-width mode (default `true`).
-pub fn run_html(file: &str, output: Option<&str>, full_width: bool) -> io::Result<()> {
+/// - `locale`: language of the page's own labels.
+/// - `site_title`: site name for `<title>` and the page header, if any.
+/// - `substitute_vars`: whether to substitute `{{var.name}}` placeholders.
+/// - `render`: optional rendering transformations.
+pub fn run_html(
+    file: &str,
+    output: Option<&str>,
+    full_width: bool,
+    locale: Locale,
+    site_title: Option<&str>,
+    substitute_vars: bool,
+    render: html::RenderOptions,
+) -> io::Result<()> {
     let input_path = Path::new(file);
 
     // Validate extension (same rules as other file-based commands).
@@ -44,48 +67,406 @@ pub fn run_html(file: &str, output: Option<&str>, full_width: bool) -> io::Resul
     });
 
     let canonical = fs::canonicalize(input_path).unwrap_or_else(|_| input_path.to_path_buf());
-    let parent = canonical.parent().unwrap_or(Path::new("."));
+    let page = standalone_page(
+        &source,
+        &canonical,
+        None,
+        full_width,
+        locale,
+        site_title,
+        substitute_vars,
+        render,
+    );
+
+    // Determine output path.
+    let output_path: PathBuf = match output {
+        Some(p) => PathBuf::from(p),
+        None => input_path.with_extension("html"),
+    };
+
+    // Write the file.
+    fs::write(&output_path, page)?;
+
+    // Print the written path to stdout.
+    println!("{}", output_path.display());
+
+    Ok(())
+}
+
+/// Run `html --single-file`: write every markdown file under `dir`, drafts
+/// aside, as one page.
+///
+/// The page goes to `output`, else to `<dir>.html` beside the directory.
+/// Links to the images and other files beside the markdown are written
+/// relative to where the page is written.
+pub fn run_combined(
+    dir: &str,
+    output: Option<&str>,
+    full_width: bool,
+    locale: Locale,
+    site_title: Option<&str>,
+    substitute_vars: bool,
+    render: html::RenderOptions,
+) -> io::Result<()> {
+    let root = match fs::canonicalize(dir) {
+        Ok(root) if root.is_dir() => root,
+        Ok(_) => {
+            eprintln!("Error: '{dir}' is not a directory.");
+            eprintln!("--single-file combines the markdown files under a directory.");
+            process::exit(1);
+        }
+        Err(_) => {
+            eprintln!("Error: directory not found: {dir}");
+            process::exit(1);
+        }
+    };
+    let Some(name) = root.file_name().and_then(|n| n.to_str()).map(str::to_owned) else {
+        eprintln!("Error: cannot name the page for '{dir}'; pass --output.");
+        process::exit(1);
+    };
+    let nodes = crate::tree::markdown_tree(&root, false, None);
+    let parts: Vec<CombinedPart> = reading_order(&nodes)
+        .into_iter()
+        .map(|node| CombinedPart {
+            path: root.join(node.path.trim_start_matches('/')),
+            url: node.path.clone(),
+        })
+        .collect();
+    if parts.is_empty() {
+        eprintln!("Error: no markdown files under '{dir}'.");
+        process::exit(1);
+    }
+
+    let output_path: PathBuf = match output {
+        Some(p) => PathBuf::from(p),
+        None => root.with_file_name(format!("{name}.html")),
+    };
+    // Where the page lands decides how it reaches the files it links to.
+    let page_dir = output_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let link_base = match fs::canonicalize(page_dir)
+        .ok()
+        .and_then(|page_dir| root.strip_prefix(page_dir).ok().map(Path::to_path_buf))
+    {
+        Some(rel) if rel.as_os_str().is_empty() => String::new(),
+        Some(rel) => format!("{}/", rel.to_string_lossy()),
+        None => format!("{}/", root.display()),
+    };
+
+    let page = combined_page(
+        &parts,
+        &root,
+        &name,
+        &link_base,
+        full_width,
+        locale,
+        site_title,
+        substitute_vars,
+        render,
+    );
+    fs::write(&output_path, page)?;
+    println!("{}", output_path.display());
+    Ok(())
+}
+
+/// A markdown file in a combined page.
+pub struct CombinedPart {
+    pub path: PathBuf,
+    /// URL path of the file under the root, with a leading slash.
+    pub url: String,
+}
+
+/// The files of a markdown tree in reading order: in each directory its
+/// `README.md` or `index.md` first, then its other files, then its
+/// subdirectories, names in natural order.
+pub fn reading_order(nodes: &[TreeNode]) -> Vec<&TreeNode> {
+    let mut sorted: Vec<&TreeNode> = nodes.iter().collect();
+    sorted.sort_by(|a, b| {
+        let rank = |node: &TreeNode| match &node.children {
+            Some(_) => 2,
+            None if is_index_name(&node.name) => 0,
+            None => 1,
+        };
+        rank(a)
+            .cmp(&rank(b))
+            .then_with(|| natural_cmp(&a.name, &b.name))
+    });
+    let mut files = Vec::new();
+    for node in sorted {
+        match &node.children {
+            Some(children) => files.extend(reading_order(children)),
+            None => files.push(node),
+        }
+    }
+    files
+}
 
+fn is_index_name(name: &str) -> bool {
+    name.eq_ignore_ascii_case("README.md") || name.eq_ignore_ascii_case("index.md")
+}
+
+/// Render `parts`, markdown files under `root`, one after another as a
+/// single self-contained page titled `title`.
+///
+/// Each file is a `<section>` whose id is made from its path, and its
+/// heading anchors are prefixed with that id so they stay unique.  Links
+/// from one part to another jump to its section, and other root-relative
+/// links are written below `link_base`: `/` when serving, the way from the
+/// page to `root` when exporting.
+#[allow(clippy::too_many_arguments)]
+pub fn combined_page(
+    parts: &[CombinedPart],
+    root: &Path,
+    title: &str,
+    link_base: &str,
+    full_width: bool,
+    locale: Locale,
+    site_title: Option<&str>,
+    substitute_vars: bool,
+    render: html::RenderOptions,
+) -> String {
+    let mut used = HashSet::new();
+    let anchors: Vec<String> = parts
+        .iter()
+        .map(|part| {
+            let base = part_anchor(&part.url);
+            let mut anchor = base.clone();
+            let mut n = 1;
+            while !used.insert(anchor.clone()) {
+                n += 1;
+                anchor = format!("{base}-{n}");
+            }
+            anchor
+        })
+        .collect();
+    let by_url: HashMap<&str, &str> = parts
+        .iter()
+        .zip(&anchors)
+        .map(|(part, anchor)| (part.url.as_str(), anchor.as_str()))
+        .collect();
+    // Each part renders its own H1s; none is lifted into the page title.
+    let part_render = html::RenderOptions {
+        base_path: root.to_path_buf(),
+        title_from_h1: false,
+        ..render.clone()
+    };
+
+    let mut body = String::new();
+    let mut headings: Vec<HeadingEntry> = Vec::new();
+    for (part, anchor) in parts.iter().zip(&anchors) {
+        let Ok(source) = fs::read_to_string(&part.path) else {
+            continue;
+        };
+        let extracted = frontmatter::extract(&source);
+        let mut text = include::expand(extracted.render_body.as_ref(), &part.path, Some(root)).text;
+        if substitute_vars {
+            let vars = Vars::for_document(&part.path, extracted.meta.as_ref(), Some(root));
+            text = Cow::Owned(vars::substitute(&text, &vars).into_owned());
+        }
+        // The serve target makes local links root-relative, so they can be
+        // matched against the other parts.
+        let rendered =
+            html::render_markdown_with(&text, &part.path, RenderTarget::Serve, false, &part_render);
+        let own: HashSet<&str> = rendered
+            .headings
+            .iter()
+            .map(|h| h.anchor_id.as_str())
+            .collect();
+        let html = combined_links(&rendered.html, &by_url, (anchor.as_str(), &own), link_base);
+        let source_name = crate::serve::html_escape_text(part.url.trim_start_matches('/'));
+        body.push_str(&format!(
+            "<section class=\"combined-part\" id=\"{anchor}\"><p class=\"combined-source\"><a href=\"#{anchor}\">{source_name}</a></p>{html}</section>"
+        ));
+        headings.extend(rendered.headings.into_iter().map(|h| HeadingEntry {
+            anchor_id: format!("{anchor}--{}", h.anchor_id),
+            ..h
+        }));
+    }
+
+    let ctx = PageShellContext {
+        frontmatter: None,
+        backlinks: &[],
+        mentions: &[],
+        outgoing: &[],
+        file_mtime_secs: None,
+        page_url_path: None,
+        full_width,
+        locale,
+        canonical_url: None,
+        site_title,
+        allow_edit: false,
+        page_title: Some(title),
+        encoding: None,
+        render,
+    };
+    html::build_page_shell(&body, &headings, root, &ctx, RenderTarget::Html)
+}
+
+/// Section id for the part at URL path `url`: its path with runs of other
+/// characters than letters and digits turned into `-`.
+fn part_anchor(url: &str) -> String {
+    let mut anchor = String::new();
+    for c in url.to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            anchor.push(c);
+        } else if !anchor.is_empty() && !anchor.ends_with('-') {
+            anchor.push('-');
+        }
+    }
+    anchor.trim_end_matches('-').to_owned()
+}
+
+/// `html`, a part rendered for a combined page, with its `href` and `src`
+/// URLs rewritten: `#heading` within the part, given as its `(anchor,
+/// heading anchors)`, links to other parts (see `by_url`) to their
+/// sections, and other root-relative URLs below `link_base`.
+fn combined_links(
+    html: &str,
+    by_url: &HashMap<&str, &str>,
+    (own, own_headings): (&str, &HashSet<&str>),
+    link_base: &str,
+) -> String {
+    let rewrite = |url: &str| -> String {
+        if let Some(fragment) = url.strip_prefix('#') {
+            return if own_headings.contains(fragment) {
+                format!("#{own}--{fragment}")
+            } else {
+                url.to_owned()
+            };
+        }
+        let Some(path) = url.strip_prefix('/').filter(|p| !p.starts_with('/')) else {
+            return url.to_owned();
+        };
+        let (file, fragment) = match path.split_once('#') {
+            Some((file, fragment)) => (file, Some(fragment)),
+            None => (path, None),
+        };
+        let key = crate::serve::percent_decode(file).unwrap_or_else(|_| file.to_owned());
+        match (by_url.get(format!("/{key}").as_str()), fragment) {
+            (Some(anchor), Some(fragment)) => format!("#{anchor}--{fragment}"),
+            (Some(anchor), None) => format!("#{anchor}"),
+            (None, _) => format!("{link_base}{path}"),
+        }
+    };
+
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(start) = [" href=\"", " src=\""]
+        .iter()
+        .filter_map(|attr| rest.find(attr).map(|at| at + attr.len()))
+        .min()
+    {
+        let Some(len) = rest[start..].find('"') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        out.push_str(&rewrite(&rest[start..start + len]));
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Render `source`, the markdown of the file at `canonical`, as a
+/// self-contained HTML page: CSS inlined, no scripts, and authored relative
+/// links kept as written.  With a `root`, includes and variables are
+/// resolved within it, as when serving.
+#[allow(clippy::too_many_arguments)]
+pub fn standalone_page(
+    source: &str,
+    canonical: &Path,
+    root: Option<&Path>,
+    full_width: bool,
+    locale: Locale,
+    site_title: Option<&str>,
+    substitute_vars: bool,
+    render: html::RenderOptions,
+) -> String {
     // Extract frontmatter.
-    let extracted = frontmatter::extract(&source);
+    let extracted = frontmatter::extract(source);
+    let mut body = include::expand(extracted.render_body.as_ref(), canonical, root).text;
+    if substitute_vars {
+        let vars = Vars::for_document(canonical, extracted.meta.as_ref(), root);
+        body = Cow::Owned(vars::substitute(&body, &vars).into_owned());
+    }
 
     // Render markdown with Html target (preserves authored relative links).
-    let (html_body, headings) = html::render_markdown(
-        extracted.render_body.as_ref(),
-        &canonical,
-        parent, // serve_root is unused for Html target but required by the signature
-        RenderTarget::Html,
-        false,
-    );
+    let rendered = html::render_markdown_with(&body, canonical, RenderTarget::Html, false, &render);
 
-    // Build page shell with no backlinks, no mtime, no url path.
+    // Build page shell with no backlinks, no mtime, no url path, and no
+    // canonical URL (an exported file has no address of its own).
     let ctx = PageShellContext {
         frontmatter: extracted.meta.as_ref(),
         backlinks: &[],
+        mentions: &[],
+        outgoing: &[],
         file_mtime_secs: None,
         page_url_path: None,
         full_width,
+        locale,
+        canonical_url: None,
+        site_title,
+        allow_edit: false,
+        page_title: rendered.title.as_deref(),
+        encoding: None,
+        render,
     };
-    let page = html::build_page_shell(
-        &html_body,
-        &headings,
-        &canonical,
-        parent,
+    html::build_page_shell(
+        &rendered.html,
+        &rendered.headings,
+        canonical,
         &ctx,
         RenderTarget::Html,
-    );
+    )
+}
 
-    // Determine output path.
-    let output_path: PathBuf = match output {
-        Some(p) => PathBuf::from(p),
-        None => input_path.with_extension("html"),
-    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // Write the file.
-    fs::write(&output_path, page)?;
+    fn file(name: &str) -> TreeNode {
+        TreeNode {
+            name: name.to_owned(),
+            path: format!("/{name}"),
+            href: format!("/{name}"),
+            children: None,
+        }
+    }
 
-    // Print the written path to stdout.
-    println!("{}", output_path.display());
+    #[test]
+    fn the_index_comes_first_and_subdirectories_last() {
+        let mut api = file("api");
+        api.children = Some(vec![file("ch10.md"), file("ch2.md"), file("index.md")]);
+        let nodes = vec![api, file("z.md"), file("README.md"), file("a.md")];
+        let names: Vec<&str> = reading_order(&nodes)
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            ["README.md", "a.md", "z.md", "index.md", "ch2.md", "ch10.md"]
+        );
+        assert_eq!(part_anchor("/docs/User Guide.md"), "docs-user-guide-md");
+    }
 
-    Ok(())
+    #[test]
+    fn links_between_parts_jump_to_their_sections() {
+        let by_url = HashMap::from([("/docs/user guide.md", "docs-user-guide-md")]);
+        let own = HashSet::from(["setup"]);
+        let html = concat!(
+            r##"<a href="#setup">s</a><sup><a href="#fn-1">1</a></sup>"##,
+            r##"<a href="/docs/user%20guide.md#install">i</a><a href="/docs/user%20guide.md">g</a>"##,
+            r##"<img src="/docs/img/logo.png" alt=""><a href="https://example.com/">e</a>"##,
+        );
+        assert_eq!(
+            combined_links(html, &by_url, ("intro-md", &own), "site/"),
+            concat!(
+                r##"<a href="#intro-md--setup">s</a><sup><a href="#fn-1">1</a></sup>"##,
+                r##"<a href="#docs-user-guide-md--install">i</a><a href="#docs-user-guide-md">g</a>"##,
+                r##"<img src="site/docs/img/logo.png" alt=""><a href="https://example.com/">e</a>"##,
+            )
+        );
+    }
 }
@@ -0,0 +1,240 @@
+//! Terminal hyperlinks (OSC 8).
+//!
+//! Terminals that support OSC 8 make text between an opening and a closing
+//! sequence clickable, like an `<a>` element.  The TUI redraws the links on
+//! screen wrapped in these sequences after each frame; the rest of the
+//! interface is unaffected, and terminals without support ignore them.
+//!
+//! Link destinations must be absolute URIs, so relative links and `#anchor`
+//! links are turned into `file://` URLs against the document they appear in.
+
+use std::io::{self, Write};
+use std::path::Path;
+
+use mdmd::render::{self, RenderedDocument};
+use ratatui::{
+    backend::Backend,
+    buffer::{Buffer, Cell},
+    layout::{Position, Rect},
+    text::Span,
+};
+
+/// Whether hyperlinks should be written to a terminal whose `TERM` is
+/// `term`.  The Linux console and dumb terminals print OSC 8 as garbage;
+/// everything else either supports it or ignores it.
+pub fn supported(term: Option<&str>) -> bool {
+    !matches!(term, None | Some("" | "dumb" | "linux"))
+}
+
+/// Whether the current terminal gets hyperlinks, per [`supported`].
+pub fn terminal_supports() -> bool {
+    supported(std::env::var("TERM").ok().as_deref())
+}
+
+/// Sequence that starts a hyperlink to `url`.
+pub fn open(url: &str) -> String {
+    format!("\x1b]8;;{url}\x1b\\")
+}
+
+/// Sequence that ends the current hyperlink.
+pub const CLOSE: &str = "\x1b]8;;\x1b\\";
+
+/// The absolute URI a link to `url` in `document` points at: URLs with a
+/// scheme as written, and paths and `#anchors` as `file://` URLs.
+pub fn target_uri(url: &str, document: &Path) -> Option<String> {
+    if url.is_empty() {
+        return None;
+    }
+    if has_scheme(url) {
+        return Some(url.to_owned());
+    }
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (url, None),
+    };
+    let file = if path.is_empty() {
+        document.to_path_buf()
+    } else {
+        document.parent().unwrap_or(Path::new("")).join(path)
+    };
+    let file = if file.is_absolute() {
+        file
+    } else {
+        std::env::current_dir().ok()?.join(file)
+    };
+    let mut uri = String::from("file://");
+    for component in file.components() {
+        use std::path::Component;
+        match component {
+            Component::Normal(name) => {
+                uri.push('/');
+                uri.push_str(&encode(&name.to_string_lossy()));
+            }
+            Component::Prefix(prefix) => {
+                // A Windows drive, as in file:///C:/docs.
+                uri.push('/');
+                uri.push_str(&prefix.as_os_str().to_string_lossy());
+            }
+            Component::ParentDir => uri.push_str("/.."),
+            Component::RootDir | Component::CurDir => {}
+        }
+    }
+    if let Some(fragment) = fragment {
+        uri.push('#');
+        uri.push_str(&encode(fragment));
+    }
+    Some(uri)
+}
+
+/// Cells to write again after a frame, under a hyperlink or none.
+pub struct Run {
+    pub uri: Option<String>,
+    pub cells: Vec<(u16, u16, Cell)>,
+}
+
+/// The runs to write over a frame drawn as `buffer`: first the cells
+/// `linked` on the previous frame, without a hyperlink, so a link that
+/// scrolled away stops being clickable; then the cells of each link of
+/// `rendered` visible in the document `area`, scrolled by `scroll_offset`.
+/// `linked` is updated to the cells linked on this frame.  `area` is `None`
+/// when no link should be clickable, e.g. under a modal.
+pub fn runs(
+    buffer: &Buffer,
+    area: Option<Rect>,
+    rendered: &RenderedDocument,
+    scroll_offset: usize,
+    document: &Path,
+    linked: &mut Vec<Position>,
+) -> Vec<Run> {
+    let cell = |pos: Position| buffer.cell(pos).map(|cell| (pos.x, pos.y, cell.clone()));
+    let mut runs = vec![Run {
+        uri: None,
+        cells: linked.drain(..).filter_map(cell).collect(),
+    }];
+    let Some(area) = area else {
+        return runs;
+    };
+    for link in &rendered.link_positions {
+        let Some(row) = link
+            .rendered_line
+            .checked_sub(scroll_offset)
+            .filter(|&row| row < area.height as usize)
+        else {
+            continue;
+        };
+        let Some(uri) = target_uri(&link.url, document) else {
+            continue;
+        };
+        let shift = render::alignment_offset(
+            &rendered.text.lines[link.rendered_line],
+            area.width as usize,
+        );
+        let mut cells = Vec::new();
+        let mut col = link.column_start + shift;
+        while col < link.column_end + shift {
+            let pos = Position::new(area.x + col as u16, area.y + row as u16);
+            let Some(cell) = cell(pos) else {
+                break;
+            };
+            // A wide character covers the cells after it, which must not
+            // be written over it.
+            col += Span::raw(cell.2.symbol()).width().max(1);
+            linked.push(pos);
+            cells.push(cell);
+        }
+        runs.push(Run {
+            uri: Some(uri),
+            cells,
+        });
+    }
+    runs
+}
+
+/// Write `runs` to the terminal behind `backend`, each inside its
+/// hyperlink.
+pub fn write<B>(backend: &mut B, runs: &[Run]) -> io::Result<()>
+where
+    B: Backend<Error = io::Error> + Write,
+{
+    for run in runs.iter().filter(|run| !run.cells.is_empty()) {
+        if let Some(uri) = &run.uri {
+            backend.write_all(open(uri).as_bytes())?;
+        }
+        backend.draw(run.cells.iter().map(|(x, y, cell)| (*x, *y, cell)))?;
+        if run.uri.is_some() {
+            backend.write_all(CLOSE.as_bytes())?;
+        }
+    }
+    Backend::flush(backend)
+}
+
+/// Whether `url` starts with a URI scheme such as `https:` or `mailto:`.
+fn has_scheme(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Percent-encode the bytes of a path segment or fragment that may not
+/// appear in a URI as is.
+fn encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~!$&'()*+,;=:@".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_incapable_terminals_are_excluded() {
+        assert!(supported(Some("xterm-256color")));
+        assert!(supported(Some("tmux-256color")));
+        assert!(!supported(Some("dumb")));
+        assert!(!supported(Some("linux")));
+        assert!(!supported(None));
+    }
+
+    #[test]
+    fn links_become_absolute_uris() {
+        let doc = Path::new("/docs/guide.md");
+        assert_eq!(
+            target_uri("https://example.com/a?b#c", doc).unwrap(),
+            "https://example.com/a?b#c"
+        );
+        assert_eq!(
+            target_uri("mailto:me@example.com", doc).unwrap(),
+            "mailto:me@example.com"
+        );
+        assert_eq!(
+            target_uri("my notes.md#part two", doc).unwrap(),
+            "file:///docs/my%20notes.md#part%20two"
+        );
+        assert_eq!(
+            target_uri("#install", doc).unwrap(),
+            "file:///docs/guide.md#install"
+        );
+        assert_eq!(
+            target_uri("../README.md", doc).unwrap(),
+            "file:///docs/../README.md"
+        );
+        assert_eq!(target_uri("", doc), None);
+    }
+
+    #[test]
+    fn sequences_wrap_text() {
+        let link = format!("{}docs{CLOSE}", open("https://example.com"));
+        assert_eq!(link, "\x1b]8;;https://example.com\x1b\\docs\x1b]8;;\x1b\\");
+    }
+}
@@ -0,0 +1,458 @@
+//! Message catalog for user-facing TUI and HTML page-shell strings.
+//!
+//! Strings are looked up gettext-style: the English text is the message id,
+//! and each other locale maps ids to translations.  Ids missing from a
+//! catalog fall back to English, so an incomplete translation degrades to
+//! mixed-language text rather than failing.  Placeholders are written
+//! `{name}` and filled by [`Locale::trf`].
+//!
+//! The locale comes from the `ui.locale` setting, else from `LC_ALL`,
+//! `LC_MESSAGES`, or `LANG` (see [`Locale::from_env`]).
+
+use std::fmt::Display;
+
+/// A supported UI language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+}
+
+impl Locale {
+    /// Every supported locale, English first.
+    pub const ALL: &'static [Locale] = &[Locale::En, Locale::De];
+
+    /// BCP 47 language tag, as used for `<html lang>`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+        }
+    }
+
+    /// Parse a language tag or POSIX locale name (`de`, `de-AT`,
+    /// `de_DE.UTF-8`).  `C` and `POSIX` are English.  Returns `None` for
+    /// unsupported languages.
+    pub fn parse(name: &str) -> Option<Locale> {
+        let lang = name
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match lang.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+
+    /// The locale named by the first non-empty of `LC_ALL`, `LC_MESSAGES`,
+    /// and `LANG` (POSIX precedence), falling back to English when it is
+    /// unset or unsupported.
+    pub fn from_env() -> Locale {
+        Self::from_vars(|var| std::env::var(var).ok())
+    }
+
+    fn from_vars(get: impl Fn(&str) -> Option<String>) -> Locale {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| get(var).filter(|v| !v.is_empty()))
+            .next()
+            .and_then(|v| Locale::parse(&v))
+            .unwrap_or_default()
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Locale::En => &[],
+            Locale::De => DE,
+        }
+    }
+
+    /// Translate `msgid`, falling back to the id itself.
+    pub fn tr(self, msgid: &'static str) -> &'static str {
+        self.catalog()
+            .iter()
+            .find(|(id, _)| *id == msgid)
+            .map_or(msgid, |(_, text)| text)
+    }
+
+    /// Translate `msgid` and substitute its `{name}` placeholders.
+    pub fn trf(self, msgid: &'static str, args: &[(&str, &dyn Display)]) -> String {
+        let mut out = self.tr(msgid).to_owned();
+        for (name, value) in args {
+            out = out.replace(&format!("{{{name}}}"), &value.to_string());
+        }
+        out
+    }
+}
+
+/// German catalog.
+const DE: &[(&str, &str)] = &[
+    // TUI status bar and prompts
+    ("Terminal too small", "Terminal zu klein"),
+    ("Line {line}/{total}", "Zeile {line}/{total}"),
+    ("Empty", "Leer"),
+    ("All", "Alles"),
+    ("Top", "Anfang"),
+    ("Bot", "Ende"),
+    ("No matches", "Keine Treffer"),
+    ("No matches for \"{query}\"", "Keine Treffer für „{query}“"),
+    ("No such file: {url}", "Datei nicht gefunden: {url}"),
+    ("No such heading: {url}", "Überschrift nicht gefunden: {url}"),
+    ("Replace: ", "Ersetzen: "),
+    ("Save section to: ", "Abschnitt speichern unter: "),
+    ("{path} already exists", "{path} existiert bereits"),
+    ("Saved the section to {path}", "Abschnitt in {path} gespeichert"),
+    (
+        "Could not write {path}: {error}",
+        "{path} konnte nicht geschrieben werden: {error}",
+    ),
+    ("Copied the section \"{heading}\"", "Abschnitt „{heading}“ kopiert"),
+    ("Could not copy: {error}", "Kopieren fehlgeschlagen: {error}"),
+    (
+        "No heading above the current line",
+        "Keine Überschrift über der aktuellen Zeile",
+    ),
+    (
+        "The current heading comes from an included file",
+        "Die aktuelle Überschrift stammt aus einer eingebundenen Datei",
+    ),
+    ("Could not read the document", "Das Dokument konnte nicht gelesen werden"),
+    ("Replace \"{find}\" with: ", "„{find}“ ersetzen durch: "),
+    (
+        "{accepted}/{total} accepted \u{2014} y/n accept/reject  Space toggle  a/r all  Enter apply  Esc cancel",
+        "{accepted}/{total} übernommen \u{2014} y/n übernehmen/verwerfen  Leertaste umschalten  a/r alle  Enter anwenden  Esc abbrechen",
+    ),
+    (
+        " Replace \"{find}\" \u{2192} \"{replacement}\" ",
+        " „{find}“ \u{2192} „{replacement}“ ersetzen ",
+    ),
+    ("1 occurrence", "1 Vorkommen"),
+    ("{n} occurrences", "{n} Vorkommen"),
+    ("1 file", "1 Datei"),
+    ("{n} files", "{n} Dateien"),
+    ("Replaced {occurrences} in {files}", "{occurrences} in {files} ersetzt"),
+    ("skipped {n} changed since preview", "{n} seit der Vorschau geändert, übersprungen"),
+    ("{n} failed ({path}: {error})", "{n} fehlgeschlagen ({path}: {error})"),
+    ("No handler command for {name}", "Kein Programm zum Öffnen von {name}"),
+    ("Opened {name} with {command}", "{name} mit {command} geöffnet"),
+    (
+        "Failed to open {name} with {command}: {error}",
+        "{name} konnte nicht mit {command} geöffnet werden: {error}",
+    ),
+    (
+        "No README.md or index.md in {dir}",
+        "Keine README.md oder index.md in {dir}",
+    ),
+    (
+        "Cannot open {path}: {error}",
+        "{path} kann nicht geöffnet werden: {error}",
+    ),
+    (
+        "Showing tabs, non-breaking spaces, and trailing spaces",
+        "Tabs, geschützte und nachgestellte Leerzeichen werden angezeigt",
+    ),
+    ("Hiding whitespace markers", "Leerraum-Markierungen ausgeblendet"),
+    ("Showing the markdown source", "Markdown-Quelltext wird angezeigt"),
+    ("Showing the rendered document", "Gerendertes Dokument wird angezeigt"),
+    (
+        "Showing the markdown source beside the rendering",
+        "Markdown-Quelltext wird neben dem Dokument angezeigt",
+    ),
+    ("Hiding the markdown source", "Markdown-Quelltext ausgeblendet"),
+    ("No task on the current line", "Keine Aufgabe in der aktuellen Zeile"),
+    ("File {n} of {count}", "Datei {n} von {count}"),
+    ("Only one file was given", "Es wurde nur eine Datei angegeben"),
+    ("Already at the last file", "Bereits bei der letzten Datei"),
+    ("Already at the first file", "Bereits bei der ersten Datei"),
+    (
+        "Cannot toggle task: {error}",
+        "Aufgabe kann nicht umgeschaltet werden: {error}",
+    ),
+    // TUI overlays
+    (" Outline ", " Gliederung "),
+    (" Go to heading ", " Zu Überschrift springen "),
+    (" No matching headings", " Keine passenden Überschriften"),
+    (" Open file ({n}) ", " Datei öffnen ({n}) "),
+    (" No matching files", " Keine passenden Dateien"),
+    (" Listing files...", " Dateien werden aufgelistet …"),
+    (" History ", " Verlauf "),
+    (" Backlinks ({count}) ", " Rückverweise ({count}) "),
+    ("No backlinks to this file", "Keine Rückverweise auf diese Datei"),
+    (" Outgoing links ({count}) ", " Ausgehende Links ({count}) "),
+    ("No links in this file", "Keine Links in dieser Datei"),
+    ("just now", "gerade eben"),
+    ("{age} ago", "vor {age}"),
+    ("Start page", "Startseite"),
+    ("READMEs", "READMEs"),
+    ("No README files in this project.", "Keine README-Dateien in diesem Projekt."),
+    ("and {n} more", "und {n} weitere"),
+    ("Recent files", "Zuletzt geöffnet"),
+    ("No files opened here yet.", "Hier wurden noch keine Dateien geöffnet."),
+    ("Sessions", "Sitzungen"),
+    (
+        "No saved sessions here; `mdmd view --session NAME FILE` starts one.",
+        "Keine gespeicherten Sitzungen hier; `mdmd view --session NAME FILE` beginnt eine.",
+    ),
+    (" Type to filter...", " Zum Filtern tippen …"),
+    (" No matching shortcuts", " Keine passenden Tastenkürzel"),
+    (" Help \u{2014} ? to close ", " Hilfe \u{2014} ? zum Schließen "),
+    (" Help \u{2014} Esc to close ", " Hilfe \u{2014} Esc zum Schließen "),
+    // TUI shortcut help
+    ("Navigation", "Navigation"),
+    ("Scroll down (--scroll-step lines)", "Nach unten scrollen (--scroll-step Zeilen)"),
+    ("Scroll up (--scroll-step lines)", "Nach oben scrollen (--scroll-step Zeilen)"),
+    ("Scroll down half page", "Eine halbe Seite nach unten"),
+    ("Scroll up half page", "Eine halbe Seite nach oben"),
+    ("Scroll down full page", "Eine Seite nach unten"),
+    ("Scroll up full page", "Eine Seite nach oben"),
+    ("Recentre view on current line", "Ansicht auf aktuelle Zeile zentrieren"),
+    ("Jump to top", "Zum Anfang springen"),
+    ("Jump to bottom", "Zum Ende springen"),
+    ("Headings", "Überschriften"),
+    ("Next heading", "Nächste Überschrift"),
+    ("Previous heading", "Vorherige Überschrift"),
+    ("Open outline", "Gliederung öffnen"),
+    (
+        "Go to heading by typing part of its name",
+        "Zu einer Überschrift springen, indem man einen Teil ihres Namens tippt",
+    ),
+    ("Search", "Suche"),
+    ("Start search", "Suche starten"),
+    ("Next search match", "Nächster Treffer"),
+    ("Previous search match", "Vorheriger Treffer"),
+    ("Confirm search", "Suche bestätigen"),
+    ("Cancel search", "Suche abbrechen"),
+    ("Find and replace across files", "In allen Dateien suchen und ersetzen"),
+    ("Editing", "Bearbeiten"),
+    (
+        "Toggle task checkbox on the current line",
+        "Aufgaben-Kästchen in der aktuellen Zeile umschalten",
+    ),
+    (
+        "Copy the markdown of the current section",
+        "Markdown des aktuellen Abschnitts kopieren",
+    ),
+    (
+        "Save the markdown of the current section to a file",
+        "Markdown des aktuellen Abschnitts in einer Datei speichern",
+    ),
+    ("Links", "Links"),
+    ("Next link", "Nächster Link"),
+    ("Previous link", "Vorheriger Link"),
+    ("Follow focused link", "Ausgewähltem Link folgen"),
+    ("Navigate back", "Zurück"),
+    ("Navigate forward", "Vorwärts"),
+    ("Show visited files history", "Verlauf besuchter Dateien anzeigen"),
+    (
+        "Switch between the rendering and the markdown source",
+        "Zwischen gerenderter Ansicht und Markdown-Quelltext wechseln",
+    ),
+    (
+        "Show or hide the markdown source beside the rendering",
+        "Markdown-Quelltext neben dem Dokument ein- oder ausblenden",
+    ),
+    ("Run any command by name", "Beliebigen Befehl über seinen Namen ausführen"),
+    (" Commands ", " Befehle "),
+    (" No matching commands", " Keine passenden Befehle"),
+    (
+        "Rewrap paragraphs to COLUMNS, or keep their lines with 0",
+        "Absätze auf COLUMNS Spalten umbrechen, mit 0 ihre Zeilen behalten",
+    ),
+    (
+        "Justify rewrapped paragraphs or stop",
+        "Umbrochene Absätze im Blocksatz setzen oder nicht mehr",
+    ),
+    (
+        "Break words at soft hyphens or stop",
+        "Wörter an weichen Trennstrichen trennen oder nicht mehr",
+    ),
+    (
+        "Number the headings or stop",
+        "Überschriften nummerieren oder nicht mehr",
+    ),
+    (
+        "Use smart punctuation or stop",
+        "Typografische Satzzeichen verwenden oder nicht mehr",
+    ),
+    (
+        "Highlight code for a light or a dark background",
+        "Code für hellen oder dunklen Hintergrund hervorheben",
+    ),
+    (
+        "Serve this document and open it in the browser",
+        "Dieses Dokument bereitstellen und im Browser öffnen",
+    ),
+    (
+        "Paragraphs keep their source lines",
+        "Absätze behalten ihre Quellzeilen",
+    ),
+    (
+        "Paragraphs rewrapped to {cols} columns",
+        "Absätze auf {cols} Spalten umbrochen",
+    ),
+    ("Usage: width COLUMNS", "Verwendung: width COLUMNS"),
+    ("Justifying rewrapped paragraphs", "Umbrochene Absätze im Blocksatz"),
+    ("Not justifying paragraphs", "Kein Blocksatz"),
+    (
+        "Breaking words at soft hyphens",
+        "Wörter werden an weichen Trennstrichen getrennt",
+    ),
+    (
+        "Not breaking words at soft hyphens",
+        "Keine Trennung an weichen Trennstrichen",
+    ),
+    ("Numbering headings", "Überschriften werden nummeriert"),
+    ("Not numbering headings", "Überschriften werden nicht nummeriert"),
+    ("Using smart punctuation", "Typografische Satzzeichen aktiv"),
+    ("Not using smart punctuation", "Typografische Satzzeichen aus"),
+    (
+        "Highlighting code for a light background",
+        "Code-Hervorhebung für hellen Hintergrund",
+    ),
+    (
+        "Highlighting code for a dark background",
+        "Code-Hervorhebung für dunklen Hintergrund",
+    ),
+    ("Only files can be served", "Nur Dateien können bereitgestellt werden"),
+    (
+        "Could not start the server: {error}",
+        "Server konnte nicht gestartet werden: {error}",
+    ),
+    ("Serving at {url}", "Bereitgestellt unter {url}"),
+    (
+        "Show or hide tabs, non-breaking and trailing spaces",
+        "Tabs, geschützte und nachgestellte Leerzeichen ein- oder ausblenden",
+    ),
+    (
+        "Open a project file by typing part of its path",
+        "Eine Projektdatei öffnen, indem man einen Teil ihres Pfads tippt",
+    ),
+    ("Show backlinks to this file", "Rückverweise auf diese Datei anzeigen"),
+    (
+        "Show outgoing links and whether they resolve",
+        "Ausgehende Links und ihre Erreichbarkeit anzeigen",
+    ),
+    ("Jump back to previous position", "Zur vorherigen Position springen"),
+    ("Jump forward to next position", "Zur nächsten Position springen"),
+    ("Files", "Dateien"),
+    (
+        "Next file given on the command line",
+        "Nächste auf der Kommandozeile angegebene Datei",
+    ),
+    (
+        "Previous file given on the command line",
+        "Vorherige auf der Kommandozeile angegebene Datei",
+    ),
+    ("General", "Allgemein"),
+    ("Toggle this help", "Diese Hilfe ein-/ausblenden"),
+    ("Quit", "Beenden"),
+    ("Clear search or link focus", "Suche oder Linkauswahl aufheben"),
+    // HTML page shell
+    ("Skip to content", "Zum Inhalt springen"),
+    ("Table of contents", "Inhaltsverzeichnis"),
+    ("All files", "Alle Dateien"),
+    ("Document metadata", "Dokument-Metadaten"),
+    ("Properties", "Eigenschaften"),
+    ("View raw markdown", "Markdown-Quelltext anzeigen"),
+    ("Search (/)", "Suchen (/)"),
+    ("Search all files", "Alle Dateien durchsuchen"),
+    ("This file has changed on disk.", "Diese Datei wurde auf der Festplatte geändert."),
+    ("Load latest", "Neu laden"),
+    (
+        "Draft: this page is not listed in indexes or search.",
+        "Entwurf: Diese Seite erscheint nicht in Verzeichnissen oder der Suche.",
+    ),
+    (
+        "This file is not UTF-8; it was decoded as {encoding}.",
+        "Diese Datei ist nicht UTF-8-kodiert; sie wurde als {encoding} gelesen.",
+    ),
+    ("Page template failed: {error}", "Seitenvorlage fehlgeschlagen: {error}"),
+    ("Toggle dark mode", "Dunkelmodus umschalten"),
+    ("Toggle indentation hierarchy", "Einrückungshierarchie umschalten"),
+    ("Toggle full width", "Volle Breite umschalten"),
+    ("Backlinks", "Rückverweise"),
+    ("Backlinks ({count})", "Rückverweise ({count})"),
+    ("Mentions ({count})", "Erwähnungen ({count})"),
+    ("Outgoing links", "Ausgehende Links"),
+    ("Outgoing links ({count})", "Ausgehende Links ({count})"),
+    ("ok", "ok"),
+    ("broken", "defekt"),
+    ("external", "extern"),
+    ("Link", "Verlinken"),
+    ("Linked", "Verlinkt"),
+    ("Turn this mention into a link", "Diese Erwähnung in einen Link umwandeln"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(s: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = s
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn translations_keep_placeholders_and_ids_are_unique() {
+        for (i, (id, text)) in DE.iter().enumerate() {
+            assert_eq!(
+                placeholders(id),
+                placeholders(text),
+                "placeholders of {id:?}"
+            );
+            assert!(
+                DE[..i].iter().all(|(other, _)| other != id),
+                "duplicate id {id:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn lookup_falls_back_to_english() {
+        assert_eq!(Locale::De.tr("Quit"), "Beenden");
+        assert_eq!(Locale::En.tr("Quit"), "Quit");
+        assert_eq!(Locale::De.tr("not in any catalog"), "not in any catalog");
+        assert_eq!(
+            Locale::De.trf("Line {line}/{total}", &[("line", &3), ("total", &40)]),
+            "Zeile 3/40"
+        );
+    }
+
+    #[test]
+    fn locale_names_and_env_precedence() {
+        assert_eq!(Locale::parse("de_DE.UTF-8"), Some(Locale::De));
+        assert_eq!(Locale::parse("de-AT"), Some(Locale::De));
+        assert_eq!(Locale::parse("C"), Some(Locale::En));
+        assert_eq!(Locale::parse("fr_FR"), None);
+
+        let env = |pairs: &'static [(&str, &str)]| {
+            move |var: &str| {
+                pairs
+                    .iter()
+                    .find(|(k, _)| *k == var)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            Locale::from_vars(env(&[("LANG", "de_DE.UTF-8")])),
+            Locale::De
+        );
+        assert_eq!(
+            Locale::from_vars(env(&[("LC_ALL", "C"), ("LANG", "de_DE")])),
+            Locale::En
+        );
+        assert_eq!(
+            Locale::from_vars(env(&[("LC_ALL", ""), ("LC_MESSAGES", "de")])),
+            Locale::De
+        );
+        assert_eq!(Locale::from_vars(env(&[("LANG", "ja_JP")])), Locale::En);
+    }
+}
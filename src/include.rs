@@ -0,0 +1,478 @@
+//! Include directives, expanded before a document is rendered.
+//!
+//! A line holding only `{{include: path}}` or an Obsidian-style embed
+//! `![[note]]` is replaced by the body of the named file, frontmatter
+//! stripped, with that file's own includes expanded in turn.  Paths resolve
+//! against the including file's directory (`/path` against the root, when
+//! there is one); an embed name without an extension gets `.md`, and its
+//! `#section` and `|alias` parts are ignored.
+//!
+//! A line holding only `{{snippet: path}}` is replaced by a fenced code block
+//! of that file, tagged with its extension, so examples stay in sync with
+//! real code.  `path#L10-L20` (or `#L10`) takes those lines, and
+//! `path#name` the lines between `#region name` and `#endregion` (or
+//! mdBook's `ANCHOR: name` and `ANCHOR_END: name`) marker comments.  Marker
+//! lines are dropped and the snippet is dedented.
+//!
+//! Directives inside fenced code blocks are left alone.  One that cannot be
+//! expanded (missing file or region, cycle, nesting deeper than
+//! [`MAX_INCLUDE_DEPTH`], or a file outside the root) is replaced by a block
+//! quote saying why, so the rest of the page still renders.
+//!
+//! ```
+//! let out = mdmd::include::expand("{{include: missing.md}}\n", "doc.md".as_ref(), None);
+//! assert!(out.text.starts_with("> **Include failed:** `missing.md`"));
+//! ```
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::frontmatter;
+
+/// Deepest chain of nested includes followed before giving up.
+pub const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// A document with its includes expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expanded<'a> {
+    /// The expanded text.  Callers may rewrite it further as long as its
+    /// lines stay where they are.
+    pub text: Cow<'a, str>,
+    /// Source line of each line of `text`, `None` for included lines.
+    /// Empty when nothing was included.
+    lines: Vec<Option<usize>>,
+}
+
+impl Expanded<'_> {
+    /// Whether any include directive was expanded.
+    pub fn has_includes(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    /// The 1-based line of the original source that 1-based `line` of the
+    /// expanded text came from, or `None` when it came from an included file.
+    pub fn source_line(&self, line: usize) -> Option<usize> {
+        if !self.has_includes() {
+            return Some(line);
+        }
+        self.lines.get(line.checked_sub(1)?).copied().flatten()
+    }
+}
+
+/// Expand the include directives in `source`, the contents of `file`.
+///
+/// With a `root`, includes must resolve inside it (as `mdmd serve` requires
+/// of every file it reads) and `/`-prefixed paths resolve against it.
+pub fn expand<'a>(source: &'a str, file: &Path, root: Option<&Path>) -> Expanded<'a> {
+    if !source.contains("{{") && !source.contains("![[") {
+        return unchanged(source);
+    }
+    let mut stack = vec![fs::canonicalize(file).unwrap_or_else(|_| file.to_path_buf())];
+    let mut lines = Vec::new();
+    match expand_source(source, file, root, &mut stack, Some(&mut lines)) {
+        Some(text) => Expanded {
+            text: Cow::Owned(text),
+            lines,
+        },
+        None => unchanged(source),
+    }
+}
+
+fn unchanged(source: &str) -> Expanded<'_> {
+    Expanded {
+        text: Cow::Borrowed(source),
+        lines: Vec::new(),
+    }
+}
+
+/// `source` with its directives expanded, or `None` when it has none.
+/// `stack` holds the canonical paths of the files being expanded, outermost
+/// first.  `line_map`, when given, receives the source line of every output
+/// line.
+fn expand_source(
+    source: &str,
+    file: &Path,
+    root: Option<&Path>,
+    stack: &mut Vec<PathBuf>,
+    mut line_map: Option<&mut Vec<Option<usize>>>,
+) -> Option<String> {
+    let mut out = String::with_capacity(source.len());
+    let mut fence: Option<(u8, usize)> = None;
+    let mut changed = false;
+    for (i, line) in source.split_inclusive('\n').enumerate() {
+        let target = match fence {
+            Some((marker, len)) => {
+                if closes_fence(line, marker, len) {
+                    fence = None;
+                }
+                None
+            }
+            None => {
+                fence = opens_fence(line);
+                fence.is_none().then(|| directive(line)).flatten()
+            }
+        };
+        let Some(directive) = target else {
+            out.push_str(line);
+            if let Some(map) = line_map.as_deref_mut() {
+                map.push(Some(i + 1));
+            }
+            continue;
+        };
+        changed = true;
+        let mut included = match directive {
+            Directive::Include(target) => include(&target, file, root, stack),
+            Directive::Snippet(target) => snippet(&target, file, root),
+        };
+        if !included.ends_with('\n') {
+            included.push('\n');
+        }
+        if let Some(map) = line_map.as_deref_mut() {
+            map.extend(std::iter::repeat_n(None, included.lines().count()));
+        }
+        out.push_str(&included);
+    }
+    changed.then_some(out)
+}
+
+/// The expanded body of the file `target` names, or a block quote saying
+/// why it cannot be included.
+fn include(target: &str, file: &Path, root: Option<&Path>, stack: &mut Vec<PathBuf>) -> String {
+    let path = match resolve(target, file, root) {
+        Ok(path) => path,
+        Err(reason) => return failure("Include", target, &reason),
+    };
+    if stack.contains(&path) {
+        let chain: Vec<String> = stack[stack.iter().position(|p| *p == path).unwrap_or(0)..]
+            .iter()
+            .chain([&path])
+            .map(|p| display_name(p))
+            .collect();
+        return failure(
+            "Include",
+            target,
+            &format!("include cycle {}", chain.join(" \u{2192} ")),
+        );
+    }
+    if stack.len() > MAX_INCLUDE_DEPTH {
+        return failure(
+            "Include",
+            target,
+            &format!("includes nest more than {MAX_INCLUDE_DEPTH} levels deep"),
+        );
+    }
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => return failure("Include", target, &format!("cannot read it: {e}")),
+    };
+    let body = frontmatter::extract(&source).render_body.into_owned();
+    stack.push(path.clone());
+    let expanded = expand_source(&body, &path, root, stack, None);
+    stack.pop();
+    expanded.unwrap_or(body)
+}
+
+/// Canonical path of the file `target` names, relative to `file`.
+fn resolve(target: &str, file: &Path, root: Option<&Path>) -> Result<PathBuf, String> {
+    let path = match (target.strip_prefix('/'), root) {
+        (Some(rooted), Some(root)) => root.join(rooted),
+        _ => file.parent().unwrap_or(Path::new(".")).join(target),
+    };
+    let path = fs::canonicalize(path).map_err(|_| "no such file".to_owned())?;
+    if root.is_some_and(|root| !path.starts_with(root)) {
+        return Err("it is outside the served directory".to_owned());
+    }
+    if !path.is_file() {
+        return Err("not a file".to_owned());
+    }
+    Ok(path)
+}
+
+/// A fenced code block of the part of a file `target` selects, or a block
+/// quote saying why it cannot be shown.
+fn snippet(target: &str, file: &Path, root: Option<&Path>) -> String {
+    let (path, selector) = match target.split_once('#') {
+        Some((path, selector)) => (path, Some(selector)),
+        None => (target, None),
+    };
+    let code = resolve(path, file, root).and_then(|path| {
+        let source = fs::read_to_string(&path).map_err(|e| format!("cannot read it: {e}"))?;
+        let lines: Vec<&str> = source.lines().collect();
+        let selected = match selector {
+            None => lines.clone(),
+            Some(selector) => match line_range(selector) {
+                Some(range) => select_lines(&lines, range)?,
+                None => select_region(&lines, selector)?,
+            },
+        };
+        let selected: Vec<&str> = selected
+            .into_iter()
+            .filter(|line| region_marker(line).is_none())
+            .collect();
+        Ok((dedent(&selected), path))
+    });
+    let (code, path) = match code {
+        Ok(found) => found,
+        Err(reason) => return failure("Snippet", target, &reason),
+    };
+    let lang = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let longest_run = code
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat((longest_run + 1).max(3));
+    format!("{fence}{lang}\n{code}{fence}\n")
+}
+
+/// 1-based inclusive line range of a `L10-L20`, `L10-20`, or `L10` selector.
+fn line_range(selector: &str) -> Option<(usize, usize)> {
+    let (start, end) = match selector.split_once('-') {
+        Some((start, end)) => (start, Some(end)),
+        None => (selector, None),
+    };
+    let start = start.strip_prefix('L')?.parse().ok()?;
+    let end = match end {
+        Some(end) => end.strip_prefix('L').unwrap_or(end).parse().ok()?,
+        None => start,
+    };
+    Some((start, end))
+}
+
+fn select_lines<'a>(
+    lines: &[&'a str],
+    (start, end): (usize, usize),
+) -> Result<Vec<&'a str>, String> {
+    if start == 0 || start > end {
+        return Err(format!("L{start}-L{end} is not a line range"));
+    }
+    if end > lines.len() {
+        return Err(format!("the file has only {} lines", lines.len()));
+    }
+    Ok(lines[start - 1..end].to_vec())
+}
+
+/// The lines between the start and end markers of region `name`; an
+/// unclosed region runs to the end of the file.
+fn select_region<'a>(lines: &[&'a str], name: &str) -> Result<Vec<&'a str>, String> {
+    let start = lines
+        .iter()
+        .position(|line| region_marker(line) == Some((true, name)))
+        .ok_or_else(|| format!("no region named `{name}`"))?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(
+            |line| matches!(region_marker(line), Some((false, n)) if n.is_empty() || n == name),
+        )
+        .map_or(lines.len(), |i| start + 1 + i);
+    Ok(lines[start + 1..end].to_vec())
+}
+
+/// Whether `line` is a region start (`true`) or end marker, and its name.
+fn region_marker(line: &str) -> Option<(bool, &str)> {
+    [
+        ("#endregion", false),
+        ("#region", true),
+        ("ANCHOR_END:", false),
+        ("ANCHOR:", true),
+    ]
+    .into_iter()
+    .find_map(|(tag, start)| {
+        let at = line.find(tag)?;
+        let name = line[at + tag.len()..]
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        Some((start, name))
+    })
+}
+
+/// `lines` joined with newlines, with the indentation they share removed.
+fn dedent(lines: &[&str]) -> String {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| format!("{}\n", line.get(indent..).unwrap_or("").trim_end()))
+        .collect()
+}
+
+fn failure(kind: &str, target: &str, reason: &str) -> String {
+    format!("> **{kind} failed:** `{target}`: {reason}\n")
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
+
+/// A directive and the path (with any `#selector`) it names.
+enum Directive {
+    Include(String),
+    Snippet(String),
+}
+
+/// The directive `line` holds, if it is one.
+fn directive(line: &str) -> Option<Directive> {
+    if line.len() - line.trim_start_matches(' ').len() > 3 {
+        return None;
+    }
+    let line = line.trim();
+    if let Some(inner) = line.strip_prefix("{{").and_then(|l| l.strip_suffix("}}")) {
+        let inner = inner.trim();
+        let (make, target): (fn(String) -> Directive, _) =
+            if let Some(target) = inner.strip_prefix("include:") {
+                (Directive::Include, target)
+            } else {
+                (Directive::Snippet, inner.strip_prefix("snippet:")?)
+            };
+        let target = target.trim();
+        return (!target.is_empty()).then(|| make(target.to_owned()));
+    }
+    let inner = line.strip_prefix("![[")?.strip_suffix("]]")?;
+    let name = inner.split(['|', '#']).next().unwrap_or_default().trim();
+    if name.is_empty() {
+        return None;
+    }
+    Some(Directive::Include(
+        if Path::new(name).extension().is_some() {
+            name.to_owned()
+        } else {
+            format!("{name}.md")
+        },
+    ))
+}
+
+/// Marker byte and length of the code fence `line` opens, if it does.
+pub(crate) fn opens_fence(line: &str) -> Option<(u8, usize)> {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return None;
+    }
+    let marker = *trimmed
+        .as_bytes()
+        .first()
+        .filter(|b| matches!(b, b'`' | b'~'))?;
+    let len = trimmed.bytes().take_while(|&b| b == marker).count();
+    // A backtick fence's info string cannot itself contain backticks.
+    let info_ok = marker == b'~' || !trimmed[len..].contains('`');
+    (len >= 3 && info_ok).then_some((marker, len))
+}
+
+pub(crate) fn closes_fence(line: &str, marker: u8, len: usize) -> bool {
+    let trimmed = line.trim_start_matches(' ');
+    if line.len() - trimmed.len() > 3 {
+        return false;
+    }
+    let run = trimmed.bytes().take_while(|&b| b == marker).count();
+    run >= len && trimmed[run..].trim().is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_nested_includes_and_maps_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::create_dir(root.join("parts")).unwrap();
+        fs::write(
+            root.join("parts/intro.md"),
+            "---\ntitle: Intro\n---\nHello.\n\n![[footer|the footer]]\n",
+        )
+        .unwrap();
+        fs::write(root.join("parts/footer.md"), "- [ ] footer task").unwrap();
+        let source = "# Doc\n\n{{ include: parts/intro.md }}\n\n- [ ] own task\n";
+        let out = expand(source, &root.join("doc.md"), Some(&root));
+        assert_eq!(
+            out.text,
+            "# Doc\n\nHello.\n\n- [ ] footer task\n\n- [ ] own task\n"
+        );
+        assert!(out.has_includes());
+        assert_eq!(out.source_line(2), Some(2));
+        assert_eq!(out.source_line(5), None);
+        assert_eq!(out.source_line(7), Some(5));
+    }
+
+    #[test]
+    fn leaves_code_and_plain_documents_alone() {
+        let source = "```md\n{{include: a.md}}\n```\n    ![[b]]\ntext {{include: c.md}}\n";
+        let out = expand(source, Path::new("doc.md"), None);
+        assert!(!out.has_includes());
+        assert_eq!(out.text, source);
+        assert_eq!(out.source_line(3), Some(3));
+    }
+
+    #[test]
+    fn snippets_take_regions_and_line_ranges() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::write(
+            root.join("lib.rs"),
+            "fn main() {\n    // #region setup\n    let x = 1;\n    let s = \"```\";\n    // #endregion\n}\n",
+        )
+        .unwrap();
+        let doc = root.join("doc.md");
+        let out = expand("{{snippet: lib.rs#setup}}\n", &doc, Some(&root));
+        assert_eq!(out.text, "````rs\nlet x = 1;\nlet s = \"```\";\n````\n");
+        let out = expand(
+            "{{snippet: lib.rs#L1-L2}}\n{{snippet: lib.rs#L6}}\n",
+            &doc,
+            None,
+        );
+        assert_eq!(out.text, "```rs\nfn main() {\n```\n```rs\n}\n```\n");
+        assert_eq!(out.source_line(1), None);
+
+        let out = expand(
+            "{{snippet: lib.rs#nope}}\n{{snippet: lib.rs#L5-L9}}\n",
+            &doc,
+            None,
+        );
+        assert_eq!(
+            out.text,
+            "> **Snippet failed:** `lib.rs#nope`: no region named `nope`\n\
+> **Snippet failed:** `lib.rs#L5-L9`: the file has only 6 lines\n"
+        );
+    }
+
+    #[test]
+    fn reports_cycles_depth_and_escapes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        fs::write(root.join("a.md"), "A\n\n![[b]]\n").unwrap();
+        fs::write(root.join("b.md"), "B\n\n![[a]]\n").unwrap();
+        let out = expand("![[a]]\n", &root.join("doc.md"), Some(&root));
+        assert_eq!(
+            out.text,
+            "A\n\nB\n\n> **Include failed:** `a.md`: include cycle a.md \u{2192} b.md \u{2192} a.md\n"
+        );
+
+        for i in 0..=MAX_INCLUDE_DEPTH {
+            fs::write(root.join(format!("n{i}.md")), format!("![[n{}]]\n", i + 1)).unwrap();
+        }
+        let out = expand("![[n0]]\n", &root.join("doc.md"), Some(&root));
+        assert!(out.text.contains("more than 8 levels"), "{}", out.text);
+
+        let inner = root.join("inner");
+        fs::create_dir(&inner).unwrap();
+        let out = expand(
+            "{{include: ../a.md}}\n",
+            &inner.join("doc.md"),
+            Some(&inner),
+        );
+        assert!(out.text.contains("outside the served directory"));
+        let out = expand("{{include: /a.md}}\n", &inner.join("doc.md"), Some(&root));
+        assert!(out.text.starts_with("A\n"));
+    }
+}
@@ -0,0 +1,53 @@
+//! Build metadata for `mdmd info` and `mdmd --version --verbose`.
+//!
+//! Everything here is fixed at compile time (see `build.rs`) except the
+//! default config path, which depends on the environment mdmd runs in.
+
+use serde_json::{json, Value};
+
+use crate::settings::default_config_path;
+use crate::web_assets::{self, fnv1a_64};
+
+/// Build metadata as JSON, for bug reports and scripts.
+pub fn build_info_json() -> Value {
+    let features: Vec<&str> = env!("MDMD_FEATURES")
+        .split(',')
+        .filter(|f| !f.is_empty())
+        .collect();
+    let asset_hash = |data: &str| format!("{:016x}", fnv1a_64(data.as_bytes()));
+
+    json!({
+        "name": env!("CARGO_PKG_NAME"),
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_commit": env!("MDMD_GIT_COMMIT"),
+        "build_date": env!("MDMD_BUILD_DATE"),
+        "features": features,
+        "default_config_path": default_config_path().map(|p| p.display().to_string()),
+        "asset_hashes": {
+            "mdmd.css": asset_hash(web_assets::CSS),
+            "mdmd.js": asset_hash(web_assets::JS),
+            "openapi.json": asset_hash(web_assets::OPENAPI),
+            "favicon.svg": asset_hash(web_assets::FAVICON_SVG),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn info_has_version_and_asset_etag_hashes() {
+        let info = build_info_json();
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert!(info["features"].is_array());
+        // Asset hashes match the ETags serve sends, minus the quotes.
+        let css_etag = crate::serve::compute_etag(web_assets::CSS.as_bytes());
+        assert_eq!(
+            format!("\"{}\"", info["asset_hashes"]["mdmd.css"].as_str().unwrap()),
+            css_etag
+        );
+        let date = info["build_date"].as_str().unwrap();
+        assert_eq!(date.len(), 10, "got: {date}");
+    }
+}
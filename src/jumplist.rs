@@ -0,0 +1,156 @@
+//! Vim-style jump list for the TUI viewer.
+//!
+//! Every "jump" (heading jump, search jump, link follow, goto top/bottom)
+//! records the position it left.  `Ctrl-o` walks backward through those
+//! positions and `Ctrl-i` walks forward again, across files.
+
+use std::path::PathBuf;
+
+/// Maximum number of positions retained; the oldest entry is dropped first.
+const MAX_JUMPS: usize = 100;
+
+/// A position in a document that can be returned to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpPosition {
+    /// Canonical path of the document.
+    pub file_path: PathBuf,
+    /// Scroll offset (0-based rendered line at the top of the viewport).
+    pub scroll_offset: usize,
+}
+
+/// Ordered history of jump origins with a cursor for `Ctrl-o` / `Ctrl-i`.
+#[derive(Debug, Default)]
+pub struct JumpList {
+    entries: Vec<JumpPosition>,
+    /// Index of the entry currently being visited.  Equal to `entries.len()`
+    /// when the user is not walking the list.
+    index: usize,
+}
+
+impl JumpList {
+    /// Rebuild a jump list from saved `entries` and cursor `index`.
+    pub fn restore(mut entries: Vec<JumpPosition>, index: usize) -> Self {
+        let excess = entries.len().saturating_sub(MAX_JUMPS);
+        entries.drain(..excess);
+        let index = index.saturating_sub(excess).min(entries.len());
+        Self { entries, index }
+    }
+
+    /// The recorded positions, oldest first, and the cursor into them.
+    pub fn snapshot(&self) -> (&[JumpPosition], usize) {
+        (&self.entries, self.index)
+    }
+
+    /// Record `from` as the origin of a new jump.
+    ///
+    /// Any positions ahead of the cursor (reachable with `Ctrl-i`) are
+    /// discarded, as in vim.  Consecutive duplicates are collapsed.
+    pub fn record(&mut self, from: JumpPosition) {
+        self.entries.truncate(self.index);
+        if self.entries.last() != Some(&from) {
+            self.entries.push(from);
+        }
+        if self.entries.len() > MAX_JUMPS {
+            self.entries.remove(0);
+        }
+        self.index = self.entries.len();
+    }
+
+    /// Step backward, returning the position to restore.
+    ///
+    /// `current` is the position being left; when starting a walk from the
+    /// tip of the list it is appended so that [`forward`](Self::forward) can
+    /// return to it.
+    pub fn back(&mut self, current: JumpPosition) -> Option<JumpPosition> {
+        if self.index == self.entries.len() {
+            if self.entries.last() != Some(&current) {
+                self.entries.push(current);
+            }
+            self.index = self.entries.len() - 1;
+        }
+        if self.index == 0 {
+            return None;
+        }
+        self.index -= 1;
+        Some(self.entries[self.index].clone())
+    }
+
+    /// Step forward after [`back`](Self::back), returning the position to restore.
+    pub fn forward(&mut self) -> Option<JumpPosition> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+        self.index += 1;
+        Some(self.entries[self.index].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pos(file: &str, scroll_offset: usize) -> JumpPosition {
+        JumpPosition {
+            file_path: PathBuf::from(file),
+            scroll_offset,
+        }
+    }
+
+    #[test]
+    fn back_on_empty_list_is_none() {
+        let mut jumps = JumpList::default();
+        assert_eq!(jumps.back(pos("a.md", 0)), None);
+        assert_eq!(jumps.forward(), None);
+    }
+
+    #[test]
+    fn back_and_forward_walk_recorded_positions() {
+        let mut jumps = JumpList::default();
+        jumps.record(pos("a.md", 0));
+        jumps.record(pos("a.md", 40));
+
+        assert_eq!(jumps.back(pos("b.md", 5)), Some(pos("a.md", 40)));
+        assert_eq!(jumps.back(pos("a.md", 40)), Some(pos("a.md", 0)));
+        assert_eq!(jumps.back(pos("a.md", 0)), None);
+
+        assert_eq!(jumps.forward(), Some(pos("a.md", 40)));
+        // The position left by the first Ctrl-o is reachable again.
+        assert_eq!(jumps.forward(), Some(pos("b.md", 5)));
+        assert_eq!(jumps.forward(), None);
+    }
+
+    #[test]
+    fn record_discards_forward_entries() {
+        let mut jumps = JumpList::default();
+        jumps.record(pos("a.md", 0));
+        jumps.record(pos("a.md", 10));
+        assert_eq!(jumps.back(pos("a.md", 20)), Some(pos("a.md", 10)));
+
+        jumps.record(pos("a.md", 10));
+        assert_eq!(jumps.forward(), None);
+        assert_eq!(jumps.back(pos("c.md", 0)), Some(pos("a.md", 10)));
+        assert_eq!(jumps.back(pos("a.md", 10)), Some(pos("a.md", 0)));
+    }
+
+    #[test]
+    fn consecutive_duplicates_are_collapsed() {
+        let mut jumps = JumpList::default();
+        jumps.record(pos("a.md", 3));
+        jumps.record(pos("a.md", 3));
+        assert_eq!(jumps.back(pos("a.md", 9)), Some(pos("a.md", 3)));
+        assert_eq!(jumps.back(pos("a.md", 3)), None);
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped_past_capacity() {
+        let mut jumps = JumpList::default();
+        for i in 0..(MAX_JUMPS + 10) {
+            jumps.record(pos("a.md", i));
+        }
+        let mut last = None;
+        while let Some(p) = jumps.back(pos("a.md", 9999)) {
+            last = Some(p);
+        }
+        assert_eq!(last, Some(pos("a.md", 10)));
+    }
+}
@@ -0,0 +1,99 @@
+//! Markdown parsing and rendering used by the `mdmd` viewer, as a library.
+//!
+//! The `mdmd` binary (TUI pager, `serve`, `html` export) is built on these
+//! modules; other tools can use them to render markdown the same way.
+//!
+//! - [`parse`] turns markdown into blocks, headings, links, and code blocks.
+//! - [`render`] turns a parsed document into styled ratatui [`Text`] with
+//!   heading and link positions.
+//! - [`html`] renders markdown to HTML with deduplicated heading anchors and
+//!   builds the full page shell used by `mdmd serve` and `mdmd html`.
+//! - [`backlinks`] scans a directory tree and indexes which documents link to
+//!   which, and which mention each other's titles without linking.
+//! - [`frontmatter`] extracts YAML frontmatter.
+//! - [`head`] checks the extra `<head>` tags of `--inject-head` files and
+//!   frontmatter `head:` and `scripts:` fields.
+//! - [`bidi`] detects right-to-left paragraphs.
+//! - [`normalize`] strips byte order marks and turns `\r\n` line endings
+//!   into `\n` before parsing.
+//! - [`include`] expands `{{include: path}}`, `![[note]]`, and
+//!   `{{snippet: path#region}}` directives.
+//! - [`vars`] substitutes `{{var.name}}` placeholders.
+//! - [`filter`] pipes markdown or rendered HTML through external commands.
+//! - [`outgoing`] lists a document's links and whether each one resolves.
+//! - [`shortcode`] expands `{{< name >}}` shortcodes with Lua handlers (the
+//!   `shortcodes` feature, on by default).
+//! - [`toc`] finds inline table of contents markers (`[TOC]`).
+//! - [`numbering`] numbers headings hierarchically (`1.2.3`).
+//! - [`summary`] derives a document's title and short summary.
+//! - [`template`] fills user page templates (`--template`) in place of the
+//!   built-in page shell.
+//! - [`i18n`] is the message catalog for TUI and page-shell labels.
+//! - [`ffi`] exposes the HTML renderer through a C ABI (`include/mdmd.h`).
+//!
+//! [`render_to_text`] and [`render_to_html`] cover the common cases:
+//!
+//! ```
+//! let text = mdmd::render_to_text("# Title\n\nSome *emphasis*.\n");
+//! assert!(text.lines.iter().any(|l| l.to_string().contains("Title")));
+//!
+//! let html = mdmd::render_to_html("# Title\n");
+//! assert_eq!(html, "<h1 id=\"title\">Title</h1>\n");
+//! ```
+//!
+//! Building a backlinks index for a directory:
+//!
+//! ```no_run
+//! let index = mdmd::backlinks::build_backlinks_index(std::path::Path::new("docs"), false);
+//! for r in index.get("/docs/readme.md").into_iter().flatten() {
+//!     println!("{} links here", r.source_url_path);
+//! }
+//! ```
+
+use std::path::Path;
+
+use ratatui::text::Text;
+
+pub mod backlinks;
+pub mod bidi;
+pub mod ffi;
+pub mod filter;
+pub mod frontmatter;
+pub mod head;
+pub mod html;
+pub mod i18n;
+pub mod include;
+pub mod normalize;
+pub mod numbering;
+pub mod outgoing;
+pub mod parse;
+pub mod render;
+#[cfg(feature = "shortcodes")]
+pub mod shortcode;
+pub mod summary;
+pub mod template;
+pub mod toc;
+pub mod vars;
+pub mod web_assets;
+
+/// Parse and render markdown to styled terminal text, as shown by the pager.
+pub fn render_to_text(markdown: &str) -> Text<'static> {
+    render::render_document(&parse::parse(markdown)).text
+}
+
+/// Render markdown to an HTML fragment (no page shell) whose headings carry
+/// the same `id` anchors as pages served by `mdmd serve`.
+///
+/// Relative links are left as authored and frontmatter is not stripped; use
+/// [`frontmatter::extract`] first when the source may carry it.  See
+/// [`html::render_markdown`] for link rewriting and heading metadata.
+pub fn render_to_html(markdown: &str) -> String {
+    let (body, headings) = html::render_markdown(
+        markdown,
+        Path::new(""),
+        Path::new(""),
+        html::RenderTarget::Html,
+        false,
+    );
+    html::inject_heading_ids(&body, &headings)
+}
@@ -1,29 +1,78 @@
-mod backlinks;
-mod frontmatter;
-mod html;
+mod audit;
+mod bench;
+mod binary;
+mod breadcrumbs;
+mod changes;
+mod clipboard;
+mod control;
+mod encoding;
+mod finder;
+mod handlers;
+mod history;
 mod html_export;
-mod parse;
-mod render;
+mod hyperlink;
+mod info;
+mod jumplist;
+mod pager;
+mod palette;
+mod preview;
+mod queue;
+mod render_export;
+mod replace;
+mod reposition;
+mod search;
+mod selection;
 mod serve;
-mod web_assets;
+mod session;
+mod settings;
+mod share;
+mod start;
+mod structure;
+mod tasks;
+mod tree;
+mod workspace;
+mod zip;
+
+use mdmd::{
+    backlinks, filter, frontmatter, head, html, i18n, include, numbering, outgoing, parse, render,
+    summary, template, vars, web_assets,
+};
+
+#[cfg(feature = "shortcodes")]
+use mdmd::shortcode;
 
 use std::{
-    fs, io,
+    borrow::Cow,
+    collections::VecDeque,
+    fs,
+    io::{self, IsTerminal},
     path::{Path, PathBuf},
     process,
+    time::{Duration, SystemTime},
 };
 
-use clap::{Parser, Subcommand};
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand};
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    KeyboardEnhancementFlags, MouseButton, MouseEventKind, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+};
 use ratatui::{
     layout::{Constraint, Layout, Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Clear, Paragraph},
+    widgets::{Block, Borders, Clear, Paragraph},
     DefaultTerminal, Frame,
 };
+use unicode_width::UnicodeWidthStr;
 
+use finder::FileFinder;
+use handlers::{HandlerMode, HandlerRule, HandlerTable};
+use history::VisitHistory;
+use i18n::Locale;
+use jumplist::{JumpList, JumpPosition};
 use render::{HeadingPosition, RenderedDocument};
+use workspace::NavigationEntry;
 
 /// State for the outline modal overlay.
 struct OutlineState {
@@ -33,13 +82,63 @@ struct OutlineState {
     saved_scroll: usize,
 }
 
+/// State for the go-to-heading finder (`Ctrl-k`): the query typed so far
+/// and the headings matching it.
+struct HeadingFinder {
+    query: String,
+    /// Indices into `heading_lines` of the matching headings, best first.
+    matches: Vec<usize>,
+    /// Index into `matches` of the selected heading.
+    selected: usize,
+    /// Scroll offset saved when the finder was opened (for Esc restore).
+    saved_scroll: usize,
+}
+
+impl HeadingFinder {
+    /// Match `headings` against the query again and select the best match.
+    fn refilter(&mut self, headings: &[HeadingPosition]) {
+        self.matches = finder::rank(&self.query, headings.iter().map(|h| h.text.as_str()));
+        self.selected = 0;
+    }
+
+    /// Rendered line of the selected heading; `None` when nothing matches.
+    fn target(&self, headings: &[HeadingPosition]) -> Option<usize> {
+        let &i = self.matches.get(self.selected)?;
+        headings.get(i).map(|h| h.rendered_line)
+    }
+}
+
+/// State for the command palette (`:`): the line typed so far and the
+/// commands matching its first word.
+struct Palette {
+    query: String,
+    /// Indices into [`palette::COMMANDS`] of the matches, best first.
+    matches: Vec<usize>,
+    /// Index into `matches` of the selected command.
+    selected: usize,
+}
+
+impl Palette {
+    /// Match the commands against the query again and select the best.
+    /// Typing the argument leaves the matches, and the selection, as they
+    /// were.
+    fn refilter(&mut self) {
+        let matches = palette::matches(&self.query);
+        if matches != self.matches {
+            self.matches = matches;
+            self.selected = 0;
+        }
+    }
+}
+
 /// A single search match position in the rendered output.
 struct SearchMatch {
     /// 0-based line index in the rendered output.
     rendered_line: usize,
-    /// 0-based column where the match starts (byte offset in line text).
+    /// 0-based display column where the match starts, counting wide (CJK,
+    /// emoji) characters as two columns like terminal cells.
     column_start: usize,
-    /// 0-based column where the match ends (exclusive, byte offset).
+    /// 0-based display column where the match ends (exclusive).
     column_end: usize,
 }
 
@@ -48,6 +147,77 @@ struct SearchMatch {
 enum Commands {
     /// View a markdown file in TUI mode (equivalent to legacy positional form)
     View {
+        /// Markdown files to read in turn with `]f` and `[f`, or `-` to read
+        /// one from stdin (optional when resuming a --session)
+        #[arg(value_name = "FILE", required_unless_present = "session")]
+        files: Vec<String>,
+        /// Name shown in the status bar for a document read from stdin
+        #[arg(long, value_name = "NAME", default_value = "stdin")]
+        stdin_title: String,
+        /// View FILE as markdown whatever its extension, e.g. LICENSE
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        scroll: ScrollArgs,
+        #[command(flatten)]
+        open: OpenArgs,
+        /// Record key presses and their timing to FILE for `mdmd replay`
+        #[arg(long, value_name = "FILE")]
+        record: Option<String>,
+        /// Scroll to source lines an editor writes to this Unix socket, one
+        /// `LINE` or `FILE:LINE` per line
+        #[arg(long, value_name = "SOCKET")]
+        listen: Option<String>,
+        /// Take `open FILE`, `scroll [FILE:]LINE`, `reload`, and `quit`
+        /// commands from editors on this Unix socket, one per line
+        #[arg(long, value_name = "SOCKET")]
+        control: Option<String>,
+        /// Reload the document whenever its file changes on disk
+        #[arg(long)]
+        watch: bool,
+        /// Show the file's path within its project as a clickable header
+        #[arg(long)]
+        breadcrumbs: bool,
+        /// Do not make links clickable in terminals that support hyperlinks
+        #[arg(long)]
+        no_hyperlinks: bool,
+        /// Rewrap paragraphs to this many columns (0 keeps their lines as written)
+        #[arg(long, default_value = "0", value_name = "COLS")]
+        text_width: u16,
+        /// Stretch wrapped paragraph lines to the full --text-width
+        #[arg(long)]
+        justify: bool,
+        /// Break long words at their soft hyphens when wrapping to --text-width
+        #[arg(long)]
+        hyphenate: bool,
+        #[command(flatten)]
+        render: RenderArgs,
+        /// Restore the named session on start and save it on quit: the open
+        /// document, scroll position, back/forward stacks, history, and
+        /// jump list
+        #[arg(long, value_name = "NAME", value_parser = parse_session_name)]
+        session: Option<String>,
+    },
+    /// Play back a session recorded with `--record`
+    ///
+    /// Recorded keys are replayed at their original pace; press Esc or
+    /// Ctrl-c to stop playback and take over interactively.
+    Replay {
+        /// Path to the recording
+        recording: String,
+        /// View this file instead of the one named in the recording
+        #[arg(long)]
+        file: Option<String>,
+        /// Playback speed multiplier (2 plays twice as fast)
+        #[arg(long, default_value = "1.0", value_parser = parse_speed)]
+        speed: f64,
+    },
+    /// Open the viewer beside the editor in a tmux pane or kitty split
+    ///
+    /// Runs `mdmd view --watch FILE` to the right of the current pane, so
+    /// saved edits show as they are made, and keeps the focus where it was.
+    /// In kitty, remote control must be enabled (`allow_remote_control`).
+    Preview {
         /// Path to the markdown file
         file: String,
     },
@@ -57,6 +227,11 @@ enum Commands {
     /// inside the CWD.  If the entry path is outside the CWD, the serve root is
     /// derived from the entry location and a network-exposure warning is shown.
     ///
+    /// Given several paths or a glob (`mdmd serve docs guide.md`,
+    /// `mdmd serve 'docs/**/*.md'`), the serve root is the deepest directory
+    /// holding them all, the entry is a collection page listing the matched
+    /// files by directory, and indexes list only those files.
+    ///
     /// On startup, two URLs are printed to stdout:
     ///   url:   http://127.0.0.1:<port>/<path-to-entry>   (entry document)
     ///   index: http://127.0.0.1:<port>/                   (root directory index)
@@ -74,8 +249,10 @@ enum Commands {
     /// Directory listings exclude dotfiles and out-of-root symlinks.
     /// Entries are sorted: directories first, then files, both alphabetical.
     Serve {
-        /// Path to the markdown file or directory
-        file: String,
+        /// Markdown files, directories, or globs to serve; a single file or
+        /// directory is the entry page
+        #[arg(value_name = "FILE", required = true)]
+        files: Vec<String>,
         /// Interface address to bind to
         #[arg(long, default_value = "0.0.0.0")]
         bind: String,
@@ -88,17 +265,169 @@ enum Commands {
         /// Enable verbose output (show per-request log lines)
         #[arg(long, short = 'v')]
         verbose: bool,
+        /// With --verbose, warn when rendering a page exceeds this many milliseconds
+        #[arg(long, default_value = "100", value_name = "MS")]
+        render_budget_ms: u64,
+        /// With --verbose, warn when a request exceeds this many milliseconds in total
+        #[arg(long, default_value = "250", value_name = "MS")]
+        request_budget_ms: u64,
+        /// Site name shown in page titles, page headers, and directory indexes
+        #[arg(long, value_name = "TITLE")]
+        site_title: Option<String>,
+        /// Image served as the site icon instead of the built-in one (.ico, .png, .svg, ...)
+        #[arg(long, value_name = "PATH")]
+        favicon: Option<String>,
+        /// Make task list checkboxes clickable, writing changes back to the markdown files
+        #[arg(long)]
+        allow_edit: bool,
+        /// List pages with `draft: true` front matter in directory indexes,
+        /// the file sidebar, search, and backlinks
+        #[arg(long)]
+        drafts: bool,
+        /// Demote every heading by N levels (up to 5), for content embedded under other headings
+        #[arg(long, default_value = "0", value_name = "N", value_parser = clap::value_parser!(u8).range(0..=5))]
+        heading_offset: u8,
+        /// Use the first H1 as the page title in the header instead of the body
+        #[arg(long)]
+        title_from_h1: bool,
+        #[command(flatten)]
+        render: RenderArgs,
+        /// Add the `<meta>`, `<link>`, `<script>`, `<style>`, and `<noscript>`
+        /// tags in FILE to the head of every page, e.g. analytics or web fonts
+        #[arg(long, value_name = "FILE")]
+        inject_head: Option<String>,
+        /// Let pages add scripts and `<link>`/`<meta>` tags to their head with
+        /// `scripts:` and `head:` front matter
+        #[arg(long)]
+        allow_page_head: bool,
+        /// Fill pages from the minijinja template FILE instead of the built-in
+        /// page layout
+        #[arg(long, value_name = "FILE")]
+        template: Option<String>,
+        /// Serve mdmd.css and mdmd.js uncached from the source tree mdmd was
+        /// built from, so style and script edits show without recompiling
+        #[arg(long)]
+        dev: bool,
+        /// Seconds browsers may reuse a page before revalidating it (Cache-Control max-age)
+        #[arg(long, default_value = "0", value_name = "SECS")]
+        page_max_age: u64,
+        /// Seconds browsers may reuse images and other files from the serve root
+        #[arg(long, default_value = "3600", value_name = "SECS")]
+        asset_max_age: u64,
+        /// Answer requests still unfinished after this many seconds with 503
+        #[arg(long, default_value = "30", value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..=3600))]
+        request_timeout: u64,
+        /// Most requests handled at once; more are answered with 503
+        #[arg(long, default_value = "256", value_name = "N", value_parser = clap::value_parser!(u64).range(1..=65535))]
+        max_in_flight: u64,
+        /// Largest request body accepted; larger ones get 413
+        #[arg(long, default_value = "65536", value_name = "BYTES")]
+        max_body_bytes: u64,
+        /// Match request paths to files ignoring case when no exact match exists
+        #[arg(long)]
+        case_insensitive: bool,
+        /// Serve files that symlinks in the served directory point to outside it
+        #[arg(long, conflicts_with = "no_follow_symlinks")]
+        follow_symlinks: bool,
+        /// Refuse symlinks that lead outside the served directory (the default;
+        /// overrides follow_symlinks from a config file)
+        #[arg(long)]
+        no_follow_symlinks: bool,
+        /// Also serve files from DIR, read-only, where symlinks in the served
+        /// directory lead into it (repeatable)
+        #[arg(long, value_name = "DIR")]
+        allow: Vec<PathBuf>,
+        /// Serve only files with these extensions, e.g. `md,png,jpg,svg,css`;
+        /// requests for any other file get a 404
+        #[arg(long, value_name = "EXTS")]
+        serve_types: Option<String>,
+        /// Append a line per request to FILE (by default
+        /// `$XDG_STATE_HOME/mdmd/audit.log` when binding to a non-loopback
+        /// address)
+        #[arg(long, value_name = "FILE", conflicts_with = "no_audit_log")]
+        audit_log: Option<String>,
+        /// Do not keep an audit log, even when binding to a non-loopback
+        /// address
+        #[arg(long)]
+        no_audit_log: bool,
+        /// Answer only requests from the printed link, which carries a random
+        /// token, and stop serving after DURATION, e.g. `30m` or `1h30m`
+        #[arg(long, value_name = "DURATION", value_parser = share::parse_duration)]
+        share: Option<Duration>,
+        /// Also put the startup URL on the clipboard (a platform command
+        /// locally, OSC 52 over SSH)
+        #[arg(long)]
+        copy_url: bool,
+        /// Browser to open the page in, with optional arguments, e.g.
+        /// `firefox` or `"firefox -P work"` (an application name on macOS)
+        #[arg(long, value_name = "NAME")]
+        open_browser: Option<String>,
+        /// Open this path, relative to the served directory, instead of FILE
+        #[arg(long, value_name = "SUBPATH")]
+        open_path: Option<String>,
+        /// Take `open FILE`, `scroll [FILE:]LINE`, `reload`, and `quit`
+        /// commands from editors on this Unix socket, one per line
+        #[arg(long, value_name = "SOCKET")]
+        control: Option<PathBuf>,
     },
     /// Export a markdown file as a self-contained HTML page
+    ///
+    /// With --single-file, FILE is a directory and every markdown file under
+    /// it is written into one page, in reading order, with a section per
+    /// file: handy for printing or searching a whole doc set.
     Html {
-        /// Path to the markdown file
+        /// Path to the markdown file, or the directory with --single-file
         file: String,
         /// Output file path (defaults to <input-stem>.html)
         #[arg(short, long)]
         output: Option<String>,
+        /// Combine the markdown files under the directory FILE into one page
+        #[arg(long)]
+        single_file: bool,
         /// Use constrained content width instead of full width
         #[arg(long)]
         constrained: bool,
+        /// Site name shown in the page title and header
+        #[arg(long, value_name = "TITLE")]
+        site_title: Option<String>,
+        /// Demote every heading by N levels (up to 5), for content embedded under other headings
+        #[arg(long, default_value = "0", value_name = "N", value_parser = clap::value_parser!(u8).range(0..=5))]
+        heading_offset: u8,
+        /// Use the first H1 as the page title in the header instead of the body
+        #[arg(long)]
+        title_from_h1: bool,
+        #[command(flatten)]
+        render: RenderArgs,
+        /// Add the `<meta>`, `<link>`, `<script>`, `<style>`, and `<noscript>`
+        /// tags in FILE to the head of every page, e.g. analytics or web fonts
+        #[arg(long, value_name = "FILE")]
+        inject_head: Option<String>,
+        /// Let pages add scripts and `<link>`/`<meta>` tags to their head with
+        /// `scripts:` and `head:` front matter
+        #[arg(long)]
+        allow_page_head: bool,
+        /// Fill pages from the minijinja template FILE instead of the built-in
+        /// page layout
+        #[arg(long, value_name = "FILE")]
+        template: Option<String>,
+    },
+    /// Write a rendered markdown file to stdout
+    ///
+    /// Text formats show the document as the viewer does, styled with ANSI
+    /// escape sequences (`ansi`, the default on a terminal) or not (`plain`,
+    /// the default otherwise); `html` writes the HTML fragment without a
+    /// page shell.  For example: `mdmd render --format ansi README.md | less -R`.
+    Render {
+        /// Path to the markdown file
+        file: String,
+        /// Output format (defaults to ansi on a terminal, plain otherwise)
+        #[arg(long, value_name = "FORMAT", value_parser = ["ansi", "plain", "html"])]
+        format: Option<String>,
+        /// Do not write links as terminal hyperlinks
+        #[arg(long)]
+        no_hyperlinks: bool,
+        #[command(flatten)]
+        render: RenderArgs,
     },
     /// List all headings in a markdown file
     Headings {
@@ -108,6 +437,22 @@ enum Commands {
         #[arg(long)]
         max_level: Option<u8>,
     },
+    /// Print the heading tree of a markdown file with anchors and line numbers
+    ///
+    /// `md` (the default) writes a nested list of links to the headings'
+    /// anchors, ready to paste as a table of contents, with each heading's
+    /// line in an HTML comment; `json` writes the `headings` array of
+    /// `GET /_mdmd/v1/doc`.
+    Outline {
+        /// Path to the markdown file
+        file: String,
+        /// Output format
+        #[arg(long, value_name = "FORMAT", value_parser = ["md", "json"], default_value = "md")]
+        format: String,
+        /// Make heading anchors the way GitHub, GitLab, or kramdown does
+        #[arg(long, value_name = "STYLE", value_parser = ["github", "gitlab", "kramdown"])]
+        slug_style: Option<String>,
+    },
     /// Print a section of a markdown file as raw markdown
     ///
     /// Selects a heading and prints everything from that heading up to (but not
@@ -126,58 +471,335 @@ enum Commands {
         #[arg(long, group = "selector")]
         index: Option<usize>,
     },
+    /// Benchmark parse/render/serve throughput on synthetic stress documents
+    ///
+    /// Generates a fixed corpus (huge table, deep nesting, thousands of links
+    /// and headings, highlighted code) and reports the median time and MB/s
+    /// of each pipeline stage.
+    Bench {
+        /// Timed runs per fixture and stage; the median is reported
+        #[arg(long, default_value = "5", value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+        /// Only run fixtures whose name contains this text
+        #[arg(long)]
+        filter: Option<String>,
+        /// Print results as JSON instead of a table
+        #[arg(long)]
+        json: bool,
+        /// Write the fixture documents to DIR and exit without benchmarking
+        #[arg(long, value_name = "DIR")]
+        write_fixtures: Option<String>,
+    },
+    /// Print a shell completion script to stdout
+    ///
+    /// For example: `mdmd completions zsh > ~/.zfunc/_mdmd`, or
+    /// `mdmd completions bash > ~/.local/share/bash-completion/completions/mdmd`.
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+    /// Print the mdmd(1) man page (roff) to stdout
+    Man {
+        /// Write mdmd.1 and one page per subcommand (mdmd-serve.1, ...) to DIR
+        /// instead of printing
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<String>,
+    },
+    /// Print build metadata as JSON: version, git commit, build date,
+    /// enabled features, default config path, and embedded asset hashes
+    ///
+    /// Include this output in bug reports.  `mdmd --version --verbose` prints
+    /// the same thing.
+    Info,
+    /// Inspect configuration
+    ///
+    /// Settings are layered: built-in defaults < config file < MDMD_*
+    /// environment variables < command-line flags.  The config file is read
+    /// from $MDMD_CONFIG, else $XDG_CONFIG_HOME/mdmd/config.toml (or
+    /// ~/.config/mdmd/config.toml).
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+/// `mdmd config` actions.
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the config file, or with --resolved every effective setting
+    Show {
+        /// Print each setting's effective value and the layer it came from
+        #[arg(long)]
+        resolved: bool,
+    },
 }
 
-/// Full CLI with explicit subcommands.
+/// Command line: an explicit subcommand, or the legacy `mdmd <file>` form.
+///
+/// Both forms share one parser so help and error messages always describe
+/// what was typed.  A first argument naming a subcommand selects it;
+/// anything else is the file to view.
 #[derive(Parser)]
 #[command(
     name = "mdmd",
     version,
     about = "A TUI markdown viewer and navigator",
-    after_help = "INVOCATION FORMS:\n  mdmd <file>                      View file in TUI mode (legacy)\n  mdmd view <file>                 View file in TUI mode\n  mdmd serve [OPTIONS] <file>      Serve file over HTTP\n\nSERVE NOTES:\n  Serve root defaults to CWD when the entry is inside CWD.\n  Passing an entry outside CWD prints a network-exposure warning.\n  See docs/serve-semantics.md for the full behavior contract."
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    disable_version_flag = true,
+    after_help = "INVOCATION FORMS:\n  mdmd <file>                      View file in TUI mode (legacy)\n  mdmd view <file>                 View file in TUI mode\n  mdmd replay <recording>          Replay a session recorded with --record\n  mdmd preview <file>              View file in a tmux/kitty split beside the editor\n  mdmd bench [--json]              Benchmark parse/render/serve throughput\n  mdmd completions <shell>         Print a shell completion script\n  mdmd man                         Print the man page\n  mdmd info                        Print build metadata as JSON\n  mdmd config show [--resolved]    Print the config file or effective settings\n  mdmd serve [OPTIONS] <file>      Serve file over HTTP\n\nSERVE NOTES:\n  Serve root defaults to CWD when the entry is inside CWD.\n  Passing an entry outside CWD prints a network-exposure warning.\n  See docs/serve-semantics.md for the full behavior contract."
 )]
 struct Cli {
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+    #[command(flatten)]
+    legacy: LegacyArgs,
+    /// Print version
+    #[arg(short = 'V', long)]
+    version: bool,
+    /// With --version, print build metadata as JSON (same as `mdmd info`)
+    #[arg(long, requires = "version")]
+    verbose: bool,
 }
 
-/// Legacy positional form: mdmd <file>
-#[derive(Parser)]
-#[command(name = "mdmd", version, about = "A TUI markdown viewer and navigator")]
-struct LegacyCli {
-    /// Path to a markdown file to view
-    file: String,
+/// Arguments of the legacy positional form: mdmd <file>
+#[derive(clap::Args)]
+struct LegacyArgs {
+    /// Path to a markdown file to view (same as `mdmd view <file>`).
+    /// Without one, a start page lists the project's READMEs, recent
+    /// files, and saved sessions
+    file: Option<String>,
+    /// View FILE as markdown whatever its extension, e.g. LICENSE
+    #[arg(long)]
+    force: bool,
+    #[command(flatten)]
+    scroll: ScrollArgs,
+    #[command(flatten)]
+    open: OpenArgs,
+    /// Record key presses and their timing to FILE for `mdmd replay`
+    #[arg(long, value_name = "FILE")]
+    record: Option<String>,
+    /// Scroll to source lines an editor writes to this Unix socket, one
+    /// `LINE` or `FILE:LINE` per line
+    #[arg(long, value_name = "SOCKET")]
+    listen: Option<String>,
+    /// Take `open FILE`, `scroll [FILE:]LINE`, `reload`, and `quit`
+    /// commands from editors on this Unix socket, one per line
+    #[arg(long, value_name = "SOCKET")]
+    control: Option<String>,
+    /// Reload the document whenever its file changes on disk
+    #[arg(long)]
+    watch: bool,
+    /// Show the file's path within its project as a clickable header
+    #[arg(long)]
+    breadcrumbs: bool,
+    /// Do not make links clickable in terminals that support hyperlinks
+    #[arg(long)]
+    no_hyperlinks: bool,
+    /// Rewrap paragraphs to this many columns (0 keeps their lines as written)
+    #[arg(long, default_value = "0", value_name = "COLS")]
+    text_width: u16,
+    /// Stretch wrapped paragraph lines to the full --text-width
+    #[arg(long)]
+    justify: bool,
+    /// Break long words at their soft hyphens when wrapping to --text-width
+    #[arg(long)]
+    hyphenate: bool,
+    #[command(flatten)]
+    render: RenderArgs,
+}
+
+/// Rendering flags shared by every command that renders markdown; see
+/// [`render_options`].
+#[derive(clap::Args)]
+struct RenderArgs {
+    /// Leave `{{var.name}}` placeholders as written instead of substituting them
+    #[arg(long)]
+    no_vars: bool,
+    /// Prefix headings with hierarchical numbers (1., 1.2, 1.2.3)
+    #[arg(long)]
+    numbered_headings: bool,
+    /// Use curly quotes, en/em dashes for -- and ---, and ellipses for ...
+    #[arg(long)]
+    smart: bool,
+    /// Make heading anchors the way GitHub, GitLab, or kramdown does
+    #[arg(long, value_name = "STYLE", value_parser = ["github", "gitlab", "kramdown"])]
+    slug_style: Option<String>,
+    #[command(flatten)]
+    filters: FilterArgs,
+    /// Lua script, or directory of scripts, defining `{{< name >}}` shortcodes
+    /// (needs the `shortcodes` feature)
+    #[arg(long, value_name = "PATH")]
+    shortcodes: Option<String>,
+}
+
+/// Scrolling flags shared by the TUI invocation forms.
+#[derive(clap::Args)]
+struct ScrollArgs {
+    /// Lines to scroll per j/k press
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u16).range(1..))]
+    scroll_step: u16,
+    /// Animate page and jump scrolling instead of snapping
+    #[arg(long)]
+    smooth_scroll: bool,
+}
+
+/// Handler flags for links to local non-markdown files.
+#[derive(clap::Args)]
+struct OpenArgs {
+    /// Open linked files with these extensions using CMD (repeatable).
+    /// EXT may be a comma-separated list; prefix CMD with `!` to run it in
+    /// the terminal, e.g. `--open-with png,jpg=feh --open-with 'log=!less'`
+    #[arg(long = "open-with", value_name = "EXT=CMD", value_parser = handlers::parse_rule)]
+    open_with: Vec<HandlerRule>,
+}
+
+/// External filter flags shared by every rendering command.
+#[derive(clap::Args)]
+struct FilterArgs {
+    /// Pipe the document through CMD (repeatable): `markdown=CMD` before it
+    /// is parsed, `html=CMD` after it is rendered (serve and html only),
+    /// e.g. `--filter 'markdown=pandoc -f markdown -t gfm'`
+    #[arg(long, value_name = "STAGE=CMD", value_parser = filter::parse_filter)]
+    filter: Vec<filter::Filter>,
+    /// Seconds a filter may run before it is killed and skipped
+    #[arg(long, default_value = "10", value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..=3600))]
+    filter_timeout: u64,
+}
+
+/// Scrolling behavior for the TUI viewer.
+#[derive(Debug, Clone, Copy)]
+struct ScrollConfig {
+    /// Lines moved per `j`/`k` press.
+    step: usize,
+    /// Whether multi-line jumps are animated over several frames.
+    smooth: bool,
 }
 
 /// Resolved dispatch mode after CLI argument parsing.
 enum DispatchMode {
     Legacy {
-        file: String,
+        /// `None` shows the start page.
+        file: Option<String>,
+        force: bool,
+        scroll: ScrollConfig,
+        handlers: HandlerTable,
+        record: Option<String>,
+        listen: Option<String>,
+        control: Option<String>,
+        watch: bool,
+        locale: Locale,
+        breadcrumbs: bool,
+        hyperlinks: bool,
+        vars: bool,
+        render: html::RenderOptions,
     },
     View {
+        files: Vec<String>,
+        stdin_title: String,
+        force: bool,
+        scroll: ScrollConfig,
+        handlers: HandlerTable,
+        record: Option<String>,
+        listen: Option<String>,
+        control: Option<String>,
+        watch: bool,
+        locale: Locale,
+        breadcrumbs: bool,
+        hyperlinks: bool,
+        vars: bool,
+        render: html::RenderOptions,
+        session: Option<String>,
+    },
+    Replay {
+        recording: String,
+        file: Option<String>,
+        speed: f64,
+        locale: Locale,
+    },
+    Preview {
         file: String,
     },
     Serve {
-        file: String,
+        files: Vec<String>,
         bind: String,
         port: u16,
         no_open: bool,
         verbose: bool,
+        render_budget_ms: u64,
+        request_budget_ms: u64,
+        locale: Locale,
+        site_title: Option<String>,
+        favicon: Option<String>,
+        allow_edit: bool,
+        drafts: bool,
+        vars: bool,
+        render: html::RenderOptions,
+        dev: bool,
+        cache: serve::CachePolicy,
+        limits: serve::ServeLimits,
+        case_insensitive: bool,
+        follow_symlinks: bool,
+        allow: Vec<PathBuf>,
+        serve_types: Vec<String>,
+        audit_log: Option<PathBuf>,
+        no_audit_log: bool,
+        share: Option<Duration>,
+        copy_url: bool,
+        open_browser: Option<String>,
+        open_path: Option<String>,
+        control: Option<PathBuf>,
     },
     Html {
         file: String,
         output: Option<String>,
+        single_file: bool,
         constrained: bool,
+        locale: Locale,
+        site_title: Option<String>,
+        vars: bool,
+        render: html::RenderOptions,
+    },
+    Render {
+        file: String,
+        format: render_export::Format,
+        hyperlinks: bool,
+        vars: bool,
+        render: html::RenderOptions,
     },
     Headings {
         file: String,
         max_level: Option<u8>,
     },
+    Outline {
+        file: String,
+        json: bool,
+        slug_style: html::SlugStyle,
+    },
     Select {
         file: String,
         heading: Option<String>,
         index: Option<usize>,
     },
+    Bench {
+        iterations: u32,
+        filter: Option<String>,
+        json: bool,
+        write_fixtures: Option<String>,
+    },
+    Completions {
+        shell: clap_complete::Shell,
+    },
+    Man {
+        out_dir: Option<String>,
+    },
+    Version {
+        verbose: bool,
+    },
+    Info,
+    ConfigShow {
+        resolved: bool,
+    },
 }
 
 /// State for vim-like `/` search.
@@ -194,6 +816,28 @@ struct SearchState {
     saved_scroll: usize,
 }
 
+/// State for the visited-files history modal.
+struct HistoryState {
+    /// Index of the selected visit, newest first.
+    selected: usize,
+}
+
+/// State for the backlinks modal: the documents linking to the viewed file.
+struct BacklinksModal {
+    /// Inbound links, ordered by source path.
+    refs: Vec<backlinks::BacklinkRef>,
+    /// Index of the selected backlink.
+    selected: usize,
+}
+
+/// State for the outgoing links modal: the viewed file's own links.
+struct OutgoingModal {
+    /// Links in source order, with their resolution status.
+    links: Vec<outgoing::OutgoingLink>,
+    /// Index of the selected link.
+    selected: usize,
+}
+
 /// State for the help/shortcuts modal overlay.
 struct HelpState {
     /// Current filter string for narrowing displayed shortcuts.
@@ -204,6 +848,38 @@ struct HelpState {
     saved_scroll: usize,
 }
 
+/// Which step of the find-and-replace flow is active.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReplaceStage {
+    /// Typing the text to find.
+    Find,
+    /// Typing the replacement text.
+    Replacement,
+    /// Reviewing matches across files, accepting or rejecting each one.
+    Review,
+}
+
+/// State for the project-wide `R` find-and-replace flow.
+struct ReplaceState {
+    stage: ReplaceStage,
+    /// Literal text to find.
+    find: String,
+    /// Text substituted for each accepted match.
+    replacement: String,
+    /// Matches collected across the project, in file order.
+    candidates: Vec<replace::ReplaceCandidate>,
+    /// Index into `candidates` of the match under review.
+    selected: usize,
+}
+
+/// State for the `Y` prompt naming the file to save a section to.
+struct SectionSave {
+    /// The section's markdown source.
+    text: String,
+    /// File name typed so far, relative to the document's directory.
+    path: String,
+}
+
 /// A single keyboard shortcut entry.
 struct ShortcutEntry {
     key: &'static str,
@@ -224,11 +900,11 @@ fn shortcut_categories() -> Vec<ShortcutCategory> {
             entries: vec![
                 ShortcutEntry {
                     key: "j / \u{2193}",
-                    description: "Scroll down one line",
+                    description: "Scroll down (--scroll-step lines)",
                 },
                 ShortcutEntry {
                     key: "k / \u{2191}",
-                    description: "Scroll up one line",
+                    description: "Scroll up (--scroll-step lines)",
                 },
                 ShortcutEntry {
                     key: "Ctrl-d / PgDn",
@@ -238,6 +914,18 @@ fn shortcut_categories() -> Vec<ShortcutCategory> {
                     key: "Ctrl-u / PgUp",
                     description: "Scroll up half page",
                 },
+                ShortcutEntry {
+                    key: "Ctrl-f / Space",
+                    description: "Scroll down full page",
+                },
+                ShortcutEntry {
+                    key: "Ctrl-b",
+                    description: "Scroll up full page",
+                },
+                ShortcutEntry {
+                    key: "zz / zt / zb",
+                    description: "Recentre view on current line",
+                },
                 ShortcutEntry {
                     key: "g / Home",
                     description: "Jump to top",
@@ -263,6 +951,10 @@ fn shortcut_categories() -> Vec<ShortcutCategory> {
                     key: "o",
                     description: "Open outline",
                 },
+                ShortcutEntry {
+                    key: "Ctrl-k",
+                    description: "Go to heading by typing part of its name",
+                },
             ],
         },
         ShortcutCategory {
@@ -288,6 +980,27 @@ fn shortcut_categories() -> Vec<ShortcutCategory> {
                     key: "Esc",
                     description: "Cancel search",
                 },
+                ShortcutEntry {
+                    key: "R",
+                    description: "Find and replace across files",
+                },
+            ],
+        },
+        ShortcutCategory {
+            name: "Editing",
+            entries: vec![
+                ShortcutEntry {
+                    key: "x",
+                    description: "Toggle task checkbox on the current line",
+                },
+                ShortcutEntry {
+                    key: "y",
+                    description: "Copy the markdown of the current section",
+                },
+                ShortcutEntry {
+                    key: "Y",
+                    description: "Save the markdown of the current section to a file",
+                },
             ],
         },
         ShortcutCategory {
@@ -309,6 +1022,47 @@ fn shortcut_categories() -> Vec<ShortcutCategory> {
                     key: "Backspace",
                     description: "Navigate back",
                 },
+                ShortcutEntry {
+                    key: "L",
+                    description: "Navigate forward",
+                },
+                ShortcutEntry {
+                    key: "H",
+                    description: "Show visited files history",
+                },
+                ShortcutEntry {
+                    key: "Ctrl-p",
+                    description: "Open a project file by typing part of its path",
+                },
+                ShortcutEntry {
+                    key: "b",
+                    description: "Show backlinks to this file",
+                },
+                ShortcutEntry {
+                    key: "O",
+                    description: "Show outgoing links and whether they resolve",
+                },
+                ShortcutEntry {
+                    key: "Ctrl-o",
+                    description: "Jump back to previous position",
+                },
+                ShortcutEntry {
+                    key: "Ctrl-i",
+                    description: "Jump forward to next position",
+                },
+            ],
+        },
+        ShortcutCategory {
+            name: "Files",
+            entries: vec![
+                ShortcutEntry {
+                    key: "]f",
+                    description: "Next file given on the command line",
+                },
+                ShortcutEntry {
+                    key: "[f",
+                    description: "Previous file given on the command line",
+                },
             ],
         },
         ShortcutCategory {
@@ -318,6 +1072,22 @@ fn shortcut_categories() -> Vec<ShortcutCategory> {
                     key: "?",
                     description: "Toggle this help",
                 },
+                ShortcutEntry {
+                    key: ":",
+                    description: "Run any command by name",
+                },
+                ShortcutEntry {
+                    key: "s",
+                    description: "Switch between the rendering and the markdown source",
+                },
+                ShortcutEntry {
+                    key: "S",
+                    description: "Show or hide the markdown source beside the rendering",
+                },
+                ShortcutEntry {
+                    key: "w",
+                    description: "Show or hide tabs, non-breaking and trailing spaces",
+                },
                 ShortcutEntry {
                     key: "q",
                     description: "Quit",
@@ -331,65 +1101,345 @@ fn shortcut_categories() -> Vec<ShortcutCategory> {
     ]
 }
 
-/// Saved navigation state for back-navigation when following links.
-struct NavigationEntry {
-    file_path: PathBuf,
-    scroll_offset: usize,
-    focused_link: Option<usize>,
+/// Resolve the settings for `command` from every layer, exiting with a
+/// message when the config file or an `MDMD_*` variable is invalid.
+fn load_settings(command: Option<&str>, matches: Option<&ArgMatches>) -> settings::Settings {
+    settings::resolve(&Cli::command(), command, matches).unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        process::exit(2);
+    })
 }
 
-fn resolve_dispatch_mode() -> DispatchMode {
-    match Cli::try_parse() {
-        Ok(cli) => match cli.command {
-            Commands::View { file } => DispatchMode::View { file },
-            Commands::Serve {
-                file,
-                bind,
-                port,
-                no_open,
-                verbose,
-            } => DispatchMode::Serve {
-                file,
-                bind,
-                port,
-                no_open,
-                verbose,
+/// The shortcode scripts named by the `key` setting, exiting when they
+/// cannot be loaded.
+#[cfg(feature = "shortcodes")]
+fn load_shortcodes(settings: &settings::Settings, key: &str) -> shortcode::Shortcodes {
+    let Some(path) = settings.optional_string(key) else {
+        return shortcode::Shortcodes::default();
+    };
+    shortcode::Shortcodes::load(Path::new(&path)).unwrap_or_else(|e| {
+        eprintln!("Error: --shortcodes: {e}");
+        process::exit(2);
+    })
+}
+
+/// Exit when the `key` setting names shortcode scripts, which this build
+/// cannot run without the `shortcodes` feature.
+#[cfg(not(feature = "shortcodes"))]
+fn reject_shortcodes(settings: &settings::Settings, key: &str) {
+    if settings.optional_string(key).is_some() {
+        eprintln!("Error: --shortcodes: mdmd was built without the `shortcodes` feature");
+        process::exit(2);
+    }
+}
+
+/// The page template named by the `key` setting, exiting when it cannot be
+/// read or does not parse.
+fn load_template(settings: &settings::Settings, key: &str) -> Option<template::PageTemplate> {
+    let path = settings.optional_string(key)?;
+    Some(
+        template::PageTemplate::load(Path::new(&path)).unwrap_or_else(|e| {
+            eprintln!("Error: --template: {e}");
+            process::exit(2);
+        }),
+    )
+}
+
+/// The extra `<head>` tags of the `--inject-head` and `--allow-page-head`
+/// settings of `command`, exiting when the injected file cannot be used.
+fn page_head(settings: &settings::Settings, command: &str) -> head::PageHead {
+    let inject = settings
+        .optional_string(&format!("{command}.inject_head"))
+        .map(|path| {
+            head::load(Path::new(&path)).unwrap_or_else(|e| {
+                eprintln!("Error: --inject-head: {e}");
+                process::exit(2);
+            })
+        });
+    head::PageHead {
+        inject,
+        allow_page_head: settings.bool(&format!("{command}.allow_page_head")),
+    }
+}
+
+/// The `--slug-style` setting `key`, exiting on a name that is not one.
+fn slug_style(settings: &settings::Settings, key: &str) -> html::SlugStyle {
+    let Some(name) = settings.optional_string(key) else {
+        return html::SlugStyle::default();
+    };
+    html::SlugStyle::parse(&name).unwrap_or_else(|| {
+        eprintln!(
+            "Error: {key}: unknown slug style '{name}', expected github, gitlab, or kramdown"
+        );
+        process::exit(2);
+    })
+}
+
+/// The [`RenderArgs`] settings of `command` as render options, which
+/// callers extend with their own.
+fn render_options(settings: &settings::Settings, command: &str) -> html::RenderOptions {
+    let key = |name: &str| format!("{command}.{name}");
+    #[cfg(not(feature = "shortcodes"))]
+    reject_shortcodes(settings, &key("shortcodes"));
+    html::RenderOptions {
+        numbered_headings: settings.bool(&key("numbered_headings")),
+        smart_punctuation: settings.bool(&key("smart")),
+        slug_style: slug_style(settings, &key("slug_style")),
+        filters: settings.filters(command),
+        #[cfg(feature = "shortcodes")]
+        shortcodes: load_shortcodes(settings, &key("shortcodes")),
+        ..html::RenderOptions::default()
+    }
+}
+
+/// TUI settings shared by the legacy and `view` forms.
+fn view_settings(
+    matches: &ArgMatches,
+) -> (
+    ScrollConfig,
+    HandlerTable,
+    Locale,
+    bool,
+    bool,
+    bool,
+    html::RenderOptions,
+) {
+    let settings = load_settings(Some("view"), Some(matches));
+    let scroll = ScrollConfig {
+        step: settings.uint("view.scroll_step") as usize,
+        smooth: settings.bool("view.smooth_scroll"),
+    };
+    (
+        scroll,
+        HandlerTable::new(settings.handler_rules("view.open_with")),
+        settings.locale(),
+        settings.bool("view.breadcrumbs"),
+        !settings.bool("view.no_hyperlinks") && hyperlink::terminal_supports(),
+        !settings.bool("view.no_vars"),
+        html::RenderOptions {
+            layout: render::Layout {
+                text_width: match settings.uint("view.text_width") {
+                    0 => None,
+                    cols => Some(cols as usize),
+                },
+                justify: settings.bool("view.justify"),
+                hyphenate: settings.bool("view.hyphenate"),
+                show_whitespace: false,
+                light_theme: false,
             },
-            Commands::Html {
-                file,
-                output,
-                constrained,
-            } => DispatchMode::Html {
+            ..render_options(&settings, "view")
+        },
+    )
+}
+
+fn resolve_dispatch_mode() -> DispatchMode {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let sub_matches = |name: &str| {
+        matches
+            .subcommand_matches(name)
+            .expect("matches exist for the parsed subcommand")
+    };
+    match cli.command {
+        Some(Commands::View {
+            files,
+            stdin_title,
+            force,
+            record,
+            listen,
+            control,
+            watch,
+            session,
+            ..
+        }) => {
+            let (scroll, handlers, locale, breadcrumbs, hyperlinks, vars, render) =
+                view_settings(sub_matches("view"));
+            DispatchMode::View {
+                files,
+                stdin_title,
+                force,
+                scroll,
+                handlers,
+                record,
+                listen,
+                control,
+                watch,
+                locale,
+                breadcrumbs,
+                hyperlinks,
+                vars,
+                render,
+                session,
+            }
+        }
+        Some(Commands::Replay {
+            recording,
+            file,
+            speed,
+        }) => DispatchMode::Replay {
+            recording,
+            file,
+            speed,
+            locale: load_settings(Some("replay"), None).locale(),
+        },
+        Some(Commands::Preview { file }) => DispatchMode::Preview { file },
+        Some(Commands::Serve {
+            files,
+            share,
+            control,
+            ..
+        }) => {
+            let settings = load_settings(Some("serve"), Some(sub_matches("serve")));
+            DispatchMode::Serve {
+                files,
+                bind: settings.string("serve.bind"),
+                port: settings.uint("serve.port") as u16,
+                no_open: settings.bool("serve.no_open"),
+                verbose: settings.bool("serve.verbose"),
+                render_budget_ms: settings.uint("serve.render_budget_ms"),
+                request_budget_ms: settings.uint("serve.request_budget_ms"),
+                locale: settings.locale(),
+                site_title: settings.optional_string("serve.site_title"),
+                favicon: settings.optional_string("serve.favicon"),
+                allow_edit: settings.bool("serve.allow_edit"),
+                drafts: settings.bool("serve.drafts"),
+                vars: !settings.bool("serve.no_vars"),
+                dev: settings.bool("serve.dev"),
+                cache: serve::CachePolicy {
+                    page_max_age: settings.uint("serve.page_max_age"),
+                    asset_max_age: settings.uint("serve.asset_max_age"),
+                },
+                limits: serve::ServeLimits {
+                    request_timeout: Duration::from_secs(settings.uint("serve.request_timeout")),
+                    max_in_flight: settings.uint("serve.max_in_flight") as usize,
+                    max_body_bytes: settings.uint("serve.max_body_bytes") as usize,
+                },
+                case_insensitive: settings.bool("serve.case_insensitive"),
+                follow_symlinks: settings.bool("serve.follow_symlinks")
+                    && !settings.bool("serve.no_follow_symlinks"),
+                allow: settings.paths("serve.allow"),
+                serve_types: settings.extensions("serve.serve_types"),
+                audit_log: settings
+                    .optional_string("serve.audit_log")
+                    .map(PathBuf::from),
+                no_audit_log: settings.bool("serve.no_audit_log"),
+                share,
+                copy_url: settings.bool("serve.copy_url"),
+                open_browser: settings.optional_string("serve.open_browser"),
+                open_path: settings.optional_string("serve.open_path"),
+                control,
+                render: html::RenderOptions {
+                    heading_offset: settings.uint("serve.heading_offset") as u8,
+                    title_from_h1: settings.bool("serve.title_from_h1"),
+                    head: page_head(&settings, "serve"),
+                    template: load_template(&settings, "serve.template"),
+                    ..render_options(&settings, "serve")
+                },
+            }
+        }
+        Some(Commands::Html {
+            file,
+            output,
+            single_file,
+            ..
+        }) => {
+            let settings = load_settings(Some("html"), Some(sub_matches("html")));
+            DispatchMode::Html {
                 file,
                 output,
-                constrained,
-            },
-            Commands::Headings { file, max_level } => {
-                DispatchMode::Headings { file, max_level }
+                single_file,
+                constrained: settings.bool("html.constrained"),
+                locale: settings.locale(),
+                site_title: settings.optional_string("html.site_title"),
+                vars: !settings.bool("html.no_vars"),
+                render: html::RenderOptions {
+                    heading_offset: settings.uint("html.heading_offset") as u8,
+                    title_from_h1: settings.bool("html.title_from_h1"),
+                    head: page_head(&settings, "html"),
+                    template: load_template(&settings, "html.template"),
+                    ..render_options(&settings, "html")
+                },
             }
-            Commands::Select {
-                file,
-                heading,
-                index,
-            } => DispatchMode::Select {
+        }
+        Some(Commands::Render { file, .. }) => {
+            let settings = load_settings(Some("render"), Some(sub_matches("render")));
+            let format = match settings.optional_string("render.format") {
+                None => render_export::Format::for_stdout(),
+                Some(name) => render_export::Format::parse(&name).unwrap_or_else(|| {
+                    eprintln!(
+                        "Error: render.format: unknown format '{name}', expected ansi, plain, or html"
+                    );
+                    process::exit(2);
+                }),
+            };
+            DispatchMode::Render {
                 file,
-                heading,
-                index,
-            },
-        },
-        Err(clap_err) => {
-            // Pass --help, --version, and subcommand-level help through to the full Cli handler.
-            use clap::error::ErrorKind;
-            if matches!(
-                clap_err.kind(),
-                ErrorKind::DisplayHelp | ErrorKind::DisplayVersion
-            ) {
-                clap_err.exit();
+                format,
+                hyperlinks: !settings.bool("render.no_hyperlinks")
+                    && hyperlink::terminal_supports(),
+                vars: !settings.bool("render.no_vars"),
+                render: render_options(&settings, "render"),
             }
-            // Fall back to legacy positional parse: mdmd <file>
-            match LegacyCli::try_parse() {
-                Ok(legacy) => DispatchMode::Legacy { file: legacy.file },
-                Err(legacy_err) => legacy_err.exit(),
+        }
+        Some(Commands::Headings { file, max_level }) => DispatchMode::Headings { file, max_level },
+        Some(Commands::Outline {
+            file,
+            format,
+            slug_style,
+        }) => DispatchMode::Outline {
+            file,
+            json: format == "json",
+            slug_style: slug_style
+                .and_then(|name| html::SlugStyle::parse(&name))
+                .unwrap_or_default(),
+        },
+        Some(Commands::Select {
+            file,
+            heading,
+            index,
+        }) => DispatchMode::Select {
+            file,
+            heading,
+            index,
+        },
+        Some(Commands::Bench {
+            iterations,
+            filter,
+            json,
+            write_fixtures,
+        }) => DispatchMode::Bench {
+            iterations,
+            filter,
+            json,
+            write_fixtures,
+        },
+        Some(Commands::Completions { shell }) => DispatchMode::Completions { shell },
+        Some(Commands::Man { out_dir }) => DispatchMode::Man { out_dir },
+        Some(Commands::Info) => DispatchMode::Info,
+        Some(Commands::Config {
+            action: ConfigAction::Show { resolved },
+        }) => DispatchMode::ConfigShow { resolved },
+        None if cli.version => DispatchMode::Version {
+            verbose: cli.verbose,
+        },
+        None => {
+            let legacy = cli.legacy;
+            let (scroll, handlers, locale, breadcrumbs, hyperlinks, vars, render) =
+                view_settings(&matches);
+            DispatchMode::Legacy {
+                scroll,
+                handlers,
+                locale,
+                breadcrumbs,
+                hyperlinks,
+                vars,
+                render,
+                record: legacy.record,
+                listen: legacy.listen,
+                control: legacy.control,
+                watch: legacy.watch,
+                force: legacy.force,
+                file: legacy.file,
             }
         }
     }
@@ -397,43 +1447,287 @@ fn resolve_dispatch_mode() -> DispatchMode {
 
 fn main() -> io::Result<()> {
     match resolve_dispatch_mode() {
-        DispatchMode::Legacy { file } => run_tui_file(&file),
-        DispatchMode::View { file } => {
-            eprintln!("[view] TUI viewer dispatched for: {file}");
-            run_tui_file(&file)
+        DispatchMode::Legacy {
+            file,
+            force,
+            scroll,
+            handlers,
+            record,
+            listen,
+            control,
+            watch,
+            locale,
+            breadcrumbs,
+            hyperlinks,
+            vars,
+            render,
+        } => run_tui_file(
+            file.as_slice(),
+            "stdin",
+            force,
+            scroll,
+            &handlers,
+            record.as_deref(),
+            listen.as_deref(),
+            control.as_deref(),
+            watch,
+            locale,
+            breadcrumbs,
+            hyperlinks,
+            vars,
+            render,
+            None,
+            file.is_none().then(|| start_page(locale)),
+        ),
+        DispatchMode::View {
+            files,
+            stdin_title,
+            force,
+            scroll,
+            handlers,
+            record,
+            listen,
+            control,
+            watch,
+            locale,
+            breadcrumbs,
+            hyperlinks,
+            vars,
+            render,
+            session,
+        } => {
+            match (files.as_slice(), &session) {
+                ([], Some(name)) => eprintln!("[view] TUI viewer resuming session: {name}"),
+                ([], None) => {}
+                (files, _) => eprintln!("[view] TUI viewer dispatched for: {}", files.join(" ")),
+            }
+            run_tui_file(
+                &files,
+                &stdin_title,
+                force,
+                scroll,
+                &handlers,
+                record.as_deref(),
+                listen.as_deref(),
+                control.as_deref(),
+                watch,
+                locale,
+                breadcrumbs,
+                hyperlinks,
+                vars,
+                render,
+                session.as_deref(),
+                None,
+            )
         }
-        DispatchMode::Serve {
+        DispatchMode::Replay {
+            recording,
             file,
+            speed,
+            locale,
+        } => run_replay(&recording, file.as_deref(), speed, locale),
+        DispatchMode::Preview { file } => preview::run_preview(&file),
+        DispatchMode::Serve {
+            files,
             bind,
             port,
             no_open,
             verbose,
+            render_budget_ms,
+            request_budget_ms,
+            locale,
+            site_title,
+            favicon,
+            allow_edit,
+            drafts,
+            vars,
+            render,
+            dev,
+            cache,
+            limits,
+            case_insensitive,
+            follow_symlinks,
+            allow,
+            serve_types,
+            audit_log,
+            no_audit_log,
+            share,
+            copy_url,
+            open_browser,
+            open_path,
+            control,
         } => {
+            let config = serve::AppConfig {
+                latency_budget: serve::LatencyBudget {
+                    render: Duration::from_millis(render_budget_ms),
+                    total: Duration::from_millis(request_budget_ms),
+                },
+                locale,
+                favicon: favicon.map(PathBuf::from),
+                site_title,
+                allow_edit,
+                drafts,
+                vars,
+                render,
+                dev_assets: dev.then(|| PathBuf::from(web_assets::SOURCE_DIR)),
+                cache,
+                limits,
+                case_insensitive,
+                follow_symlinks,
+                allow,
+                serve_types: Some(serve_types).filter(|types| !types.is_empty()),
+                audit_log,
+                no_audit_log,
+                share,
+                copy_url,
+                open_browser,
+                open_path,
+                control,
+            };
             let rt = tokio::runtime::Builder::new_multi_thread()
                 .enable_all()
                 .build()
                 .map_err(io::Error::other)?;
-            rt.block_on(serve::run_serve(file, bind, port, no_open, verbose))
+            rt.block_on(serve::run_serve(
+                files, bind, port, no_open, verbose, config,
+            ))
         }
         DispatchMode::Html {
             file,
             output,
+            single_file,
             constrained,
-        } => html_export::run_html(&file, output.as_deref(), !constrained),
+            locale,
+            site_title,
+            vars,
+            render,
+        } => {
+            let run = if single_file {
+                html_export::run_combined
+            } else {
+                html_export::run_html
+            };
+            run(
+                &file,
+                output.as_deref(),
+                !constrained,
+                locale,
+                site_title.as_deref(),
+                vars,
+                render,
+            )
+        }
+        DispatchMode::Render {
+            file,
+            format,
+            hyperlinks,
+            vars,
+            render,
+        } => render_export::run_render(&file, format, hyperlinks, vars, render),
         DispatchMode::Headings { file, max_level } => run_headings(&file, max_level),
+        DispatchMode::Outline {
+            file,
+            json,
+            slug_style,
+        } => run_outline(&file, json, slug_style),
         DispatchMode::Select {
             file,
             heading,
             index,
         } => run_select(&file, heading.as_deref(), index),
+        DispatchMode::Bench {
+            iterations,
+            filter,
+            json,
+            write_fixtures,
+        } => run_bench(
+            iterations as usize,
+            filter.as_deref(),
+            json,
+            write_fixtures.as_deref(),
+        ),
+        DispatchMode::Completions { shell } => {
+            clap_complete::generate(shell, &mut Cli::command(), "mdmd", &mut io::stdout());
+            Ok(())
+        }
+        DispatchMode::Man { out_dir } => run_man(out_dir.as_deref()),
+        DispatchMode::ConfigShow { resolved } => run_config_show(resolved),
+        DispatchMode::Version { verbose: false } => {
+            println!("mdmd {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        DispatchMode::Version { verbose: true } | DispatchMode::Info => {
+            let info = info::build_info_json();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&info).map_err(io::Error::other)?
+            );
+            Ok(())
+        }
     }
 }
 
-/// Read a markdown file, validating its extension and handling errors.
-fn read_markdown_file(file_arg: &str) -> String {
-    let path = Path::new(file_arg);
-
+/// Print the config file, or every effective setting with its source.
+fn run_config_show(resolved: bool) -> io::Result<()> {
+    if resolved {
+        let settings = load_settings(None, None);
+        let lines: Vec<(String, String)> = settings
+            .entries()
+            .iter()
+            .map(|e| (format!("{} = {}", e.key, e.value), e.source.to_string()))
+            .collect();
+        let width = lines.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+        for (line, source) in lines {
+            println!("{line:<width$}  # {source}");
+        }
+        return Ok(());
+    }
+    match settings::config_path() {
+        None => println!("# no config file: neither XDG_CONFIG_HOME nor HOME is set"),
+        Some((path, required)) => match fs::read_to_string(&path) {
+            Ok(text) => {
+                println!("# {}", path.display());
+                print!("{text}");
+            }
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => {
+                println!("# no config file at {}", path.display());
+            }
+            Err(e) => {
+                eprintln!("Error: cannot read config file {}: {e}", path.display());
+                process::exit(2);
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Render the man page to stdout, or every page to `out_dir`.
+fn run_man(out_dir: Option<&str>) -> io::Result<()> {
+    match out_dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            clap_mangen::generate_to(Cli::command(), dir)?;
+            let mut pages: Vec<PathBuf> = fs::read_dir(dir)?
+                .filter_map(|e| e.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|e| e == "1"))
+                .collect();
+            pages.sort();
+            for page in pages {
+                println!("{}", page.display());
+            }
+            Ok(())
+        }
+        None => clap_mangen::Man::new(Cli::command()).render(&mut io::stdout()),
+    }
+}
+
+/// Read a markdown file, validating its extension unless `force` and
+/// handling errors.
+fn read_markdown_file(file_arg: &str, force: bool) -> String {
+    let path = Path::new(file_arg);
+
     match path.extension().and_then(|e| e.to_str()) {
+        _ if force => {}
         Some("md" | "markdown" | "mdx" | "mdown" | "mkd" | "mkdn") => {}
         Some(ext) => {
             eprintln!("Error: '{ext}' is not a recognized markdown extension.");
@@ -447,7 +1741,7 @@ fn read_markdown_file(file_arg: &str) -> String {
         }
     }
 
-    fs::read_to_string(path).unwrap_or_else(|e| {
+    let bytes = fs::read(path).unwrap_or_else(|e| {
         match e.kind() {
             io::ErrorKind::NotFound => {
                 eprintln!("Error: file not found: {file_arg}");
@@ -460,11 +1754,74 @@ fn read_markdown_file(file_arg: &str) -> String {
             }
         }
         process::exit(1);
-    })
+    });
+    if binary::looks_binary(&bytes) {
+        eprintln!("Error: '{file_arg}' is a binary file.");
+        process::exit(1);
+    }
+    encoding::decode(&bytes).text
+}
+
+/// Markdown read from stdin (`mdmd view -`), viewed under a title.
+struct StdinDocument {
+    /// Stands in for the document's path: the title in the working
+    /// directory, so relative links resolve from there.
+    path: PathBuf,
+    title: String,
+    text: String,
+}
+
+impl StdinDocument {
+    /// Read stdin to its end, exiting when it is a terminal or unreadable.
+    fn read(title: &str) -> StdinDocument {
+        if io::stdin().is_terminal() {
+            eprintln!("Error: stdin is a terminal; pipe markdown into `mdmd view -`");
+            process::exit(1);
+        }
+        let text = io::read_to_string(io::stdin()).unwrap_or_else(|e| {
+            eprintln!("Error reading stdin: {e}");
+            process::exit(1);
+        });
+        let cwd = std::env::current_dir().unwrap_or_default();
+        StdinDocument {
+            path: cwd.join(title),
+            title: title.to_owned(),
+            text,
+        }
+    }
+}
+
+/// The markdown at `path`: the text read from stdin when `path` stands
+/// for it, else the file's decoded contents, or a placeholder for a binary
+/// file.
+fn read_document(stdin: Option<&StdinDocument>, path: &Path) -> io::Result<String> {
+    match stdin {
+        Some(doc) if doc.path == path => Ok(doc.text.clone()),
+        _ => {
+            let bytes = fs::read(path)?;
+            if binary::looks_binary(&bytes) {
+                return Ok(binary::placeholder(path, bytes.len()));
+            }
+            Ok(encoding::decode(&bytes).text)
+        }
+    }
+}
+
+/// Name of the encoding the text file at `path` was decoded from when it is
+/// not UTF-8, for the status bar.
+fn document_encoding(stdin: Option<&StdinDocument>, path: &Path) -> Option<&'static str> {
+    if stdin.is_some_and(|doc| doc.path == path) {
+        return None;
+    }
+    let bytes = fs::read(path).ok()?;
+    if binary::looks_binary(&bytes) {
+        return None;
+    }
+    encoding::non_utf8(&bytes)
 }
 
 fn run_headings(file_arg: &str, max_level: Option<u8>) -> io::Result<()> {
-    let source = read_markdown_file(file_arg);
+    let source = read_markdown_file(file_arg, false);
     let doc = parse::parse(&source);
 
     if doc.headings.is_empty() {
@@ -485,8 +1842,22 @@ fn run_headings(file_arg: &str, max_level: Option<u8>) -> io::Result<()> {
     Ok(())
 }
 
+fn run_outline(file_arg: &str, json: bool, slug_style: html::SlugStyle) -> io::Result<()> {
+    let source = read_markdown_file(file_arg, false);
+    if json {
+        let outline = structure::outline_json(&source, slug_style);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&outline).map_err(io::Error::other)?
+        );
+    } else {
+        print!("{}", structure::outline_markdown(&source, slug_style));
+    }
+    Ok(())
+}
+
 fn run_select(file_arg: &str, heading: Option<&str>, index: Option<usize>) -> io::Result<()> {
-    let source = read_markdown_file(file_arg);
+    let source = read_markdown_file(file_arg, false);
     let doc = parse::parse(&source);
 
     if doc.headings.is_empty() {
@@ -563,60 +1934,985 @@ fn run_select(file_arg: &str, heading: Option<&str>, index: Option<usize>) -> io
     Ok(())
 }
 
-fn run_tui_file(file_arg: &str) -> io::Result<()> {
-    let source = read_markdown_file(file_arg);
-    let path = Path::new(file_arg);
+fn run_bench(
+    iterations: usize,
+    filter: Option<&str>,
+    json: bool,
+    write_fixtures: Option<&str>,
+) -> io::Result<()> {
+    if let Some(dir) = write_fixtures {
+        for path in bench::write_fixtures(Path::new(dir))? {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
+
+    let fixtures: Vec<bench::Fixture> = bench::fixtures()
+        .into_iter()
+        .filter(|f| filter.is_none_or(|pat| f.name.contains(pat)))
+        .collect();
+    if fixtures.is_empty() {
+        eprintln!("Error: no fixtures match '{}'.", filter.unwrap_or(""));
+        process::exit(1);
+    }
+
+    let mut results = Vec::new();
+    for fixture in &fixtures {
+        if !json {
+            eprintln!(
+                "[bench] {} ({} KiB)",
+                fixture.name,
+                fixture.source.len() / 1024
+            );
+        }
+        results.extend(bench::run_fixture(fixture, iterations));
+    }
+
+    if json {
+        println!("{}", bench::format_json(&results));
+    } else {
+        print!("{}", bench::format_table(&results));
+    }
+    Ok(())
+}
+
+/// View `files`, or with `session` the named session's document (with the
+/// first file opened on top of it when given).  Several files are sorted
+/// naturally and queued for `]f` and `[f`.  A file of `-` is read from
+/// stdin and shown as `stdin_title`, and `start_page` is shown in place of
+/// any file.  With `force`, files are viewed as markdown whatever their
+/// extension.  Where the viewer cannot run, the document is paged as plain
+/// text instead.
+#[allow(clippy::too_many_arguments)]
+fn run_tui_file(
+    files: &[String],
+    stdin_title: &str,
+    force: bool,
+    scroll: ScrollConfig,
+    handlers: &HandlerTable,
+    record: Option<&str>,
+    listen: Option<&str>,
+    control: Option<&str>,
+    watch: bool,
+    locale: Locale,
+    breadcrumbs: bool,
+    hyperlinks: bool,
+    vars: bool,
+    render: html::RenderOptions,
+    session: Option<&str>,
+    start_page: Option<StdinDocument>,
+) -> io::Result<()> {
+    if files.len() > 1 && files.iter().any(|f| f == "-") {
+        eprintln!("Error: '-' (stdin) cannot be combined with other files");
+        process::exit(2);
+    }
+    let stdin = start_page.or_else(|| (files == ["-"]).then(|| StdinDocument::read(stdin_title)));
+    let mut files = files.to_vec();
+    files.sort_by(|a, b| queue::natural_cmp(a, b));
+    let canonical_files: Vec<PathBuf> = match &stdin {
+        Some(doc) => vec![doc.path.clone()],
+        None => files
+            .iter()
+            .map(|arg| {
+                let path = Path::new(arg);
+                fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+            })
+            .collect(),
+    };
+    let canonical_arg = canonical_files.first().cloned();
+    let (mut start, session_file) = match session {
+        Some(name) => {
+            let (session_file, start) = open_workspace(name, canonical_arg);
+            (start, Some(session_file))
+        }
+        None => (
+            workspace::Workspace::new(canonical_arg.expect("clap requires FILE without --session")),
+            None,
+        ),
+    };
+    if canonical_files.len() > 1 {
+        start.queue = canonical_files;
+    }
+    let source = match (&stdin, files.split_first()) {
+        (Some(doc), _) => doc.text.clone(),
+        (None, Some((first, _))) if session.is_none() => read_markdown_file(first, force),
+        (None, _) => read_markdown_file(&start.current.file_path.to_string_lossy(), force),
+    };
+    // Check the rest of the queue now rather than when `]f` reaches it.
+    for file in files.iter().skip(1) {
+        read_markdown_file(file, force);
+    }
+    let canonical = start.current.file_path.clone();
+
+    if !pager::tui_supported_here() {
+        let doc = parse_file(&canonical, &source, vars, &render);
+        let mut text = Vec::new();
+        let rendered = render::render_document_with(&doc, &render.layout);
+        render_export::write_text(&mut text, &rendered, false, None)?;
+        return pager::page(&text);
+    }
+
+    let recorder = match record {
+        Some(out) => {
+            match session::Recorder::create(Path::new(out), &canonical, scroll.step, scroll.smooth)
+            {
+                Ok(r) => Some(r),
+                Err(e) => {
+                    eprintln!("Error: cannot create recording '{out}': {e}");
+                    process::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+    let sockets = [
+        (listen, control::parse_scroll as control::Parser),
+        (control, control::parse_command),
+    ];
+    let listeners: Vec<control::Listener> = sockets
+        .into_iter()
+        .filter_map(|(socket, parse)| {
+            let socket = socket?;
+            match control::Listener::bind(Path::new(socket), parse) {
+                Ok(l) => Some(l),
+                Err(e) => {
+                    eprintln!("Error: cannot listen on '{socket}': {e}");
+                    process::exit(1);
+                }
+            }
+        })
+        .collect();
+
+    let end = run_tui_session(
+        start,
+        source,
+        stdin.as_ref(),
+        scroll,
+        handlers,
+        session::Input::Live(recorder),
+        &listeners,
+        watch,
+        locale,
+        breadcrumbs,
+        hyperlinks,
+        vars,
+        render,
+    )?;
+    if let Some(session_file) = session_file {
+        if let Err(e) = workspace::save(&session_file, &end) {
+            eprintln!("Error: cannot save session {}: {e}", session_file.display());
+            process::exit(1);
+        }
+    }
+    // The recent list only feeds the start page, so failing to keep it is
+    // not worth an error on the way out.
+    if let Some(recent) = start::recent_path() {
+        let _ = start::record_recent(&recent, &end.visits);
+    }
+    Ok(())
+}
+
+/// The start page of the project holding the working directory, shown
+/// when `mdmd` runs without a FILE.  Exits when there is no project.
+fn start_page(locale: Locale) -> StdinDocument {
+    let cwd = std::env::current_dir()
+        .and_then(fs::canonicalize)
+        .unwrap_or_default();
+    let Some(root) = start::project_root(&cwd) else {
+        eprintln!(
+            "Error: no FILE given, and {} is not in a project (no .git or README.md above it)",
+            cwd.display()
+        );
+        process::exit(2);
+    };
+    let readmes: Vec<PathBuf> = replace::collect_markdown_files(&root)
+        .into_iter()
+        .filter(|p| {
+            let name = p.file_name().and_then(|n| n.to_str());
+            matches!(name, Some("README.md" | "index.md"))
+        })
+        .collect();
+    let recent = start::recent_path()
+        .map(|path| start::load_recent(&path))
+        .unwrap_or_default();
+    let sessions = start::sessions_in(&root);
+    let text = start::page(
+        &root,
+        &readmes,
+        &recent,
+        &sessions,
+        SystemTime::now(),
+        locale,
+    );
+    let title = locale.tr("Start page");
+    StdinDocument {
+        path: root.join(title),
+        title: title.to_owned(),
+        text,
+    }
+}
+
+/// Load the session `name` and open `file` on top of it, exiting with a
+/// message when the session cannot be read or there is nothing to show.
+/// Returns the session file and the starting state.
+fn open_workspace(name: &str, file: Option<PathBuf>) -> (PathBuf, workspace::Workspace) {
+    let Some(session_file) = workspace::session_path(name) else {
+        eprintln!("Error: cannot locate sessions: neither XDG_STATE_HOME nor HOME is set");
+        process::exit(1);
+    };
+    let saved = workspace::load(&session_file).unwrap_or_else(|e| {
+        eprintln!("Error: {e}");
+        process::exit(1);
+    });
+    let start = match (saved, file) {
+        (Some(mut ws), Some(file)) => {
+            ws.open(file);
+            ws
+        }
+        (Some(ws), None) => ws,
+        (None, Some(file)) => workspace::Workspace::new(file),
+        (None, None) => {
+            eprintln!("Error: no saved session named '{name}'; pass a FILE to start one");
+            process::exit(1);
+        }
+    };
+    (session_file, start)
+}
+
+/// Validate a `--session` name.
+fn parse_session_name(s: &str) -> Result<String, String> {
+    workspace::validate_name(s).map(|()| s.to_owned())
+}
+
+/// Replay a recorded session against the file it was recorded on (or
+/// `file_override`), then leave the viewer interactive.
+fn run_replay(
+    recording: &str,
+    file_override: Option<&str>,
+    speed: f64,
+    locale: Locale,
+) -> io::Result<()> {
+    let rec = match session::load_recording(Path::new(recording)) {
+        Ok(rec) => rec,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            process::exit(1);
+        }
+    };
+    let file_arg = file_override
+        .map(str::to_owned)
+        .unwrap_or_else(|| rec.file.to_string_lossy().into_owned());
+    let source = read_markdown_file(&file_arg, false);
+    let path = Path::new(&file_arg);
     let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let scroll = ScrollConfig {
+        step: rec.scroll_step,
+        smooth: rec.smooth_scroll,
+    };
+
+    run_tui_session(
+        workspace::Workspace::new(canonical),
+        source,
+        None,
+        scroll,
+        &HandlerTable::default(),
+        session::Input::Replay(session::Player::new(rec.events, speed)),
+        &[],
+        false,
+        locale,
+        false,
+        hyperlink::terminal_supports(),
+        true,
+        html::RenderOptions::default(),
+    )
+    .map(drop)
+}
+
+/// Parse a `--speed` multiplier, which must be positive.
+fn parse_speed(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(v) if v > 0.0 && v.is_finite() => Ok(v),
+        _ => Err(format!("expected a positive number, got '{s}'")),
+    }
+}
+
+/// Run the viewer from `start` until the user quits, returning the state
+/// it was left in.
+#[allow(clippy::too_many_arguments)]
+fn run_tui_session(
+    start: workspace::Workspace,
+    source: String,
+    stdin: Option<&StdinDocument>,
+    scroll: ScrollConfig,
+    handlers: &HandlerTable,
+    mut input: session::Input,
+    listeners: &[control::Listener],
+    watch: bool,
+    locale: Locale,
+    breadcrumbs: bool,
+    hyperlinks: bool,
+    vars: bool,
+    render: html::RenderOptions,
+) -> io::Result<workspace::Workspace> {
+    ratatui::run(|terminal| {
+        let enhanced = enable_key_disambiguation();
+        // Mouse reporting is only needed for clicking breadcrumbs, and it
+        // takes plain drag-selection away from the terminal.
+        let mouse = breadcrumbs && crossterm::execute!(io::stdout(), EnableMouseCapture).is_ok();
+        let result = run(
+            terminal,
+            start,
+            source,
+            stdin,
+            scroll,
+            handlers,
+            &mut input,
+            listeners,
+            watch,
+            locale,
+            breadcrumbs,
+            hyperlinks,
+            vars,
+            render,
+        );
+        if mouse {
+            let _ = crossterm::execute!(io::stdout(), DisableMouseCapture);
+        }
+        if enhanced {
+            let _ = crossterm::execute!(io::stdout(), PopKeyboardEnhancementFlags);
+        }
+        result
+    })
+}
+
+/// Ask the terminal to report modified keys unambiguously, when supported.
+///
+/// Legacy terminals encode `Ctrl-i` as `Tab`; with the kitty keyboard protocol
+/// enabled the two arrive as distinct events so `Ctrl-i` can drive the jump
+/// list while `Tab` keeps cycling links.  Returns whether the flags were pushed.
+fn enable_key_disambiguation() -> bool {
+    if !crossterm::terminal::supports_keyboard_enhancement().unwrap_or(false) {
+        return false;
+    }
+    crossterm::execute!(
+        io::stdout(),
+        PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+    )
+    .is_ok()
+}
+
+/// Frame interval used while a smooth scroll animation is in progress.
+const SMOOTH_SCROLL_FRAME: Duration = Duration::from_millis(16);
+
+/// How often the `--listen` and `--control` sockets, and with `--watch`
+/// the document's file, are checked while waiting for terminal events.
+const CONTROL_POLL: Duration = Duration::from_millis(50);
+
+/// Modification time of the file at `path`, for `--watch`.
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
 
-    ratatui::run(|terminal| run(terminal, &canonical, source))
+/// Re-render `path` from `source` after it changed under the reader.
+/// Returns the new rendering, the offset that keeps the passage shown at
+/// `old_offset` in view, and the changed lines to highlight.
+fn rerender(
+    path: &Path,
+    source: &str,
+    old: &RenderedDocument,
+    old_offset: usize,
+    vars: bool,
+    render: &html::RenderOptions,
+    raw_source: bool,
+) -> (RenderedDocument, usize, changes::Highlight) {
+    let new = render_file(path, source, vars, render, raw_source);
+    let offset = reposition::restore(old, old_offset, &new);
+    let highlight = changes::Highlight::between(path.to_path_buf(), old, &new);
+    (new, offset, highlight)
 }
 
+/// The state handed back when the viewer quits, for `--session`.
+fn final_state(
+    current: NavigationEntry,
+    back: Vec<NavigationEntry>,
+    forward: Vec<NavigationEntry>,
+    history: &VisitHistory,
+    jumps: &JumpList,
+    queue: &queue::Queue,
+) -> workspace::Workspace {
+    let (jump_entries, jump_index) = jumps.snapshot();
+    workspace::Workspace {
+        current,
+        back,
+        forward,
+        visits: history.visits().to_vec(),
+        jumps: jump_entries.to_vec(),
+        jump_index,
+        queue: queue.files().to_vec(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn run(
     terminal: &mut DefaultTerminal,
-    initial_path: &Path,
+    start: workspace::Workspace,
     initial_source: String,
-) -> io::Result<()> {
-    let mut current_path = initial_path.to_path_buf();
-    let doc = parse::parse(&initial_source);
-    let mut rendered = render::render_document(&doc);
+    stdin: Option<&StdinDocument>,
+    scroll: ScrollConfig,
+    handlers: &HandlerTable,
+    input: &mut session::Input,
+    listeners: &[control::Listener],
+    watch: bool,
+    locale: Locale,
+    breadcrumbs: bool,
+    hyperlinks: bool,
+    vars: bool,
+    mut render: html::RenderOptions,
+) -> io::Result<workspace::Workspace> {
+    let mut current_path = start.current.file_path;
+    // Whether the markdown source is shown instead of the rendering (`s`).
+    let mut raw_source = false;
+    let mut rendered = render_file(&current_path, &initial_source, vars, &render, raw_source);
     let mut total_lines = rendered.text.lines.len();
-    let mut scroll_offset: usize = 0;
-    let mut focused_link: Option<usize> = None;
+    let mut scroll_offset = start.current.scroll_offset;
+    let mut focused_link = start
+        .current
+        .focused_link
+        .filter(|&idx| idx < rendered.link_positions.len());
     let mut outline: Option<OutlineState> = None;
+    let mut heading_finder: Option<HeadingFinder> = None;
+    let mut file_finder: Option<FileFinder> = None;
+    let mut palette: Option<Palette> = None;
+    // Keys a palette command presses, handled before the terminal's.
+    let mut queued: VecDeque<KeyEvent> = VecDeque::new();
+    // The server `:serve` started, stopped when the viewer quits.
+    let mut server: Option<BackgroundServer> = None;
     let mut search: Option<SearchState> = None;
     let mut help: Option<HelpState> = None;
-    let mut nav_stack: Vec<NavigationEntry> = Vec::new();
+    let mut nav_stack: Vec<NavigationEntry> = start.back;
+    // Entries popped by Backspace, replayed by `L` until a new link is followed.
+    let mut forward_stack: Vec<NavigationEntry> = start.forward;
+    let mut history = VisitHistory::from_visits(start.visits);
+    let mut history_modal: Option<HistoryState> = None;
+    let mut backlinks_modal: Option<BacklinksModal> = None;
+    let mut outgoing_modal: Option<OutgoingModal> = None;
+    // Backlinks index of a project root, built on first use of `b`.
+    let mut backlinks_index: Option<(PathBuf, backlinks::BacklinksIndex)> = None;
+    let mut jumps = JumpList::restore(start.jumps, start.jump_index);
+    // Files given together on the command line, stepped through by `]f` / `[f`.
+    let mut file_queue = queue::Queue::new(start.queue);
+    // Destination of an in-progress smooth scroll animation.
+    let mut scroll_target: Option<usize> = None;
+    // Set after `z` is pressed, awaiting the `z`/`t`/`b` that completes the command.
+    let mut pending_z = false;
+    // Set after `]` or `[` is pressed, awaiting the `f` that completes the command.
+    let mut pending_bracket: Option<char> = None;
+    let mut replacing: Option<ReplaceState> = None;
+    let mut section_save: Option<SectionSave> = None;
+    // One-shot message shown in the status bar until the next key press.
+    let mut notice: Option<String> = None;
+    let project_root = project_root_for(&current_path);
+    let mut trail: Option<breadcrumbs::Trail> = None;
+    // The file whose encoding was last detected, and the encoding when it
+    // is not UTF-8.
+    let mut encoding: Option<(PathBuf, Option<&'static str>)> = None;
+    // Lines the last reload of the current document changed.
+    let mut changed: Option<changes::Highlight> = None;
+    // Whether the markdown source is shown beside the rendering (`S`).
+    let mut split = false;
+    let mut source_pane: Option<SourcePane> = None;
+    // With `--watch`, the current file and its modification time when it
+    // was last read.  Cleared after the viewer writes the file itself.
+    let mut watched: Option<(PathBuf, Option<SystemTime>)> = None;
+    // Rows taken by the status bar and, when shown, the breadcrumb header.
+    let chrome_rows: u16 = if breadcrumbs { 2 } else { 1 };
+    // Screen cells written inside a hyperlink on the last frame.
+    let mut linked_cells: Vec<Position> = Vec::new();
+
+    file_queue.show(&current_path);
+    if !file_queue.files().is_empty() {
+        notice = Some(locale.trf(
+            "File {n} of {count}",
+            &[
+                ("n", &file_queue.position()),
+                ("count", &file_queue.files().len()),
+            ],
+        ));
+    }
 
     loop {
-        terminal.draw(|frame| {
-            ui(
+        file_queue.show(&current_path);
+        if history.get_newest_first(0).map(|v| &v.file_path) != Some(&current_path) {
+            history.record(&current_path, SystemTime::now());
+        }
+        if breadcrumbs && trail.as_ref().map(|t| &t.file) != Some(&current_path) {
+            trail = Some(breadcrumbs::Trail::for_file(&current_path));
+        }
+        if encoding.as_ref().map(|(path, _)| path) != Some(&current_path) {
+            encoding = Some((
+                current_path.clone(),
+                document_encoding(stdin, &current_path),
+            ));
+        }
+
+        if changed.as_ref().is_some_and(|c| c.path != current_path) {
+            changed = None;
+        }
+        if split {
+            let mtime = modified(&current_path);
+            let stale = source_pane
+                .as_ref()
+                .is_none_or(|pane| pane.path != current_path || pane.modified != mtime);
+            if stale {
+                source_pane = read_document(stdin, &current_path)
+                    .ok()
+                    .map(|source| SourcePane::new(&current_path, mtime, &source, &render.layout));
+            }
+        }
+        if watch && watched.as_ref().map(|(path, _)| path) != Some(&current_path) {
+            watched = Some((current_path.clone(), modified(&current_path)));
+        }
+
+        let mut content_area = None;
+        let completed = terminal.draw(|frame| {
+            content_area = ui(
                 frame,
                 &rendered,
                 scroll_offset,
                 total_lines,
                 focused_link,
+                changed.as_ref().map_or(&[][..], |c| &c.lines[..]),
+                source_pane.as_ref().filter(|_| split).map(|pane| {
+                    let line = rendered.source_line_for_rendered(scroll_offset);
+                    let top = if raw_source {
+                        line
+                    } else {
+                        pane.file_line(line)
+                    };
+                    (&pane.view, pane.view.rendered_line_for_source(top))
+                }),
                 outline.as_ref().map(|o| o.selected),
+                heading_finder.as_ref(),
+                file_finder.as_ref(),
+                palette.as_ref(),
+                history_modal.as_ref().map(|h| (&history, h.selected)),
+                backlinks_modal.as_ref(),
+                outgoing_modal.as_ref(),
                 search.as_ref(),
                 help.as_ref(),
+                replacing.as_ref(),
+                section_save.as_ref(),
+                notice.as_deref(),
+                encoding.as_ref().and_then(|(_, name)| *name),
+                &project_root,
                 &current_path,
+                stdin
+                    .filter(|doc| doc.path == current_path)
+                    .map(|doc| doc.title.as_str()),
                 !nav_stack.is_empty(),
+                locale,
+                trail.as_ref(),
             );
         })?;
+        if hyperlinks {
+            // Links under a modal or the replace prompt are not clickable.
+            let covered = outline.is_some()
+                || heading_finder.is_some()
+                || file_finder.is_some()
+                || palette.is_some()
+                || help.is_some()
+                || history_modal.is_some()
+                || backlinks_modal.is_some()
+                || outgoing_modal.is_some()
+                || replacing
+                    .as_ref()
+                    .is_some_and(|r| matches!(r.stage, ReplaceStage::Review));
+            let runs = hyperlink::runs(
+                completed.buffer,
+                content_area.filter(|_| !covered),
+                &rendered,
+                scroll_offset,
+                &current_path,
+                &mut linked_cells,
+            );
+            hyperlink::write(terminal.backend_mut(), &runs)?;
+        }
 
-        let event = event::read()?;
+        // Advance the smooth scroll animation one frame at a time until either
+        // the target is reached or a new event arrives (which snaps to it).
+        if let Some(target) = scroll_target {
+            if queued.is_empty() && !input.poll(SMOOTH_SCROLL_FRAME)? {
+                scroll_offset = smooth_scroll_step(scroll_offset, target);
+                if scroll_offset == target {
+                    scroll_target = None;
+                }
+                continue;
+            }
+            scroll_offset = target;
+            scroll_target = None;
+        }
+
+        // Show the file finder's listing once it is made, unless an event
+        // arrives first.
+        if let Some(ff) = file_finder.as_mut() {
+            if ff.poll() {
+                continue;
+            }
+            if ff.listing() && queued.is_empty() && !input.poll(CONTROL_POLL)? {
+                continue;
+            }
+        }
+
+        // Follow editors (`--listen`, `--control`) and saves (`--watch`)
+        // until a terminal event arrives, redrawing whenever the view changes.
+        if queued.is_empty() && (watch || !listeners.is_empty()) {
+            let mut redraw = false;
+            while !redraw && !input.poll(CONTROL_POLL)? {
+                let viewport_height = terminal.size()?.height.saturating_sub(chrome_rows) as usize;
+                let mut commands: Vec<control::Command> =
+                    listeners.iter().flat_map(control::Listener::pending).collect();
+                if let Some((path, seen)) = &mut watched {
+                    let now = modified(path);
+                    if now != *seen {
+                        *seen = now;
+                        commands.push(control::Command::Reload);
+                    }
+                }
+                for command in commands {
+                    redraw = true;
+                    if matches!(command, control::Command::Open(_) | control::Command::Reload) {
+                        // The editor takes over from whatever was open.
+                        outline = None;
+                        heading_finder = None;
+                        file_finder = None;
+                        palette = None;
+                        help = None;
+                        history_modal = None;
+                        backlinks_modal = None;
+                        outgoing_modal = None;
+                        replacing = None;
+                        section_save = None;
+                        search = None;
+                    }
+                    match command {
+                        control::Command::Scroll { path, line } => {
+                            if control::is_current(path.as_deref(), &current_path) {
+                                scroll_offset = recenter_scroll(
+                                    rendered.rendered_line_for_source(line),
+                                    viewport_height,
+                                    total_lines.saturating_sub(viewport_height),
+                                    Recenter::Center,
+                                );
+                            }
+                        }
+                        control::Command::Open(path) => {
+                            let target = fs::canonicalize(&path).unwrap_or(path);
+                            if target == current_path {
+                                continue;
+                            }
+                            match read_document(stdin, &target) {
+                                Ok(new_source) => {
+                                    jumps.record(JumpPosition {
+                                        file_path: current_path.clone(),
+                                        scroll_offset,
+                                    });
+                                    nav_stack.push(NavigationEntry {
+                                        file_path: current_path.clone(),
+                                        scroll_offset,
+                                        focused_link,
+                                    });
+                                    forward_stack.clear();
+                                    current_path = target;
+                                    rendered = render_file(
+                                        &current_path,
+                                        &new_source,
+                                        vars,
+                                        &render,
+                                        raw_source,
+                                    );
+                                    total_lines = rendered.text.lines.len();
+                                    scroll_offset = 0;
+                                    focused_link = None;
+                                }
+                                Err(e) => {
+                                    notice = Some(locale.trf(
+                                        "Cannot open {path}: {error}",
+                                        &[("path", &target.display()), ("error", &e)],
+                                    ));
+                                }
+                            }
+                        }
+                        control::Command::Reload => {
+                            if let Ok(new_source) = read_document(stdin, &current_path) {
+                                let (new_rendered, offset, highlight) = rerender(
+                                    &current_path,
+                                    &new_source,
+                                    &rendered,
+                                    scroll_offset,
+                                    vars,
+                                    &render,
+                                    raw_source,
+                                );
+                                rendered = new_rendered;
+                                changed = Some(highlight);
+                                total_lines = rendered.text.lines.len();
+                                scroll_offset =
+                                    offset.min(total_lines.saturating_sub(viewport_height));
+                                focused_link = None;
+                            }
+                        }
+                        control::Command::Quit => {
+                            return Ok(final_state(
+                                NavigationEntry {
+                                    file_path: current_path,
+                                    scroll_offset,
+                                    focused_link,
+                                },
+                                nav_stack,
+                                forward_stack,
+                                &history,
+                                &jumps,
+                                &file_queue,
+                            ));
+                        }
+                    }
+                }
+                if changed.as_ref().is_some_and(|c| c.remaining().is_zero()) {
+                    changed = None;
+                    redraw = true;
+                }
+            }
+            if redraw {
+                continue;
+            }
+        }
+
+        // Drop the changed-line highlight when it expires, unless an event
+        // arrives first.
+        if let Some(highlight) = changed.as_ref().filter(|_| queued.is_empty()) {
+            if !input.poll(highlight.remaining())? {
+                changed = None;
+                continue;
+            }
+        }
+
+        let event = match queued.pop_front() {
+            Some(key) => Event::Key(key),
+            None => input.read()?,
+        };
 
         // Recalculate bounds and clamp scroll offset on every event,
         // including Event::Resize, so the view stays valid after terminal resize.
-        let viewport_height = terminal.size()?.height.saturating_sub(1) as usize;
+        let viewport_height = terminal.size()?.height.saturating_sub(chrome_rows) as usize;
         let max_scroll = total_lines.saturating_sub(viewport_height);
         scroll_offset = scroll_offset.min(max_scroll);
 
+        if let Event::Mouse(mouse) = event {
+            let overlay_open = replacing.is_some()
+                || section_save.is_some()
+                || help.is_some()
+                || outline.is_some()
+                || heading_finder.is_some()
+                || file_finder.is_some()
+                || palette.is_some()
+                || history_modal.is_some()
+                || backlinks_modal.is_some()
+                || outgoing_modal.is_some()
+                || search.as_ref().is_some_and(|s| s.typing);
+            if overlay_open {
+                continue;
+            }
+            match mouse.kind {
+                MouseEventKind::ScrollDown => {
+                    scroll_offset = (scroll_offset + scroll.step).min(max_scroll);
+                }
+                MouseEventKind::ScrollUp => {
+                    scroll_offset = scroll_offset.saturating_sub(scroll.step);
+                }
+                MouseEventKind::Down(MouseButton::Left) if mouse.row == 0 => {
+                    let width = terminal.size()?.width as usize;
+                    let Some(dir) = trail
+                        .as_ref()
+                        .and_then(|t| t.dir_at(mouse.column as usize, width))
+                    else {
+                        continue;
+                    };
+                    notice = None;
+                    let Some(target) = breadcrumbs::index_file(dir) else {
+                        notice = Some(locale.trf(
+                            "No README.md or index.md in {dir}",
+                            &[("dir", &dir.display())],
+                        ));
+                        continue;
+                    };
+                    if target == current_path {
+                        continue;
+                    }
+                    if let Ok(new_source) = read_document(stdin, &target) {
+                        jumps.record(JumpPosition {
+                            file_path: current_path.clone(),
+                            scroll_offset,
+                        });
+                        nav_stack.push(NavigationEntry {
+                            file_path: current_path.clone(),
+                            scroll_offset,
+                            focused_link,
+                        });
+                        forward_stack.clear();
+                        current_path = target;
+                        rendered =
+                            render_file(&current_path, &new_source, vars, &render, raw_source);
+                        total_lines = rendered.text.lines.len();
+                        scroll_offset = 0;
+                        focused_link = None;
+                        search = None;
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+
         if let Event::Key(key) = event {
             if key.kind != KeyEventKind::Press {
                 continue;
             }
+            notice = None;
 
-            if let Some(ref mut hl) = help {
+            if let Some(ref mut rp) = replacing {
+                // Find-and-replace flow is active — handle its input and review keys
+                let mut close = false;
+                match rp.stage {
+                    ReplaceStage::Find | ReplaceStage::Replacement => {
+                        let input = if rp.stage == ReplaceStage::Find {
+                            &mut rp.find
+                        } else {
+                            &mut rp.replacement
+                        };
+                        match key.code {
+                            KeyCode::Esc => close = true,
+                            KeyCode::Backspace => {
+                                input.pop();
+                            }
+                            KeyCode::Char(c) => input.push(c),
+                            KeyCode::Enter if rp.stage == ReplaceStage::Find => {
+                                if rp.find.is_empty() {
+                                    close = true;
+                                } else {
+                                    rp.stage = ReplaceStage::Replacement;
+                                }
+                            }
+                            KeyCode::Enter => {
+                                rp.candidates = replace::find_in_project(&project_root, &rp.find);
+                                if rp.candidates.is_empty() {
+                                    notice =
+                                        Some(locale.trf(
+                                            "No matches for \"{query}\"",
+                                            &[("query", &rp.find)],
+                                        ));
+                                    close = true;
+                                } else {
+                                    rp.selected = 0;
+                                    rp.stage = ReplaceStage::Review;
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    ReplaceStage::Review => {
+                        let last = rp.candidates.len() - 1;
+                        match key.code {
+                            KeyCode::Esc | KeyCode::Char('q') => close = true,
+                            KeyCode::Char('j') | KeyCode::Down => {
+                                rp.selected = (rp.selected + 1).min(last);
+                            }
+                            KeyCode::Char('k') | KeyCode::Up => {
+                                rp.selected = rp.selected.saturating_sub(1);
+                            }
+                            KeyCode::Char('y') => {
+                                rp.candidates[rp.selected].accepted = true;
+                                rp.selected = (rp.selected + 1).min(last);
+                            }
+                            KeyCode::Char('n') => {
+                                rp.candidates[rp.selected].accepted = false;
+                                rp.selected = (rp.selected + 1).min(last);
+                            }
+                            KeyCode::Char(' ') => {
+                                let c = &mut rp.candidates[rp.selected];
+                                c.accepted = !c.accepted;
+                            }
+                            KeyCode::Char('a') => {
+                                rp.candidates.iter_mut().for_each(|c| c.accepted = true);
+                            }
+                            KeyCode::Char('r') => {
+                                rp.candidates.iter_mut().for_each(|c| c.accepted = false);
+                            }
+                            KeyCode::Enter => {
+                                let summary = replace::apply_accepted(
+                                    &rp.candidates,
+                                    &rp.find,
+                                    &rp.replacement,
+                                );
+                                notice = Some(replace_summary_message(&summary, locale));
+                                watched = None;
+                                // Pick up the rewritten contents of the current document.
+                                if let Ok(new_source) = read_document(stdin, &current_path) {
+                                    // Keep the passage the reader was on in view even
+                                    // when replacements above it add or remove lines.
+                                    let (new_rendered, offset, highlight) = rerender(
+                                        &current_path,
+                                        &new_source,
+                                        &rendered,
+                                        scroll_offset,
+                                        vars,
+                                        &render,
+                                        raw_source,
+                                    );
+                                    rendered = new_rendered;
+                                    changed = Some(highlight);
+                                    total_lines = rendered.text.lines.len();
+                                    let max_scroll = total_lines.saturating_sub(viewport_height);
+                                    scroll_offset = offset.min(max_scroll);
+                                    focused_link = None;
+                                    search = None;
+                                }
+                                close = true;
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                if close {
+                    replacing = None;
+                }
+            } else if let Some(ref mut save) = section_save {
+                match key.code {
+                    KeyCode::Enter if !save.path.trim().is_empty() => {
+                        let name = save.path.trim();
+                        let target = current_path.parent().unwrap_or(Path::new(".")).join(name);
+                        notice = Some(if target.exists() {
+                            locale.trf("{path} already exists", &[("path", &name)])
+                        } else {
+                            match fs::write(&target, &save.text) {
+                                Ok(()) => {
+                                    locale.trf("Saved the section to {path}", &[("path", &name)])
+                                }
+                                Err(e) => locale.trf(
+                                    "Could not write {path}: {error}",
+                                    &[("path", &name), ("error", &e)],
+                                ),
+                            }
+                        });
+                        section_save = None;
+                    }
+                    KeyCode::Esc => section_save = None,
+                    KeyCode::Backspace => {
+                        save.path.pop();
+                    }
+                    KeyCode::Char(c) => save.path.push(c),
+                    _ => {}
+                }
+            } else if let Some(ref mut hl) = help {
                 // Help modal is open — handle help-specific keys
                 match key.code {
                     KeyCode::Esc | KeyCode::Char('?') => {
@@ -673,6 +2969,12 @@ fn run(
                     }
                     KeyCode::Enter => {
                         // Close and stay at selected heading position
+                        if scroll_offset != ol.saved_scroll {
+                            jumps.record(JumpPosition {
+                                file_path: current_path.clone(),
+                                scroll_offset: ol.saved_scroll,
+                            });
+                        }
                         outline = None;
                     }
                     KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('o') => {
@@ -682,6 +2984,393 @@ fn run(
                     }
                     _ => {}
                 }
+            } else if let Some(ref mut pl) = palette {
+                // Command palette is open — typing filters the commands and
+                // Enter runs the selected one
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                let last = pl.matches.len().saturating_sub(1);
+                match key.code {
+                    KeyCode::Esc => palette = None,
+                    KeyCode::Enter => {
+                        let (_, arg) = palette::split(&pl.query);
+                        let arg = arg.to_owned();
+                        let command = pl.matches.get(pl.selected).map(|&i| &palette::COMMANDS[i]);
+                        palette = None;
+                        let mut relayout = false;
+                        let toggled = |on: bool, shown: &'static str, hidden: &'static str| {
+                            locale.tr(if on { shown } else { hidden }).to_owned()
+                        };
+                        match command.map(|c| c.action) {
+                            None => {}
+                            Some(palette::Action::Keys(keys)) => {
+                                queued.extend(palette::key_events(keys));
+                            }
+                            Some(palette::Action::Open) => {
+                                let mut ff = FileFinder::open(&current_path);
+                                ff.query = arg;
+                                ff.refilter();
+                                file_finder = Some(ff);
+                                focused_link = None;
+                            }
+                            Some(palette::Action::Width) => match arg.parse::<usize>() {
+                                Ok(cols) => {
+                                    render.layout.text_width = (cols > 0).then_some(cols);
+                                    relayout = true;
+                                    notice = Some(match cols {
+                                        0 => locale
+                                            .tr("Paragraphs keep their source lines")
+                                            .to_owned(),
+                                        _ => locale.trf(
+                                            "Paragraphs rewrapped to {cols} columns",
+                                            &[("cols", &cols)],
+                                        ),
+                                    });
+                                }
+                                Err(_) => {
+                                    notice = Some(locale.tr("Usage: width COLUMNS").to_owned());
+                                }
+                            },
+                            Some(palette::Action::Justify) => {
+                                render.layout.justify = !render.layout.justify;
+                                relayout = true;
+                                notice = Some(toggled(
+                                    render.layout.justify,
+                                    "Justifying rewrapped paragraphs",
+                                    "Not justifying paragraphs",
+                                ));
+                            }
+                            Some(palette::Action::Hyphenate) => {
+                                render.layout.hyphenate = !render.layout.hyphenate;
+                                relayout = true;
+                                notice = Some(toggled(
+                                    render.layout.hyphenate,
+                                    "Breaking words at soft hyphens",
+                                    "Not breaking words at soft hyphens",
+                                ));
+                            }
+                            Some(palette::Action::Numbering) => {
+                                render.numbered_headings = !render.numbered_headings;
+                                relayout = true;
+                                notice = Some(toggled(
+                                    render.numbered_headings,
+                                    "Numbering headings",
+                                    "Not numbering headings",
+                                ));
+                            }
+                            Some(palette::Action::Smart) => {
+                                render.smart_punctuation = !render.smart_punctuation;
+                                relayout = true;
+                                notice = Some(toggled(
+                                    render.smart_punctuation,
+                                    "Using smart punctuation",
+                                    "Not using smart punctuation",
+                                ));
+                            }
+                            Some(palette::Action::Theme) => {
+                                render.layout.light_theme = !render.layout.light_theme;
+                                relayout = true;
+                                notice = Some(toggled(
+                                    render.layout.light_theme,
+                                    "Highlighting code for a light background",
+                                    "Highlighting code for a dark background",
+                                ));
+                            }
+                            Some(palette::Action::Serve) => {
+                                if stdin.is_some_and(|doc| doc.path == current_path) {
+                                    notice = Some(locale.tr("Only files can be served").to_owned());
+                                } else if server.is_none() {
+                                    match BackgroundServer::start(&current_path) {
+                                        Ok(started) => server = Some(started),
+                                        Err(e) => {
+                                            notice = Some(locale.trf(
+                                                "Could not start the server: {error}",
+                                                &[("error", &e)],
+                                            ))
+                                        }
+                                    }
+                                }
+                                if let Some(server) = &server {
+                                    notice = Some(
+                                        locale.trf("Serving at {url}", &[("url", &server.url)]),
+                                    );
+                                }
+                            }
+                        }
+                        if relayout {
+                            if let Ok(new_source) = read_document(stdin, &current_path) {
+                                let new = render_file(
+                                    &current_path,
+                                    &new_source,
+                                    vars,
+                                    &render,
+                                    raw_source,
+                                );
+                                scroll_offset = reposition::restore(&rendered, scroll_offset, &new);
+                                rendered = new;
+                                total_lines = rendered.text.lines.len();
+                                scroll_offset =
+                                    scroll_offset.min(total_lines.saturating_sub(viewport_height));
+                                focused_link =
+                                    focused_link.filter(|&idx| idx < rendered.link_positions.len());
+                                search = None;
+                            }
+                            source_pane = None;
+                        }
+                    }
+                    KeyCode::Down | KeyCode::Tab => pl.selected = (pl.selected + 1).min(last),
+                    KeyCode::Char('n') if ctrl => pl.selected = (pl.selected + 1).min(last),
+                    KeyCode::Up | KeyCode::BackTab => pl.selected = pl.selected.saturating_sub(1),
+                    KeyCode::Char('p') if ctrl => pl.selected = pl.selected.saturating_sub(1),
+                    KeyCode::Backspace => {
+                        pl.query.pop();
+                        pl.refilter();
+                    }
+                    KeyCode::Char(c) if !ctrl => {
+                        pl.query.push(c);
+                        pl.refilter();
+                    }
+                    _ => {}
+                }
+            } else if let Some(ref mut hf) = heading_finder {
+                // Heading finder is open — typing filters the headings and
+                // the document shows the selected one
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                let last = hf.matches.len().saturating_sub(1);
+                let mut close = false;
+                match key.code {
+                    KeyCode::Esc => {
+                        scroll_offset = hf.saved_scroll;
+                        close = true;
+                    }
+                    KeyCode::Enter => {
+                        if hf.matches.is_empty() {
+                            scroll_offset = hf.saved_scroll;
+                        } else if scroll_offset != hf.saved_scroll {
+                            jumps.record(JumpPosition {
+                                file_path: current_path.clone(),
+                                scroll_offset: hf.saved_scroll,
+                            });
+                        }
+                        close = true;
+                    }
+                    KeyCode::Down | KeyCode::Tab => hf.selected = (hf.selected + 1).min(last),
+                    KeyCode::Char('n') if ctrl => hf.selected = (hf.selected + 1).min(last),
+                    KeyCode::Up | KeyCode::BackTab => hf.selected = hf.selected.saturating_sub(1),
+                    KeyCode::Char('p') if ctrl => hf.selected = hf.selected.saturating_sub(1),
+                    KeyCode::Backspace => {
+                        hf.query.pop();
+                        hf.refilter(&rendered.heading_lines);
+                    }
+                    KeyCode::Char(c) if !ctrl => {
+                        hf.query.push(c);
+                        hf.refilter(&rendered.heading_lines);
+                    }
+                    _ => {}
+                }
+                if close {
+                    heading_finder = None;
+                } else if let Some(line) = hf.target(&rendered.heading_lines) {
+                    scroll_offset = line.min(max_scroll);
+                }
+            } else if let Some(ref mut bm) = backlinks_modal {
+                // Backlinks modal is open — handle backlinks-specific keys
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        bm.selected = (bm.selected + 1).min(bm.refs.len().saturating_sub(1));
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        bm.selected = bm.selected.saturating_sub(1);
+                    }
+                    KeyCode::Enter => {
+                        let root = backlinks_index.as_ref().map(|(root, _)| root);
+                        let target = root
+                            .zip(bm.refs.get(bm.selected))
+                            .map(|(root, r)| root.join(r.source_url_path.trim_start_matches('/')))
+                            .map(|path| fs::canonicalize(&path).unwrap_or(path));
+                        if let Some(target) = target {
+                            if let Ok(new_source) = read_document(stdin, &target) {
+                                jumps.record(JumpPosition {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                });
+                                nav_stack.push(NavigationEntry {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                    focused_link,
+                                });
+                                forward_stack.clear();
+                                let linked = std::mem::replace(&mut current_path, target);
+                                rendered = render_file(
+                                    &current_path,
+                                    &new_source,
+                                    vars,
+                                    &render,
+                                    raw_source,
+                                );
+                                total_lines = rendered.text.lines.len();
+                                search = None;
+                                // Land on the link back to the file we came from.
+                                focused_link = rendered.link_positions.iter().position(|l| {
+                                    resolve_markdown_link(&current_path, &l.url).as_ref()
+                                        == Some(&linked)
+                                });
+                                let max_scroll = total_lines.saturating_sub(viewport_height);
+                                scroll_offset = focused_link
+                                    .map(|idx| rendered.link_positions[idx].rendered_line)
+                                    .map_or(0, |line| {
+                                        line.saturating_sub(viewport_height / 3).min(max_scroll)
+                                    });
+                            }
+                        }
+                        backlinks_modal = None;
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => {
+                        backlinks_modal = None;
+                    }
+                    _ => {}
+                }
+            } else if let Some(ref mut om) = outgoing_modal {
+                // Outgoing links modal is open — handle its keys
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        om.selected = (om.selected + 1).min(om.links.len().saturating_sub(1));
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        om.selected = om.selected.saturating_sub(1);
+                    }
+                    KeyCode::Enter => {
+                        // Focus the rendered link with the same URL, counting
+                        // earlier links to that URL so repeats line up.
+                        if let Some(link) = om.links.get(om.selected) {
+                            let nth = om.links[..om.selected]
+                                .iter()
+                                .filter(|l| l.url == link.url)
+                                .count();
+                            let found = rendered
+                                .link_positions
+                                .iter()
+                                .enumerate()
+                                .filter(|(_, l)| l.url == link.url)
+                                .nth(nth)
+                                .map(|(idx, _)| idx);
+                            if let Some(idx) = found {
+                                let line = rendered.link_positions[idx].rendered_line;
+                                if line < scroll_offset || line >= scroll_offset + viewport_height {
+                                    jumps.record(JumpPosition {
+                                        file_path: current_path.clone(),
+                                        scroll_offset,
+                                    });
+                                    scroll_offset =
+                                        line.saturating_sub(viewport_height / 3).min(max_scroll);
+                                }
+                                focused_link = Some(idx);
+                            }
+                        }
+                        outgoing_modal = None;
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('O') => {
+                        outgoing_modal = None;
+                    }
+                    _ => {}
+                }
+            } else if let Some(ref mut ff) = file_finder {
+                // File finder is open — typing filters the project's files
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                let last = ff.matches.len().saturating_sub(1);
+                match key.code {
+                    KeyCode::Esc => file_finder = None,
+                    KeyCode::Enter => {
+                        let target = ff
+                            .target()
+                            .map(|path| fs::canonicalize(&path).unwrap_or(path));
+                        if let Some(target) = target.filter(|t| *t != current_path) {
+                            if let Ok(new_source) = read_document(stdin, &target) {
+                                jumps.record(JumpPosition {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                });
+                                nav_stack.push(NavigationEntry {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                    focused_link,
+                                });
+                                forward_stack.clear();
+                                current_path = target;
+                                rendered = render_file(
+                                    &current_path,
+                                    &new_source,
+                                    vars,
+                                    &render,
+                                    raw_source,
+                                );
+                                total_lines = rendered.text.lines.len();
+                                scroll_offset = 0;
+                                focused_link = None;
+                                search = None;
+                            }
+                        }
+                        file_finder = None;
+                    }
+                    KeyCode::Down | KeyCode::Tab => ff.selected = (ff.selected + 1).min(last),
+                    KeyCode::Char('n') if ctrl => ff.selected = (ff.selected + 1).min(last),
+                    KeyCode::Up | KeyCode::BackTab => ff.selected = ff.selected.saturating_sub(1),
+                    KeyCode::Char('p') if ctrl => ff.selected = ff.selected.saturating_sub(1),
+                    KeyCode::Backspace => {
+                        ff.query.pop();
+                        ff.refilter();
+                    }
+                    KeyCode::Char(c) if !ctrl => {
+                        ff.query.push(c);
+                        ff.refilter();
+                    }
+                    _ => {}
+                }
+            } else if let Some(ref mut hm) = history_modal {
+                // History modal is open — handle history-specific keys
+                match key.code {
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        hm.selected = (hm.selected + 1).min(history.len().saturating_sub(1));
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        hm.selected = hm.selected.saturating_sub(1);
+                    }
+                    KeyCode::Enter => {
+                        let target = history
+                            .get_newest_first(hm.selected)
+                            .map(|v| v.file_path.clone());
+                        if let Some(target) = target.filter(|t| *t != current_path) {
+                            if let Ok(new_source) = read_document(stdin, &target) {
+                                jumps.record(JumpPosition {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                });
+                                nav_stack.push(NavigationEntry {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                    focused_link,
+                                });
+                                forward_stack.clear();
+                                current_path = target;
+                                rendered = render_file(
+                                    &current_path,
+                                    &new_source,
+                                    vars,
+                                    &render,
+                                    raw_source,
+                                );
+                                total_lines = rendered.text.lines.len();
+                                scroll_offset = 0;
+                                focused_link = None;
+                                search = None;
+                            }
+                        }
+                        history_modal = None;
+                    }
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => {
+                        history_modal = None;
+                    }
+                    _ => {}
+                }
             } else if search.as_ref().is_some_and(|s| s.typing) {
                 // Search typing mode — handle search input
                 let mut cancel = false;
@@ -692,6 +3381,12 @@ fn run(
                             cancel = true;
                         } else if let Some(ref mut s) = search {
                             s.typing = false;
+                            if scroll_offset != s.saved_scroll {
+                                jumps.record(JumpPosition {
+                                    file_path: current_path.clone(),
+                                    scroll_offset: s.saved_scroll,
+                                });
+                            }
                         }
                     }
                     KeyCode::Esc => {
@@ -738,8 +3433,115 @@ fn run(
                 }
             } else {
                 // Normal mode — handle regular keys
+                let scroll_before = scroll_offset;
+                let path_before = current_path.clone();
+                let z_prefix = std::mem::take(&mut pending_z);
+                let bracket_prefix = pending_bracket.take();
+                // Set by keys that count as jumps for the jump list.
+                let mut jumped = false;
+                // Position to restore from the jump list (Ctrl-o / Ctrl-i).
+                let mut jump_to: Option<JumpPosition> = None;
                 match key.code {
-                    KeyCode::Char('q') => return Ok(()),
+                    KeyCode::Char('q') => {
+                        return Ok(final_state(
+                            NavigationEntry {
+                                file_path: current_path,
+                                scroll_offset,
+                                focused_link,
+                            },
+                            nav_stack,
+                            forward_stack,
+                            &history,
+                            &jumps,
+                            &file_queue,
+                        ));
+                    }
+
+                    // Jump list back / forward
+                    KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        jump_to = jumps.back(JumpPosition {
+                            file_path: current_path.clone(),
+                            scroll_offset,
+                        });
+                    }
+                    KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        jump_to = jumps.forward();
+                    }
+
+                    // Next / previous file given on the command line (]f / [f)
+                    KeyCode::Char('f') if bracket_prefix.is_some() => {
+                        let forward = bracket_prefix == Some(']');
+                        let target = if forward {
+                            file_queue.next()
+                        } else {
+                            file_queue.previous()
+                        };
+                        match target.map(Path::to_path_buf) {
+                            None if file_queue.files().is_empty() => {
+                                notice = Some(locale.tr("Only one file was given").to_owned());
+                            }
+                            None if forward => {
+                                notice = Some(locale.tr("Already at the last file").to_owned());
+                            }
+                            None => {
+                                notice = Some(locale.tr("Already at the first file").to_owned());
+                            }
+                            Some(target) => match read_document(stdin, &target) {
+                                Ok(new_source) => {
+                                    nav_stack.push(NavigationEntry {
+                                        file_path: current_path.clone(),
+                                        scroll_offset,
+                                        focused_link,
+                                    });
+                                    forward_stack.clear();
+                                    current_path = target;
+                                    rendered = render_file(
+                                        &current_path,
+                                        &new_source,
+                                        vars,
+                                        &render,
+                                        raw_source,
+                                    );
+                                    total_lines = rendered.text.lines.len();
+                                    scroll_offset = 0;
+                                    focused_link = None;
+                                    outline = None;
+                                    search = None;
+                                    jumped = true;
+                                    file_queue.show(&current_path);
+                                    notice = Some(locale.trf(
+                                        "File {n} of {count}",
+                                        &[
+                                            ("n", &file_queue.position()),
+                                            ("count", &file_queue.files().len()),
+                                        ],
+                                    ));
+                                }
+                                Err(e) => {
+                                    notice = Some(locale.trf(
+                                        "Cannot open {path}: {error}",
+                                        &[("path", &target.display()), ("error", &e)],
+                                    ));
+                                }
+                            },
+                        }
+                    }
+                    KeyCode::Char(c @ (']' | '[')) => {
+                        pending_bracket = Some(c);
+                    }
+
+                    // Recentre the view around the current line (zz / zt / zb)
+                    KeyCode::Char(_) if z_prefix && recenter_key(&key).is_some() => {
+                        let line =
+                            anchor_line(&rendered, focused_link, search.as_ref(), scroll_offset);
+                        if let Some(mode) = recenter_key(&key) {
+                            scroll_offset =
+                                recenter_scroll(line, viewport_height, max_scroll, mode);
+                        }
+                    }
+                    KeyCode::Char('z') => {
+                        pending_z = true;
+                    }
 
                     // Open outline modal
                     KeyCode::Char('o')
@@ -757,15 +3559,55 @@ fn run(
                         focused_link = None;
                     }
 
-                    // Single line down
+                    // Line(s) down
                     KeyCode::Char('j') | KeyCode::Down => {
-                        scroll_offset = (scroll_offset + 1).min(max_scroll);
+                        scroll_offset = (scroll_offset + scroll.step).min(max_scroll);
+                        focused_link = None;
+                    }
+
+                    // Open the go-to-heading finder (Ctrl-k)
+                    KeyCode::Char('k')
+                        if key.modifiers.contains(KeyModifiers::CONTROL)
+                            && !rendered.heading_lines.is_empty() =>
+                    {
+                        let mut hf = HeadingFinder {
+                            query: String::new(),
+                            matches: Vec::new(),
+                            selected: 0,
+                            saved_scroll: scroll_offset,
+                        };
+                        hf.refilter(&rendered.heading_lines);
+                        // Start on the heading at the top of the view.
+                        hf.selected = rendered
+                            .heading_lines
+                            .iter()
+                            .rposition(|h| h.rendered_line <= scroll_offset)
+                            .unwrap_or(0);
+                        heading_finder = Some(hf);
                         focused_link = None;
                     }
 
-                    // Single line up
+                    // Line(s) up
                     KeyCode::Char('k') | KeyCode::Up => {
-                        scroll_offset = scroll_offset.saturating_sub(1);
+                        scroll_offset = scroll_offset.saturating_sub(scroll.step);
+                        focused_link = None;
+                    }
+
+                    // Full page down
+                    KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        scroll_offset =
+                            (scroll_offset + full_page(viewport_height)).min(max_scroll);
+                        focused_link = None;
+                    }
+                    KeyCode::Char(' ') => {
+                        scroll_offset =
+                            (scroll_offset + full_page(viewport_height)).min(max_scroll);
+                        focused_link = None;
+                    }
+
+                    // Full page up
+                    KeyCode::Char('b') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        scroll_offset = scroll_offset.saturating_sub(full_page(viewport_height));
                         focused_link = None;
                     }
 
@@ -797,12 +3639,14 @@ fn run(
                     KeyCode::Char('g') | KeyCode::Home => {
                         scroll_offset = 0;
                         focused_link = None;
+                        jumped = true;
                     }
 
                     // Jump to bottom
                     KeyCode::Char('G') | KeyCode::End => {
                         scroll_offset = max_scroll;
                         focused_link = None;
+                        jumped = true;
                     }
 
                     // Next search match (Ctrl-n)
@@ -810,6 +3654,7 @@ fn run(
                         if key.modifiers.contains(KeyModifiers::CONTROL) && search.is_some() =>
                     {
                         advance_search_match(&mut search, true);
+                        jumped = true;
                         if let Some(ref s) = search {
                             if let Some(idx) = s.current_match {
                                 let line = s.matches[idx].rendered_line;
@@ -832,6 +3677,7 @@ fn run(
                             scroll_offset = pos.rendered_line.min(max_scroll);
                         }
                         focused_link = None;
+                        jumped = true;
                     }
 
                     // Previous search match (Ctrl-p)
@@ -839,6 +3685,7 @@ fn run(
                         if key.modifiers.contains(KeyModifiers::CONTROL) && search.is_some() =>
                     {
                         advance_search_match(&mut search, false);
+                        jumped = true;
                         if let Some(ref s) = search {
                             if let Some(idx) = s.current_match {
                                 let line = s.matches[idx].rendered_line;
@@ -851,6 +3698,12 @@ fn run(
                         focused_link = None;
                     }
 
+                    // Open the file finder (Ctrl-p)
+                    KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                        file_finder = Some(FileFinder::open(&current_path));
+                        focused_link = None;
+                    }
+
                     // Previous heading
                     KeyCode::Char('p') => {
                         if let Some(pos) = rendered
@@ -862,6 +3715,7 @@ fn run(
                             scroll_offset = pos.rendered_line.min(max_scroll);
                         }
                         focused_link = None;
+                        jumped = true;
                     }
 
                     // Next link (Tab)
@@ -929,24 +3783,53 @@ fn run(
                                 let url = link.url.clone();
                                 if is_external_url(&url) {
                                     open_url_in_browser(&url);
+                                } else if url.starts_with('#') {
+                                    match fragment_line(&rendered, &url) {
+                                        Some(line) => {
+                                            scroll_offset = line.min(max_scroll);
+                                            focused_link = None;
+                                            jumped = true;
+                                        }
+                                        None => {
+                                            notice =
+                                                Some(locale.trf(
+                                                    "No such heading: {url}",
+                                                    &[("url", &url)],
+                                                ));
+                                        }
+                                    }
                                 } else if let Some(target) =
                                     resolve_markdown_link(&current_path, &url)
                                 {
-                                    if let Ok(new_source) = fs::read_to_string(&target) {
+                                    if let Ok(new_source) = read_document(stdin, &target) {
                                         nav_stack.push(NavigationEntry {
                                             file_path: current_path.clone(),
                                             scroll_offset,
                                             focused_link,
                                         });
+                                        forward_stack.clear();
                                         current_path = target;
-                                        let new_doc = parse::parse(&new_source);
-                                        rendered = render::render_document(&new_doc);
+                                        rendered = render_file(
+                                            &current_path,
+                                            &new_source,
+                                            vars,
+                                            &render,
+                                            raw_source,
+                                        );
                                         total_lines = rendered.text.lines.len();
-                                        scroll_offset = 0;
+                                        scroll_offset = fragment_line(&rendered, &url).unwrap_or(0);
                                         focused_link = None;
                                         outline = None;
                                         search = None;
+                                        jumped = true;
                                     }
+                                } else if let Some(target) = resolve_local_file(&current_path, &url)
+                                {
+                                    notice =
+                                        Some(open_local_file(terminal, handlers, &target, locale)?);
+                                } else {
+                                    notice =
+                                        Some(locale.trf("No such file: {url}", &[("url", &url)]));
                                 }
                             }
                         }
@@ -955,19 +3838,121 @@ fn run(
                     // Navigate back (Backspace)
                     KeyCode::Backspace => {
                         if let Some(entry) = nav_stack.pop() {
-                            if let Ok(new_source) = fs::read_to_string(&entry.file_path) {
+                            if let Ok(new_source) = read_document(stdin, &entry.file_path) {
+                                forward_stack.push(NavigationEntry {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                    focused_link,
+                                });
+                                current_path = entry.file_path;
+                                rendered = render_file(
+                                    &current_path,
+                                    &new_source,
+                                    vars,
+                                    &render,
+                                    raw_source,
+                                );
+                                total_lines = rendered.text.lines.len();
+                                scroll_offset = entry.scroll_offset;
+                                focused_link = entry.focused_link;
+                                outline = None;
+                                search = None;
+                                jumped = true;
+                            }
+                        }
+                    }
+
+                    // Navigate forward (after going back)
+                    KeyCode::Char('L') => {
+                        if let Some(entry) = forward_stack.pop() {
+                            if let Ok(new_source) = read_document(stdin, &entry.file_path) {
+                                nav_stack.push(NavigationEntry {
+                                    file_path: current_path.clone(),
+                                    scroll_offset,
+                                    focused_link,
+                                });
                                 current_path = entry.file_path;
-                                let new_doc = parse::parse(&new_source);
-                                rendered = render::render_document(&new_doc);
+                                rendered = render_file(
+                                    &current_path,
+                                    &new_source,
+                                    vars,
+                                    &render,
+                                    raw_source,
+                                );
                                 total_lines = rendered.text.lines.len();
                                 scroll_offset = entry.scroll_offset;
                                 focused_link = entry.focused_link;
                                 outline = None;
                                 search = None;
+                                jumped = true;
                             }
                         }
                     }
 
+                    // Open visited files history modal
+                    KeyCode::Char('H') => {
+                        history_modal = Some(HistoryState { selected: 0 });
+                        focused_link = None;
+                    }
+
+                    // Open backlinks modal
+                    KeyCode::Char('b') => {
+                        let root = project_root_for(&current_path);
+                        if backlinks_index.as_ref().is_none_or(|(r, _)| *r != root) {
+                            let index = backlinks::build_backlinks_index(&root, false);
+                            backlinks_index = Some((root, index));
+                        }
+                        let refs = backlinks_index
+                            .as_ref()
+                            .and_then(|(root, index)| {
+                                let rel = current_path.strip_prefix(root).ok()?;
+                                let key = backlinks::url_key_from_rel_path(
+                                    &rel.to_string_lossy().replace('\\', "/"),
+                                );
+                                index.get(&key).cloned()
+                            })
+                            .unwrap_or_default();
+                        if refs.is_empty() {
+                            notice = Some(locale.tr("No backlinks to this file").to_owned());
+                        } else {
+                            let mut refs = refs;
+                            refs.sort_by(|a, b| a.source_url_path.cmp(&b.source_url_path));
+                            backlinks_modal = Some(BacklinksModal { refs, selected: 0 });
+                            focused_link = None;
+                        }
+                    }
+
+                    // Open outgoing links modal
+                    KeyCode::Char('O') => {
+                        let links = read_document(stdin, &current_path)
+                            .map(|source| {
+                                outgoing::outgoing_links(
+                                    &source,
+                                    &current_path,
+                                    &project_root_for(&current_path),
+                                    render.slug_style,
+                                )
+                            })
+                            .unwrap_or_default();
+                        if links.is_empty() {
+                            notice = Some(locale.tr("No links in this file").to_owned());
+                        } else {
+                            outgoing_modal = Some(OutgoingModal { links, selected: 0 });
+                        }
+                    }
+
+                    // Open the command palette
+                    KeyCode::Char(':') => {
+                        let mut pl = Palette {
+                            query: String::new(),
+                            matches: Vec::new(),
+                            selected: 0,
+                        };
+                        pl.refilter();
+                        palette = Some(pl);
+                        focused_link = None;
+                    }
+
                     // Open help modal
                     KeyCode::Char('?') => {
                         help = Some(HelpState {
@@ -978,6 +3963,168 @@ fn run(
                         focused_link = None;
                     }
 
+                    // Toggle the task checkbox on the current line
+                    KeyCode::Char('x') => {
+                        let line =
+                            anchor_line(&rendered, focused_link, search.as_ref(), scroll_offset);
+                        match rendered.task_lines.iter().find(|t| t.rendered_line == line) {
+                            None => {
+                                notice = Some(locale.tr("No task on the current line").to_owned())
+                            }
+                            Some(task) => {
+                                let toggled = source_line_in_file(&current_path, task.source_line)
+                                    .ok_or_else(|| "the task is in an included file".to_owned())
+                                    .and_then(|line| {
+                                        tasks::toggle_task(&current_path, line, task.checked)
+                                    });
+                                match toggled {
+                                    Ok(new_source) => {
+                                        watched = None;
+                                        rendered = render_file(
+                                            &current_path,
+                                            &new_source,
+                                            vars,
+                                            &render,
+                                            raw_source,
+                                        );
+                                        total_lines = rendered.text.lines.len();
+                                        focused_link = focused_link
+                                            .filter(|&idx| idx < rendered.link_positions.len());
+                                    }
+                                    Err(error) => {
+                                        notice = Some(locale.trf(
+                                            "Cannot toggle task: {error}",
+                                            &[("error", &error)],
+                                        ));
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    // Show or hide whitespace markers
+                    KeyCode::Char('w') => {
+                        let layout = &mut render.layout;
+                        layout.show_whitespace = !layout.show_whitespace;
+                        if let Ok(new_source) = read_document(stdin, &current_path) {
+                            rendered =
+                                render_file(&current_path, &new_source, vars, &render, raw_source);
+                            total_lines = rendered.text.lines.len();
+                            focused_link =
+                                focused_link.filter(|&idx| idx < rendered.link_positions.len());
+                        }
+                        let shown = if render.layout.show_whitespace {
+                            "Showing tabs, non-breaking spaces, and trailing spaces"
+                        } else {
+                            "Hiding whitespace markers"
+                        };
+                        notice = Some(locale.tr(shown).to_owned());
+                        source_pane = None;
+                    }
+
+                    // Show the markdown source beside the rendering
+                    KeyCode::Char('S') => {
+                        split = !split;
+                        if !split {
+                            source_pane = None;
+                        }
+                        let shown = if split {
+                            "Showing the markdown source beside the rendering"
+                        } else {
+                            "Hiding the markdown source"
+                        };
+                        notice = Some(locale.tr(shown).to_owned());
+                    }
+
+                    // Copy the markdown of the section under the current heading
+                    KeyCode::Char('y') => {
+                        let section = current_section(
+                            stdin,
+                            &current_path,
+                            &rendered,
+                            scroll_offset,
+                            raw_source,
+                        );
+                        notice = Some(match section {
+                            Ok((heading, text)) => {
+                                let env = serve::EnvSnapshot::from_env();
+                                match clipboard::copy(&text, &env) {
+                                    Ok(_) => locale.trf(
+                                        "Copied the section \"{heading}\"",
+                                        &[("heading", &heading.text)],
+                                    ),
+                                    Err(e) => {
+                                        locale.trf("Could not copy: {error}", &[("error", &e)])
+                                    }
+                                }
+                            }
+                            Err(why) => locale.tr(why).to_owned(),
+                        });
+                    }
+
+                    // Save the section under the current heading to a file
+                    KeyCode::Char('Y') => {
+                        let section = current_section(
+                            stdin,
+                            &current_path,
+                            &rendered,
+                            scroll_offset,
+                            raw_source,
+                        );
+                        match section {
+                            Ok((heading, text)) => {
+                                let name = match heading.anchor.as_str() {
+                                    "" => "section",
+                                    anchor => anchor,
+                                };
+                                section_save = Some(SectionSave {
+                                    text,
+                                    path: format!("{name}.md"),
+                                });
+                                focused_link = None;
+                            }
+                            Err(why) => notice = Some(locale.tr(why).to_owned()),
+                        }
+                    }
+
+                    // Switch between the rendering and the markdown source
+                    KeyCode::Char('s') => {
+                        if let Ok(new_source) = read_document(stdin, &current_path) {
+                            // The source line at the top of the view stays there.
+                            let mut line = rendered.source_line_for_rendered(scroll_offset);
+                            if !raw_source {
+                                line = source_line_in_file(&current_path, line).unwrap_or(line);
+                            }
+                            raw_source = !raw_source;
+                            rendered =
+                                render_file(&current_path, &new_source, vars, &render, raw_source);
+                            total_lines = rendered.text.lines.len();
+                            scroll_offset = rendered
+                                .rendered_line_for_source(line)
+                                .min(total_lines.saturating_sub(viewport_height));
+                            focused_link = None;
+                            search = None;
+                            let shown = if raw_source {
+                                "Showing the markdown source"
+                            } else {
+                                "Showing the rendered document"
+                            };
+                            notice = Some(locale.tr(shown).to_owned());
+                        }
+                    }
+
+                    // Start project-wide find and replace
+                    KeyCode::Char('R') => {
+                        replacing = Some(ReplaceState {
+                            stage: ReplaceStage::Find,
+                            find: String::new(),
+                            replacement: String::new(),
+                            candidates: Vec::new(),
+                            selected: 0,
+                        });
+                        focused_link = None;
+                    }
+
                     // Enter search mode
                     KeyCode::Char('/') => {
                         search = Some(SearchState {
@@ -1001,11 +4148,330 @@ fn run(
 
                     _ => {}
                 }
+
+                if jumped && (scroll_offset != scroll_before || current_path != path_before) {
+                    jumps.record(JumpPosition {
+                        file_path: path_before.clone(),
+                        scroll_offset: scroll_before,
+                    });
+                }
+
+                // Restore a jump list position, switching documents if needed.
+                if let Some(pos) = jump_to {
+                    let loaded = if pos.file_path == current_path {
+                        true
+                    } else if let Ok(new_source) = read_document(stdin, &pos.file_path) {
+                        current_path = pos.file_path;
+                        rendered =
+                            render_file(&current_path, &new_source, vars, &render, raw_source);
+                        total_lines = rendered.text.lines.len();
+                        outline = None;
+                        search = None;
+                        true
+                    } else {
+                        false
+                    };
+                    if loaded {
+                        let max_scroll = total_lines.saturating_sub(viewport_height);
+                        scroll_offset = pos.scroll_offset.min(max_scroll);
+                        focused_link = None;
+                    }
+                }
+
+                // Turn multi-line jumps within the same document into an animation.
+                if scroll.smooth
+                    && current_path == path_before
+                    && scroll_offset.abs_diff(scroll_before) > 1
+                {
+                    scroll_target = Some(scroll_offset);
+                    scroll_offset = scroll_before;
+                }
+            }
+        }
+    }
+}
+
+/// Draw the breadcrumb header: directory segments look like links, the
+/// viewed file is bold.
+fn render_breadcrumbs(frame: &mut Frame, trail: &breadcrumbs::Trail, area: Rect) {
+    let spans: Vec<Span> = trail
+        .pieces(area.width as usize)
+        .into_iter()
+        .map(|piece| match piece {
+            breadcrumbs::Piece::Segment(idx, label) if trail.segments[idx].dir.is_some() => {
+                Span::styled(
+                    label.to_owned(),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::UNDERLINED),
+                )
             }
+            breadcrumbs::Piece::Segment(_, label) => Span::styled(
+                label.to_owned(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            breadcrumbs::Piece::Text(text) => Span::raw(text),
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// The markdown source shown beside the rendering in the split view (`S`).
+struct SourcePane {
+    path: PathBuf,
+    /// Modification time of `path` when it was read, to notice edits.
+    modified: Option<SystemTime>,
+    view: RenderedDocument,
+    /// Line of the file that each line of its expanded text came from,
+    /// `None` for included lines.
+    file_lines: Vec<Option<usize>>,
+}
+
+impl SourcePane {
+    fn new(
+        path: &Path,
+        modified: Option<SystemTime>,
+        source: &str,
+        layout: &render::Layout,
+    ) -> Self {
+        let expanded = include::expand(source, path, None);
+        let file_lines = (1..=expanded.text.lines().count())
+            .map(|line| expanded.source_line(line))
+            .collect();
+        SourcePane {
+            path: path.to_path_buf(),
+            modified,
+            view: render::render_source(source, layout),
+            file_lines,
+        }
+    }
+
+    /// The file line that 1-based `line` of the expanded text came from;
+    /// an included line counts as the last file line before it.
+    fn file_line(&self, line: usize) -> usize {
+        self.file_lines
+            .get(..line.min(self.file_lines.len()))
+            .and_then(|lines| lines.iter().rev().find_map(|l| *l))
+            .unwrap_or(line)
+    }
+}
+
+/// Parse `source`, the contents of `path`, with its include directives
+/// expanded and, when `vars` is set, its `{{var.name}}` placeholders
+/// substituted.  Punctuation and heading numbers follow `render`.
+/// Render `source`, the document at `path`, for the viewer: parsed and laid
+/// out, or with `raw_source` as its highlighted markdown source.
+fn render_file(
+    path: &Path,
+    source: &str,
+    vars: bool,
+    render: &html::RenderOptions,
+    raw_source: bool,
+) -> RenderedDocument {
+    if raw_source {
+        return render::render_source(source, &render.layout);
+    }
+    let doc = parse_file(path, source, vars, render);
+    render::render_document_with(&doc, &render.layout)
+}
+
+fn parse_file(
+    path: &Path,
+    source: &str,
+    vars: bool,
+    render: &html::RenderOptions,
+) -> parse::ParsedDocument {
+    let expanded = include::expand(source, path, None);
+    let text = if vars {
+        vars::substitute_document(&expanded.text, path, None)
+    } else {
+        Cow::Borrowed(expanded.text.as_ref())
+    };
+    // A failing filter cannot be reported over the TUI; the unfiltered
+    // text is shown instead.
+    let text = render
+        .filters
+        .apply(filter::Stage::Markdown, &text, path, |_, _| {});
+    #[cfg(feature = "shortcodes")]
+    let text = render
+        .shortcodes
+        .expand(&text, path, shortcode::Output::Text)
+        .text;
+    let mut doc = if render.smart_punctuation {
+        parse::parse_smart(&text)
+    } else {
+        parse::parse(&text)
+    };
+    doc.set_slug_style(render.slug_style);
+    if render.numbered_headings {
+        numbering::number_document(&mut doc);
+    }
+    doc
+}
+
+/// The markdown source of the section under the heading at the top of the
+/// view, ending in a newline, with that heading; or the notice to show when
+/// there is no such section in the file.
+fn current_section(
+    stdin: Option<&StdinDocument>,
+    path: &Path,
+    rendered: &RenderedDocument,
+    scroll_offset: usize,
+    raw_source: bool,
+) -> Result<(parse::Heading, String), &'static str> {
+    let no_heading = "No heading above the current line";
+    let heading =
+        current_heading_context(&rendered.heading_lines, scroll_offset).ok_or(no_heading)?;
+    let line = rendered.source_line_for_rendered(heading.rendered_line);
+    let line = if raw_source {
+        line
+    } else {
+        source_line_in_file(path, line).ok_or("The current heading comes from an included file")?
+    };
+    let source = read_document(stdin, path).map_err(|_| "Could not read the document")?;
+    let (heading, text) = parse::section_source(&source, line).ok_or(no_heading)?;
+    Ok((heading, format!("{text}\n")))
+}
+
+/// The line of the file at `path` that 1-based `line` of its expanded text
+/// came from, or `None` when it came from an included file.
+fn source_line_in_file(path: &Path, line: usize) -> Option<usize> {
+    match fs::read_to_string(path) {
+        // An unreadable file is reported by the caller's own read.
+        Ok(source) => include::expand(&source, path, None).source_line(line),
+        Err(_) => Some(line),
+    }
+}
+
+/// Directory searched by find-and-replace: the working directory when the
+/// viewed file lives beneath it, otherwise the file's own directory.
+fn project_root_for(file: &Path) -> PathBuf {
+    let parent = file.parent().unwrap_or(Path::new(".")).to_path_buf();
+    match std::env::current_dir().and_then(fs::canonicalize) {
+        Ok(cwd) if file.starts_with(&cwd) => cwd,
+        _ => parent,
+    }
+}
+
+/// One-line status bar summary of a find-and-replace run.
+fn replace_summary_message(summary: &replace::ApplySummary, locale: Locale) -> String {
+    let count = |n: usize, one: &'static str, many: &'static str| {
+        if n == 1 {
+            locale.tr(one).to_owned()
+        } else {
+            locale.trf(many, &[("n", &n)])
         }
+    };
+    let mut msg = locale.trf(
+        "Replaced {occurrences} in {files}",
+        &[
+            (
+                "occurrences",
+                &count(summary.replaced, "1 occurrence", "{n} occurrences"),
+            ),
+            (
+                "files",
+                &count(summary.files_written, "1 file", "{n} files"),
+            ),
+        ],
+    );
+    if !summary.stale.is_empty() {
+        msg.push_str("; ");
+        msg.push_str(&locale.trf(
+            "skipped {n} changed since preview",
+            &[("n", &summary.stale.len())],
+        ));
+    }
+    if let Some((path, err)) = summary.failed.first() {
+        msg.push_str("; ");
+        msg.push_str(&locale.trf(
+            "{n} failed ({path}: {error})",
+            &[
+                ("n", &summary.failed.len()),
+                ("path", &path.display()),
+                ("error", err),
+            ],
+        ));
+    }
+    msg
+}
+
+/// Number of lines moved by a full-page scroll, keeping two lines of overlap
+/// for context like vim's `Ctrl-f`.
+fn full_page(viewport_height: usize) -> usize {
+    viewport_height.saturating_sub(2).max(1)
+}
+
+/// Move one animation frame from `current` towards `target`.
+///
+/// Covers a quarter of the remaining distance per frame (at least one line),
+/// so long jumps ease out as they approach the destination.
+fn smooth_scroll_step(current: usize, target: usize) -> usize {
+    let distance = current.abs_diff(target);
+    let step = (distance / 4).max(1).min(distance);
+    if target > current {
+        current + step
+    } else {
+        current - step
+    }
+}
+
+/// Where `zz` / `zt` / `zb` place the current line in the viewport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Recenter {
+    Center,
+    Top,
+    Bottom,
+}
+
+/// The recentring that `key` asks for after a `z`.  Control chords are
+/// left to their own bindings, so `z` then Ctrl-b still pages up.
+fn recenter_key(key: &KeyEvent) -> Option<Recenter> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        return None;
+    }
+    match key.code {
+        KeyCode::Char('z') => Some(Recenter::Center),
+        KeyCode::Char('t') => Some(Recenter::Top),
+        KeyCode::Char('b') => Some(Recenter::Bottom),
+        _ => None,
     }
 }
 
+/// The line that recentring commands act on: the focused link, then the
+/// current search match, falling back to the top line of the viewport.
+fn anchor_line(
+    rendered: &RenderedDocument,
+    focused_link: Option<usize>,
+    search: Option<&SearchState>,
+    scroll_offset: usize,
+) -> usize {
+    focused_link
+        .and_then(|idx| rendered.link_positions.get(idx))
+        .map(|l| l.rendered_line)
+        .or_else(|| {
+            search
+                .and_then(|s| s.current_match.and_then(|idx| s.matches.get(idx)))
+                .map(|m| m.rendered_line)
+        })
+        .unwrap_or(scroll_offset)
+}
+
+/// Compute the scroll offset that places `line` at the requested viewport position.
+fn recenter_scroll(
+    line: usize,
+    viewport_height: usize,
+    max_scroll: usize,
+    mode: Recenter,
+) -> usize {
+    let offset = match mode {
+        Recenter::Top => line,
+        Recenter::Center => line.saturating_sub(viewport_height / 2),
+        Recenter::Bottom => line.saturating_sub(viewport_height.saturating_sub(1)),
+    };
+    offset.min(max_scroll)
+}
+
 /// Find the heading context for the current scroll position.
 ///
 /// Returns the most recent heading at or before `scroll_offset`.
@@ -1034,12 +4500,15 @@ fn find_matches(rendered: &RenderedDocument, query: &str) -> Vec<SearchMatch> {
             match text_lower[pos..].find(&query_lower) {
                 Some(rel) => {
                     let start = pos + rel;
+                    let column_start = text_lower[..start].width();
                     matches.push(SearchMatch {
                         rendered_line: line_idx,
-                        column_start: start,
-                        column_end: start + query_lower.len(),
+                        column_start,
+                        column_end: column_start + query_lower.width(),
                     });
-                    pos = start + 1;
+                    // Step over the whole first character, which may take
+                    // several bytes.
+                    pos = start + text_lower[start..].chars().next().map_or(1, char::len_utf8);
                 }
                 None => break,
             }
@@ -1117,15 +4586,127 @@ fn resolve_markdown_link(current_file: &Path, url: &str) -> Option<PathBuf> {
     }
 }
 
+/// Rendered line of the heading that `url`'s `#fragment` names, matched
+/// against the anchor ids `--slug-style` gives the headings.
+fn fragment_line(rendered: &render::RenderedDocument, url: &str) -> Option<usize> {
+    let (_, fragment) = url.split_once('#')?;
+    let fragment = serve::percent_decode(fragment).unwrap_or_else(|_| fragment.to_owned());
+    rendered
+        .heading_lines
+        .iter()
+        .find(|h| h.anchor == fragment)
+        .map(|h| h.rendered_line)
+}
+
+/// Resolve a link URL to an existing local file of any type.
+fn resolve_local_file(current_file: &Path, url: &str) -> Option<PathBuf> {
+    let path_part = url.split('#').next()?;
+    if path_part.is_empty() {
+        return None;
+    }
+    let target = current_file.parent()?.join(path_part);
+    target
+        .is_file()
+        .then(|| fs::canonicalize(&target).unwrap_or(target))
+}
+
+/// Open a linked local non-markdown file with its configured handler,
+/// returning a status bar message describing the outcome.
+///
+/// Terminal handlers (e.g. `$EDITOR`) run with the TUI suspended; the
+/// alternate screen and raw mode are restored once they exit.
+fn open_local_file(
+    terminal: &mut DefaultTerminal,
+    handlers: &HandlerTable,
+    target: &Path,
+    locale: Locale,
+) -> io::Result<String> {
+    let name = target
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("file");
+    let handler = handlers.handler_for(target);
+    let Some(argv) = handler.command_line(target, handlers::handler_env) else {
+        return Ok(locale.trf("No handler command for {name}", &[("name", &name)]));
+    };
+
+    let result = match handler.mode {
+        HandlerMode::Detached => handlers::spawn_detached(&argv),
+        HandlerMode::Terminal => {
+            crossterm::terminal::disable_raw_mode()?;
+            crossterm::execute!(io::stdout(), crossterm::terminal::LeaveAlternateScreen)?;
+            let status = handlers::run_foreground(&argv);
+            crossterm::execute!(io::stdout(), crossterm::terminal::EnterAlternateScreen)?;
+            crossterm::terminal::enable_raw_mode()?;
+            terminal.clear()?;
+            status.and_then(|s| {
+                if s.success() {
+                    Ok(())
+                } else {
+                    Err(io::Error::other(format!("exited with {s}")))
+                }
+            })
+        }
+    };
+
+    let args: [(&str, &dyn std::fmt::Display); 2] = [("name", &name), ("command", &argv[0])];
+    Ok(match result {
+        Ok(()) => locale.trf("Opened {name} with {command}", &args),
+        Err(e) => locale.trf(
+            "Failed to open {name} with {command}: {error}",
+            &[args[0], args[1], ("error", &e)],
+        ),
+    })
+}
+
+/// An `mdmd serve` started from the palette (`:serve`).  It is stopped
+/// when dropped, so it lives as long as the viewer.
+struct BackgroundServer {
+    child: process::Child,
+    /// The URL the server printed on starting.
+    url: String,
+    /// The server's stdout, kept open so its later writes do not fail.
+    stdout: io::BufReader<process::ChildStdout>,
+}
+
+impl BackgroundServer {
+    /// Serve `path` with this executable, as `mdmd serve` does from the
+    /// shell, and wait for the URL it prints.
+    fn start(path: &Path) -> io::Result<BackgroundServer> {
+        let mut child = process::Command::new(std::env::current_exe()?)
+            .arg("serve")
+            .arg(path)
+            .stdin(process::Stdio::null())
+            .stdout(process::Stdio::piped())
+            .stderr(process::Stdio::null())
+            .spawn()?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let mut server = BackgroundServer {
+            child,
+            url: String::new(),
+            stdout: io::BufReader::new(stdout),
+        };
+        io::BufRead::read_line(&mut server.stdout, &mut server.url)?;
+        server.url.truncate(server.url.trim_end().len());
+        if server.url.is_empty() {
+            return Err(io::Error::other("it exited before printing its URL"));
+        }
+        Ok(server)
+    }
+}
+
+impl Drop for BackgroundServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
 /// Open an external URL in the system browser.
 fn open_url_in_browser(url: &str) {
-    let program = if cfg!(target_os = "macos") {
-        "open"
-    } else {
-        "xdg-open"
-    };
-    let _ = std::process::Command::new(program)
-        .arg(url)
+    let argv = serve::open_command_line(serve::RuntimePlatform::current(), None, None, url);
+    let _ = std::process::Command::new(&argv[0])
+        .args(&argv[1..])
         .stdin(std::process::Stdio::null())
         .stdout(std::process::Stdio::null())
         .stderr(std::process::Stdio::null())
@@ -1139,19 +4720,35 @@ fn ui(
     scroll_offset: usize,
     total_lines: usize,
     focused_link: Option<usize>,
+    changed: &[usize],
+    source_pane: Option<(&RenderedDocument, usize)>,
     outline_selected: Option<usize>,
+    heading_finder: Option<&HeadingFinder>,
+    file_finder: Option<&FileFinder>,
+    palette: Option<&Palette>,
+    history: Option<(&VisitHistory, usize)>,
+    backlinks: Option<&BacklinksModal>,
+    outgoing: Option<&OutgoingModal>,
     search: Option<&SearchState>,
     help: Option<&HelpState>,
+    replacing: Option<&ReplaceState>,
+    section_save: Option<&SectionSave>,
+    notice: Option<&str>,
+    encoding: Option<&str>,
+    project_root: &Path,
     current_file: &Path,
+    title: Option<&str>,
     can_go_back: bool,
-) {
+    locale: Locale,
+    trail: Option<&breadcrumbs::Trail>,
+) -> Option<Rect> {
     let area = frame.area();
 
     // Minimum usable terminal size: need width for content and height for viewport + status bar
     const MIN_WIDTH: u16 = 20;
     const MIN_HEIGHT: u16 = 5;
     if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
-        let msg = "Terminal too small";
+        let msg = locale.tr("Terminal too small");
         let msg_len = msg.len() as u16;
         let x = area.x + area.width.saturating_sub(msg_len) / 2;
         let y = area.y + area.height / 2;
@@ -1165,16 +4762,52 @@ fn ui(
                 Rect::new(x, y, w, 1),
             );
         }
-        return;
+        return None;
     }
 
+    // Peel off the breadcrumb header row first, so `chunks` is laid out the
+    // same with or without it.
+    let area = match trail {
+        Some(trail) => {
+            let [header, rest] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(1)]).areas(area);
+            render_breadcrumbs(frame, trail, header);
+            rest
+        }
+        None => area,
+    };
     let chunks = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).split(area);
 
     let viewport_height = chunks[0].height as usize;
 
+    // With the source beside it, the document takes the right half.
+    let doc_area = match source_pane {
+        Some((source, top)) => {
+            let [left, right] =
+                Layout::horizontal([Constraint::Percentage(50), Constraint::Fill(1)])
+                    .areas(chunks[0]);
+            let pane = Paragraph::new(source.text.clone())
+                .block(Block::default().borders(Borders::RIGHT))
+                .scroll((top as u16, 0));
+            frame.render_widget(pane, left);
+            right
+        }
+        None => chunks[0],
+    };
+
     // Render scrolled content
     let widget = Paragraph::new(rendered.text.clone()).scroll((scroll_offset as u16, 0));
-    frame.render_widget(widget, chunks[0]);
+    frame.render_widget(widget, doc_area);
+
+    // Mark the lines the last reload changed
+    let changed_style = Style::default().bg(Color::DarkGray);
+    for &line in changed {
+        let rel_line = line as isize - scroll_offset as isize;
+        if rel_line >= 0 && (rel_line as usize) < viewport_height {
+            let row = Rect::new(doc_area.x, doc_area.y + rel_line as u16, doc_area.width, 1);
+            frame.buffer_mut().set_style(row, changed_style);
+        }
+    }
 
     // Apply search match highlights
     if let Some(s) = search {
@@ -1188,14 +4821,18 @@ fn ui(
             for (idx, m) in s.matches.iter().enumerate() {
                 let rel_line = m.rendered_line as isize - scroll_offset as isize;
                 if rel_line >= 0 && (rel_line as usize) < viewport_height {
-                    let row = chunks[0].y + rel_line as u16;
+                    let row = doc_area.y + rel_line as u16;
                     let style = if s.current_match == Some(idx) {
                         current_style
                     } else {
                         match_style
                     };
-                    for col in m.column_start..m.column_end {
-                        let pos = Position::new(chunks[0].x + col as u16, row);
+                    let shift = render::alignment_offset(
+                        &rendered.text.lines[m.rendered_line],
+                        doc_area.width as usize,
+                    );
+                    for col in m.column_start + shift..m.column_end + shift {
+                        let pos = Position::new(doc_area.x + col as u16, row);
                         if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
                             cell.set_style(style);
                         }
@@ -1209,13 +4846,17 @@ fn ui(
     if let Some(link) = focused_link.and_then(|idx| rendered.link_positions.get(idx)) {
         let rel_line = link.rendered_line as isize - scroll_offset as isize;
         if rel_line >= 0 && (rel_line as usize) < viewport_height {
-            let row = chunks[0].y + rel_line as u16;
+            let row = doc_area.y + rel_line as u16;
             let focused_style = Style::default()
                 .fg(Color::White)
                 .bg(Color::Blue)
                 .add_modifier(Modifier::BOLD);
-            for col in link.column_start..link.column_end {
-                let pos = Position::new(chunks[0].x + col as u16, row);
+            let shift = render::alignment_offset(
+                &rendered.text.lines[link.rendered_line],
+                doc_area.width as usize,
+            );
+            for col in link.column_start + shift..link.column_end + shift {
+                let pos = Position::new(doc_area.x + col as u16, row);
                 if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
                     cell.set_style(focused_style);
                 }
@@ -1225,12 +4866,83 @@ fn ui(
 
     // Render outline modal overlay
     if let Some(selected) = outline_selected {
-        render_outline(frame, &rendered.heading_lines, selected, chunks[0]);
+        render_outline(frame, &rendered.heading_lines, selected, chunks[0], locale);
+    }
+
+    // Render go-to-heading finder overlay
+    if let Some(hf) = heading_finder {
+        render_heading_finder(frame, hf, &rendered.heading_lines, chunks[0], locale);
+    }
+
+    // Render file finder overlay
+    if let Some(ff) = file_finder {
+        render_file_finder(frame, ff, current_file, chunks[0], locale);
+    }
+
+    if let Some(pl) = palette {
+        render_palette(frame, pl, chunks[0], locale);
+    }
+
+    // Render history modal overlay
+    if let Some((visits, selected)) = history {
+        render_history(frame, visits, selected, current_file, chunks[0], locale);
+    }
+
+    // Render backlinks modal overlay
+    if let Some(bm) = backlinks {
+        render_backlinks(frame, bm, chunks[0], locale);
+    }
+
+    // Render outgoing links modal overlay
+    if let Some(om) = outgoing {
+        render_outgoing(frame, om, chunks[0], locale);
+    }
+
+    // Render help modal overlay
+    if let Some(hl) = help {
+        render_help(frame, hl, chunks[0], locale);
+    }
+
+    // Render find-and-replace review modal and its input/status bar
+    if let Some(rp) = replacing {
+        let bar_text = match rp.stage {
+            ReplaceStage::Find => format!("{}{}|", locale.tr("Replace: "), rp.find),
+            ReplaceStage::Replacement => format!(
+                "{}{}|",
+                locale.trf("Replace \"{find}\" with: ", &[("find", &rp.find)]),
+                rp.replacement
+            ),
+            ReplaceStage::Review => {
+                render_replace_review(frame, rp, project_root, chunks[0], locale);
+                let accepted = rp.candidates.iter().filter(|c| c.accepted).count();
+                format!(
+                    " {}",
+                    locale.trf(
+                        "{accepted}/{total} accepted \u{2014} y/n accept/reject  Space toggle  a/r all  Enter apply  Esc cancel",
+                        &[("accepted", &accepted), ("total", &rp.candidates.len())],
+                    )
+                )
+            }
+        };
+        let bar = Paragraph::new(Span::styled(
+            bar_text,
+            Style::default().fg(Color::White).bg(Color::DarkGray),
+        ))
+        .style(Style::default().bg(Color::DarkGray));
+        frame.render_widget(bar, chunks[1]);
+        return Some(doc_area);
     }
 
-    // Render help modal overlay
-    if let Some(hl) = help {
-        render_help(frame, hl, chunks[0]);
+    // Render the prompt for the file to save a section to
+    if let Some(save) = section_save {
+        let bar_text = format!("{}{}|", locale.tr("Save section to: "), save.path);
+        let bar = Paragraph::new(Span::styled(
+            bar_text,
+            Style::default().fg(Color::White).bg(Color::DarkGray),
+        ))
+        .style(Style::default().bg(Color::DarkGray));
+        frame.render_widget(bar, chunks[1]);
+        return Some(doc_area);
     }
 
     // Render status bar or search input bar
@@ -1238,7 +4950,7 @@ fn ui(
         if s.typing {
             // Search input bar
             let match_info = if s.matches.is_empty() && !s.query.is_empty() {
-                " [No matches]".to_owned()
+                format!(" [{}]", locale.tr("No matches"))
             } else if !s.matches.is_empty() {
                 let current = s.current_match.map(|i| i + 1).unwrap_or(0);
                 format!(" [{}/{}]", current, s.matches.len())
@@ -1252,19 +4964,19 @@ fn ui(
             ))
             .style(Style::default().bg(Color::DarkGray));
             frame.render_widget(bar, chunks[1]);
-            return;
+            return Some(doc_area);
         }
     }
 
     // Render normal status bar with scroll position indicator
     let position = if total_lines == 0 {
-        "Empty".to_owned()
+        locale.tr("Empty").to_owned()
     } else if total_lines <= viewport_height {
-        "All".to_owned()
+        locale.tr("All").to_owned()
     } else if scroll_offset == 0 {
-        "Top".to_owned()
+        locale.tr("Top").to_owned()
     } else if scroll_offset >= total_lines.saturating_sub(viewport_height) {
-        "Bot".to_owned()
+        locale.tr("Bot").to_owned()
     } else {
         let pct = (scroll_offset * 100) / total_lines;
         format!("{pct}%")
@@ -1282,7 +4994,7 @@ fn ui(
     let search_info = search
         .map(|s| {
             if s.matches.is_empty() {
-                format!("  /{} [No matches]", s.query)
+                format!("  /{} [{}]", s.query, locale.tr("No matches"))
             } else {
                 let current = s.current_match.map(|i| i + 1).unwrap_or(0);
                 format!("  /{} [{}/{}]", s.query, current, s.matches.len())
@@ -1300,15 +5012,26 @@ fn ui(
         String::new()
     };
 
+    let notice_info = notice.map(|n| format!("  {n}")).unwrap_or_default();
+
+    let encoding_info = encoding.map(|e| format!("  [{e}]")).unwrap_or_default();
+
+    let title_info = title.map(|t| format!("{t} \u{2014} ")).unwrap_or_default();
+
     let status = format!(
-        " Line {}/{} \u{2014} {}{}{}{}{}",
-        scroll_offset + 1,
-        total_lines,
+        " {}{} \u{2014} {}{}{}{}{}{}{}",
+        title_info,
+        locale.trf(
+            "Line {line}/{total}",
+            &[("line", &(scroll_offset + 1)), ("total", &total_lines)],
+        ),
         position,
         nav_info,
         heading_ctx,
         link_info,
         search_info,
+        encoding_info,
+        notice_info,
     );
     let status_bar = Paragraph::new(Span::styled(
         status,
@@ -1316,6 +5039,7 @@ fn ui(
     ))
     .style(Style::default().bg(Color::White));
     frame.render_widget(status_bar, chunks[1]);
+    Some(doc_area)
 }
 
 /// Compute a centered rectangle within `area`.
@@ -1333,20 +5057,29 @@ fn render_outline(
     heading_lines: &[HeadingPosition],
     selected: usize,
     viewport_area: Rect,
+    locale: Locale,
 ) {
     let popup = centered_rect(60, 70, viewport_area);
 
     // Clear the popup area
     frame.render_widget(Clear, popup);
 
-    // Build styled lines for each heading
+    // Build styled lines for each heading; repeated headings show their
+    // anchor ids, whose `-1`, `-2` suffixes tell them apart.
     let lines: Vec<Line<'static>> = heading_lines
         .iter()
         .map(|h| {
             let indent = "  ".repeat((h.level as usize).saturating_sub(1));
             let prefix = "#".repeat(h.level as usize);
             let style = render::heading_style(h.level);
-            Line::from(Span::styled(format!("{indent}{prefix} {}", h.text), style))
+            let mut spans = vec![Span::styled(format!("{indent}{prefix} {}", h.text), style)];
+            if heading_lines.iter().filter(|o| o.text == h.text).count() > 1 {
+                spans.push(Span::styled(
+                    format!("  #{}", h.anchor),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            Line::from(spans)
         })
         .collect();
 
@@ -1360,7 +5093,7 @@ fn render_outline(
     };
 
     let block = Block::bordered()
-        .title(" Outline ")
+        .title(locale.tr(" Outline "))
         .style(Style::default().fg(Color::White));
 
     let paragraph = Paragraph::new(lines)
@@ -1388,8 +5121,511 @@ fn render_outline(
     }
 }
 
+/// Render the find-and-replace review modal listing every match with a
+/// before/after preview.
+fn render_replace_review(
+    frame: &mut Frame,
+    rp: &ReplaceState,
+    project_root: &Path,
+    viewport_area: Rect,
+    locale: Locale,
+) {
+    let popup = centered_rect(80, 80, viewport_area);
+
+    // Clear the popup area
+    frame.render_widget(Clear, popup);
+
+    let removed = Style::default()
+        .fg(Color::Red)
+        .add_modifier(Modifier::CROSSED_OUT);
+    let added = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD);
+
+    // Two lines per match: location, then the previewed line
+    let mut lines: Vec<Line<'static>> = Vec::new();
+    for c in &rp.candidates {
+        let rel = c.path.strip_prefix(project_root).unwrap_or(&c.path);
+        let mark = if c.accepted { "[x]" } else { "[ ]" };
+        lines.push(Line::from(Span::styled(
+            format!(" {mark} {}:{}", rel.display(), c.line),
+            Style::default().fg(Color::Cyan),
+        )));
+
+        let before = &c.line_text[..c.line_column];
+        let after = &c.line_text[c.line_column + rp.find.len()..];
+        let mut spans = vec![
+            Span::raw(format!("     {}", before.trim_start())),
+            Span::styled(rp.find.clone(), removed),
+        ];
+        if c.accepted {
+            spans.push(Span::styled(rp.replacement.clone(), added));
+        }
+        spans.push(Span::raw(after.to_owned()));
+        lines.push(Line::from(spans));
+    }
+
+    // Keep the selected match visible (roughly centered)
+    let inner_height = popup.height.saturating_sub(2) as usize;
+    let selected_line = rp.selected * 2;
+    let max_scroll = lines.len().saturating_sub(inner_height);
+    let scroll = selected_line
+        .saturating_sub(inner_height / 2)
+        .min(max_scroll);
+
+    let title = locale.trf(
+        " Replace \"{find}\" \u{2192} \"{replacement}\" ",
+        &[("find", &rp.find), ("replacement", &rp.replacement)],
+    );
+    let block = Block::bordered()
+        .title(title)
+        .style(Style::default().fg(Color::White));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0));
+
+    frame.render_widget(paragraph, popup);
+
+    // Highlight both lines of the selected match
+    for rel_line in [selected_line, selected_line + 1] {
+        let rel = rel_line as isize - scroll as isize;
+        if rel >= 0 && (rel as usize) < inner_height {
+            let row = popup.y + 1 + rel as u16; // +1 for top border
+            for col in (popup.x + 1)..(popup.x + popup.width.saturating_sub(1)) {
+                let pos = Position::new(col, row);
+                if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
+                    cell.set_bg(Color::DarkGray);
+                }
+            }
+        }
+    }
+}
+
+/// Render the visited-files history modal, newest visit first.
+fn render_history(
+    frame: &mut Frame,
+    visits: &VisitHistory,
+    selected: usize,
+    current_file: &Path,
+    viewport_area: Rect,
+    locale: Locale,
+) {
+    let popup = centered_rect(60, 70, viewport_area);
+
+    // Clear the popup area
+    frame.render_widget(Clear, popup);
+
+    let now = SystemTime::now();
+    let lines: Vec<Line<'static>> = visits
+        .newest_first()
+        .map(|v| {
+            let age = now
+                .duration_since(v.visited_at)
+                .map(|elapsed| history::format_age(elapsed, locale))
+                .unwrap_or_else(|_| locale.tr("just now").to_owned());
+            let name_style = if v.file_path == current_file {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Line::from(vec![
+                Span::styled(format!(" {age:>9}  "), Style::default().fg(Color::DarkGray)),
+                Span::styled(v.file_path.display().to_string(), name_style),
+            ])
+        })
+        .collect();
+
+    // Calculate scroll offset to keep selected item visible (roughly centered)
+    let inner_height = popup.height.saturating_sub(2) as usize;
+    let max_scroll = lines.len().saturating_sub(inner_height);
+    let scroll = selected.saturating_sub(inner_height / 2).min(max_scroll);
+
+    let block = Block::bordered()
+        .title(locale.tr(" History "))
+        .style(Style::default().fg(Color::White));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0));
+
+    frame.render_widget(paragraph, popup);
+
+    // Apply full-width highlight to the selected visit
+    if inner_height > 0 {
+        let rel_line = selected as isize - scroll as isize;
+        if rel_line >= 0 && (rel_line as usize) < inner_height {
+            let row = popup.y + 1 + rel_line as u16; // +1 for top border
+            let highlight = Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD);
+            for col in (popup.x + 1)..(popup.x + popup.width.saturating_sub(1)) {
+                let pos = Position::new(col, row);
+                if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
+                    cell.set_style(highlight);
+                }
+            }
+        }
+    }
+}
+
+/// Render the go-to-heading finder: the query, then the matching headings
+/// best first, indented by level as in the outline.
+fn render_heading_finder(
+    frame: &mut Frame,
+    finder: &HeadingFinder,
+    heading_lines: &[HeadingPosition],
+    viewport_area: Rect,
+    locale: Locale,
+) {
+    let popup = centered_rect(60, 70, viewport_area);
+
+    // Clear the popup area
+    frame.render_widget(Clear, popup);
+
+    // The query and a blank separator stay put; the matches below scroll to
+    // keep the selected one visible (roughly centered).
+    const HEADER_ROWS: usize = 2;
+    let inner_height = popup.height.saturating_sub(2) as usize;
+    let list_height = inner_height.saturating_sub(HEADER_ROWS);
+    let scroll = finder
+        .selected
+        .saturating_sub(list_height / 2)
+        .min(finder.matches.len().saturating_sub(list_height));
+
+    let mut lines: Vec<Line<'static>> = vec![
+        Line::from(Span::styled(
+            format!(" {}\u{2502}", finder.query), // │ as cursor
+            Style::default().fg(Color::Yellow),
+        )),
+        Line::from(""),
+    ];
+    if finder.matches.is_empty() {
+        lines.push(Line::from(Span::styled(
+            locale.tr(" No matching headings").to_owned(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for &i in finder.matches.iter().skip(scroll).take(list_height) {
+        let h = &heading_lines[i];
+        let indent = "  ".repeat((h.level as usize).saturating_sub(1));
+        let prefix = "#".repeat(h.level as usize);
+        lines.push(Line::from(Span::styled(
+            format!(" {indent}{prefix} {}", h.text),
+            render::heading_style(h.level),
+        )));
+    }
+
+    let block = Block::bordered()
+        .title(locale.tr(" Go to heading "))
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(Paragraph::new(lines).block(block), popup);
+
+    // Apply full-width highlight to the selected heading line
+    if !finder.matches.is_empty() && list_height > 0 {
+        // +1 for the top border
+        let row = popup.y + 1 + (HEADER_ROWS + finder.selected - scroll) as u16;
+        let highlight = Style::default()
+            .bg(Color::Blue)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD);
+        for col in (popup.x + 1)..(popup.x + popup.width.saturating_sub(1)) {
+            let pos = Position::new(col, row);
+            if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
+                cell.set_style(highlight);
+            }
+        }
+    }
+}
+
+/// Render the file finder: the query, then the matching paths best first,
+/// relative to the project root.
+fn render_file_finder(
+    frame: &mut Frame,
+    finder: &FileFinder,
+    current_file: &Path,
+    viewport_area: Rect,
+    locale: Locale,
+) {
+    let popup = centered_rect(60, 70, viewport_area);
+
+    // Clear the popup area
+    frame.render_widget(Clear, popup);
+
+    // As in the heading finder, the query and a blank separator stay put.
+    const HEADER_ROWS: usize = 2;
+    let inner_height = popup.height.saturating_sub(2) as usize;
+    let list_height = inner_height.saturating_sub(HEADER_ROWS);
+    let scroll = finder
+        .selected
+        .saturating_sub(list_height / 2)
+        .min(finder.matches.len().saturating_sub(list_height));
+
+    let mut lines: Vec<Line<'static>> = vec![
+        Line::from(Span::styled(
+            format!(" {}\u{2502}", finder.query), // │ as cursor
+            Style::default().fg(Color::Yellow),
+        )),
+        Line::from(""),
+    ];
+    if finder.matches.is_empty() {
+        let empty = if finder.listing() {
+            " Listing files..."
+        } else {
+            " No matching files"
+        };
+        lines.push(Line::from(Span::styled(
+            locale.tr(empty).to_owned(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for &i in finder.matches.iter().skip(scroll).take(list_height) {
+        let rel = &finder.files[i];
+        let style = if finder.root.join(rel) == current_file {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        lines.push(Line::from(Span::styled(format!(" {rel}"), style)));
+    }
+
+    let title = locale.trf(" Open file ({n}) ", &[("n", &finder.files.len())]);
+    let block = Block::bordered()
+        .title(title)
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(Paragraph::new(lines).block(block), popup);
+
+    // Apply full-width highlight to the selected path
+    if !finder.matches.is_empty() && list_height > 0 {
+        // +1 for the top border
+        let row = popup.y + 1 + (HEADER_ROWS + finder.selected - scroll) as u16;
+        let highlight = Style::default()
+            .bg(Color::Blue)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD);
+        for col in (popup.x + 1)..(popup.x + popup.width.saturating_sub(1)) {
+            let pos = Position::new(col, row);
+            if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
+                cell.set_style(highlight);
+            }
+        }
+    }
+}
+
+/// Render the command palette: the line typed, then the matching commands
+/// best first, each with its argument and what it does.
+fn render_palette(frame: &mut Frame, palette: &Palette, viewport_area: Rect, locale: Locale) {
+    let popup = centered_rect(60, 70, viewport_area);
+
+    // Clear the popup area
+    frame.render_widget(Clear, popup);
+
+    // As in the finders, the query and a blank separator stay put.
+    const HEADER_ROWS: usize = 2;
+    let inner_height = popup.height.saturating_sub(2) as usize;
+    let list_height = inner_height.saturating_sub(HEADER_ROWS);
+    let scroll = palette
+        .selected
+        .saturating_sub(list_height / 2)
+        .min(palette.matches.len().saturating_sub(list_height));
+
+    let mut lines: Vec<Line<'static>> = vec![
+        Line::from(Span::styled(
+            format!(" :{}\u{2502}", palette.query), // │ as cursor
+            Style::default().fg(Color::Yellow),
+        )),
+        Line::from(""),
+    ];
+    if palette.matches.is_empty() {
+        lines.push(Line::from(Span::styled(
+            locale.tr(" No matching commands").to_owned(),
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    let name_width = palette::COMMANDS
+        .iter()
+        .map(|c| c.name.len() + c.arg.map_or(0, |a| a.len() + 1))
+        .max()
+        .unwrap_or(0);
+    for &i in palette.matches.iter().skip(scroll).take(list_height) {
+        let command = &palette::COMMANDS[i];
+        let usage = match command.arg {
+            Some(arg) => format!("{} {arg}", command.name),
+            None => command.name.to_owned(),
+        };
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(" {usage:<name_width$}  "),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(
+                locale.tr(command.description).to_owned(),
+                Style::default().fg(Color::White),
+            ),
+        ]));
+    }
+
+    let block = Block::bordered()
+        .title(locale.tr(" Commands "))
+        .style(Style::default().fg(Color::White));
+    frame.render_widget(Paragraph::new(lines).block(block), popup);
+
+    // Apply full-width highlight to the selected command
+    if !palette.matches.is_empty() && list_height > 0 {
+        // +1 for the top border
+        let row = popup.y + 1 + (HEADER_ROWS + palette.selected - scroll) as u16;
+        let highlight = Style::default()
+            .bg(Color::Blue)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD);
+        for col in (popup.x + 1)..(popup.x + popup.width.saturating_sub(1)) {
+            let pos = Position::new(col, row);
+            if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
+                cell.set_style(highlight);
+            }
+        }
+    }
+}
+
+/// Render the backlinks modal: one line per linking document, its title
+/// followed by the sentence containing the link.
+fn render_backlinks(
+    frame: &mut Frame,
+    modal: &BacklinksModal,
+    viewport_area: Rect,
+    locale: Locale,
+) {
+    let popup = centered_rect(70, 60, viewport_area);
+
+    // Clear the popup area
+    frame.render_widget(Clear, popup);
+
+    let lines: Vec<Line<'static>> = modal
+        .refs
+        .iter()
+        .map(|r| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {}", r.source_display),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    format!("  {}", r.snippet),
+                    Style::default().fg(Color::DarkGray),
+                ),
+            ])
+        })
+        .collect();
+
+    // Calculate scroll offset to keep selected item visible (roughly centered)
+    let inner_height = popup.height.saturating_sub(2) as usize;
+    let max_scroll = lines.len().saturating_sub(inner_height);
+    let scroll = modal
+        .selected
+        .saturating_sub(inner_height / 2)
+        .min(max_scroll);
+
+    let block = Block::bordered()
+        .title(locale.trf(" Backlinks ({count}) ", &[("count", &modal.refs.len())]))
+        .style(Style::default().fg(Color::White));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0));
+
+    frame.render_widget(paragraph, popup);
+
+    // Apply full-width highlight to the selected backlink
+    if inner_height > 0 {
+        let rel_line = modal.selected as isize - scroll as isize;
+        if rel_line >= 0 && (rel_line as usize) < inner_height {
+            let row = popup.y + 1 + rel_line as u16; // +1 for top border
+            let highlight = Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD);
+            for col in (popup.x + 1)..(popup.x + popup.width.saturating_sub(1)) {
+                let pos = Position::new(col, row);
+                if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
+                    cell.set_style(highlight);
+                }
+            }
+        }
+    }
+}
+
+/// Render the outgoing links modal: one line per link with its status,
+/// text, and destination.
+fn render_outgoing(frame: &mut Frame, modal: &OutgoingModal, viewport_area: Rect, locale: Locale) {
+    let popup = centered_rect(70, 60, viewport_area);
+
+    // Clear the popup area
+    frame.render_widget(Clear, popup);
+
+    let lines: Vec<Line<'static>> = modal
+        .links
+        .iter()
+        .map(|l| {
+            let (status, color) = match l.status {
+                outgoing::LinkStatus::Ok => (locale.tr("ok"), Color::Green),
+                outgoing::LinkStatus::Broken => (locale.tr("broken"), Color::Red),
+                outgoing::LinkStatus::External => (locale.tr("external"), Color::Cyan),
+            };
+            Line::from(vec![
+                Span::styled(format!(" {status:>8}  "), Style::default().fg(color)),
+                Span::styled(l.text.clone(), Style::default().fg(Color::White)),
+                Span::styled(format!("  {}", l.url), Style::default().fg(Color::DarkGray)),
+            ])
+        })
+        .collect();
+
+    // Calculate scroll offset to keep selected item visible (roughly centered)
+    let inner_height = popup.height.saturating_sub(2) as usize;
+    let max_scroll = lines.len().saturating_sub(inner_height);
+    let scroll = modal
+        .selected
+        .saturating_sub(inner_height / 2)
+        .min(max_scroll);
+
+    let block = Block::bordered()
+        .title(locale.trf(
+            " Outgoing links ({count}) ",
+            &[("count", &modal.links.len())],
+        ))
+        .style(Style::default().fg(Color::White));
+
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .scroll((scroll as u16, 0));
+
+    frame.render_widget(paragraph, popup);
+
+    // Apply full-width highlight to the selected link
+    if inner_height > 0 {
+        let rel_line = modal.selected as isize - scroll as isize;
+        if rel_line >= 0 && (rel_line as usize) < inner_height {
+            let row = popup.y + 1 + rel_line as u16; // +1 for top border
+            let highlight = Style::default()
+                .bg(Color::Blue)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD);
+            for col in (popup.x + 1)..(popup.x + popup.width.saturating_sub(1)) {
+                let pos = Position::new(col, row);
+                if let Some(cell) = frame.buffer_mut().cell_mut(pos) {
+                    cell.set_style(highlight);
+                }
+            }
+        }
+    }
+}
+
 /// Render the help/shortcuts modal overlay with filterable shortcut list.
-fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect) {
+fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect, locale: Locale) {
     let popup = centered_rect(60, 70, viewport_area);
 
     // Clear the popup area
@@ -1403,7 +5639,7 @@ fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect) {
 
     // Filter input line
     let filter_display = if help.filter.is_empty() {
-        " Type to filter...".to_owned()
+        locale.tr(" Type to filter...").to_owned()
     } else {
         format!(" {}\u{2502}", help.filter) // │ as cursor
     };
@@ -1424,8 +5660,11 @@ fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect) {
                     return true;
                 }
                 e.key.to_lowercase().contains(&filter_lower)
-                    || e.description.to_lowercase().contains(&filter_lower)
-                    || cat.name.to_lowercase().contains(&filter_lower)
+                    || locale
+                        .tr(e.description)
+                        .to_lowercase()
+                        .contains(&filter_lower)
+                    || locale.tr(cat.name).to_lowercase().contains(&filter_lower)
             })
             .collect();
 
@@ -1436,7 +5675,7 @@ fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect) {
 
         // Category header
         lines.push(Line::from(Span::styled(
-            format!(" {}", cat.name),
+            format!(" {}", locale.tr(cat.name)),
             Style::default()
                 .fg(Color::Cyan)
                 .add_modifier(Modifier::BOLD),
@@ -1452,7 +5691,7 @@ fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect) {
                         .add_modifier(Modifier::BOLD),
                 ),
                 Span::styled(
-                    entry.description.to_owned(),
+                    locale.tr(entry.description).to_owned(),
                     Style::default().fg(Color::White),
                 ),
             ]));
@@ -1464,15 +5703,15 @@ fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect) {
 
     if !any_match && !filter_lower.is_empty() {
         lines.push(Line::from(Span::styled(
-            " No matching shortcuts",
+            locale.tr(" No matching shortcuts"),
             Style::default().fg(Color::DarkGray),
         )));
     }
 
     let title = if help.filter.is_empty() {
-        " Help \u{2014} ? to close "
+        locale.tr(" Help \u{2014} ? to close ")
     } else {
-        " Help \u{2014} Esc to close "
+        locale.tr(" Help \u{2014} Esc to close ")
     };
 
     let block = Block::bordered()
@@ -1485,3 +5724,27 @@ fn render_help(frame: &mut Frame, help: &HelpState, viewport_area: Rect) {
 
     frame.render_widget(paragraph, popup);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recenter_keys_leave_control_chords_alone() {
+        let key = |c, modifiers| KeyEvent::new(KeyCode::Char(c), modifiers);
+        assert_eq!(
+            recenter_key(&key('z', KeyModifiers::NONE)),
+            Some(Recenter::Center)
+        );
+        assert_eq!(
+            recenter_key(&key('t', KeyModifiers::NONE)),
+            Some(Recenter::Top)
+        );
+        assert_eq!(
+            recenter_key(&key('b', KeyModifiers::NONE)),
+            Some(Recenter::Bottom)
+        );
+        assert_eq!(recenter_key(&key('b', KeyModifiers::CONTROL)), None);
+        assert_eq!(recenter_key(&key('x', KeyModifiers::NONE)), None);
+    }
+}
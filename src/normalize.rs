@@ -0,0 +1,52 @@
+//! Source normalization applied before parsing and rendering.
+//!
+//! Markdown written on Windows, or exported by some editors, starts with a
+//! byte order mark and ends its lines with `\r\n` (or, rarely, a lone `\r`).
+//! CommonMark treats all three line endings alike, but line counts, byte
+//! offsets, and text matching elsewhere only look for `\n`.  [`source`]
+//! rewrites them to `\n` so [`parse`](crate::parse), [`html`](crate::html),
+//! and [`backlinks`](crate::backlinks) see the same lines on every platform.
+
+use std::borrow::Cow;
+
+/// `text` without a leading byte order mark and with every line ending as
+/// `\n`.  Borrows `text` when there is nothing to change.
+pub fn source(text: &str) -> Cow<'_, str> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    if !text.contains('\r') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\r' {
+            chars.next_if_eq(&'\n');
+            out.push('\n');
+        } else {
+            out.push(c);
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unix_text_is_borrowed() {
+        assert!(matches!(source("# Title\n\nBody\n"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn bom_is_stripped() {
+        assert_eq!(source("\u{feff}# Title\n"), "# Title\n");
+        assert!(matches!(source("\u{feff}# Title\n"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn crlf_and_lone_cr_become_lf() {
+        assert_eq!(source("a\r\nb\rc\n\r\n"), "a\nb\nc\n\n");
+        assert_eq!(source("\u{feff}a\r\n"), "a\n");
+    }
+}
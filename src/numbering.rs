@@ -0,0 +1,85 @@
+//! Hierarchical heading numbers for `--numbered-headings`.
+//!
+//! Each heading is numbered within the heading it falls under: `1.`, `1.2`,
+//! `1.2.3`.  Nesting follows [`toc::nesting`], so a document whose top
+//! headings are `##` still starts at `1.` and a skipped level adds no `0`
+//! component.  The same numbers are shown in rendered HTML, both tables of
+//! contents, and the TUI.
+//!
+//! ```
+//! use mdmd::numbering::heading_numbers;
+//! assert_eq!(
+//!     heading_numbers(&[1, 2, 3, 2, 1]),
+//!     ["1.", "1.1", "1.1.1", "1.2", "2."]
+//! );
+//! ```
+
+use crate::parse::{BlockKind, ParsedDocument};
+use crate::toc;
+
+/// Number of each heading of an outline, given their `levels` in document
+/// order.
+pub fn heading_numbers(levels: &[u8]) -> Vec<String> {
+    let mut counters: Vec<usize> = Vec::new();
+    toc::nesting(levels)
+        .into_iter()
+        .map(|depth| {
+            counters.resize(depth + 1, 0);
+            counters[depth] += 1;
+            match counters.as_slice() {
+                [top] => format!("{top}."),
+                parts => parts
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join("."),
+            }
+        })
+        .collect()
+}
+
+/// Prefix each heading of `doc` with its number, both in
+/// [`ParsedDocument::headings`] and in the heading's own block.
+pub fn number_document(doc: &mut ParsedDocument) {
+    let levels: Vec<u8> = doc.headings.iter().map(|h| h.level).collect();
+    for (heading, number) in doc.headings.iter_mut().zip(heading_numbers(&levels)) {
+        let prefix = format!("{number} ");
+        let block = doc
+            .blocks
+            .iter_mut()
+            .find(|b| b.line_start == heading.line && matches!(b.kind, BlockKind::Heading(_)));
+        if let Some(block) = block {
+            block.content.insert_str(0, &prefix);
+            for link in &mut block.inline_links {
+                link.start += prefix.len();
+                link.end += prefix.len();
+            }
+        }
+        heading.text.insert_str(0, &prefix);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn numbers_follow_nesting() {
+        assert_eq!(heading_numbers(&[2, 3, 3, 2]), ["1.", "1.1", "1.2", "2."]);
+        assert_eq!(heading_numbers(&[1, 3, 2]), ["1.", "1.1", "1.2"]);
+        assert_eq!(heading_numbers(&[3, 1, 2]), ["1.", "2.", "2.1"]);
+        assert!(heading_numbers(&[]).is_empty());
+    }
+
+    #[test]
+    fn numbering_a_document_keeps_links_in_place() {
+        let mut doc = parse::parse("# Intro\n\n## See [the guide](guide.md)\n");
+        number_document(&mut doc);
+        let texts: Vec<&str> = doc.headings.iter().map(|h| h.text.as_str()).collect();
+        assert_eq!(texts, ["1. Intro", "1.1 See the guide"]);
+        let block = &doc.blocks[1];
+        let link = &block.inline_links[0];
+        assert_eq!(&block.content[link.start..link.end], "the guide");
+    }
+}
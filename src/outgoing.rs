@@ -0,0 +1,227 @@
+//! Outgoing links of a document and whether they resolve.
+//!
+//! The links are the ones [`parse`](crate::parse) collects, images excluded.
+//! Each is classified as external (it has a URL scheme), ok (the file, and
+//! the heading anchor if one is given, exists), or broken.  Used by the
+//! "Outgoing links" panel of served pages and the TUI's outgoing links modal.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::frontmatter;
+use crate::html::{heading_anchors_with, SlugStyle};
+use crate::parse::{self, LinkKind};
+
+/// Whether an outgoing link resolves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStatus {
+    /// A local link whose file (and anchor, if any) exists.
+    Ok,
+    /// A local link to a missing file or anchor.
+    Broken,
+    /// A link with a URL scheme (`https:`, `mailto:`, …); not checked.
+    External,
+}
+
+impl LinkStatus {
+    /// Lowercase name, as used in CSS classes and JSON.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LinkStatus::Ok => "ok",
+            LinkStatus::Broken => "broken",
+            LinkStatus::External => "external",
+        }
+    }
+}
+
+/// A link from the document, in source order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingLink {
+    /// Visible text of the link.
+    pub text: String,
+    /// Destination URL as written.
+    pub url: String,
+    /// 1-based source line, frontmatter included.
+    pub line: usize,
+    pub status: LinkStatus,
+}
+
+/// Outgoing links of `source`, the contents of `file`.  Relative paths
+/// resolve against `file`'s directory and root-relative ones (`/docs/a.md`)
+/// against `root`, and anchors against heading ids in `style`.
+pub fn outgoing_links(
+    source: &str,
+    file: &Path,
+    root: &Path,
+    style: SlugStyle,
+) -> Vec<OutgoingLink> {
+    let extracted = frontmatter::extract(source);
+    let body = extracted.render_body.as_ref();
+    let line_offset = source[..source.len() - extracted.body.len()]
+        .matches('\n')
+        .count();
+
+    let doc = parse::parse(body);
+    let mut own_anchors: Option<Vec<String>> = None;
+    doc.links
+        .iter()
+        .filter(|l| l.kind != LinkKind::Image)
+        .map(|l| {
+            let status = if l.kind == LinkKind::Email || has_scheme(&l.url) {
+                LinkStatus::External
+            } else if let Some(fragment) = l.url.strip_prefix('#') {
+                let anchors = own_anchors.get_or_insert_with(|| anchors_of(body, style));
+                found(anchors.iter().any(|a| *a == decode(fragment)))
+            } else {
+                local_status(&l.url, file, root, style)
+            };
+            OutgoingLink {
+                text: l.text.clone(),
+                url: l.url.clone(),
+                line: l.line + line_offset,
+                status,
+            }
+        })
+        .collect()
+}
+
+/// Status of a link to another local file, with an optional `#anchor`
+/// checked against the target's headings when it is markdown.
+fn local_status(url: &str, file: &Path, root: &Path, style: SlugStyle) -> LinkStatus {
+    let (path, fragment) = match url.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (url, None),
+    };
+    let path = path.split('?').next().unwrap_or(path);
+    if path.is_empty() {
+        return LinkStatus::Broken;
+    }
+    let path = decode(path);
+    let target: PathBuf = match path.strip_prefix('/') {
+        Some(rooted) => root.join(rooted),
+        None => file.parent().unwrap_or(Path::new(".")).join(path.as_ref()),
+    };
+    if !target.exists() {
+        return LinkStatus::Broken;
+    }
+    let is_markdown = target
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| matches!(e, "md" | "markdown"));
+    match fragment.filter(|f| !f.is_empty() && is_markdown) {
+        Some(fragment) => {
+            let anchors = fs::read_to_string(&target)
+                .map(|src| anchors_of(frontmatter::extract(&src).render_body.as_ref(), style))
+                .unwrap_or_default();
+            found(anchors.iter().any(|a| *a == decode(fragment)))
+        }
+        None => LinkStatus::Ok,
+    }
+}
+
+fn found(exists: bool) -> LinkStatus {
+    if exists {
+        LinkStatus::Ok
+    } else {
+        LinkStatus::Broken
+    }
+}
+
+fn anchors_of(body: &str, style: SlugStyle) -> Vec<String> {
+    heading_anchors_with(body, style)
+        .into_iter()
+        .map(|(heading, _)| heading.anchor_id)
+        .collect()
+}
+
+/// Whether `url` starts with a URI scheme (`https:`, `mailto:`) or is
+/// protocol-relative (`//host/path`).
+fn has_scheme(url: &str) -> bool {
+    if url.starts_with("//") {
+        return true;
+    }
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Percent-decode `text`, leaving it as written when it is not valid
+/// percent-encoded UTF-8.
+fn decode(text: &str) -> Cow<'_, str> {
+    if !text.contains('%') {
+        return Cow::Borrowed(text);
+    }
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match hex {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).map_or(Cow::Borrowed(text), Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(root.join("docs/guide.md"), "# Guide\n\n## Set up\n").unwrap();
+        fs::write(root.join("docs/my notes.md"), "notes\n").unwrap();
+        let source = "---\ntitle: T\n---\n# Top\n\n\
+[a](https://example.com) [b](mailto:x@y.z) <x@y.z>\n\
+[d](docs/guide.md#set-up) [e](docs/guide.md#nope) [f](/docs/guide.md)\n\
+[g](docs/my%20notes.md) [h](missing.md) [i](#top) [j](#bottom) ![img](pic.png)\n";
+        let links = outgoing_links(source, &root.join("index.md"), root, SlugStyle::default());
+        let statuses: Vec<(&str, &str, usize)> = links
+            .iter()
+            .map(|l| (l.text.as_str(), l.status.as_str(), l.line))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("a", "external", 6),
+                ("b", "external", 6),
+                ("x@y.z", "external", 6),
+                ("d", "ok", 7),
+                ("e", "broken", 7),
+                ("f", "ok", 7),
+                ("g", "ok", 8),
+                ("h", "broken", 8),
+                ("i", "ok", 8),
+                ("j", "broken", 8),
+            ]
+        );
+    }
+
+    #[test]
+    fn scheme_detection() {
+        assert!(has_scheme("https://a"));
+        assert!(has_scheme("//cdn.example.com/x.js"));
+        assert!(has_scheme("tel:+123"));
+        assert!(!has_scheme("docs/a.md"));
+        assert!(!has_scheme("c:/windows"));
+        assert!(!has_scheme("a.md#x:y"));
+    }
+}
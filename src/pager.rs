@@ -0,0 +1,71 @@
+//! Plain-text fallback for terminals the viewer cannot drive.
+//!
+//! The TUI needs a terminal that understands cursor movement and the
+//! alternate screen.  On a dumb terminal (`TERM=dumb`, as in an Emacs shell
+//! buffer), when stdout is redirected, or when the terminal's size cannot be
+//! read, the viewer instead writes the rendered document as plain text:
+//! through `$PAGER` when stdout is a terminal and `$PAGER` is set, and
+//! straight to stdout otherwise.
+
+use std::io::{self, IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Whether the viewer can run on a terminal whose `TERM` is `term`.
+/// `stdout_tty` is whether stdout is a terminal and `size` whether the
+/// terminal's size could be read.
+pub fn tui_supported(term: Option<&str>, stdout_tty: bool, size: bool) -> bool {
+    stdout_tty && size && term != Some("dumb")
+}
+
+/// Whether the viewer can run on the current terminal, per
+/// [`tui_supported`].
+pub fn tui_supported_here() -> bool {
+    let size = crossterm::terminal::size().is_ok_and(|(cols, rows)| cols > 0 && rows > 0);
+    tui_supported(
+        std::env::var("TERM").ok().as_deref(),
+        io::stdout().is_terminal(),
+        size,
+    )
+}
+
+/// Show `text` through `$PAGER`, or write it to stdout when that is not a
+/// terminal, `$PAGER` is unset, or it cannot be started.
+pub fn page(text: &[u8]) -> io::Result<()> {
+    let pager = std::env::var("PAGER").unwrap_or_default();
+    // `$PAGER` may carry arguments, e.g. "less -R".
+    let argv: Vec<&str> = pager.split_whitespace().collect();
+    if !argv.is_empty() && io::stdout().is_terminal() {
+        match Command::new(argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            Ok(mut child) => {
+                // A pager quit before the end closes its stdin; that is fine.
+                let _ = child.stdin.take().expect("stdin is piped").write_all(text);
+                child.wait()?;
+                return Ok(());
+            }
+            Err(e) => eprintln!("Warning: cannot run $PAGER ({}): {e}", argv[0]),
+        }
+    }
+    let mut out = io::stdout().lock();
+    match out.write_all(text).and_then(|()| out.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_without_a_capable_terminal() {
+        assert!(tui_supported(Some("xterm-256color"), true, true));
+        assert!(tui_supported(None, true, true));
+        assert!(!tui_supported(Some("dumb"), true, true));
+        assert!(!tui_supported(Some("xterm"), false, true));
+        assert!(!tui_supported(Some("xterm"), true, false));
+    }
+}
@@ -0,0 +1,316 @@
+//! The TUI's command palette (`:`).
+//!
+//! Every viewer action has a named command here, so those without a key of
+//! their own (the text width, the code theme, serving the document) stay
+//! reachable and those with one can be found by name.  The first word typed
+//! is fuzzy-matched against the command names, as in the finders; the rest
+//! of the line is the command's argument.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::finder;
+
+/// What running a palette command does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Press these keys, as in normal mode.
+    Keys(&'static [(KeyCode, KeyModifiers)]),
+    /// Open the file finder, with the argument as its query.
+    Open,
+    /// Rewrap paragraphs to the argument's width; `0` keeps source lines.
+    Width,
+    Justify,
+    Hyphenate,
+    Numbering,
+    Smart,
+    /// Switch code highlighting between dark and light backgrounds.
+    Theme,
+    /// Start `mdmd serve` on the current document.
+    Serve,
+}
+
+/// A command in the palette.
+pub struct Command {
+    pub name: &'static str,
+    /// Placeholder for the argument of a command that takes one.
+    pub arg: Option<&'static str>,
+    /// What the command does, as in the shortcut help.
+    pub description: &'static str,
+    pub action: Action,
+}
+
+/// The commands, in the order the palette lists them before anything is
+/// typed.
+pub const COMMANDS: &[Command] = &[
+    Command {
+        name: "open",
+        arg: Some("FILE"),
+        description: "Open a project file by typing part of its path",
+        action: Action::Open,
+    },
+    Command {
+        name: "headings",
+        arg: None,
+        description: "Go to heading by typing part of its name",
+        action: Action::Keys(&[(KeyCode::Char('k'), KeyModifiers::CONTROL)]),
+    },
+    Command {
+        name: "outline",
+        arg: None,
+        description: "Open outline",
+        action: Action::Keys(&[(KeyCode::Char('o'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "top",
+        arg: None,
+        description: "Jump to top",
+        action: Action::Keys(&[(KeyCode::Char('g'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "bottom",
+        arg: None,
+        description: "Jump to bottom",
+        action: Action::Keys(&[(KeyCode::Char('G'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "next-heading",
+        arg: None,
+        description: "Next heading",
+        action: Action::Keys(&[(KeyCode::Char('n'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "previous-heading",
+        arg: None,
+        description: "Previous heading",
+        action: Action::Keys(&[(KeyCode::Char('p'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "search",
+        arg: None,
+        description: "Start search",
+        action: Action::Keys(&[(KeyCode::Char('/'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "replace",
+        arg: None,
+        description: "Find and replace across files",
+        action: Action::Keys(&[(KeyCode::Char('R'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "toggle-task",
+        arg: None,
+        description: "Toggle task checkbox on the current line",
+        action: Action::Keys(&[(KeyCode::Char('x'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "copy-section",
+        arg: None,
+        description: "Copy the markdown of the current section",
+        action: Action::Keys(&[(KeyCode::Char('y'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "export-section",
+        arg: None,
+        description: "Save the markdown of the current section to a file",
+        action: Action::Keys(&[(KeyCode::Char('Y'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "back",
+        arg: None,
+        description: "Navigate back",
+        action: Action::Keys(&[(KeyCode::Backspace, KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "forward",
+        arg: None,
+        description: "Navigate forward",
+        action: Action::Keys(&[(KeyCode::Char('L'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "history",
+        arg: None,
+        description: "Show visited files history",
+        action: Action::Keys(&[(KeyCode::Char('H'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "backlinks",
+        arg: None,
+        description: "Show backlinks to this file",
+        action: Action::Keys(&[(KeyCode::Char('b'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "links",
+        arg: None,
+        description: "Show outgoing links and whether they resolve",
+        action: Action::Keys(&[(KeyCode::Char('O'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "jump-back",
+        arg: None,
+        description: "Jump back to previous position",
+        action: Action::Keys(&[(KeyCode::Char('o'), KeyModifiers::CONTROL)]),
+    },
+    Command {
+        name: "jump-forward",
+        arg: None,
+        description: "Jump forward to next position",
+        action: Action::Keys(&[(KeyCode::Char('i'), KeyModifiers::CONTROL)]),
+    },
+    Command {
+        name: "next-file",
+        arg: None,
+        description: "Next file given on the command line",
+        action: Action::Keys(&[
+            (KeyCode::Char(']'), KeyModifiers::NONE),
+            (KeyCode::Char('f'), KeyModifiers::NONE),
+        ]),
+    },
+    Command {
+        name: "previous-file",
+        arg: None,
+        description: "Previous file given on the command line",
+        action: Action::Keys(&[
+            (KeyCode::Char('['), KeyModifiers::NONE),
+            (KeyCode::Char('f'), KeyModifiers::NONE),
+        ]),
+    },
+    Command {
+        name: "source",
+        arg: None,
+        description: "Switch between the rendering and the markdown source",
+        action: Action::Keys(&[(KeyCode::Char('s'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "split",
+        arg: None,
+        description: "Show or hide the markdown source beside the rendering",
+        action: Action::Keys(&[(KeyCode::Char('S'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "whitespace",
+        arg: None,
+        description: "Show or hide tabs, non-breaking and trailing spaces",
+        action: Action::Keys(&[(KeyCode::Char('w'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "width",
+        arg: Some("COLUMNS"),
+        description: "Rewrap paragraphs to COLUMNS, or keep their lines with 0",
+        action: Action::Width,
+    },
+    Command {
+        name: "justify",
+        arg: None,
+        description: "Justify rewrapped paragraphs or stop",
+        action: Action::Justify,
+    },
+    Command {
+        name: "hyphenate",
+        arg: None,
+        description: "Break words at soft hyphens or stop",
+        action: Action::Hyphenate,
+    },
+    Command {
+        name: "numbering",
+        arg: None,
+        description: "Number the headings or stop",
+        action: Action::Numbering,
+    },
+    Command {
+        name: "smart",
+        arg: None,
+        description: "Use smart punctuation or stop",
+        action: Action::Smart,
+    },
+    Command {
+        name: "theme",
+        arg: None,
+        description: "Highlight code for a light or a dark background",
+        action: Action::Theme,
+    },
+    Command {
+        name: "serve",
+        arg: None,
+        description: "Serve this document and open it in the browser",
+        action: Action::Serve,
+    },
+    Command {
+        name: "help",
+        arg: None,
+        description: "Toggle this help",
+        action: Action::Keys(&[(KeyCode::Char('?'), KeyModifiers::NONE)]),
+    },
+    Command {
+        name: "quit",
+        arg: None,
+        description: "Quit",
+        action: Action::Keys(&[(KeyCode::Char('q'), KeyModifiers::NONE)]),
+    },
+];
+
+/// Split palette `input` into the command name typed and its argument.
+pub fn split(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    match input.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, arg.trim()),
+        None => (input, ""),
+    }
+}
+
+/// Indices into [`COMMANDS`] of the commands whose names match the name
+/// typed in `input`, best first; a name typed in full comes first.
+pub fn matches(input: &str) -> Vec<usize> {
+    let (name, _) = split(input);
+    let mut found = finder::rank(name, COMMANDS.iter().map(|c| c.name));
+    if let Some(pos) = found.iter().position(|&i| COMMANDS[i].name == name) {
+        let exact = found.remove(pos);
+        found.insert(0, exact);
+    }
+    found
+}
+
+/// The key presses a [`Action::Keys`] command replays.
+pub fn key_events(keys: &[(KeyCode, KeyModifiers)]) -> impl Iterator<Item = KeyEvent> + '_ {
+    keys.iter()
+        .map(|&(code, modifiers)| KeyEvent::new(code, modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(input: &str) -> Vec<&'static str> {
+        matches(input)
+            .into_iter()
+            .map(|i| COMMANDS[i].name)
+            .collect()
+    }
+
+    #[test]
+    fn the_argument_follows_the_name() {
+        assert_eq!(split("  width  72 "), ("width", "72"));
+        assert_eq!(split("open docs/my guide.md"), ("open", "docs/my guide.md"));
+        assert_eq!(split("theme"), ("theme", ""));
+        assert_eq!(split(""), ("", ""));
+    }
+
+    #[test]
+    fn names_match_fuzzily_and_in_full_first() {
+        assert_eq!(names("wid 80")[0], "width");
+        assert_eq!(names("nh")[0], "next-heading");
+        assert_eq!(names("links")[0], "links");
+        assert_eq!(names("").len(), COMMANDS.len());
+        assert!(names("zzz").is_empty());
+    }
+
+    #[test]
+    fn command_names_are_unique() {
+        for (i, command) in COMMANDS.iter().enumerate() {
+            assert!(
+                COMMANDS[i + 1..].iter().all(|c| c.name != command.name),
+                "{} is listed twice",
+                command.name
+            );
+        }
+    }
+}
@@ -4,9 +4,13 @@
 //! - A flat list of content blocks with their line ranges
 //! - A heading list with level, text, and line position
 //! - A collection of all links with text, URL, and position
+//! - Every code block (at any nesting depth) with its language and source
 
 use pulldown_cmark::{CodeBlockKind, Event, HeadingLevel, LinkType, Options, Parser, Tag, TagEnd};
 
+use crate::html::{AnchorIds, SlugStyle};
+use crate::normalize;
+
 // ---------------------------------------------------------------------------
 // Public types
 // ---------------------------------------------------------------------------
@@ -47,6 +51,18 @@ pub struct ContentBlock {
     pub content: String,
     /// Links whose text appears within `content`, with byte offsets.
     pub inline_links: Vec<InlineLink>,
+    /// Task list items (`- [ ]` / `- [x]`) within the block, in order.
+    pub tasks: Vec<TaskItem>,
+}
+
+/// A task list item's checkbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskItem {
+    /// Byte offset within `ContentBlock::content` where the item text starts.
+    pub offset: usize,
+    /// 1-based line number of the checkbox in the source.
+    pub line: usize,
+    pub checked: bool,
 }
 
 /// A heading extracted from the document.
@@ -58,6 +74,9 @@ pub struct Heading {
     pub text: String,
     /// 1-based line number where the heading appears.
     pub line: usize,
+    /// Anchor id, as the HTML renderer assigns it; see
+    /// [`ParsedDocument::set_slug_style`].
+    pub anchor: String,
 }
 
 /// The kind of a collected link.
@@ -84,6 +103,19 @@ pub struct Link {
     pub kind: LinkKind,
 }
 
+/// A fenced or indented code block, at any nesting depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeBlock {
+    /// First word of a fenced block's info string, if any.
+    pub language: Option<String>,
+    /// 1-based line of the opening fence (or first indented line).
+    pub line_start: usize,
+    /// 1-based ending line number (inclusive).
+    pub line_end: usize,
+    /// The code itself, without fences.
+    pub code: String,
+}
+
 /// The fully parsed representation of a markdown document.
 #[derive(Debug, Clone)]
 pub struct ParsedDocument {
@@ -92,6 +124,19 @@ pub struct ParsedDocument {
     pub headings: Vec<Heading>,
     #[allow(dead_code)]
     pub links: Vec<Link>,
+    pub code_blocks: Vec<CodeBlock>,
+}
+
+impl ParsedDocument {
+    /// Give the headings anchor ids in `style` instead of the default one.
+    /// Call it before anything rewrites heading text, such as
+    /// [`number_document`](crate::numbering::number_document).
+    pub fn set_slug_style(&mut self, style: SlugStyle) {
+        let mut ids = AnchorIds::new(style);
+        for heading in &mut self.headings {
+            heading.anchor = ids.next(&heading.text);
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -197,10 +242,25 @@ fn tag_to_block_kind(tag: &Tag) -> Option<BlockKind> {
 
 /// Parse a markdown source string into a [`ParsedDocument`].
 pub fn parse(source: &str) -> ParsedDocument {
+    parse_ext(source, Options::empty())
+}
+
+/// [`parse`] with smart punctuation (`--smart`): straight quotes become
+/// curly, `--` and `---` become en and em dashes, and `...` an ellipsis,
+/// as in HTML rendered with [`RenderOptions::smart_punctuation`].
+///
+/// [`RenderOptions::smart_punctuation`]: crate::html::RenderOptions::smart_punctuation
+pub fn parse_smart(source: &str) -> ParsedDocument {
+    parse_ext(source, Options::ENABLE_SMART_PUNCTUATION)
+}
+
+fn parse_ext(source: &str, extra: Options) -> ParsedDocument {
+    let source = normalize::source(source);
+    let source = source.as_ref();
     let line_index = LineIndex::new(source);
 
     let options =
-        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS | extra;
     let parser = Parser::new_ext(source, options);
 
     let mut blocks: Vec<ContentBlock> = Vec::new();
@@ -216,6 +276,7 @@ pub fn parse(source: &str) -> ParsedDocument {
     let mut in_heading: Option<u8> = None;
     let mut heading_line: usize = 0;
     let mut heading_text_buf = String::new();
+    let mut anchor_ids = AnchorIds::new(SlugStyle::default());
 
     // Link tracking
     let mut in_link: Option<(String, LinkKind)> = None; // (url, kind)
@@ -225,6 +286,11 @@ pub fn parse(source: &str) -> ParsedDocument {
     // Inline link tracking (byte offsets within current block's text_buf)
     let mut link_content_start: usize = 0;
     let mut block_inline_links: Vec<InlineLink> = Vec::new();
+    let mut block_tasks: Vec<TaskItem> = Vec::new();
+
+    // Code block tracking: (language, start_offset, code)
+    let mut code_blocks: Vec<CodeBlock> = Vec::new();
+    let mut in_code: Option<(Option<String>, usize, String)> = None;
 
     for (event, range) in parser.into_offset_iter() {
         match &event {
@@ -248,6 +314,14 @@ pub fn parse(source: &str) -> ParsedDocument {
                     block_depth += 1;
                 }
 
+                if let Tag::CodeBlock(_) = tag {
+                    let language = match tag_to_block_kind(tag) {
+                        Some(BlockKind::CodeBlock(lang)) => lang,
+                        _ => None,
+                    };
+                    in_code = Some((language, range.start, String::new()));
+                }
+
                 // Heading tracking
                 if let Tag::Heading { level, .. } = tag {
                     in_heading = Some(heading_level_to_u8(level));
@@ -295,12 +369,25 @@ pub fn parse(source: &str) -> ParsedDocument {
                                 line_end: end_line,
                                 content: text_buf.clone(),
                                 inline_links: std::mem::take(&mut block_inline_links),
+                                tasks: std::mem::take(&mut block_tasks),
                             });
                         }
                         text_buf.clear();
                     }
                 }
 
+                if let TagEnd::CodeBlock = tag_end {
+                    if let Some((language, start_offset, code)) = in_code.take() {
+                        code_blocks.push(CodeBlock {
+                            language,
+                            line_start: line_index.line_at(start_offset),
+                            line_end: line_index
+                                .line_at(range.end.saturating_sub(1).max(start_offset)),
+                            code,
+                        });
+                    }
+                }
+
                 // Finalize heading
                 if let TagEnd::Heading(_) = tag_end {
                     if let Some(level) = in_heading.take() {
@@ -308,6 +395,7 @@ pub fn parse(source: &str) -> ParsedDocument {
                             level,
                             text: heading_text_buf.clone(),
                             line: heading_line,
+                            anchor: anchor_ids.next(&heading_text_buf),
                         });
                         heading_text_buf.clear();
                     }
@@ -337,6 +425,9 @@ pub fn parse(source: &str) -> ParsedDocument {
 
             Event::Text(text) => {
                 text_buf.push_str(text);
+                if let Some((_, _, code)) = in_code.as_mut() {
+                    code.push_str(text);
+                }
                 if in_heading.is_some() {
                     heading_text_buf.push_str(text);
                 }
@@ -373,6 +464,7 @@ pub fn parse(source: &str) -> ParsedDocument {
                         line_end: line_index.line_at(range.end.saturating_sub(1).max(range.start)),
                         content: html.to_string(),
                         inline_links: Vec::new(),
+                        tasks: Vec::new(),
                     });
                 } else {
                     text_buf.push_str(html);
@@ -383,6 +475,14 @@ pub fn parse(source: &str) -> ParsedDocument {
                 text_buf.push_str(html);
             }
 
+            Event::TaskListMarker(checked) if block_depth > 0 => {
+                block_tasks.push(TaskItem {
+                    offset: text_buf.len(),
+                    line: line_index.line_at(range.start),
+                    checked: *checked,
+                });
+            }
+
             Event::Rule => {
                 let line = line_index.line_at(range.start);
                 blocks.push(ContentBlock {
@@ -391,6 +491,7 @@ pub fn parse(source: &str) -> ParsedDocument {
                     line_end: line,
                     content: String::new(),
                     inline_links: Vec::new(),
+                    tasks: Vec::new(),
                 });
             }
 
@@ -402,9 +503,33 @@ pub fn parse(source: &str) -> ParsedDocument {
         blocks,
         headings,
         links,
+        code_blocks,
     }
 }
 
+/// The section of `source` under the last heading at or before 1-based
+/// `line`: that heading and the markdown after it, subsections included,
+/// up to the next heading of the same or a higher level.  Trailing blank
+/// lines are left off.  `None` when no heading comes at or before `line`.
+pub fn section_source(source: &str, line: usize) -> Option<(Heading, &str)> {
+    let headings = parse(source).headings;
+    let i = headings.iter().rposition(|h| h.line <= line)?;
+    let heading = &headings[i];
+    let end = headings[i + 1..]
+        .iter()
+        .find(|h| h.level <= heading.level)
+        .map(|h| h.line);
+    // Byte offset of each line's start, by 1-based line number.
+    let line_start = |n: usize| {
+        let skipped = source.split_inclusive('\n').take(n - 1);
+        skipped.map(str::len).sum::<usize>()
+    };
+    let start = line_start(heading.line);
+    let end = end.map_or(source.len(), line_start);
+    let text = source[start..end].trim_start_matches('\u{feff}').trim_end();
+    Some((heading.clone(), text))
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -517,6 +642,23 @@ mod tests {
         assert_eq!(code[0].kind, BlockKind::CodeBlock(Some("rust".to_string())));
     }
 
+    #[test]
+    fn code_blocks_collected_at_any_depth() {
+        let src = "```sh\necho hi\n```\n\n- item\n\n  ```rust\n  let x = 1;\n  ```\n";
+        let doc = parse(src);
+
+        assert_eq!(doc.code_blocks.len(), 2);
+        assert_eq!(doc.code_blocks[0].language.as_deref(), Some("sh"));
+        assert_eq!(doc.code_blocks[0].code, "echo hi\n");
+        assert_eq!(
+            (doc.code_blocks[0].line_start, doc.code_blocks[0].line_end),
+            (1, 3)
+        );
+        assert_eq!(doc.code_blocks[1].language.as_deref(), Some("rust"));
+        assert_eq!(doc.code_blocks[1].code, "let x = 1;\n");
+        assert_eq!(doc.code_blocks[1].line_start, 7);
+    }
+
     #[test]
     fn unordered_list() {
         let src = "- alpha\n- beta\n- gamma\n";
@@ -585,6 +727,20 @@ mod tests {
         assert_eq!(doc.links[0].kind, LinkKind::Image);
     }
 
+    #[test]
+    fn task_items_record_offset_line_and_state() {
+        let src = "# Todo\n\n- [ ] milk\n- plain\n- [x] eggs\n";
+        let doc = parse(src);
+
+        let list = &doc.blocks[1];
+        assert_eq!(list.kind, BlockKind::List);
+        assert_eq!(list.tasks.len(), 2);
+        assert_eq!(&list.content[list.tasks[0].offset..], "milk\nplain\neggs");
+        assert_eq!((list.tasks[0].line, list.tasks[0].checked), (3, false));
+        assert_eq!(&list.content[list.tasks[1].offset..], "eggs");
+        assert_eq!((list.tasks[1].line, list.tasks[1].checked), (5, true));
+    }
+
     #[test]
     fn mixed_document() {
         let src = "\
@@ -652,4 +808,30 @@ mdmd README.md
         assert!(doc.blocks[0].content.contains("line three"));
         assert_eq!(doc.blocks[0].line_start, 1);
     }
+
+    #[test]
+    fn bom_and_crlf_match_unix_line_numbers() {
+        let unix = parse("# Title\n\nBody\n\n## Section\n");
+        let windows = parse("\u{feff}# Title\r\n\r\nBody\r\n\r\n## Section\r\n");
+        let lines = |doc: &ParsedDocument| -> Vec<(String, usize)> {
+            doc.headings.iter().map(|h| (h.text.clone(), h.line)).collect()
+        };
+        assert_eq!(lines(&windows), lines(&unix));
+        assert_eq!(windows.blocks.len(), unix.blocks.len());
+        assert!(!windows.blocks[1].content.contains('\r'));
+    }
+
+    #[test]
+    fn section_runs_to_the_next_heading_at_its_level() {
+        let src = "# Title\n\nIntro\n\n## Install\n\nRun it.\n\n```sh\n# not a heading\n```\n\n### From source\n\nBuild it.\n\n## Usage\n\nUse it.\n";
+        let (heading, text) = section_source(src, 7).unwrap();
+        assert_eq!(heading.text, "Install");
+        assert_eq!(
+            text,
+            "## Install\n\nRun it.\n\n```sh\n# not a heading\n```\n\n### From source\n\nBuild it."
+        );
+        assert_eq!(section_source(src, 19).unwrap().1, "## Usage\n\nUse it.");
+        assert_eq!(section_source(src, 1).unwrap().1, src.trim_end());
+        assert!(section_source("Preamble\n\n# Title\n", 1).is_none());
+    }
 }
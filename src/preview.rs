@@ -0,0 +1,194 @@
+//! `mdmd preview <file>`: open the viewer beside the editor.
+//!
+//! Inside tmux the viewer starts in a new pane to the right of the current
+//! one; inside kitty, in a new window split off to the right (kitty's
+//! `splits` layout, with `allow_remote_control` enabled).  It runs
+//! `mdmd view --watch`, so every save in the editor shows in the preview,
+//! and focus stays where it was.
+
+use std::io;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// A terminal multiplexer `preview` knows how to split.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Kitty,
+}
+
+impl Multiplexer {
+    /// The multiplexer the terminal is running in, from the variables each
+    /// sets for its children.  tmux wins when it runs inside kitty, since
+    /// its panes are what the editor sits in.
+    pub fn detect(get: impl Fn(&str) -> Option<String>) -> Option<Multiplexer> {
+        let set = |var| get(var).is_some_and(|v| !v.is_empty());
+        if set("TMUX") {
+            Some(Multiplexer::Tmux)
+        } else if set("KITTY_WINDOW_ID") {
+            Some(Multiplexer::Kitty)
+        } else {
+            None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Kitty => "kitty",
+        }
+    }
+
+    /// The command that runs `viewer` (program and arguments) in a split to
+    /// the right of the current pane, leaving it focused.
+    pub fn split_command(self, viewer: &[String], cwd: &Path) -> Vec<String> {
+        let cwd = cwd.to_string_lossy().into_owned();
+        match self {
+            // split-window runs its command through the shell.
+            Multiplexer::Tmux => {
+                let shell_command: Vec<String> = viewer.iter().map(|a| shell_quote(a)).collect();
+                let mut argv: Vec<String> = ["tmux", "split-window", "-h", "-d", "-c"]
+                    .map(str::to_owned)
+                    .to_vec();
+                argv.push(cwd);
+                argv.push(shell_command.join(" "));
+                argv
+            }
+            Multiplexer::Kitty => {
+                let mut argv: Vec<String> = [
+                    "kitty",
+                    "@",
+                    "launch",
+                    "--type=window",
+                    "--location=vsplit",
+                    "--keep-focus",
+                ]
+                .map(str::to_owned)
+                .to_vec();
+                argv.push(format!("--cwd={cwd}"));
+                argv.extend(viewer.iter().cloned());
+                argv
+            }
+        }
+    }
+}
+
+/// Quote `arg` for a POSIX shell.
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=:@%+,".contains(c));
+    if plain {
+        arg.to_owned()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+/// Open `file` in `mdmd view --watch` beside the current pane.
+pub fn run_preview(file: &str) -> io::Result<()> {
+    let Some(mux) = Multiplexer::detect(|var| std::env::var(var).ok()) else {
+        eprintln!("Error: mdmd preview needs tmux or kitty; run `mdmd view --watch FILE` instead");
+        std::process::exit(1);
+    };
+    let path = std::fs::canonicalize(file).unwrap_or_else(|e| {
+        eprintln!("Error: cannot preview '{file}': {e}");
+        std::process::exit(1);
+    });
+    let exe = std::env::current_exe()?;
+    let viewer = [
+        exe.to_string_lossy().into_owned(),
+        "view".to_owned(),
+        "--watch".to_owned(),
+        path.to_string_lossy().into_owned(),
+    ];
+    let cwd = std::env::current_dir()?;
+    let argv = mux.split_command(&viewer, &cwd);
+    let output = Command::new(&argv[0])
+        .args(&argv[1..])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap_or_else(|e| {
+            eprintln!("Error: cannot run {}: {e}", argv[0]);
+            std::process::exit(1);
+        });
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        eprintln!(
+            "Error: {} could not open a split: {}",
+            mux.name(),
+            stderr.trim()
+        );
+        if mux == Multiplexer::Kitty {
+            eprintln!("kitty needs `allow_remote_control yes` in kitty.conf for mdmd preview.");
+        }
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(pairs: &'static [(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        move |var| {
+            pairs
+                .iter()
+                .find(|(k, _)| *k == var)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn detects_tmux_before_kitty() {
+        assert_eq!(
+            Multiplexer::detect(env(&[("TMUX", "/tmp/tmux-1000/default,1,0")])),
+            Some(Multiplexer::Tmux)
+        );
+        assert_eq!(
+            Multiplexer::detect(env(&[("KITTY_WINDOW_ID", "1")])),
+            Some(Multiplexer::Kitty)
+        );
+        assert_eq!(
+            Multiplexer::detect(env(&[("KITTY_WINDOW_ID", "1"), ("TMUX", "x")])),
+            Some(Multiplexer::Tmux)
+        );
+        assert_eq!(Multiplexer::detect(env(&[("TMUX", "")])), None);
+    }
+
+    #[test]
+    fn split_commands_run_the_viewer_to_the_right() {
+        let viewer = ["/bin/mdmd", "view", "--watch", "/notes/it's.md"].map(str::to_owned);
+        let cwd = Path::new("/notes");
+        assert_eq!(
+            Multiplexer::Tmux.split_command(&viewer, cwd),
+            [
+                "tmux",
+                "split-window",
+                "-h",
+                "-d",
+                "-c",
+                "/notes",
+                r"/bin/mdmd view --watch '/notes/it'\''s.md'"
+            ]
+        );
+        assert_eq!(
+            Multiplexer::Kitty.split_command(&viewer, cwd),
+            [
+                "kitty",
+                "@",
+                "launch",
+                "--type=window",
+                "--location=vsplit",
+                "--keep-focus",
+                "--cwd=/notes",
+                "/bin/mdmd",
+                "view",
+                "--watch",
+                "/notes/it's.md"
+            ]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! The files given together to `mdmd view a.md b.md c.md`.
+//!
+//! The viewer opens the first and keeps the rest in a queue that `]f` and
+//! `[f` step through.  Files are queued in natural order, so `mdmd view
+//! *.md` reads `ch2.md` before `ch10.md` whatever order the shell expanded
+//! the glob in.
+
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+
+/// Compare names the way people count: runs of digits by their value,
+/// everything else case-insensitively, with the raw text breaking ties.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut x, mut y) = (a, b);
+    loop {
+        match (x.chars().next(), y.chars().next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(c), Some(d)) if c.is_ascii_digit() && d.is_ascii_digit() => {
+                let (m, rest_x) = split_digits(x);
+                let (n, rest_y) = split_digits(y);
+                let (m, n) = (m.trim_start_matches('0'), n.trim_start_matches('0'));
+                let order = m.len().cmp(&n.len()).then_with(|| m.cmp(n));
+                if order != Ordering::Equal {
+                    return order;
+                }
+                (x, y) = (rest_x, rest_y);
+            }
+            (Some(c), Some(d)) => {
+                let order = c.to_lowercase().cmp(d.to_lowercase());
+                if order != Ordering::Equal {
+                    return order;
+                }
+                (x, y) = (&x[c.len_utf8()..], &y[d.len_utf8()..]);
+            }
+        }
+    }
+}
+
+fn split_digits(s: &str) -> (&str, &str) {
+    s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()))
+}
+
+/// Queued files and which of them was shown last.
+#[derive(Debug, Default)]
+pub struct Queue {
+    files: Vec<PathBuf>,
+    index: usize,
+}
+
+impl Queue {
+    pub fn new(files: Vec<PathBuf>) -> Self {
+        Self { files, index: 0 }
+    }
+
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// The 1-based position of the file shown last.
+    pub fn position(&self) -> usize {
+        self.index + 1
+    }
+
+    /// Note that `current` is being shown.  Files reached by links leave
+    /// the position where it was, so `]f` carries on from the last queued
+    /// file read.
+    pub fn show(&mut self, current: &Path) {
+        if let Some(i) = self.files.iter().position(|f| f == current) {
+            self.index = i;
+        }
+    }
+
+    /// The file after the one shown last; `None` at the end of the queue.
+    pub fn next(&self) -> Option<&Path> {
+        self.files.get(self.index + 1).map(PathBuf::as_path)
+    }
+
+    /// The file before the one shown last; `None` at the start.
+    pub fn previous(&self) -> Option<&Path> {
+        let i = self.index.checked_sub(1)?;
+        self.files.get(i).map(PathBuf::as_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_sort_by_value() {
+        let mut names = vec![
+            "ch10.md", "ch2.md", "Intro.md", "ch1.md", "ch02.md", "intro.md",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            ["ch1.md", "ch02.md", "ch2.md", "ch10.md", "Intro.md", "intro.md"]
+        );
+    }
+
+    #[test]
+    fn links_away_keep_the_queue_position() {
+        let mut queue = Queue::new(["/a.md", "/b.md", "/c.md"].map(PathBuf::from).to_vec());
+        assert_eq!(queue.previous(), None);
+        assert_eq!(queue.next(), Some(Path::new("/b.md")));
+        queue.show(Path::new("/b.md"));
+        queue.show(Path::new("/elsewhere.md"));
+        assert_eq!(queue.position(), 2);
+        assert_eq!(queue.next(), Some(Path::new("/c.md")));
+        assert_eq!(queue.previous(), Some(Path::new("/a.md")));
+        queue.show(Path::new("/c.md"));
+        assert_eq!(queue.next(), None);
+    }
+}
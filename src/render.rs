@@ -3,9 +3,11 @@
 //! Converts a [`ParsedDocument`] into styled ratatui [`Text`] for display
 //! in the terminal viewport.
 
+use std::collections::VecDeque;
 use std::sync::OnceLock;
 
 use ratatui::{
+    layout::Alignment,
     style::{Color, Modifier, Style},
     text::{Line, Span, Text},
 };
@@ -13,20 +15,33 @@ use syntect::{
     highlighting::{Theme, ThemeSet},
     parsing::SyntaxSet,
 };
+use unicode_width::UnicodeWidthStr;
 
-use crate::parse::{BlockKind, ContentBlock, InlineLink, ParsedDocument};
+use crate::bidi;
+use crate::parse::{BlockKind, ContentBlock, Heading, InlineLink, ParsedDocument, TaskItem};
+use crate::toc;
 
 fn syntax_set() -> &'static SyntaxSet {
     static SS: OnceLock<SyntaxSet> = OnceLock::new();
     SS.get_or_init(SyntaxSet::load_defaults_newlines)
 }
 
-fn theme() -> &'static Theme {
-    static TH: OnceLock<Theme> = OnceLock::new();
-    TH.get_or_init(|| {
+/// The highlighting theme for code on a dark or, with `light`, a light
+/// terminal background.
+fn theme(light: bool) -> &'static Theme {
+    static TH: OnceLock<[Theme; 2]> = OnceLock::new();
+    let [dark, light_theme] = TH.get_or_init(|| {
         let ts = ThemeSet::load_defaults();
-        ts.themes["base16-eighties.dark"].clone()
-    })
+        [
+            ts.themes["base16-eighties.dark"].clone(),
+            ts.themes["InspiredGitHub"].clone(),
+        ]
+    });
+    if light {
+        light_theme
+    } else {
+        dark
+    }
 }
 
 fn syntect_to_ratatui_color(c: syntect::highlighting::Color) -> Color {
@@ -42,6 +57,9 @@ pub struct HeadingPosition {
     pub level: u8,
     /// Text content of the heading.
     pub text: String,
+    /// Anchor id that `#fragment` links to the heading use, as on the
+    /// rendered page: repeated headings get `-1`, `-2`, ... suffixes.
+    pub anchor: String,
 }
 
 /// A link's position in the rendered output, for Tab navigation and focus highlighting.
@@ -49,9 +67,10 @@ pub struct HeadingPosition {
 pub struct LinkPosition {
     /// 0-based line index in the rendered output.
     pub rendered_line: usize,
-    /// 0-based column where the link text starts.
+    /// 0-based display column where the link text starts.  Wide (CJK,
+    /// emoji) characters count as two columns, like terminal cells.
     pub column_start: usize,
-    /// 0-based column where the link text ends (exclusive).
+    /// 0-based display column where the link text ends (exclusive).
     pub column_end: usize,
     /// Destination URL.
     pub url: String,
@@ -60,6 +79,26 @@ pub struct LinkPosition {
     pub text: String,
 }
 
+/// A task list checkbox's position in the rendered output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskPosition {
+    /// 0-based line index in the rendered output.
+    pub rendered_line: usize,
+    /// 1-based line number of the checkbox in the source.
+    pub source_line: usize,
+    pub checked: bool,
+}
+
+/// A block's position in the rendered output, for mapping source lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockPosition {
+    /// 0-based line index where the block starts in the rendered output.
+    pub rendered_line: usize,
+    /// 1-based source lines the block spans (inclusive).
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
 /// The result of rendering a parsed document.
 pub struct RenderedDocument {
     /// Styled text ready for display.
@@ -68,43 +107,215 @@ pub struct RenderedDocument {
     pub heading_lines: Vec<HeadingPosition>,
     /// Positions of all links in the rendered output.
     pub link_positions: Vec<LinkPosition>,
+    /// Positions of all task list checkboxes in the rendered output.
+    pub task_lines: Vec<TaskPosition>,
+    /// Positions of all blocks in the rendered output, in source order.
+    pub block_lines: Vec<BlockPosition>,
 }
 
+impl RenderedDocument {
+    /// The rendered line showing 1-based source `line`: as far into its
+    /// block as `line` is into the block's source, up to the block's last
+    /// rendered line.  A line between blocks maps to the start of the next.
+    pub fn rendered_line_for_source(&self, line: usize) -> usize {
+        let Some(i) = self.block_lines.iter().rposition(|b| b.line_start <= line) else {
+            return 0;
+        };
+        let block = &self.block_lines[i];
+        // The blank line separating blocks is not part of either.
+        let last = self
+            .block_lines
+            .get(i + 1)
+            .map_or(self.text.lines.len(), |next| next.rendered_line - 1)
+            .saturating_sub(1)
+            .max(block.rendered_line);
+        if line > block.line_end {
+            return self.block_lines.get(i + 1).map_or(last, |next| next.rendered_line);
+        }
+        (block.rendered_line + (line - block.line_start)).min(last)
+    }
+
+    /// The 1-based source line shown on rendered `line`, the inverse of
+    /// [`rendered_line_for_source`](Self::rendered_line_for_source).  Lines
+    /// a block renders beyond its source lines, and the blank line after
+    /// it, map to its last source line.
+    pub fn source_line_for_rendered(&self, line: usize) -> usize {
+        let blocks = &self.block_lines;
+        let Some(block) = blocks.iter().rfind(|b| b.rendered_line <= line) else {
+            return blocks.first().map_or(1, |b| b.line_start);
+        };
+        (block.line_start + (line - block.rendered_line)).min(block.line_end.max(block.line_start))
+    }
+}
+
+/// How paragraphs are laid out (`--text-width`, `--justify`, `--hyphenate`)
+/// and whether invisible whitespace is shown.  The default keeps each
+/// paragraph's lines as written in the source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Layout {
+    /// Columns paragraphs are rewrapped to; `None` keeps their source lines.
+    pub text_width: Option<usize>,
+    /// Pad the spaces of each wrapped line but a paragraph's last so the
+    /// line fills `text_width`.
+    pub justify: bool,
+    /// Break a word that does not fit at one of its soft hyphens (U+00AD),
+    /// shown as `-` at the end of the line.
+    pub hyphenate: bool,
+    /// Mark tabs, non-breaking spaces, and the spaces ending code lines with
+    /// dim `→`, `␣`, and `·` (the TUI's `w`).
+    pub show_whitespace: bool,
+    /// Highlight code for a light terminal background rather than a dark
+    /// one (the TUI's `:theme`).
+    pub light_theme: bool,
+}
+
+/// Markers drawn for whitespace when [`Layout::show_whitespace`] is set.
+const TAB_MARKER: &str = "\u{2192}";
+const NBSP_MARKER: &str = "\u{2423}";
+const TRAILING_SPACE_MARKER: &str = "\u{b7}";
+
 /// Convert a parsed markdown document into styled [`Text`] ready for rendering,
 /// along with heading positions in the rendered output.
 ///
 /// The caller is responsible for clipping to the viewport height.
 pub fn render_document(doc: &ParsedDocument) -> RenderedDocument {
+    render_document_with(doc, &Layout::default())
+}
+
+/// [`render_document`] with paragraphs laid out as `layout` says.
+pub fn render_document_with(doc: &ParsedDocument, layout: &Layout) -> RenderedDocument {
     let mut lines: Vec<Line<'static>> = Vec::new();
     let mut heading_lines: Vec<HeadingPosition> = Vec::new();
     let mut link_positions: Vec<LinkPosition> = Vec::new();
+    let mut task_lines: Vec<TaskPosition> = Vec::new();
+    let mut block_lines: Vec<BlockPosition> = Vec::new();
 
     for (i, block) in doc.blocks.iter().enumerate() {
         if i > 0 {
             // Blank line between blocks
             lines.push(Line::default());
         }
+        block_lines.push(BlockPosition {
+            rendered_line: lines.len(),
+            line_start: block.line_start,
+            line_end: block.line_end,
+        });
         if let BlockKind::Heading(level) = &block.kind {
             heading_lines.push(HeadingPosition {
                 rendered_line: lines.len(),
                 level: *level,
                 text: block.content.clone(),
+                anchor: doc
+                    .headings
+                    .iter()
+                    .find(|h| h.line == block.line_start)
+                    .map(|h| h.anchor.clone())
+                    .unwrap_or_default(),
             });
         }
-        render_block(block, &mut lines, &mut link_positions);
+        let toc_depth = matches!(block.kind, BlockKind::Paragraph | BlockKind::HtmlBlock)
+            .then(|| toc::marker_depth(&block.content))
+            .flatten();
+        match toc_depth {
+            Some(depth) => render_inline_toc(&doc.headings, depth, &mut lines),
+            None => {
+                let first = lines.len();
+                render_block(block, layout, &mut lines, &mut link_positions, &mut task_lines);
+                if layout.show_whitespace {
+                    mark_whitespace(&mut lines[first..], first, &mut link_positions);
+                }
+                if !matches!(
+                    block.kind,
+                    BlockKind::CodeBlock(_) | BlockKind::Table | BlockKind::ThematicBreak
+                ) {
+                    align_rtl_lines(&mut lines[first..]);
+                }
+            }
+        }
     }
 
     RenderedDocument {
         text: Text::from(lines),
         heading_lines,
         link_positions,
+        task_lines,
+        block_lines,
+    }
+}
+
+/// Replace the tabs and non-breaking spaces in `lines`, which start at
+/// rendered line `first`, with dim markers, moving the links after a marker
+/// wider than what it replaces.
+fn mark_whitespace(lines: &mut [Line<'static>], first: usize, links: &mut [LinkPosition]) {
+    for (i, line) in lines.iter_mut().enumerate() {
+        let invisible = |s: &Span| s.content.contains(['\t', '\u{a0}']);
+        if !line.spans.iter().any(invisible) {
+            continue;
+        }
+        let mut spans = Vec::with_capacity(line.spans.len());
+        let mut column = 0;
+        for span in std::mem::take(&mut line.spans) {
+            let mut plain = String::new();
+            for c in span.content.chars() {
+                let marker = match c {
+                    '\t' => TAB_MARKER,
+                    '\u{a0}' => NBSP_MARKER,
+                    _ => {
+                        plain.push(c);
+                        continue;
+                    }
+                };
+                column += plain.width();
+                if !plain.is_empty() {
+                    spans.push(Span::styled(std::mem::take(&mut plain), span.style));
+                }
+                let grown = marker.width().saturating_sub(c.to_string().width());
+                for link in links.iter_mut().filter(|l| l.rendered_line == first + i) {
+                    if link.column_start > column {
+                        link.column_start += grown;
+                    }
+                    if link.column_end > column {
+                        link.column_end += grown;
+                    }
+                }
+                column += marker.width();
+                spans.push(Span::styled(marker, span.style.fg(Color::DarkGray)));
+            }
+            if !plain.is_empty() {
+                column += plain.width();
+                spans.push(Span::styled(plain, span.style));
+            }
+        }
+        line.spans = spans;
+    }
+}
+
+/// Right-align the lines whose text is right-to-left (see [`bidi::is_rtl`]).
+fn align_rtl_lines(lines: &mut [Line<'static>]) {
+    for line in lines {
+        let text: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        if bidi::is_rtl(&text) {
+            line.alignment = Some(Alignment::Right);
+        }
+    }
+}
+
+/// Columns `line` is shifted right by when drawn in a viewport `width`
+/// cells wide: the space left of a right-aligned line, else 0.  Add it to
+/// [`LinkPosition`] columns to find the link on screen.
+pub fn alignment_offset(line: &Line, width: usize) -> usize {
+    match line.alignment {
+        Some(Alignment::Right) => width.saturating_sub(line.width()),
+        _ => 0,
     }
 }
 
 fn render_block(
     block: &ContentBlock,
+    layout: &Layout,
     lines: &mut Vec<Line<'static>>,
     link_positions: &mut Vec<LinkPosition>,
+    task_lines: &mut Vec<TaskPosition>,
 ) {
     match &block.kind {
         BlockKind::Heading(level) => render_heading(
@@ -114,18 +325,35 @@ fn render_block(
             lines,
             link_positions,
         ),
-        BlockKind::Paragraph => {
-            render_paragraph(&block.content, &block.inline_links, lines, link_positions)
+        BlockKind::Paragraph => render_paragraph(
+            &block.content,
+            &block.inline_links,
+            layout,
+            lines,
+            link_positions,
+        ),
+        BlockKind::CodeBlock(ref lang) => {
+            render_code_block(&block.content, lang.as_deref(), layout, lines)
         }
-        BlockKind::CodeBlock(ref lang) => render_code_block(&block.content, lang.as_deref(), lines),
-        BlockKind::List => render_list(&block.content, &block.inline_links, lines, link_positions),
+        BlockKind::List => render_list(
+            &block.content,
+            &block.inline_links,
+            &block.tasks,
+            lines,
+            link_positions,
+            task_lines,
+        ),
         BlockKind::BlockQuote => {
             render_block_quote(&block.content, &block.inline_links, lines, link_positions)
         }
         BlockKind::ThematicBreak => render_thematic_break(lines),
-        BlockKind::HtmlBlock => {
-            render_paragraph(&block.content, &block.inline_links, lines, link_positions)
-        }
+        BlockKind::HtmlBlock => render_paragraph(
+            &block.content,
+            &block.inline_links,
+            &Layout::default(),
+            lines,
+            link_positions,
+        ),
         BlockKind::Table => render_table(&block.content, lines),
     }
 }
@@ -208,11 +436,11 @@ fn split_line_at_links(
         spans.push(Span::styled(link_text.to_owned(), ls));
 
         // Record position
-        let col_start = column_offset + link_slice_start;
+        let col_start = column_offset + line_text[..link_slice_start].width();
         link_positions.push(LinkPosition {
             rendered_line: rendered_line_idx,
             column_start: col_start,
-            column_end: col_start + link_text.len(),
+            column_end: col_start + link_text.width(),
             url: link.url.clone(),
             text: link_text.to_owned(),
         });
@@ -238,7 +466,7 @@ fn render_heading(
 ) {
     let style = heading_style(level);
     let prefix = heading_prefix(level);
-    let prefix_width = prefix.len();
+    let prefix_width = prefix.width();
 
     let mut content_offset = 0;
     for text_line in content.lines() {
@@ -261,10 +489,55 @@ fn render_heading(
 fn render_paragraph(
     content: &str,
     inline_links: &[InlineLink],
+    layout: &Layout,
     lines: &mut Vec<Line<'static>>,
     link_positions: &mut Vec<LinkPosition>,
 ) {
     let base_style = Style::default();
+    if let Some(width) = layout.text_width {
+        // Source line breaks become spaces, keeping the links' byte offsets.
+        let text = content.replace('\n', " ");
+        let wrapped = wrap(&text, width, layout.hyphenate);
+        for (n, line) in wrapped.iter().enumerate() {
+            let line_text = &text[line.start..line.end];
+            let first_link = link_positions.len();
+            let spans = split_line_at_links(
+                line_text,
+                line.start,
+                inline_links,
+                base_style,
+                0,
+                lines.len(),
+                link_positions,
+            );
+            let gaps = gap_starts(line_text);
+            let shown = visible_width(line_text) + usize::from(line.hyphen);
+            let extra = if layout.justify && n + 1 < wrapped.len() && !gaps.is_empty() {
+                width.saturating_sub(shown)
+            } else {
+                0
+            };
+            let (spans, columns) = rewrite_spans(spans, |i, c, out| {
+                if c == SOFT_HYPHEN {
+                    if line.hyphen && i + c.len_utf8() == line_text.len() {
+                        out.push('-');
+                    }
+                    return;
+                }
+                out.push(c);
+                if let Some(g) = gaps.iter().position(|&g| g == i) {
+                    let pad = extra / gaps.len() + usize::from(g < extra % gaps.len());
+                    out.push_str(&" ".repeat(pad));
+                }
+            });
+            for link in &mut link_positions[first_link..] {
+                link.column_start = columns[link.column_start];
+                link.column_end = columns[link.column_end];
+            }
+            lines.push(Line::from(spans));
+        }
+        return;
+    }
     let mut content_offset = 0;
     for text_line in content.lines() {
         let spans = split_line_at_links(
@@ -281,9 +554,173 @@ fn render_paragraph(
     }
 }
 
-fn render_code_block(content: &str, lang: Option<&str>, lines: &mut Vec<Line<'static>>) {
+const SOFT_HYPHEN: char = '\u{ad}';
+
+/// Display width of `text` with its soft hyphens hidden.
+fn visible_width(text: &str) -> usize {
+    text.split(SOFT_HYPHEN).map(UnicodeWidthStr::width).sum()
+}
+
+/// One line of a wrapped paragraph.
+struct WrappedLine {
+    /// Byte range of the line in the paragraph text.
+    start: usize,
+    end: usize,
+    /// Whether the line ends at a soft hyphen, shown as `-`.
+    hyphen: bool,
+}
+
+/// Byte ranges of the whitespace-separated words of `text`.
+fn word_ranges(text: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in text.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                words.push((s, i));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(s) = start {
+        words.push((s, text.len()));
+    }
+    words
+}
+
+/// Byte offsets in `line` where each run of whitespace between words starts.
+fn gap_starts(line: &str) -> Vec<usize> {
+    let mut gaps = Vec::new();
+    let mut prev_space = true;
+    for (i, c) in line.char_indices() {
+        let space = c.is_whitespace();
+        if space && !prev_space {
+            gaps.push(i);
+        }
+        prev_space = space;
+    }
+    gaps
+}
+
+/// Where to break the word `text[word_start..word_end]` so the line from
+/// `line_start` fits `width` columns with a trailing `-`: just past its
+/// last soft hyphen that fits, if any.
+fn hyphen_break(
+    text: &str,
+    line_start: usize,
+    word_start: usize,
+    word_end: usize,
+    width: usize,
+) -> Option<usize> {
+    text[word_start..word_end]
+        .rmatch_indices(SOFT_HYPHEN)
+        .map(|(i, _)| word_start + i)
+        .find(|&h| h > word_start && visible_width(&text[line_start..h]) < width)
+        .map(|h| h + SOFT_HYPHEN.len_utf8())
+}
+
+/// Greedily wrap `text`, which has no line breaks, to `width` columns
+/// between words.  A word longer than a line gets a line of its own.
+fn wrap(text: &str, width: usize, hyphenate: bool) -> Vec<WrappedLine> {
+    let mut lines = Vec::new();
+    let mut words: VecDeque<(usize, usize)> = word_ranges(text).into();
+    let mut current: Option<(usize, usize)> = None;
+    while let Some((word_start, word_end)) = words.pop_front() {
+        let start = current.map_or(word_start, |(s, _)| s);
+        if visible_width(&text[start..word_end]) <= width {
+            current = Some((start, word_end));
+            continue;
+        }
+        let cut = hyphenate
+            .then(|| hyphen_break(text, start, word_start, word_end, width))
+            .flatten();
+        if let Some(cut) = cut {
+            lines.push(WrappedLine {
+                start,
+                end: cut,
+                hyphen: true,
+            });
+            current = None;
+            if cut < word_end {
+                words.push_front((cut, word_end));
+            }
+            continue;
+        }
+        match current {
+            Some((s, e)) => {
+                lines.push(WrappedLine {
+                    start: s,
+                    end: e,
+                    hyphen: false,
+                });
+                current = None;
+                words.push_front((word_start, word_end));
+            }
+            None => current = Some((word_start, word_end)),
+        }
+    }
+    if let Some((start, end)) = current {
+        lines.push(WrappedLine {
+            start,
+            end,
+            hyphen: false,
+        });
+    }
+    lines
+}
+
+/// Rewrite the spans of one line character by character: `show(i, c, out)`
+/// pushes what is shown for the character `c` at byte `i` of the line.
+/// Also returns, for each display column of the old line and its end, the
+/// column it moved to.
+fn rewrite_spans(
+    spans: Vec<Span<'static>>,
+    mut show: impl FnMut(usize, char, &mut String),
+) -> (Vec<Span<'static>>, Vec<usize>) {
+    let line: String = spans.iter().map(|s| s.content.as_ref()).collect();
+    let mut columns = Vec::new();
+    let mut column = 0;
+    let mut i = 0;
+    let spans = spans
+        .into_iter()
+        .map(|span| {
+            let mut content = String::new();
+            for c in span.content.chars() {
+                let old_end = line[..i + c.len_utf8()].width();
+                if old_end > columns.len() {
+                    columns.resize(old_end, column);
+                }
+                let shown_from = content.len();
+                show(i, c, &mut content);
+                column += content[shown_from..].width();
+                i += c.len_utf8();
+            }
+            Span::styled(content, span.style)
+        })
+        .collect();
+    let end = line.width().max(columns.len());
+    columns.resize(end, column);
+    columns.push(column);
+    (spans, columns)
+}
+
+fn render_code_block(
+    content: &str,
+    lang: Option<&str>,
+    layout: &Layout,
+    lines: &mut Vec<Line<'static>>,
+) {
+    let (fg, bg) = if layout.light_theme {
+        (Color::Black, Color::White)
+    } else {
+        (Color::Green, Color::Black)
+    };
     let border_style = Style::default().fg(Color::DarkGray);
-    let fallback_style = Style::default().fg(Color::Green).bg(Color::Black);
+    let fallback_style = Style::default().fg(fg).bg(bg);
+    let marker_style = Style::default().fg(Color::DarkGray).bg(bg);
+    let split_line = |line| split_trailing_whitespace(line, layout.show_whitespace, marker_style);
 
     let ss = syntax_set();
     let syntax = lang
@@ -293,17 +730,18 @@ fn render_code_block(content: &str, lang: Option<&str>, lines: &mut Vec<Line<'st
     lines.push(Line::from(Span::styled("┌───", border_style)));
 
     if let Some(syn) = syntax {
-        let th = theme();
+        let th = theme(layout.light_theme);
         let mut highlighter = syntect::easy::HighlightLines::new(syn, th);
 
         for text_line in content.lines() {
             let mut spans = vec![Span::styled("│ ", border_style)];
+            let (text_line, trailing) = split_line(text_line);
 
             match highlighter.highlight_line(text_line, ss) {
                 Ok(regions) => {
                     for (style, text) in regions {
                         let fg = syntect_to_ratatui_color(style.foreground);
-                        let ratatui_style = Style::default().fg(fg).bg(Color::Black);
+                        let ratatui_style = Style::default().fg(fg).bg(bg);
                         spans.push(Span::styled(text.to_owned(), ratatui_style));
                     }
                 }
@@ -311,33 +749,124 @@ fn render_code_block(content: &str, lang: Option<&str>, lines: &mut Vec<Line<'st
                     spans.push(Span::styled(text_line.to_owned(), fallback_style));
                 }
             }
+            spans.extend(trailing);
 
             lines.push(Line::from(spans));
         }
     } else {
         // No recognized syntax — plain monospace fallback
         for text_line in content.lines() {
-            lines.push(Line::from(vec![
+            let (text_line, trailing) = split_line(text_line);
+            let mut spans = vec![
                 Span::styled("│ ", border_style),
                 Span::styled(text_line.to_owned(), fallback_style),
-            ]));
+            ];
+            spans.extend(trailing);
+            lines.push(Line::from(spans));
         }
     }
 
     lines.push(Line::from(Span::styled("└───", border_style)));
 }
 
+/// With `show`, split the spaces and tabs ending `line` off as a span of
+/// markers in `style`; otherwise, or when there are none, keep `line` whole.
+fn split_trailing_whitespace(
+    line: &str,
+    show: bool,
+    style: Style,
+) -> (&str, Option<Span<'static>>) {
+    let text = line.trim_end_matches([' ', '\t']);
+    if !show || text.len() == line.len() {
+        return (line, None);
+    }
+    let markers: String = line[text.len()..]
+        .chars()
+        .map(|c| match c {
+            '\t' => TAB_MARKER,
+            _ => TRAILING_SPACE_MARKER,
+        })
+        .collect();
+    (text, Some(Span::styled(markers, style)))
+}
+
+/// Render markdown `source` as itself, highlighted and with line numbers:
+/// the TUI's raw source view (`s`).  Rendered line `n` is source line
+/// `n + 1`, and the headings keep their places so heading jumps and the
+/// outline still work.
+pub fn render_source(source: &str, layout: &Layout) -> RenderedDocument {
+    let number_style = Style::default().fg(Color::DarkGray);
+    let ss = syntax_set();
+    let syntax = ss
+        .find_syntax_by_extension("md")
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+    let mut highlighter = syntect::easy::HighlightLines::new(syntax, theme(layout.light_theme));
+    let count = source.lines().count();
+    let gutter = count.max(1).to_string().len();
+
+    let mut lines: Vec<Line<'static>> = Vec::with_capacity(count);
+    for (i, text_line) in source.lines().enumerate() {
+        let (text_line, trailing) =
+            split_trailing_whitespace(text_line, layout.show_whitespace, number_style);
+        let mut spans = vec![Span::styled(format!("{:>gutter$} ", i + 1), number_style)];
+        // The syntax set expects each line to end with its newline.
+        match highlighter.highlight_line(&format!("{text_line}\n"), ss) {
+            Ok(regions) => {
+                for (style, text) in regions {
+                    let text = text.trim_end_matches('\n');
+                    if !text.is_empty() {
+                        let fg = syntect_to_ratatui_color(style.foreground);
+                        spans.push(Span::styled(text.to_owned(), Style::default().fg(fg)));
+                    }
+                }
+            }
+            Err(_) => spans.push(Span::raw(text_line.to_owned())),
+        }
+        spans.extend(trailing);
+        lines.push(Line::from(spans));
+    }
+    if layout.show_whitespace {
+        mark_whitespace(&mut lines, 0, &mut []);
+    }
+
+    let heading_lines = crate::parse::parse(source)
+        .headings
+        .into_iter()
+        .map(|h| HeadingPosition {
+            rendered_line: h.line.saturating_sub(1),
+            level: h.level,
+            text: h.text,
+            anchor: h.anchor,
+        })
+        .collect();
+    RenderedDocument {
+        text: Text::from(lines),
+        heading_lines,
+        link_positions: Vec::new(),
+        task_lines: Vec::new(),
+        block_lines: vec![BlockPosition {
+            rendered_line: 0,
+            line_start: 1,
+            line_end: count,
+        }],
+    }
+}
+
 fn render_list(
     content: &str,
     inline_links: &[InlineLink],
+    tasks: &[TaskItem],
     lines: &mut Vec<Line<'static>>,
     link_positions: &mut Vec<LinkPosition>,
+    task_lines: &mut Vec<TaskPosition>,
 ) {
     let bullet_style = Style::default()
         .fg(Color::Cyan)
         .add_modifier(Modifier::BOLD);
+    let done_style = Style::default()
+        .fg(Color::Green)
+        .add_modifier(Modifier::BOLD);
     let base_style = Style::default();
-    let prefix_width = 4; // "  • " is 4 display columns
 
     let mut content_offset = 0;
     for text_line in content.lines() {
@@ -346,7 +875,23 @@ fn render_list(
             let leading_ws = text_line.len() - text_line.trim_start().len();
             let trimmed_offset = content_offset + leading_ws;
 
-            let mut spans = vec![Span::styled("  • ", bullet_style)];
+            // "  • " is 4 display columns, "  [ ] " is 6.
+            let (prefix, prefix_width) = match tasks.iter().find(|t| t.offset == trimmed_offset) {
+                Some(task) => {
+                    task_lines.push(TaskPosition {
+                        rendered_line: lines.len(),
+                        source_line: task.line,
+                        checked: task.checked,
+                    });
+                    if task.checked {
+                        (Span::styled("  [x] ", done_style), 6)
+                    } else {
+                        (Span::styled("  [ ] ", bullet_style), 6)
+                    }
+                }
+                None => (Span::styled("  • ", bullet_style), 4),
+            };
+            let mut spans = vec![prefix];
             let link_spans = split_line_at_links(
                 trimmed,
                 trimmed_offset,
@@ -363,6 +908,22 @@ fn render_list(
     }
 }
 
+/// An inline table of contents: the headings up to level `depth` as a
+/// bulleted list, nested by [`toc::nesting`].
+fn render_inline_toc(headings: &[Heading], depth: u8, lines: &mut Vec<Line<'static>>) {
+    let bullet_style = Style::default()
+        .fg(Color::Cyan)
+        .add_modifier(Modifier::BOLD);
+    let shown: Vec<&Heading> = headings.iter().filter(|h| h.level <= depth).collect();
+    let levels: Vec<u8> = shown.iter().map(|h| h.level).collect();
+    for (heading, indent) in shown.iter().zip(toc::nesting(&levels)) {
+        lines.push(Line::from(vec![
+            Span::styled(format!("{}  • ", "  ".repeat(indent)), bullet_style),
+            Span::raw(heading.text.clone()),
+        ]));
+    }
+}
+
 fn render_block_quote(
     content: &str,
     inline_links: &[InlineLink],
@@ -459,6 +1020,29 @@ mod tests {
         assert!(joined.contains("beta"));
     }
 
+    #[test]
+    fn task_items_render_checkboxes() {
+        let doc = parse::parse("- [ ] milk\n- plain\n- [x] eggs\n");
+        let rendered = render_document(&doc);
+        let lines: Vec<String> = rendered.text.lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(lines, ["  [ ] milk", "  • plain", "  [x] eggs"]);
+        assert_eq!(
+            rendered.task_lines,
+            [
+                TaskPosition {
+                    rendered_line: 0,
+                    source_line: 1,
+                    checked: false,
+                },
+                TaskPosition {
+                    rendered_line: 2,
+                    source_line: 3,
+                    checked: true,
+                },
+            ]
+        );
+    }
+
     #[test]
     fn block_quote_has_bar() {
         let doc = parse::parse("> quoted\n");
@@ -513,4 +1097,148 @@ mod tests {
         assert_eq!(rendered.heading_lines[1].level, 2);
         assert_eq!(rendered.heading_lines[1].text, "Section");
     }
+
+    #[test]
+    fn repeated_headings_get_the_page_anchor_ids() {
+        let src = "# Usage\n\n## Examples\n\n## Examples\n\n### Examples\n";
+        let mut doc = parse::parse(src);
+        crate::numbering::number_document(&mut doc);
+        let rendered = render_document(&doc);
+
+        let anchors: Vec<&str> = rendered
+            .heading_lines
+            .iter()
+            .map(|h| h.anchor.as_str())
+            .collect();
+        assert_eq!(anchors, ["usage", "examples", "examples-1", "examples-2"]);
+        let page: Vec<String> = crate::html::heading_anchors(src)
+            .into_iter()
+            .map(|(h, _)| h.anchor_id)
+            .collect();
+        assert_eq!(anchors, page);
+    }
+
+    #[test]
+    fn toc_marker_lists_headings() {
+        let doc = parse::parse("# A\n\n[TOC depth=2]\n\n## B\n\n### C\n");
+        let rendered = render_document(&doc);
+        let text: Vec<String> = rendered.text.lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(text[2..4], ["  • A", "    • B"]);
+        assert!(!text.iter().any(|l| l.contains("TOC") || l.contains("• C")));
+    }
+
+    #[test]
+    fn link_columns_count_wide_characters_as_two_cells() {
+        let src = "\u{65e5}\u{672c} \u{1f600} [\u{6587}\u{66f8}](b.md)\n\n- caf\u{e9} [x](y.md)\n";
+        let rendered = render_document(&parse::parse(src));
+        let link = &rendered.link_positions[0];
+        // Two CJK characters, a space, an emoji, and a space: 2+2+1+2+1.
+        assert_eq!((link.column_start, link.column_end), (8, 12));
+        // "  • " then "café ", each character one column wide.
+        let item = &rendered.link_positions[1];
+        assert_eq!((item.column_start, item.column_end), (9, 10));
+    }
+
+    #[test]
+    fn rtl_lines_are_right_aligned() {
+        let src = concat!(
+            "\u{05e9}\u{05dc}\u{05d5}\u{05dd} [\u{05d3}\u{05e3}](a.md)\n\n",
+            "Hello\n\n```\n\u{05e9}\n```\n",
+        );
+        let rendered = render_document(&parse::parse(src));
+        let lines = &rendered.text.lines;
+        assert_eq!(lines[0].alignment, Some(Alignment::Right));
+        assert_eq!(lines[2].alignment, None);
+        assert!(lines[4..].iter().all(|l| l.alignment.is_none()));
+
+        // "שלום דף" is 7 columns, so it starts 13 columns into a 20-wide view.
+        assert_eq!(alignment_offset(&lines[0], 20), 13);
+        assert_eq!(alignment_offset(&lines[2], 20), 0);
+    }
+
+    #[test]
+    fn paragraphs_wrap_and_justify_to_text_width() {
+        let doc = parse::parse("aa [bb](x.md) cc\ndd\n");
+        let layout = Layout {
+            text_width: Some(9),
+            justify: true,
+            ..Layout::default()
+        };
+        let rendered = render_document_with(&doc, &layout);
+        let text: Vec<String> = rendered.text.lines.iter().map(|l| l.to_string()).collect();
+        // The last line of a paragraph is left ragged.
+        assert_eq!(text, ["aa  bb cc", "dd"]);
+        let link = &rendered.link_positions[0];
+        assert_eq!((link.rendered_line, link.column_start, link.column_end), (0, 4, 6));
+    }
+
+    #[test]
+    fn whitespace_markers_show_tabs_nbsp_and_trailing_spaces() {
+        let doc = parse::parse("a\u{a0}b [c](x.md)\n\n```\nx  \n\ty\n```\n");
+        let layout = Layout {
+            show_whitespace: true,
+            ..Layout::default()
+        };
+        let rendered = render_document_with(&doc, &layout);
+        let text: Vec<String> = rendered.text.lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(text[0], "a\u{2423}b c");
+        assert_eq!(text[3], "│ x\u{b7}\u{b7}");
+        assert_eq!(text[4], "│ \u{2192}y");
+        let marker = &rendered.text.lines[0].spans[1];
+        assert_eq!(marker.content, "\u{2423}");
+        assert_eq!(marker.style.fg, Some(Color::DarkGray));
+        let link = &rendered.link_positions[0];
+        assert_eq!((link.column_start, link.column_end), (4, 5));
+
+        let plain = render_document(&doc);
+        assert_eq!(plain.text.lines[0].to_string(), "a\u{a0}b c");
+        assert_eq!(plain.text.lines[3].to_string(), "│ x  ");
+    }
+
+    #[test]
+    fn source_view_lines_are_source_lines() {
+        let src = "# Title\n\nfirst line\nsecond line\n\n```\ncode\n```\n";
+        let rendered = render_document(&parse::parse(src));
+        assert_eq!(rendered.source_line_for_rendered(0), 1);
+        assert_eq!(rendered.source_line_for_rendered(3), 4);
+        assert_eq!(rendered.source_line_for_rendered(6), 7);
+
+        let source = render_source(src, &Layout::default());
+        let text: Vec<String> = source.text.lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(text.len(), 8);
+        assert_eq!(text[0], "1 # Title");
+        assert_eq!(text[6], "7 code");
+        assert_eq!(source.heading_lines[0].rendered_line, 0);
+        assert_eq!(source.rendered_line_for_source(4), 3);
+        assert_eq!(source.source_line_for_rendered(3), 4);
+    }
+
+    #[test]
+    fn hyphenation_breaks_at_soft_hyphens() {
+        let doc = parse::parse("one hy\u{ad}phen\u{ad}ation\n");
+        let mut layout = Layout {
+            text_width: Some(10),
+            ..Layout::default()
+        };
+        let text = |layout: &Layout| -> Vec<String> {
+            let rendered = render_document_with(&doc, layout);
+            rendered.text.lines.iter().map(|l| l.to_string()).collect()
+        };
+        assert_eq!(text(&layout), ["one", "hyphenation"]);
+        layout.hyphenate = true;
+        assert_eq!(text(&layout), ["one hy-", "phenation"]);
+    }
+
+    #[test]
+    fn source_lines_map_to_rendered_lines() {
+        let src = "# Title\n\nfirst line\nsecond line\n\n```\ncode\n```\n";
+        let rendered = render_document(&parse::parse(src));
+        // Heading on 0, paragraph on 2-3, code block (with borders) on 5-7.
+        assert_eq!(rendered.rendered_line_for_source(1), 0);
+        assert_eq!(rendered.rendered_line_for_source(4), 3);
+        // The blank source line before the code block maps to its start.
+        assert_eq!(rendered.rendered_line_for_source(5), 5);
+        assert_eq!(rendered.rendered_line_for_source(7), 6);
+        assert_eq!(rendered.rendered_line_for_source(100), 7);
+    }
 }
@@ -0,0 +1,266 @@
+//! `mdmd render` subcommand: write a rendered markdown file to stdout.
+//!
+//! The text formats are the document as the TUI shows it, either styled
+//! with ANSI escape sequences or plain; `html` is the rendered HTML fragment
+//! without the page shell that `mdmd html` adds.
+
+use std::borrow::Cow;
+use std::fs;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+
+use crossterm::queue;
+use crossterm::style::{Attribute, Print, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use ratatui::backend::IntoCrossterm;
+use ratatui::style::{Modifier, Style};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::html::{self, RenderTarget};
+use crate::hyperlink;
+use crate::render::{self, RenderedDocument};
+use crate::vars::{self, Vars};
+use crate::{frontmatter, include};
+
+/// What `mdmd render` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Text styled with ANSI escape sequences.
+    Ansi,
+    /// Text without styling.
+    Plain,
+    /// An HTML fragment.
+    Html,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "ansi" => Some(Format::Ansi),
+            "plain" => Some(Format::Plain),
+            "html" => Some(Format::Html),
+            _ => None,
+        }
+    }
+
+    /// `ansi` when stdout is a terminal, `plain` otherwise.
+    pub fn for_stdout() -> Format {
+        if io::stdout().is_terminal() {
+            Format::Ansi
+        } else {
+            Format::Plain
+        }
+    }
+}
+
+/// ANSI attributes for the modifiers the renderer uses.
+const ATTRIBUTES: &[(Modifier, Attribute)] = &[
+    (Modifier::BOLD, Attribute::Bold),
+    (Modifier::DIM, Attribute::Dim),
+    (Modifier::ITALIC, Attribute::Italic),
+    (Modifier::UNDERLINED, Attribute::Underlined),
+    (Modifier::REVERSED, Attribute::Reverse),
+    (Modifier::CROSSED_OUT, Attribute::CrossedOut),
+];
+
+/// Run the `render` subcommand.
+///
+/// # Parameters
+/// - `file`: path to the source markdown file.
+/// - `format`: what to write.
+/// - `hyperlinks`: whether links in the text formats may be written as
+///   OSC 8 hyperlinks; `plain` output only gets them on a terminal.
+/// - `substitute_vars`: whether to substitute `{{var.name}}` placeholders.
+/// - `render`: optional rendering transformations.
+pub fn run_render(
+    file: &str,
+    format: Format,
+    hyperlinks: bool,
+    substitute_vars: bool,
+    render: html::RenderOptions,
+) -> io::Result<()> {
+    let source = crate::read_markdown_file(file, false);
+    let path = Path::new(file);
+    let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    let mut out = BufWriter::new(io::stdout().lock());
+    let result = match format {
+        Format::Html => {
+            let extracted = frontmatter::extract(&source);
+            let mut body = include::expand(extracted.render_body.as_ref(), &canonical, None).text;
+            if substitute_vars {
+                let vars = Vars::for_document(&canonical, extracted.meta.as_ref(), None);
+                body = Cow::Owned(vars::substitute(&body, &vars).into_owned());
+            }
+            let rendered =
+                html::render_markdown_with(&body, &canonical, RenderTarget::Html, false, &render);
+            out.write_all(html::inject_heading_ids(&rendered.html, &rendered.headings).as_bytes())
+        }
+        Format::Ansi | Format::Plain => {
+            let doc = crate::parse_file(&canonical, &source, substitute_vars, &render);
+            let rendered = render::render_document(&doc);
+            let ansi = format == Format::Ansi;
+            let links = hyperlinks && (ansi || io::stdout().is_terminal());
+            write_text(
+                &mut out,
+                &rendered,
+                ansi,
+                links.then_some(canonical.as_path()),
+            )
+        }
+    };
+    // A reader such as `head` that stops early is not an error.
+    match result.and_then(|()| out.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        result => result,
+    }
+}
+
+/// Write `rendered` as lines of text, styled with ANSI escape sequences
+/// when `ansi`.  With `document`, the document's links are wrapped in
+/// hyperlinks to their targets, resolved against it.
+pub fn write_text(
+    out: &mut impl Write,
+    rendered: &RenderedDocument,
+    ansi: bool,
+    document: Option<&Path>,
+) -> io::Result<()> {
+    for (idx, line) in rendered.text.lines.iter().enumerate() {
+        // Display columns in the line where a hyperlink opens or closes,
+        // closes first where one link ends as the next begins.
+        let mut marks: Vec<(usize, Option<String>)> = Vec::new();
+        if let Some(document) = document {
+            for link in rendered
+                .link_positions
+                .iter()
+                .filter(|l| l.rendered_line == idx)
+            {
+                if let Some(uri) = hyperlink::target_uri(&link.url, document) {
+                    marks.push((link.column_start, Some(uri)));
+                    marks.push((link.column_end, None));
+                }
+            }
+            marks.sort();
+        }
+        let mut marks = marks.into_iter().peekable();
+
+        let mut col = 0;
+        for span in &line.spans {
+            let style = rendered.text.style.patch(line.style).patch(span.style);
+            let mut rest = span.content.as_ref();
+            while !rest.is_empty() {
+                while let Some((_, mark)) = marks.next_if(|(at, _)| *at <= col) {
+                    write_mark(out, mark.as_deref())?;
+                }
+                let end = marks
+                    .peek()
+                    .map_or(rest.len(), |(at, _)| byte_at_column(rest, at - col));
+                write_styled(out, &rest[..end], style, ansi)?;
+                col += rest[..end].width();
+                rest = &rest[end..];
+            }
+        }
+        for (_, mark) in marks {
+            write_mark(out, mark.as_deref())?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Byte offset in `text` of the first character that starts at or after
+/// display column `column`, or the length of `text` when none does.
+fn byte_at_column(text: &str, column: usize) -> usize {
+    let mut width = 0;
+    for (i, c) in text.char_indices() {
+        if width >= column {
+            return i;
+        }
+        width += c.width().unwrap_or(0);
+    }
+    text.len()
+}
+
+/// Open a hyperlink to `uri`, or close the open one.
+fn write_mark(out: &mut impl Write, uri: Option<&str>) -> io::Result<()> {
+    match uri {
+        Some(uri) => out.write_all(hyperlink::open(uri).as_bytes()),
+        None => out.write_all(hyperlink::CLOSE.as_bytes()),
+    }
+}
+
+fn write_styled(out: &mut impl Write, text: &str, style: Style, ansi: bool) -> io::Result<()> {
+    let styled =
+        ansi && (style.fg.is_some() || style.bg.is_some() || !style.add_modifier.is_empty());
+    if !styled {
+        return out.write_all(text.as_bytes());
+    }
+    if let Some(fg) = style.fg {
+        queue!(out, SetForegroundColor(fg.into_crossterm()))?;
+    }
+    if let Some(bg) = style.bg {
+        queue!(out, SetBackgroundColor(bg.into_crossterm()))?;
+    }
+    for &(modifier, attribute) in ATTRIBUTES {
+        if style.add_modifier.contains(modifier) {
+            queue!(out, SetAttribute(attribute))?;
+        }
+    }
+    queue!(out, Print(text), SetAttribute(Attribute::Reset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rendered(markdown: &str) -> RenderedDocument {
+        render::render_document(&crate::parse::parse(markdown))
+    }
+
+    fn text(doc: &RenderedDocument, ansi: bool, document: Option<&Path>) -> String {
+        let mut out = Vec::new();
+        write_text(&mut out, doc, ansi, document).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn plain_text_has_no_escape_sequences() {
+        let doc = rendered("# Title\n\nSome **bold** and [a link](b.md).\n");
+        let plain = text(&doc, false, None);
+        assert!(!plain.contains('\x1b'), "{plain:?}");
+        assert!(plain.contains("bold"));
+        let ansi = text(&doc, true, None);
+        assert!(ansi.contains("\x1b["), "{ansi:?}");
+        assert!(!ansi.contains("\x1b]8"), "{ansi:?}");
+    }
+
+    #[test]
+    fn links_are_wrapped_in_hyperlinks() {
+        let doc = rendered("See [docs](https://example.com/docs) and [b](b.md#x).\n");
+        let plain = text(&doc, false, Some(Path::new("/notes/a.md")));
+        let line = plain
+            .lines()
+            .find(|l| l.contains("See"))
+            .expect("paragraph line");
+        let docs = &doc.link_positions[0].text;
+        assert!(
+            line.contains(&format!(
+                "\x1b]8;;https://example.com/docs\x1b\\{docs}\x1b]8;;\x1b\\"
+            )),
+            "{line:?}"
+        );
+        assert!(
+            line.contains("\x1b]8;;file:///notes/b.md#x\x1b\\"),
+            "{line:?}"
+        );
+    }
+
+    #[test]
+    fn hyperlinks_after_wide_characters_wrap_the_link_text() {
+        let doc = rendered("\u{65e5}\u{672c}\u{8a9e} \u{1f600} [\u{6587}\u{66f8}](b.md) end\n");
+        let plain = text(&doc, false, Some(Path::new("/notes/a.md")));
+        assert!(
+            plain.contains("\x1b]8;;file:///notes/b.md\x1b\\\u{6587}\u{66f8}\x1b]8;;\x1b\\ end"),
+            "{plain:?}"
+        );
+    }
+}
@@ -0,0 +1,421 @@
+//! Project-wide find-and-replace for the TUI.
+//!
+//! Literal matches are collected from every markdown file under a project
+//! root, reviewed one at a time in the TUI, and the accepted ones are written
+//! back with an atomic temp-file-and-rename so a crash never leaves a file
+//! half written.
+//!
+//! Writes are cooperative: before a file is rewritten its current contents
+//! are checked against the preview, and files edited in the meantime are
+//! skipped rather than clobbered.  Files that are not UTF-8 are left alone;
+//! see [`crate::encoding::read_utf8`].
+
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::encoding;
+use crate::web_assets::fnv1a_64;
+
+/// A single occurrence of the search text, offered for replacement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaceCandidate {
+    /// Path of the file containing the match.
+    pub path: PathBuf,
+    /// 1-based line number of the match.
+    pub line: usize,
+    /// Byte offset of the match start within the file.
+    pub start: usize,
+    /// Byte offset of the match end (exclusive) within the file.
+    pub end: usize,
+    /// Full text of the line containing the match, for the preview.
+    pub line_text: String,
+    /// Byte offset of the match start within `line_text`.
+    pub line_column: usize,
+    /// Whether the user has accepted this replacement.
+    pub accepted: bool,
+    /// Hash of the whole file when the match was collected, so a file
+    /// edited anywhere since the preview is skipped.
+    pub source_hash: u64,
+}
+
+/// Outcome of writing accepted replacements back to disk.
+#[derive(Debug, Default)]
+pub struct ApplySummary {
+    /// Number of files rewritten.
+    pub files_written: usize,
+    /// Number of occurrences replaced across all rewritten files.
+    pub replaced: usize,
+    /// Files skipped because their contents changed since the preview.
+    pub stale: Vec<PathBuf>,
+    /// Files that could not be read or written, with the error message.
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+/// Collect every markdown file under `root`, sorted by path.
+///
+/// Skips `.git`, `node_modules`, and `.jj` directories, matching the
+/// backlinks index traversal.  Unreadable directories are ignored, and a
+/// directory reached again through a symlink is walked only once, so a
+/// symlink loop ends the walk instead of hanging it.
+pub fn collect_markdown_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut visited = HashSet::new();
+    let mut queue: VecDeque<PathBuf> = VecDeque::new();
+    queue.push_back(root.to_path_buf());
+
+    while let Some(dir) = queue.pop_front() {
+        if !fs::canonicalize(&dir).is_ok_and(|c| visited.insert(c)) {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                let dir_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                if !matches!(dir_name, ".git" | "node_modules" | ".jj") {
+                    queue.push_back(path);
+                }
+                continue;
+            }
+            let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+            if matches!(ext, "md" | "markdown" | "mdx" | "mdown" | "mkd" | "mkdn") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Find all non-overlapping literal occurrences of `needle` in `source`.
+///
+/// Matching is case-sensitive so that the replaced text is exactly what was
+/// previewed.  All candidates start out accepted.
+pub fn find_in_source(path: &Path, source: &str, needle: &str) -> Vec<ReplaceCandidate> {
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    let source_hash = fnv1a_64(source.as_bytes());
+    let mut candidates = Vec::new();
+    let mut line_start = 0;
+    for (line_idx, line) in source.split_inclusive('\n').enumerate() {
+        let line_text = line.trim_end_matches(['\n', '\r']);
+        for (col, _) in line_text.match_indices(needle) {
+            candidates.push(ReplaceCandidate {
+                path: path.to_path_buf(),
+                line: line_idx + 1,
+                start: line_start + col,
+                end: line_start + col + needle.len(),
+                line_text: line_text.to_owned(),
+                line_column: col,
+                accepted: true,
+                source_hash,
+            });
+        }
+        line_start += line.len();
+    }
+    candidates
+}
+
+/// Find all occurrences of `needle` across the markdown files under `root`.
+pub fn find_in_project(root: &Path, needle: &str) -> Vec<ReplaceCandidate> {
+    collect_markdown_files(root)
+        .into_iter()
+        .filter_map(|path| {
+            let source = encoding::read_utf8(&path).ok()?;
+            Some(find_in_source(&path, &source, needle))
+        })
+        .flatten()
+        .collect()
+}
+
+/// Replace the given byte ranges of `source` with `replacement`.
+///
+/// Returns `None` when any range no longer holds `needle` (the file changed
+/// since the matches were collected) so the caller can skip the file.
+pub fn apply_to_source(
+    source: &str,
+    needle: &str,
+    replacement: &str,
+    candidates: &[&ReplaceCandidate],
+) -> Option<String> {
+    let mut ranges: Vec<(usize, usize)> = candidates.iter().map(|c| (c.start, c.end)).collect();
+    ranges.sort_unstable();
+
+    let mut out = String::with_capacity(source.len());
+    let mut pos = 0;
+    for (start, end) in ranges {
+        if start < pos || source.get(start..end) != Some(needle) {
+            return None;
+        }
+        out.push_str(&source[pos..start]);
+        out.push_str(replacement);
+        pos = end;
+    }
+    out.push_str(&source[pos..]);
+    Some(out)
+}
+
+/// Write `contents` to `path` atomically.
+///
+/// The data is written to a hidden temporary file in the same directory,
+/// given the original file's permissions before anything is written to it,
+/// flushed, and renamed over `path`.  A symlink is followed, so the file it
+/// points to is replaced and the link kept.  Each write gets its own
+/// temporary file, named after the process and a counter, so concurrent
+/// writers to one file never share one.
+pub fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    use std::io::Write;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+    // A file that does not exist yet is written where it was asked for.
+    let resolved = fs::canonicalize(path);
+    let path = resolved.as_deref().unwrap_or(path);
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file");
+    let tmp = dir.join(format!(
+        ".{name}.mdmd-replace.{}.{}.tmp",
+        std::process::id(),
+        NEXT_TMP.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| {
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp)?;
+        if let Ok(meta) = fs::metadata(path) {
+            fs::set_permissions(&tmp, meta.permissions())?;
+        }
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// Write all accepted candidates back to disk, one atomic write per file.
+pub fn apply_accepted(
+    candidates: &[ReplaceCandidate],
+    needle: &str,
+    replacement: &str,
+) -> ApplySummary {
+    let mut summary = ApplySummary::default();
+
+    let mut paths: Vec<&Path> = candidates
+        .iter()
+        .filter(|c| c.accepted)
+        .map(|c| c.path.as_path())
+        .collect();
+    paths.dedup();
+
+    for path in paths {
+        let accepted: Vec<&ReplaceCandidate> = candidates
+            .iter()
+            .filter(|c| c.accepted && c.path == path)
+            .collect();
+
+        let source = match encoding::read_utf8(path) {
+            Ok(s) => s,
+            Err(e) => {
+                summary.failed.push((path.to_path_buf(), e));
+                continue;
+            }
+        };
+        let hash = fnv1a_64(source.as_bytes());
+        let unchanged = accepted.iter().all(|c| c.source_hash == hash);
+        let updated = unchanged
+            .then(|| apply_to_source(&source, needle, replacement, &accepted))
+            .flatten();
+        let Some(updated) = updated else {
+            summary.stale.push(path.to_path_buf());
+            continue;
+        };
+        match write_atomic(path, &updated) {
+            Ok(()) => {
+                summary.files_written += 1;
+                summary.replaced += accepted.len();
+            }
+            Err(e) => summary.failed.push((path.to_path_buf(), e.to_string())),
+        }
+    }
+
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_in_source_reports_lines_and_offsets() {
+        let source = "alpha beta\nbeta gamma beta\n";
+        let found = find_in_source(Path::new("a.md"), source, "beta");
+        assert_eq!(found.len(), 3);
+        assert_eq!((found[0].line, found[0].start, found[0].end), (1, 6, 10));
+        assert_eq!((found[1].line, found[1].line_column), (2, 0));
+        assert_eq!((found[2].line, found[2].line_column), (2, 11));
+        assert_eq!(found[2].line_text, "beta gamma beta");
+        assert!(found.iter().all(|c| c.accepted));
+    }
+
+    #[test]
+    fn find_in_source_is_case_sensitive_and_ignores_empty_needle() {
+        assert!(find_in_source(Path::new("a.md"), "Beta", "beta").is_empty());
+        assert!(find_in_source(Path::new("a.md"), "beta", "").is_empty());
+    }
+
+    #[test]
+    fn find_in_source_handles_crlf_lines() {
+        let found = find_in_source(Path::new("a.md"), "one\r\ntwo one\r\n", "one");
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[1].line_text, "two one");
+        assert_eq!(found[1].start, 9);
+    }
+
+    #[test]
+    fn apply_to_source_replaces_only_given_candidates() {
+        let source = "beta beta beta";
+        let found = find_in_source(Path::new("a.md"), source, "beta");
+        let chosen = vec![&found[0], &found[2]];
+        assert_eq!(
+            apply_to_source(source, "beta", "BETA", &chosen).as_deref(),
+            Some("BETA beta BETA")
+        );
+    }
+
+    #[test]
+    fn apply_to_source_detects_stale_offsets() {
+        let found = find_in_source(Path::new("a.md"), "x beta", "beta");
+        let chosen: Vec<&ReplaceCandidate> = found.iter().collect();
+        assert_eq!(apply_to_source("xx beta", "beta", "B", &chosen), None);
+    }
+
+    #[test]
+    fn apply_accepted_writes_files_and_skips_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.md");
+        let b = dir.path().join("sub/b.md");
+        fs::create_dir_all(dir.path().join("sub")).unwrap();
+        fs::write(&a, "old and old\n").unwrap();
+        fs::write(&b, "keep old\n").unwrap();
+        fs::write(dir.path().join("notes.txt"), "old\n").unwrap();
+
+        let mut found = find_in_project(dir.path(), "old");
+        assert_eq!(found.len(), 3, "non-markdown files are not searched");
+        found[1].accepted = false;
+
+        let summary = apply_accepted(&found, "old", "new");
+        assert_eq!(summary.files_written, 2);
+        assert_eq!(summary.replaced, 2);
+        assert!(summary.stale.is_empty() && summary.failed.is_empty());
+        assert_eq!(fs::read_to_string(&a).unwrap(), "new and old\n");
+        assert_eq!(fs::read_to_string(&b).unwrap(), "keep new\n");
+        assert_eq!(
+            fs::read_to_string(dir.path().join("notes.txt")).unwrap(),
+            "old\n"
+        );
+    }
+
+    #[test]
+    fn apply_accepted_skips_files_changed_since_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.md");
+        fs::write(&a, "old\n").unwrap();
+        let found = find_in_project(dir.path(), "old");
+        fs::write(&a, "edited elsewhere\n").unwrap();
+
+        let summary = apply_accepted(&found, "old", "new");
+        assert_eq!(summary.files_written, 0);
+        assert_eq!(summary.stale, vec![a.clone()]);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "edited elsewhere\n");
+    }
+
+    #[test]
+    fn apply_accepted_skips_files_edited_on_another_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.md");
+        fs::write(&a, "old\nsecond\n").unwrap();
+        let found = find_in_project(dir.path(), "old");
+        fs::write(&a, "old\nsecond, edited\n").unwrap();
+
+        let summary = apply_accepted(&found, "old", "new");
+        assert_eq!(summary.files_written, 0);
+        assert_eq!(summary.stale, vec![a.clone()]);
+        assert_eq!(fs::read_to_string(&a).unwrap(), "old\nsecond, edited\n");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_keeps_symlinks_and_modes() {
+        use std::os::unix::fs::{symlink, PermissionsExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("target.md");
+        let link = dir.path().join("link.md");
+        fs::write(&target, "before").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        symlink(&target, &link).unwrap();
+
+        write_atomic(&link, "after").unwrap();
+        let link_meta = fs::symlink_metadata(&link).unwrap();
+        assert!(link_meta.file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "after");
+        let mode = fs::metadata(&target).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn collect_markdown_files_survives_symlink_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("docs/a.md"), "a").unwrap();
+        std::os::unix::fs::symlink(dir.path(), dir.path().join("docs/loop")).unwrap();
+
+        let files = collect_markdown_files(dir.path());
+        assert_eq!(files, [dir.path().join("docs/a.md")]);
+    }
+
+    #[test]
+    fn write_atomic_leaves_no_temp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.md");
+        fs::write(&a, "before").unwrap();
+        write_atomic(&a, "after").unwrap();
+        assert_eq!(fs::read_to_string(&a).unwrap(), "after");
+        let names: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names.len(), 1);
+    }
+
+    #[test]
+    fn concurrent_atomic_writes_each_land_whole() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.md");
+        let bodies: Vec<String> = (0..8).map(|i| format!("{i}\n").repeat(10_000)).collect();
+        std::thread::scope(|scope| {
+            for body in &bodies {
+                let a = &a;
+                scope.spawn(move || write_atomic(a, body).unwrap());
+            }
+        });
+        let written = fs::read_to_string(&a).unwrap();
+        assert!(bodies.contains(&written), "a torn or mixed write");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+//! Keeping the reader's place when the viewed document changes.
+//!
+//! An edit above the top of the view shifts every line below it, so reusing
+//! the old scroll offset after re-rendering shows some other passage.
+//! [`restore`] estimates where the old top line went instead: it anchors to
+//! the nearest heading at or above it, then looks in that heading's section
+//! for a line with the same text, preferring the match nearest the old
+//! distance below the heading.
+
+use crate::render::{HeadingPosition, RenderedDocument};
+
+/// Text of rendered line `index`, or `None` past the end.
+fn line_text(doc: &RenderedDocument, index: usize) -> Option<String> {
+    doc.text.lines.get(index).map(|line| line.to_string())
+}
+
+/// Where the heading starting `section` sits in `new`: the heading with the
+/// same level and anchor id, whose `-1`, `-2` suffixes keep the second
+/// "Examples" the second, else the first one with the same level and text.
+fn find_heading(old: &RenderedDocument, section: usize, new: &RenderedDocument) -> Option<usize> {
+    let heading = &old.heading_lines[section];
+    let position = |same: &dyn Fn(&HeadingPosition) -> bool| {
+        new.heading_lines
+            .iter()
+            .position(|h| h.level == heading.level && same(h))
+    };
+    position(&|h| h.anchor == heading.anchor).or_else(|| position(&|h| h.text == heading.text))
+}
+
+/// The scroll offset in `new` showing what `old_offset` showed in `old`.
+/// Falls back to `old_offset` when nothing recognizable is left; the
+/// caller clamps the result to the new scroll range.
+pub fn restore(old: &RenderedDocument, old_offset: usize, new: &RenderedDocument) -> usize {
+    // Blank lines are everywhere; match the first line with text instead.
+    let probe = (old_offset..old.text.lines.len())
+        .find(|&i| line_text(old, i).is_some_and(|t| !t.trim().is_empty()));
+
+    let section = old
+        .heading_lines
+        .iter()
+        .rposition(|h| h.rendered_line <= old_offset);
+    let (estimate, range) = match section.and_then(|s| Some((s, find_heading(old, s, new)?))) {
+        Some((old_section, new_section)) => {
+            let start = new.heading_lines[new_section].rendered_line;
+            let end = new
+                .heading_lines
+                .get(new_section + 1)
+                .map_or(new.text.lines.len(), |h| h.rendered_line);
+            let below = old_offset - old.heading_lines[old_section].rendered_line;
+            (start + below, start..end)
+        }
+        None => (old_offset, 0..new.text.lines.len()),
+    };
+
+    let Some(probe) = probe else {
+        return estimate;
+    };
+    let text = line_text(old, probe);
+    let lead = probe - old_offset;
+    range
+        .filter(|&i| i >= lead && line_text(new, i) == text)
+        .map(|i| i - lead)
+        .min_by_key(|&i| i.abs_diff(estimate))
+        .unwrap_or(estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::render::render_document;
+
+    fn offset_after_edit(old: &str, new: &str, old_offset: usize) -> usize {
+        let old = render_document(&parse::parse(old));
+        let new = render_document(&parse::parse(new));
+        restore(&old, old_offset, &new)
+    }
+
+    #[test]
+    fn unchanged_document_keeps_offset() {
+        let src = "# A\n\none\n\ntwo\n\n# B\n\nthree\n";
+        for offset in 0..9 {
+            assert_eq!(offset_after_edit(src, src, offset), offset);
+        }
+    }
+
+    #[test]
+    fn insertion_above_keeps_the_same_line_on_top() {
+        let old = "# A\n\none\n\n# B\n\ntwo\n\nthree\n";
+        let new = "# A\n\none\n\nadded\n\nalso added\n\n# B\n\ntwo\n\nthree\n";
+        // "three" was line 8 and is line 12 after four new lines.
+        assert_eq!(offset_after_edit(old, new, 8), 12);
+        // The blank line above "three" moves with it.
+        assert_eq!(offset_after_edit(old, new, 7), 11);
+    }
+
+    #[test]
+    fn duplicate_headings_are_told_apart() {
+        let old = "# Example\n\nfirst\n\n# Example\n\nsecond\n";
+        let new = "intro\n\n# Example\n\nfirst\n\n# Example\n\nsecond\n";
+        assert_eq!(offset_after_edit(old, new, 6), 8);
+    }
+
+    #[test]
+    fn renamed_heading_falls_back_to_line_text() {
+        let old = "# Old\n\nbody\n\ntarget\n";
+        let new = "intro\n\n# New\n\nbody\n\ntarget\n";
+        assert_eq!(offset_after_edit(old, new, 4), 6);
+    }
+}
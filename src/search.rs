@@ -0,0 +1,246 @@
+//! Full-text search across the serve root for `GET /_mdmd/v1/search`.
+//!
+//! Every markdown file under the root is scanned line by line for a
+//! case-insensitive literal match.  Results carry a root-relative URL with a
+//! `#:~:text=` fragment so following one scrolls to and highlights the match.
+
+use std::path::Path;
+
+use crate::html::text_fragment;
+use crate::selection::Selection;
+
+/// Maximum number of characters of context kept on each side of a match.
+const SNIPPET_CONTEXT: usize = 60;
+
+/// A single matching line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// Root-relative URL path of the document, with leading slash.
+    pub url_path: String,
+    /// Title of the document (see [`crate::summary`]), else its root-relative
+    /// path.
+    pub title: String,
+    /// 1-based line number of the match.
+    pub line: usize,
+    /// The matching line, trimmed to [`SNIPPET_CONTEXT`] characters around
+    /// the match.
+    pub snippet: String,
+    /// Link to the match: `url_path` plus a text fragment for the match.
+    pub href: String,
+}
+
+/// Search outcome: hits in path order, and whether the limit cut it short.
+#[derive(Debug, Default)]
+pub struct SearchResults {
+    pub hits: Vec<SearchHit>,
+    pub truncated: bool,
+}
+
+/// Find up to `limit` lines containing `query` (case-insensitive) across the
+/// markdown files under `root`.  Files larger than `max_file_size` bytes,
+/// symlinks resolving outside `root`, drafts (see
+/// [`crate::frontmatter::is_draft`]) unless `include_drafts`, files
+/// outside `selection` when there is one, and files `served` rejects (as
+/// `--serve-types` does) are skipped.
+pub fn search_root(
+    root: &Path,
+    query: &str,
+    limit: usize,
+    max_file_size: u64,
+    include_drafts: bool,
+    selection: Option<&Selection>,
+    served: impl Fn(&Path) -> bool,
+) -> SearchResults {
+    let mut results = SearchResults::default();
+    let needle = query.trim().to_lowercase();
+    if needle.is_empty() {
+        return results;
+    }
+
+    let canonical_root = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    for path in crate::replace::collect_markdown_files(root) {
+        // Symlinks may point outside the root; never search what serve
+        // would refuse to show.
+        let inside = std::fs::canonicalize(&path).is_ok_and(|c| c.starts_with(&canonical_root));
+        if !inside || std::fs::metadata(&path).map_or(true, |m| m.len() > max_file_size) {
+            continue;
+        }
+        if selection.is_some_and(|sel| !sel.contains(&path)) || !served(&path) {
+            continue;
+        }
+        let Ok(source) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if !include_drafts && crate::frontmatter::is_draft(&source) {
+            continue;
+        }
+        let rel = path
+            .strip_prefix(root)
+            .map(|r| r.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let url_path = crate::backlinks::url_key_from_rel_path(&rel);
+        let title = crate::summary::extract(&source)
+            .title
+            .unwrap_or_else(|| rel.clone());
+
+        for (idx, line) in source.lines().enumerate() {
+            let Some((start, end)) = find_case_insensitive(line, &needle) else {
+                continue;
+            };
+            if results.hits.len() == limit {
+                results.truncated = true;
+                return results;
+            }
+            results.hits.push(SearchHit {
+                url_path: url_path.clone(),
+                title: title.clone(),
+                line: idx + 1,
+                snippet: snippet_around(line, start, end),
+                href: format!("{url_path}#{}", text_fragment(&line[start..end])),
+            });
+        }
+    }
+    results
+}
+
+/// Byte range of the first case-insensitive occurrence of `needle_lower`
+/// (already lowercased) in `line`.
+fn find_case_insensitive(line: &str, needle_lower: &str) -> Option<(usize, usize)> {
+    // Compare char by char so byte offsets stay valid in `line` even when
+    // lowercasing changes the UTF-8 length of a character.
+    let needle: Vec<char> = needle_lower.chars().collect();
+    let starts: Vec<(usize, char)> = line.char_indices().collect();
+    'outer: for (i, &(start, _)) in starts.iter().enumerate() {
+        let mut j = i;
+        for &n in &needle {
+            let &(_, c) = starts.get(j)?;
+            if !c.to_lowercase().eq(n.to_lowercase()) {
+                continue 'outer;
+            }
+            j += 1;
+        }
+        let end = starts.get(j).map_or(line.len(), |&(b, _)| b);
+        return Some((start, end));
+    }
+    None
+}
+
+/// Trim `line` to at most [`SNIPPET_CONTEXT`] characters on each side of the
+/// match at `start..end`, marking cut ends with an ellipsis.
+fn snippet_around(line: &str, start: usize, end: usize) -> String {
+    let before: Vec<char> = line[..start].chars().collect();
+    let after: Vec<char> = line[end..].chars().collect();
+    let mut out = String::new();
+    if before.len() > SNIPPET_CONTEXT {
+        out.push('…');
+        out.extend(&before[before.len() - SNIPPET_CONTEXT..]);
+    } else {
+        out.extend(&before);
+    }
+    out.push_str(&line[start..end]);
+    if after.len() > SNIPPET_CONTEXT {
+        out.extend(&after[..SNIPPET_CONTEXT]);
+        out.push('…');
+    } else {
+        out.extend(&after);
+    }
+    out.trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn finds_matches_case_insensitively_with_text_fragment_links() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        fs::write(
+            dir.path().join("docs/guide.md"),
+            "# Guide\n\nInstall the Widget first.\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.md"), "no match here\nwidget two\n").unwrap();
+
+        let results = search_root(dir.path(), "WIDGET", 10, u64::MAX, true, None, |_| true);
+        assert!(!results.truncated);
+        assert_eq!(results.hits.len(), 2);
+
+        let hit = &results.hits[0];
+        assert_eq!(hit.url_path, "/docs/guide.md");
+        assert_eq!(hit.title, "Guide");
+        assert_eq!(hit.line, 3);
+        assert_eq!(hit.snippet, "Install the Widget first.");
+        assert_eq!(hit.href, "/docs/guide.md#:~:text=Widget");
+
+        assert_eq!(results.hits[1].title, "notes.md");
+        assert_eq!(results.hits[1].line, 2);
+    }
+
+    #[test]
+    fn limit_truncates_results() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "x\nx\nx\n").unwrap();
+        let results = search_root(dir.path(), "x", 2, u64::MAX, true, None, |_| true);
+        assert_eq!(results.hits.len(), 2);
+        assert!(results.truncated);
+    }
+
+    #[test]
+    fn blank_query_and_oversized_files_yield_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "findme\n").unwrap();
+        assert!(
+            search_root(dir.path(), "  ", 10, u64::MAX, true, None, |_| true)
+                .hits
+                .is_empty()
+        );
+        assert!(
+            search_root(dir.path(), "findme", 10, 3, true, None, |_| true)
+                .hits
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn drafts_are_searched_only_when_included() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("wip.md"), "---\ndraft: true\n---\nfindme\n").unwrap();
+        assert!(
+            search_root(dir.path(), "findme", 10, u64::MAX, false, None, |_| true)
+                .hits
+                .is_empty()
+        );
+        assert_eq!(
+            search_root(dir.path(), "findme", 10, u64::MAX, true, None, |_| true).hits[0].line,
+            4
+        );
+    }
+
+    #[test]
+    fn files_not_served_are_not_searched() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.md"), "findme\n").unwrap();
+        fs::write(dir.path().join("b.markdown"), "findme\n").unwrap();
+        let results = search_root(dir.path(), "findme", 10, u64::MAX, true, None, |p| {
+            p.extension().is_some_and(|e| e == "md")
+        });
+        assert_eq!(results.hits.len(), 1);
+        assert_eq!(results.hits[0].url_path, "/a.md");
+    }
+
+    #[test]
+    fn case_insensitive_match_keeps_original_byte_offsets() {
+        assert_eq!(find_case_insensitive("Grüße WELT", "welt"), Some((8, 12)));
+        assert_eq!(find_case_insensitive("abc", "abcd"), None);
+    }
+
+    #[test]
+    fn snippet_is_trimmed_around_match() {
+        let line = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let snippet = snippet_around(&line, 100, 106);
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert_eq!(snippet.chars().count(), 2 * SNIPPET_CONTEXT + 6 + 2);
+    }
+}
@@ -0,0 +1,312 @@
+//! Serving a chosen set of files: `mdmd serve docs guide.md` or
+//! `mdmd serve 'docs/**/*.md'`.
+//!
+//! Given several paths, or a glob, the server's root becomes the deepest
+//! directory holding every match and the entry is a collection page
+//! listing them (`/_mdmd/collection`).
+//! Directory indexes, the file tree, search, backlinks, and archives then
+//! cover exactly the selection, and markdown files outside it are answered
+//! with 404.  Images and other files the pages use are served as usual.
+//!
+//! Globs are expanded here as well as by the shell, so a quoted pattern
+//! works too: `*` and `?` match within one path segment, `**` matches any
+//! number of segments, and files and directories starting with `.` are
+//! never matched.  A quoted pattern is also matched again on every request,
+//! so files created while the server runs join the selection.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The files and directories given on the command line.
+#[derive(Debug)]
+pub struct Selection {
+    /// The deepest directory holding every match, canonicalized.
+    pub root: PathBuf,
+    /// The first match, canonicalized: a file or a directory.
+    pub entry: PathBuf,
+    files: HashSet<PathBuf>,
+    /// Directories given whole; everything under them is selected.
+    dirs: Vec<PathBuf>,
+    patterns: Vec<Pattern>,
+}
+
+impl Selection {
+    /// Expand `args`, paths and globs, into a selection.  Fails on a path
+    /// that does not exist and on a glob that matches nothing.
+    pub fn expand(args: &[String]) -> Result<Selection, String> {
+        let mut files = Vec::new();
+        let mut dirs = Vec::new();
+        let mut patterns = Vec::new();
+        let mut entry = None;
+        for arg in args {
+            if is_glob(arg) {
+                let pattern = Pattern::parse(arg);
+                let matches = pattern.walk();
+                let Some(first) = matches.first() else {
+                    return Err(format!("no files match '{arg}'"));
+                };
+                entry.get_or_insert_with(|| first.clone());
+                files.extend(matches);
+                patterns.push(pattern);
+                continue;
+            }
+            let path =
+                fs::canonicalize(arg).map_err(|e| format!("entry '{arg}' not found: {e}"))?;
+            entry.get_or_insert_with(|| path.clone());
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+        let entry = entry.ok_or("no paths given")?;
+        let root = files
+            .iter()
+            .filter_map(|f| f.parent())
+            .chain(dirs.iter().map(PathBuf::as_path))
+            .fold(None, |common: Option<PathBuf>, dir| {
+                let Some(mut common) = common else {
+                    return Some(dir.to_path_buf());
+                };
+                while !dir.starts_with(&common) && common.pop() {}
+                Some(common)
+            })
+            .unwrap_or_else(|| PathBuf::from("/"));
+        Ok(Selection {
+            root,
+            entry,
+            files: files.into_iter().collect(),
+            dirs,
+            patterns,
+        })
+    }
+
+    /// Whether the file at `path` was selected, or has since been created
+    /// where a glob matches it.
+    pub fn contains(&self, path: &Path) -> bool {
+        let selected =
+            |p: &Path| self.files.contains(p) || self.dirs.iter().any(|d| p.starts_with(d));
+        if selected(path) {
+            return true;
+        }
+        let Ok(canonical) = fs::canonicalize(path) else {
+            return false;
+        };
+        selected(&canonical) || self.patterns.iter().any(|p| p.matches(&canonical))
+    }
+
+    /// Whether the directory at `dir` holds or lies within the selection,
+    /// or is one a glob reaches into, and so is listed.
+    pub fn shows_dir(&self, dir: &Path) -> bool {
+        let shown = |dir: &Path| {
+            self.files.iter().any(|f| f.starts_with(dir))
+                || self
+                    .dirs
+                    .iter()
+                    .any(|d| d.starts_with(dir) || dir.starts_with(d))
+                || self.patterns.iter().any(|p| p.reaches(dir))
+        };
+        shown(dir) || fs::canonicalize(dir).is_ok_and(|d| shown(&d))
+    }
+}
+
+/// Whether `arg` is a pattern rather than a path: it has a wildcard and
+/// names nothing as written.
+pub fn is_glob(arg: &str) -> bool {
+    arg.contains(['*', '?']) && !Path::new(arg).exists()
+}
+
+/// A glob split into the directory its literal leading segments name and
+/// the segments left to match below it.
+#[derive(Debug)]
+struct Pattern {
+    /// Canonicalized when it exists.
+    base: PathBuf,
+    segments: Vec<String>,
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Pattern {
+        let segments: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+        let literal = segments
+            .iter()
+            .take_while(|s| !s.contains(['*', '?']))
+            .count();
+        let mut base = PathBuf::from(if pattern.starts_with('/') { "/" } else { "." });
+        base.extend(&segments[..literal]);
+        Pattern {
+            base: fs::canonicalize(&base).unwrap_or(base),
+            segments: segments[literal..].iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// The names of `path` below the base, or `None` when it lies
+    /// elsewhere or under a dot-entry or `node_modules`.
+    fn names<'p>(&self, path: &'p Path) -> Option<Vec<&'p str>> {
+        let names = path
+            .strip_prefix(&self.base)
+            .ok()?
+            .iter()
+            .map(|name| name.to_str())
+            .collect::<Option<Vec<&str>>>()?;
+        let hidden = names
+            .iter()
+            .any(|name| name.starts_with('.') || *name == "node_modules");
+        (!hidden).then_some(names)
+    }
+
+    /// Whether the canonical file path `path` matches.
+    fn matches(&self, path: &Path) -> bool {
+        let segments: Vec<&str> = self.segments.iter().map(String::as_str).collect();
+        self.names(path)
+            .is_some_and(|names| matches_segments(&segments, &names))
+    }
+
+    /// Whether the directory `dir` leads to the base or could hold matches.
+    fn reaches(&self, dir: &Path) -> bool {
+        let segments: Vec<&str> = self.segments.iter().map(String::as_str).collect();
+        self.base.starts_with(dir)
+            || self
+                .names(dir)
+                .is_some_and(|names| matches_leading(&segments, &names))
+    }
+
+    /// The files matching, canonicalized, in natural order of their paths.
+    fn walk(&self) -> Vec<PathBuf> {
+        let segments: Vec<&str> = self.segments.iter().map(String::as_str).collect();
+        let mut matches: Vec<(String, PathBuf)> = Vec::new();
+        let mut pending = vec![(self.base.clone(), Vec::<String>::new())];
+        while let Some((dir, rel)) = pending.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+                    continue;
+                };
+                if name.starts_with('.') || name == "node_modules" {
+                    continue;
+                }
+                let mut rel = rel.clone();
+                rel.push(name);
+                let path = entry.path();
+                // Symlinked directories are not walked, so a link back up the
+                // tree cannot loop.
+                match entry.file_type() {
+                    Ok(t) if t.is_dir() => pending.push((path, rel)),
+                    Ok(_) if path.is_file() => {
+                        let names: Vec<&str> = rel.iter().map(String::as_str).collect();
+                        if matches_segments(&segments, &names) {
+                            if let Ok(canonical) = fs::canonicalize(&path) {
+                                matches.push((rel.join("/"), canonical));
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        matches.sort_by(|(a, _), (b, _)| crate::queue::natural_cmp(a, b));
+        matches.into_iter().map(|(_, path)| path).collect()
+    }
+}
+
+/// Whether the path `names` matches the glob `pattern`, segment by segment.
+fn matches_segments(pattern: &[&str], names: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => names.is_empty(),
+        Some((&"**", rest)) => (0..=names.len()).any(|i| matches_segments(rest, &names[i..])),
+        Some((p, rest)) => names.split_first().is_some_and(|(name, tail)| {
+            let (p, name): (Vec<char>, Vec<char>) = (p.chars().collect(), name.chars().collect());
+            matches_name(&p, &name) && matches_segments(rest, tail)
+        }),
+    }
+}
+
+/// Whether the directory path `names` can begin a match of `pattern`.
+fn matches_leading(pattern: &[&str], names: &[&str]) -> bool {
+    match (pattern.split_first(), names.split_first()) {
+        (_, None) => !pattern.is_empty(),
+        (None, Some(_)) => false,
+        (Some((&"**", _)), Some(_)) => true,
+        (Some((p, rest)), Some((name, tail))) => {
+            let (p, name): (Vec<char>, Vec<char>) = (p.chars().collect(), name.chars().collect());
+            matches_name(&p, &name) && matches_leading(rest, tail)
+        }
+    }
+}
+
+/// Whether the file name `name` matches the single-segment `pattern`.
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| matches_name(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && matches_name(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_name(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_match_within_and_across_segments() {
+        let matches = |pattern: &str, path: &str| {
+            let pattern: Vec<&str> = pattern.split('/').collect();
+            let path: Vec<&str> = path.split('/').collect();
+            matches_segments(&pattern, &path)
+        };
+        assert!(matches("*.md", "guide.md"));
+        assert!(!matches("*.md", "api/guide.md"));
+        assert!(matches("**/*.md", "guide.md"));
+        assert!(matches("**/*.md", "api/v2/guide.md"));
+        assert!(matches("api/**", "api/v2/guide.md"));
+        assert!(matches("ch?.md", "ch2.md"));
+        assert!(!matches("ch?.md", "ch10.md"));
+        assert!(!matches("*.md", "guide.markdown"));
+    }
+
+    #[test]
+    fn the_root_holds_every_path_given() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = fs::canonicalize(dir.path()).unwrap();
+        for file in [
+            "docs/api/ch10.md",
+            "docs/api/ch2.md",
+            "docs/guide.md",
+            "notes/a.md",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "# x\n").unwrap();
+        }
+        let arg = |rel: &str| root.join(rel).to_string_lossy().into_owned();
+
+        let sel = Selection::expand(&[arg("docs/api/*.md")]).unwrap();
+        assert_eq!(sel.root, root.join("docs/api"));
+        assert_eq!(sel.entry, root.join("docs/api/ch2.md"));
+        assert!(sel.contains(&root.join("docs/api/ch10.md")));
+        assert!(!sel.contains(&root.join("docs/guide.md")));
+        fs::write(root.join("docs/api/ch3.md"), "# x\n").unwrap();
+        assert!(sel.contains(&root.join("docs/api/ch3.md")), "created later");
+
+        let sel = Selection::expand(&[arg("notes"), arg("docs/guide.md")]).unwrap();
+        assert_eq!(sel.root, root);
+        assert_eq!(sel.entry, root.join("notes"));
+        assert!(sel.contains(&root.join("notes/a.md")));
+        assert!(!sel.contains(&root.join("docs/api/ch2.md")));
+        assert!(sel.shows_dir(&root.join("docs")));
+        assert!(!sel.shows_dir(&root.join("docs/api")));
+
+        let sel = Selection::expand(&[arg("docs/*/ch?.md")]).unwrap();
+        assert!(sel.shows_dir(&root));
+        assert!(sel.shows_dir(&root.join("docs/api")));
+        assert!(!sel.shows_dir(&root.join("notes")));
+
+        assert!(Selection::expand(&[arg("docs/*.txt")])
+            .unwrap_err()
+            .starts_with("no files match"));
+    }
+}
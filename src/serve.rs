@@ -1,23 +1,38 @@
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::net::TcpListener;
+use std::net::{SocketAddr, TcpListener};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use axum::{
-    body::Body,
-    extract::{Request, State},
-    http::{header, StatusCode},
-    response::Response,
+    body::{Body, Bytes},
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     Router,
 };
 use tokio::signal;
+use tokio::sync::{watch, Semaphore};
 use tower_http::compression::CompressionLayer;
-
-use crate::backlinks::BacklinkRef;
-use crate::frontmatter;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+
+use crate::audit::{self, AuditLog};
+use crate::backlinks::{BacklinkRef, MentionRef};
+use crate::binary;
+use crate::control;
+use crate::encoding;
+use crate::frontmatter::{self, FrontmatterMeta};
 use crate::html;
+use crate::html_export::CombinedPart;
+use crate::i18n::Locale;
+use crate::include;
+use crate::selection::Selection;
+use crate::share::{self, Share};
+use crate::vars::{self, Vars};
 use crate::web_assets;
 
 // ---------------------------------------------------------------------------
@@ -112,6 +127,8 @@ pub enum RuntimePlatform {
     #[allow(dead_code)]
     Linux,
     #[allow(dead_code)]
+    Windows,
+    #[allow(dead_code)]
     Other,
 }
 
@@ -143,6 +160,8 @@ pub struct EnvSnapshot {
 /// - **macOS**: `true` unless `SSH_CONNECTION` or `SSH_TTY` is set.
 /// - **Linux**: `true` only when `DISPLAY` or `WAYLAND_DISPLAY` is set, *and*
 ///   none of `SSH_CONNECTION`, `SSH_TTY`, `CI`, or `GITHUB_ACTIONS` is set.
+/// - **Windows**: `true` unless any of `SSH_CONNECTION`, `SSH_TTY`, `CI`, or
+///   `GITHUB_ACTIONS` is set; every interactive session has a desktop.
 /// - **Other**: always `false`.
 ///
 /// This function accepts explicit inputs so it can be exercised in unit tests
@@ -157,6 +176,9 @@ pub fn is_headed_for(platform: RuntimePlatform, env: &EnvSnapshot) -> bool {
                 && !env.ci
                 && !env.github_actions
         }
+        RuntimePlatform::Windows => {
+            !env.ssh_connection && !env.ssh_tty && !env.ci && !env.github_actions
+        }
         RuntimePlatform::Other => false,
     }
 }
@@ -169,27 +191,39 @@ pub fn is_headed_for(platform: RuntimePlatform, env: &EnvSnapshot) -> bool {
 /// This is the production entry point.  For unit testing, call [`is_headed_for`]
 /// directly with a synthetic [`EnvSnapshot`].
 pub fn is_headed_environment() -> bool {
-    fn env_set(key: &str) -> bool {
-        std::env::var_os(key).is_some_and(|v| !v.is_empty())
-    }
+    is_headed_for(RuntimePlatform::current(), &EnvSnapshot::from_env())
+}
 
-    let env = EnvSnapshot {
-        ssh_connection: env_set("SSH_CONNECTION"),
-        ssh_tty: env_set("SSH_TTY"),
-        display: env_set("DISPLAY"),
-        wayland_display: env_set("WAYLAND_DISPLAY"),
-        ci: env_set("CI"),
-        github_actions: env_set("GITHUB_ACTIONS"),
-    };
+impl RuntimePlatform {
+    /// The platform mdmd was built for, via `cfg(target_os = ...)`.
+    pub fn current() -> Self {
+        #[cfg(target_os = "macos")]
+        return RuntimePlatform::MacOs;
+        #[cfg(target_os = "linux")]
+        return RuntimePlatform::Linux;
+        #[cfg(windows)]
+        return RuntimePlatform::Windows;
+        #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+        return RuntimePlatform::Other;
+    }
+}
 
-    #[cfg(target_os = "macos")]
-    let platform = RuntimePlatform::MacOs;
-    #[cfg(target_os = "linux")]
-    let platform = RuntimePlatform::Linux;
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
-    let platform = RuntimePlatform::Other;
+impl EnvSnapshot {
+    /// Snapshot the actual process environment.
+    pub fn from_env() -> Self {
+        fn env_set(key: &str) -> bool {
+            std::env::var_os(key).is_some_and(|v| !v.is_empty())
+        }
 
-    is_headed_for(platform, &env)
+        EnvSnapshot {
+            ssh_connection: env_set("SSH_CONNECTION"),
+            ssh_tty: env_set("SSH_TTY"),
+            display: env_set("DISPLAY"),
+            wayland_display: env_set("WAYLAND_DISPLAY"),
+            ci: env_set("CI"),
+            github_actions: env_set("GITHUB_ACTIONS"),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -208,15 +242,18 @@ pub fn should_attempt_open(no_open: bool, headed: bool) -> bool {
 /// Returns the platform-appropriate command used to open a URL in the default
 /// browser.
 ///
-/// - macOS  → `"open"`
-/// - Linux  → `"xdg-open"`
-/// - Other  → `""` (empty string; treated as "no command available")
+/// - macOS    → `"open"`
+/// - Linux    → `"xdg-open"`
+/// - Windows  → `"cmd"`, run as `cmd /C start "" <url>` by [`open_command_line`]
+/// - Other    → `""` (empty string; treated as "no command available")
 pub fn default_open_command() -> &'static str {
     #[cfg(target_os = "macos")]
     return "open";
     #[cfg(target_os = "linux")]
     return "xdg-open";
-    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    #[cfg(windows)]
+    return "cmd";
+    #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
     return "";
 }
 
@@ -239,18 +276,87 @@ pub fn resolve_open_cmd(env_override: Option<&str>) -> String {
         .unwrap_or_else(|| default_open_command().to_owned())
 }
 
-/// Spawn a child process that opens `url` using `cmd`.
+/// The command line that opens `url`.
+///
+/// `browser` (`--open-browser`) is an application name, optionally followed
+/// by arguments for it such as a profile:
+///
+/// - macOS    → `open -a <app> <url> [--args <arguments>]`
+/// - Windows  → `cmd /C start "" [<app> [<arguments>]] <url>`, also without
+///   a browser, since `start` is a `cmd` builtin
+/// - other    → `<app> [<arguments>] <url>`
+///
+/// A `MDMD_OPEN_CMD` override (`env_override`) takes precedence over
+/// `browser`, so tests never launch a real one; without either, the command
+/// is [`resolve_open_cmd`]'s.
+pub fn open_command_line(
+    platform: RuntimePlatform,
+    env_override: Option<&str>,
+    browser: Option<&str>,
+    url: &str,
+) -> Vec<String> {
+    let mut words = browser.unwrap_or_default().split_whitespace();
+    let app = match (env_override, words.next()) {
+        (None, app) if platform == RuntimePlatform::Windows => {
+            // `start` takes its first quoted argument as the window title.
+            return ["cmd", "/C", "start", ""]
+                .into_iter()
+                .chain(app)
+                .chain(words)
+                .chain(std::iter::once(url))
+                .map(str::to_owned)
+                .collect();
+        }
+        (None, Some(app)) => app,
+        _ => return vec![resolve_open_cmd(env_override), url.to_owned()],
+    };
+    let args = words.map(str::to_owned);
+    match platform {
+        RuntimePlatform::MacOs => {
+            let mut argv: Vec<String> = vec!["open".into(), "-a".into(), app.into(), url.into()];
+            let args: Vec<String> = args.collect();
+            if !args.is_empty() {
+                argv.push("--args".into());
+                argv.extend(args);
+            }
+            argv
+        }
+        _ => std::iter::once(app.to_owned())
+            .chain(args)
+            .chain(std::iter::once(url.to_owned()))
+            .collect(),
+    }
+}
+
+/// Spawn the browser-open command line `argv` (see [`open_command_line`]).
 ///
-/// Returns `Err` immediately if `cmd` is empty or if the spawn fails.
+/// Returns `Err` immediately if the command is empty or if the spawn fails.
 /// The child process is **not** waited on — this is a fire-and-forget call.
-pub fn spawn_browser_open(cmd: &str, url: &str) -> io::Result<std::process::Child> {
-    if cmd.is_empty() {
-        return Err(io::Error::new(
+pub fn spawn_browser_open(argv: &[String]) -> io::Result<std::process::Child> {
+    match argv.split_first() {
+        Some((cmd, args)) if !cmd.is_empty() => std::process::Command::new(cmd).args(args).spawn(),
+        _ => Err(io::Error::new(
             io::ErrorKind::NotFound,
             "no browser-open command for this platform",
-        ));
+        )),
     }
-    std::process::Command::new(cmd).arg(url).spawn()
+}
+
+/// URL path that `--open-path` names: `sub`, relative to the serve root,
+/// with each segment percent-encoded.  A trailing `/` is kept, so a
+/// directory can be opened at its canonical URL.
+pub fn open_url_path(sub: &str) -> String {
+    let segments: Vec<String> = sub
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .map(percent_encode_segment)
+        .collect();
+    let slash = if sub.ends_with('/') && !segments.is_empty() {
+        "/"
+    } else {
+        ""
+    };
+    format!("/{}{slash}", segments.join("/"))
 }
 
 /// Maximum number of consecutive ports to try before giving up.
@@ -259,8 +365,122 @@ const MAX_PORT_ATTEMPTS: u16 = 100;
 /// Maximum file size that will be read and served (16 MiB).
 pub const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
 
-/// Minimal server configuration (extended by later issues).
-pub struct AppConfig;
+/// Server configuration derived from command-line flags.
+#[derive(Debug, Clone, Default)]
+pub struct AppConfig {
+    /// Latency thresholds for verbose-mode `[latency]` warnings.
+    pub latency_budget: LatencyBudget,
+    /// Language of page-shell labels.
+    pub locale: Locale,
+    /// Image served at `/favicon.ico` in place of the embedded default.
+    pub favicon: Option<PathBuf>,
+    /// Site name shown in page titles, page headers, and directory indexes.
+    pub site_title: Option<String>,
+    /// Let readers tick task list checkboxes, writing the change back to the
+    /// markdown file (`--allow-edit`).
+    pub allow_edit: bool,
+    /// List pages marked `draft: true` in their front matter in directory
+    /// indexes, the file tree, search, and backlinks (`--drafts`).  Drafts
+    /// are served by URL either way.
+    pub drafts: bool,
+    /// Substitute `{{var.name}}` placeholders (off with `--no-vars`).
+    pub vars: bool,
+    /// Optional rendering transformations, such as `--numbered-headings`.
+    pub render: html::RenderOptions,
+    /// Directory `mdmd.css` and `mdmd.js` are read from on every request,
+    /// uncached, instead of the embedded copies (`--dev`).
+    pub dev_assets: Option<PathBuf>,
+    /// `Cache-Control` lifetimes for pages and files.
+    pub cache: CachePolicy,
+    /// Caps on request time, concurrency, and body size.
+    pub limits: ServeLimits,
+    /// Fall back to matching request paths against file names ignoring
+    /// case (`--case-insensitive`).
+    pub case_insensitive: bool,
+    /// Serve files that symlinks inside the serve root point to outside it
+    /// (`--follow-symlinks`), instead of refusing them.
+    pub follow_symlinks: bool,
+    /// Directories outside the serve root whose files may be served, read
+    /// only, through symlinks that lead into them (`--allow`).
+    pub allow: Vec<PathBuf>,
+    /// File extensions, lowercase and without the dot, that may be served
+    /// (`--serve-types`); other files are answered with 404.  `None` serves
+    /// every type.
+    pub serve_types: Option<Vec<String>>,
+    /// File to append a line per request to (`--audit-log`); by default
+    /// [`crate::audit::default_path`] when binding to a reachable address.
+    pub audit_log: Option<PathBuf>,
+    /// Keep no audit log at all (`--no-audit-log`).
+    pub no_audit_log: bool,
+    /// Answer only requests carrying a random token, and shut down after
+    /// this long (`--share`; see [`crate::share`]).
+    pub share: Option<Duration>,
+    /// Put the startup URL on the clipboard as well as printing it
+    /// (`--copy-url`).
+    pub copy_url: bool,
+    /// Application, with optional arguments, to open the page in instead of
+    /// the default browser (`--open-browser`).
+    pub open_browser: Option<String>,
+    /// Path under the serve root to open instead of the entry file
+    /// (`--open-path`).
+    pub open_path: Option<String>,
+    /// Unix socket editors send `open`, `scroll`, `reload`, and `quit`
+    /// commands to (`--control`).
+    pub control: Option<PathBuf>,
+}
+
+/// The icon served at `/favicon.ico`: a `--favicon` image read once at
+/// startup, or the embedded default.
+pub struct Favicon {
+    pub bytes: Bytes,
+    pub content_type: &'static str,
+    pub etag: String,
+}
+
+impl Favicon {
+    /// Read the image at `path`, or use the embedded default when `None`.
+    /// Fails on unreadable files and on extensions browsers cannot show as
+    /// icons.
+    pub fn load(path: Option<&Path>) -> Result<Favicon, String> {
+        let Some(path) = path else {
+            return Ok(Favicon::new(
+                Bytes::from_static(web_assets::FAVICON_SVG.as_bytes()),
+                "image/svg+xml",
+            ));
+        };
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let content_type = match ext.as_str() {
+            "ico" => "image/x-icon",
+            "png" => "image/png",
+            "svg" => "image/svg+xml",
+            "gif" => "image/gif",
+            "jpg" | "jpeg" => "image/jpeg",
+            "webp" => "image/webp",
+            _ => {
+                return Err(format!(
+                    "favicon '{}' must be .ico, .png, .svg, .gif, .jpg, or .webp",
+                    path.display()
+                ))
+            }
+        };
+        let bytes = std::fs::read(path)
+            .map_err(|e| format!("favicon '{}' could not be read: {e}", path.display()))?;
+        Ok(Favicon::new(Bytes::from(bytes), content_type))
+    }
+
+    fn new(bytes: Bytes, content_type: &'static str) -> Favicon {
+        let etag = compute_etag(&bytes);
+        Favicon {
+            bytes,
+            content_type,
+            etag,
+        }
+    }
+}
 
 /// Shared application state passed to all request handlers via `Arc<AppState>`.
 pub struct AppState {
@@ -268,61 +488,395 @@ pub struct AppState {
     pub serve_root: PathBuf,
     /// Canonicalized `serve_root` used for symlink-safe containment checks (R1).
     pub canonical_root: PathBuf,
+    /// Canonicalized `--allow` directories, also passing the containment
+    /// check for reads.
+    pub allowed_roots: Vec<PathBuf>,
     /// The primary markdown entry file.
     #[allow(dead_code)]
     pub entry_file: PathBuf,
     /// URL path for the primary entry file (percent-encoded, starts with `/`).
     pub entry_url_path: String,
     /// Server configuration.
-    #[allow(dead_code)]
     pub config: AppConfig,
-    /// Precomputed strong ETag for the embedded CSS asset (`/assets/mdmd.css`).
+    /// Render options for every page: `config.render` with the canonical
+    /// serve root as base path.  Pages apply query overrides to a copy.
+    pub render: html::RenderOptions,
+    /// Precomputed strong ETag for the embedded CSS asset, at both its hashed
+    /// URL and `/assets/mdmd.css`.
     pub css_etag: String,
-    /// Precomputed strong ETag for the embedded JS asset (`/assets/mdmd.js`).
+    /// Precomputed strong ETag for the embedded JS asset, likewise.
     pub js_etag: String,
-    /// `Last-Modified` timestamp for embedded static assets, derived from the
-    /// binary's own modification time.  Falls back to the Unix epoch.
-    pub asset_mtime: SystemTime,
+    /// Icon served at `/favicon.ico`.
+    pub favicon: Favicon,
     /// Startup-built backlinks index: maps root-relative URL path keys
     /// (e.g. `/docs/readme.md`) to all inbound [`BacklinkRef`]s for that page.
     /// Built once at startup; intentionally stale until server restart.
     pub backlinks: HashMap<String, Vec<BacklinkRef>>,
+    /// Startup-built unlinked mentions index, keyed like `backlinks`.
+    /// Equally stale: linking a mention does not remove it until restart.
+    pub mentions: HashMap<String, Vec<MentionRef>>,
     /// When true, request handlers emit per-request diagnostic lines to stderr.
     pub verbose: bool,
+    /// One permit per request that may be handled at once
+    /// ([`ServeLimits::max_in_flight`]).
+    pub in_flight: Arc<Semaphore>,
+    /// The latest command for open pages, which they wait on (see
+    /// [`scroll_wait_handler`]).
+    pub page_commands: watch::Sender<PageCommand>,
+    /// How long a page's wait for a command is held open before it is
+    /// answered with 204 and repeated; kept under the request timeout.
+    pub scroll_wait: Duration,
+    /// Where requests are logged, when they are (see [`crate::audit`]).
+    pub audit: Option<AuditLog>,
+    /// The token requests must carry with `--share`.
+    pub share: Option<Share>,
+    /// The files shown when `serve` was given several paths or a glob;
+    /// `None` shows everything under the root.
+    pub selection: Option<Selection>,
+}
+
+/// A command for open pages: an editor's scroll position, from
+/// `POST /_mdmd/v1/scroll` or `--control`, or a `--control` reload.
+#[derive(Debug, Clone, Default)]
+pub struct PageCommand {
+    /// Increases with every command; 0 before the first.
+    pub seq: u64,
+    /// Canonical path of the file whose pages it is for; `None` for all.
+    pub path: Option<PathBuf>,
+    /// What the pages are sent, with `seq` added:
+    /// [`crate::structure::scroll_target_json`] for a scroll or
+    /// `{"reload": true}`.  Null answers waiting pages with 204, which
+    /// shutdown uses to release them.
+    pub body: serde_json::Value,
+}
+
+/// Longest a page's wait for a scroll request is held open.
+const SCROLL_WAIT: Duration = Duration::from_secs(25);
+
+// ---------------------------------------------------------------------------
+// Latency budgets
+// ---------------------------------------------------------------------------
+
+/// Latency thresholds above which a request logs a `[latency]` warning.
+///
+/// Warnings are verbose-only diagnostics; requests are never rejected for
+/// exceeding a budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyBudget {
+    /// Maximum time spent rendering markdown to HTML.
+    pub render: Duration,
+    /// Maximum time from request receipt until the response is built.
+    pub total: Duration,
+}
+
+impl Default for LatencyBudget {
+    fn default() -> Self {
+        Self {
+            render: Duration::from_millis(100),
+            total: Duration::from_millis(250),
+        }
+    }
 }
 
 // ---------------------------------------------------------------------------
-// Cache validation helpers
+// Request limits
+// ---------------------------------------------------------------------------
+
+/// Caps that keep one client from tying up the server
+/// (`--request-timeout`, `--max-in-flight`, `--max-body-bytes`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServeLimits {
+    /// Longest a request may take before it is answered with 503.
+    pub request_timeout: Duration,
+    /// Most requests handled at once; more are answered with 503 at once
+    /// rather than queued.
+    pub max_in_flight: usize,
+    /// Largest request body accepted; larger ones get 413.
+    pub max_body_bytes: usize,
+}
+
+impl Default for ServeLimits {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(30),
+            max_in_flight: 256,
+            max_body_bytes: 64 * 1024,
+        }
+    }
+}
+
+/// Middleware answering 503 with `Retry-After` when
+/// [`ServeLimits::max_in_flight`] requests are already being handled.
+async fn limit_in_flight(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let Ok(_permit) = state.in_flight.clone().try_acquire_owned() else {
+        vlog!(
+            state.verbose,
+            "[limit] path={} reason=max-in-flight status=503",
+            req.uri().path()
+        );
+        return ServeError::Busy.into_response();
+    };
+    next.run(req).await
+}
+
+// ---------------------------------------------------------------------------
+// Cache-Control policy
+// ---------------------------------------------------------------------------
+
+/// How long browsers may reuse responses before revalidating them
+/// (`--page-max-age`, `--asset-max-age`).  Applied by [`cache_control`] to
+/// every response that does not set `Cache-Control` itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachePolicy {
+    /// Seconds for rendered pages, raw markdown, and directory indexes,
+    /// sent with `must-revalidate`.
+    pub page_max_age: u64,
+    /// Seconds for other files under the serve root.
+    pub asset_max_age: u64,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        Self {
+            page_max_age: 0,
+            asset_max_age: 3600,
+        }
+    }
+}
+
+impl CachePolicy {
+    /// `Cache-Control` for a response to `path` with `status`: `no-store`
+    /// for `/_mdmd/` endpoints and errors, the page policy for paths
+    /// without an extension or ending in `.md`/`.markdown`, and the asset
+    /// policy for everything else.
+    pub fn header_for(&self, path: &str, status: StatusCode) -> String {
+        if path.starts_with("/_mdmd/")
+            || !(status.is_success() || status == StatusCode::NOT_MODIFIED)
+        {
+            return "no-store".to_owned();
+        }
+        let name = path.rsplit('/').next().unwrap_or_default();
+        let is_page = match name.rsplit_once('.') {
+            None => true,
+            Some((_, ext)) => {
+                ext.eq_ignore_ascii_case("md") || ext.eq_ignore_ascii_case("markdown")
+            }
+        };
+        if is_page {
+            format!("max-age={}, must-revalidate", self.page_max_age)
+        } else {
+            format!("max-age={}", self.asset_max_age)
+        }
+    }
+}
+
+/// Middleware adding the [`CachePolicy`] header to responses that lack
+/// one.  Handlers with their own rules (hashed assets, JSON endpoints) set
+/// `Cache-Control` themselves and are left alone.
+async fn cache_control(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let path = req.uri().path().to_owned();
+    let mut response = next.run(req).await;
+    if !response.headers().contains_key(header::CACHE_CONTROL) {
+        let value = state.config.cache.header_for(&path, response.status());
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_str(&value).expect("cache policy header is ASCII"),
+        );
+    }
+    response
+}
+
+// ---------------------------------------------------------------------------
+// Audit log
+// ---------------------------------------------------------------------------
+
+/// Middleware recording each request in the audit log, when one is kept.
+/// Sits inside compression, so sizes are of the uncompressed body.
+async fn audit_request(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let Some(log) = &state.audit else {
+        return next.run(req).await;
+    };
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let method = req.method().clone();
+    // The `--share` token would let anyone reading the log in.
+    let target = req.uri().path_and_query().map_or_else(
+        || req.uri().path().to_owned(),
+        |pq| share::redact(pq.as_str()).into_owned(),
+    );
+    let response = next.run(req).await;
+    log.record(&audit::Entry {
+        time: SystemTime::now(),
+        peer,
+        method: method.as_str(),
+        target: &target,
+        status: response.status().as_u16(),
+        bytes: axum::body::HttpBody::size_hint(response.body()).exact(),
+    });
+    response
+}
+
 // ---------------------------------------------------------------------------
+// Share links
+// ---------------------------------------------------------------------------
+
+/// Middleware refusing requests without the `--share` token.  A request
+/// carrying it in the query is answered with a cookie holding it, so the
+/// page's assets, API calls, and links work without it.
+async fn require_share(State(state): State<Arc<AppState>>, req: Request, next: Next) -> Response {
+    let Some(share) = &state.share else {
+        return next.run(req).await;
+    };
+    if share.remaining().is_zero() {
+        vlog!(
+            state.verbose,
+            "[share] path={} reason=expired",
+            req.uri().path()
+        );
+        return ServeError::Forbidden("this share link has expired").into_response();
+    }
+    let from_cookie = req
+        .headers()
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|cookies| share.in_cookie(cookies));
+    if from_cookie {
+        return next.run(req).await;
+    }
+    if !share.in_query(req.uri().query().unwrap_or_default()) {
+        vlog!(
+            state.verbose,
+            "[share] path={} reason=missing-token",
+            req.uri().path()
+        );
+        return ServeError::Forbidden("open the share link to read this page").into_response();
+    }
+    let mut response = next.run(req).await;
+    response.headers_mut().append(
+        header::SET_COOKIE,
+        HeaderValue::from_str(&share.cookie()).expect("share cookie is ASCII"),
+    );
+    response
+}
+
+/// `url` as a share link when serving with `--share`.
+fn share_link(state: &AppState, url: String) -> String {
+    match &state.share {
+        Some(share) => share.link(&url),
+        None => url,
+    }
+}
 
-/// Compute a 64-bit FNV-1a hash of `data`.
+/// Time spent in each phase of a file-backed request.
 ///
-/// FNV-1a is used here for its speed — it is suitable for cache-validation
-/// ETags but NOT for any cryptographic purpose.  Algorithm:
-///   hash = offset_basis
-///   for each byte: hash ^= byte; hash *= FNV_prime
-/// with `offset_basis` = 14695981039346656037 and `FNV_prime` = 1099511628211
-/// (the standard 64-bit FNV-1a constants).
+/// Phases that do not apply to a route (e.g. `render` for static assets)
+/// stay at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    /// Path decoding, normalisation, fallback resolution, and stat.
+    pub resolve: Duration,
+    /// Reading the file from disk.
+    pub read: Duration,
+    /// Frontmatter extraction and markdown-to-HTML rendering.
+    pub render: Duration,
+    /// Page shell assembly (TOC, backlinks, frontmatter panel).
+    pub shell: Duration,
+}
+
+impl PhaseTimings {
+    /// Return the name and duration of the slowest phase.
+    ///
+    /// Ties resolve to the earliest phase in request order.
+    pub fn dominant(&self) -> (&'static str, Duration) {
+        [
+            ("resolve", self.resolve),
+            ("read", self.read),
+            ("render", self.render),
+            ("shell", self.shell),
+        ]
+        .into_iter()
+        .fold(("resolve", Duration::ZERO), |best, phase| {
+            if phase.1 > best.1 {
+                phase
+            } else {
+                best
+            }
+        })
+    }
+}
+
+/// Format a duration as fractional milliseconds for log output.
+fn fmt_ms(d: Duration) -> String {
+    format!("{:.1}", d.as_secs_f64() * 1000.0)
+}
+
+/// Build a `[latency]` warning line when `timings` or `total` exceed `budget`.
 ///
-/// To change the hash algorithm, replace only this function and update the
-/// comment above — all callers go through `compute_etag`.
-pub fn fnv1a_64(data: &[u8]) -> u64 {
-    // 64-bit FNV-1a constants from the FNV specification.
-    const FNV_PRIME: u64 = 1099511628211;
-    const FNV_OFFSET_BASIS: u64 = 14695981039346656037;
-    let mut hash = FNV_OFFSET_BASIS;
-    for &byte in data {
-        hash ^= byte as u64;
-        hash = hash.wrapping_mul(FNV_PRIME);
+/// Returns `None` when the request stayed within both thresholds.  The line
+/// names every exceeded budget and the phase that dominated the request.
+pub fn latency_warning(
+    path: &str,
+    mode: &str,
+    timings: &PhaseTimings,
+    total: Duration,
+    budget: &LatencyBudget,
+) -> Option<String> {
+    let mut exceeded: Vec<&str> = Vec::new();
+    if total > budget.total {
+        exceeded.push("total");
+    }
+    if timings.render > budget.render {
+        exceeded.push("render");
+    }
+    if exceeded.is_empty() {
+        return None;
+    }
+
+    let (dominant, dominant_time) = timings.dominant();
+    Some(format!(
+        "[latency] WARN path={path} mode={mode} exceeded={} total_ms={} budget_total_ms={} render_ms={} budget_render_ms={} dominant={dominant} dominant_ms={} resolve_ms={} read_ms={} shell_ms={}",
+        exceeded.join(","),
+        fmt_ms(total),
+        fmt_ms(budget.total),
+        fmt_ms(timings.render),
+        fmt_ms(budget.render),
+        fmt_ms(dominant_time),
+        fmt_ms(timings.resolve),
+        fmt_ms(timings.read),
+        fmt_ms(timings.shell),
+    ))
+}
+
+/// Emit a `[latency]` warning for the request when verbose mode is on and a
+/// budget was exceeded.
+fn check_latency_budget(
+    state: &AppState,
+    path: &str,
+    mode: &str,
+    timings: &PhaseTimings,
+    request_start: Instant,
+) {
+    if !state.verbose {
+        return;
+    }
+    let total = request_start.elapsed();
+    if let Some(line) = latency_warning(path, mode, timings, total, &state.config.latency_budget) {
+        eprintln!("{line}");
     }
-    hash
 }
 
+// ---------------------------------------------------------------------------
+// Cache validation helpers
+// ---------------------------------------------------------------------------
+
 /// Format `data` as a strong HTTP ETag: a quoted 16-char hex string.
 ///
 /// Returns a value of the form `"<16 hex chars>"` (strong ETag, RFC 7232 §2.3).
 pub fn compute_etag(data: &[u8]) -> String {
-    format!("\"{:016x}\"", fnv1a_64(data))
+    format!("\"{:016x}\"", web_assets::fnv1a_64(data))
 }
 
 /// Format a `SystemTime` as an RFC 7231 HTTP-date string
@@ -454,15 +1008,28 @@ fn hex_digit(b: u8) -> Result<u8, ()> {
 /// Splits on `/`, ignores empty components and `.`, resolves `..` by popping
 /// the stack.  Returns `None` if a `..` would escape the root (stack underflow),
 /// which signals a path-traversal attempt.
+///
+/// On Windows, where `\` also separates path components and `C:` names a
+/// drive, see [`normalize_path_for`].
 pub fn normalize_path(decoded: &str) -> Option<PathBuf> {
+    normalize_path_for(decoded, cfg!(windows))
+}
+
+/// [`normalize_path`], with Windows path rules when `windows` is set:
+/// `\` (`%5C`) separates components like `/`, so `..\` is caught as
+/// traversal, and a component holding `:` is rejected, since joined to the
+/// root it would name a drive (`C:`) or an alternate data stream.
+pub fn normalize_path_for(decoded: &str, windows: bool) -> Option<PathBuf> {
+    let separators: &[char] = if windows { &['/', '\\'] } else { &['/'] };
     let mut parts: Vec<&str> = Vec::new();
-    for component in decoded.split('/') {
+    for component in decoded.split(separators) {
         match component {
             "" | "." => {}
             ".." => {
                 // Attempted traversal above root → reject.
                 parts.pop()?;
             }
+            name if windows && name.contains(':') => return None,
             name => parts.push(name),
         }
     }
@@ -493,6 +1060,27 @@ pub fn mime_for_ext(ext: &str) -> &'static str {
     }
 }
 
+/// `Content-Disposition` value that downloads the file at `path` under its
+/// own name.
+fn attachment_disposition(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy())
+        .unwrap_or_default()
+        .replace(['"', '\\'], "_")
+        .replace(|c: char| c.is_control(), "_");
+    format!("attachment; filename=\"{name}\"")
+}
+
+/// `path`, relative to the serve root, with its components joined by `/`
+/// whatever the platform's separator, for logs, URLs, and index keys.
+pub fn slash_path(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 /// Percent-encode a single URL path segment (RFC 3986 §2.1 / §3.3).
 ///
 /// Encodes all bytes that are not unreserved characters (ALPHA, DIGIT, `-`, `_`, `.`, `~`)
@@ -596,6 +1184,145 @@ async fn resolve_candidate(candidate: &Path) -> Option<(PathBuf, &'static str)>
     None
 }
 
+/// Under `--case-insensitive`, the path below `root` whose components match
+/// those of `rel` ignoring case, preferring exact matches and otherwise the
+/// first name in sort order.  A last component without an extension may
+/// also match a `.md` file, as in the extensionless fallback.  `None` when
+/// some component has no match.
+async fn case_variant(root: &Path, rel: &Path) -> Option<PathBuf> {
+    let components: Vec<&std::ffi::OsStr> = rel.iter().collect();
+    let mut path = root.to_path_buf();
+    for (i, component) in components.iter().enumerate() {
+        let wanted = component.to_str()?;
+        let last = i + 1 == components.len();
+        let wanted_md =
+            (last && Path::new(wanted).extension().is_none()).then(|| format!("{wanted}.md"));
+        let mut names = Vec::new();
+        let mut rd = tokio::fs::read_dir(&path).await.ok()?;
+        while let Ok(Some(entry)) = rd.next_entry().await {
+            if let Ok(name) = entry.file_name().into_string() {
+                names.push(name);
+            }
+        }
+        names.sort();
+        let matches = |target: &str| {
+            let folded = target.to_lowercase();
+            names
+                .iter()
+                .find(|n| n.as_str() == target)
+                .or_else(|| names.iter().find(|n| n.to_lowercase() == folded))
+        };
+        let name = matches(wanted).or_else(|| wanted_md.as_deref().and_then(matches))?;
+        path.push(name);
+    }
+    Some(path)
+}
+
+/// The canonical URL of a directory's page, `/dir/`, when the request for
+/// `raw_path` reached it another way: without the trailing slash, or by
+/// naming the `README.md` or `index.md` the directory resolves to.  `None`
+/// when `raw_path` is already canonical.
+async fn directory_redirect(
+    raw_path: &str,
+    resolved: &Path,
+    branch: &str,
+    serve_root: &Path,
+) -> Option<String> {
+    match branch {
+        "readme" | "index" | "dir-index" => {
+            (!raw_path.ends_with('/')).then(|| format!("{raw_path}/"))
+        }
+        "exact" => {
+            let name = resolved.file_name()?.to_str()?;
+            if name != "README.md" && name != "index.md" {
+                return None;
+            }
+            let dir = resolved.parent().filter(|dir| *dir != serve_root)?;
+            let (index, _) = resolve_candidate(dir).await?;
+            let slash = raw_path.rfind('/')?;
+            (index == resolved).then(|| raw_path[..=slash].to_owned())
+        }
+        _ => None,
+    }
+}
+
+/// 301 to `location`, keeping the request's `query`.
+fn redirect_response(
+    state: &AppState,
+    norm_display: &str,
+    location: &str,
+    query: &str,
+) -> Response {
+    let location = if query.is_empty() {
+        location.to_owned()
+    } else {
+        format!("{location}?{query}")
+    };
+    vlog!(
+        state.verbose,
+        "[resolve] path={norm_display} branch=redirect location={}",
+        share::redact(&location)
+    );
+    Response::builder()
+        .status(StatusCode::MOVED_PERMANENTLY)
+        .header(header::LOCATION, location)
+        .body(Body::empty())
+        .expect("redirect response builder is infallible")
+}
+
+/// The root holding `canonical`, the symlink-resolved form of a path under
+/// the serve root: the serve root itself or the first `--allow` directory
+/// that contains it.
+fn containing_root<'a>(state: &'a AppState, canonical: &Path) -> Option<&'a Path> {
+    std::iter::once(&state.canonical_root)
+        .chain(&state.allowed_roots)
+        .find(|root| canonical.starts_with(root))
+        .map(PathBuf::as_path)
+}
+
+/// Whether `canonical` may be read: when a root holds it, or under
+/// `--follow-symlinks`.  Request paths cannot climb out of the root with
+/// `..`, so only a symlink can lead outside it.
+fn readable(state: &AppState, canonical: &Path) -> bool {
+    containing_root(state, canonical).is_some() || state.config.follow_symlinks
+}
+
+/// Whether `path` has one of the markdown extensions.
+fn is_markdown_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| matches!(ext, "md" | "markdown" | "mdx" | "mdown" | "mkd" | "mkdn"))
+}
+
+/// Whether `path` is listed and served given the paths `serve` was started
+/// with: markdown files only when selected, other files always.
+fn selected(state: &AppState, path: &Path) -> bool {
+    state
+        .selection
+        .as_ref()
+        .is_none_or(|sel| !is_markdown_path(path) || sel.contains(path))
+}
+
+/// Whether the directory `dir` holds or lies within the selection, and so
+/// is listed.
+fn shown_dir(state: &AppState, dir: &Path) -> bool {
+    state
+        .selection
+        .as_ref()
+        .is_none_or(|sel| sel.shows_dir(dir))
+}
+
+/// Whether `--serve-types` lets `path` be served, judged by its extension
+/// ignoring case.  Files without an extension pass only when no list is set.
+fn servable_type(state: &AppState, path: &Path) -> bool {
+    let Some(types) = &state.config.serve_types else {
+        return true;
+    };
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| types.iter().any(|t| t.eq_ignore_ascii_case(ext)))
+}
+
 // ---------------------------------------------------------------------------
 // Response helpers
 // ---------------------------------------------------------------------------
@@ -610,20 +1337,248 @@ fn not_modified_response(etag: &str, last_modified: &str) -> Response {
         .expect("not_modified_response builder is infallible")
 }
 
-/// 404 Not Found with mandatory security headers.
-fn not_found_response() -> Response {
-    Response::builder()
-        .status(StatusCode::NOT_FOUND)
-        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
-        .header("X-Content-Type-Options", "nosniff")
-        .body(Body::from("Not Found"))
-        .expect("not_found_response builder is infallible")
+// ---------------------------------------------------------------------------
+// Errors
+// ---------------------------------------------------------------------------
+
+/// Why a request failed.  Handlers return these rather than building error
+/// responses, and [`negotiate_error`] renders each as HTML, JSON, or plain
+/// text to suit the request, so every error path looks alike.
+#[derive(Debug, Clone)]
+pub enum ServeError {
+    /// Terse 404 for unresolvable or refused paths.  Never echoes the path,
+    /// so security denials disclose nothing.
+    NotFound,
+    /// Rich 404 for a genuine miss: the requested URL path, `recovery`, the
+    /// HTML links and nearest-parent listing shown on the page, and
+    /// `suggestions`, the URLs of the nearest parent and its entries.
+    Missing {
+        requested: String,
+        recovery: String,
+        suggestions: Vec<String>,
+    },
+    /// 413 for a file over [`MAX_FILE_SIZE`].
+    TooLarge { path: String, size: u64 },
+    /// 400 for a malformed API request body.
+    BadRequest(&'static str),
+    /// 403 for an edit while editing is off, or a request without the
+    /// `--share` token.
+    Forbidden(&'static str),
+    /// 415 for an edit whose body is not JSON.
+    UnsupportedMediaType(&'static str),
+    /// 409 for an edit that no longer applies to the file.
+    Conflict(String),
+    /// 503 when [`ServeLimits::max_in_flight`] requests are being handled.
+    Busy,
+    /// 500 when rendering a page failed unexpectedly.
+    Internal(String),
+    /// An error status produced outside mdmd's handlers, such as a request
+    /// timeout, an oversized body, or an unsupported method.
+    Status(StatusCode),
+}
+
+/// Body format of an error response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    Html,
+    Json,
+    Text,
+}
+
+impl ErrorFormat {
+    /// The format first named in `accept` (by the order written, skipping
+    /// `q=0`), or `None` when it names none of them, as with `*/*`.
+    fn from_accept(accept: &str) -> Option<ErrorFormat> {
+        accept.split(',').find_map(|item| {
+            let mut parts = item.split(';');
+            let media = parts.next().unwrap_or_default().trim();
+            let refused = parts.any(|p| {
+                p.trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            match media.to_ascii_lowercase().as_str() {
+                _ if refused => None,
+                "text/html" | "application/xhtml+xml" => Some(ErrorFormat::Html),
+                "application/json" => Some(ErrorFormat::Json),
+                "text/plain" => Some(ErrorFormat::Text),
+                _ => None,
+            }
+        })
+    }
+}
+
+impl ServeError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ServeError::NotFound | ServeError::Missing { .. } => StatusCode::NOT_FOUND,
+            ServeError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            ServeError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ServeError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ServeError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ServeError::Conflict(_) => StatusCode::CONFLICT,
+            ServeError::Busy => StatusCode::SERVICE_UNAVAILABLE,
+            ServeError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ServeError::Status(status) => *status,
+        }
+    }
+
+    /// The status's name, as in `404 Not Found`.
+    fn title(&self) -> &'static str {
+        match self.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => "Content Too Large",
+            status => status.canonical_reason().unwrap_or("Error"),
+        }
+    }
+
+    /// What went wrong beyond the status, if anything may be said.
+    fn detail(&self) -> Option<String> {
+        match self {
+            ServeError::NotFound | ServeError::Status(_) => None,
+            ServeError::Missing { requested, .. } => Some(requested.clone()),
+            ServeError::TooLarge { path, size } => Some(format!(
+                "{path} ({size} bytes exceeds {MAX_FILE_SIZE} byte limit)"
+            )),
+            ServeError::BadRequest(message)
+            | ServeError::Forbidden(message)
+            | ServeError::UnsupportedMediaType(message) => Some((*message).to_owned()),
+            ServeError::Conflict(message) | ServeError::Internal(message) => Some(message.clone()),
+            ServeError::Busy => Some("too many requests in flight".to_owned()),
+        }
+    }
+
+    /// Format used when the request's `Accept` header names none: JSON under
+    /// `/_mdmd/`, the page for a rich 404, and plain text otherwise.
+    fn default_format(&self, path: &str) -> ErrorFormat {
+        if path.starts_with("/_mdmd/") {
+            ErrorFormat::Json
+        } else if matches!(self, ServeError::Missing { .. }) {
+            ErrorFormat::Html
+        } else {
+            ErrorFormat::Text
+        }
+    }
+
+    fn render(&self, format: ErrorFormat) -> Response {
+        let (content_type, body) = match format {
+            ErrorFormat::Text => (
+                "text/plain; charset=utf-8",
+                match self.detail() {
+                    Some(detail) => format!("{}: {detail}", self.title()),
+                    None => self.title().to_owned(),
+                },
+            ),
+            ErrorFormat::Json => {
+                let mut body = serde_json::json!({ "error": self.title().to_ascii_lowercase() });
+                match self {
+                    ServeError::Missing {
+                        requested,
+                        suggestions,
+                        ..
+                    } => {
+                        body["path"] = requested.as_str().into();
+                        body["suggestions"] = suggestions.as_slice().into();
+                    }
+                    ServeError::TooLarge { path, size } => {
+                        body["path"] = format!("/{path}").into();
+                        body["size"] = (*size).into();
+                        body["limit"] = MAX_FILE_SIZE.into();
+                    }
+                    _ => {
+                        if let Some(detail) = self.detail() {
+                            body["error"] = detail.into();
+                        }
+                    }
+                }
+                ("application/json", body.to_string())
+            }
+            ErrorFormat::Html => ("text/html; charset=utf-8", self.page()),
+        };
+        let mut builder = Response::builder()
+            .status(self.status())
+            .header(header::CONTENT_TYPE, content_type)
+            .header("X-Content-Type-Options", "nosniff");
+        if matches!(self, ServeError::Busy) {
+            builder = builder.header(header::RETRY_AFTER, "1");
+        }
+        let mut response = builder
+            .body(Body::from(body))
+            .expect("error response builder is infallible");
+        response.extensions_mut().insert(self.clone());
+        response
+    }
+
+    /// Standalone HTML error page in the site's stylesheet.
+    fn page(&self) -> String {
+        let heading = format!("{} {}", self.status().as_u16(), self.title());
+        let main = match self {
+            ServeError::Missing {
+                requested,
+                recovery,
+                ..
+            } => format!(
+                "<p>The requested path was not found:</p><pre><code>{}</code></pre>{recovery}",
+                html_escape_text(requested)
+            ),
+            _ => self
+                .detail()
+                .map(|detail| format!("<p>{}</p>", html_escape_text(&detail)))
+                .unwrap_or_default(),
+        };
+        format!(
+            "<!DOCTYPE html>\
+<html lang=\"en\">\
+<head>\
+<meta charset=\"utf-8\">\
+<title>{heading}</title>\
+<link rel=\"stylesheet\" href=\"{css_url}\">\
+</head>\
+<body>\
+<main id=\"mdmd-content\" class=\"content\">\
+<h1>{heading}</h1>\
+{main}\
+</main>\
+</body>\
+</html>",
+            css_url = web_assets::css_url()
+        )
+    }
+}
+
+impl IntoResponse for ServeError {
+    /// The error in its default format for a page request; [`negotiate_error`]
+    /// re-renders it for the actual request.
+    fn into_response(self) -> Response {
+        self.render(self.default_format("/"))
+    }
+}
+
+/// Middleware rendering every error response, from a [`ServeError`] or from
+/// a layer or the router, in the format the request's `Accept` header asks
+/// for, falling back to [`ServeError::default_format`].
+async fn negotiate_error(req: Request, next: Next) -> Response {
+    let path = req.uri().path().to_owned();
+    let accept = req
+        .headers()
+        .get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .and_then(ErrorFormat::from_accept);
+    let response = next.run(req).await;
+    let error = match response.extensions().get::<ServeError>() {
+        Some(error) => error.clone(),
+        None if response.status().is_client_error() || response.status().is_server_error() => {
+            ServeError::Status(response.status())
+        }
+        None => return response,
+    };
+    error.render(accept.unwrap_or_else(|| error.default_format(&path)))
 }
 
 /// Minimal HTML escaping for text content and attribute values.
 ///
 /// Replaces `<`, `>`, `&`, and `"` with their entity equivalents.
-fn html_escape_text(s: &str) -> String {
+pub fn html_escape_text(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for c in s.chars() {
         match c {
@@ -682,19 +1637,21 @@ pub fn nearest_existing_parent(
     }
 }
 
-/// Build an HTML snippet listing the contents of `dir_path` (nearest parent).
+/// The entries of `dir_path` (nearest parent) as `(name, href)` pairs,
+/// with hrefs under `url_prefix`.
 ///
 /// Applies the same policy as the full directory index (dotfile exclusion,
-/// symlink containment, dirs-first alphabetical sort).  Returns an empty
-/// string when the directory cannot be read or is empty after filtering.
-async fn build_nearest_parent_listing(
+/// symlink containment, `--serve-types`, the selection, dirs-first
+/// alphabetical sort).  Empty when the
+/// directory cannot be read.
+async fn nearest_parent_entries(
     state: &Arc<AppState>,
     dir_path: &Path,
     url_prefix: &str,
-) -> String {
+) -> Vec<(String, String)> {
     let mut rd = match tokio::fs::read_dir(dir_path).await {
         Ok(rd) => rd,
-        Err(_) => return String::new(),
+        Err(_) => return Vec::new(),
     };
 
     let mut raw_entries: Vec<(String, bool)> = Vec::new();
@@ -716,7 +1673,7 @@ async fn build_nearest_parent_listing(
                 };
                 if file_type.is_symlink() {
                     match tokio::fs::canonicalize(&entry_path).await {
-                        Ok(target) if target.starts_with(&state.canonical_root) => {}
+                        Ok(target) if readable(state, &target) => {}
                         _ => continue,
                     }
                 }
@@ -724,6 +1681,17 @@ async fn build_nearest_parent_listing(
                     Ok(m) => m.is_dir(),
                     Err(_) => continue,
                 };
+                if !is_dir && !servable_type(state, &entry_path) {
+                    continue;
+                }
+                let listed = if is_dir {
+                    shown_dir(state, &entry_path)
+                } else {
+                    selected(state, &entry_path)
+                };
+                if !listed {
+                    continue;
+                }
                 raw_entries.push((name, is_dir));
             }
             Ok(None) => break,
@@ -731,27 +1699,35 @@ async fn build_nearest_parent_listing(
         }
     }
 
-    let entries = apply_dir_listing_policy(raw_entries);
-    if entries.is_empty() {
-        return String::new();
-    }
-
     let base = if url_prefix.ends_with('/') {
         url_prefix.to_owned()
     } else {
         format!("{url_prefix}/")
     };
+    apply_dir_listing_policy(raw_entries)
+        .into_iter()
+        .map(|(name, is_dir)| {
+            let encoded = percent_encode_segment(&name);
+            let href = if is_dir {
+                format!("{base}{encoded}/")
+            } else {
+                format!("{base}{encoded}")
+            };
+            (name, href)
+        })
+        .collect()
+}
 
+/// HTML snippet listing the nearest parent's `entries`, or an empty string
+/// when there are none.
+fn nearest_parent_listing(url_prefix: &str, entries: &[(String, String)]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
     let prefix_escaped = html_escape_text(url_prefix);
     let mut html = format!("<h2>Contents of {prefix_escaped}</h2><ul>");
-    for (name, is_dir) in &entries {
-        let encoded = percent_encode_segment(name);
-        let href = if *is_dir {
-            format!("{base}{encoded}/")
-        } else {
-            format!("{base}{encoded}")
-        };
-        let href_escaped = html_escape_text(&href);
+    for (name, href) in entries {
+        let href_escaped = html_escape_text(href);
         let name_escaped = html_escape_text(name);
         html.push_str(&format!(
             "<li><a href=\"{href_escaped}\">{name_escaped}</a></li>"
@@ -761,15 +1737,75 @@ async fn build_nearest_parent_listing(
     html
 }
 
-/// Rich HTML 404 response with nearest-parent recovery links.
-///
-/// Renders the requested path, links to the entry document, root index, and
-/// the nearest existing ancestor directory, plus a listing of that directory.
+/// Most "Did you mean" links on a 404.
+const MAX_DID_YOU_MEAN: usize = 3;
+
+/// The `entries` whose names look like a typo of `missing`, best first: a
+/// name whose stem (the name less `.md`/`.markdown`, case-folded) starts
+/// with the other's or vice versa, or lies within an edit distance of a
+/// third of the longer stem.
+fn did_you_mean<'a>(missing: &str, entries: &'a [(String, String)]) -> Vec<&'a (String, String)> {
+    fn stem(name: &str) -> String {
+        let lower = name.to_lowercase();
+        let lower = lower.trim_end_matches('/');
+        lower
+            .strip_suffix(".md")
+            .or_else(|| lower.strip_suffix(".markdown"))
+            .unwrap_or(lower)
+            .to_owned()
+    }
+    let wanted = stem(missing);
+    if wanted.is_empty() {
+        return Vec::new();
+    }
+    let mut scored: Vec<(usize, &(String, String))> = entries
+        .iter()
+        .filter_map(|entry| {
+            let candidate = stem(&entry.0);
+            let (shorter, longer) = if candidate.len() < wanted.len() {
+                (&candidate, &wanted)
+            } else {
+                (&wanted, &candidate)
+            };
+            if shorter.chars().count() >= 3 && longer.starts_with(shorter.as_str()) {
+                return Some((0, entry));
+            }
+            let distance = edit_distance(&wanted, &candidate);
+            (distance > 0 && distance <= longer.chars().count() / 3).then_some((distance, entry))
+        })
+        .collect();
+    scored.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1 .0.cmp(&b.1 .0)));
+    scored
+        .into_iter()
+        .take(MAX_DID_YOU_MEAN)
+        .map(|(_, entry)| entry)
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`, in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The rich 404 for `norm_display`: links to the entry document, root
+/// index, and the nearest existing ancestor directory, "Did you mean" links
+/// to similarly named entries there, plus a listing of that directory.
 ///
-/// Called only for genuine unresolved-path misses.  Security-denial branches
-/// continue to use the terse `not_found_response()` to avoid disclosing
-/// internal path information.
-async fn rich_not_found_response(state: &Arc<AppState>, norm_display: &str) -> Response {
+/// Used only for genuine unresolved-path misses.  Security-denial branches
+/// use the terse [`ServeError::NotFound`] to avoid disclosing internal path
+/// information.
+async fn missing_page(state: &Arc<AppState>, norm_display: &str) -> ServeError {
     let requested_path = format!("/{norm_display}");
 
     // Find nearest existing parent directory within canonical_root.
@@ -785,36 +1821,52 @@ async fn rich_not_found_response(state: &Arc<AppState>, norm_display: &str) -> R
         format!("{parent_url}/")
     };
 
-    // Build directory listing snippet for nearest parent.
-    let listing_html = build_nearest_parent_listing(state, &nearest_parent, &parent_url).await;
+    // List the nearest parent, on the page and as JSON suggestions, with
+    // near misses of the first missing path segment below it first.
+    let entries = nearest_parent_entries(state, &nearest_parent, &parent_url).await;
+    let depth = nearest_parent
+        .strip_prefix(&state.canonical_root)
+        .map_or(0, |rel| rel.components().count());
+    let missing_segment = norm_display.split('/').nth(depth).unwrap_or_default();
+    let near = did_you_mean(missing_segment, &entries);
+    let did_you_mean_html = if near.is_empty() {
+        String::new()
+    } else {
+        let links: String = near
+            .iter()
+            .map(|(name, href)| {
+                format!(
+                    "<li><a href=\"{}\">{}</a></li>",
+                    html_escape_text(href),
+                    html_escape_text(name)
+                )
+            })
+            .collect();
+        format!(
+            "<nav aria-labelledby=\"did-you-mean\"><h2 id=\"did-you-mean\">Did you mean</h2><ul>{links}</ul></nav>"
+        )
+    };
+    let listing_html = nearest_parent_listing(&parent_url, &entries);
+    let mut suggestions: Vec<String> = near.iter().map(|(_, href)| href.clone()).collect();
+    for href in std::iter::once(&parent_url).chain(entries.iter().map(|(_, href)| href)) {
+        if !suggestions.contains(href) {
+            suggestions.push(href.clone());
+        }
+    }
 
-    let requested_escaped = html_escape_text(&requested_path);
     let parent_url_escaped = html_escape_text(&parent_url);
     let entry_url_escaped = html_escape_text(&state.entry_url_path);
-
-    let body = format!(
-        "<!DOCTYPE html>\
-<html lang=\"en\">\
-<head>\
-<meta charset=\"utf-8\">\
-<title>404 Not Found</title>\
-<link rel=\"stylesheet\" href=\"/assets/mdmd.css\">\
-</head>\
-<body>\
-<main class=\"content\">\
-<h1>404 Not Found</h1>\
-<p>The requested path was not found:</p>\
-<pre><code>{requested_escaped}</code></pre>\
-<h2>Recovery options</h2>\
+    let recovery = format!(
+        "{did_you_mean_html}\
+<nav aria-labelledby=\"recovery-options\">\
+<h2 id=\"recovery-options\">Recovery options</h2>\
 <ul>\
 <li><a href=\"/\">Root index</a></li>\
 <li><a href=\"{entry_url_escaped}\">Entry document</a></li>\
 <li><a href=\"{parent_url_escaped}\">Nearest parent: {parent_url_escaped}</a></li>\
 </ul>\
-{listing_html}\
-</main>\
-</body>\
-</html>"
+</nav>\
+{listing_html}"
     );
 
     vlog!(
@@ -827,26 +1879,11 @@ async fn rich_not_found_response(state: &Arc<AppState>, norm_display: &str) -> R
         "[request] path={norm_display} mode=rich_404 nearest_parent={parent_url}"
     );
 
-    Response::builder()
-        .status(StatusCode::NOT_FOUND)
-        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
-        .header("X-Content-Type-Options", "nosniff")
-        .body(Body::from(body))
-        .expect("rich_not_found_response builder is infallible")
-}
-
-/// 413 Content Too Large with mandatory security headers.
-fn too_large_response(norm_path: &str, size: u64) -> Response {
-    let body = format!(
-        "Content Too Large: {} ({} bytes exceeds {} byte limit)",
-        norm_path, size, MAX_FILE_SIZE
-    );
-    Response::builder()
-        .status(StatusCode::PAYLOAD_TOO_LARGE)
-        .header(header::CONTENT_TYPE, "text/plain; charset=utf-8")
-        .header("X-Content-Type-Options", "nosniff")
-        .body(Body::from(body))
-        .expect("too_large_response builder is infallible")
+    ServeError::Missing {
+        requested: requested_path,
+        recovery,
+        suggestions,
+    }
 }
 
 /// Return `true` when the query string contains the `raw=1` parameter.
@@ -857,6 +1894,29 @@ fn is_raw_mode(query: &str) -> bool {
     query.split('&').any(|param| param == "raw=1")
 }
 
+/// `base`, the server's render options, with the per-view overrides in a
+/// page's query string applied: `toc`, `numbered`, and `smart` take `0` or
+/// `1`, and `theme` takes `light` or `dark`.  Unknown values are ignored.
+fn render_options_from_query(base: &html::RenderOptions, query: &str) -> html::RenderOptions {
+    let param = |name: &str| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+    };
+    let flag = |name: &str, default: bool| match param(name) {
+        Some("1") => true,
+        Some("0") => false,
+        _ => default,
+    };
+    html::RenderOptions {
+        sidebar_toc: flag("toc", base.sidebar_toc),
+        numbered_headings: flag("numbered", base.numbered_headings),
+        smart_punctuation: flag("smart", base.smart_punctuation),
+        theme: param("theme").and_then(html::Theme::parse).or(base.theme),
+        ..base.clone()
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Directory listing helpers
 // ---------------------------------------------------------------------------
@@ -885,6 +1945,175 @@ pub fn apply_dir_listing_policy(entries: Vec<(String, bool)>) -> Vec<(String, bo
     filtered
 }
 
+/// One row of a directory index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirListingEntry {
+    pub name: String,
+    pub is_dir: bool,
+    /// File size in bytes; `0` for directories.
+    pub size: u64,
+    pub mtime: Option<SystemTime>,
+    /// Title (else first heading) of a markdown file, if it has one.
+    pub title: Option<String>,
+}
+
+/// Column a directory index is sorted by (`?sort=`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirSortKey {
+    Name,
+    Mtime,
+    Size,
+}
+
+impl DirSortKey {
+    fn param(self) -> &'static str {
+        match self {
+            DirSortKey::Name => "name",
+            DirSortKey::Mtime => "mtime",
+            DirSortKey::Size => "size",
+        }
+    }
+
+    /// Order used when a column is first picked: names A–Z, newest and
+    /// largest first.
+    fn default_descending(self) -> bool {
+        self != DirSortKey::Name
+    }
+}
+
+/// Directory index ordering from `?sort=name|mtime|size&order=asc|desc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirSort {
+    pub key: DirSortKey,
+    pub descending: bool,
+}
+
+impl DirSort {
+    /// Parse the sort parameters of a query string.  A missing or unknown
+    /// `sort` means name; a missing or unknown `order` means the key's
+    /// default order.
+    pub fn from_query(query: &str) -> DirSort {
+        let param = |name: &str| {
+            query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+        };
+        let key = match param("sort") {
+            Some("mtime") => DirSortKey::Mtime,
+            Some("size") => DirSortKey::Size,
+            _ => DirSortKey::Name,
+        };
+        let descending = match param("order") {
+            Some("asc") => false,
+            Some("desc") => true,
+            _ => key.default_descending(),
+        };
+        DirSort { key, descending }
+    }
+
+    /// Query string of the column header link for `key`: flips the order
+    /// when `key` is already the sort column.
+    fn header_query(self, key: DirSortKey) -> String {
+        let descending = if key == self.key {
+            !self.descending
+        } else {
+            key.default_descending()
+        };
+        let order = if descending { "desc" } else { "asc" };
+        format!("?sort={}&amp;order={order}", key.param())
+    }
+}
+
+/// Sort directory index rows.  Directories always precede files; within
+/// each group rows follow `sort`, with case-insensitive name as the tie
+/// breaker, so the default order matches [`apply_dir_listing_policy`].
+pub fn sort_dir_entries(entries: &mut [DirListingEntry], sort: DirSort) {
+    entries.sort_by(|a, b| {
+        let by_key = match sort.key {
+            DirSortKey::Name => std::cmp::Ordering::Equal,
+            DirSortKey::Mtime => a.mtime.cmp(&b.mtime),
+            DirSortKey::Size => a.size.cmp(&b.size),
+        };
+        let by_key = by_key.then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        b.is_dir.cmp(&a.is_dir).then(if sort.descending {
+            by_key.reverse()
+        } else {
+            by_key
+        })
+    });
+}
+
+/// Human-readable file size with binary units (`512 B`, `1.5 KiB`).
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+/// UTC `[year, month, day, hour, minute, second]` of `t`, or `None` before
+/// the epoch.
+pub fn utc_fields(t: SystemTime) -> Option<[u64; 6]> {
+    let secs = t.duration_since(SystemTime::UNIX_EPOCH).ok()?.as_secs();
+    // Howard Hinnant's civil_from_days.
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    Some([
+        year as u64,
+        month as u64,
+        day as u64,
+        secs / 3600 % 24,
+        secs / 60 % 60,
+        secs % 60,
+    ])
+}
+
+/// UTC timestamp as `(datetime attribute, display text)`, e.g.
+/// `("2024-03-09T14:05:00Z", "2024-03-09 14:05")`.
+pub fn format_listing_time(t: SystemTime) -> Option<(String, String)> {
+    let [year, month, day, hour, minute, second] = utc_fields(t)?;
+    Some((
+        format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z"),
+        format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}"),
+    ))
+}
+
+/// Source of a markdown file, read only when it is under [`MAX_FILE_SIZE`].
+async fn markdown_source(path: &Path, size: u64) -> Option<String> {
+    if !is_markdown_path(path) || size > MAX_FILE_SIZE {
+        return None;
+    }
+    tokio::fs::read_to_string(path).await.ok()
+}
+
+/// Title of a markdown source (see [`crate::summary`]), else its first
+/// heading of any level.
+fn markdown_title(source: &str) -> Option<String> {
+    if let Some(title) = crate::summary::extract(source).title {
+        return Some(title);
+    }
+    let extracted = frontmatter::extract(source);
+    html::heading_anchors(extracted.render_body.as_ref())
+        .into_iter()
+        .next()
+        .map(|(heading, _)| heading.text)
+        .filter(|text| !text.is_empty())
+}
+
 /// Build an HTML breadcrumb navigation string from a URL prefix.
 ///
 /// `url_prefix` is either `"/"` (root) or an absolute path like `"/docs/guide"`.
@@ -909,14 +2138,99 @@ fn build_breadcrumbs(url_prefix: &str) -> String {
 // Directory index renderer
 // ---------------------------------------------------------------------------
 
+/// The markdown `body` of the file at `path` as it is rendered: include
+/// directives expanded and, unless `--no-vars`, `{{var.name}}` placeholders
+/// substituted.
+fn render_input<'a>(
+    state: &AppState,
+    path: &Path,
+    meta: Option<&FrontmatterMeta>,
+    body: &'a str,
+) -> include::Expanded<'a> {
+    let mut expanded = include::expand(body, path, Some(&state.canonical_root));
+    if state.config.vars {
+        let vars = Vars::for_document(path, meta, Some(&state.canonical_root));
+        if let Cow::Owned(text) = vars::substitute(&expanded.text, &vars) {
+            expanded.text = Cow::Owned(text);
+        }
+    }
+    expanded
+}
+
+/// Rendered `README.md` (any case) of a listed directory, shown below the
+/// listing the way code hosts do.  Empty when there is no readable README.
+async fn readme_preview_html(
+    state: &AppState,
+    dir_path: &Path,
+    entries: &[DirListingEntry],
+) -> String {
+    let Some(readme) = entries
+        .iter()
+        .find(|e| !e.is_dir && e.name.eq_ignore_ascii_case("README.md") && e.size <= MAX_FILE_SIZE)
+    else {
+        return String::new();
+    };
+    let path = dir_path.join(&readme.name);
+    let Ok(source) = tokio::fs::read_to_string(&path).await else {
+        return String::new();
+    };
+    let extracted = frontmatter::extract(&source);
+    let expanded = render_input(
+        state,
+        &path,
+        extracted.meta.as_ref(),
+        extracted.render_body.as_ref(),
+    );
+    // The preview has its own heading, so its H1 stays in the body.
+    let render = html::RenderOptions {
+        title_from_h1: false,
+        ..state.render.clone()
+    };
+    let rendered = html::render_markdown_with(
+        &expanded.text,
+        &path,
+        html::RenderTarget::Serve,
+        state.verbose,
+        &render,
+    );
+    format!(
+        "<section class=\"readme-preview\" aria-labelledby=\"readme-preview-title\"><h2 id=\"readme-preview-title\" class=\"readme-preview-title\">{}</h2>{}</section>",
+        html_escape_text(&readme.name),
+        html::inject_heading_ids(&rendered.html, &rendered.headings)
+    )
+}
+
+/// Optional site branding (`--site-title`) for generated pages: the suffix
+/// for `<title>` and the header shown above the content, both empty
+/// without a site title.
+fn site_branding(state: &AppState) -> (String, String) {
+    match state.config.site_title.as_deref() {
+        Some(site_title) => {
+            let site_title = html_escape_text(site_title);
+            (
+                format!(" · {site_title}"),
+                format!("<header class=\"site-header\"><a class=\"site-title\" href=\"/\">{site_title}</a></header>"),
+            )
+        }
+        None => (String::new(), String::new()),
+    }
+}
+
 /// Render an HTML directory listing for `dir_path` at URL path `url_prefix`.
 ///
 /// Listing policy (enforced):
 /// - Hidden entries (names starting with `'.'`) are excluded.
+/// - Markdown files marked `draft: true` are excluded unless `--drafts`.
+/// - Files whose extension `--serve-types` does not list are excluded.
+/// - Given several paths or a glob, unselected markdown files and
+///   directories holding none of the selection are excluded.
 /// - Symlinks are included only when their canonicalized target is inside
 ///   `state.canonical_root`; out-of-root symlinks are silently omitted.
-/// - Sorted: directories first (case-insensitive alpha), then files
-///   (case-insensitive alpha).
+/// - Sorted: directories first, then files; within each group by
+///   case-insensitive name unless `query` picks another column
+///   (see [`DirSort::from_query`]).
+/// - Rows show size, modification time, and a markdown file's first heading.
+/// - A `README.md` in the directory is rendered below the listing.
 /// - Each entry's href is built by percent-encoding the name individually
 ///   and appending it to the base URL.  Directory entries get a trailing `"/"`.
 /// - A breadcrumb navigation bar is rendered above the listing.
@@ -926,6 +2240,7 @@ async fn render_directory_index_response(
     state: &AppState,
     dir_path: &Path,
     url_prefix: &str,
+    query: &str,
 ) -> Response {
     let mut rd = match tokio::fs::read_dir(dir_path).await {
         Ok(rd) => rd,
@@ -935,11 +2250,11 @@ async fn render_directory_index_response(
                 "[dir-index] cannot read dir={} err={e}",
                 dir_path.display()
             );
-            return not_found_response();
+            return ServeError::NotFound.into_response();
         }
     };
 
-    let mut raw_entries: Vec<(String, bool)> = Vec::new();
+    let mut entries: Vec<DirListingEntry> = Vec::new();
     loop {
         match rd.next_entry().await {
             Ok(Some(entry)) => {
@@ -948,8 +2263,7 @@ async fn render_directory_index_response(
                     None => continue,
                 };
 
-                // Skip dotfiles — handled by apply_dir_listing_policy, but we also
-                // skip here to avoid unnecessary canonicalize calls.
+                // Skip dotfiles (same policy as apply_dir_listing_policy).
                 if name.starts_with('.') {
                     continue;
                 }
@@ -964,7 +2278,7 @@ async fn render_directory_index_response(
                 };
                 if file_type.is_symlink() {
                     match tokio::fs::canonicalize(&entry_path).await {
-                        Ok(target) if target.starts_with(&state.canonical_root) => {}
+                        Ok(target) if readable(state, &target) => {}
                         _ => {
                             vlog!(
                                 state.verbose,
@@ -976,21 +2290,64 @@ async fn render_directory_index_response(
                     }
                 }
 
-                // Determine if the entry is a directory (follows symlinks).
-                let is_dir = match tokio::fs::metadata(&entry_path).await {
-                    Ok(m) => m.is_dir(),
+                // Stat the entry (follows symlinks) for kind, size, and mtime.
+                let meta = match tokio::fs::metadata(&entry_path).await {
+                    Ok(m) => m,
                     Err(_) => continue,
                 };
-
-                raw_entries.push((name, is_dir));
+                let is_dir = meta.is_dir();
+                if !is_dir && !servable_type(state, &entry_path) {
+                    vlog!(
+                        state.verbose,
+                        "[dir-index] omit unserved type name={name} dir={}",
+                        dir_path.display()
+                    );
+                    continue;
+                }
+                let listed = if is_dir {
+                    shown_dir(state, &entry_path)
+                } else {
+                    selected(state, &entry_path)
+                };
+                if !listed {
+                    vlog!(
+                        state.verbose,
+                        "[dir-index] omit unselected name={name} dir={}",
+                        dir_path.display()
+                    );
+                    continue;
+                }
+                let size = if is_dir { 0 } else { meta.len() };
+                let source = if is_dir {
+                    None
+                } else {
+                    markdown_source(&entry_path, size).await
+                };
+                if !state.config.drafts && source.as_deref().is_some_and(frontmatter::is_draft) {
+                    vlog!(
+                        state.verbose,
+                        "[dir-index] omit draft name={name} dir={}",
+                        dir_path.display()
+                    );
+                    continue;
+                }
+                let title = source.as_deref().and_then(markdown_title);
+
+                entries.push(DirListingEntry {
+                    name,
+                    is_dir,
+                    size,
+                    mtime: meta.modified().ok(),
+                    title,
+                });
             }
             Ok(None) => break,
             Err(_) => break,
         }
     }
 
-    // Apply sort and filter policy.
-    let entries = apply_dir_listing_policy(raw_entries);
+    let sort = DirSort::from_query(query);
+    sort_dir_entries(&mut entries, sort);
 
     // Build breadcrumbs and base href.
     let breadcrumbs = build_breadcrumbs(url_prefix);
@@ -1000,19 +2357,71 @@ async fn render_directory_index_response(
         format!("{url_prefix}/")
     };
 
+    let (title_suffix, site_header) = site_branding(state);
+    let head_tags = state.render.head.tags(None);
+    let css_url = web_assets::css_url();
     let mut body = format!(
-        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\"><title>Index of {url_prefix}</title></head><body><nav>{breadcrumbs}</nav><h1>Index of {url_prefix}</h1><ul>"
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\"><title>Index of {url_prefix}{title_suffix}</title><link rel=\"icon\" href=\"/favicon.ico\"><link rel=\"stylesheet\" href=\"{css_url}\">{head_tags}</head><body>{site_header}<nav aria-label=\"Breadcrumb\">{breadcrumbs}</nav><main id=\"mdmd-content\" class=\"content dir-index\"><h1>Index of {url_prefix}</h1><table class=\"dir-listing\"><thead><tr>"
     );
-    for (name, is_dir) in &entries {
-        let encoded = percent_encode_segment(name);
-        let href = if *is_dir {
+    for (key, label) in [
+        (Some(DirSortKey::Name), "Name"),
+        (None, "Title"),
+        (Some(DirSortKey::Size), "Size"),
+        (Some(DirSortKey::Mtime), "Modified"),
+    ] {
+        match key {
+            Some(key) => {
+                let aria_sort = match (key == sort.key, sort.descending) {
+                    (false, _) => "",
+                    (true, false) => " aria-sort=\"ascending\"",
+                    (true, true) => " aria-sort=\"descending\"",
+                };
+                body.push_str(&format!(
+                    "<th scope=\"col\"{aria_sort}><a href=\"{}\">{label}</a></th>",
+                    sort.header_query(key)
+                ));
+            }
+            None => body.push_str(&format!("<th scope=\"col\">{label}</th>")),
+        }
+    }
+    body.push_str("</tr></thead><tbody>");
+    for entry in &entries {
+        let encoded = percent_encode_segment(&entry.name);
+        let href = if entry.is_dir {
             format!("{base}{encoded}/")
         } else {
             format!("{base}{encoded}")
         };
-        body.push_str(&format!("<li><a href=\"{href}\">{name}</a></li>"));
+        let name = html_escape_text(&entry.name);
+        let title = entry
+            .title
+            .as_deref()
+            .map(html_escape_text)
+            .unwrap_or_default();
+        let size = if entry.is_dir {
+            String::new()
+        } else {
+            format_size(entry.size)
+        };
+        let modified = entry
+            .mtime
+            .and_then(format_listing_time)
+            .map(|(iso, shown)| format!("<time datetime=\"{iso}\">{shown}</time>"))
+            .unwrap_or_default();
+        body.push_str(&format!(
+            "<tr><td><a href=\"{href}\">{name}</a></td><td>{title}</td><td>{size}</td><td>{modified}</td></tr>"
+        ));
     }
-    body.push_str("</ul></body></html>");
+    body.push_str("</tbody></table>");
+    let archive = format!(
+        "/_mdmd/archive?path={}/",
+        open_url_path(url_prefix).trim_end_matches('/')
+    );
+    body.push_str(&format!(
+        "<p class=\"dir-archive\"><a href=\"{archive}\" download>Download as zip</a> · <a href=\"{archive}&amp;raw=1\" download>markdown sources</a></p>"
+    ));
+    body.push_str(&readme_preview_html(state, dir_path, &entries).await);
+    body.push_str("</main></body></html>");
 
     let etag = compute_etag(body.as_bytes());
     vlog!(
@@ -1035,6 +2444,146 @@ async fn render_directory_index_response(
         .expect("dir index response builder is infallible")
 }
 
+/// `Cache-Control` for assets at content-hashed URLs, whose bytes never
+/// change.
+const IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+/// The content-coding to send a precompressed asset in for a request's
+/// `Accept-Encoding`: `br` if accepted, else `gzip`, else `None` for the
+/// uncompressed bytes.  Codings listed with `q=0` are refused.
+fn preferred_encoding(accept_encoding: &str) -> Option<&'static str> {
+    let accepted = |coding: &str| {
+        let mut wildcard = false;
+        for item in accept_encoding.split(',') {
+            let mut parts = item.split(';');
+            let name = parts.next().unwrap_or_default().trim();
+            let refused = parts.any(|p| {
+                p.trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.trim().parse::<f32>().ok())
+                    .is_some_and(|q| q == 0.0)
+            });
+            if name.eq_ignore_ascii_case(coding) {
+                return !refused;
+            }
+            if name == "*" {
+                wildcard = !refused;
+            }
+        }
+        wildcard
+    };
+    if accepted("br") {
+        Some("br")
+    } else if accepted("gzip") {
+        Some("gzip")
+    } else {
+        None
+    }
+}
+
+/// Body and `Content-Encoding` for an embedded asset in `encoding`, taken
+/// from the copies compressed at build time.
+fn precompressed_body(
+    plain: &'static str,
+    compressed: web_assets::Precompressed,
+    encoding: Option<&'static str>,
+) -> (Body, Option<&'static str>) {
+    match encoding {
+        Some("br") => (Body::from(compressed.brotli), encoding),
+        Some("gzip") => (Body::from(compressed.gzip), encoding),
+        _ => (Body::from(plain), None),
+    }
+}
+
+/// Response for an asset held in memory (`/assets/*`, `/favicon.ico`),
+/// honouring `If-None-Match` against the asset's ETag.  Embedded assets
+/// have no meaningful modification time, so no `Last-Modified` is sent.
+/// A `content_encoding` marks `body` as already compressed, which
+/// `CompressionLayer` then leaves alone.
+#[allow(clippy::too_many_arguments)]
+fn embedded_asset_response(
+    state: &AppState,
+    raw_path: &str,
+    etag: &str,
+    content_type: &str,
+    cache_control: &str,
+    body: Body,
+    content_encoding: Option<&str>,
+    if_none_match: Option<&str>,
+) -> Response {
+    if if_none_match.is_some_and(|inm| etag_matches(inm, etag)) {
+        vlog!(
+            state.verbose,
+            "[cache] path={raw_path} etag={etag} status=304"
+        );
+        return Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .body(Body::empty())
+            .expect("asset 304 response builder is infallible");
+    }
+
+    vlog!(
+        state.verbose,
+        "[cache] path={raw_path} etag={etag} status=200"
+    );
+    vlog!(state.verbose, "[request] path={raw_path} mode=asset");
+    let mut builder = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control);
+    if let Some(encoding) = content_encoding {
+        builder = builder
+            .header(header::CONTENT_ENCODING, encoding)
+            .header(header::VARY, "accept-encoding");
+    }
+    builder
+        .body(body)
+        .expect("asset response builder is infallible")
+}
+
+/// Under `--dev`, the stylesheet or script, at either its plain or its
+/// hashed URL, read fresh from
+/// [`AppConfig::dev_assets`] with `Cache-Control: no-cache` and no
+/// validators, so an edit shows on the next reload.  `None` for other paths,
+/// without `--dev`, or when the file cannot be read (the embedded copy is
+/// served instead).
+fn dev_asset_response(state: &AppState, raw_path: &str) -> Option<Response> {
+    let dir = state.config.dev_assets.as_ref()?;
+    let (name, content_type) = match raw_path {
+        path if path == "/assets/mdmd.css" || path == web_assets::css_url() => {
+            ("mdmd.css", "text/css; charset=utf-8")
+        }
+        path if path == "/assets/mdmd.js" || path == web_assets::js_url() => {
+            ("mdmd.js", "text/javascript; charset=utf-8")
+        }
+        _ => return None,
+    };
+    let body = match std::fs::read(dir.join(name)) {
+        Ok(body) => body,
+        Err(e) => {
+            vlog!(
+                state.verbose,
+                "[dev] path={raw_path} error={e} fallback=embedded"
+            );
+            return None;
+        }
+    };
+    vlog!(state.verbose, "[request] path={raw_path} mode=dev_asset");
+    Some(
+        Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, content_type)
+            .header("X-Content-Type-Options", "nosniff")
+            .header(header::CACHE_CONTROL, "no-cache")
+            .body(Body::from(body))
+            .expect("dev asset response builder is infallible"),
+    )
+}
+
 // ---------------------------------------------------------------------------
 // Axum request handler
 // ---------------------------------------------------------------------------
@@ -1044,8 +2593,9 @@ async fn render_directory_index_response(
 /// handling.
 ///
 /// Steps:
-/// 0. Early-exit: `/assets/mdmd.css` and `/assets/mdmd.js` are served from
-///    embedded constants without touching the file system.
+/// 0. Early-exit: `/assets/mdmd.<hash>.css`, `/assets/mdmd.<hash>.js`, their
+///    unhashed forms, and `/favicon.ico` are served from memory without
+///    touching the file system.
 /// 1. Percent-decode the raw request path (before any normalisation).
 /// 2. Normalise: strip `.`/`..` via component iteration; reject traversal above root.
 /// 3. Construct candidate = `serve_root` + normalised path.
@@ -1060,6 +2610,8 @@ async fn render_directory_index_response(
 /// (`If-None-Match`, `If-Modified-Since`) are evaluated and may produce a
 /// 304 Not Modified response with no body.
 async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let request_start = Instant::now();
+    let mut timings = PhaseTimings::default();
     let raw_path = req.uri().path().to_owned();
     let query = req.uri().query().unwrap_or("").to_owned();
 
@@ -1089,82 +2641,66 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
         "none"
     };
     vlog!(state.verbose, "[compression] encoding={compression_enc}");
-
-    // Step 0: serve embedded static assets early — no filesystem access needed.
-    if raw_path == "/assets/mdmd.css" {
-        let etag = &state.css_etag;
-        let last_modified = format_http_date(state.asset_mtime)
-            .unwrap_or_else(|| "Thu, 01 Jan 1970 00:00:00 GMT".to_owned());
-
-        // Evaluate If-None-Match first (RFC 7232 §6 preference order).
-        if let Some(ref inm) = if_none_match {
-            if etag_matches(inm, etag) {
-                vlog!(
-                    state.verbose,
-                    "[cache] path={raw_path} etag={etag} status=304"
-                );
-                return not_modified_response(etag, &last_modified);
-            }
-        } else if let Some(ref ims) = if_modified_since {
-            if not_modified_since(ims, state.asset_mtime) {
-                vlog!(
-                    state.verbose,
-                    "[cache] path={raw_path} etag={etag} status=304"
-                );
-                return not_modified_response(etag, &last_modified);
-            }
-        }
-
-        vlog!(
-            state.verbose,
-            "[cache] path={raw_path} etag={etag} status=200"
-        );
-        vlog!(state.verbose, "[request] path={raw_path} mode=asset");
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "text/css; charset=utf-8")
-            .header("X-Content-Type-Options", "nosniff")
-            .header(header::ETAG, etag.as_str())
-            .header(header::LAST_MODIFIED, last_modified)
-            .body(Body::from(web_assets::CSS))
-            .expect("css asset response builder is infallible");
-    }
-    if raw_path == "/assets/mdmd.js" {
-        let etag = &state.js_etag;
-        let last_modified = format_http_date(state.asset_mtime)
-            .unwrap_or_else(|| "Thu, 01 Jan 1970 00:00:00 GMT".to_owned());
-
-        if let Some(ref inm) = if_none_match {
-            if etag_matches(inm, etag) {
-                vlog!(
-                    state.verbose,
-                    "[cache] path={raw_path} etag={etag} status=304"
-                );
-                return not_modified_response(etag, &last_modified);
-            }
-        } else if let Some(ref ims) = if_modified_since {
-            if not_modified_since(ims, state.asset_mtime) {
-                vlog!(
-                    state.verbose,
-                    "[cache] path={raw_path} etag={etag} status=304"
-                );
-                return not_modified_response(etag, &last_modified);
+    let precompressed_enc = preferred_encoding(accept_encoding);
+
+    // Step 0: serve embedded static assets early — no filesystem access needed,
+    // except under --dev, where the stylesheet and script come from the
+    // source tree.
+    if let Some(response) = dev_asset_response(&state, &raw_path) {
+        return response;
+    }
+    // Pages link the hashed URLs, which are cached for good; the plain ones
+    // stay for anything linking them directly and are revalidated.
+    let css = || {
+        precompressed_body(
+            web_assets::CSS,
+            web_assets::CSS_COMPRESSED,
+            precompressed_enc,
+        )
+    };
+    let js = || precompressed_body(web_assets::JS, web_assets::JS_COMPRESSED, precompressed_enc);
+    if let Some((etag, content_type, cache_control, (body, content_encoding))) =
+        match raw_path.as_str() {
+            path if path == web_assets::css_url() => {
+                Some((&state.css_etag, "text/css; charset=utf-8", IMMUTABLE, css()))
             }
+            path if path == web_assets::js_url() => Some((
+                &state.js_etag,
+                "text/javascript; charset=utf-8",
+                IMMUTABLE,
+                js(),
+            )),
+            "/assets/mdmd.css" => Some((
+                &state.css_etag,
+                "text/css; charset=utf-8",
+                "no-cache",
+                css(),
+            )),
+            "/assets/mdmd.js" => Some((
+                &state.js_etag,
+                "text/javascript; charset=utf-8",
+                "no-cache",
+                js(),
+            )),
+            "/favicon.ico" => Some((
+                &state.favicon.etag,
+                state.favicon.content_type,
+                "no-cache",
+                (Body::from(state.favicon.bytes.clone()), None),
+            )),
+            _ => None,
         }
-
-        vlog!(
-            state.verbose,
-            "[cache] path={raw_path} etag={etag} status=200"
+    {
+        return embedded_asset_response(
+            &state,
+            &raw_path,
+            etag,
+            content_type,
+            cache_control,
+            body,
+            content_encoding,
+            if_none_match.as_deref(),
         );
-        vlog!(state.verbose, "[request] path={raw_path} mode=asset");
-        return Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "text/javascript; charset=utf-8")
-            .header("X-Content-Type-Options", "nosniff")
-            .header(header::ETAG, etag.as_str())
-            .header(header::LAST_MODIFIED, last_modified)
-            .body(Body::from(web_assets::JS))
-            .expect("js asset response builder is infallible");
     }
 
     // Step 1: percent-decode.
@@ -1175,7 +2711,7 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
                 state.verbose,
                 "[resolve] path={raw_path} branch=denied reason=invalid-percent-encoding"
             );
-            return not_found_response();
+            return ServeError::NotFound.into_response();
         }
     };
 
@@ -1185,7 +2721,7 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
             state.verbose,
             "[resolve] path={raw_path} branch=denied reason=null-byte"
         );
-        return not_found_response();
+        return ServeError::NotFound.into_response();
     }
 
     // Step 2: normalise.
@@ -1196,11 +2732,11 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
                 state.verbose,
                 "[resolve] path={raw_path} branch=denied reason=path-traversal"
             );
-            return not_found_response();
+            return ServeError::NotFound.into_response();
         }
     };
 
-    let norm_display = normalized.display().to_string();
+    let norm_display = slash_path(&normalized);
 
     // Step 3: early-exit for root "/" → render root directory index directly,
     // bypassing resolve_candidate() entirely.  This ensures GET / always shows
@@ -1211,11 +2747,28 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
             "[resolve] path=/ branch=dir-index dir={}",
             state.canonical_root.display()
         );
-        return render_directory_index_response(&state, &state.canonical_root, "/").await;
+        return render_directory_index_response(&state, &state.canonical_root, "/", &query).await;
     }
 
     // Non-root paths: construct candidate relative to serve_root.
-    let candidate = state.serve_root.join(&normalized);
+    let mut candidate = state.serve_root.join(&normalized);
+
+    // Step 3b: with --case-insensitive, a path with no exact match may name
+    // a file whose case differs.
+    if state.config.case_insensitive
+        && tokio::fs::metadata(&candidate).await.is_err()
+        && resolve_candidate(&candidate).await.is_none()
+    {
+        if let Some(variant) = case_variant(&state.serve_root, &normalized).await {
+            let matched = slash_path(variant.strip_prefix(&state.serve_root).unwrap_or(&variant));
+            eprintln!("Warning: /{norm_display} matched {matched} by ignoring case");
+            vlog!(
+                state.verbose,
+                "[resolve] path={norm_display} branch=case-variant matched={matched}"
+            );
+            candidate = variant;
+        }
+    }
 
     // Step 4: fallback resolution.
     let (resolved, branch) = match resolve_candidate(&candidate).await {
@@ -1225,20 +2778,39 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
             // render a browsable directory listing instead of returning 404.
             if let Ok(meta) = tokio::fs::metadata(&candidate).await {
                 if meta.is_dir() {
+                    if let Some(location) =
+                        directory_redirect(&raw_path, &candidate, "dir-index", &state.serve_root)
+                            .await
+                    {
+                        return redirect_response(&state, &norm_display, &location, &query);
+                    }
+                    if !shown_dir(&state, &candidate) {
+                        vlog!(
+                            state.verbose,
+                            "[resolve] path={norm_display} branch=denied reason=not-selected"
+                        );
+                        return ServeError::NotFound.into_response();
+                    }
                     let url_prefix = format!("/{norm_display}");
                     vlog!(
                         state.verbose,
                         "[resolve] path={norm_display} branch=dir-index dir={}",
                         candidate.display()
                     );
-                    return render_directory_index_response(&state, &candidate, &url_prefix).await;
+                    return render_directory_index_response(
+                        &state,
+                        &candidate,
+                        &url_prefix,
+                        &query,
+                    )
+                    .await;
                 }
             }
             vlog!(
                 state.verbose,
                 "[resolve] path={norm_display} branch=not-found"
             );
-            return rich_not_found_response(&state, &norm_display).await;
+            return missing_page(&state, &norm_display).await.into_response();
         }
     };
 
@@ -1250,19 +2822,65 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
                 state.verbose,
                 "[resolve] path={norm_display} branch=denied reason=canonicalize-failed"
             );
-            return not_found_response();
+            return ServeError::NotFound.into_response();
         }
     };
 
-    if !canonical.starts_with(&state.canonical_root) {
+    if !readable(&state, &canonical) {
         vlog!(
             state.verbose,
             "[resolve] path={norm_display} branch=denied reason=outside-root canonical={}",
             canonical.display()
         );
-        return not_found_response();
+        return ServeError::NotFound.into_response();
     }
-
+    match containing_root(&state, &canonical) {
+        Some(root) => vlog!(
+            state.verbose,
+            "[resolve] path={norm_display} root={}",
+            root.display()
+        ),
+        None => vlog!(
+            state.verbose,
+            "[resolve] path={norm_display} branch=followed-symlink canonical={}",
+            canonical.display()
+        ),
+    }
+
+    // Step 5b: a directory's page lives at `/dir/` only.
+    if let Some(location) =
+        directory_redirect(&raw_path, &resolved, branch, &state.serve_root).await
+    {
+        return redirect_response(&state, &norm_display, &location, &query);
+    }
+
+    // Step 5c: with --serve-types, only listed extensions are served.
+    if !servable_type(&state, &canonical) {
+        vlog!(
+            state.verbose,
+            "[resolve] path={norm_display} branch=denied reason=type-not-served"
+        );
+        return ServeError::NotFound.into_response();
+    }
+
+    // Step 5d: given several paths, markdown files outside the selection are
+    // not served.  A directory whose README is unselected gets its listing.
+    if !selected(&state, &canonical) {
+        if matches!(branch, "readme" | "index") && shown_dir(&state, &candidate) {
+            vlog!(
+                state.verbose,
+                "[resolve] path={norm_display} branch=dir-index reason=index-not-selected"
+            );
+            let url_prefix = format!("/{norm_display}");
+            return render_directory_index_response(&state, &candidate, &url_prefix, &query).await;
+        }
+        vlog!(
+            state.verbose,
+            "[resolve] path={norm_display} branch=denied reason=not-selected"
+        );
+        return ServeError::NotFound.into_response();
+    }
+
     // Step 6 (R5): file size guard — stat before reading; also capture mtime.
     let file_meta = match tokio::fs::metadata(&canonical).await {
         Ok(m) => m,
@@ -1271,7 +2889,7 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
                 state.verbose,
                 "[resolve] path={norm_display} branch=denied reason=metadata-failed"
             );
-            return not_found_response();
+            return ServeError::NotFound.into_response();
         }
     };
     let size = file_meta.len();
@@ -1282,7 +2900,11 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
             state.verbose,
             "[resolve] path={norm_display} branch=denied reason=too-large size={size}"
         );
-        return too_large_response(&norm_display, size);
+        return ServeError::TooLarge {
+            path: norm_display,
+            size,
+        }
+        .into_response();
     }
 
     vlog!(
@@ -1292,15 +2914,38 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
 
     // Step 7: dispatch on extension.
     let ext = canonical.extension().and_then(|e| e.to_str()).unwrap_or("");
+    timings.resolve = request_start.elapsed();
 
     if ext.eq_ignore_ascii_case("md") {
-        let content = match tokio::fs::read_to_string(&canonical).await {
-            Ok(c) => c,
-            Err(_) => return not_found_response(),
+        let read_start = Instant::now();
+        let bytes = match tokio::fs::read(&canonical).await {
+            Ok(b) => b,
+            Err(_) => return ServeError::NotFound.into_response(),
         };
+        timings.read = read_start.elapsed();
+
+        // A binary file behind a `.md` name is downloaded, never rendered.
+        if binary::looks_binary(&bytes) {
+            vlog!(state.verbose, "[request] path={norm_display} mode=binary");
+            return Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, "application/octet-stream")
+                .header(header::CONTENT_DISPOSITION, attachment_disposition(&canonical))
+                .header("X-Content-Type-Options", "nosniff")
+                .body(Body::from(bytes))
+                .expect("binary response builder is infallible");
+        }
+        let encoding::Decoded {
+            text: content,
+            encoding: source_encoding,
+        } = encoding::decode(&bytes);
+        if let Some(name) = source_encoding {
+            vlog!(state.verbose, "[request] path={norm_display} encoding={name}");
+        }
 
         // ?raw=1 — return the markdown source as plain text.
         if is_raw_mode(&query) {
+            check_latency_budget(&state, &norm_display, "raw", &timings, request_start);
             let body_bytes = content.as_bytes();
             let etag = compute_etag(body_bytes);
             let last_modified = mtime
@@ -1343,39 +2988,122 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
         }
 
         // Default: render as a full HTML page with TOC shell.
+        let render_start = Instant::now();
         let extracted = frontmatter::extract(&content);
-        let (html_body, headings) = html::render_markdown(
-            extracted.render_body.as_ref(),
+        let mut render = render_options_from_query(&state.render, &query);
+        render.editable_tasks = state.config.allow_edit;
+        let expanded = render_input(
+            &state,
             &canonical,
-            &state.canonical_root,
-            html::RenderTarget::Serve,
-            state.verbose,
+            extracted.meta.as_ref(),
+            extracted.render_body.as_ref(),
         );
+        // Rendering may run filters and shortcode scripts, so it runs off
+        // the async workers, where `--request-timeout` can still cut it off.
+        let task = {
+            let text = expanded.text.to_string();
+            let (path, options) = (canonical.clone(), render.clone());
+            let verbose = state.verbose;
+            tokio::task::spawn_blocking(move || {
+                html::render_markdown_with(
+                    &text,
+                    &path,
+                    html::RenderTarget::Serve,
+                    verbose,
+                    &options,
+                )
+            })
+        };
+        let rendered = match task.await {
+            Ok(rendered) => rendered,
+            Err(e) => {
+                vlog!(
+                    state.verbose,
+                    "[request] path={norm_display} mode=render-failed err={e}"
+                );
+                return ServeError::Internal(format!("rendering {norm_display} failed"))
+                    .into_response();
+            }
+        };
+        let html::RenderedMarkdown {
+            html: mut html_body,
+            headings,
+            title,
+            task_lines,
+        } = rendered;
+        if state.config.allow_edit {
+            // Checkbox lines count from the top of the file, frontmatter included.
+            let frontmatter_lines = content[..content.len() - extracted.body.len()]
+                .lines()
+                .count();
+            html_body = html::editable_task_checkboxes(&html_body, &task_lines, |line| {
+                expanded
+                    .source_line(line)
+                    .map(|line| line + frontmatter_lines)
+            });
+        }
+        timings.render = render_start.elapsed();
+        let shell_start = Instant::now();
         let key = crate::backlinks::url_key_from_rel_path(&norm_display);
         let backlinks_slice = state.backlinks.get(&key).map(Vec::as_slice).unwrap_or(&[]);
+        let mentions_slice = state.mentions.get(&key).map(Vec::as_slice).unwrap_or(&[]);
         vlog!(
             state.verbose,
-            "[backlinks] key={key} found={}",
-            backlinks_slice.len()
+            "[backlinks] key={key} found={} mentions={}",
+            backlinks_slice.len(),
+            mentions_slice.len()
+        );
+        let outgoing = crate::outgoing::outgoing_links(
+            &content,
+            &canonical,
+            &state.canonical_root,
+            render.slug_style,
         );
         let file_mtime_secs = mtime
             .and_then(|t| t.duration_since(std::time::SystemTime::UNIX_EPOCH).ok())
             .map(|d| d.as_secs());
+        // Canonical URL from the Host the client used, so unfurls over a
+        // tailnet or intranet name link back to the same address.  Its path
+        // names the file as on disk (`/guide.md` for `/guide`), or the
+        // directory for a README or index page.
+        let page_path = match branch {
+            "readme" | "index" => resolved
+                .parent()
+                .map(|dir| derive_entry_url_path(dir, &state.serve_root).map(|p| format!("{p}/"))),
+            _ => Some(derive_entry_url_path(&resolved, &state.serve_root)),
+        }
+        .and_then(Result::ok)
+        .unwrap_or_else(|| raw_path.clone());
+        let canonical_url = req
+            .headers()
+            .get(header::HOST)
+            .and_then(|v| v.to_str().ok())
+            .map(|host| format!("http://{host}{page_path}"));
         let shell_ctx = html::PageShellContext {
             frontmatter: extracted.meta.as_ref(),
             backlinks: backlinks_slice,
+            mentions: mentions_slice,
+            outgoing: &outgoing,
             file_mtime_secs,
             page_url_path: Some(&norm_display),
             full_width: false,
+            locale: state.config.locale,
+            canonical_url: canonical_url.as_deref(),
+            site_title: state.config.site_title.as_deref(),
+            allow_edit: state.config.allow_edit,
+            page_title: title.as_deref(),
+            encoding: source_encoding,
+            render,
         };
         let page = html::build_page_shell(
             &html_body,
             &headings,
             &canonical,
-            &state.canonical_root,
             &shell_ctx,
             html::RenderTarget::Serve,
         );
+        timings.shell = shell_start.elapsed();
+        check_latency_budget(&state, &norm_display, "rendered", &timings, request_start);
 
         let etag = compute_etag(page.as_bytes());
         let last_modified = mtime
@@ -1417,10 +3145,19 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
             .expect("serve_handler md response builder is infallible")
     } else {
         // Serve as a static asset with the derived MIME type.
+        let read_start = Instant::now();
         let bytes = match tokio::fs::read(&canonical).await {
             Ok(b) => b,
-            Err(_) => return not_found_response(),
+            Err(_) => return ServeError::NotFound.into_response(),
         };
+        timings.read = read_start.elapsed();
+        check_latency_budget(
+            &state,
+            &norm_display,
+            "static_asset",
+            &timings,
+            request_start,
+        );
 
         let etag = compute_etag(&bytes);
         let last_modified = mtime
@@ -1456,12 +3193,20 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
             "[request] path={norm_display} mode=static_asset"
         );
         let content_type = mime_for_ext(ext);
-        Response::builder()
+        let mut builder = Response::builder()
             .status(StatusCode::OK)
             .header(header::CONTENT_TYPE, content_type)
             .header("X-Content-Type-Options", "nosniff")
             .header(header::ETAG, etag)
-            .header(header::LAST_MODIFIED, last_modified)
+            .header(header::LAST_MODIFIED, last_modified);
+        // Files of unknown type are downloaded rather than shown inline.
+        if content_type == "application/octet-stream" {
+            builder = builder.header(
+                header::CONTENT_DISPOSITION,
+                attachment_disposition(&canonical),
+            );
+        }
+        builder
             .body(Body::from(bytes))
             .expect("serve_handler asset response builder is infallible")
     }
@@ -1471,30 +3216,34 @@ async fn serve_handler(State(state): State<Arc<AppState>>, req: Request) -> Resp
 // Freshness endpoint
 // ---------------------------------------------------------------------------
 
-/// JSON 404 response used by the freshness endpoint for all error cases.
-fn freshness_404() -> Response {
-    let body = serde_json::json!({ "error": "not found" }).to_string();
-    Response::builder()
-        .status(StatusCode::NOT_FOUND)
-        .header(header::CONTENT_TYPE, "application/json")
-        .header("X-Content-Type-Options", "nosniff")
-        .body(Body::from(body))
-        .expect("freshness_404 builder is infallible")
+/// Resolve the `path` query parameter of an `/_mdmd/*` API request to a
+/// canonical file inside the serve root.
+///
+/// Applies the same percent-decode → null-byte → normalize → canonicalize →
+/// containment steps as page requests.  Returns the canonical path and its
+/// root-relative display form, or `None` (after logging under `[{key}]`)
+/// when any step fails.
+async fn resolve_api_path(
+    state: &AppState,
+    query: &str,
+    key: &str,
+) -> Option<(std::path::PathBuf, String)> {
+    resolve_api_param(state, query, "path", key).await
 }
 
-/// Handler for `GET /_mdmd/freshness?path=<encoded>`.
-///
-/// Returns `{"mtime":<u64>}` with the file's Unix-epoch modification time.
-/// Returns a JSON 404 on path traversal, outside-root, or file errors.
-async fn freshness_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
-    // Extract the `path` query parameter.
-    let query = req.uri().query().unwrap_or("");
+/// [`resolve_api_path`] for the query parameter `name`.
+async fn resolve_api_param(
+    state: &AppState,
+    query: &str,
+    name: &str,
+    key: &str,
+) -> Option<(std::path::PathBuf, String)> {
     let path_raw = query
         .split('&')
         .find_map(|param| {
             let mut parts = param.splitn(2, '=');
             match (parts.next(), parts.next()) {
-                (Some("path"), Some(v)) => Some(v),
+                (Some(param), Some(v)) if param == name => Some(v),
                 _ => None,
             }
         })
@@ -1506,34 +3255,34 @@ async fn freshness_handler(State(state): State<Arc<AppState>>, req: Request) ->
         Err(_) => {
             vlog!(
                 state.verbose,
-                "[freshness] path={path_raw} reason=invalid-percent-encoding"
+                "[{key}] path={path_raw} reason=invalid-percent-encoding"
             );
-            return freshness_404();
+            return None;
         }
     };
 
     // Reject null bytes.
     if decoded.contains('\0') {
-        vlog!(state.verbose, "[freshness] reason=null-byte");
-        return freshness_404();
+        vlog!(state.verbose, "[{key}] reason=null-byte");
+        return None;
     }
 
     // Step 2: normalize (handles WITH or WITHOUT leading slash).
     let normalized = match normalize_path(&decoded) {
         Some(n) => n,
         None => {
-            vlog!(state.verbose, "[freshness] reason=path-traversal");
-            return freshness_404();
+            vlog!(state.verbose, "[{key}] reason=path-traversal");
+            return None;
         }
     };
 
     // Reject empty path (points to root directory, not a file).
     if normalized == std::path::PathBuf::new() {
-        vlog!(state.verbose, "[freshness] reason=empty-path");
-        return freshness_404();
+        vlog!(state.verbose, "[{key}] reason=empty-path");
+        return None;
     }
 
-    let display_path = normalized.display().to_string();
+    let display_path = slash_path(&normalized);
 
     // Step 3: resolve via canonical_root and canonicalize.
     let candidate = state.canonical_root.join(&normalized);
@@ -1542,21 +3291,53 @@ async fn freshness_handler(State(state): State<Arc<AppState>>, req: Request) ->
         Err(_) => {
             vlog!(
                 state.verbose,
-                "[freshness] path={display_path} reason=canonicalize-failed"
+                "[{key}] path={display_path} reason=canonicalize-failed"
             );
-            return freshness_404();
+            return None;
         }
     };
 
-    // Containment check: must stay within canonical_root.
-    if !canonical.starts_with(&state.canonical_root) {
+    // Containment check: must stay within canonical_root unless
+    // --follow-symlinks allows the symlink's target.
+    if !readable(state, &canonical) {
+        vlog!(
+            state.verbose,
+            "[{key}] path={display_path} reason=outside-root"
+        );
+        return None;
+    }
+    if !selected(state, &canonical) {
+        vlog!(
+            state.verbose,
+            "[{key}] path={display_path} reason=not-selected"
+        );
+        return None;
+    }
+    // Directories (`dir=`) have no type; files must be of a served one.
+    let is_dir = tokio::fs::metadata(&canonical)
+        .await
+        .is_ok_and(|m| m.is_dir());
+    if !is_dir && !servable_type(state, &canonical) {
         vlog!(
             state.verbose,
-            "[freshness] path={display_path} reason=outside-root"
+            "[{key}] path={display_path} reason=type-not-served"
         );
-        return freshness_404();
+        return None;
     }
 
+    Some((canonical, display_path))
+}
+
+/// Handler for `GET /_mdmd/v1/freshness?path=<encoded>` (alias `/_mdmd/freshness`).
+///
+/// Returns `{"mtime":<u64>}` with the file's Unix-epoch modification time.
+/// Returns a JSON 404 on path traversal, outside-root, or file errors.
+async fn freshness_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let query = req.uri().query().unwrap_or("");
+    let Some((canonical, display_path)) = resolve_api_path(&state, query, "freshness").await else {
+        return ServeError::NotFound.into_response();
+    };
+
     // Step 4: stat the file.
     let meta = match tokio::fs::metadata(&canonical).await {
         Ok(m) => m,
@@ -1565,21 +3346,1129 @@ async fn freshness_handler(State(state): State<Arc<AppState>>, req: Request) ->
                 state.verbose,
                 "[freshness] path={display_path} reason=metadata-failed"
             );
-            return freshness_404();
+            return ServeError::NotFound.into_response();
+        }
+    };
+
+    // Extract mtime as Unix seconds (0 if unavailable).
+    let mtime_secs = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    vlog!(
+        state.verbose,
+        "[freshness] path={display_path} mtime={mtime_secs}"
+    );
+
+    let body = serde_json::json!({ "mtime": mtime_secs }).to_string();
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Content-Type-Options", "nosniff")
+        .body(Body::from(body))
+        .expect("freshness_handler response builder is infallible")
+}
+
+// ---------------------------------------------------------------------------
+// Document structure endpoint
+// ---------------------------------------------------------------------------
+
+/// Handler for `GET /_mdmd/v1/doc?path=<encoded>` (alias `/_mdmd/doc`).
+///
+/// Returns the parsed structure of a markdown file as JSON (see
+/// [`crate::structure::document_json`]).  Path handling matches the
+/// freshness endpoint; non-markdown files, files over `MAX_FILE_SIZE`, and
+/// unreadable files also yield the JSON 404.
+async fn doc_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let query = req.uri().query().unwrap_or("");
+    let Some((canonical, display_path)) = resolve_api_path(&state, query, "doc").await else {
+        return ServeError::NotFound.into_response();
+    };
+
+    let ext = canonical.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if !ext.eq_ignore_ascii_case("md") {
+        vlog!(
+            state.verbose,
+            "[doc] path={display_path} reason=not-markdown"
+        );
+        return ServeError::NotFound.into_response();
+    }
+    match tokio::fs::metadata(&canonical).await {
+        Ok(m) if m.is_file() && m.len() <= MAX_FILE_SIZE => {}
+        _ => {
+            vlog!(
+                state.verbose,
+                "[doc] path={display_path} reason=metadata-failed-or-too-large"
+            );
+            return ServeError::NotFound.into_response();
+        }
+    }
+    let content = match tokio::fs::read_to_string(&canonical).await {
+        Ok(c) => c,
+        Err(_) => {
+            vlog!(
+                state.verbose,
+                "[doc] path={display_path} reason=read-failed"
+            );
+            return ServeError::NotFound.into_response();
+        }
+    };
+
+    let url_path = crate::backlinks::url_key_from_rel_path(&display_path);
+    let doc = crate::structure::document_json(&content, &url_path, state.render.slug_style);
+    vlog!(state.verbose, "[doc] path={display_path}");
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(doc.to_string()))
+        .expect("doc_handler response builder is infallible")
+}
+
+// ---------------------------------------------------------------------------
+// Search endpoint
+// ---------------------------------------------------------------------------
+
+/// Maximum number of hits returned by the search endpoint.
+const SEARCH_RESULT_LIMIT: usize = 50;
+
+/// Handler for `GET /_mdmd/v1/search?q=<encoded>` (alias `/_mdmd/search`).
+///
+/// Returns `{"query":..,"results":[{path,title,line,snippet,href}],"truncated":bool}`
+/// for a case-insensitive search across every markdown file under the serve
+/// root.  A missing or blank query yields an empty result list.
+async fn search_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let query = req.uri().query().unwrap_or("");
+    let q_raw = query
+        .split('&')
+        .find_map(|param| {
+            let mut parts = param.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some("q"), Some(v)) => Some(v),
+                _ => None,
+            }
+        })
+        .unwrap_or("");
+
+    // Browsers encode spaces in query strings as `+`.
+    let q = match percent_decode(&q_raw.replace('+', "%20")) {
+        Ok(q) => q,
+        Err(_) => {
+            vlog!(
+                state.verbose,
+                "[search] q={q_raw} reason=invalid-percent-encoding"
+            );
+            String::new()
+        }
+    };
+
+    let task_state = state.clone();
+    let needle = q.clone();
+    let results = tokio::task::spawn_blocking(move || {
+        crate::search::search_root(
+            &task_state.canonical_root,
+            &needle,
+            SEARCH_RESULT_LIMIT,
+            MAX_FILE_SIZE,
+            task_state.config.drafts,
+            task_state.selection.as_ref(),
+            |path| servable_type(&task_state, path),
+        )
+    })
+    .await
+    .unwrap_or_default();
+
+    vlog!(
+        state.verbose,
+        "[search] q={q:?} hits={} truncated={}",
+        results.hits.len(),
+        results.truncated
+    );
+
+    let hits: Vec<serde_json::Value> = results
+        .hits
+        .iter()
+        .map(|h| {
+            serde_json::json!({
+                "path": h.url_path,
+                "title": h.title,
+                "line": h.line,
+                "snippet": h.snippet,
+                "href": h.href,
+            })
+        })
+        .collect();
+    let body = serde_json::json!({
+        "query": q,
+        "results": hits,
+        "truncated": results.truncated,
+    })
+    .to_string();
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(body))
+        .expect("search_handler response builder is infallible")
+}
+
+// ---------------------------------------------------------------------------
+// File tree
+// ---------------------------------------------------------------------------
+
+/// Handler for `GET /_mdmd/v1/tree` (alias `/_mdmd/tree`).
+///
+/// Returns every markdown file under the serve root as a nested tree (see
+/// [`crate::tree::tree_json`]).  With `format=html` the response is instead
+/// the `<ul>` fragment the page shell's file sidebar inserts.
+async fn tree_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let as_html = req
+        .uri()
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .any(|param| param == "format=html");
+
+    let task_state = state.clone();
+    let nodes = tokio::task::spawn_blocking(move || {
+        crate::tree::markdown_tree(
+            &task_state.canonical_root,
+            task_state.config.drafts,
+            task_state.selection.as_ref(),
+        )
+    })
+    .await
+    .unwrap_or_default();
+
+    vlog!(
+        state.verbose,
+        "[tree] top_level={} format={}",
+        nodes.len(),
+        if as_html { "html" } else { "json" }
+    );
+
+    let (content_type, body) = if as_html {
+        ("text/html; charset=utf-8", crate::tree::tree_html(&nodes))
+    } else {
+        (
+            "application/json",
+            crate::tree::tree_json(&nodes).to_string(),
+        )
+    };
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(body))
+        .expect("tree_handler response builder is infallible")
+}
+
+// ---------------------------------------------------------------------------
+// Collection
+// ---------------------------------------------------------------------------
+
+/// URL of the collection page, the entry when `serve` is given several
+/// paths or a glob.
+const COLLECTION_PATH: &str = "/_mdmd/collection";
+
+/// A markdown file listed on the collection page.
+struct CollectionEntry {
+    name: String,
+    href: String,
+    title: Option<String>,
+    summary: Option<String>,
+}
+
+/// The files of the markdown tree grouped by the directory holding them, as
+/// `(directory path, directory href, files)`, a directory's own files before
+/// those of its subdirectories.
+fn collection_groups(
+    state: &AppState,
+    nodes: &[crate::tree::TreeNode],
+    dir: (&str, &str),
+    groups: &mut Vec<(String, String, Vec<CollectionEntry>)>,
+) {
+    let files: Vec<CollectionEntry> = nodes
+        .iter()
+        .filter(|node| node.children.is_none())
+        .map(|node| {
+            let path = state.canonical_root.join(node.path.trim_start_matches('/'));
+            let doc = if std::fs::metadata(&path).is_ok_and(|m| m.len() <= MAX_FILE_SIZE) {
+                std::fs::read_to_string(&path)
+                    .map(|source| crate::summary::extract(&source))
+                    .unwrap_or_default()
+            } else {
+                crate::summary::DocSummary::default()
+            };
+            CollectionEntry {
+                name: node.name.clone(),
+                href: node.href.clone(),
+                title: doc.title,
+                summary: doc.summary,
+            }
+        })
+        .collect();
+    if !files.is_empty() {
+        groups.push((dir.0.to_owned(), dir.1.to_owned(), files));
+    }
+    for node in nodes {
+        if let Some(children) = &node.children {
+            collection_groups(
+                state,
+                children,
+                (node.path.as_str(), node.href.as_str()),
+                groups,
+            );
+        }
+    }
+}
+
+/// Handler for `GET /_mdmd/collection`.
+///
+/// A page listing every served markdown file, grouped by directory, with
+/// its title and summary (see [`crate::summary`]).  It covers what the file
+/// tree does, so given several paths or a glob only the selection, and is
+/// built on each request: files a glob matches after startup are listed.
+async fn collection_handler(State(state): State<Arc<AppState>>) -> Response {
+    let task_state = state.clone();
+    let groups = tokio::task::spawn_blocking(move || {
+        let nodes = crate::tree::markdown_tree(
+            &task_state.canonical_root,
+            task_state.config.drafts,
+            task_state.selection.as_ref(),
+        );
+        let mut groups = Vec::new();
+        collection_groups(&task_state, &nodes, ("/", "/"), &mut groups);
+        groups
+    })
+    .await
+    .unwrap_or_default();
+    let count: usize = groups.iter().map(|(_, _, files)| files.len()).sum();
+
+    let (title_suffix, site_header) = site_branding(&state);
+    let head_tags = state.render.head.tags(None);
+    let css_url = web_assets::css_url();
+    let noun = if count == 1 { "file" } else { "files" };
+    let mut body = format!(
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\"><title>Collection{title_suffix}</title><link rel=\"icon\" href=\"/favicon.ico\"><link rel=\"stylesheet\" href=\"{css_url}\">{head_tags}</head><body>{site_header}<main id=\"mdmd-content\" class=\"content collection\"><h1>Collection</h1><p class=\"collection-count\">{count} markdown {noun}</p>"
+    );
+    for (dir, dir_href, files) in &groups {
+        body.push_str(&format!(
+            "<section><h2><a href=\"{dir_href}\">{}</a></h2><ul class=\"collection-list\">",
+            html_escape_text(dir)
+        ));
+        for file in files {
+            let name = html_escape_text(&file.name);
+            let title = file.title.as_deref().map(html_escape_text);
+            body.push_str(&format!(
+                "<li><a href=\"{}\">{}</a>",
+                file.href,
+                title.as_deref().unwrap_or(&name)
+            ));
+            if title.is_some() {
+                body.push_str(&format!(" <span class=\"collection-name\">{name}</span>"));
+            }
+            if let Some(summary) = &file.summary {
+                body.push_str(&format!("<p>{}</p>", html_escape_text(summary)));
+            }
+            body.push_str("</li>");
+        }
+        body.push_str("</ul></section>");
+    }
+    body.push_str("</main></body></html>");
+
+    vlog!(
+        state.verbose,
+        "[request] path={COLLECTION_PATH} mode=collection groups={} files={count}",
+        groups.len()
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::ETAG, compute_etag(body.as_bytes()))
+        .body(Body::from(body))
+        .expect("collection response builder is infallible")
+}
+
+// ---------------------------------------------------------------------------
+// Archive
+// ---------------------------------------------------------------------------
+
+/// Bytes of zip data gathered before they are sent on as one body chunk.
+const ARCHIVE_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Body chunks an archive may build up ahead of a slow client.
+const ARCHIVE_CHUNKS_AHEAD: usize = 4;
+
+/// [`io::Write`] sink that hands an archive to its response body in
+/// [`ARCHIVE_CHUNK_SIZE`] chunks.  Writes block while the body is
+/// [`ARCHIVE_CHUNKS_AHEAD`] chunks behind, and fail once the client is gone.
+struct ArchiveBody {
+    tx: tokio::sync::mpsc::Sender<io::Result<Vec<u8>>>,
+    buf: Vec<u8>,
+    /// Bytes written so far.
+    size: u64,
+}
+
+impl ArchiveBody {
+    fn send(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(ARCHIVE_CHUNK_SIZE));
+        self.tx
+            .blocking_send(Ok(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))
+    }
+}
+
+impl io::Write for ArchiveBody {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        self.size += data.len() as u64;
+        if self.buf.len() >= ARCHIVE_CHUNK_SIZE {
+            self.send()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send()
+    }
+}
+
+/// Handler for `GET /_mdmd/v1/archive?path=<dir>` (alias `/_mdmd/archive`).
+///
+/// Answers with a zip of the directory at `path`, or of the serve root when
+/// `path` is omitted, named after the directory.  Markdown files become
+/// self-contained HTML pages, as `mdmd html` writes them, beside the
+/// directory's other files; with `raw=1` the markdown sources are included
+/// instead.  The archive holds what directory indexes list, at every
+/// depth: no dotfiles, drafts only with `--drafts`, only `--serve-types`
+/// types, and symlinks only into readable roots.
+///
+/// The zip is streamed as it is built.  A failure partway through, such as
+/// more files than the zip format holds, aborts the response body.
+async fn archive_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let query = req.uri().query().unwrap_or("");
+    let raw = is_raw_mode(query);
+    let whole_root = query
+        .split('&')
+        .find_map(|param| param.strip_prefix("path="))
+        .is_none_or(|p| p.is_empty() || p == "/" || p.eq_ignore_ascii_case("%2F"));
+    let (dir, display_path) = if whole_root {
+        (state.canonical_root.clone(), String::new())
+    } else {
+        match resolve_api_path(&state, query, "archive").await {
+            Some(found) => found,
+            None => return ServeError::NotFound.into_response(),
+        }
+    };
+    if !tokio::fs::metadata(&dir).await.is_ok_and(|m| m.is_dir()) {
+        vlog!(
+            state.verbose,
+            "[archive] path={display_path} reason=not-a-directory"
+        );
+        return ServeError::NotFound.into_response();
+    }
+    if !shown_dir(&state, &dir) {
+        vlog!(
+            state.verbose,
+            "[archive] path={display_path} reason=not-selected"
+        );
+        return ServeError::NotFound.into_response();
+    }
+
+    let name = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("mdmd")
+        .to_owned();
+    let (tx, rx) = tokio::sync::mpsc::channel(ARCHIVE_CHUNKS_AHEAD);
+    let task_state = state.clone();
+    let task_name = name.clone();
+    tokio::task::spawn_blocking(move || {
+        let state = task_state;
+        let body = ArchiveBody {
+            tx: tx.clone(),
+            buf: Vec::with_capacity(ARCHIVE_CHUNK_SIZE),
+            size: 0,
+        };
+        match build_archive(&state, body, &dir, &task_name, raw) {
+            Ok((files, bytes)) => vlog!(
+                state.verbose,
+                "[archive] path={display_path} files={files} bytes={bytes} format={}",
+                if raw { "raw" } else { "html" }
+            ),
+            Err(reason) => {
+                vlog!(
+                    state.verbose,
+                    "[archive] path={display_path} reason=aborted ({reason})"
+                );
+                let _ = tx.blocking_send(Err(io::Error::other(reason)));
+            }
+        }
+    });
+
+    // Header values must be ASCII; anything else in the name becomes `_`.
+    let filename: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/zip")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{filename}.zip\""),
+        )
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from_stream(
+            tokio_stream::wrappers::ReceiverStream::new(rx),
+        ))
+        .expect("archive_handler response builder is infallible")
+}
+
+/// Handler for `GET /_mdmd/v1/combined?dir=<dir>` (alias `/_mdmd/combined`).
+///
+/// Answers with every markdown file under `dir`, or the serve root when
+/// `dir` is omitted, rendered one after another as a single self-contained
+/// page (see [`crate::html_export::combined_page`]), for printing or
+/// searching a whole doc set at once.  The files are those the file tree
+/// lists, in reading order.
+async fn combined_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let query = req.uri().query().unwrap_or("");
+    let whole_root = query
+        .split('&')
+        .find_map(|param| param.strip_prefix("dir="))
+        .is_none_or(|p| p.is_empty() || p == "/" || p.eq_ignore_ascii_case("%2F"));
+    let (dir, display_path) = if whole_root {
+        (state.canonical_root.clone(), String::new())
+    } else {
+        match resolve_api_param(&state, query, "dir", "combined").await {
+            Some(found) => found,
+            None => return ServeError::NotFound.into_response(),
+        }
+    };
+    if !tokio::fs::metadata(&dir).await.is_ok_and(|m| m.is_dir()) {
+        vlog!(
+            state.verbose,
+            "[combined] dir={display_path} reason=not-a-directory"
+        );
+        return ServeError::NotFound.into_response();
+    }
+    if !shown_dir(&state, &dir) {
+        vlog!(
+            state.verbose,
+            "[combined] dir={display_path} reason=not-selected"
+        );
+        return ServeError::NotFound.into_response();
+    }
+
+    let task_state = state.clone();
+    let task_display_path = display_path.clone();
+    let built = tokio::task::spawn_blocking(move || {
+        let state = task_state;
+        let nodes = crate::tree::markdown_tree(&dir, state.config.drafts, state.selection.as_ref());
+        let url_prefix = if task_display_path.is_empty() {
+            String::new()
+        } else {
+            format!("/{task_display_path}")
+        };
+        let parts: Vec<CombinedPart> = crate::html_export::reading_order(&nodes)
+            .into_iter()
+            .map(|node| CombinedPart {
+                path: dir.join(node.path.trim_start_matches('/')),
+                url: format!("{url_prefix}{}", node.path),
+            })
+            .filter(|part| servable_type(&state, &part.path))
+            .filter(|part| std::fs::metadata(&part.path).is_ok_and(|m| m.len() <= MAX_FILE_SIZE))
+            .collect();
+        let title = dir
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("mdmd")
+            .to_owned();
+        let page = crate::html_export::combined_page(
+            &parts,
+            &state.canonical_root,
+            &title,
+            "/",
+            false,
+            state.config.locale,
+            state.config.site_title.as_deref(),
+            state.config.vars,
+            state.render.clone(),
+        );
+        (page, parts.len())
+    })
+    .await;
+    let (page, files) = match built {
+        Ok(built) => built,
+        Err(e) => {
+            return ServeError::Internal(format!("combined page failed: {e}")).into_response()
+        }
+    };
+    vlog!(
+        state.verbose,
+        "[combined] dir={display_path} files={files} bytes={}",
+        page.len()
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/html; charset=utf-8")
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(page))
+        .expect("combined_handler response builder is infallible")
+}
+
+/// Zip the files under `dir` into a folder called `name`, writing the
+/// archive to `body`.  Returns the number of files and bytes written, or why
+/// the archive could not be finished.
+fn build_archive(
+    state: &AppState,
+    body: ArchiveBody,
+    dir: &Path,
+    name: &str,
+    raw: bool,
+) -> Result<(usize, u64), String> {
+    let mut zip = crate::zip::ZipWriter::new(body);
+    let mut files = 0;
+    let mut visited = HashSet::new();
+    let mut pending = vec![(dir.to_path_buf(), name.to_owned())];
+    while let Some((dir, prefix)) = pending.pop() {
+        // A symlink back up the tree must not be walked forever.
+        if !std::fs::canonicalize(&dir).is_ok_and(|c| visited.insert(c)) {
+            continue;
+        }
+        let Ok(rd) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<_> = rd.filter_map(Result::ok).collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let Some(entry_name) = entry.file_name().to_str().map(str::to_owned) else {
+                continue;
+            };
+            if entry_name.starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|t| t.is_symlink())
+                && !std::fs::canonicalize(&path).is_ok_and(|target| readable(state, &target))
+            {
+                continue;
+            }
+            let Ok(meta) = std::fs::metadata(&path) else {
+                continue;
+            };
+            let rel = format!("{prefix}/{entry_name}");
+            if meta.is_dir() {
+                if shown_dir(state, &path) {
+                    pending.push((path, rel));
+                }
+                continue;
+            }
+            if !servable_type(state, &path) || !selected(state, &path) || meta.len() > MAX_FILE_SIZE
+            {
+                continue;
+            }
+            let Ok(data) = std::fs::read(&path) else {
+                continue;
+            };
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            let source = if is_markdown_path(&path) {
+                std::str::from_utf8(&data).ok()
+            } else {
+                None
+            };
+            if !state.config.drafts && source.is_some_and(frontmatter::is_draft) {
+                continue;
+            }
+            match source.filter(|_| !raw) {
+                Some(source) => {
+                    let page = crate::html_export::standalone_page(
+                        source,
+                        &path,
+                        Some(&state.canonical_root),
+                        false,
+                        state.config.locale,
+                        state.config.site_title.as_deref(),
+                        state.config.vars,
+                        state.render.clone(),
+                    );
+                    let rel = Path::new(&rel).with_extension("html");
+                    zip.add(&slash_path(&rel), modified, page.as_bytes())?;
+                }
+                None => zip.add(&rel, modified, &data)?,
+            }
+            files += 1;
+        }
+    }
+    Ok((files, zip.finish()?.size))
+}
+
+// ---------------------------------------------------------------------------
+// Task toggling
+// ---------------------------------------------------------------------------
+
+/// The 403 or 415 for an edit request the server must refuse: editing is
+/// off, or the body is not `application/json`.
+fn refuse_edit(state: &AppState, req: &Request, key: &str) -> Option<ServeError> {
+    if !state.config.allow_edit {
+        vlog!(state.verbose, "[{key}] reason=editing-disabled");
+        return Some(ServeError::Forbidden(
+            "editing is disabled; start mdmd serve with --allow-edit",
+        ));
+    }
+    refuse_non_json(req)
+}
+
+/// The 415 for a POST whose body is not `application/json`.  Requiring it
+/// means a cross-site page cannot send the request without a CORS
+/// preflight, which is never granted.
+fn refuse_non_json(req: &Request) -> Option<ServeError> {
+    let is_json = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !is_json {
+        return Some(ServeError::UnsupportedMediaType(
+            "expected an application/json body",
+        ));
+    }
+    None
+}
+
+/// Largest request body accepted by the edit endpoints.
+const EDIT_MAX_BODY: usize = 4 * 1024;
+
+/// Handler for `POST /_mdmd/v1/toggle-task?path=<encoded>` (alias
+/// `/_mdmd/toggle-task`).
+///
+/// The JSON body `{"line": N, "checked": bool}` names the task's 1-based
+/// source line and the state the page showed; the checkbox is flipped with
+/// [`crate::tasks::toggle_task`] and the response is the file's new
+/// `{"mtime": secs}`.  Refused with 403 unless the server was started with
+/// `--allow-edit`.  Requiring an `application/json` body means a cross-site
+/// page cannot send the request without a CORS preflight, which is never
+/// granted.
+async fn toggle_task_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    if let Some(refused) = refuse_edit(&state, &req, "toggle-task") {
+        return refused.into_response();
+    }
+
+    let query = req.uri().query().unwrap_or("").to_owned();
+    let Some((canonical, display_path)) = resolve_api_path(&state, &query, "toggle-task").await
+    else {
+        return ServeError::NotFound.into_response();
+    };
+    if !canonical.starts_with(&state.canonical_root) {
+        vlog!(
+            state.verbose,
+            "[toggle-task] path={display_path} reason=edit-outside-root"
+        );
+        return ServeError::NotFound.into_response();
+    }
+    let ext = canonical.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if !ext.eq_ignore_ascii_case("md") {
+        vlog!(
+            state.verbose,
+            "[toggle-task] path={display_path} reason=not-markdown"
+        );
+        return ServeError::NotFound.into_response();
+    }
+
+    let request: Option<(usize, bool)> = axum::body::to_bytes(req.into_body(), EDIT_MAX_BODY)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        .and_then(|body| {
+            let line = body["line"].as_u64().filter(|&l| l > 0)?;
+            Some((line as usize, body["checked"].as_bool()?))
+        });
+    let Some((line, checked)) = request else {
+        return ServeError::BadRequest(
+            "expected a body of {\"line\": <1-based line>, \"checked\": <bool>}",
+        )
+        .into_response();
+    };
+
+    let target = canonical.clone();
+    let result =
+        tokio::task::spawn_blocking(move || crate::tasks::toggle_task(&target, line, checked))
+            .await
+            .unwrap_or_else(|e| Err(e.to_string()));
+    if let Err(e) = result {
+        vlog!(
+            state.verbose,
+            "[toggle-task] path={display_path} line={line} reason={e}"
+        );
+        return ServeError::Conflict(e).into_response();
+    }
+
+    let mtime_secs = tokio::fs::metadata(&canonical)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    vlog!(
+        state.verbose,
+        "[toggle-task] path={display_path} line={line} checked={} mtime={mtime_secs}",
+        !checked
+    );
+
+    let body = serde_json::json!({ "mtime": mtime_secs, "checked": !checked }).to_string();
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(body))
+        .expect("toggle_task_handler response builder is infallible")
+}
+
+// ---------------------------------------------------------------------------
+// Editor scroll sync
+// ---------------------------------------------------------------------------
+
+/// Handler for `POST /_mdmd/v1/scroll?path=<encoded>` (alias
+/// `/_mdmd/scroll`).
+///
+/// The JSON body `{"line": N}` names the 1-based source line an editor is
+/// showing.  Pages open on the file scroll to it (see
+/// [`scroll_wait_handler`]); the response is the position they are sent,
+/// from [`crate::structure::scroll_target_json`] plus its `seq`.  Nothing
+/// is written, so `--allow-edit` is not needed.
+async fn scroll_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    if let Some(refused) = refuse_non_json(&req) {
+        return refused.into_response();
+    }
+
+    let query = req.uri().query().unwrap_or("").to_owned();
+    let Some((canonical, display_path)) = resolve_api_path(&state, &query, "scroll").await else {
+        return ServeError::NotFound.into_response();
+    };
+    let ext = canonical.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if !ext.eq_ignore_ascii_case("md") {
+        vlog!(
+            state.verbose,
+            "[scroll] path={display_path} reason=not-markdown"
+        );
+        return ServeError::NotFound.into_response();
+    }
+
+    let line = axum::body::to_bytes(req.into_body(), EDIT_MAX_BODY)
+        .await
+        .ok()
+        .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        .and_then(|body| body["line"].as_u64().filter(|&l| l > 0));
+    let Some(line) = line else {
+        return ServeError::BadRequest("expected a body of {\"line\": <1-based line>}")
+            .into_response();
+    };
+
+    let content = match tokio::fs::read(&canonical).await {
+        Ok(bytes) => encoding::decode(&bytes).text,
+        Err(_) => {
+            vlog!(
+                state.verbose,
+                "[scroll] path={display_path} reason=read-failed"
+            );
+            return ServeError::NotFound.into_response();
+        }
+    };
+    let target =
+        crate::structure::scroll_target_json(&content, line as usize, state.render.slug_style);
+    let target = send_page_command(&state, Some(canonical), target);
+    vlog!(
+        state.verbose,
+        "[scroll] path={display_path} line={line} seq={}",
+        target["seq"]
+    );
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(target.to_string()))
+        .expect("scroll_handler response builder is infallible")
+}
+
+/// Send `body` to the open pages of `path`, or of every file when `None`,
+/// returning it with its `seq` added.
+fn send_page_command(
+    state: &AppState,
+    path: Option<PathBuf>,
+    mut body: serde_json::Value,
+) -> serde_json::Value {
+    state.page_commands.send_modify(|latest| {
+        latest.seq += 1;
+        body["seq"] = latest.seq.into();
+        latest.path = path;
+        latest.body = body.clone();
+    });
+    body
+}
+
+/// Handler for `GET /_mdmd/v1/scroll?path=<encoded>&after=<seq>`, which
+/// pages long-poll: answers with the first command for `path` newer than
+/// `after` as soon as there is one, or with 204 after
+/// [`AppState::scroll_wait`].  Without `after`, only commands sent from
+/// now on are waited for, so a page does not replay the reload that
+/// loaded it.
+async fn scroll_wait_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    let query = req.uri().query().unwrap_or("");
+    let Some((canonical, _)) = resolve_api_path(&state, query, "scroll").await else {
+        return ServeError::NotFound.into_response();
+    };
+    let mut latest = state.page_commands.subscribe();
+    let after: u64 = query
+        .split('&')
+        .find_map(|param| param.strip_prefix("after="))
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| latest.borrow().seq);
+
+    let wait = async {
+        loop {
+            {
+                let command = latest.borrow_and_update();
+                if command.seq > after && command.path.as_ref().is_none_or(|p| *p == canonical) {
+                    return Some(command.body.clone());
+                }
+            }
+            latest.changed().await.ok()?;
         }
     };
+    match tokio::time::timeout(state.scroll_wait, wait).await {
+        Ok(Some(target)) if !target.is_null() => Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("X-Content-Type-Options", "nosniff")
+            .header(header::CACHE_CONTROL, "no-store")
+            .body(Body::from(target.to_string()))
+            .expect("scroll_wait_handler response builder is infallible"),
+        _ => Response::builder()
+            .status(StatusCode::NO_CONTENT)
+            .header(header::CACHE_CONTROL, "no-store")
+            .body(Body::empty())
+            .expect("scroll_wait_handler response builder is infallible"),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Editor control socket
+// ---------------------------------------------------------------------------
+
+/// How often the `--control` socket is checked for commands.
+const CONTROL_POLL: Duration = Duration::from_millis(50);
+
+/// Carry out the commands sent to the `--control` socket, returning on
+/// `quit`.  `scroll` without a file scrolls the entry file's pages; `open`
+/// opens a file's page in the browser.
+async fn follow_control(state: Arc<AppState>, listener: control::Listener, port: u16) {
+    let mut tick = tokio::time::interval(CONTROL_POLL);
+    loop {
+        tick.tick().await;
+        let commands: Vec<control::Command> = listener.pending().collect();
+        for command in commands {
+            match command {
+                control::Command::Scroll { path, line } => {
+                    let canonical = match path {
+                        Some(path) => match tokio::fs::canonicalize(&path).await {
+                            Ok(canonical) => canonical,
+                            Err(_) => {
+                                vlog!(
+                                    state.verbose,
+                                    "[control] scroll path={} reason=not-found",
+                                    path.display()
+                                );
+                                continue;
+                            }
+                        },
+                        None => state.entry_file.clone(),
+                    };
+                    let Ok(bytes) = tokio::fs::read(&canonical).await else {
+                        vlog!(
+                            state.verbose,
+                            "[control] scroll path={} reason=read-failed",
+                            canonical.display()
+                        );
+                        continue;
+                    };
+                    let content = encoding::decode(&bytes).text;
+                    let target = crate::structure::scroll_target_json(
+                        &content,
+                        line,
+                        state.render.slug_style,
+                    );
+                    let target = send_page_command(&state, Some(canonical.clone()), target);
+                    vlog!(
+                        state.verbose,
+                        "[control] scroll path={} line={line} seq={}",
+                        canonical.display(),
+                        target["seq"]
+                    );
+                }
+                control::Command::Reload => {
+                    let body = send_page_command(&state, None, serde_json::json!({"reload": true}));
+                    vlog!(state.verbose, "[control] reload seq={}", body["seq"]);
+                }
+                control::Command::Open(path) => {
+                    let Ok(canonical) = tokio::fs::canonicalize(&path).await else {
+                        vlog!(
+                            state.verbose,
+                            "[control] open path={} reason=not-found",
+                            path.display()
+                        );
+                        continue;
+                    };
+                    let Ok(url_path) = derive_entry_url_path(&canonical, &state.canonical_root)
+                    else {
+                        vlog!(
+                            state.verbose,
+                            "[control] open path={} reason=outside-root",
+                            path.display()
+                        );
+                        continue;
+                    };
+                    let url = share_link(&state, format!("http://127.0.0.1:{port}{url_path}"));
+                    let argv = open_command_line(
+                        RuntimePlatform::current(),
+                        std::env::var("MDMD_OPEN_CMD").ok().as_deref(),
+                        state.config.open_browser.as_deref(),
+                        &url,
+                    );
+                    match spawn_browser_open(&argv) {
+                        Ok(_) => vlog!(state.verbose, "[control] open {url}"),
+                        Err(e) => vlog!(state.verbose, "[control] open failed: {e}"),
+                    }
+                }
+                control::Command::Quit => {
+                    vlog!(state.verbose, "[control] quit");
+                    return;
+                }
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Mention linking
+// ---------------------------------------------------------------------------
+
+/// Handler for `POST /_mdmd/v1/link-mention?path=<encoded>` (alias
+/// `/_mdmd/link-mention`).
+///
+/// `path` names the mentioning document.  The JSON body
+/// `{"target": "/page.md", "line": N, "column": N, "text": "..."}` names the
+/// mentioned page and the mention as listed in its backlinks panel; the
+/// mention is rewritten to a relative link with
+/// [`crate::backlinks::link_mention_in_source`] and the response is the
+/// source file's new `{"mtime": secs}`.  Only mentions in the startup index
+/// can be linked.  Refused like toggle-task without `--allow-edit`.
+async fn link_mention_handler(State(state): State<Arc<AppState>>, req: Request) -> Response {
+    if let Some(refused) = refuse_edit(&state, &req, "link-mention") {
+        return refused.into_response();
+    }
+
+    let query = req.uri().query().unwrap_or("").to_owned();
+    let Some((canonical, display_path)) = resolve_api_path(&state, &query, "link-mention").await
+    else {
+        return ServeError::NotFound.into_response();
+    };
+    if !canonical.starts_with(&state.canonical_root) {
+        vlog!(
+            state.verbose,
+            "[link-mention] path={display_path} reason=edit-outside-root"
+        );
+        return ServeError::NotFound.into_response();
+    }
+    let source = crate::backlinks::url_key_from_rel_path(&display_path);
+
+    let request: Option<(String, usize, usize, String)> =
+        axum::body::to_bytes(req.into_body(), EDIT_MAX_BODY)
+            .await
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+            .and_then(|body| {
+                Some((
+                    body["target"].as_str()?.to_owned(),
+                    body["line"].as_u64().filter(|&l| l > 0)? as usize,
+                    body["column"].as_u64()? as usize,
+                    body["text"].as_str()?.to_owned(),
+                ))
+            });
+    let Some((target, line, column, text)) = request else {
+        return ServeError::BadRequest(
+            "expected a body of {\"target\": <page>, \"line\": <1-based line>, \"column\": <byte>, \"text\": <mention>}",
+        )
+        .into_response();
+    };
+    let indexed = state
+        .mentions
+        .get(&target)
+        .is_some_and(|mentions| mentions.iter().any(|m| m.source_url_path == source));
+    if !indexed {
+        vlog!(
+            state.verbose,
+            "[link-mention] path={display_path} target={target} reason=not-a-mention"
+        );
+        return ServeError::NotFound.into_response();
+    }
+
+    let href = crate::backlinks::relative_href(&source, &target);
+    let path = canonical.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let content = encoding::read_utf8(&path)
+            .map_err(|e| format!("cannot edit {}: {e}", path.display()))?;
+        let updated =
+            crate::backlinks::link_mention_in_source(&content, line, column, &text, &href)?;
+        crate::replace::write_atomic(&path, &updated)
+            .map_err(|e| format!("cannot write {}: {e}", path.display()))
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    if let Err(e) = result {
+        vlog!(
+            state.verbose,
+            "[link-mention] path={display_path} line={line} reason={e}"
+        );
+        return ServeError::Conflict(e).into_response();
+    }
 
-    // Extract mtime as Unix seconds (0 if unavailable).
-    let mtime_secs = meta
-        .modified()
+    let mtime_secs = tokio::fs::metadata(&canonical)
+        .await
         .ok()
+        .and_then(|m| m.modified().ok())
         .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
         .map(|d| d.as_secs())
         .unwrap_or(0);
-
     vlog!(
         state.verbose,
-        "[freshness] path={display_path} mtime={mtime_secs}"
+        "[link-mention] path={display_path} line={line} target={target} mtime={mtime_secs}"
     );
 
     let body = serde_json::json!({ "mtime": mtime_secs }).to_string();
@@ -1587,45 +4476,111 @@ async fn freshness_handler(State(state): State<Arc<AppState>>, req: Request) ->
         .status(StatusCode::OK)
         .header(header::CONTENT_TYPE, "application/json")
         .header("X-Content-Type-Options", "nosniff")
+        .header(header::CACHE_CONTROL, "no-store")
         .body(Body::from(body))
-        .expect("freshness_handler response builder is infallible")
+        .expect("link_mention_handler response builder is infallible")
+}
+
+// ---------------------------------------------------------------------------
+// API description
+// ---------------------------------------------------------------------------
+
+/// Handler for `GET /_mdmd/api.json`: the embedded OpenAPI description.
+async fn api_description_handler() -> Response {
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Content-Type-Options", "nosniff")
+        .body(Body::from(crate::web_assets::OPENAPI))
+        .expect("api_description_handler response builder is infallible")
 }
 
 // ---------------------------------------------------------------------------
 // Server entry point
 // ---------------------------------------------------------------------------
 
-/// Start the HTTP server for the given markdown `file`.
+/// URL keys (see [`crate::backlinks::url_key_from_rel_path`]) of the
+/// markdown files under `root` that `pick` holds for.
+fn url_keys(root: &Path, pick: impl Fn(&Path) -> bool) -> HashSet<String> {
+    crate::replace::collect_markdown_files(root)
+        .into_iter()
+        .filter(|path| pick(path))
+        .filter_map(|path| {
+            let rel = path
+                .strip_prefix(root)
+                .ok()?
+                .to_string_lossy()
+                .replace('\\', "/");
+            Some(crate::backlinks::url_key_from_rel_path(&rel))
+        })
+        .collect()
+}
+
+/// Whether the markdown file at `path` is marked `draft: true`.
+fn is_draft_file(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|m| m.len() <= MAX_FILE_SIZE)
+        && std::fs::read_to_string(path).is_ok_and(|s| frontmatter::is_draft(&s))
+}
+
+/// Start the HTTP server for `files`: one markdown file or directory, or
+/// several paths and globs selecting the files to serve (see
+/// [`crate::selection`]).
 ///
 /// Binds to `bind_addr` starting at `start_port`, retrying on `EADDRINUSE` up
-/// to 100 times.  The server shuts down cleanly when SIGINT (Ctrl+C) is
-/// received.
+/// to 100 times.  `config` carries flag-driven settings such as latency
+/// budgets.  The server shuts down cleanly when SIGINT (Ctrl+C) is received,
+/// or `quit` on the `--control` socket.
 pub async fn run_serve(
-    file: String,
+    files: Vec<String>,
     bind_addr: String,
     start_port: u16,
     no_open: bool,
     verbose: bool,
+    config: AppConfig,
 ) -> io::Result<()> {
     // Use CWD as the default serve root.
     let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
     let canonical_cwd = std::fs::canonicalize(&cwd).unwrap_or_else(|_| cwd.clone());
 
+    // Several paths, or a glob, select the files to serve under their common
+    // root.
+    let selection = match files.as_slice() {
+        [file] if !crate::selection::is_glob(file) => None,
+        _ => Some(Selection::expand(&files).map_err(|msg| {
+            eprintln!("Error: {msg}");
+            io::Error::new(io::ErrorKind::NotFound, msg)
+        })?),
+    };
+
     // Canonicalize the entry file path.
-    let raw_entry = PathBuf::from(&file);
-    let canonical_entry = std::fs::canonicalize(&raw_entry).map_err(|e| {
-        let msg = format!("entry '{}' not found: {}", file, e);
-        eprintln!("Error: {msg}");
-        io::Error::new(io::ErrorKind::NotFound, msg)
-    })?;
+    let canonical_entry = match &selection {
+        Some(selection) => selection.entry.clone(),
+        None => {
+            let file = &files[0];
+            std::fs::canonicalize(file).map_err(|e| {
+                let msg = format!("entry '{}' not found: {}", file, e);
+                eprintln!("Error: {msg}");
+                io::Error::new(io::ErrorKind::NotFound, msg)
+            })?
+        }
+    };
 
     // Determine serve_root and canonical_root based on whether the entry is inside CWD.
-    let (serve_root, canonical_root) = if canonical_entry.starts_with(&canonical_cwd) {
+    let (serve_root, canonical_root) = if let Some(root) = selection
+        .as_ref()
+        .map(|sel| sel.root.clone())
+        .filter(|root| root.starts_with(&canonical_cwd))
+    {
+        // Selection inside CWD: serve its common root.
+        (root.clone(), root)
+    } else if selection.is_none() && canonical_entry.starts_with(&canonical_cwd) {
         // Entry is inside CWD: use CWD as serve root (unchanged behavior).
         (cwd, canonical_cwd)
     } else {
         // Entry is outside CWD: derive serve_root from entry location.
-        let new_root = if canonical_entry.is_dir() {
+        let new_root = if let Some(selection) = &selection {
+            selection.root.clone()
+        } else if canonical_entry.is_dir() {
             canonical_entry.clone()
         } else {
             canonical_entry
@@ -1687,6 +4642,8 @@ pub async fn run_serve(
             readme
         } else if index_md.is_file() {
             index_md
+        } else if selection.is_some() {
+            canonical_entry
         } else {
             let msg = format!(
                 "no README.md or index.md found in directory '{}'",
@@ -1700,41 +4657,145 @@ pub async fn run_serve(
     };
 
     // Compute the URL path for the entry file (used in the startup banner and by handlers).
-    let entry_url_path = derive_entry_url_path(&entry_file, &canonical_root).map_err(|msg| {
-        eprintln!("Error: {msg}");
-        io::Error::new(io::ErrorKind::InvalidInput, msg)
-    })?;
+    // Without a single entry file, the collection page of the matches is
+    // shown instead.
+    let entry_url_path = if selection.is_some() {
+        COLLECTION_PATH.to_owned()
+    } else {
+        derive_entry_url_path(&entry_file, &canonical_root).map_err(|msg| {
+            eprintln!("Error: {msg}");
+            io::Error::new(io::ErrorKind::InvalidInput, msg)
+        })?
+    };
 
     // Build the startup backlinks index synchronously before server bind.
     // The index is eventually-stale by design; users must restart the server
     // after editing files to pick up changes.
-    let backlinks = crate::backlinks::build_backlinks_index(&canonical_root, verbose);
+    let mut backlinks = crate::backlinks::build_backlinks_index(&canonical_root, verbose);
+    let mut mentions = crate::backlinks::build_mentions_index(&canonical_root, &backlinks, verbose);
+    // Pages outside the selection are neither served nor listed as sources.
+    if let Some(selection) = &selection {
+        let unselected = url_keys(&canonical_root, |path| !selection.contains(path));
+        vlog!(verbose, "[backlinks] omit unselected={}", unselected.len());
+        for refs in backlinks.values_mut() {
+            refs.retain(|r| !unselected.contains(&r.source_url_path));
+        }
+        for refs in mentions.values_mut() {
+            refs.retain(|m| !unselected.contains(&m.source_url_path));
+        }
+    }
+    // Drafts are still indexed so a draft's own panel is complete, but they
+    // are not listed as sources on other pages unless `--drafts`.
+    if !config.drafts {
+        let drafts = url_keys(&canonical_root, is_draft_file);
+        if !drafts.is_empty() {
+            vlog!(verbose, "[backlinks] omit drafts={}", drafts.len());
+            for refs in backlinks.values_mut() {
+                refs.retain(|r| !drafts.contains(&r.source_url_path));
+            }
+            for refs in mentions.values_mut() {
+                refs.retain(|m| !drafts.contains(&m.source_url_path));
+            }
+        }
+    }
 
     // Precompute ETags for embedded static assets (stable for the lifetime of
     // this server process — embedded bytes never change at runtime).
     let css_etag = compute_etag(web_assets::CSS.as_bytes());
     let js_etag = compute_etag(web_assets::JS.as_bytes());
+    let favicon = Favicon::load(config.favicon.as_deref()).map_err(|msg| {
+        eprintln!("Error: {msg}");
+        io::Error::new(io::ErrorKind::InvalidInput, msg)
+    })?;
 
-    // Use the binary's own mtime as Last-Modified for embedded assets, falling
-    // back to the Unix epoch when the path or metadata is unavailable.
-    let asset_mtime = std::env::current_exe()
-        .ok()
-        .and_then(|p| std::fs::metadata(p).ok())
-        .and_then(|m| m.modified().ok())
-        .unwrap_or(SystemTime::UNIX_EPOCH);
+    if let Some(dir) = config.dev_assets.as_ref().filter(|dir| !dir.is_dir()) {
+        eprintln!(
+            "Warning: --dev: {} not found; serving the embedded assets",
+            dir.display()
+        );
+    }
+    if config.follow_symlinks {
+        eprintln!(
+            "Warning: --follow-symlinks: files that symlinks under {} point to elsewhere will be served",
+            canonical_root.display()
+        );
+    }
+    let allowed_roots = config
+        .allow
+        .iter()
+        .map(|dir| match std::fs::canonicalize(dir) {
+            Ok(canonical) if canonical.is_dir() => Ok(canonical),
+            _ => {
+                let msg = format!("--allow: {} is not a directory", dir.display());
+                eprintln!("Error: {msg}");
+                Err(io::Error::new(io::ErrorKind::InvalidInput, msg))
+            }
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    let audit_path = if config.no_audit_log {
+        None
+    } else if let Some(path) = &config.audit_log {
+        Some(path.clone())
+    } else if audit::binds_remote(&bind_addr) {
+        let path = audit::default_path();
+        if path.is_none() {
+            eprintln!(
+                "Warning: keeping no audit log: neither XDG_STATE_HOME nor HOME is set (use --audit-log FILE)"
+            );
+        }
+        path
+    } else {
+        None
+    };
+    let audit = match audit_path {
+        Some(path) => {
+            let log = AuditLog::open(&path, &canonical_root, &bind_addr).map_err(|e| {
+                let msg = format!("cannot open the audit log '{}': {e}", path.display());
+                eprintln!("Error: {msg}");
+                io::Error::new(e.kind(), msg)
+            })?;
+            vlog!(verbose, "[audit] path={}", path.display());
+            Some(log)
+        }
+        None => None,
+    };
 
+    let limits = config.limits;
+    let share = config.share.map(Share::new);
+    let render = html::RenderOptions {
+        base_path: canonical_root.clone(),
+        ..config.render.clone()
+    };
     let state = Arc::new(AppState {
         serve_root,
         canonical_root,
+        allowed_roots,
         entry_file,
         entry_url_path,
-        config: AppConfig,
+        config,
+        render,
         css_etag,
         js_etag,
-        asset_mtime,
+        favicon,
         backlinks,
+        mentions,
         verbose,
+        in_flight: Arc::new(Semaphore::new(limits.max_in_flight)),
+        page_commands: watch::Sender::new(PageCommand::default()),
+        scroll_wait: SCROLL_WAIT.min(limits.request_timeout / 2),
+        audit,
+        share,
+        selection,
     });
+    let control = match &state.config.control {
+        Some(socket) => Some(
+            control::Listener::bind(socket, control::parse_command).inspect_err(|e| {
+                eprintln!("Error: cannot listen on '{}': {e}", socket.display());
+            })?,
+        ),
+        None => None,
+    };
 
     let (std_listener, bound_port) =
         bind_with_retry(&bind_addr, start_port, verbose).map_err(|msg| {
@@ -1749,8 +4810,62 @@ pub async fn run_serve(
     // brotli based on the client's Accept-Encoding header.  It is added as the
     // outermost layer so it wraps all handler responses.
     let app = Router::new()
+        .route(
+            "/_mdmd/api.json",
+            axum::routing::get(api_description_handler),
+        )
+        .route("/_mdmd/v1/freshness", axum::routing::get(freshness_handler))
+        .route("/_mdmd/v1/search", axum::routing::get(search_handler))
+        .route("/_mdmd/v1/doc", axum::routing::get(doc_handler))
+        .route("/_mdmd/v1/tree", axum::routing::get(tree_handler))
+        .route(
+            "/_mdmd/v1/toggle-task",
+            axum::routing::post(toggle_task_handler),
+        )
+        .route(
+            "/_mdmd/v1/link-mention",
+            axum::routing::post(link_mention_handler),
+        )
+        .route(
+            "/_mdmd/v1/scroll",
+            axum::routing::get(scroll_wait_handler).post(scroll_handler),
+        )
+        .route("/_mdmd/v1/archive", axum::routing::get(archive_handler))
+        .route("/_mdmd/v1/combined", axum::routing::get(combined_handler))
+        // Unversioned aliases of the current API version.
         .route("/_mdmd/freshness", axum::routing::get(freshness_handler))
+        .route("/_mdmd/search", axum::routing::get(search_handler))
+        .route("/_mdmd/doc", axum::routing::get(doc_handler))
+        .route("/_mdmd/tree", axum::routing::get(tree_handler))
+        .route(
+            "/_mdmd/toggle-task",
+            axum::routing::post(toggle_task_handler),
+        )
+        .route(
+            "/_mdmd/link-mention",
+            axum::routing::post(link_mention_handler),
+        )
+        .route(
+            "/_mdmd/scroll",
+            axum::routing::get(scroll_wait_handler).post(scroll_handler),
+        )
+        .route("/_mdmd/archive", axum::routing::get(archive_handler))
+        .route("/_mdmd/combined", axum::routing::get(combined_handler))
+        .route(COLLECTION_PATH, axum::routing::get(collection_handler))
         .fallback(serve_handler)
+        .layer(RequestBodyLimitLayer::new(limits.max_body_bytes))
+        .layer(TimeoutLayer::with_status_code(
+            StatusCode::SERVICE_UNAVAILABLE,
+            limits.request_timeout,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            limit_in_flight,
+        ))
+        .layer(middleware::from_fn_with_state(state.clone(), require_share))
+        .layer(middleware::from_fn(negotiate_error))
+        .layer(middleware::from_fn_with_state(state.clone(), cache_control))
+        .layer(middleware::from_fn_with_state(state.clone(), audit_request))
         .with_state(state.clone())
         .layer(CompressionLayer::new());
 
@@ -1769,10 +4884,24 @@ pub async fn run_serve(
         .await
         .ok()
         .flatten();
-    if let Some(ref ts) = tailscale {
-        println!("http://{}:{bound_port}{}", ts.ip, state.entry_url_path);
-    } else {
-        println!("http://127.0.0.1:{bound_port}{}", state.entry_url_path);
+    let startup_url = share_link(
+        &state,
+        match tailscale {
+            Some(ref ts) => format!("http://{}:{bound_port}{}", ts.ip, state.entry_url_path),
+            None => format!("http://127.0.0.1:{bound_port}{}", state.entry_url_path),
+        },
+    );
+    println!("{startup_url}");
+    if let Some(share) = &state.share {
+        let until = SystemTime::now() + share.remaining();
+        let until = format_listing_time(until).map_or_else(String::new, |(iso, _)| iso);
+        eprintln!("Sharing until {until}; the server stops then");
+    }
+    if state.config.copy_url {
+        match crate::clipboard::copy(&startup_url, &EnvSnapshot::from_env()) {
+            Ok(method) => vlog!(verbose, "[clipboard] copied url={startup_url} via={method}"),
+            Err(e) => eprintln!("Warning: --copy-url: could not copy the URL ({e})"),
+        }
     }
 
     // Attempt to open the entry URL in the default browser (fire-and-forget).
@@ -1783,23 +4912,63 @@ pub async fn run_serve(
     // variable.  Integration tests set this to a nonexistent binary so they
     // can verify open-attempt logic without launching a real browser.
     if should_attempt_open(no_open, is_headed_environment()) {
-        let url = format!("http://127.0.0.1:{bound_port}{}", state.entry_url_path);
-        let open_cmd = resolve_open_cmd(std::env::var("MDMD_OPEN_CMD").ok().as_deref());
-        match spawn_browser_open(&open_cmd, &url) {
+        let path = match &state.config.open_path {
+            Some(sub) => open_url_path(sub),
+            None => state.entry_url_path.clone(),
+        };
+        let url = share_link(&state, format!("http://127.0.0.1:{bound_port}{path}"));
+        let argv = open_command_line(
+            RuntimePlatform::current(),
+            std::env::var("MDMD_OPEN_CMD").ok().as_deref(),
+            state.config.open_browser.as_deref(),
+            &url,
+        );
+        match spawn_browser_open(&argv) {
             Ok(_) => vlog!(verbose, "[browser] opened {url}"),
             Err(e) => vlog!(verbose, "[browser] open failed: {e}"),
         }
     }
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            signal::ctrl_c()
-                .await
-                .expect("failed to install SIGINT handler");
-            vlog!(verbose, "[shutdown] complete");
-        })
-        .await
-        .map_err(io::Error::other)?;
+    let control_state = state.clone();
+    let quit = async move {
+        match control {
+            Some(control) => follow_control(control_state, control, bound_port).await,
+            None => std::future::pending().await,
+        }
+    };
+    let expires = state.share.as_ref().map(|share| share.expires);
+    let expiry = async move {
+        match expires {
+            Some(at) => tokio::time::sleep_until(at.into()).await,
+            None => std::future::pending().await,
+        }
+    };
+    let shutdown_state = state.clone();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        tokio::select! {
+            result = signal::ctrl_c() => result.expect("failed to install SIGINT handler"),
+            () = quit => {}
+            () = expiry => eprintln!("Share link expired; shutting down"),
+        }
+        // Answer pages waiting for editor commands so their
+        // connections do not hold up the shutdown.
+        shutdown_state.page_commands.send_modify(|latest| {
+            latest.seq += 1;
+            latest.path = None;
+            latest.body = serde_json::Value::Null;
+        });
+        vlog!(verbose, "[shutdown] complete");
+    })
+    .await
+    .map_err(io::Error::other)?;
+
+    if let Some(log) = &state.audit {
+        eprint!("{}", log.summary());
+    }
 
     Ok(())
 }
@@ -2121,6 +5290,135 @@ mod tests {
         );
     }
 
+    // --- ServeError ---
+
+    #[test]
+    fn error_format_follows_accept_order() {
+        let f = ErrorFormat::from_accept;
+        assert_eq!(
+            f("text/html,application/xhtml+xml,*/*;q=0.8"),
+            Some(ErrorFormat::Html)
+        );
+        assert_eq!(f("application/json, text/plain"), Some(ErrorFormat::Json));
+        assert_eq!(f("text/html;q=0, text/plain"), Some(ErrorFormat::Text));
+        assert_eq!(f("*/*"), None);
+        assert_eq!(f(""), None);
+    }
+
+    #[test]
+    fn errors_render_in_each_format() {
+        let error = ServeError::TooLarge {
+            path: "big.md".into(),
+            size: 20,
+        };
+        let text = error.render(ErrorFormat::Text);
+        assert_eq!(text.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(
+            text.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+
+        let json = ServeError::Conflict("line 3 is not a task".into()).render(ErrorFormat::Json);
+        assert_eq!(json.headers()[header::CONTENT_TYPE], "application/json");
+
+        let page = ServeError::NotFound.page();
+        assert!(page.contains("<h1>404 Not Found</h1>"), "{page}");
+        assert!(!page.contains("<p>"), "terse 404 must not say more: {page}");
+        assert_eq!(
+            ServeError::Busy.detail().as_deref(),
+            Some("too many requests in flight")
+        );
+    }
+
+    // --- did_you_mean ---
+
+    #[test]
+    fn edit_distance_counts_characters() {
+        assert_eq!(edit_distance("guide", "guide"), 0);
+        assert_eq!(edit_distance("gide", "guide"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn did_you_mean_finds_typos_and_prefixes() {
+        let entries: Vec<(String, String)> = ["docs/", "guide.md", "guides.md", "README.md"]
+            .iter()
+            .map(|name| (name.to_string(), format!("/{name}")))
+            .collect();
+        let names = |missing: &str| -> Vec<&str> {
+            did_you_mean(missing, &entries)
+                .into_iter()
+                .map(|(name, _)| name.as_str())
+                .collect()
+        };
+        assert_eq!(names("gide.md"), ["guide.md", "guides.md"]);
+        assert_eq!(names("Guide"), ["guide.md", "guides.md"]);
+        assert_eq!(names("readm.md"), ["README.md"]);
+        assert_eq!(names("dcs"), ["docs/"]);
+        assert!(names("changelog.md").is_empty());
+        assert!(names("").is_empty());
+    }
+
+    // --- preferred_encoding ---
+
+    #[test]
+    fn preferred_encoding_honours_refusals() {
+        assert_eq!(preferred_encoding("gzip, deflate, br"), Some("br"));
+        assert_eq!(preferred_encoding("gzip, br;q=0"), Some("gzip"));
+        assert_eq!(preferred_encoding("GZIP;q=0.5"), Some("gzip"));
+        assert_eq!(preferred_encoding("*"), Some("br"));
+        assert_eq!(preferred_encoding("*, br;q=0, gzip;q=0"), None);
+        assert_eq!(preferred_encoding("identity"), None);
+        assert_eq!(preferred_encoding(""), None);
+    }
+
+    // --- CachePolicy ---
+
+    #[test]
+    fn cache_policy_by_path_and_status() {
+        let policy = CachePolicy {
+            page_max_age: 60,
+            asset_max_age: 600,
+        };
+        let ok = StatusCode::OK;
+        assert_eq!(
+            policy.header_for("/guide", ok),
+            "max-age=60, must-revalidate"
+        );
+        assert_eq!(
+            policy.header_for("/docs/", ok),
+            "max-age=60, must-revalidate"
+        );
+        assert_eq!(
+            policy.header_for("/README.MD", StatusCode::NOT_MODIFIED),
+            "max-age=60, must-revalidate"
+        );
+        assert_eq!(policy.header_for("/img/logo.png", ok), "max-age=600");
+        assert_eq!(policy.header_for("/_mdmd/v1/tree", ok), "no-store");
+        assert_eq!(
+            policy.header_for("/missing", StatusCode::NOT_FOUND),
+            "no-store"
+        );
+    }
+
+    // --- render_options_from_query ---
+
+    #[test]
+    fn query_overrides_render_options() {
+        let base = html::RenderOptions {
+            smart_punctuation: true,
+            ..html::RenderOptions::default()
+        };
+        let render = render_options_from_query(&base, "toc=0&numbered=1&theme=dark&smart=0");
+        assert!(!render.sidebar_toc && render.numbered_headings && !render.smart_punctuation);
+        assert_eq!(render.theme, Some(html::Theme::Dark));
+
+        let render = render_options_from_query(&base, "raw=0&toc=maybe&theme=blue&xtoc=0");
+        assert_eq!(render, base);
+    }
+
     // --- is_raw_mode ---
 
     #[test]
@@ -2219,6 +5517,26 @@ mod tests {
         assert_eq!(normalize_path("/a/b/../c").unwrap(), PathBuf::from("a/c"));
     }
 
+    #[test]
+    fn normalize_windows_separators_and_drives() {
+        assert_eq!(
+            normalize_path_for("/docs\\guide.md", true).unwrap(),
+            PathBuf::from("docs").join("guide.md")
+        );
+        assert!(normalize_path_for("/a\\..\\..\\secret", true).is_none());
+        assert!(normalize_path_for("/C:/Windows/win.ini", true).is_none());
+        assert!(normalize_path_for("/notes.md:stream", true).is_none());
+        // Elsewhere both are ordinary file name characters.
+        assert_eq!(
+            normalize_path_for("/a\\b:c", false).unwrap(),
+            PathBuf::from("a\\b:c")
+        );
+        assert_eq!(
+            slash_path(&normalize_path_for("/docs\\guide.md", true).unwrap()),
+            "docs/guide.md"
+        );
+    }
+
     #[test]
     fn normalize_traversal_above_root_rejected() {
         // /../etc/passwd → None (traversal above root)
@@ -2408,6 +5726,91 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    // --- directory index sorting and formatting ---
+
+    fn listing_entry(name: &str, is_dir: bool, size: u64, mtime_secs: u64) -> DirListingEntry {
+        DirListingEntry {
+            name: name.to_owned(),
+            is_dir,
+            size,
+            mtime: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(mtime_secs)),
+            title: None,
+        }
+    }
+
+    fn names(entries: &[DirListingEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn dir_sort_parses_query_with_per_key_default_order() {
+        let sort = |q| DirSort::from_query(q);
+        assert_eq!(
+            sort(""),
+            DirSort {
+                key: DirSortKey::Name,
+                descending: false
+            }
+        );
+        assert_eq!(
+            sort("sort=mtime"),
+            DirSort {
+                key: DirSortKey::Mtime,
+                descending: true
+            }
+        );
+        assert_eq!(
+            sort("order=asc&sort=size"),
+            DirSort {
+                key: DirSortKey::Size,
+                descending: false
+            }
+        );
+        assert_eq!(sort("sort=bogus&order=sideways"), sort(""));
+        // The active column's header link flips the order.
+        assert_eq!(
+            sort("sort=size").header_query(DirSortKey::Size),
+            "?sort=size&amp;order=asc"
+        );
+        assert_eq!(
+            sort("sort=size").header_query(DirSortKey::Mtime),
+            "?sort=mtime&amp;order=desc"
+        );
+    }
+
+    #[test]
+    fn dir_entries_sort_by_column_with_directories_first() {
+        let mut entries = vec![
+            listing_entry("small.md", false, 10, 300),
+            listing_entry("Big.txt", false, 5000, 100),
+            listing_entry("docs", true, 0, 50),
+            listing_entry("mid.md", false, 700, 200),
+        ];
+        sort_dir_entries(&mut entries, DirSort::from_query(""));
+        assert_eq!(names(&entries), ["docs", "Big.txt", "mid.md", "small.md"]);
+        sort_dir_entries(&mut entries, DirSort::from_query("sort=size"));
+        assert_eq!(names(&entries), ["docs", "Big.txt", "mid.md", "small.md"]);
+        sort_dir_entries(&mut entries, DirSort::from_query("sort=mtime"));
+        assert_eq!(names(&entries), ["docs", "small.md", "mid.md", "Big.txt"]);
+        sort_dir_entries(&mut entries, DirSort::from_query("sort=size&order=asc"));
+        assert_eq!(names(&entries), ["docs", "small.md", "mid.md", "Big.txt"]);
+    }
+
+    #[test]
+    fn listing_sizes_and_times_are_human_readable() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        let t = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_993_100);
+        assert_eq!(
+            format_listing_time(t),
+            Some((
+                "2024-03-09T14:05:00Z".to_owned(),
+                "2024-03-09 14:05".to_owned()
+            ))
+        );
+    }
+
     // --- build_breadcrumbs ---
 
     #[test]
@@ -2530,6 +5933,26 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[tokio::test]
+    async fn case_variant_matches_each_component() {
+        let dir = std::env::temp_dir().join(format!("mdmd_case_variant_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("Docs")).unwrap();
+        std::fs::write(dir.join("Docs/Guide.md"), b"# Guide").unwrap();
+        std::fs::write(dir.join("Docs/guide.MD"), b"# Other").unwrap();
+
+        let variant = case_variant(&dir, Path::new("docs/GUIDE.md")).await;
+        assert_eq!(variant, Some(dir.join("Docs/Guide.md")));
+        let variant = case_variant(&dir, Path::new("docs/guide.MD")).await;
+        assert_eq!(variant, Some(dir.join("Docs/guide.MD")));
+        let variant = case_variant(&dir, Path::new("DOCS/guide")).await;
+        assert_eq!(variant, Some(dir.join("Docs/Guide.md")));
+        assert!(case_variant(&dir, Path::new("docs/missing.md"))
+            .await
+            .is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     // --- nearest_existing_parent ---
 
     /// Helper: create a temp directory tree and return the canonical root.
@@ -2657,7 +6080,7 @@ mod tests {
         // → url_key_from_rel_path(norm_display) = "/docs/read me.md"
         let decoded = percent_decode("/docs/read%20me.md").unwrap();
         let normalized = normalize_path(&decoded).unwrap();
-        let norm_display = normalized.display().to_string();
+        let norm_display = slash_path(&normalized);
         assert_eq!(
             norm_display, "docs/read me.md",
             "decoded path must produce norm_display without leading slash"
@@ -2675,7 +6098,7 @@ mod tests {
         // Confirm that normalize_path + url_key_from_rel_path produces a fragment-free key.
         let decoded = percent_decode("/docs/page.md").unwrap();
         let normalized = normalize_path(&decoded).unwrap();
-        let norm_display = normalized.display().to_string();
+        let norm_display = slash_path(&normalized);
         let key = crate::backlinks::url_key_from_rel_path(&norm_display);
         assert_eq!(key, "/docs/page.md");
         assert!(
@@ -2931,6 +6354,33 @@ mod tests {
         assert!(!is_headed_for(RuntimePlatform::Linux, &env));
     }
 
+    // Windows rules
+
+    #[test]
+    fn windows_is_headed_without_display_vars() {
+        assert!(is_headed_for(RuntimePlatform::Windows, &clear_env()));
+    }
+
+    #[test]
+    fn windows_ssh_or_ci_is_not_headed() {
+        for env in [
+            EnvSnapshot {
+                ssh_connection: true,
+                ..clear_env()
+            },
+            EnvSnapshot {
+                ci: true,
+                ..clear_env()
+            },
+            EnvSnapshot {
+                github_actions: true,
+                ..clear_env()
+            },
+        ] {
+            assert!(!is_headed_for(RuntimePlatform::Windows, &env));
+        }
+    }
+
     // Other platform rules
 
     #[test]
@@ -2986,12 +6436,12 @@ mod tests {
 
     #[test]
     fn default_open_command_is_not_empty_on_known_platform() {
-        // On macOS or Linux the command must be a non-empty string.
+        // On macOS, Linux, or Windows the command must be a non-empty string.
         // On other platforms it must be empty (no browser opener).
-        #[cfg(any(target_os = "macos", target_os = "linux"))]
+        #[cfg(any(target_os = "macos", target_os = "linux", windows))]
         assert!(!default_open_command().is_empty());
 
-        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
         assert!(default_open_command().is_empty());
     }
 
@@ -3007,6 +6457,12 @@ mod tests {
         assert_eq!(default_open_command(), "xdg-open");
     }
 
+    #[cfg(windows)]
+    #[test]
+    fn default_open_command_windows_is_cmd() {
+        assert_eq!(default_open_command(), "cmd");
+    }
+
     // --- resolve_open_cmd ---
 
     /// When `MDMD_OPEN_CMD` is provided as `Some`, `resolve_open_cmd` returns
@@ -3031,12 +6487,12 @@ mod tests {
     #[test]
     fn resolve_open_cmd_no_override_falls_back_to_platform_default() {
         let cmd = resolve_open_cmd(None);
-        #[cfg(any(target_os = "macos", target_os = "linux"))]
+        #[cfg(any(target_os = "macos", target_os = "linux", windows))]
         assert!(
             !cmd.is_empty(),
-            "platform default must be non-empty on macOS/Linux"
+            "platform default must be non-empty on macOS/Linux/Windows"
         );
-        #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+        #[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
         assert!(
             cmd.is_empty(),
             "platform default must be empty on unsupported platforms"
@@ -3047,14 +6503,152 @@ mod tests {
 
     #[test]
     fn spawn_browser_open_empty_cmd_returns_err() {
-        let result = spawn_browser_open("", "http://127.0.0.1:8080/");
+        let result = spawn_browser_open(&["".into(), "http://127.0.0.1:8080/".into()]);
         assert!(result.is_err());
+        assert!(spawn_browser_open(&[]).is_err());
     }
 
     #[test]
     fn spawn_browser_open_nonexistent_cmd_returns_err() {
         // A command that cannot possibly exist should fail at spawn time.
-        let result = spawn_browser_open("__mdmd_no_such_binary__", "http://127.0.0.1:8080/");
+        let result = spawn_browser_open(&[
+            "__mdmd_no_such_binary__".into(),
+            "http://127.0.0.1:8080/".into(),
+        ]);
         assert!(result.is_err());
     }
+
+    // --- open_command_line ---
+
+    #[test]
+    fn open_command_line_on_windows_uses_start() {
+        let url = "http://127.0.0.1:3333/";
+        let windows = RuntimePlatform::Windows;
+        assert_eq!(
+            open_command_line(windows, None, None, url),
+            ["cmd", "/C", "start", "", url]
+        );
+        assert_eq!(
+            open_command_line(windows, None, Some("firefox -P work"), url),
+            ["cmd", "/C", "start", "", "firefox", "-P", "work", url]
+        );
+        assert_eq!(
+            open_command_line(windows, Some("stub"), None, url),
+            ["stub", url]
+        );
+    }
+
+    #[test]
+    fn open_command_line_names_the_browser() {
+        let url = "http://127.0.0.1:3333/";
+        let linux = RuntimePlatform::Linux;
+        assert_eq!(
+            open_command_line(linux, None, Some("firefox -P work"), url),
+            ["firefox", "-P", "work", url]
+        );
+        assert_eq!(
+            open_command_line(RuntimePlatform::MacOs, None, Some("Firefox -P work"), url),
+            ["open", "-a", "Firefox", url, "--args", "-P", "work"]
+        );
+        assert_eq!(
+            open_command_line(RuntimePlatform::MacOs, None, Some("Safari"), url),
+            ["open", "-a", "Safari", url]
+        );
+        // MDMD_OPEN_CMD wins; a blank browser means the default.
+        assert_eq!(
+            open_command_line(linux, Some("stub"), Some("firefox"), url),
+            ["stub", url]
+        );
+        assert_eq!(
+            open_command_line(linux, None, Some(" "), url),
+            [default_open_command(), url]
+        );
+    }
+
+    #[test]
+    fn open_url_path_encodes_segments() {
+        assert_eq!(open_url_path("docs/my notes.md"), "/docs/my%20notes.md");
+        assert_eq!(open_url_path("/docs/"), "/docs/");
+        assert_eq!(open_url_path("./guide.md"), "/guide.md");
+        assert_eq!(open_url_path(""), "/");
+    }
+
+    // --- latency budgets ---
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn phase_timings_dominant_picks_slowest_phase() {
+        let timings = PhaseTimings {
+            resolve: ms(2),
+            read: ms(5),
+            render: ms(40),
+            shell: ms(3),
+        };
+        assert_eq!(timings.dominant(), ("render", ms(40)));
+    }
+
+    #[test]
+    fn phase_timings_dominant_defaults_to_resolve_when_all_zero() {
+        assert_eq!(
+            PhaseTimings::default().dominant(),
+            ("resolve", Duration::ZERO)
+        );
+    }
+
+    #[test]
+    fn latency_warning_none_within_budget() {
+        let timings = PhaseTimings {
+            render: ms(10),
+            ..PhaseTimings::default()
+        };
+        let budget = LatencyBudget::default();
+        assert_eq!(
+            latency_warning("a.md", "rendered", &timings, ms(20), &budget),
+            None
+        );
+    }
+
+    #[test]
+    fn latency_warning_reports_total_overrun_and_dominant_phase() {
+        let timings = PhaseTimings {
+            resolve: ms(1),
+            read: ms(300),
+            render: ms(10),
+            shell: ms(1),
+        };
+        let line = latency_warning(
+            "big.md",
+            "rendered",
+            &timings,
+            ms(312),
+            &LatencyBudget::default(),
+        )
+        .expect("total over budget must warn");
+        assert!(
+            line.starts_with("[latency] WARN path=big.md mode=rendered"),
+            "{line}"
+        );
+        assert!(line.contains("exceeded=total "), "{line}");
+        assert!(line.contains("dominant=read "), "{line}");
+        assert!(line.contains("total_ms=312.0"), "{line}");
+    }
+
+    #[test]
+    fn latency_warning_reports_render_overrun_within_total() {
+        let timings = PhaseTimings {
+            render: ms(150),
+            ..PhaseTimings::default()
+        };
+        let budget = LatencyBudget {
+            render: ms(100),
+            total: ms(1000),
+        };
+        let line = latency_warning("doc.md", "rendered", &timings, ms(160), &budget)
+            .expect("render over budget must warn");
+        assert!(line.contains("exceeded=render "), "{line}");
+        assert!(line.contains("dominant=render "), "{line}");
+    }
 }
@@ -0,0 +1,412 @@
+//! Recording and playback of TUI sessions.
+//!
+//! `mdmd view --record <file>` appends every key press, with its time offset,
+//! to a plain-text recording.  `mdmd replay <file>` feeds the recorded keys
+//! back into the viewer at the original pace, which makes interaction bugs
+//! reproducible and lets scripted walkthroughs be written by hand.
+//!
+//! The format is line based:
+//!
+//! ```text
+//! # mdmd session recording v1
+//! file /home/me/notes/README.md
+//! scroll-step 1
+//! # terminal 120x40
+//! 0 key j
+//! 412 key C-d
+//! 1200 key Enter
+//! ```
+//!
+//! Times are milliseconds since recording started.  Key names use
+//! `C-`/`A-`/`S-` prefixes for Ctrl/Alt/Shift; blank lines and `#` comments
+//! are ignored.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+const HEADER: &str = "# mdmd session recording v1";
+
+/// A parsed recording: the document it was made on, the scroll settings in
+/// effect, and the timed key presses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    pub file: PathBuf,
+    pub scroll_step: usize,
+    pub smooth_scroll: bool,
+    /// Key presses with their offset from the start of the recording.
+    pub events: Vec<(Duration, KeyEvent)>,
+}
+
+/// Format a key press as a recording key name, e.g. `j`, `C-o`, `S-BackTab`.
+pub fn format_key(key: &KeyEvent) -> Option<String> {
+    let name = match key.code {
+        KeyCode::Char(' ') => "Space".to_owned(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::Enter => "Enter".to_owned(),
+        KeyCode::Esc => "Esc".to_owned(),
+        KeyCode::Tab => "Tab".to_owned(),
+        KeyCode::BackTab => "BackTab".to_owned(),
+        KeyCode::Backspace => "Backspace".to_owned(),
+        KeyCode::Up => "Up".to_owned(),
+        KeyCode::Down => "Down".to_owned(),
+        KeyCode::Left => "Left".to_owned(),
+        KeyCode::Right => "Right".to_owned(),
+        KeyCode::Home => "Home".to_owned(),
+        KeyCode::End => "End".to_owned(),
+        KeyCode::PageUp => "PageUp".to_owned(),
+        KeyCode::PageDown => "PageDown".to_owned(),
+        KeyCode::Delete => "Delete".to_owned(),
+        KeyCode::F(n) => format!("F{n}"),
+        _ => return None,
+    };
+
+    let mut out = String::new();
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        out.push_str("C-");
+    }
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        out.push_str("A-");
+    }
+    // Shift is already reflected in the character itself for printable keys.
+    if key.modifiers.contains(KeyModifiers::SHIFT) && !matches!(key.code, KeyCode::Char(_)) {
+        out.push_str("S-");
+    }
+    out.push_str(&name);
+    Some(out)
+}
+
+/// Parse a recording key name produced by [`format_key`].
+pub fn parse_key(spec: &str) -> Option<KeyEvent> {
+    let mut modifiers = KeyModifiers::NONE;
+    let mut rest = spec;
+    loop {
+        // A bare "C", "A" or "S" is a character, not a modifier prefix.
+        if let Some(r) = rest.strip_prefix("C-").filter(|r| !r.is_empty()) {
+            modifiers |= KeyModifiers::CONTROL;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("A-").filter(|r| !r.is_empty()) {
+            modifiers |= KeyModifiers::ALT;
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("S-").filter(|r| !r.is_empty()) {
+            modifiers |= KeyModifiers::SHIFT;
+            rest = r;
+        } else {
+            break;
+        }
+    }
+
+    let code = match rest {
+        "Space" => KeyCode::Char(' '),
+        "Enter" => KeyCode::Enter,
+        "Esc" => KeyCode::Esc,
+        "Tab" => KeyCode::Tab,
+        "BackTab" => KeyCode::BackTab,
+        "Backspace" => KeyCode::Backspace,
+        "Up" => KeyCode::Up,
+        "Down" => KeyCode::Down,
+        "Left" => KeyCode::Left,
+        "Right" => KeyCode::Right,
+        "Home" => KeyCode::Home,
+        "End" => KeyCode::End,
+        "PageUp" => KeyCode::PageUp,
+        "PageDown" => KeyCode::PageDown,
+        "Delete" => KeyCode::Delete,
+        _ => {
+            let mut chars = rest.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => {
+                    if c.is_uppercase() {
+                        modifiers |= KeyModifiers::SHIFT;
+                    }
+                    KeyCode::Char(c)
+                }
+                (Some('F'), Some(_)) => KeyCode::F(rest[1..].parse().ok()?),
+                _ => return None,
+            }
+        }
+    };
+    Some(KeyEvent::new(code, modifiers))
+}
+
+/// Parse the text of a recording.
+pub fn parse_recording(text: &str) -> Result<Recording, String> {
+    let mut file = None;
+    let mut scroll_step = 1;
+    let mut smooth_scroll = false;
+    let mut events = Vec::new();
+
+    for (idx, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| format!("line {}: {msg}: {line}", idx + 1);
+        let (head, rest) = line.split_once(' ').unwrap_or((line, ""));
+        match head {
+            "file" => file = Some(PathBuf::from(rest)),
+            "scroll-step" => {
+                scroll_step = rest
+                    .parse()
+                    .ok()
+                    .filter(|n| *n >= 1)
+                    .ok_or_else(|| err("invalid scroll step"))?;
+            }
+            "smooth-scroll" => smooth_scroll = true,
+            _ => {
+                let ms: u64 = head.parse().map_err(|_| err("unrecognized line"))?;
+                let spec = rest
+                    .strip_prefix("key ")
+                    .ok_or_else(|| err("expected `<ms> key <name>`"))?;
+                let key = parse_key(spec.trim()).ok_or_else(|| err("unknown key"))?;
+                events.push((Duration::from_millis(ms), key));
+            }
+        }
+    }
+
+    Ok(Recording {
+        file: file.ok_or("recording has no `file` line")?,
+        scroll_step,
+        smooth_scroll,
+        events,
+    })
+}
+
+/// Read and parse a recording from disk.
+pub fn load_recording(path: &Path) -> Result<Recording, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read recording {}: {e}", path.display()))?;
+    parse_recording(&text).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Appends key presses to a recording file as they happen.
+pub struct Recorder {
+    out: File,
+    start: Instant,
+}
+
+impl Recorder {
+    /// Create `path` and write the recording header.
+    pub fn create(
+        path: &Path,
+        file: &Path,
+        scroll_step: usize,
+        smooth_scroll: bool,
+    ) -> io::Result<Self> {
+        let mut out = File::create(path)?;
+        writeln!(out, "{HEADER}")?;
+        writeln!(out, "file {}", file.display())?;
+        writeln!(out, "scroll-step {scroll_step}")?;
+        if smooth_scroll {
+            writeln!(out, "smooth-scroll")?;
+        }
+        if let Ok((w, h)) = crossterm::terminal::size() {
+            writeln!(out, "# terminal {w}x{h}")?;
+        }
+        Ok(Self {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    /// Append a key press.  Each line is written straight to the file so a
+    /// crash still leaves a usable recording.
+    fn record(&mut self, key: &KeyEvent) -> io::Result<()> {
+        if let Some(name) = format_key(key) {
+            let ms = self.start.elapsed().as_millis();
+            writeln!(self.out, "{ms} key {name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Feeds recorded key presses back at their original pace.
+pub struct Player {
+    events: VecDeque<(Duration, KeyEvent)>,
+    start: Instant,
+    speed: f64,
+}
+
+impl Player {
+    pub fn new(events: Vec<(Duration, KeyEvent)>, speed: f64) -> Self {
+        Self {
+            events: events.into(),
+            start: Instant::now(),
+            speed,
+        }
+    }
+
+    /// When the next recorded key is due, if any remain.
+    fn next_due(&self) -> Option<Instant> {
+        self.events
+            .front()
+            .map(|(t, _)| self.start + t.div_f64(self.speed))
+    }
+
+    /// Wait until `deadline`, watching real input.  Returns `true` when the
+    /// user pressed Esc or Ctrl-c, which stops playback.
+    fn wait_until(&mut self, deadline: Instant) -> io::Result<bool> {
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(false);
+            }
+            if event::poll(deadline - now)? {
+                if let Event::Key(key) = event::read()? {
+                    let interrupt = key.code == KeyCode::Esc
+                        || (key.code == KeyCode::Char('c')
+                            && key.modifiers.contains(KeyModifiers::CONTROL));
+                    if key.kind == KeyEventKind::Press && interrupt {
+                        self.events.clear();
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Where the TUI loop gets its events from.
+pub enum Input {
+    /// Read from the terminal, optionally recording key presses.
+    Live(Option<Recorder>),
+    /// Play back a recording, then hand over to the terminal.
+    Replay(Player),
+}
+
+impl Input {
+    /// Like [`event::poll`]: whether an event is ready within `timeout`.
+    pub fn poll(&mut self, timeout: Duration) -> io::Result<bool> {
+        match self {
+            Input::Replay(player) => match player.next_due() {
+                Some(due) => {
+                    let deadline = due.min(Instant::now() + timeout);
+                    if player.wait_until(deadline)? {
+                        return Ok(false);
+                    }
+                    Ok(Instant::now() >= due)
+                }
+                None => event::poll(timeout),
+            },
+            Input::Live(_) => event::poll(timeout),
+        }
+    }
+
+    /// Like [`event::read`]: block until the next event.
+    pub fn read(&mut self) -> io::Result<Event> {
+        match self {
+            Input::Replay(player) => {
+                if let Some(due) = player.next_due() {
+                    if !player.wait_until(due)? {
+                        if let Some((_, key)) = player.events.pop_front() {
+                            return Ok(Event::Key(key));
+                        }
+                    }
+                }
+                event::read()
+            }
+            Input::Live(recorder) => {
+                let ev = event::read()?;
+                if let (Some(rec), Event::Key(key)) = (recorder.as_mut(), &ev) {
+                    if key.kind == KeyEventKind::Press {
+                        rec.record(key)?;
+                    }
+                }
+                Ok(ev)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        let keys = [
+            key(KeyCode::Char('j'), KeyModifiers::NONE),
+            key(KeyCode::Char('G'), KeyModifiers::SHIFT),
+            key(KeyCode::Char('o'), KeyModifiers::CONTROL),
+            key(KeyCode::Char(' '), KeyModifiers::NONE),
+            key(KeyCode::BackTab, KeyModifiers::SHIFT),
+            key(KeyCode::Enter, KeyModifiers::NONE),
+            key(KeyCode::F(5), KeyModifiers::ALT),
+            key(KeyCode::Char('-'), KeyModifiers::NONE),
+        ];
+        for k in keys {
+            let name = format_key(&k).unwrap();
+            assert_eq!(parse_key(&name), Some(k), "round trip of {name}");
+        }
+        assert_eq!(
+            format_key(&key(KeyCode::Char('o'), KeyModifiers::CONTROL)).unwrap(),
+            "C-o"
+        );
+    }
+
+    #[test]
+    fn single_letter_modifier_names_are_characters() {
+        assert_eq!(
+            parse_key("C"),
+            Some(key(KeyCode::Char('C'), KeyModifiers::SHIFT))
+        );
+        assert_eq!(
+            parse_key("C-S"),
+            Some(key(
+                KeyCode::Char('S'),
+                KeyModifiers::CONTROL | KeyModifiers::SHIFT
+            ))
+        );
+        assert_eq!(parse_key("Nope"), None);
+    }
+
+    #[test]
+    fn parse_recording_reads_header_and_events() {
+        let text = "# mdmd session recording v1\nfile /tmp/a.md\nscroll-step 3\nsmooth-scroll\n\
+                    # terminal 80x24\n\n0 key j\n250 key C-d\n";
+        let rec = parse_recording(text).unwrap();
+        assert_eq!(rec.file, PathBuf::from("/tmp/a.md"));
+        assert_eq!(rec.scroll_step, 3);
+        assert!(rec.smooth_scroll);
+        assert_eq!(rec.events.len(), 2);
+        assert_eq!(rec.events[1].0, Duration::from_millis(250));
+        assert_eq!(
+            rec.events[1].1,
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn parse_recording_reports_bad_lines() {
+        assert!(parse_recording("0 key j\n")
+            .unwrap_err()
+            .contains("no `file`"));
+        let err = parse_recording("file a.md\n10 key Hyper-x\n").unwrap_err();
+        assert!(err.starts_with("line 2: unknown key"), "{err}");
+        assert!(parse_recording("file a.md\nscroll-step 0\n").is_err());
+    }
+
+    #[test]
+    fn recorder_output_parses_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.txt");
+        let mut rec = Recorder::create(&path, Path::new("/docs/a.md"), 2, false).unwrap();
+        rec.record(&key(KeyCode::Char('n'), KeyModifiers::NONE))
+            .unwrap();
+        rec.record(&key(KeyCode::Tab, KeyModifiers::NONE)).unwrap();
+
+        let parsed = load_recording(&path).unwrap();
+        assert_eq!(parsed.file, PathBuf::from("/docs/a.md"));
+        assert_eq!(parsed.scroll_step, 2);
+        let keys: Vec<_> = parsed.events.iter().map(|(_, k)| k.code).collect();
+        assert_eq!(keys, vec![KeyCode::Char('n'), KeyCode::Tab]);
+    }
+}
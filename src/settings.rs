@@ -0,0 +1,1168 @@
+//! Layered settings: defaults < config file < `MDMD_*` env vars < CLI flags.
+//!
+//! Settings mirror command-line flags, and their defaults are the flags'
+//! clap defaults, so the CLI definitions stay the single source of truth.
+//! The exception is the `[ui]` table, whose settings have no flag and apply
+//! to every subcommand.  The config file is TOML with one table per
+//! subcommand:
+//!
+//! ```toml
+//! [view]
+//! scroll_step = 3
+//! open_with = ["png,jpg=feh", "log=!less"]
+//!
+//! [serve]
+//! port = 4000
+//!
+//! [ui]
+//! locale = "de"
+//! ```
+//!
+//! It is read from `$MDMD_CONFIG` when set, else from
+//! [`default_config_path`].  Each key also has an environment variable named
+//! `MDMD_<TABLE>_<KEY>` (e.g. `MDMD_SERVE_PORT`); list values in env vars are
+//! separated by `;`.  A higher layer replaces the value of a lower one
+//! outright, lists included.
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::parser::ValueSource;
+use clap::{ArgMatches, Command};
+use mdmd::filter::{self, Filters};
+use mdmd::i18n::Locale;
+
+use crate::handlers::{self, HandlerRule};
+
+/// Environment variable naming the config file to read instead of the
+/// default location.
+pub const CONFIG_ENV: &str = "MDMD_CONFIG";
+
+/// Value type of a setting, which decides how config, env, and CLI values
+/// are parsed.
+#[derive(Clone, Copy)]
+enum Kind {
+    Bool,
+    UInt {
+        min: u64,
+        max: u64,
+    },
+    Str,
+    /// `--open-with` handler rules.
+    Rules,
+    /// `--filter` commands.
+    Filters,
+    /// Directories, such as `--allow`.
+    Paths,
+    /// File extensions, comma-separated, such as `--serve-types`.
+    Extensions,
+    /// UI language: `auto` or a name accepted by [`Locale::parse`].
+    Locale,
+}
+
+/// One configurable setting and the flag it mirrors.
+struct Spec {
+    /// `<table>.<key>`, as written in the config file.
+    key: &'static str,
+    /// Subcommand defining the flag; its table name in the config file.
+    /// Settings in [`SHARED_TABLE`] apply to every subcommand.
+    command: &'static str,
+    /// clap argument id of the flag, if the setting has one.
+    arg: Option<&'static str>,
+    kind: Kind,
+}
+
+const fn uint(max: u64) -> Kind {
+    Kind::UInt { min: 0, max }
+}
+
+/// Config table whose settings apply to every subcommand.
+const SHARED_TABLE: &str = "ui";
+
+const SPECS: &[Spec] = &[
+    Spec {
+        key: "ui.locale",
+        command: SHARED_TABLE,
+        arg: None,
+        kind: Kind::Locale,
+    },
+    Spec {
+        key: "view.scroll_step",
+        command: "view",
+        arg: Some("scroll_step"),
+        kind: Kind::UInt {
+            min: 1,
+            max: u16::MAX as u64,
+        },
+    },
+    Spec {
+        key: "view.smooth_scroll",
+        command: "view",
+        arg: Some("smooth_scroll"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.breadcrumbs",
+        command: "view",
+        arg: Some("breadcrumbs"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.no_hyperlinks",
+        command: "view",
+        arg: Some("no_hyperlinks"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.no_vars",
+        command: "view",
+        arg: Some("no_vars"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.numbered_headings",
+        command: "view",
+        arg: Some("numbered_headings"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.smart",
+        command: "view",
+        arg: Some("smart"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.slug_style",
+        command: "view",
+        arg: Some("slug_style"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "view.text_width",
+        command: "view",
+        arg: Some("text_width"),
+        kind: uint(u16::MAX as u64),
+    },
+    Spec {
+        key: "view.justify",
+        command: "view",
+        arg: Some("justify"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.hyphenate",
+        command: "view",
+        arg: Some("hyphenate"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "view.filter",
+        command: "view",
+        arg: Some("filter"),
+        kind: Kind::Filters,
+    },
+    Spec {
+        key: "view.filter_timeout",
+        command: "view",
+        arg: Some("filter_timeout"),
+        kind: Kind::UInt { min: 1, max: 3600 },
+    },
+    Spec {
+        key: "view.shortcodes",
+        command: "view",
+        arg: Some("shortcodes"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "view.open_with",
+        command: "view",
+        arg: Some("open_with"),
+        kind: Kind::Rules,
+    },
+    Spec {
+        key: "serve.bind",
+        command: "serve",
+        arg: Some("bind"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.port",
+        command: "serve",
+        arg: Some("port"),
+        kind: uint(u16::MAX as u64),
+    },
+    Spec {
+        key: "serve.no_open",
+        command: "serve",
+        arg: Some("no_open"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.verbose",
+        command: "serve",
+        arg: Some("verbose"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.render_budget_ms",
+        command: "serve",
+        arg: Some("render_budget_ms"),
+        kind: uint(u64::MAX),
+    },
+    Spec {
+        key: "serve.request_budget_ms",
+        command: "serve",
+        arg: Some("request_budget_ms"),
+        kind: uint(u64::MAX),
+    },
+    Spec {
+        key: "serve.site_title",
+        command: "serve",
+        arg: Some("site_title"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.favicon",
+        command: "serve",
+        arg: Some("favicon"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.allow_edit",
+        command: "serve",
+        arg: Some("allow_edit"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.drafts",
+        command: "serve",
+        arg: Some("drafts"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.no_vars",
+        command: "serve",
+        arg: Some("no_vars"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.numbered_headings",
+        command: "serve",
+        arg: Some("numbered_headings"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.heading_offset",
+        command: "serve",
+        arg: Some("heading_offset"),
+        kind: uint(5),
+    },
+    Spec {
+        key: "serve.title_from_h1",
+        command: "serve",
+        arg: Some("title_from_h1"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.smart",
+        command: "serve",
+        arg: Some("smart"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.slug_style",
+        command: "serve",
+        arg: Some("slug_style"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.filter",
+        command: "serve",
+        arg: Some("filter"),
+        kind: Kind::Filters,
+    },
+    Spec {
+        key: "serve.filter_timeout",
+        command: "serve",
+        arg: Some("filter_timeout"),
+        kind: Kind::UInt { min: 1, max: 3600 },
+    },
+    Spec {
+        key: "serve.shortcodes",
+        command: "serve",
+        arg: Some("shortcodes"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.inject_head",
+        command: "serve",
+        arg: Some("inject_head"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.allow_page_head",
+        command: "serve",
+        arg: Some("allow_page_head"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.template",
+        command: "serve",
+        arg: Some("template"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.dev",
+        command: "serve",
+        arg: Some("dev"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.page_max_age",
+        command: "serve",
+        arg: Some("page_max_age"),
+        kind: uint(u32::MAX as u64),
+    },
+    Spec {
+        key: "serve.asset_max_age",
+        command: "serve",
+        arg: Some("asset_max_age"),
+        kind: uint(u32::MAX as u64),
+    },
+    Spec {
+        key: "serve.request_timeout",
+        command: "serve",
+        arg: Some("request_timeout"),
+        kind: Kind::UInt { min: 1, max: 3600 },
+    },
+    Spec {
+        key: "serve.max_in_flight",
+        command: "serve",
+        arg: Some("max_in_flight"),
+        kind: Kind::UInt { min: 1, max: 65535 },
+    },
+    Spec {
+        key: "serve.max_body_bytes",
+        command: "serve",
+        arg: Some("max_body_bytes"),
+        kind: uint(u32::MAX as u64),
+    },
+    Spec {
+        key: "serve.case_insensitive",
+        command: "serve",
+        arg: Some("case_insensitive"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.follow_symlinks",
+        command: "serve",
+        arg: Some("follow_symlinks"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.no_follow_symlinks",
+        command: "serve",
+        arg: Some("no_follow_symlinks"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.allow",
+        command: "serve",
+        arg: Some("allow"),
+        kind: Kind::Paths,
+    },
+    Spec {
+        key: "serve.serve_types",
+        command: "serve",
+        arg: Some("serve_types"),
+        kind: Kind::Extensions,
+    },
+    Spec {
+        key: "serve.audit_log",
+        command: "serve",
+        arg: Some("audit_log"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.no_audit_log",
+        command: "serve",
+        arg: Some("no_audit_log"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.copy_url",
+        command: "serve",
+        arg: Some("copy_url"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "serve.open_browser",
+        command: "serve",
+        arg: Some("open_browser"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "serve.open_path",
+        command: "serve",
+        arg: Some("open_path"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "html.constrained",
+        command: "html",
+        arg: Some("constrained"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "html.site_title",
+        command: "html",
+        arg: Some("site_title"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "html.no_vars",
+        command: "html",
+        arg: Some("no_vars"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "html.numbered_headings",
+        command: "html",
+        arg: Some("numbered_headings"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "html.heading_offset",
+        command: "html",
+        arg: Some("heading_offset"),
+        kind: uint(5),
+    },
+    Spec {
+        key: "html.title_from_h1",
+        command: "html",
+        arg: Some("title_from_h1"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "html.smart",
+        command: "html",
+        arg: Some("smart"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "html.slug_style",
+        command: "html",
+        arg: Some("slug_style"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "html.filter",
+        command: "html",
+        arg: Some("filter"),
+        kind: Kind::Filters,
+    },
+    Spec {
+        key: "html.filter_timeout",
+        command: "html",
+        arg: Some("filter_timeout"),
+        kind: Kind::UInt { min: 1, max: 3600 },
+    },
+    Spec {
+        key: "html.shortcodes",
+        command: "html",
+        arg: Some("shortcodes"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "html.inject_head",
+        command: "html",
+        arg: Some("inject_head"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "html.allow_page_head",
+        command: "html",
+        arg: Some("allow_page_head"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "html.template",
+        command: "html",
+        arg: Some("template"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "render.format",
+        command: "render",
+        arg: Some("format"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "render.no_hyperlinks",
+        command: "render",
+        arg: Some("no_hyperlinks"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "render.no_vars",
+        command: "render",
+        arg: Some("no_vars"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "render.numbered_headings",
+        command: "render",
+        arg: Some("numbered_headings"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "render.smart",
+        command: "render",
+        arg: Some("smart"),
+        kind: Kind::Bool,
+    },
+    Spec {
+        key: "render.slug_style",
+        command: "render",
+        arg: Some("slug_style"),
+        kind: Kind::Str,
+    },
+    Spec {
+        key: "render.filter",
+        command: "render",
+        arg: Some("filter"),
+        kind: Kind::Filters,
+    },
+    Spec {
+        key: "render.filter_timeout",
+        command: "render",
+        arg: Some("filter_timeout"),
+        kind: Kind::UInt { min: 1, max: 3600 },
+    },
+    Spec {
+        key: "render.shortcodes",
+        command: "render",
+        arg: Some("shortcodes"),
+        kind: Kind::Str,
+    },
+];
+
+impl Spec {
+    fn env_var(&self) -> String {
+        format!("MDMD_{}", self.key.replace('.', "_").to_ascii_uppercase())
+    }
+
+    /// Parse one textual value (env var, CLI flag, or clap default).
+    fn parse_text(&self, raw: &[String]) -> Result<Value, String> {
+        let single = || raw.last().map(String::as_str).unwrap_or_default();
+        match self.kind {
+            Kind::Bool => match single().to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" => Ok(Value::Bool(true)),
+                "0" | "false" | "no" | "off" | "" => Ok(Value::Bool(false)),
+                other => Err(format!("expected true or false, got '{other}'")),
+            },
+            Kind::UInt { .. } => {
+                let n = single()
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| format!("expected a non-negative integer, got '{}'", single()))?;
+                self.check_range(n)
+            }
+            Kind::Str => Ok(Value::Str(single().to_owned())),
+            Kind::Locale => match single() {
+                "auto" => Ok(Value::Str("auto".to_owned())),
+                name if Locale::parse(name).is_some() => Ok(Value::Str(name.to_owned())),
+                name => {
+                    let known: Vec<&str> = Locale::ALL.iter().map(|l| l.tag()).collect();
+                    Err(format!(
+                        "unsupported locale '{name}', expected auto or one of {}",
+                        known.join(", ")
+                    ))
+                }
+            },
+            Kind::Rules => {
+                for rule in raw {
+                    handlers::parse_rule(rule)?;
+                }
+                Ok(Value::List(raw.to_vec()))
+            }
+            Kind::Filters => {
+                for spec in raw {
+                    filter::parse_filter(spec)?;
+                }
+                Ok(Value::List(raw.to_vec()))
+            }
+            Kind::Paths => {
+                if raw.iter().any(String::is_empty) {
+                    return Err("expected a directory, got an empty path".to_owned());
+                }
+                Ok(Value::List(raw.to_vec()))
+            }
+            Kind::Extensions => {
+                let mut extensions = Vec::new();
+                for ext in raw.iter().flat_map(|list| list.split(',')) {
+                    let ext = ext.trim().trim_start_matches('.').to_ascii_lowercase();
+                    if ext.is_empty() {
+                        continue;
+                    }
+                    if !ext
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "-_+".contains(c))
+                    {
+                        return Err(format!("expected extensions like md,png, got '{ext}'"));
+                    }
+                    extensions.push(ext);
+                }
+                Ok(Value::List(extensions))
+            }
+        }
+    }
+
+    fn parse_toml(&self, value: &toml::Value) -> Result<Value, String> {
+        match (self.kind, value) {
+            (Kind::Bool, toml::Value::Boolean(b)) => Ok(Value::Bool(*b)),
+            (Kind::UInt { .. }, toml::Value::Integer(n)) => {
+                let n = u64::try_from(*n)
+                    .map_err(|_| format!("expected a non-negative integer, got {n}"))?;
+                self.check_range(n)
+            }
+            (Kind::Str, toml::Value::String(s)) => Ok(Value::Str(s.clone())),
+            (Kind::Locale, toml::Value::String(s)) => self.parse_text(std::slice::from_ref(s)),
+            (
+                Kind::Rules | Kind::Filters | Kind::Paths | Kind::Extensions,
+                toml::Value::String(s),
+            ) => self.parse_text(std::slice::from_ref(s)),
+            (
+                Kind::Rules | Kind::Filters | Kind::Paths | Kind::Extensions,
+                toml::Value::Array(items),
+            ) => {
+                let rules = items
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_owned))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| format!("expected {}", self.kind.describe()))?;
+                self.parse_text(&rules)
+            }
+            (kind, other) => Err(format!(
+                "expected {}, got {}",
+                kind.describe(),
+                other.type_str()
+            )),
+        }
+    }
+
+    fn check_range(&self, n: u64) -> Result<Value, String> {
+        match self.kind {
+            Kind::UInt { min, max } if n < min || n > max => {
+                Err(format!("{n} is not in {min}..={max}"))
+            }
+            _ => Ok(Value::UInt(n)),
+        }
+    }
+}
+
+impl Kind {
+    fn describe(self) -> &'static str {
+        match self {
+            Kind::Bool => "a boolean",
+            Kind::UInt { .. } => "an integer",
+            Kind::Str | Kind::Locale => "a string",
+            Kind::Rules => "an array of \"EXT=CMD\" strings",
+            Kind::Filters => "an array of \"STAGE=CMD\" strings",
+            Kind::Paths => "an array of paths",
+            Kind::Extensions => "an array of file extensions",
+        }
+    }
+}
+
+/// A resolved setting value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    UInt(u64),
+    Str(String),
+    List(Vec<String>),
+}
+
+impl fmt::Display for Value {
+    /// TOML syntax, so `mdmd config show --resolved` output can be pasted
+    /// into a config file.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::UInt(n) => write!(f, "{n}"),
+            Value::Str(s) => write!(f, "{}", toml::Value::String(s.clone())),
+            Value::List(items) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .map(|s| toml::Value::String(s.clone()).to_string())
+                    .collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+        }
+    }
+}
+
+/// Which layer a resolved value came from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    Default,
+    ConfigFile(PathBuf),
+    Env(String),
+    CommandLine,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::ConfigFile(path) => write!(f, "config file {}", path.display()),
+            Source::Env(var) => write!(f, "env {var}"),
+            Source::CommandLine => write!(f, "command line"),
+        }
+    }
+}
+
+/// One resolved setting.
+pub struct Entry {
+    pub key: &'static str,
+    pub value: Value,
+    pub source: Source,
+}
+
+/// The effective settings for one invocation.
+pub struct Settings {
+    entries: Vec<Entry>,
+}
+
+impl Settings {
+    /// All resolved settings, in definition order.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    fn value(&self, key: &str) -> &Value {
+        &self
+            .entries
+            .iter()
+            .find(|e| e.key == key)
+            .unwrap_or_else(|| panic!("setting {key} was not resolved"))
+            .value
+    }
+
+    pub fn bool(&self, key: &str) -> bool {
+        matches!(self.value(key), Value::Bool(true))
+    }
+
+    pub fn uint(&self, key: &str) -> u64 {
+        match self.value(key) {
+            Value::UInt(n) => *n,
+            other => panic!("setting {key} is not an integer: {other:?}"),
+        }
+    }
+
+    pub fn string(&self, key: &str) -> String {
+        match self.value(key) {
+            Value::Str(s) => s.clone(),
+            other => panic!("setting {key} is not a string: {other:?}"),
+        }
+    }
+
+    /// A string setting without a default, `None` while it is empty.
+    pub fn optional_string(&self, key: &str) -> Option<String> {
+        Some(self.string(key)).filter(|s| !s.is_empty())
+    }
+
+    /// The UI locale: the `ui.locale` setting, or the environment's locale
+    /// when it is `auto`.
+    pub fn locale(&self) -> Locale {
+        match self.string("ui.locale").as_str() {
+            "auto" => Locale::from_env(),
+            name => Locale::parse(name).expect("locales are validated on resolve"),
+        }
+    }
+
+    /// Handler rules; every rule was validated when it was resolved.
+    pub fn handler_rules(&self, key: &str) -> Vec<HandlerRule> {
+        match self.value(key) {
+            Value::List(rules) => rules
+                .iter()
+                .map(|r| handlers::parse_rule(r).expect("rules are validated on resolve"))
+                .collect(),
+            other => panic!("setting {key} is not a rule list: {other:?}"),
+        }
+    }
+
+    /// File extensions, lowercased and without dots; empty when unset.
+    pub fn extensions(&self, key: &str) -> Vec<String> {
+        match self.value(key) {
+            Value::List(extensions) => extensions.clone(),
+            other => panic!("setting {key} is not an extension list: {other:?}"),
+        }
+    }
+
+    /// A list of paths, such as `serve.allow`.
+    pub fn paths(&self, key: &str) -> Vec<PathBuf> {
+        match self.value(key) {
+            Value::List(paths) => paths.iter().map(PathBuf::from).collect(),
+            other => panic!("setting {key} is not a path list: {other:?}"),
+        }
+    }
+
+    /// The `--filter` commands and timeout of `command`; every filter was
+    /// validated when it was resolved.
+    pub fn filters(&self, command: &str) -> Filters {
+        let commands = match self.value(&format!("{command}.filter")) {
+            Value::List(specs) => specs
+                .iter()
+                .map(|s| filter::parse_filter(s).expect("filters are validated on resolve"))
+                .collect(),
+            other => panic!("setting {command}.filter is not a filter list: {other:?}"),
+        };
+        Filters {
+            commands,
+            timeout: Duration::from_secs(self.uint(&format!("{command}.filter_timeout"))),
+        }
+    }
+}
+
+/// Where mdmd looks for its config file when `$MDMD_CONFIG` is unset:
+/// `$XDG_CONFIG_HOME/mdmd/config.toml`, falling back to
+/// `$HOME/.config/mdmd/config.toml`.  `None` when neither variable is set.
+pub fn default_config_path() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|v| !v.is_empty())
+                .map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(base.join("mdmd").join("config.toml"))
+}
+
+/// The config file to read, and whether it must exist: a path named by
+/// `$MDMD_CONFIG` must, the default location is optional.
+pub fn config_path() -> Option<(PathBuf, bool)> {
+    match env::var_os(CONFIG_ENV).filter(|v| !v.is_empty()) {
+        Some(path) => Some((PathBuf::from(path), true)),
+        None => default_config_path().map(|p| (p, false)),
+    }
+}
+
+/// Read and parse the config file, or `None` if there is none.
+fn load_config_file() -> Result<Option<(PathBuf, toml::Table)>, String> {
+    let Some((path, required)) = config_path() else {
+        return Ok(None);
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if !required && e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("cannot read config file {}: {e}", path.display())),
+    };
+    let table = text
+        .parse::<toml::Table>()
+        .map_err(|e| format!("invalid config file {}: {e}", path.display()))?;
+    Ok(Some((path, table)))
+}
+
+/// Look up `spec` in a parsed config file, rejecting unknown keys.
+fn config_values<'t>(
+    path: &Path,
+    table: &'t toml::Table,
+    specs: &[&Spec],
+) -> Result<Vec<(&'static str, &'t toml::Value)>, String> {
+    let mut found = Vec::new();
+    for (section, body) in table {
+        let Some(body) = body.as_table() else {
+            return Err(format!(
+                "{}: '{section}' must be a table like [{section}]",
+                path.display()
+            ));
+        };
+        for (name, value) in body {
+            let key = format!("{section}.{name}");
+            let spec = SPECS
+                .iter()
+                .find(|s| s.key == key)
+                .ok_or_else(|| format!("{}: unknown setting '{key}'", path.display()))?;
+            if specs.iter().any(|s| s.key == spec.key) {
+                found.push((spec.key, value));
+            }
+        }
+    }
+    Ok(found)
+}
+
+/// The default of `spec` as text: its flag's clap default, or the built-in
+/// default of settings without a flag.
+fn default_text(cli: &Command, spec: &Spec) -> Vec<String> {
+    let Some(id) = spec.arg else {
+        return match spec.kind {
+            Kind::Locale => vec!["auto".to_owned()],
+            _ => Vec::new(),
+        };
+    };
+    cli.find_subcommand(spec.command)
+        .and_then(|sub| sub.get_arguments().find(|a| a.get_id() == id))
+        .map(|arg| {
+            arg.get_default_values()
+                .iter()
+                .map(|v| v.to_string_lossy().into_owned())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The values of `spec`'s flag if it was given on the command line.
+fn cli_values(matches: &ArgMatches, spec: &Spec) -> Option<Vec<String>> {
+    let id = spec.arg?;
+    let raw = matches.try_get_raw(id).ok()??;
+    if matches.value_source(id) != Some(ValueSource::CommandLine) {
+        return None;
+    }
+    Some(raw.map(|v| v.to_string_lossy().into_owned()).collect())
+}
+
+/// Resolve the settings of `command` (every setting when `None`), plus the
+/// shared `[ui]` settings.
+///
+/// `cli` is the full command definition, which supplies defaults; `matches`
+/// holds the parsed flags of the invoked (sub)command, if any.
+pub fn resolve(
+    cli: &Command,
+    command: Option<&str>,
+    matches: Option<&ArgMatches>,
+) -> Result<Settings, String> {
+    let config = load_config_file()?;
+    resolve_layers(cli, command, matches, config, |var| env::var(var).ok())
+}
+
+fn resolve_layers(
+    cli: &Command,
+    command: Option<&str>,
+    matches: Option<&ArgMatches>,
+    config: Option<(PathBuf, toml::Table)>,
+    env_var: impl Fn(&str) -> Option<String>,
+) -> Result<Settings, String> {
+    let specs: Vec<&Spec> = SPECS
+        .iter()
+        .filter(|s| s.command == SHARED_TABLE || command.is_none_or(|c| c == s.command))
+        .collect();
+    let file_values = match &config {
+        Some((path, table)) => config_values(path, table, &specs)?,
+        None => Vec::new(),
+    };
+
+    let mut entries = Vec::with_capacity(specs.len());
+    for spec in specs {
+        let mut value = spec
+            .parse_text(&default_text(cli, spec))
+            .map_err(|e| format!("bad default for {}: {e}", spec.key))?;
+        let mut source = Source::Default;
+
+        if let Some((_, v)) = file_values.iter().find(|(k, _)| *k == spec.key) {
+            let path = &config.as_ref().expect("values come from a file").0;
+            value = spec
+                .parse_toml(v)
+                .map_err(|e| format!("{}: {}: {e}", path.display(), spec.key))?;
+            source = Source::ConfigFile(path.clone());
+        }
+
+        let var = spec.env_var();
+        if let Some(raw) = env_var(&var).filter(|v| !v.is_empty()) {
+            let raw: Vec<String> = match spec.kind {
+                Kind::Rules | Kind::Filters => raw
+                    .split(';')
+                    .map(str::trim)
+                    .filter(|r| !r.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+                Kind::Paths => env::split_paths(&raw)
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .map(|p| p.to_string_lossy().into_owned())
+                    .collect(),
+                _ => vec![raw],
+            };
+            value = spec.parse_text(&raw).map_err(|e| format!("{var}: {e}"))?;
+            source = Source::Env(var);
+        }
+
+        if let Some(raw) = matches.and_then(|m| cli_values(m, spec)) {
+            value = spec.parse_text(&raw).map_err(|e| {
+                format!("--{}: {e}", spec.arg.unwrap_or(spec.key).replace('_', "-"))
+            })?;
+            source = Source::CommandLine;
+        }
+
+        entries.push(Entry {
+            key: spec.key,
+            value,
+            source,
+        });
+    }
+    Ok(Settings { entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn resolve_with(args: &[&str], config: &str, env: &[(&str, &str)]) -> Result<Settings, String> {
+        let cli = crate::Cli::command();
+        let matches = cli.clone().try_get_matches_from(args).unwrap();
+        let (name, sub) = matches.subcommand().unwrap();
+        let table = config.parse::<toml::Table>().unwrap();
+        resolve_layers(
+            &cli,
+            Some(name),
+            Some(sub),
+            Some((PathBuf::from("/cfg.toml"), table)),
+            |var| {
+                env.iter()
+                    .find(|(k, _)| *k == var)
+                    .map(|(_, v)| v.to_string())
+            },
+        )
+    }
+
+    fn source_of(settings: &Settings, key: &str) -> Source {
+        let entry = settings.entries().iter().find(|e| e.key == key).unwrap();
+        entry.source.clone()
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let config = "[serve]\nport = 4000\nbind = \"10.0.0.1\"\nverbose = true\n";
+        let env = [
+            ("MDMD_SERVE_BIND", "127.0.0.1"),
+            ("MDMD_SERVE_PORT", "4500"),
+        ];
+        let s = resolve_with(&["mdmd", "serve", "--port", "5000", "x.md"], config, &env).unwrap();
+
+        assert_eq!(s.uint("serve.port"), 5000);
+        assert_eq!(source_of(&s, "serve.port"), Source::CommandLine);
+        assert_eq!(s.string("serve.bind"), "127.0.0.1");
+        assert_eq!(
+            source_of(&s, "serve.bind"),
+            Source::Env("MDMD_SERVE_BIND".into())
+        );
+        assert!(s.bool("serve.verbose"));
+        assert_eq!(
+            source_of(&s, "serve.verbose"),
+            Source::ConfigFile("/cfg.toml".into())
+        );
+        assert_eq!(s.uint("serve.request_budget_ms"), 250);
+        assert_eq!(source_of(&s, "serve.request_budget_ms"), Source::Default);
+        // Only the invoked subcommand's settings and shared ones are resolved.
+        assert!(s
+            .entries()
+            .iter()
+            .all(|e| e.key.starts_with("serve.") || e.key.starts_with("ui.")));
+    }
+
+    #[test]
+    fn rules_come_from_toml_arrays_and_semicolon_separated_env() {
+        let s = resolve_with(
+            &["mdmd", "view", "x.md"],
+            "[view]\nopen_with = [\"png,jpg=feh\"]\n",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(s.handler_rules("view.open_with").len(), 1);
+
+        let env = [("MDMD_VIEW_OPEN_WITH", "png=feh; log=!less")];
+        let s = resolve_with(&["mdmd", "view", "x.md"], "", &env).unwrap();
+        let rules = s.handler_rules("view.open_with");
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].extensions, vec!["log"]);
+    }
+
+    #[test]
+    fn paths_come_from_repeated_flags_and_path_separated_env() {
+        let args = ["mdmd", "serve", "--allow", "/a", "--allow", "b", "x.md"];
+        let s = resolve_with(&args, "[serve]\nallow = \"/c\"\n", &[]).unwrap();
+        assert_eq!(s.paths("serve.allow"), [Path::new("/a"), Path::new("b")]);
+
+        let env = [("MDMD_SERVE_ALLOW", "/srv/img::/srv/pdf")];
+        let s = resolve_with(&["mdmd", "serve", "x.md"], "", &env).unwrap();
+        assert_eq!(
+            s.paths("serve.allow"),
+            [Path::new("/srv/img"), Path::new("/srv/pdf")]
+        );
+        let s = resolve_with(&["mdmd", "serve", "x.md"], "", &[]).unwrap();
+        assert!(s.paths("serve.allow").is_empty());
+    }
+
+    #[test]
+    fn extensions_are_split_on_commas_and_normalized() {
+        let args = ["mdmd", "serve", "--serve-types", "md, .PNG,jpg", "x.md"];
+        let s = resolve_with(&args, "", &[]).unwrap();
+        assert_eq!(s.extensions("serve.serve_types"), ["md", "png", "jpg"]);
+
+        let config = "[serve]\nserve_types = [\"md\", \"svg\"]\n";
+        let s = resolve_with(&["mdmd", "serve", "x.md"], config, &[]).unwrap();
+        assert_eq!(s.extensions("serve.serve_types"), ["md", "svg"]);
+
+        let s = resolve_with(&["mdmd", "serve", "x.md"], "", &[]).unwrap();
+        assert!(s.extensions("serve.serve_types").is_empty());
+
+        let args = ["mdmd", "serve", "--serve-types", "md,a/b", "x.md"];
+        let err = resolve_with(&args, "", &[]).err().unwrap();
+        assert!(
+            err.starts_with("--serve-types: expected extensions"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn invalid_values_name_their_layer() {
+        let args = ["mdmd", "view", "x.md"];
+        let err = resolve_with(&args, "[view]\nscrol_step = 2\n", &[])
+            .err()
+            .unwrap();
+        assert!(err.contains("unknown setting 'view.scrol_step'"), "{err}");
+
+        let err = resolve_with(&args, "[view]\nscroll_step = 0\n", &[])
+            .err()
+            .unwrap();
+        assert!(
+            err.contains("view.scroll_step: 0 is not in 1..=65535"),
+            "{err}"
+        );
+
+        let err = resolve_with(&args, "[view]\nsmooth_scroll = \"yes\"\n", &[])
+            .err()
+            .unwrap();
+        assert!(err.contains("expected a boolean, got string"), "{err}");
+
+        let env = [("MDMD_VIEW_SMOOTH_SCROLL", "maybe")];
+        let err = resolve_with(&args, "", &env).err().unwrap();
+        assert!(err.starts_with("MDMD_VIEW_SMOOTH_SCROLL:"), "{err}");
+    }
+
+    #[test]
+    fn ui_locale_is_shared_and_validated() {
+        let s = resolve_with(&["mdmd", "html", "x.md"], "[ui]\nlocale = \"de\"\n", &[]).unwrap();
+        assert_eq!(s.locale(), Locale::De);
+
+        let env = [("MDMD_UI_LOCALE", "fr")];
+        let err = resolve_with(&["mdmd", "serve", "x.md"], "", &env)
+            .err()
+            .unwrap();
+        assert!(err.contains("unsupported locale 'fr'"), "{err}");
+    }
+
+    #[test]
+    fn resolved_values_print_as_toml() {
+        assert_eq!(Value::Str("a\"b".into()).to_string(), "'a\"b'");
+        assert_eq!(
+            Value::List(vec!["png=feh".into()]).to_string(),
+            "[\"png=feh\"]"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Expiring share links for `mdmd serve --share DURATION`.
+//!
+//! The server makes up a random token at startup and answers only requests
+//! that carry it: as `?share=<token>` on the printed URL, or in the cookie
+//! set by the first request that did.  Once the duration is up the server
+//! shuts down, so a link sent around stops working instead of leaving the
+//! directory readable for as long as someone forgets the terminal.
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+/// Name of the query parameter and cookie carrying the token.
+const PARAM: &str = "share";
+const COOKIE: &str = "mdmd_share";
+
+/// Parse a duration such as `30m`, `2h`, `90s`, `1d`, or `1h30m`.
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let invalid = || format!("expected a duration like 30m, 2h, or 1h30m, got '{text}'");
+    let mut total: u64 = 0;
+    let mut digits = String::new();
+    for c in text.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let unit = match c {
+            's' => 1,
+            'm' => 60,
+            'h' => 60 * 60,
+            'd' => 24 * 60 * 60,
+            _ => return Err(invalid()),
+        };
+        let n: u64 = digits.parse().map_err(|_| invalid())?;
+        total = n
+            .checked_mul(unit)
+            .and_then(|secs| total.checked_add(secs))
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+    if !digits.is_empty() || total == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(total))
+}
+
+/// The token of a running share and when it stops being accepted.
+pub struct Share {
+    pub token: String,
+    pub expires: Instant,
+}
+
+impl Share {
+    /// A share with a fresh token, lasting `duration` from now.
+    pub fn new(duration: Duration) -> Share {
+        Share {
+            token: random_token(),
+            expires: Instant::now() + duration,
+        }
+    }
+
+    /// Time until the share expires; zero once it has.
+    pub fn remaining(&self) -> Duration {
+        self.expires.saturating_duration_since(Instant::now())
+    }
+
+    /// `url` with the token added to its query.
+    pub fn link(&self, url: &str) -> String {
+        let sep = if url.contains('?') { '&' } else { '?' };
+        format!("{url}{sep}{PARAM}={}", self.token)
+    }
+
+    /// Whether `query`, a raw query string, carries the token.
+    pub fn in_query(&self, query: &str) -> bool {
+        query
+            .split('&')
+            .filter_map(|pair| pair.strip_prefix(PARAM)?.strip_prefix('='))
+            .any(|value| self.matches(value))
+    }
+
+    /// Whether a `Cookie` header value carries the token.
+    pub fn in_cookie(&self, cookies: &str) -> bool {
+        cookies
+            .split(';')
+            .filter_map(|pair| pair.trim().strip_prefix(COOKIE)?.strip_prefix('='))
+            .any(|value| self.matches(value))
+    }
+
+    /// The `Set-Cookie` value remembering the token until the share expires,
+    /// so pages and the requests they make need not repeat it.
+    pub fn cookie(&self) -> String {
+        format!(
+            "{COOKIE}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+            self.token,
+            self.remaining().as_secs()
+        )
+    }
+
+    /// Compare in time independent of where `value` first differs.
+    fn matches(&self, value: &str) -> bool {
+        value.len() == self.token.len()
+            && value
+                .bytes()
+                .zip(self.token.bytes())
+                .fold(0, |diff, (a, b)| diff | (a ^ b))
+                == 0
+    }
+}
+
+/// `target`, a request target such as `/guide.md?share=<token>`, with the
+/// token masked, for logs that must not hand out the link.
+pub fn redact(target: &str) -> Cow<'_, str> {
+    let Some((path, query)) = target.split_once('?') else {
+        return Cow::Borrowed(target);
+    };
+    let is_token = |pair: &str| pair.strip_prefix(PARAM).is_some_and(|v| v.starts_with('='));
+    if !query.split('&').any(is_token) {
+        return Cow::Borrowed(target);
+    }
+    let query: Vec<String> = query
+        .split('&')
+        .map(|pair| {
+            if is_token(pair) {
+                format!("{PARAM}=***")
+            } else {
+                pair.to_owned()
+            }
+        })
+        .collect();
+    Cow::Owned(format!("{path}?{}", query.join("&")))
+}
+
+/// 128 random bits as lowercase hex, from `/dev/urandom` where there is one.
+fn random_token() -> String {
+    let mut bytes = [0u8; 16];
+    let read = File::open("/dev/urandom").and_then(|mut f| f.read_exact(&mut bytes));
+    if read.is_err() {
+        // Each RandomState is seeded from the operating system's generator.
+        use std::collections::hash_map::RandomState;
+        use std::hash::{BuildHasher, Hasher};
+        for (i, half) in bytes.chunks_mut(8).enumerate() {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_usize(i);
+            half.copy_from_slice(&hasher.finish().to_le_bytes());
+        }
+    }
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_combine_units() {
+        assert_eq!(parse_duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+        for bad in ["", "30", "0m", "m", "1w", "1.5h", "-1h"] {
+            assert!(parse_duration(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn the_token_is_accepted_from_the_query_or_the_cookie() {
+        let share = Share::new(Duration::from_secs(60));
+        assert_eq!(share.token.len(), 32);
+        assert_ne!(share.token, Share::new(Duration::from_secs(60)).token);
+
+        let link = share.link("http://127.0.0.1:3333/guide.md");
+        let query = link.split_once('?').unwrap().1;
+        assert!(share.in_query(query));
+        assert!(share.in_query(&format!("raw=1&{query}")));
+        assert!(!share.in_query("share=0123"));
+        assert!(!share.in_query("raw=1"));
+
+        let cookie = share.cookie();
+        let pair = cookie.split(';').next().unwrap();
+        assert!(share.in_cookie(&format!("theme=dark; {pair}")));
+        assert!(!share.in_cookie("mdmd_share=nope"));
+        assert!(cookie.ends_with("; Max-Age=59") || cookie.ends_with("; Max-Age=60"));
+    }
+
+    #[test]
+    fn redact_masks_only_the_token() {
+        assert_eq!(
+            redact("/guide.md?raw=1&share=0123abcd"),
+            "/guide.md?raw=1&share=***"
+        );
+        assert_eq!(redact("/guide.md?shared=1"), "/guide.md?shared=1");
+        assert_eq!(redact("/guide.md"), "/guide.md");
+    }
+}
@@ -0,0 +1,495 @@
+//! `{{< name args >}}` shortcodes, defined by Lua scripts.
+//!
+//! A script registers handlers with `shortcode(name, handler)`.  A handler
+//! gets the shortcode's arguments, positional ones by index and `key=value`
+//! ones by key, and a context table with `output` (`"html"` or `"text"`)
+//! and `file` (the document's file name).  It returns a string used for
+//! both outputs, or a table with `html` and `text` fields:
+//!
+//! ```lua
+//! shortcode("youtube", function(args, ctx)
+//!   local id = mdmd.escape_html(args[1] or "")
+//!   return {
+//!     html = '<iframe src="https://www.youtube-nocookie.com/embed/' .. id .. '"></iframe>',
+//!     text = "▶ YouTube video " .. id,
+//!   }
+//! end)
+//! ```
+//!
+//! Scripts run with only Lua's `string`, `table`, `math`, and `utf8`
+//! libraries, plus `mdmd.escape_html`: they cannot read files, run
+//! commands, or print.  Each document gets a fresh runtime limited to
+//! [`MEMORY_LIMIT`] bytes and [`TIME_LIMIT`] of running time.
+//!
+//! Shortcodes inside fenced code blocks, escaped ones (`\{{< … >}}`), and
+//! ones with no handler are left as written.  A handler that fails is
+//! replaced by a message saying why, so the rest of the page still renders.
+//!
+//! ```
+//! use mdmd::shortcode::{Output, Shortcodes};
+//! let shortcodes = Shortcodes::from_source(
+//!     "shout.lua",
+//!     r#"shortcode("shout", function(args) return string.upper(args[1]) .. "!" end)"#,
+//! )
+//! .unwrap();
+//! let out = shortcodes.expand("Say {{< shout hi >}}\n", "doc.md".as_ref(), Output::Text);
+//! assert_eq!(out.text, "Say HI!\n");
+//! ```
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use mlua::{Function, HookTriggers, Lua, LuaOptions, StdLib, Table, Value};
+
+use crate::html::html_escape;
+use crate::include::{closes_fence, opens_fence};
+
+/// Memory a document's shortcode runtime may allocate.
+pub const MEMORY_LIMIT: usize = 16 << 20;
+
+/// Running time a document's shortcode handlers may use in total.
+pub const TIME_LIMIT: Duration = Duration::from_secs(1);
+
+/// Registry key of the table of handlers.
+const HANDLERS: &str = "mdmd.shortcodes";
+
+/// Brackets of the placeholder left in the markdown for an HTML fragment;
+/// private-use characters, which markdown passes through untouched.
+const OPEN: char = '\u{E000}';
+const CLOSE: char = '\u{E001}';
+
+/// Which output a shortcode is expanded for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// Rendered pages: the handler's `html`.
+    Html,
+    /// The TUI: the handler's `text`, as markdown.
+    Text,
+}
+
+impl Output {
+    fn as_str(self) -> &'static str {
+        match self {
+            Output::Html => "html",
+            Output::Text => "text",
+        }
+    }
+}
+
+/// The shortcode scripts of a render.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Shortcodes {
+    scripts: Vec<Script>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Script {
+    name: String,
+    source: String,
+}
+
+/// A document with its shortcodes expanded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expanded<'a> {
+    /// The expanded markdown.  For [`Output::Html`], HTML fragments stand
+    /// in as placeholders until [`Expanded::restore`].
+    pub text: Cow<'a, str>,
+    fragments: Vec<String>,
+}
+
+impl Expanded<'_> {
+    /// `html`, rendered from [`Expanded::text`], with the placeholders
+    /// replaced by their fragments.  A placeholder that is a paragraph of
+    /// its own loses the paragraph around it.
+    pub fn restore(&self, html: String) -> String {
+        let mut html = html;
+        for (i, fragment) in self.fragments.iter().enumerate() {
+            let placeholder = placeholder(i);
+            html = html
+                .replace(&format!("<p>{placeholder}</p>"), fragment)
+                .replace(&placeholder, fragment);
+        }
+        html
+    }
+}
+
+fn placeholder(index: usize) -> String {
+    format!("{OPEN}{index}{CLOSE}")
+}
+
+impl Shortcodes {
+    /// The scripts at `path`: a `.lua` file, or a directory whose `.lua`
+    /// files are loaded in name order.  Each is run once to check it.
+    pub fn load(path: &Path) -> Result<Shortcodes, String> {
+        let files = if path.is_dir() {
+            let mut files: Vec<_> = fs::read_dir(path)
+                .map_err(|e| format!("cannot read {}: {e}", path.display()))?
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|p| p.extension().is_some_and(|e| e == "lua"))
+                .collect();
+            files.sort();
+            files
+        } else {
+            vec![path.to_path_buf()]
+        };
+        let mut scripts = Vec::new();
+        for file in files {
+            let source = fs::read_to_string(&file)
+                .map_err(|e| format!("cannot read {}: {e}", file.display()))?;
+            scripts.push(Script {
+                name: file.display().to_string(),
+                source,
+            });
+        }
+        let shortcodes = Shortcodes { scripts };
+        shortcodes.runtime().map_err(|e| first_line(&e))?;
+        Ok(shortcodes)
+    }
+
+    /// A single script, named `name` in error messages.
+    pub fn from_source(name: &str, source: &str) -> Result<Shortcodes, String> {
+        let shortcodes = Shortcodes {
+            scripts: vec![Script {
+                name: name.to_owned(),
+                source: source.to_owned(),
+            }],
+        };
+        shortcodes.runtime().map_err(|e| first_line(&e))?;
+        Ok(shortcodes)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    /// Expand the shortcodes in `text`, the markdown of `file`.  Replacements
+    /// never span lines, so line numbers are kept.
+    pub fn expand<'a>(&self, text: &'a str, file: &Path, output: Output) -> Expanded<'a> {
+        let unchanged = || Expanded {
+            text: Cow::Borrowed(text),
+            fragments: Vec::new(),
+        };
+        if self.is_empty() || !text.contains("{{<") {
+            return unchanged();
+        }
+        let file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut runtime: Option<Result<Lua, String>> = None;
+        let mut fragments = Vec::new();
+        let mut out = String::with_capacity(text.len());
+        let mut changed = false;
+        let mut fence: Option<(u8, usize)> = None;
+
+        for line in text.split_inclusive('\n') {
+            match fence {
+                Some((marker, len)) => {
+                    if closes_fence(line, marker, len) {
+                        fence = None;
+                    }
+                    out.push_str(line);
+                    continue;
+                }
+                None => fence = opens_fence(line),
+            }
+            if fence.is_some() {
+                out.push_str(line);
+                continue;
+            }
+            let mut rest = line;
+            while let Some(open) = rest.find("{{<") {
+                let Some(len) = rest[open..].find(">}}").map(|close| close + 3) else {
+                    break;
+                };
+                let call = &rest[open..open + len];
+                let escaped = rest[..open].ends_with('\\');
+                let parsed = (!escaped).then(|| parse_call(&call[3..len - 3])).flatten();
+                out.push_str(&rest[..open]);
+                rest = &rest[open + len..];
+                let Some((name, args)) = parsed else {
+                    out.push_str(call);
+                    continue;
+                };
+                let lua = runtime.get_or_insert_with(|| self.runtime().map_err(|e| first_line(&e)));
+                let result = match lua {
+                    Ok(lua) => call_handler(lua, name, &args, output, &file_name)
+                        .map_err(|e| first_line(&e)),
+                    Err(e) => Err(e.clone()),
+                };
+                match result {
+                    Ok(None) => out.push_str(call),
+                    Ok(Some(replacement)) => {
+                        changed = true;
+                        match output {
+                            Output::Html => {
+                                out.push_str(&placeholder(fragments.len()));
+                                fragments.push(replacement);
+                            }
+                            Output::Text => out.push_str(&single_line(&replacement)),
+                        }
+                    }
+                    Err(reason) => {
+                        changed = true;
+                        out.push_str(&format!(
+                            "**Shortcode failed:** `{name}`: {}",
+                            single_line(&reason)
+                        ));
+                    }
+                }
+            }
+            out.push_str(rest);
+        }
+        if !changed {
+            return unchanged();
+        }
+        Expanded {
+            text: Cow::Owned(out),
+            fragments,
+        }
+    }
+
+    /// A sandboxed runtime with every script run and its handlers
+    /// registered.
+    fn runtime(&self) -> mlua::Result<Lua> {
+        let libs = StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8;
+        let lua = Lua::new_with(libs, LuaOptions::default())?;
+        lua.set_memory_limit(MEMORY_LIMIT)?;
+        let deadline = Instant::now() + TIME_LIMIT;
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(1000),
+            move |_, _| {
+                if Instant::now() > deadline {
+                    return Err(mlua::Error::RuntimeError(format!(
+                        "ran longer than {} ms",
+                        TIME_LIMIT.as_millis()
+                    )));
+                }
+                Ok(())
+            },
+        );
+
+        let globals = lua.globals();
+        // The base library's file and console access.
+        for name in ["dofile", "loadfile", "load", "print", "collectgarbage"] {
+            globals.set(name, Value::Nil)?;
+        }
+        lua.set_named_registry_value(HANDLERS, lua.create_table()?)?;
+        let register = lua.create_function(|lua, (name, handler): (String, Function)| {
+            lua.named_registry_value::<Table>(HANDLERS)?
+                .set(name, handler)
+        })?;
+        globals.set("shortcode", register)?;
+        let mdmd = lua.create_table()?;
+        mdmd.set(
+            "escape_html",
+            lua.create_function(|_, text: String| Ok(html_escape(&text)))?,
+        )?;
+        globals.set("mdmd", mdmd)?;
+        drop(globals);
+
+        for script in &self.scripts {
+            lua.load(script.source.as_str())
+                .set_name(script.name.as_str())
+                .exec()?;
+        }
+        Ok(lua)
+    }
+}
+
+/// Run the handler for `name`, if there is one.  `Ok(None)` means the
+/// shortcode stays as written.
+fn call_handler(
+    lua: &Lua,
+    name: &str,
+    args: &Args<'_>,
+    output: Output,
+    file_name: &str,
+) -> mlua::Result<Option<String>> {
+    let handlers: Table = lua.named_registry_value(HANDLERS)?;
+    let Some(handler) = handlers.get::<_, Option<Function>>(name)? else {
+        return Ok(None);
+    };
+    let arg_table = lua.create_table()?;
+    let mut index = 1;
+    for (key, value) in args {
+        match key {
+            Some(key) => arg_table.set(*key, value.as_str())?,
+            None => {
+                arg_table.set(index, value.as_str())?;
+                index += 1;
+            }
+        }
+    }
+    let ctx = lua.create_table()?;
+    ctx.set("output", output.as_str())?;
+    ctx.set("file", file_name)?;
+    match handler.call::<_, Value>((arg_table, ctx))? {
+        Value::Nil => Ok(Some(String::new())),
+        Value::Table(result) => Ok(result.get::<_, Option<String>>(output.as_str())?),
+        other => Ok(Some(
+            lua.coerce_string(other)?
+                .map_or_else(String::new, |s| s.to_string_lossy().into_owned()),
+        )),
+    }
+}
+
+/// A shortcode's arguments in order, with the key of each named one.
+type Args<'a> = Vec<(Option<&'a str>, String)>;
+
+/// Name and arguments of a shortcode's inner text, `youtube id start=10`,
+/// if it is one.  Values may be double-quoted to hold spaces.
+fn parse_call(inner: &str) -> Option<(&str, Args<'_>)> {
+    let inner = inner.trim();
+    let name_len = inner
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-')))
+        .unwrap_or(inner.len());
+    let (name, mut rest) = inner.split_at(name_len);
+    if name.is_empty() || !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let mut args = Vec::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some((name, args));
+        }
+        let key = rest.split_once('=').map(|(key, _)| key).filter(|key| {
+            !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-'))
+        });
+        if let Some(key) = key {
+            rest = &rest[key.len() + 1..];
+        }
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let close = quoted.find('"')?;
+            rest = &quoted[close + 1..];
+            &quoted[..close]
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let value = &rest[..end];
+            rest = &rest[end..];
+            value
+        };
+        args.push((key, value.to_owned()));
+    }
+}
+
+/// `text` with line breaks turned into spaces.
+fn single_line(text: &str) -> String {
+    text.trim_end().replace("\r\n", " ").replace('\n', " ")
+}
+
+/// First line of an error, without Lua's stack traceback.
+fn first_line(error: &impl ToString) -> String {
+    error
+        .to_string()
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCRIPT: &str = r#"
+shortcode("youtube", function(args, ctx)
+  local id = mdmd.escape_html(args[1] or "")
+  return {
+    html = '<iframe src="https://www.youtube.com/embed/' .. id .. '"></iframe>',
+    text = "▶ video " .. id .. " (" .. ctx.output .. ", " .. ctx.file .. ")",
+  }
+end)
+shortcode("kv", function(args) return args.title .. "/" .. args[1] .. "/" .. args[2] end)
+shortcode("broken", function() error("no luck") end)
+shortcode("spin", function() while true do end end)
+"#;
+
+    fn shortcodes() -> Shortcodes {
+        Shortcodes::from_source("test.lua", SCRIPT).unwrap()
+    }
+
+    #[test]
+    fn parses_calls() {
+        assert_eq!(
+            parse_call(r#" kv a title="Two words" b "#),
+            Some((
+                "kv",
+                vec![
+                    (None, "a".to_owned()),
+                    (Some("title"), "Two words".to_owned()),
+                    (None, "b".to_owned()),
+                ]
+            ))
+        );
+        assert_eq!(parse_call(" "), None);
+        assert_eq!(parse_call("a.b"), None);
+        assert_eq!(parse_call(r#"kv "open"#), None);
+    }
+
+    #[test]
+    fn expands_text_and_leaves_the_rest() {
+        let source = "A {{< kv x title=\"T\" y >}} {{< nope >}} \\{{< kv >}}\n\
+```\n{{< youtube a >}}\n```\n{{< youtube a<b >}}\n";
+        let out = shortcodes().expand(source, Path::new("dir/doc.md"), Output::Text);
+        assert_eq!(
+            out.text,
+            "A T/x/y {{< nope >}} \\{{< kv >}}\n```\n{{< youtube a >}}\n```\n\
+▶ video a&lt;b (text, doc.md)\n"
+        );
+    }
+
+    #[test]
+    fn html_fragments_replace_their_paragraph() {
+        let out = shortcodes().expand(
+            "{{< youtube x1 >}}\n\nSee {{< youtube x2 >}}.\n",
+            Path::new("doc.md"),
+            Output::Html,
+        );
+        let html = out.restore(crate::render_to_html(&out.text));
+        assert_eq!(
+            html,
+            "<iframe src=\"https://www.youtube.com/embed/x1\"></iframe>\n\
+<p>See <iframe src=\"https://www.youtube.com/embed/x2\"></iframe>.</p>\n"
+        );
+    }
+
+    #[test]
+    fn failures_are_reported_in_place() {
+        let out = shortcodes().expand(
+            "{{< broken >}}\n{{< spin >}}\n",
+            Path::new("doc.md"),
+            Output::Text,
+        );
+        let lines: Vec<&str> = out.text.lines().collect();
+        assert!(
+            lines[0].starts_with("**Shortcode failed:** `broken`: "),
+            "{}",
+            lines[0]
+        );
+        assert!(lines[0].contains("no luck"));
+        assert!(lines[1].contains("ran longer than"), "{}", lines[1]);
+    }
+
+    #[test]
+    fn scripts_cannot_reach_files_or_processes() {
+        for source in [
+            "io.open('/etc/passwd')",
+            "os.execute('true')",
+            "dofile('/etc/passwd')",
+            "require('os')",
+            "print('hi')",
+        ] {
+            assert!(
+                Shortcodes::from_source("bad.lua", source).is_err(),
+                "{source}"
+            );
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! The start page `mdmd` shows when run with no FILE inside a project.
+//!
+//! The page is a markdown document generated on the spot and opened in the
+//! viewer like any other, so its links are followed with Tab and Enter and
+//! Backspace comes back to it.  It lists the project's READMEs (the root's
+//! first), the project files most recently opened in the viewer, and the
+//! saved sessions (`mdmd view --session <name>`) last left in the project,
+//! which serve as its bookmarks.
+//!
+//! Recent files are kept across runs in `recent` in mdmd's state directory,
+//! one `visit <secs> <path>` line per file, newest last, as in session files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::history::Visit;
+use crate::i18n::Locale;
+use crate::workspace::{self, Workspace};
+
+/// Files remembered in the recent list.
+const MAX_RECENT: usize = 100;
+
+/// Files shown under each heading of the page.
+const SHOWN: usize = 10;
+
+/// The project the working directory `cwd` is in: the nearest ancestor
+/// containing `.git`, else the nearest containing a `README.md`.  `None`
+/// outside any project.
+pub fn project_root(cwd: &Path) -> Option<PathBuf> {
+    let mut dirs = cwd.ancestors();
+    dirs.clone()
+        .find(|d| d.join(".git").exists())
+        .or_else(|| dirs.find(|d| d.join("README.md").is_file()))
+        .map(Path::to_path_buf)
+}
+
+/// Where the recent list is kept.  `None` when neither `XDG_STATE_HOME`
+/// nor `HOME` is set.
+pub fn recent_path() -> Option<PathBuf> {
+    Some(workspace::state_dir()?.join("recent"))
+}
+
+/// Read the recent list at `path`, oldest first.  A missing or unreadable
+/// file is an empty list, and malformed lines are skipped.
+pub fn load_recent(path: &Path) -> Vec<Visit> {
+    let Ok(text) = fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    text.lines()
+        .filter_map(|line| {
+            let (secs, path) = line.strip_prefix("visit ")?.split_once(' ')?;
+            Some(Visit {
+                file_path: PathBuf::from(path),
+                visited_at: SystemTime::UNIX_EPOCH + Duration::from_secs(secs.parse().ok()?),
+            })
+        })
+        .collect()
+}
+
+/// Add `visits` to the recent list at `path`.  Each file keeps only its
+/// latest visit, and files that no longer exist are dropped.
+pub fn record_recent(path: &Path, visits: &[Visit]) -> std::io::Result<()> {
+    let mut recent = load_recent(path);
+    recent.extend(visits.iter().cloned());
+    recent.sort_by_key(|v| v.visited_at);
+    let mut seen = std::collections::HashSet::new();
+    let mut kept: Vec<&Visit> = recent
+        .iter()
+        .rev()
+        .filter(|v| v.file_path.is_file() && seen.insert(&v.file_path))
+        .take(MAX_RECENT)
+        .collect();
+    kept.reverse();
+    let mut out = String::new();
+    for v in kept {
+        let secs = v
+            .visited_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        out.push_str(&format!("visit {secs} {}\n", v.file_path.display()));
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    crate::replace::write_atomic(path, &out)
+}
+
+/// The saved sessions, by name, whose current document is under `root`.
+pub fn sessions_in(root: &Path) -> Vec<(String, Workspace)> {
+    let Some(dir) = workspace::state_dir().map(|d| d.join("sessions")) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut sessions: Vec<(String, Workspace)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.to_owned();
+            workspace::validate_name(&name).ok()?;
+            let ws = workspace::load(&entry.path()).ok()??;
+            ws.current.file_path.starts_with(root).then_some((name, ws))
+        })
+        .collect();
+    sessions.sort_by(|(a, _), (b, _)| crate::queue::natural_cmp(a, b));
+    sessions
+}
+
+/// The start page for the project at `root` as markdown, with links
+/// relative to `root`.  `readmes` are the project's markdown files named
+/// `README.md` or `index.md`, `recent` the recent list oldest first.
+pub fn page(
+    root: &Path,
+    readmes: &[PathBuf],
+    recent: &[Visit],
+    sessions: &[(String, Workspace)],
+    now: SystemTime,
+    locale: Locale,
+) -> String {
+    let rel = |path: &Path| {
+        path.strip_prefix(root)
+            .ok()
+            .map(|rel| rel.to_string_lossy().replace('\\', "/"))
+    };
+    let link = |rel: &str| format!("[{rel}](<{rel}>)");
+    let name = root.file_name().map_or_else(
+        || root.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    );
+    let mut out = format!("# {name}\n\n{}\n", root.display());
+
+    // The root's README first, then by depth and path.
+    let mut readmes: Vec<String> = readmes.iter().filter_map(|p| rel(p)).collect();
+    readmes.sort_by_key(|r| (r.matches('/').count(), r.clone()));
+    out.push_str(&format!("\n## {}\n\n", locale.tr("READMEs")));
+    if readmes.is_empty() {
+        out.push_str(&format!(
+            "{}\n",
+            locale.tr("No README files in this project.")
+        ));
+    }
+    for r in readmes.iter().take(SHOWN) {
+        out.push_str(&format!("- {}\n", link(r)));
+    }
+    if readmes.len() > SHOWN {
+        let more = readmes.len() - SHOWN;
+        out.push_str(&format!(
+            "- {}\n",
+            locale.trf("and {n} more", &[("n", &more)])
+        ));
+    }
+
+    out.push_str(&format!("\n## {}\n\n", locale.tr("Recent files")));
+    let recent: Vec<(String, &Visit)> = recent
+        .iter()
+        .rev()
+        .filter_map(|v| Some((rel(&v.file_path)?, v)))
+        .take(SHOWN)
+        .collect();
+    if recent.is_empty() {
+        out.push_str(&format!("{}\n", locale.tr("No files opened here yet.")));
+    }
+    for (r, v) in &recent {
+        let age = now
+            .duration_since(v.visited_at)
+            .map(|elapsed| crate::history::format_age(elapsed, locale))
+            .unwrap_or_else(|_| locale.tr("just now").to_owned());
+        out.push_str(&format!("- {} ({age})\n", link(r)));
+    }
+
+    out.push_str(&format!("\n## {}\n\n", locale.tr("Sessions")));
+    if sessions.is_empty() {
+        out.push_str(&format!(
+            "{}\n",
+            locale.tr("No saved sessions here; `mdmd view --session NAME FILE` starts one.")
+        ));
+    }
+    for (name, ws) in sessions {
+        if let Some(r) = rel(&ws.current.file_path) {
+            out.push_str(&format!(
+                "- **{name}**: {} (`mdmd view --session {name}`)\n",
+                link(&r)
+            ));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn recent_files_keep_their_latest_visit() {
+        let dir = tempfile::tempdir().unwrap();
+        let (a, b) = (dir.path().join("a.md"), dir.path().join("b.md"));
+        fs::write(&a, "# a\n").unwrap();
+        fs::write(&b, "# b\n").unwrap();
+        let visit = |path: &Path, secs| Visit {
+            file_path: path.to_path_buf(),
+            visited_at: at(secs),
+        };
+        let list = dir.path().join("state/recent");
+        record_recent(&list, &[visit(&a, 10), visit(&b, 20)]).unwrap();
+        let gone = dir.path().join("gone.md");
+        record_recent(&list, &[visit(&a, 30), visit(&gone, 40)]).unwrap();
+        assert_eq!(load_recent(&list), [visit(&b, 20), visit(&a, 30)]);
+    }
+
+    #[test]
+    fn page_links_readmes_and_recent_files_from_the_root() {
+        let root = Path::new("/work/proj");
+        let readmes = [root.join("docs/README.md"), root.join("README.md")];
+        let recent = [
+            Visit {
+                file_path: PathBuf::from("/elsewhere/notes.md"),
+                visited_at: at(100),
+            },
+            Visit {
+                file_path: root.join("docs/my guide.md"),
+                visited_at: at(3700),
+            },
+        ];
+        let mut ws = Workspace::new(root.join("docs/README.md"));
+        ws.current.scroll_offset = 12;
+        let sessions = [("reading".to_owned(), ws)];
+        let md = page(root, &readmes, &recent, &sessions, at(7300), Locale::En);
+        assert!(md.starts_with("# proj\n"), "{md}");
+        let root_readme = md.find("- [README.md](<README.md>)").unwrap();
+        assert!(root_readme < md.find("- [docs/README.md]").unwrap(), "{md}");
+        assert!(md.contains("- [docs/my guide.md](<docs/my guide.md>) (1h ago)"));
+        assert!(!md.contains("notes.md"), "{md}");
+        assert!(md.contains(
+            "- **reading**: [docs/README.md](<docs/README.md>) (`mdmd view --session reading`)"
+        ));
+    }
+}
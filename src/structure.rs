@@ -0,0 +1,340 @@
+//! Document structure as JSON for `GET /_mdmd/v1/doc`, the heading outline
+//! printed by `mdmd outline`, and the mapping from source lines to headings
+//! behind `POST /_mdmd/v1/scroll`.
+//!
+//! Combines the TUI parser (links, code blocks, prose), the HTML renderer's
+//! heading anchors, and the frontmatter extractor into one description of a
+//! markdown file that editor plugins and other tools can consume without
+//! re-parsing.  Line numbers are 1-based and refer to the original file,
+//! frontmatter included.
+
+use serde_json::{json, Map, Value};
+
+use crate::frontmatter::{self, FrontmatterField, MetaValue};
+use crate::html::{heading_anchors_with, HeadingEntry, SlugStyle};
+use crate::parse::{self, BlockKind, LinkKind};
+
+/// Build the JSON description of `source`, served from `url_path`, with
+/// heading anchors in `style`.
+pub fn document_json(source: &str, url_path: &str, style: SlugStyle) -> Value {
+    let extracted = frontmatter::extract(source);
+    let body = extracted.render_body.as_ref();
+    // Frontmatter lines stripped from the body, so body-relative line numbers
+    // can be mapped back onto the file.
+    let line_offset = source[..source.len() - extracted.body.len()]
+        .matches('\n')
+        .count();
+
+    let doc = parse::parse(body);
+    let headings = file_headings(source, style);
+
+    let links: Vec<Value> = doc
+        .links
+        .iter()
+        .map(|l| {
+            json!({
+                "text": l.text,
+                "url": l.url,
+                "line": l.line + line_offset,
+                "kind": link_kind_name(&l.kind),
+            })
+        })
+        .collect();
+
+    let code_blocks: Vec<Value> = doc
+        .code_blocks
+        .iter()
+        .map(|c| {
+            json!({
+                "language": c.language,
+                "line_start": c.line_start + line_offset,
+                "line_end": c.line_end + line_offset,
+                "code": c.code,
+            })
+        })
+        .collect();
+
+    let word_count: usize = doc
+        .blocks
+        .iter()
+        .filter(|b| !matches!(b.kind, BlockKind::CodeBlock(_) | BlockKind::HtmlBlock))
+        .map(|b| b.content.split_whitespace().count())
+        .sum();
+
+    let title = crate::summary::title(
+        extracted.meta.as_ref(),
+        headings
+            .iter()
+            .find(|(h, _)| h.level == 1)
+            .map(|(h, _)| h.text.as_str()),
+    );
+
+    json!({
+        "path": url_path,
+        "title": title,
+        "word_count": word_count,
+        "frontmatter": extracted.meta.as_ref().map(|m| fields_json(&m.fields)),
+        "headings": outline_tree(&headings),
+        "links": links,
+        "code_blocks": code_blocks,
+    })
+}
+
+/// Headings of `source` with their anchors in `style` and 1-based file
+/// lines.
+fn file_headings(source: &str, style: SlugStyle) -> Vec<(HeadingEntry, usize)> {
+    let extracted = frontmatter::extract(source);
+    let line_offset = source[..source.len() - extracted.body.len()]
+        .matches('\n')
+        .count();
+    heading_anchors_with(extracted.render_body.as_ref(), style)
+        .into_iter()
+        .map(|(h, line)| (h, line + line_offset))
+        .collect()
+}
+
+/// The heading tree of `source` as JSON: an array of `{level, text, anchor,
+/// line, children}` objects, as in `GET /_mdmd/v1/doc`.
+pub fn outline_json(source: &str, style: SlugStyle) -> Value {
+    outline_tree(&file_headings(source, style))
+}
+
+fn outline_tree(headings: &[(HeadingEntry, usize)]) -> Value {
+    let flat: Vec<(u8, Value)> = headings
+        .iter()
+        .map(|(h, line)| {
+            (
+                h.level,
+                json!({
+                    "level": h.level,
+                    "text": h.text,
+                    "anchor": h.anchor_id,
+                    "line": line,
+                    "children": [],
+                }),
+            )
+        })
+        .collect();
+    Value::Array(heading_tree(flat))
+}
+
+/// The heading tree of `source` as a markdown list of links to the
+/// headings' anchors, nested the way [`outline_json`] nests them, each
+/// followed by its file line in a comment that renders as nothing:
+///
+/// ```text
+/// - [Install](#install) <!-- line 3 -->
+///   - [From source](#from-source) <!-- line 9 -->
+/// ```
+pub fn outline_markdown(source: &str, style: SlugStyle) -> String {
+    let mut out = String::new();
+    // Levels of the headings enclosing the current one.
+    let mut open: Vec<u8> = Vec::new();
+    for (h, line) in file_headings(source, style) {
+        while open.last().is_some_and(|l| *l >= h.level) {
+            open.pop();
+        }
+        let text = h.text.replace('[', "\\[").replace(']', "\\]");
+        out.push_str(&format!(
+            "{}- [{text}](#{}) <!-- line {line} -->\n",
+            "  ".repeat(open.len()),
+            h.anchor_id
+        ));
+        open.push(h.level);
+    }
+    out
+}
+
+/// Where 1-based file `line` of `source` falls among its headings, for
+/// `POST /_mdmd/v1/scroll`: the anchor of the heading at or above it
+/// (`anchor`, `null` before the first heading), the anchor of the next one
+/// (`next`, `null` after the last), and how far `line` lies between the two
+/// (`fraction`: 0 at `anchor`'s line, 1 at `next`'s or the end of the file).
+/// A page scrolls to the same fraction of the distance between the two
+/// rendered headings.
+pub fn scroll_target_json(source: &str, line: usize, style: SlugStyle) -> Value {
+    let headings: Vec<(String, usize)> = file_headings(source, style)
+        .into_iter()
+        .map(|(h, l)| (h.anchor_id, l))
+        .collect();
+
+    let above = headings.iter().rposition(|(_, l)| *l <= line);
+    let next = headings.get(above.map_or(0, |i| i + 1));
+    let start = above.map_or(1, |i| headings[i].1);
+    let end = next.map_or(source.lines().count() + 1, |(_, l)| *l);
+    let fraction = if end > start {
+        (line.saturating_sub(start) as f64 / (end - start) as f64).min(1.0)
+    } else {
+        0.0
+    };
+    json!({
+        "line": line,
+        "anchor": above.map(|i| &headings[i].0),
+        "next": next.map(|(anchor, _)| anchor),
+        "fraction": fraction,
+    })
+}
+
+/// Nest flat `(level, heading)` pairs so each heading's `children` holds the
+/// deeper headings that follow it.  Skipped levels (an H3 directly under an
+/// H1) nest under the nearest shallower heading.
+fn heading_tree(flat: Vec<(u8, Value)>) -> Vec<Value> {
+    let mut roots: Vec<Value> = Vec::new();
+    // Open headings, shallowest first; each is moved into its parent (or the
+    // root list) once a heading at the same or a shallower level appears.
+    let mut stack: Vec<(u8, Value)> = Vec::new();
+
+    let close = |stack: &mut Vec<(u8, Value)>, roots: &mut Vec<Value>| {
+        let Some((_, node)) = stack.pop() else {
+            return;
+        };
+        match stack.last_mut() {
+            Some((_, parent)) => parent["children"]
+                .as_array_mut()
+                .expect("heading children is an array")
+                .push(node),
+            None => roots.push(node),
+        }
+    };
+
+    for (level, node) in flat {
+        while stack.last().is_some_and(|(l, _)| *l >= level) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push((level, node));
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+fn link_kind_name(kind: &LinkKind) -> &'static str {
+    match kind {
+        LinkKind::Inline => "inline",
+        LinkKind::Reference => "reference",
+        LinkKind::Autolink => "autolink",
+        LinkKind::Email => "email",
+        LinkKind::Collapsed => "collapsed",
+        LinkKind::Shortcut => "shortcut",
+        LinkKind::Image => "image",
+    }
+}
+
+fn fields_json(fields: &[FrontmatterField]) -> Value {
+    let map: Map<String, Value> = fields
+        .iter()
+        .map(|f| (f.key.clone(), meta_value_json(&f.value)))
+        .collect();
+    Value::Object(map)
+}
+
+fn meta_value_json(value: &MetaValue) -> Value {
+    match value {
+        MetaValue::Scalar(s) => Value::String(s.clone()),
+        MetaValue::Null => Value::Null,
+        MetaValue::Sequence(items) => Value::Array(items.iter().map(meta_value_json).collect()),
+        MetaValue::Mapping(fields) => fields_json(fields),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headings_nest_with_anchors_and_file_lines() {
+        let src = "---\ntitle: Doc\n---\n# Top\n\n## A\n\n### A1\n\n## A\n\n# Second\n";
+        let json = document_json(src, "/doc.md", SlugStyle::default());
+
+        assert_eq!(json["path"], "/doc.md");
+        assert_eq!(json["title"], "Doc");
+        let headings = json["headings"].as_array().unwrap();
+        assert_eq!(headings.len(), 2);
+        assert_eq!(headings[0]["anchor"], "top");
+        assert_eq!(headings[0]["line"], 4);
+        let children = headings[0]["children"].as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        assert_eq!(children[0]["children"][0]["text"], "A1");
+        assert_eq!(children[1]["anchor"], "a-1");
+        assert_eq!(headings[1]["text"], "Second");
+        assert_eq!(headings[1]["children"], json!([]));
+    }
+
+    #[test]
+    fn scroll_target_interpolates_between_headings() {
+        let src = "---\ntitle: Doc\n---\nIntro\n\n# One\n\na\nb\n\n# Two\n\nend\n";
+        // "# One" is line 6 and "# Two" line 11, so line 8 is 2/5 of the way.
+        let json = scroll_target_json(src, 8, SlugStyle::default());
+        assert_eq!(json["anchor"], "one");
+        assert_eq!(json["next"], "two");
+        assert_eq!(json["fraction"], 0.4);
+
+        let before = scroll_target_json(src, 2, SlugStyle::default());
+        assert_eq!(before["anchor"], Value::Null);
+        assert_eq!(before["next"], "one");
+
+        // After the last heading the end of the file is the far edge.
+        let after = scroll_target_json(src, 13, SlugStyle::default());
+        assert_eq!(after["anchor"], "two");
+        assert_eq!(after["next"], Value::Null);
+        assert_eq!(after["fraction"], 2.0 / 3.0);
+    }
+
+    #[test]
+    fn skipped_levels_nest_under_nearest_shallower_heading() {
+        let json = document_json("# A\n\n### Deep\n\n## B\n", "/x.md", SlugStyle::default());
+        let a = &json["headings"][0];
+        let kids: Vec<_> = a["children"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|h| h["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(kids, vec!["Deep", "B"]);
+    }
+
+    #[test]
+    fn outline_lists_headings_as_nested_links() {
+        let src = "---\ntitle: Doc\n---\n# A [draft]\n\n### Deep\n\n## B\n\n# C\n";
+        assert_eq!(
+            outline_markdown(src, SlugStyle::default()),
+            "- [A \\[draft\\]](#a-draft) <!-- line 4 -->\n  \
+             - [Deep](#deep) <!-- line 6 -->\n  \
+             - [B](#b) <!-- line 8 -->\n\
+             - [C](#c) <!-- line 10 -->\n"
+        );
+        assert_eq!(
+            outline_json(src, SlugStyle::default())[0]["children"][1]["line"],
+            8
+        );
+    }
+
+    #[test]
+    fn links_code_blocks_frontmatter_and_word_count() {
+        let src = "---\ntags: [a, b]\nauthor:\n  name: Ann\n---\n# Title\n\nSee [the guide](guide.md) now.\n\n```rust\nlet not_counted = 1;\n```\n";
+        let json = document_json(src, "/x.md", SlugStyle::default());
+
+        assert_eq!(json["title"], "Title");
+        assert_eq!(json["frontmatter"]["tags"], json!(["a", "b"]));
+        assert_eq!(json["frontmatter"]["author"]["name"], "Ann");
+
+        assert_eq!(
+            json["links"],
+            json!([{"text": "the guide", "url": "guide.md", "line": 8, "kind": "inline"}])
+        );
+        assert_eq!(json["code_blocks"][0]["language"], "rust");
+        assert_eq!(json["code_blocks"][0]["line_start"], 10);
+        assert_eq!(json["code_blocks"][0]["code"], "let not_counted = 1;\n");
+        // "Title" + "See the guide now."
+        assert_eq!(json["word_count"], 5);
+    }
+
+    #[test]
+    fn document_without_frontmatter_or_h1_has_null_title() {
+        let json = document_json("just text\n", "/x.md", SlugStyle::default());
+        assert_eq!(json["title"], Value::Null);
+        assert_eq!(json["frontmatter"], Value::Null);
+        assert_eq!(json["word_count"], 2);
+    }
+}
@@ -0,0 +1,221 @@
+//! Document titles and summaries.
+//!
+//! The rules for what a page is called and what it is about live here, so
+//! page titles, `og:description`, directory indexes, search results, and the
+//! backlinks panel all agree:
+//!
+//! - **Title**: the frontmatter `title`, else the plain text of the first H1.
+//! - **Summary**: the frontmatter `description`, else the plain text of the
+//!   first paragraph, cut to [`SUMMARY_MAX_CHARS`] by [`truncate`].
+//!
+//! ```
+//! let doc = mdmd::summary::extract("# Guide\n\nInstall the *widget* first.\n");
+//! assert_eq!(doc.title.as_deref(), Some("Guide"));
+//! assert_eq!(doc.summary.as_deref(), Some("Install the widget first."));
+//! ```
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+
+use crate::frontmatter::{self, FrontmatterMeta};
+
+/// Longest summary, in characters, including the ellipsis of a cut one.
+/// Chat unfurls and index rows show roughly two lines.
+pub const SUMMARY_MAX_CHARS: usize = 200;
+
+/// What a document is called and what it is about.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DocSummary {
+    pub title: Option<String>,
+    pub summary: Option<String>,
+}
+
+/// Title and summary of a markdown file's full source, frontmatter included.
+pub fn extract(source: &str) -> DocSummary {
+    let extracted = frontmatter::extract(source);
+    let (first_h1, first_paragraph) = first_h1_and_paragraph(extracted.render_body.as_ref());
+    DocSummary {
+        title: title(extracted.meta.as_ref(), first_h1.as_deref()),
+        summary: summary(extracted.meta.as_ref(), first_paragraph.as_deref()),
+    }
+}
+
+/// The title rule, for callers that have already parsed the document: the
+/// frontmatter `title`, else `first_h1`.  Blank titles count as missing.
+pub fn title(frontmatter: Option<&FrontmatterMeta>, first_h1: Option<&str>) -> Option<String> {
+    frontmatter
+        .and_then(|meta| meta.title.as_deref())
+        .filter(|title| !title.trim().is_empty())
+        .or(first_h1)
+        .map(collapse_whitespace)
+        .filter(|title| !title.is_empty())
+}
+
+/// The summary rule, for callers that have already parsed the document: the
+/// frontmatter `description`, else `first_paragraph`, cut by [`truncate`].
+pub fn summary(
+    frontmatter: Option<&FrontmatterMeta>,
+    first_paragraph: Option<&str>,
+) -> Option<String> {
+    frontmatter
+        .and_then(|meta| meta.scalar("description"))
+        .or(first_paragraph)
+        .map(|text| truncate(text, SUMMARY_MAX_CHARS))
+        .filter(|summary| !summary.is_empty())
+}
+
+/// Collapse runs of whitespace in `text` to single spaces and trim the ends.
+pub fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Collapse whitespace in `text` and, when it is longer than `max_chars`
+/// characters, cut it at the last word boundary that leaves room for a
+/// trailing ellipsis.  Punctuation left dangling before the ellipsis is
+/// dropped.
+pub fn truncate(text: &str, max_chars: usize) -> String {
+    let text = collapse_whitespace(text);
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let cut: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    let cut = cut.rsplit_once(' ').map_or(cut.as_str(), |(head, _)| head);
+    format!(
+        "{}\u{2026}",
+        cut.trim_end_matches(|c: char| c.is_ascii_punctuation() || c == ' ')
+    )
+}
+
+/// Like [`truncate`], but keep the end of `text` and cut from the front,
+/// leading with the ellipsis.
+pub fn truncate_start(text: &str, max_chars: usize) -> String {
+    let text = collapse_whitespace(text);
+    let count = text.chars().count();
+    if count <= max_chars {
+        return text;
+    }
+    let cut: String = text
+        .chars()
+        .skip(count - max_chars.saturating_sub(1))
+        .collect();
+    let cut = cut.split_once(' ').map_or(cut.as_str(), |(_, tail)| tail);
+    format!(
+        "\u{2026}{}",
+        cut.trim_start_matches(|c: char| c.is_ascii_punctuation() || c == ' ')
+    )
+}
+
+/// Plain text of a markdown fragment: text and inline code only, with
+/// markup, link destinations, and HTML dropped and whitespace collapsed.
+pub fn plain_text(markdown: &str) -> String {
+    let mut plain = String::new();
+    for event in Parser::new_ext(markdown, options()) {
+        match event {
+            Event::Text(t) | Event::Code(t) => plain.push_str(&t),
+            Event::SoftBreak | Event::HardBreak => plain.push(' '),
+            Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item) => plain.push(' '),
+            _ => {}
+        }
+    }
+    collapse_whitespace(&plain)
+}
+
+fn options() -> Options {
+    Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS
+}
+
+/// Plain text of the first H1 and of the first paragraph (at any depth, as
+/// the first `<p>` of the rendered page) in `body`.
+fn first_h1_and_paragraph(body: &str) -> (Option<String>, Option<String>) {
+    #[derive(PartialEq)]
+    enum In {
+        Nothing,
+        H1,
+        Paragraph,
+    }
+    let mut h1 = None;
+    let mut paragraph = None;
+    let mut inside = In::Nothing;
+    let mut buf = String::new();
+
+    for event in Parser::new_ext(body, options()) {
+        match event {
+            Event::Start(Tag::Heading {
+                level: HeadingLevel::H1,
+                ..
+            }) if h1.is_none() => inside = In::H1,
+            Event::Start(Tag::Paragraph) if paragraph.is_none() && inside == In::Nothing => {
+                inside = In::Paragraph
+            }
+            Event::Text(t) | Event::Code(t) if inside != In::Nothing => buf.push_str(&t),
+            Event::SoftBreak | Event::HardBreak if inside != In::Nothing => buf.push(' '),
+            Event::End(TagEnd::Heading(HeadingLevel::H1)) if inside == In::H1 => {
+                h1 = Some(collapse_whitespace(&std::mem::take(&mut buf)));
+                inside = In::Nothing;
+            }
+            Event::End(TagEnd::Paragraph) if inside == In::Paragraph => {
+                paragraph = Some(collapse_whitespace(&std::mem::take(&mut buf)));
+                inside = In::Nothing;
+            }
+            _ => {}
+        }
+        if h1.is_some() && paragraph.is_some() {
+            break;
+        }
+    }
+    (h1, paragraph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frontmatter_beats_body() {
+        let doc = extract(
+            "---\ntitle: Handbook\ndescription: How we work.\n---\n# Intro\n\nFirst words.\n",
+        );
+        assert_eq!(doc.title.as_deref(), Some("Handbook"));
+        assert_eq!(doc.summary.as_deref(), Some("How we work."));
+
+        let doc = extract("Lead-in with `code` and a [link](x.md).\n\n# **Bold** title\n");
+        assert_eq!(doc.title.as_deref(), Some("Bold title"));
+        assert_eq!(
+            doc.summary.as_deref(),
+            Some("Lead-in with code and a link.")
+        );
+    }
+
+    #[test]
+    fn missing_parts_are_none() {
+        assert_eq!(extract(""), DocSummary::default());
+        let doc = extract("## Only a subheading\n\n- tight\n- list\n");
+        assert_eq!(doc, DocSummary::default());
+        // A blank frontmatter title falls back to the H1.
+        assert_eq!(
+            extract("---\ntitle: \"\"\n---\n# Real\n").title.as_deref(),
+            Some("Real")
+        );
+    }
+
+    #[test]
+    fn truncate_cuts_at_a_word_with_an_ellipsis() {
+        assert_eq!(truncate("  short\n text ", 20), "short text");
+        let cut = truncate(&"word, ".repeat(80), SUMMARY_MAX_CHARS);
+        assert!(cut.chars().count() <= SUMMARY_MAX_CHARS, "{cut}");
+        assert!(cut.ends_with("word\u{2026}"), "{cut}");
+        assert_eq!(truncate("abcdefghij", 5), "abcd\u{2026}");
+        assert_eq!(
+            truncate_start("one two, three four", 12),
+            "\u{2026}three four"
+        );
+        assert_eq!(truncate_start("abcdefghij", 5), "\u{2026}ghij");
+    }
+
+    #[test]
+    fn plain_text_drops_markup() {
+        assert_eq!(
+            plain_text("See **the** [guide](g.md)\nfor `setup`.\n\n# Next"),
+            "See the guide for setup. Next"
+        );
+    }
+}
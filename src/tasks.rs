@@ -0,0 +1,167 @@
+//! Task list checkboxes (`- [ ]` / `- [x]`) toggled in place.
+//!
+//! The source file is rewritten with the one checkbox flipped, using the
+//! same atomic temp-file-and-rename as find-and-replace.  The caller passes
+//! the state it last rendered; if the file on disk disagrees, the toggle is
+//! refused rather than undoing someone else's edit.
+
+use std::path::Path;
+
+use crate::encoding;
+use crate::replace::write_atomic;
+
+/// Flip the checkbox on 1-based `line` of `source`, which must currently be
+/// `expected_checked`.  Returns the new source text.
+pub fn toggle_in_source(
+    source: &str,
+    line: usize,
+    expected_checked: bool,
+) -> Result<String, String> {
+    let start = line
+        .checked_sub(1)
+        .and_then(|skip| {
+            if skip == 0 {
+                // Rendering drops a byte order mark, so line 1 starts after it.
+                Some(if source.starts_with('\u{feff}') {
+                    '\u{feff}'.len_utf8()
+                } else {
+                    0
+                })
+            } else {
+                source.match_indices('\n').nth(skip - 1).map(|(i, _)| i + 1)
+            }
+        })
+        .filter(|&start| start < source.len())
+        .ok_or_else(|| format!("line {line} is past the end of the file"))?;
+    let end = source[start..]
+        .find('\n')
+        .map_or(source.len(), |i| start + i);
+
+    let mark = checkbox_offset(&source[start..end])
+        .map(|i| start + i + 1)
+        .ok_or_else(|| format!("line {line} has no task checkbox"))?;
+    let checked = source.as_bytes()[mark] != b' ';
+    if checked != expected_checked {
+        return Err(format!("the task on line {line} was changed on disk"));
+    }
+
+    let mut out = String::with_capacity(source.len());
+    out.push_str(&source[..mark]);
+    out.push(if checked { ' ' } else { 'x' });
+    out.push_str(&source[mark + 1..]);
+    Ok(out)
+}
+
+/// Flip the checkbox on 1-based `line` of the file at `path`, as in
+/// [`toggle_in_source`], and write the file back.  Returns the new text.
+pub fn toggle_task(path: &Path, line: usize, expected_checked: bool) -> Result<String, String> {
+    let source =
+        encoding::read_utf8(path).map_err(|e| format!("cannot edit {}: {e}", path.display()))?;
+    let updated = toggle_in_source(&source, line, expected_checked)?;
+    write_atomic(path, &updated).map_err(|e| format!("cannot write {}: {e}", path.display()))?;
+    Ok(updated)
+}
+
+/// Byte offset of the `[` of a list item's checkbox in `line`, after any
+/// indentation, block quote markers, and the bullet or number.
+fn checkbox_offset(line: &str) -> Option<usize> {
+    let bytes = line.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() && matches!(bytes[i], b' ' | b'\t' | b'>') {
+        i += 1;
+    }
+    match bytes.get(i)? {
+        b'-' | b'*' | b'+' => i += 1,
+        b'0'..=b'9' => {
+            while bytes.get(i).is_some_and(u8::is_ascii_digit) {
+                i += 1;
+            }
+            if !matches!(bytes.get(i)?, b'.' | b')') {
+                return None;
+            }
+            i += 1;
+        }
+        _ => return None,
+    }
+    if !matches!(bytes.get(i)?, b' ' | b'\t') {
+        return None;
+    }
+    while bytes.get(i).is_some_and(|b| matches!(b, b' ' | b'\t')) {
+        i += 1;
+    }
+    match bytes.get(i..i + 3)? {
+        b"[ ]" | b"[x]" | b"[X]" => Some(i),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn toggles_only_the_requested_line() {
+        let src = "# Todo\n\n- [ ] milk\n  1. [X] eggs\n> * [ ] quoted\r\n";
+        assert_eq!(
+            toggle_in_source(src, 3, false).unwrap(),
+            "# Todo\n\n- [x] milk\n  1. [X] eggs\n> * [ ] quoted\r\n"
+        );
+        assert_eq!(
+            toggle_in_source(src, 4, true).unwrap(),
+            "# Todo\n\n- [ ] milk\n  1. [ ] eggs\n> * [ ] quoted\r\n"
+        );
+        assert_eq!(
+            toggle_in_source(src, 5, false).unwrap(),
+            "# Todo\n\n- [ ] milk\n  1. [X] eggs\n> * [x] quoted\r\n"
+        );
+    }
+
+    #[test]
+    fn refuses_lines_without_a_checkbox_or_with_a_changed_state() {
+        let src = "- plain [ ] text\n- [x] done\n";
+        assert!(toggle_in_source(src, 1, false)
+            .unwrap_err()
+            .contains("no task checkbox"));
+        assert!(toggle_in_source(src, 2, false)
+            .unwrap_err()
+            .contains("changed on disk"));
+        assert!(toggle_in_source(src, 9, false)
+            .unwrap_err()
+            .contains("past the end"));
+        assert!(toggle_in_source(src, 0, false).is_err());
+    }
+
+    #[test]
+    fn toggle_task_rewrites_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.md");
+        fs::write(&path, "- [ ] milk\n").unwrap();
+        assert_eq!(toggle_task(&path, 1, false).unwrap(), "- [x] milk\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "- [x] milk\n");
+        assert!(toggle_task(&path, 1, false).is_err());
+    }
+
+    #[test]
+    fn toggle_task_skips_a_byte_order_mark_on_line_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.md");
+        fs::write(&path, "\u{feff}- [ ] milk\n- [ ] eggs\n").unwrap();
+        toggle_task(&path, 1, false).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "\u{feff}- [x] milk\n- [ ] eggs\n"
+        );
+    }
+
+    #[test]
+    fn toggle_task_refuses_files_that_are_not_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todo.md");
+        fs::write(&path, b"- [ ] caf\xe9\n").unwrap();
+        let err = toggle_task(&path, 1, false).unwrap_err();
+        assert!(err.contains("re-save it as UTF-8"), "{err}");
+        assert_eq!(fs::read(&path).unwrap(), b"- [ ] caf\xe9\n");
+    }
+}
@@ -0,0 +1,283 @@
+//! User page templates (`--template FILE`) replacing the built-in page
+//! shell of `mdmd serve` and `mdmd html`.
+//!
+//! Templates use [minijinja](https://docs.rs/minijinja), a Jinja2 dialect,
+//! with HTML auto-escaping.  A page's variables are the parts of the
+//! built-in shell, so a template can rearrange them without rebuilding
+//! them:
+//!
+//! - `title`, `site_title`, `lang`, `path`: plain text; `path` is the page's
+//!   path under the serve root and empty in `html` exports.
+//! - `head`: the stylesheet, theme scripts, favicon, social and `--inject-head`
+//!   tags, for inside `<head>` after the template's own `<meta charset>` and
+//!   `<title>`.
+//! - `scripts`: mdmd's page script (live reload, search, theme toggles) and
+//!   Mermaid, for the end of `<body>`.
+//! - `controls`: the theme, indentation and width toggles, the raw source
+//!   link, the search box, and the changed-on-disk notice.
+//! - `header`, `notices`, `properties`: the site header, the draft and
+//!   encoding banners, and the frontmatter panel.
+//! - `body`, `toc`, `site_tree`, `backlinks`, `links`: the rendered content,
+//!   the sidebar table of contents and file tree, the backlinks section, and
+//!   the outgoing links section.
+//! - `headings`: `level`, `text`, `anchor`, and `number` of each heading.
+//! - `breadcrumbs`: `name` and percent-encoded `url` of the serve root and
+//!   each directory above the page; empty in `html` exports.
+//! - `frontmatter`: the page's frontmatter as a mapping of strings, lists,
+//!   and mappings, with keys in alphabetical order.
+//!
+//! The HTML parts are marked safe, so `{{ body }}` inserts the page as
+//! rendered while `{{ frontmatter.author }}` is escaped.  Missing frontmatter
+//! fields are empty, even several levels deep.
+//!
+//! ```
+//! use mdmd::template::{PageParts, PageTemplate};
+//! let template = PageTemplate::from_source(
+//!     "page.html",
+//!     "<h1>{{ title }}</h1>{{ body }}<p>{{ frontmatter.owner.team }}</p>",
+//! )
+//! .unwrap();
+//! let page = template
+//!     .render(&PageParts {
+//!         title: "Fish & chips",
+//!         body: "<p>Recipe</p>",
+//!         ..PageParts::default()
+//!     })
+//!     .unwrap();
+//! assert_eq!(page, "<h1>Fish &amp; chips</h1><p>Recipe</p><p></p>");
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use minijinja::value::Value;
+use minijinja::{context, AutoEscape, Environment, UndefinedBehavior};
+
+use crate::frontmatter::{FrontmatterField, FrontmatterMeta, MetaValue};
+use crate::html::HeadingEntry;
+
+/// A page template, checked for syntax errors when it was created.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageTemplate {
+    /// Name used in error messages, the file's path for [`load`](Self::load).
+    name: String,
+    source: String,
+}
+
+/// The parts of a page a template receives; see the [module docs](self).
+/// HTML parts are fragments as the built-in shell uses them.
+#[derive(Debug, Default)]
+pub struct PageParts<'a> {
+    pub title: &'a str,
+    pub site_title: Option<&'a str>,
+    pub lang: &'a str,
+    pub path: Option<&'a str>,
+    pub head: &'a str,
+    pub scripts: &'a str,
+    pub controls: &'a str,
+    pub header: &'a str,
+    pub notices: &'a str,
+    pub properties: &'a str,
+    pub body: &'a str,
+    pub toc: &'a str,
+    pub site_tree: &'a str,
+    pub backlinks: &'a str,
+    pub links: &'a str,
+    pub headings: &'a [HeadingEntry],
+    pub frontmatter: Option<&'a FrontmatterMeta>,
+}
+
+impl PageTemplate {
+    /// Read and check the template at `path`.
+    pub fn load(path: &Path) -> Result<PageTemplate, String> {
+        let source = fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        PageTemplate::from_source(&path.display().to_string(), source)
+    }
+
+    /// A template named `name` from `source`, or the syntax error in it.
+    pub fn from_source(name: &str, source: impl Into<String>) -> Result<PageTemplate, String> {
+        let template = PageTemplate {
+            name: name.to_owned(),
+            source: source.into(),
+        };
+        template.environment()?;
+        Ok(template)
+    }
+
+    /// The page filled in from `parts`, or why the template failed on it.
+    pub fn render(&self, parts: &PageParts) -> Result<String, String> {
+        let env = self.environment()?;
+        let template = env.get_template(&self.name).map_err(|e| e.to_string())?;
+        template.render(context(parts)).map_err(|e| e.to_string())
+    }
+
+    fn environment(&self) -> Result<Environment<'_>, String> {
+        let mut env = Environment::new();
+        env.set_auto_escape_callback(|_| AutoEscape::Html);
+        env.set_undefined_behavior(UndefinedBehavior::Chainable);
+        env.add_template(&self.name, &self.source)
+            .map_err(|e| e.to_string())?;
+        Ok(env)
+    }
+}
+
+fn context(parts: &PageParts) -> Value {
+    let safe = |html: &str| Value::from_safe_string(html.to_owned());
+    let headings: Vec<Value> = parts
+        .headings
+        .iter()
+        .map(|h| {
+            context! {
+                level => h.level,
+                text => h.text,
+                anchor => h.anchor_id,
+                number => h.number,
+            }
+        })
+        .collect();
+    let breadcrumbs: Vec<Value> = breadcrumbs(parts.path.unwrap_or_default())
+        .into_iter()
+        .map(|(name, url)| context! { name => name, url => safe(&url) })
+        .collect();
+    context! {
+        title => parts.title,
+        site_title => parts.site_title,
+        lang => parts.lang,
+        path => parts.path.unwrap_or_default(),
+        head => safe(parts.head),
+        scripts => safe(parts.scripts),
+        controls => safe(parts.controls),
+        header => safe(parts.header),
+        notices => safe(parts.notices),
+        properties => safe(parts.properties),
+        body => safe(parts.body),
+        toc => safe(parts.toc),
+        site_tree => safe(parts.site_tree),
+        backlinks => safe(parts.backlinks),
+        links => safe(parts.links),
+        headings => headings,
+        breadcrumbs => breadcrumbs,
+        frontmatter => parts.frontmatter.map_or_else(Value::default, |fm| fields(&fm.fields)),
+    }
+}
+
+/// `(name, url)` of the serve root and each directory above the page at
+/// `path`, relative to the root (`docs/guide.md`).  Empty without a path.
+fn breadcrumbs(path: &str) -> Vec<(String, String)> {
+    if path.is_empty() {
+        return Vec::new();
+    }
+    let mut crumbs = vec![("/".to_owned(), "/".to_owned())];
+    let mut url = String::from("/");
+    let dirs = path.trim_start_matches('/').rsplit_once('/');
+    for dir in dirs.map_or("", |(dirs, _)| dirs).split('/') {
+        if dir.is_empty() {
+            continue;
+        }
+        url.push_str(&encode_segment(dir));
+        url.push('/');
+        crumbs.push((dir.to_owned(), url.clone()));
+    }
+    crumbs
+}
+
+/// Percent-encode a URL path segment, leaving RFC 3986 unreserved bytes.
+fn encode_segment(segment: &str) -> String {
+    let mut out = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{b:02X}"));
+        }
+    }
+    out
+}
+
+fn fields(fields: &[FrontmatterField]) -> Value {
+    let map: BTreeMap<&str, Value> = fields
+        .iter()
+        .map(|field| (field.key.as_str(), meta_value(&field.value)))
+        .collect();
+    Value::from(map)
+}
+
+fn meta_value(value: &MetaValue) -> Value {
+    match value {
+        MetaValue::Scalar(text) => Value::from(text.as_str()),
+        MetaValue::Null => Value::from(()),
+        MetaValue::Sequence(items) => Value::from(items.iter().map(meta_value).collect::<Vec<_>>()),
+        MetaValue::Mapping(entries) => fields(entries),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(key: &str, value: MetaValue) -> FrontmatterField {
+        FrontmatterField {
+            key: key.to_owned(),
+            value,
+        }
+    }
+
+    #[test]
+    fn syntax_errors_are_reported_up_front() {
+        let err = PageTemplate::from_source("page.html", "{% if title %}<h1>").unwrap_err();
+        assert!(err.contains("page.html"), "{err}");
+    }
+
+    #[test]
+    fn html_parts_are_safe_and_frontmatter_is_escaped() {
+        let meta = FrontmatterMeta {
+            fields: vec![
+                field("author", MetaValue::Scalar("<Ann>".to_owned())),
+                field(
+                    "tags",
+                    MetaValue::Sequence(vec![
+                        MetaValue::Scalar("a".to_owned()),
+                        MetaValue::Scalar("b".to_owned()),
+                    ]),
+                ),
+            ],
+            title: None,
+        };
+        let headings = [HeadingEntry {
+            level: 2,
+            text: "Setup".to_owned(),
+            anchor_id: "setup".to_owned(),
+            number: None,
+        }];
+        let template = PageTemplate::from_source(
+            "page.html",
+            "{{ body }}|{{ frontmatter.author }}|{{ frontmatter.tags | join(',') }}|\
+{% for h in headings %}#{{ h.anchor }}{% endfor %}|{{ frontmatter.missing.deeper }}",
+        )
+        .unwrap();
+        let page = template
+            .render(&PageParts {
+                body: "<p>Hi</p>",
+                headings: &headings,
+                frontmatter: Some(&meta),
+                ..PageParts::default()
+            })
+            .unwrap();
+        assert_eq!(page, "<p>Hi</p>|&lt;Ann&gt;|a,b|#setup|");
+    }
+
+    #[test]
+    fn breadcrumbs_name_the_directories_above_the_page() {
+        assert_eq!(
+            breadcrumbs("my docs/guide/install.md"),
+            [
+                ("/".to_owned(), "/".to_owned()),
+                ("my docs".to_owned(), "/my%20docs/".to_owned()),
+                ("guide".to_owned(), "/my%20docs/guide/".to_owned()),
+            ]
+        );
+        assert_eq!(breadcrumbs("README.md").len(), 1);
+        assert!(breadcrumbs("").is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+//! Inline tables of contents.
+//!
+//! A paragraph holding only `[TOC]`, or an HTML block holding only
+//! `<!-- toc -->`, is replaced by a nested list of the document's headings,
+//! in the page body rather than the sidebar.  Headings up to level
+//! [`DEFAULT_TOC_DEPTH`] are listed unless the marker names another depth,
+//! as in `[TOC depth=2]` or `<!-- toc depth=4 -->`.
+//!
+//! ```
+//! use mdmd::toc::{marker_depth, nesting};
+//! assert_eq!(marker_depth("[TOC]"), Some(3));
+//! assert_eq!(marker_depth("<!-- toc depth=2 -->"), Some(2));
+//! assert_eq!(nesting(&[1, 2, 3, 2, 1]), vec![0, 1, 2, 1, 0]);
+//! ```
+
+/// Deepest heading level listed when a marker does not say.
+pub const DEFAULT_TOC_DEPTH: u8 = 3;
+
+/// The heading depth a TOC marker asks for, if `text` (a paragraph's text
+/// or an HTML block's source) is one.
+pub fn marker_depth(text: &str) -> Option<u8> {
+    let text = text.trim();
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|t| t.strip_suffix(']'))
+        .or_else(|| text.strip_prefix("<!--")?.strip_suffix("-->"))?;
+    let mut words = inner.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("toc") {
+        return None;
+    }
+    match words.next() {
+        None => Some(DEFAULT_TOC_DEPTH),
+        Some(option) => {
+            let depth = option.strip_prefix("depth=")?.parse().ok()?;
+            (words.next().is_none() && (1..=6).contains(&depth)).then_some(depth)
+        }
+    }
+}
+
+/// Nesting depth of each heading of an outline, given their `levels` in
+/// document order: one more than the nearest earlier heading of a higher
+/// level, so skipped levels do not leave empty steps.
+pub fn nesting(levels: &[u8]) -> Vec<usize> {
+    let mut open: Vec<u8> = Vec::new();
+    levels
+        .iter()
+        .map(|&level| {
+            while open.last().is_some_and(|&l| l >= level) {
+                open.pop();
+            }
+            open.push(level);
+            open.len() - 1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognizes_markers() {
+        assert_eq!(marker_depth(" [toc] "), Some(3));
+        assert_eq!(marker_depth("<!--toc-->\n"), Some(3));
+        assert_eq!(marker_depth("[TOC depth=6]"), Some(6));
+        assert_eq!(marker_depth("[TOC depth=7]"), None);
+        assert_eq!(marker_depth("[TOC] of the book"), None);
+        assert_eq!(marker_depth("[contents]"), None);
+        assert_eq!(marker_depth("<!-- toc: later -->"), None);
+    }
+
+    #[test]
+    fn nesting_skips_missing_levels() {
+        assert_eq!(nesting(&[2, 4, 3, 2]), vec![0, 1, 1, 0]);
+        assert_eq!(nesting(&[3, 1, 2]), vec![0, 0, 1]);
+        assert!(nesting(&[]).is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+//! Markdown file tree of the serve root for `GET /_mdmd/v1/tree` and the
+//! page shell's site navigation sidebar.
+//!
+//! The tree holds every markdown file under the root and the directories
+//! leading to them; directories without markdown are pruned.  Ordering and
+//! hidden-entry rules match the directory index: directories before files,
+//! case-insensitive names, dot-entries left out.
+
+use std::path::Path;
+
+use serde_json::{json, Value};
+
+use crate::selection::Selection;
+use crate::serve::percent_encode_segment;
+
+/// A file or directory in the markdown tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeNode {
+    pub name: String,
+    /// Root-relative path with leading slash; directories end in `/`.
+    pub path: String,
+    /// Percent-encoded `path`, usable as a link.
+    pub href: String,
+    /// `Some` for directories (never empty), `None` for files.
+    pub children: Option<Vec<TreeNode>>,
+}
+
+/// Build the markdown tree under `root`.  Symlinks resolving outside `root`
+/// are left out, as serve would refuse to show them, and so are drafts (see
+/// [`crate::frontmatter::is_draft`]) unless `include_drafts`, and files
+/// outside `selection` when there is one.
+pub fn markdown_tree(
+    root: &Path,
+    include_drafts: bool,
+    selection: Option<&Selection>,
+) -> Vec<TreeNode> {
+    let canonical_root = std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+    let mut top: Vec<TreeNode> = Vec::new();
+    for path in crate::replace::collect_markdown_files(root) {
+        let Ok(rel) = path.strip_prefix(root) else {
+            continue;
+        };
+        let segments: Vec<String> = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if segments.iter().any(|s| s.starts_with('.')) {
+            continue;
+        }
+        if !std::fs::canonicalize(&path).is_ok_and(|c| c.starts_with(&canonical_root)) {
+            continue;
+        }
+        if selection.is_some_and(|sel| !sel.contains(&path)) {
+            continue;
+        }
+        if !include_drafts
+            && std::fs::read_to_string(&path).is_ok_and(|s| crate::frontmatter::is_draft(&s))
+        {
+            continue;
+        }
+        insert(&mut top, &segments, "/", "/");
+    }
+    sort(&mut top);
+    top
+}
+
+/// Insert the file at `segments` below the directory at `path`/`href`.
+fn insert(nodes: &mut Vec<TreeNode>, segments: &[String], path: &str, href: &str) {
+    let Some((name, rest)) = segments.split_first() else {
+        return;
+    };
+    let encoded = percent_encode_segment(name);
+    if rest.is_empty() {
+        nodes.push(TreeNode {
+            name: name.clone(),
+            path: format!("{path}{name}"),
+            href: format!("{href}{encoded}"),
+            children: None,
+        });
+        return;
+    }
+    let idx = match nodes
+        .iter()
+        .position(|n| n.children.is_some() && n.name == *name)
+    {
+        Some(idx) => idx,
+        None => {
+            nodes.push(TreeNode {
+                name: name.clone(),
+                path: format!("{path}{name}/"),
+                href: format!("{href}{encoded}/"),
+                children: Some(Vec::new()),
+            });
+            nodes.len() - 1
+        }
+    };
+    let dir = &mut nodes[idx];
+    let (dir_path, dir_href) = (dir.path.clone(), dir.href.clone());
+    if let Some(children) = dir.children.as_mut() {
+        insert(children, rest, &dir_path, &dir_href);
+    }
+}
+
+fn sort(nodes: &mut [TreeNode]) {
+    nodes.sort_by(|a, b| {
+        b.children
+            .is_some()
+            .cmp(&a.children.is_some())
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+    for node in nodes {
+        if let Some(children) = node.children.as_mut() {
+            sort(children);
+        }
+    }
+}
+
+/// The tree as JSON: `{"path":"/","children":[...]}`, where each node has
+/// `name`, `path`, `href`, `type` (`"dir"` or `"file"`), and directories
+/// carry their own `children`.
+pub fn tree_json(nodes: &[TreeNode]) -> Value {
+    fn node_json(node: &TreeNode) -> Value {
+        let mut value = json!({
+            "name": node.name,
+            "path": node.path,
+            "href": node.href,
+            "type": if node.children.is_some() { "dir" } else { "file" },
+        });
+        if let Some(children) = &node.children {
+            value["children"] = children.iter().map(node_json).collect();
+        }
+        value
+    }
+    json!({
+        "path": "/",
+        "children": nodes.iter().map(node_json).collect::<Vec<_>>(),
+    })
+}
+
+/// The tree as nested `<ul>` lists with a collapsible `<details>` per
+/// directory, for the page shell's file sidebar.
+pub fn tree_html(nodes: &[TreeNode]) -> String {
+    let mut html = String::new();
+    push_list(&mut html, nodes);
+    html
+}
+
+fn push_list(html: &mut String, nodes: &[TreeNode]) {
+    html.push_str("<ul>");
+    for node in nodes {
+        let name = escape(&node.name);
+        match &node.children {
+            Some(children) => {
+                html.push_str(&format!(
+                    "<li class=\"site-tree-dir\"><details><summary>{name}</summary>"
+                ));
+                push_list(html, children);
+                html.push_str("</details></li>");
+            }
+            None => html.push_str(&format!(
+                "<li><a href=\"{}\">{name}</a></li>",
+                escape(&node.href)
+            )),
+        }
+    }
+    html.push_str("</ul>");
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("docs/deep")).unwrap();
+        fs::create_dir_all(root.join("assets")).unwrap();
+        fs::create_dir_all(root.join(".hidden")).unwrap();
+        fs::write(root.join("README.md"), "# Root\n").unwrap();
+        fs::write(root.join("docs/b guide.md"), "").unwrap();
+        fs::write(root.join("docs/A.md"), "").unwrap();
+        fs::write(root.join("docs/deep/x.md"), "").unwrap();
+        fs::write(root.join("assets/logo.png"), "").unwrap();
+        fs::write(root.join(".hidden/secret.md"), "").unwrap();
+        dir
+    }
+
+    #[test]
+    fn tree_nests_markdown_and_prunes_other_directories() {
+        let dir = fixture();
+        let tree = markdown_tree(dir.path(), true, None);
+        let names: Vec<&str> = tree.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["docs", "README.md"]);
+
+        let docs = tree[0].children.as_ref().unwrap();
+        let names: Vec<&str> = docs.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, ["deep", "A.md", "b guide.md"]);
+        assert_eq!(docs[2].path, "/docs/b guide.md");
+        assert_eq!(docs[2].href, "/docs/b%20guide.md");
+        assert_eq!(docs[0].path, "/docs/deep/");
+    }
+
+    #[test]
+    fn drafts_are_left_out_unless_included() {
+        let dir = fixture();
+        fs::write(dir.path().join("docs/deep/x.md"), "---\ndraft: true\n---\n").unwrap();
+        let docs = |tree: Vec<TreeNode>| -> Vec<String> {
+            let docs = tree[0].children.clone().unwrap();
+            docs.into_iter().map(|n| n.name).collect()
+        };
+        assert_eq!(
+            docs(markdown_tree(dir.path(), false, None)),
+            ["A.md", "b guide.md"]
+        );
+        assert_eq!(
+            docs(markdown_tree(dir.path(), true, None)),
+            ["deep", "A.md", "b guide.md"]
+        );
+    }
+
+    #[test]
+    fn tree_renders_as_json_and_collapsible_html() {
+        let dir = fixture();
+        let tree = markdown_tree(dir.path(), true, None);
+
+        let json = tree_json(&tree);
+        assert_eq!(json["children"][0]["type"], "dir");
+        assert_eq!(json["children"][0]["children"][0]["name"], "deep");
+        assert_eq!(json["children"][1]["type"], "file");
+        assert!(json["children"][1].get("children").is_none());
+
+        let html = tree_html(&tree);
+        assert!(html
+            .starts_with("<ul><li class=\"site-tree-dir\"><details><summary>docs</summary><ul>"));
+        assert!(html.contains("<li><a href=\"/docs/b%20guide.md\">b guide.md</a></li>"));
+        assert!(!html.contains("secret"));
+    }
+}
@@ -0,0 +1,246 @@
+//! `{{var.name}}` placeholders, substituted before a document is rendered.
+//!
+//! Values come from the nearest `.mdmd.toml` above the document (its
+//! `[vars]` table) and from the document's frontmatter `vars:` mapping,
+//! which wins.  Nested tables and mappings are named with dots, so
+//!
+//! ```toml
+//! [vars]
+//! product = "Widget"
+//! links.docs = "https://example.com/docs"
+//! ```
+//!
+//! provides `{{var.product}}` and `{{var.links.docs}}`.  Unknown names are
+//! left as written, and `\{{var.name}}` renders the placeholder itself.
+//! Newlines in values become spaces so source line numbers are kept.
+//!
+//! ```
+//! let vars = mdmd::vars::Vars::from_pairs([("version", "1.2")]);
+//! let text = mdmd::vars::substitute("v{{var.version}}, not \\{{var.version}}", &vars);
+//! assert_eq!(text, "v1.2, not {{var.version}}");
+//! ```
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::frontmatter::{self, FrontmatterField, FrontmatterMeta, MetaValue};
+
+/// Name of the project-level config file holding a `[vars]` table.
+pub const PROJECT_CONFIG: &str = ".mdmd.toml";
+
+/// Variables available to a document, by dotted name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Vars {
+    values: BTreeMap<String, String>,
+}
+
+impl Vars {
+    /// Variables with the given dotted names and values.
+    pub fn from_pairs<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vars {
+        let mut vars = Vars::default();
+        for (name, value) in pairs {
+            vars.set(name.to_owned(), value);
+        }
+        vars
+    }
+
+    /// Variables for `file`: the project config's, overridden by those in
+    /// the frontmatter `meta`.  With a `root`, config files above it are not
+    /// looked at.
+    pub fn for_document(file: &Path, meta: Option<&FrontmatterMeta>, root: Option<&Path>) -> Vars {
+        let mut vars = project_vars(file, root);
+        if let Some(field) = meta.and_then(|m| m.fields.iter().find(|f| f.key == "vars")) {
+            vars.add_meta("", &field.value);
+        }
+        vars
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    fn set(&mut self, name: String, value: &str) {
+        self.values
+            .insert(name, value.replace("\r\n", " ").replace('\n', " "));
+    }
+
+    fn add_meta(&mut self, prefix: &str, value: &MetaValue) {
+        match value {
+            MetaValue::Scalar(text) if !prefix.is_empty() => self.set(prefix.to_owned(), text),
+            MetaValue::Mapping(fields) => {
+                for FrontmatterField { key, value } in fields {
+                    self.add_meta(&dotted(prefix, key), value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_toml(&mut self, prefix: &str, value: &toml::Value) {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, value) in table {
+                    self.add_toml(&dotted(prefix, key), value);
+                }
+            }
+            toml::Value::String(text) => self.set(prefix.to_owned(), text),
+            toml::Value::Array(_) => {}
+            other => self.set(prefix.to_owned(), &other.to_string()),
+        }
+    }
+}
+
+fn dotted(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+/// The `[vars]` table of the nearest [`PROJECT_CONFIG`] in `file`'s
+/// directory or above it, stopping at `root`.  A config file that cannot
+/// be read or parsed provides no variables.
+pub fn project_vars(file: &Path, root: Option<&Path>) -> Vars {
+    let mut vars = Vars::default();
+    let mut dir = file.parent();
+    while let Some(current) = dir {
+        let config = current.join(PROJECT_CONFIG);
+        if config.is_file() {
+            let table = fs::read_to_string(&config)
+                .ok()
+                .and_then(|text| text.parse::<toml::Table>().ok());
+            if let Some(table) = table.as_ref().and_then(|t| t.get("vars")) {
+                vars.add_toml("", table);
+            }
+            break;
+        }
+        if root.is_some_and(|root| current == root) {
+            break;
+        }
+        dir = current.parent();
+    }
+    vars
+}
+
+/// `source`, a markdown file's full text, with the placeholders in its body
+/// substituted from [`Vars::for_document`].  The frontmatter is kept as is.
+pub fn substitute_document<'a>(source: &'a str, file: &Path, root: Option<&Path>) -> Cow<'a, str> {
+    if !source.contains("{{") {
+        return Cow::Borrowed(source);
+    }
+    let extracted = frontmatter::extract(source);
+    let vars = Vars::for_document(file, extracted.meta.as_ref(), root);
+    match substitute(extracted.body, &vars) {
+        Cow::Borrowed(_) => Cow::Borrowed(source),
+        Cow::Owned(body) => Cow::Owned(format!(
+            "{}{body}",
+            &source[..source.len() - extracted.body.len()]
+        )),
+    }
+}
+
+/// Replace each `{{var.name}}` in `text` with its value in `vars`, and each
+/// escaped `\{{var.name}}` with the placeholder itself.
+pub fn substitute<'a>(text: &'a str, vars: &Vars) -> Cow<'a, str> {
+    if !text.contains("{{") {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut changed = false;
+    while let Some(open) = rest.find("{{") {
+        let Some((name, len)) = placeholder(&rest[open..]) else {
+            out.push_str(&rest[..open + 2]);
+            rest = &rest[open + 2..];
+            continue;
+        };
+        let escaped = rest[..open].ends_with('\\');
+        let replacement = if escaped {
+            Some(&rest[open..open + len])
+        } else {
+            vars.get(name)
+        };
+        match replacement {
+            Some(replacement) => {
+                out.push_str(&rest[..open - usize::from(escaped)]);
+                out.push_str(replacement);
+                changed = true;
+            }
+            None => out.push_str(&rest[..open + len]),
+        }
+        rest = &rest[open + len..];
+    }
+    if !changed {
+        return Cow::Borrowed(text);
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
+
+/// Name and byte length of the placeholder `text` starts with, if it does.
+fn placeholder(text: &str) -> Option<(&str, usize)> {
+    let inner = &text[2..];
+    let close = inner.find("}}")?;
+    let name = inner[..close].trim().strip_prefix("var.")?;
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    valid.then_some((name, close + 4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutes_known_names_and_keeps_the_rest() {
+        let vars = Vars::from_pairs([("product", "Widget"), ("links.docs", "https://x")]);
+        assert_eq!(
+            substitute(
+                "{{var.product}} docs: {{ var.links.docs }}, {{var.nope}}, {{other}} {{",
+                &vars
+            ),
+            "Widget docs: https://x, {{var.nope}}, {{other}} {{"
+        );
+        assert_eq!(
+            substitute("\\{{var.product}} is {{var.product}}", &vars),
+            "{{var.product}} is Widget"
+        );
+        assert!(matches!(
+            substitute("{{var.nope}} \\{{x}}", &vars),
+            Cow::Borrowed(_)
+        ));
+    }
+
+    #[test]
+    fn frontmatter_overrides_the_project_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("docs")).unwrap();
+        fs::write(
+            root.join(PROJECT_CONFIG),
+            "[vars]\nproduct = \"Widget\"\nversion = \"1.0\"\nbeta = true\n\n[vars.links]\ndocs = \"https://x\"\n",
+        )
+        .unwrap();
+        let source = "---\nvars:\n  version: \"2.0\"\n  note: |\n    two\n    lines\n---\n\
+{{var.product}} {{var.version}} {{var.beta}} {{var.links.docs}} {{var.note}}\n";
+        let out = substitute_document(source, &root.join("docs/guide.md"), Some(root));
+        assert!(
+            out.ends_with("---\nWidget 2.0 true https://x two lines \n"),
+            "{out}"
+        );
+        assert!(out.starts_with("---\nvars:\n  version: \"2.0\""));
+
+        // Config files above the root are not consulted.
+        let docs = root.join("docs");
+        assert!(project_vars(&docs.join("guide.md"), Some(&docs)).is_empty());
+    }
+}
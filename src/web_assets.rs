@@ -1,7 +1,17 @@
 //! Embedded static web assets for the mdmd serve mode.
 //!
-//! Both files are compiled into the binary via `include_str!` so the binary
+//! All assets are compiled into the binary via `include_str!` so the binary
 //! is fully self-contained; no external asset files need to be distributed.
+//! Served pages link the stylesheet and script at content-hashed URLs
+//! ([`css_url`], [`js_url`]), which change whenever the asset does and so
+//! can be cached for good.
+
+use std::sync::OnceLock;
+
+/// Directory the assets are embedded from, as laid out when this binary was
+/// built.  `mdmd serve --dev` reads `mdmd.css` and `mdmd.js` from here on
+/// every request instead of using the embedded copies.
+pub const SOURCE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/assets");
 
 /// Stylesheet for the serve-mode HTML viewer.
 ///
@@ -14,3 +24,76 @@ pub const CSS: &str = include_str!("assets/mdmd.css");
 /// contains the Mermaid initialisation stub.
 /// Loaded from `src/assets/mdmd.js` at compile time.
 pub const JS: &str = include_str!("assets/mdmd.js");
+
+/// An asset compressed at build time (see `build.rs`), so `serve` can send
+/// it to clients that accept brotli or gzip without compressing it on every
+/// request.
+#[derive(Debug, Clone, Copy)]
+pub struct Precompressed {
+    pub brotli: &'static [u8],
+    pub gzip: &'static [u8],
+}
+
+/// [`CSS`] compressed with brotli and gzip.
+pub const CSS_COMPRESSED: Precompressed = Precompressed {
+    brotli: include_bytes!(concat!(env!("OUT_DIR"), "/mdmd.css.br")),
+    gzip: include_bytes!(concat!(env!("OUT_DIR"), "/mdmd.css.gz")),
+};
+
+/// [`JS`] compressed with brotli and gzip.
+pub const JS_COMPRESSED: Precompressed = Precompressed {
+    brotli: include_bytes!(concat!(env!("OUT_DIR"), "/mdmd.js.br")),
+    gzip: include_bytes!(concat!(env!("OUT_DIR"), "/mdmd.js.gz")),
+};
+
+/// OpenAPI 3.0 description of the `/_mdmd/*` JSON endpoints, served at
+/// `/_mdmd/api.json`.
+///
+/// Loaded from `src/assets/openapi.json` at compile time.
+pub const OPENAPI: &str = include_str!("assets/openapi.json");
+
+/// Default favicon, served at `/favicon.ico` unless `serve --favicon`
+/// replaces it, and inlined as a data URI by `mdmd html`.
+///
+/// Loaded from `src/assets/favicon.svg` at compile time.
+pub const FAVICON_SVG: &str = include_str!("assets/favicon.svg");
+
+/// URL path of [`CSS`] in served pages: `/assets/mdmd.<hash>.css`, with
+/// the hash `mdmd info` reports for it.
+pub fn css_url() -> &'static str {
+    static URL: OnceLock<String> = OnceLock::new();
+    URL.get_or_init(|| hashed_url("mdmd", CSS, "css"))
+}
+
+/// URL path of [`JS`] in served pages: `/assets/mdmd.<hash>.js`.
+pub fn js_url() -> &'static str {
+    static URL: OnceLock<String> = OnceLock::new();
+    URL.get_or_init(|| hashed_url("mdmd", JS, "js"))
+}
+
+fn hashed_url(stem: &str, content: &str, ext: &str) -> String {
+    format!("/assets/{stem}.{:016x}.{ext}", fnv1a_64(content.as_bytes()))
+}
+
+/// Compute a 64-bit FNV-1a hash of `data`.
+///
+/// FNV-1a is used here for its speed — it is suitable for cache-validation
+/// ETags but NOT for any cryptographic purpose.  Algorithm:
+///   hash = offset_basis
+///   for each byte: hash ^= byte; hash *= FNV_prime
+/// with `offset_basis` = 14695981039346656037 and `FNV_prime` = 1099511628211
+/// (the standard 64-bit FNV-1a constants).
+///
+/// To change the hash algorithm, replace only this function and update the
+/// comment above — ETags (`serve::compute_etag`) and hashed asset URLs both go through it.
+pub fn fnv1a_64(data: &[u8]) -> u64 {
+    // 64-bit FNV-1a constants from the FNV specification.
+    const FNV_PRIME: u64 = 1099511628211;
+    const FNV_OFFSET_BASIS: u64 = 14695981039346656037;
+    let mut hash = FNV_OFFSET_BASIS;
+    for &byte in data {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
@@ -0,0 +1,339 @@
+//! Named viewer sessions: `mdmd view --session <name>`.
+//!
+//! On quit the viewer's navigation state — the open document with its
+//! scroll position and focused link, the back and forward stacks, the
+//! visited-files history, and the jump list — is saved under the session
+//! name, and starting the viewer with the same name picks up where it left
+//! off.  (Key recordings for `mdmd replay` live in `session.rs`.)
+//!
+//! Sessions are stored one file per name in
+//! `$XDG_STATE_HOME/mdmd/sessions/`, falling back to
+//! `$HOME/.local/state/mdmd/sessions/`, in a line-based format:
+//!
+//! ```text
+//! # mdmd view session v1
+//! current 120 3 /home/me/docs/guide.md
+//! back 0 - /home/me/docs/README.md
+//! visit 1718000000 /home/me/docs/README.md
+//! visit 1718000042 /home/me/docs/guide.md
+//! jump 0 /home/me/docs/README.md
+//! jump-index 1
+//! queue /home/me/docs/README.md
+//! queue /home/me/docs/guide.md
+//! ```
+//!
+//! Position lines are `<scroll> <focused link or -> <path>`; `back` and
+//! `forward` lines are in stack order (bottom first), `visit` lines oldest
+//! first with Unix-epoch seconds, and `queue` lines list the files `]f`
+//! steps through in order.  The path always comes last so it may
+//! contain spaces.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::history::Visit;
+use crate::jumplist::JumpPosition;
+
+const HEADER: &str = "# mdmd view session v1";
+
+/// A document and where the reader was in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavigationEntry {
+    pub file_path: PathBuf,
+    pub scroll_offset: usize,
+    pub focused_link: Option<usize>,
+}
+
+/// Everything the viewer restores from a named session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Workspace {
+    pub current: NavigationEntry,
+    /// Entries revisited by Backspace, most recent last.
+    pub back: Vec<NavigationEntry>,
+    /// Entries replayed by `L`, most recent last.
+    pub forward: Vec<NavigationEntry>,
+    /// Visited files, oldest first.
+    pub visits: Vec<Visit>,
+    pub jumps: Vec<JumpPosition>,
+    /// Jump list cursor; equal to `jumps.len()` when not walking the list.
+    pub jump_index: usize,
+    /// Files given together on the command line, stepped through by `]f`
+    /// and `[f`.  Empty when one file was given.
+    pub queue: Vec<PathBuf>,
+}
+
+impl Workspace {
+    /// A fresh workspace showing the top of `file`.
+    pub fn new(file: PathBuf) -> Self {
+        Self {
+            current: NavigationEntry {
+                file_path: file,
+                scroll_offset: 0,
+                focused_link: None,
+            },
+            back: Vec::new(),
+            forward: Vec::new(),
+            visits: Vec::new(),
+            jumps: Vec::new(),
+            jump_index: 0,
+            queue: Vec::new(),
+        }
+    }
+
+    /// Open `file` on top of the restored state, as if a link to it had been
+    /// followed.  Opening the current document again keeps its position.
+    pub fn open(&mut self, file: PathBuf) {
+        if file == self.current.file_path {
+            return;
+        }
+        let previous = std::mem::replace(
+            &mut self.current,
+            NavigationEntry {
+                file_path: file,
+                scroll_offset: 0,
+                focused_link: None,
+            },
+        );
+        self.back.push(previous);
+        self.forward.clear();
+    }
+}
+
+/// Check that `name` is usable as a session file name.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    let ok = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if ok {
+        Ok(())
+    } else {
+        Err(format!(
+            "invalid session name '{name}': use letters, digits, '-', '_' and '.'"
+        ))
+    }
+}
+
+/// mdmd's state directory: `$XDG_STATE_HOME/mdmd`, else
+/// `$HOME/.local/state/mdmd`.  `None` when neither variable is set.
+pub fn state_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_STATE_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|v| !v.is_empty())
+                .map(|home| PathBuf::from(home).join(".local").join("state"))
+        })?;
+    Some(base.join("mdmd"))
+}
+
+/// Where the session `name` is stored.  `None` when neither
+/// `XDG_STATE_HOME` nor `HOME` is set.
+pub fn session_path(name: &str) -> Option<PathBuf> {
+    Some(state_dir()?.join("sessions").join(name))
+}
+
+/// Read the session at `path`; `Ok(None)` when it has not been saved yet.
+pub fn load(path: &Path) -> Result<Option<Workspace>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse(&text)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("cannot read session {}: {e}", path.display())),
+    }
+}
+
+/// Write `workspace` to `path`, creating the sessions directory if needed.
+pub fn save(path: &Path, workspace: &Workspace) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    crate::replace::write_atomic(path, &format(workspace))
+}
+
+/// Serialize `workspace` in the session file format.
+pub fn format(workspace: &Workspace) -> String {
+    let position = |e: &NavigationEntry| {
+        let link = e
+            .focused_link
+            .map_or_else(|| "-".to_owned(), |l| l.to_string());
+        format!("{} {link} {}", e.scroll_offset, e.file_path.display())
+    };
+    let mut out = format!("{HEADER}\ncurrent {}\n", position(&workspace.current));
+    for e in &workspace.back {
+        out.push_str(&format!("back {}\n", position(e)));
+    }
+    for e in &workspace.forward {
+        out.push_str(&format!("forward {}\n", position(e)));
+    }
+    for v in &workspace.visits {
+        let secs = v
+            .visited_at
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        out.push_str(&format!("visit {secs} {}\n", v.file_path.display()));
+    }
+    for j in &workspace.jumps {
+        out.push_str(&format!(
+            "jump {} {}\n",
+            j.scroll_offset,
+            j.file_path.display()
+        ));
+    }
+    out.push_str(&format!("jump-index {}\n", workspace.jump_index));
+    for path in &workspace.queue {
+        out.push_str(&format!("queue {}\n", path.display()));
+    }
+    out
+}
+
+/// Parse the text of a session file.
+pub fn parse(text: &str) -> Result<Workspace, String> {
+    let mut current = None;
+    let mut ws = Workspace::new(PathBuf::new());
+
+    for (idx, raw) in text.lines().enumerate() {
+        let line = raw.trim_end();
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let err = |msg: &str| format!("line {}: {msg}: {line}", idx + 1);
+        let (head, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let number = |s: &str| s.parse::<u64>().map_err(|_| err("expected a number"));
+        let position = |rest: &str| -> Result<NavigationEntry, String> {
+            let mut parts = rest.splitn(3, ' ');
+            let (Some(scroll), Some(link), Some(path)) = (parts.next(), parts.next(), parts.next())
+            else {
+                return Err(err("expected `<scroll> <link|-> <path>`"));
+            };
+            Ok(NavigationEntry {
+                file_path: PathBuf::from(path),
+                scroll_offset: number(scroll)? as usize,
+                focused_link: match link {
+                    "-" => None,
+                    l => Some(number(l)? as usize),
+                },
+            })
+        };
+        match head {
+            "current" => current = Some(position(rest)?),
+            "back" => ws.back.push(position(rest)?),
+            "forward" => ws.forward.push(position(rest)?),
+            "visit" => {
+                let (secs, path) = rest
+                    .split_once(' ')
+                    .ok_or_else(|| err("expected `<secs> <path>`"))?;
+                ws.visits.push(Visit {
+                    file_path: PathBuf::from(path),
+                    visited_at: SystemTime::UNIX_EPOCH + Duration::from_secs(number(secs)?),
+                });
+            }
+            "jump" => {
+                let (scroll, path) = rest
+                    .split_once(' ')
+                    .ok_or_else(|| err("expected `<scroll> <path>`"))?;
+                ws.jumps.push(JumpPosition {
+                    file_path: PathBuf::from(path),
+                    scroll_offset: number(scroll)? as usize,
+                });
+            }
+            "jump-index" => ws.jump_index = number(rest)? as usize,
+            "queue" => ws.queue.push(PathBuf::from(rest)),
+            _ => return Err(err("unrecognized line")),
+        }
+    }
+
+    ws.current = current.ok_or("session has no `current` line")?;
+    ws.jump_index = ws.jump_index.min(ws.jumps.len());
+    Ok(ws)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, scroll: usize, link: Option<usize>) -> NavigationEntry {
+        NavigationEntry {
+            file_path: PathBuf::from(path),
+            scroll_offset: scroll,
+            focused_link: link,
+        }
+    }
+
+    #[test]
+    fn format_round_trips_through_parse() {
+        let ws = Workspace {
+            current: entry("/docs/user guide.md", 120, Some(3)),
+            back: vec![entry("/docs/README.md", 0, None)],
+            forward: vec![entry("/docs/api.md", 7, None)],
+            visits: vec![Visit {
+                file_path: PathBuf::from("/docs/README.md"),
+                visited_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_718_000_000),
+            }],
+            jumps: vec![JumpPosition {
+                file_path: PathBuf::from("/docs/README.md"),
+                scroll_offset: 40,
+            }],
+            jump_index: 1,
+            queue: vec![PathBuf::from("/docs/a.md"), PathBuf::from("/docs/b.md")],
+        };
+        let text = format(&ws);
+        assert!(text.starts_with(HEADER));
+        assert!(text.contains("\ncurrent 120 3 /docs/user guide.md\n"));
+        assert_eq!(parse(&text).unwrap(), ws);
+    }
+
+    #[test]
+    fn parse_reports_bad_lines() {
+        assert!(parse("back 0 - /a.md\n")
+            .unwrap_err()
+            .contains("no `current`"));
+        let err = parse("current x - /a.md\n").unwrap_err();
+        assert!(err.starts_with("line 1: expected a number"), "{err}");
+        assert!(parse("current 0 - /a.md\nbogus\n")
+            .unwrap_err()
+            .starts_with("line 2: unrecognized line"));
+        // An out-of-range cursor is clamped rather than rejected.
+        assert_eq!(
+            parse("current 0 - /a.md\njump-index 9\n")
+                .unwrap()
+                .jump_index,
+            0
+        );
+    }
+
+    #[test]
+    fn open_pushes_current_onto_back_stack() {
+        let mut ws = Workspace::new(PathBuf::from("/a.md"));
+        ws.current.scroll_offset = 12;
+        ws.forward.push(entry("/c.md", 0, None));
+        ws.open(PathBuf::from("/a.md"));
+        assert_eq!(ws.current.scroll_offset, 12, "same file keeps position");
+        ws.open(PathBuf::from("/b.md"));
+        assert_eq!(ws.current, entry("/b.md", 0, None));
+        assert_eq!(ws.back, vec![entry("/a.md", 12, None)]);
+        assert!(ws.forward.is_empty());
+    }
+
+    #[test]
+    fn load_and_save_use_the_session_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sessions").join("reading");
+        assert_eq!(load(&path).unwrap(), None);
+        let ws = Workspace::new(PathBuf::from("/a.md"));
+        save(&path, &ws).unwrap();
+        assert_eq!(load(&path).unwrap(), Some(ws));
+    }
+
+    #[test]
+    fn session_names_are_plain_file_names() {
+        assert!(validate_name("rust-book_2.0").is_ok());
+        for bad in ["", ".hidden", "a/b", "..", "two words"] {
+            assert!(validate_name(bad).is_err(), "{bad:?}");
+        }
+    }
+}
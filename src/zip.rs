@@ -0,0 +1,193 @@
+//! Writing zip archives for `/_mdmd/archive`.
+//!
+//! Entries are stored uncompressed: the HTTP response is compressed on the
+//! way out anyway, and most of what a doc set holds besides text is images
+//! that do not compress.  Each entry is written out as soon as it is added,
+//! so an archive can be streamed; only the central directory is kept until
+//! [`ZipWriter::finish`].  Archives are limited to what the plain zip format
+//! can describe: 65535 entries and 4 GiB.
+
+use std::io::Write;
+use std::time::SystemTime;
+
+/// CRC-32 (IEEE) lookup table.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, &b| {
+        CRC_TABLE[((crc ^ u32::from(b)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// MS-DOS `(time, date)` fields for `time`, in UTC.  Times before 1980,
+/// which the format cannot hold, become 1980-01-01.
+fn dos_time(time: SystemTime) -> (u16, u16) {
+    match crate::serve::utc_fields(time) {
+        Some([year, month, day, hour, minute, second]) if year >= 1980 => (
+            ((hour << 11) | (minute << 5) | (second / 2)) as u16,
+            (((year - 1980).min(127) << 9) | (month << 5) | day) as u16,
+        ),
+        _ => (0, (1 << 5) | 1),
+    }
+}
+
+/// A zip archive being written to `W`.
+pub struct ZipWriter<W: Write> {
+    out: W,
+    /// Bytes written to `out` so far.
+    written: u64,
+    central: Vec<u8>,
+    entries: u16,
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(out: W) -> ZipWriter<W> {
+        ZipWriter {
+            out,
+            written: 0,
+            central: Vec::new(),
+            entries: 0,
+        }
+    }
+
+    /// Add a file at `name`, a `/`-separated path inside the archive.
+    /// Fails once the archive is too big for the plain zip format, or when
+    /// writing to `W` fails.
+    pub fn add(&mut self, name: &str, modified: SystemTime, data: &[u8]) -> Result<(), String> {
+        if self.entries == u16::MAX {
+            return Err(format!("more than {} files", u16::MAX));
+        }
+        let too_big = || "more than 4 GiB".to_owned();
+        let size = u32::try_from(data.len()).map_err(|_| too_big())?;
+        let offset = u32::try_from(self.written).map_err(|_| too_big())?;
+        let name_len = u16::try_from(name.len()).map_err(|_| format!("name too long: {name}"))?;
+        let (time, date) = dos_time(modified);
+        let crc = crc32(data);
+
+        // Fields shared by the local and central headers, from "version
+        // needed" through the extra field length: stored, with UTF-8 names.
+        let mut common = Vec::with_capacity(26);
+        for half in [20, 1 << 11, 0, time, date] {
+            common.extend_from_slice(&u16::to_le_bytes(half));
+        }
+        for word in [crc, size, size] {
+            common.extend_from_slice(&word.to_le_bytes());
+        }
+        common.extend_from_slice(&name_len.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes());
+
+        let mut local = Vec::with_capacity(30 + name.len());
+        local.extend_from_slice(&0x0403_4B50u32.to_le_bytes());
+        local.extend_from_slice(&common);
+        local.extend_from_slice(name.as_bytes());
+        self.written += (local.len() + data.len()) as u64;
+        u32::try_from(self.written).map_err(|_| too_big())?;
+        self.write(&local)?;
+        self.write(data)?;
+
+        let central = &mut self.central;
+        central.extend_from_slice(&0x0201_4B50u32.to_le_bytes());
+        // Made by: zip 2.0 on Unix.
+        central.extend_from_slice(&((3u16 << 8) | 20).to_le_bytes());
+        central.extend_from_slice(&common);
+        // Comment length, disk number, internal attributes.
+        central.extend_from_slice(&[0; 6]);
+        // External attributes: a regular file, rw-r--r--.
+        central.extend_from_slice(&(0o100_644u32 << 16).to_le_bytes());
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+        self.entries += 1;
+        Ok(())
+    }
+
+    /// Write the central directory and hand back `W`, flushed.
+    pub fn finish(mut self) -> Result<W, String> {
+        let offset = self.written as u32;
+        let size = self.central.len() as u32;
+        let mut end = std::mem::take(&mut self.central);
+        end.extend_from_slice(&0x0605_4B50u32.to_le_bytes());
+        // This disk, and the disk the central directory starts on.
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&self.entries.to_le_bytes());
+        end.extend_from_slice(&self.entries.to_le_bytes());
+        end.extend_from_slice(&size.to_le_bytes());
+        end.extend_from_slice(&offset.to_le_bytes());
+        // Comment length.
+        end.extend_from_slice(&[0; 2]);
+        self.write(&end)?;
+        self.out.flush().map_err(|e| e.to_string())?;
+        Ok(self.out)
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.out.write_all(bytes).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn u16_at(bytes: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([bytes[at], bytes[at + 1]])
+    }
+
+    fn u32_at(bytes: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn entries_are_listed_in_the_central_directory() {
+        // 2024-06-10T06:13:20Z
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_718_000_000);
+        let mut zip = ZipWriter::new(Vec::new());
+        zip.add("docs/guide.html", time, b"<p>Guide</p>").unwrap();
+        zip.add("docs/img/logo.png", time, &[0x89, b'P', b'N', b'G'])
+            .unwrap();
+        let bytes = zip.finish().unwrap();
+
+        let end = bytes.len() - 22;
+        assert_eq!(u32_at(&bytes, end), 0x0605_4B50);
+        assert_eq!(u16_at(&bytes, end + 10), 2);
+        let central = u32_at(&bytes, end + 16) as usize;
+        assert_eq!(u32_at(&bytes, central), 0x0201_4B50);
+        assert_eq!(u32_at(&bytes, central + 16), crc32(b"<p>Guide</p>"));
+        assert_eq!(&bytes[central + 46..central + 61], b"docs/guide.html");
+
+        // The first local header, its name, and its data.
+        assert_eq!(u32_at(&bytes, 0), 0x0403_4B50);
+        let (time, date) = (u16_at(&bytes, 10), u16_at(&bytes, 12));
+        assert_eq!((time >> 11, (time >> 5) & 63, (time & 31) * 2), (6, 13, 20));
+        assert_eq!(
+            (1980 + (date >> 9), (date >> 5) & 15, date & 31),
+            (2024, 6, 10)
+        );
+        assert_eq!(&bytes[30..45], b"docs/guide.html");
+        assert_eq!(&bytes[45..57], b"<p>Guide</p>");
+    }
+}
@@ -0,0 +1,481 @@
+//! Integration tests for command-line parsing and the non-interactive
+//! subcommands.
+
+use std::process::{Command, Output};
+
+fn bin_path() -> String {
+    // See serve_integration.rs: fall back to the manifest-relative path where
+    // CARGO_BIN_EXE_mdmd is not propagated.
+    std::env::var("CARGO_BIN_EXE_mdmd").unwrap_or_else(|_| {
+        let dir =
+            std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is set by cargo test");
+        format!("{dir}/target/debug/mdmd")
+    })
+}
+
+fn mdmd(args: &[&str]) -> Output {
+    Command::new(bin_path())
+        .args(args)
+        .output()
+        .expect("run mdmd")
+}
+
+fn stdout(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+fn stderr(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stderr).into_owned()
+}
+
+#[test]
+fn completions_cover_subcommands_for_each_shell() {
+    for shell in ["bash", "zsh", "fish"] {
+        let out = mdmd(&["completions", shell]);
+        assert!(out.status.success(), "{shell}: {}", stderr(&out));
+        let script = stdout(&out);
+        for sub in ["serve", "view", "headings"] {
+            assert!(script.contains(sub), "{shell} completions missing {sub}");
+        }
+    }
+}
+
+#[test]
+fn completions_reject_unknown_shell_with_subcommand_error() {
+    let out = mdmd(&["completions", "tcsh"]);
+    assert!(!out.status.success());
+    let err = stderr(&out);
+    assert!(
+        err.contains("invalid value 'tcsh'"),
+        "expected completions error, got: {err}"
+    );
+}
+
+#[test]
+fn man_prints_roff_page() {
+    let out = mdmd(&["man"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let page = stdout(&out);
+    assert!(page.contains(".TH mdmd 1"), "got: {page}");
+    assert!(page.contains("mdmd\\-serve(1)"));
+}
+
+#[test]
+fn man_out_dir_writes_page_per_subcommand() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let out = mdmd(&["man", "--out-dir", dir.path().to_str().unwrap()]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(dir.path().join("mdmd.1").is_file());
+    assert!(dir.path().join("mdmd-serve.1").is_file());
+    let written = std::fs::read_dir(dir.path()).unwrap().count();
+    assert_eq!(stdout(&out).lines().count(), written);
+}
+
+#[test]
+fn unknown_flag_is_reported_by_the_parser() {
+    let out = mdmd(&["--bogus"]);
+    assert_eq!(out.status.code(), Some(2));
+    let err = stderr(&out);
+    assert!(err.contains("unexpected argument '--bogus'"), "got: {err}");
+    assert!(!err.contains("file not found"), "got: {err}");
+}
+
+#[test]
+fn subcommand_missing_file_names_the_subcommand() {
+    let out = mdmd(&["serve"]);
+    assert_eq!(out.status.code(), Some(2));
+    let err = stderr(&out);
+    assert!(err.contains("<FILE>"), "got: {err}");
+    assert!(err.contains("Usage: mdmd serve"), "got: {err}");
+}
+
+#[test]
+fn positional_file_form_still_resolves_to_the_viewer() {
+    let out = mdmd(&["--scroll-step", "3", "definitely-missing.md"]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr(&out);
+    assert!(
+        err.contains("file not found: definitely-missing.md"),
+        "got: {err}"
+    );
+}
+
+#[test]
+fn help_lists_file_positional_and_subcommands() {
+    let out = mdmd(&["--help"]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let help = stdout(&out);
+    assert!(help.contains("[FILE]"), "got: {help}");
+    for sub in ["view", "serve", "html", "completions"] {
+        assert!(help.contains(sub), "help missing {sub}");
+    }
+}
+
+#[test]
+fn preview_outside_tmux_and_kitty_points_to_view_watch() {
+    let out = Command::new(bin_path())
+        .args(["preview", "README.md"])
+        .env_remove("TMUX")
+        .env_remove("KITTY_WINDOW_ID")
+        .output()
+        .expect("run mdmd");
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr(&out);
+    assert!(err.contains("mdmd view --watch"), "got: {err}");
+}
+
+#[test]
+fn info_and_verbose_version_print_build_metadata_json() {
+    let info = mdmd(&["info"]);
+    assert!(info.status.success(), "{}", stderr(&info));
+    let json: serde_json::Value = serde_json::from_str(&stdout(&info)).expect("info is JSON");
+    assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+    for key in ["git_commit", "build_date", "features", "asset_hashes"] {
+        assert!(json.get(key).is_some(), "info missing {key}: {json}");
+    }
+    assert!(json["asset_hashes"]["mdmd.css"].is_string());
+
+    let verbose = mdmd(&["--version", "--verbose"]);
+    assert!(verbose.status.success(), "{}", stderr(&verbose));
+    assert_eq!(stdout(&verbose), stdout(&info));
+
+    let plain = mdmd(&["--version"]);
+    assert_eq!(
+        stdout(&plain).trim(),
+        format!("mdmd {}", env!("CARGO_PKG_VERSION"))
+    );
+}
+
+#[test]
+fn config_show_resolved_reports_each_setting_source() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let config = dir.path().join("config.toml");
+    std::fs::write(&config, "[serve]\nport = 4000\n").unwrap();
+
+    let out = Command::new(bin_path())
+        .args(["config", "show", "--resolved"])
+        .env("MDMD_CONFIG", &config)
+        .env("MDMD_SERVE_BIND", "127.0.0.1")
+        .env_remove("MDMD_SERVE_PORT")
+        .output()
+        .expect("run mdmd");
+    assert!(out.status.success(), "{}", stderr(&out));
+    let shown = stdout(&out);
+    let line = |key: &str| {
+        shown
+            .lines()
+            .find(|l| l.starts_with(&format!("{key} = ")))
+            .unwrap_or_else(|| panic!("{key} missing from: {shown}"))
+            .to_owned()
+    };
+    assert!(line("serve.port").starts_with("serve.port = 4000 "));
+    assert!(line("serve.port").ends_with(&format!("# config file {}", config.display())));
+    assert!(line("serve.bind").ends_with("# env MDMD_SERVE_BIND"));
+    assert!(line("view.scroll_step").ends_with("# default"));
+
+    let out = Command::new(bin_path())
+        .args(["config", "show"])
+        .env("MDMD_CONFIG", &config)
+        .output()
+        .expect("run mdmd");
+    assert!(stdout(&out).contains("port = 4000"));
+}
+
+#[test]
+fn no_file_shows_the_project_start_page() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let project = dir.path().join("proj");
+    std::fs::create_dir_all(project.join(".git")).unwrap();
+    std::fs::create_dir_all(project.join("docs")).unwrap();
+    std::fs::write(project.join("README.md"), "# Project\n").unwrap();
+    std::fs::write(project.join("docs/index.md"), "# Docs\n").unwrap();
+    let out = Command::new(bin_path())
+        .current_dir(project.join("docs"))
+        .env("XDG_STATE_HOME", dir.path().join("state"))
+        .output()
+        .expect("run mdmd");
+    assert!(out.status.success(), "{}", stderr(&out));
+    let page = stdout(&out);
+    assert!(page.contains("proj"), "got: {page}");
+    assert!(page.contains("README.md"), "got: {page}");
+    assert!(page.contains("docs/index.md"), "got: {page}");
+    assert!(page.contains("Recent files"), "got: {page}");
+
+    // Outside any project FILE is still required.
+    let outside = dir.path().join("elsewhere");
+    std::fs::create_dir_all(&outside).unwrap();
+    let out = Command::new(bin_path())
+        .current_dir(&outside)
+        .output()
+        .expect("run mdmd");
+    assert_eq!(out.status.code(), Some(2));
+    let err = stderr(&out);
+    assert!(err.contains("not in a project"), "got: {err}");
+}
+
+#[test]
+fn view_session_requires_a_file_until_saved() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let out = Command::new(bin_path())
+        .args(["view", "--session", "reading"])
+        .env("XDG_STATE_HOME", dir.path())
+        .output()
+        .expect("run mdmd");
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr(&out);
+    assert!(
+        err.contains("no saved session named 'reading'; pass a FILE"),
+        "got: {err}"
+    );
+
+    let out = mdmd(&["view", "--session", "../escape", "README.md"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr(&out).contains("invalid session name"));
+
+    let out = mdmd(&["view"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr(&out).contains("<FILE>"));
+}
+
+#[test]
+fn view_checks_every_queued_file_up_front() {
+    let out = mdmd(&["view", "README.md", "definitely-missing.md"]);
+    assert_eq!(out.status.code(), Some(1));
+    let err = stderr(&out);
+    assert!(
+        err.contains("file not found: definitely-missing.md"),
+        "got: {err}"
+    );
+
+    let out = mdmd(&["view", "README.md", "-"]);
+    assert_eq!(out.status.code(), Some(2));
+    assert!(stderr(&out).contains("cannot be combined with other files"));
+}
+
+#[test]
+fn render_writes_plain_text_and_html_to_stdout() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("doc.md");
+    std::fs::write(&file, "# Title\n\nSee **this** [link](other.md).\n").unwrap();
+    let file = file.to_str().unwrap();
+
+    // Piped stdout defaults to plain text, without escape sequences.
+    let out = mdmd(&["render", file]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let text = stdout(&out);
+    assert!(text.contains("See this link."), "got: {text:?}");
+    assert!(!text.contains('\x1b'), "got: {text:?}");
+
+    let out = Command::new(bin_path())
+        .args(["render", "--format", "ansi", file])
+        .env("TERM", "xterm-256color")
+        .output()
+        .expect("run mdmd");
+    let text = stdout(&out);
+    assert!(text.contains("\x1b[1m"), "got: {text:?}");
+    assert!(text.contains("\x1b]8;;file://"), "got: {text:?}");
+
+    let out = mdmd(&["render", "--format", "html", file]);
+    assert!(
+        stdout(&out).contains(r#"<h1 id="title">Title</h1>"#),
+        "got: {}",
+        stdout(&out)
+    );
+}
+
+#[test]
+fn outline_prints_the_heading_tree() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("doc.md");
+    std::fs::write(&file, "# Guide\n\n## Install\n\nText.\n").unwrap();
+    let file = file.to_str().unwrap();
+
+    let out = mdmd(&["outline", file]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(
+        stdout(&out),
+        "- [Guide](#guide) <!-- line 1 -->\n  - [Install](#install) <!-- line 3 -->\n"
+    );
+
+    let out = mdmd(&["outline", "--format", "json", file]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let json: serde_json::Value = serde_json::from_str(&stdout(&out)).expect("outline JSON");
+    assert_eq!(json[0]["children"][0]["anchor"], "install");
+    assert_eq!(json[0]["children"][0]["line"], 3);
+}
+
+#[test]
+fn slug_style_changes_heading_anchors() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("doc.md");
+    std::fs::write(&file, "## C++ & Rust\n\n[Jump](#c--rust)\n").unwrap();
+    let file = file.to_str().unwrap();
+
+    let out = mdmd(&["render", "--format", "html", "--slug-style", "github", file]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert!(stdout(&out).contains("id=\"c--rust\""), "{}", stdout(&out));
+
+    let out = mdmd(&["outline", "--slug-style", "gitlab", file]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    assert_eq!(stdout(&out), "- [C++ & Rust](#c-rust) <!-- line 1 -->\n");
+
+    let out = mdmd(&["outline", "--slug-style", "pandoc", file]);
+    assert!(!out.status.success());
+}
+
+#[test]
+fn viewer_without_a_terminal_writes_plain_text() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("doc.md");
+    std::fs::write(&file, "# Title\n\nSome **bold** text.\n").unwrap();
+    let file = file.to_str().unwrap();
+
+    for args in [vec![file], vec!["view", file]] {
+        let out = Command::new(bin_path())
+            .args(&args)
+            .env("TERM", "dumb")
+            .env("PAGER", "false")
+            .output()
+            .expect("run mdmd");
+        assert!(out.status.success(), "{args:?}: {}", stderr(&out));
+        let text = stdout(&out);
+        assert!(text.contains("Some bold text."), "got: {text:?}");
+        assert!(!text.contains('\x1b'), "got: {text:?}");
+    }
+}
+
+#[test]
+fn view_reads_markdown_from_stdin() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let mut child = Command::new(bin_path())
+        .args(["view", "--stdin-title", "git log", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run mdmd");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"# Generated\n\nFrom a **pipe**.\n")
+        .unwrap();
+    let out = child.wait_with_output().expect("wait for mdmd");
+    assert!(out.status.success(), "{}", stderr(&out));
+    let text = stdout(&out);
+    assert!(text.contains("From a pipe."), "got: {text:?}");
+}
+
+#[test]
+fn force_views_files_without_a_markdown_extension() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("CHANGELOG");
+    std::fs::write(&file, "# Changes\n\n- *fixed* things\n").unwrap();
+    let file = file.to_str().unwrap();
+
+    let out = mdmd(&["view", file]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stderr(&out).contains("has no file extension"));
+
+    for args in [vec!["view", "--force", file], vec!["--force", file]] {
+        let out = mdmd(&args);
+        assert!(out.status.success(), "{args:?}: {}", stderr(&out));
+        assert!(stdout(&out).contains("fixed things"), "got: {}", stdout(&out));
+    }
+}
+
+#[test]
+fn render_decodes_non_utf8_markdown() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let latin1 = dir.path().join("latin1.md");
+    std::fs::write(&latin1, b"# Caf\xe9\n").unwrap();
+    let utf16 = dir.path().join("utf16.md");
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend("# \u{dc}bersicht\n".encode_utf16().flat_map(u16::to_le_bytes));
+    std::fs::write(&utf16, bytes).unwrap();
+
+    for (file, expected) in [(&latin1, "Caf\u{e9}"), (&utf16, "\u{dc}bersicht")] {
+        let out = mdmd(&["render", "--format", "plain", file.to_str().unwrap()]);
+        assert!(out.status.success(), "{}", stderr(&out));
+        assert!(stdout(&out).contains(expected), "got: {}", stdout(&out));
+    }
+}
+
+#[test]
+fn html_injects_head_tags_and_refuses_other_markup() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("doc.md");
+    std::fs::write(&file, "---\nscripts: /widget.js\n---\n# Title\n").unwrap();
+    let head = dir.path().join("head.html");
+    std::fs::write(&head, "<meta name=\"team\" content=\"docs\">\n").unwrap();
+    let output = dir.path().join("doc.html");
+    let (file, head, output) = (
+        file.to_str().unwrap(),
+        head.to_str().unwrap(),
+        output.to_str().unwrap(),
+    );
+
+    let out = mdmd(&["html", "--inject-head", head, "-o", output, file]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let page = std::fs::read_to_string(output).unwrap();
+    let injected = "<meta name=\"team\" content=\"docs\">\n</head>";
+    assert!(page.contains(injected), "{page}");
+    assert!(!page.contains("widget.js\" defer"), "{page}");
+
+    let out = mdmd(&[
+        "html",
+        "--allow-page-head",
+        "--inject-head",
+        head,
+        "-o",
+        output,
+        file,
+    ]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let page = std::fs::read_to_string(output).unwrap();
+    let script = "<script src=\"/widget.js\" defer></script>\n</head>";
+    assert!(page.contains(script), "{page}");
+
+    std::fs::write(head, "</head><body>hi").unwrap();
+    let out = mdmd(&["html", "--inject-head", head, "-o", output, file]);
+    assert_eq!(out.status.code(), Some(2));
+    let err = stderr(&out);
+    assert!(err.contains("Error: --inject-head:"), "{err}");
+    assert!(err.contains("</head> is not allowed"), "{err}");
+}
+
+#[test]
+fn html_fills_a_page_template() {
+    let dir = tempfile::tempdir().expect("tempdir");
+    let file = dir.path().join("doc.md");
+    std::fs::write(&file, "---\nowner: docs team\n---\n# Title\n\nText.\n").unwrap();
+    let template = dir.path().join("page.html");
+    std::fs::write(
+        &template,
+        "<html><head><title>{{ title }}</title>{{ head }}</head>\
+<body><p class=\"owner\">{{ frontmatter.owner }}</p>{{ body }}{{ scripts }}</body></html>\n",
+    )
+    .unwrap();
+    let output = dir.path().join("doc.html");
+    let (file, template, output) = (
+        file.to_str().unwrap(),
+        template.to_str().unwrap(),
+        output.to_str().unwrap(),
+    );
+
+    let out = mdmd(&["html", "--template", template, "-o", output, file]);
+    assert!(out.status.success(), "{}", stderr(&out));
+    let page = std::fs::read_to_string(output).unwrap();
+    let start = "<html><head><title>Title</title>";
+    assert!(page.starts_with(start), "{page}");
+    assert!(page.contains("<p class=\"owner\">docs team</p>"), "{page}");
+    assert!(page.contains("<p>Text.</p>"), "{page}");
+
+    std::fs::write(template, "{% for x in %}").unwrap();
+    let out = mdmd(&["html", "--template", template, "-o", output, file]);
+    assert_eq!(out.status.code(), Some(2));
+    let err = stderr(&out);
+    assert!(err.contains("Error: --template:"), "{err}");
+}
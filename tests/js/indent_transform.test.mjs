@@ -47,7 +47,11 @@ function extractIndentIIFE(src) {
             'check that the marker string matches the source.'
         );
     }
-    return src.slice(idx);
+    // Stop at the end of this IIFE so later IIFEs (which need browser APIs
+    // the mock DOM does not provide) are not evaluated.
+    const END = '\n}());\n';
+    const end = src.indexOf(END, idx);
+    return end === -1 ? src.slice(idx) : src.slice(idx, end + END.length);
 }
 
 const INDENT_SRC = extractIndentIIFE(MDMD_SRC);
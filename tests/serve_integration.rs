@@ -530,6 +530,84 @@ fn assert_body_order(resp: &ResponseSnapshot, earlier: &str, later: &str, label:
     assert!(earlier_pos < later_pos, "{label}\n{}", resp.context());
 }
 
+/// Structural accessibility checks shared by every HTML page serve emits:
+/// a document language, exactly one `<main>` landmark, labelled `<nav>`
+/// landmarks, a skip link whose target exists, accessible names on buttons,
+/// and `alt` text on images.
+fn assert_accessible(resp: &ResponseSnapshot) {
+    let body = resp.body_text();
+    let tags = |name: &str| -> Vec<String> {
+        body.match_indices(&format!("<{name}"))
+            .filter(|(i, _)| {
+                let next = body[i + name.len() + 1..].chars().next();
+                matches!(next, Some(' ' | '>'))
+            })
+            .map(|(i, _)| body[i..i + body[i..].find('>').unwrap_or(0) + 1].to_owned())
+            .collect()
+    };
+
+    assert!(
+        body.contains("<html lang=\""),
+        "missing <html lang>\n{}",
+        resp.context()
+    );
+    assert_eq!(
+        tags("main").len(),
+        1,
+        "expected one <main>\n{}",
+        resp.context()
+    );
+    for nav in tags("nav") {
+        assert!(
+            nav.contains("aria-label=\"") || nav.contains("aria-labelledby=\""),
+            "unlabelled {nav}\n{}",
+            resp.context()
+        );
+    }
+    for link in tags("a").iter().filter(|a| a.contains("skip-link")) {
+        let target = link
+            .split("href=\"#")
+            .nth(1)
+            .and_then(|rest| rest.split('"').next())
+            .expect("skip link has a fragment href");
+        assert!(
+            body.contains(&format!("id=\"{target}\"")),
+            "skip link target #{target} missing\n{}",
+            resp.context()
+        );
+    }
+    for (i, _) in body.match_indices("<button") {
+        let end = i + body[i..].find("</button>").expect("closed <button>");
+        let button = &body[i..end];
+        let open_tag = &button[..button.find('>').map_or(button.len(), |e| e + 1)];
+        // Visible text only: icon-only buttons (inline SVG) need aria-label.
+        let mut in_tag = false;
+        let text: String = button[open_tag.len()..]
+            .chars()
+            .filter(|&ch| {
+                match ch {
+                    '<' => in_tag = true,
+                    '>' => in_tag = false,
+                    _ => return !in_tag,
+                }
+                false
+            })
+            .collect();
+        assert!(
+            open_tag.contains("aria-label=\"") || !text.trim().is_empty(),
+            "button without accessible name: {button}\n{}",
+            resp.context()
+        );
+    }
+    for img in tags("img") {
+        assert!(
+            img.contains("alt="),
+            "image without alt: {img}\n{}",
+            resp.context()
+        );
+    }
+}
+
 fn wait_with_timeout(child: &mut Child, timeout: Duration) {
     let start = std::time::Instant::now();
     loop {
@@ -602,7 +680,7 @@ fn test_serve_toc_present() {
     assert_status(&resp, 200);
     let body = resp.body_text();
     assert!(
-        body.contains("<nav class=\"toc-sidebar\">") && body.contains("href=\"#home\""),
+        body.contains("<nav class=\"toc-sidebar\"") && body.contains("href=\"#home\""),
         "TOC not present\n{}",
         resp.context()
     );
@@ -754,6 +832,163 @@ fn test_serve_symlink_escape_denied() {
     let _ = fs::remove_file(outside);
 }
 
+#[cfg(unix)]
+#[test]
+fn test_serve_follow_symlinks() {
+    use std::os::unix::fs::symlink;
+
+    let fixture = Fixture::new(FixtureOptions::default());
+    let outside = tempfile::tempdir().expect("create tempdir");
+    fs::write(
+        outside.path().join("notes.md"),
+        "# Shared notes\n\n- [ ] file it\n",
+    )
+    .expect("write outside file");
+    symlink(outside.path(), fixture.root.join("shared")).expect("create symlink");
+
+    let server = ServerHandle::new_with_env(
+        "test_serve_follow_symlinks",
+        &fixture,
+        &["--follow-symlinks"],
+        &[],
+        &[],
+    );
+    let resp = fetch(&client(), &server.url("/shared/notes.md"));
+    assert_status(&resp, 200);
+    assert_body_contains(&resp, "Shared notes", "followed symlink is rendered");
+    let resp = fetch(&client(), &server.url("/"));
+    assert_body_contains(&resp, "shared", "followed symlink is listed");
+
+    // Edits stay inside the serve root even when reads may leave it.
+    let server = ServerHandle::new_with_env(
+        "test_serve_follow_symlinks_edit",
+        &fixture,
+        &["--follow-symlinks", "--allow-edit"],
+        &[],
+        &[],
+    );
+    let resp = post_json(
+        &client(),
+        &server.url("/_mdmd/v1/toggle-task?path=shared/notes.md"),
+        r#"{"line": 3, "checked": true}"#,
+    );
+    assert_status(&resp, 404);
+    let notes = fs::read_to_string(outside.path().join("notes.md")).expect("read notes");
+    assert!(notes.contains("- [ ] file it"), "{notes}");
+
+    let server = ServerHandle::new_with_env(
+        "test_serve_no_follow_symlinks",
+        &fixture,
+        &["--no-follow-symlinks"],
+        &[],
+        &[],
+    );
+    assert_status(&fetch(&client(), &server.url("/shared/notes.md")), 404);
+    let resp = fetch(&client(), &server.url("/"));
+    assert_body_not_contains(&resp, "shared", "refused symlink is not listed");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_serve_allow_extra_directories() {
+    use std::os::unix::fs::symlink;
+
+    let fixture = Fixture::new(FixtureOptions::default());
+    let images = tempfile::tempdir().expect("create tempdir");
+    fs::copy(
+        fixture.root.join("image.png"),
+        images.path().join("logo.png"),
+    )
+    .expect("copy image");
+    fs::write(images.path().join("todo.md"), "- [ ] file it\n").expect("write todo");
+    symlink(images.path(), fixture.root.join("images")).expect("create symlink");
+    let other = tempfile::tempdir().expect("create tempdir");
+    fs::write(other.path().join("secret.md"), "# secret\n").expect("write secret");
+    symlink(other.path(), fixture.root.join("other")).expect("create symlink");
+
+    let allow = images.path().to_str().unwrap();
+    let server = ServerHandle::new_with_env(
+        "test_serve_allow_extra_directories",
+        &fixture,
+        &["--allow", allow, "--allow-edit"],
+        &[],
+        &[],
+    );
+    let resp = fetch(&client(), &server.url("/images/logo.png"));
+    assert_status(&resp, 200);
+    assert_header_eq(&resp, "content-type", "image/png");
+    assert_status(&fetch(&client(), &server.url("/images/todo.md")), 200);
+    assert_status(&fetch(&client(), &server.url("/other/secret.md")), 404);
+
+    // Allowed directories are served read-only.
+    let resp = post_json(
+        &client(),
+        &server.url("/_mdmd/v1/toggle-task?path=images/todo.md"),
+        r#"{"line": 1, "checked": true}"#,
+    );
+    assert_status(&resp, 404);
+}
+
+#[test]
+fn test_serve_types_allowlist() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    fs::write(fixture.root.join("settings.env.bak"), "TOKEN=secret\n").expect("write env");
+    fs::write(fixture.root.join("LICENSE"), "MIT\n").expect("write license");
+
+    let server = ServerHandle::new_with_env(
+        "test_serve_types_allowlist",
+        &fixture,
+        &["--serve-types", "md,PNG"],
+        &[],
+        &[],
+    );
+    let c = client();
+    assert_status(&fetch(&c, &server.url("/guide.md")), 200);
+    assert_status(&fetch(&c, &server.url("/image.png")), 200);
+    assert_status(&fetch(&c, &server.url("/settings.env.bak")), 404);
+    assert_status(&fetch(&c, &server.url("/LICENSE")), 404);
+    let index = fetch(&c, &server.url("/"));
+    assert_body_contains(&index, "image.png", "served type is listed");
+    assert_body_not_contains(&index, "settings.env.bak", "unserved type is not listed");
+    let missing = fetch(&c, &server.url("/settings.env"));
+    assert_body_not_contains(
+        &missing,
+        "settings.env.bak",
+        "no suggestion of unserved files",
+    );
+}
+
+#[test]
+fn test_serve_types_restrict_api_routes() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new_with_env(
+        "test_serve_types_restrict_api_routes",
+        &fixture,
+        &["--serve-types", "png"],
+        &[],
+        &[],
+    );
+    let c = client();
+    assert_status(&fetch(&c, &server.url("/_mdmd/v1/doc?path=guide.md")), 404);
+    assert_status(
+        &fetch(&c, &server.url("/_mdmd/v1/freshness?path=guide.md")),
+        404,
+    );
+    let search = fetch(&c, &server.url("/_mdmd/search?q=Guide+content"));
+    let json: serde_json::Value = serde_json::from_str(&search.body_text()).expect("search JSON");
+    assert!(
+        json["results"].as_array().is_some_and(|r| r.is_empty()),
+        "unserved files are not searched\n{}",
+        search.context()
+    );
+    let combined = fetch(&c, &server.url("/_mdmd/combined"));
+    assert_body_not_contains(
+        &combined,
+        "Guide content",
+        "unserved files are not combined",
+    );
+}
+
 #[test]
 fn test_serve_extensionless_resolves() {
     let fixture = Fixture::new(FixtureOptions::default());
@@ -809,6 +1044,43 @@ fn test_serve_static_asset_image() {
     assert_header_eq(&resp, "content-type", "image/png");
 }
 
+#[test]
+fn test_serve_decodes_latin1_with_encoding_notice() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    fs::write(fixture.root.join("latin1.md"), b"# Caf\xe9\n\nNa\xefve.\n")
+        .expect("write latin1.md");
+    let server = ServerHandle::new("test_serve_decodes_latin1_with_encoding_notice", &fixture);
+
+    let resp = fetch(&client(), &server.url("/latin1.md"));
+    assert_status(&resp, 200);
+    assert_body_contains(&resp, "Caf\u{e9}", "decoded heading");
+    assert_body_contains(&resp, "Na\u{ef}ve.", "decoded paragraph");
+    assert_body_contains(&resp, "class=\"encoding-notice\"", "encoding notice");
+    assert_body_contains(&resp, "decoded as windows-1252", "encoding name");
+
+    let utf8 = fetch(&client(), &server.url("/guide.md"));
+    assert_body_not_contains(&utf8, "class=\"encoding-notice\"", "no notice for UTF-8");
+}
+
+#[test]
+fn test_serve_binary_files_are_downloaded() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    fs::write(fixture.root.join("corrupt.md"), b"# Title\0\x01\x02").expect("write corrupt.md");
+    fs::write(fixture.root.join("archive.bin"), b"\0\x01\x02").expect("write archive.bin");
+    let server = ServerHandle::new("test_serve_binary_files_are_downloaded", &fixture);
+
+    for (path, name) in [("/corrupt.md", "corrupt.md"), ("/archive.bin", "archive.bin")] {
+        let resp = fetch(&client(), &server.url(path));
+        assert_status(&resp, 200);
+        assert_header_eq(&resp, "content-type", "application/octet-stream");
+        assert_header_eq(
+            &resp,
+            "content-disposition",
+            &format!("attachment; filename=\"{name}\""),
+        );
+    }
+}
+
 #[test]
 fn test_serve_nosniff_header() {
     let fixture = Fixture::new(FixtureOptions {
@@ -957,6 +1229,45 @@ fn test_serve_compression_br() {
     assert_header_eq(&resp, "content-encoding", "br");
 }
 
+#[test]
+fn test_serve_precompressed_assets() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new("test_serve_precompressed_assets", &fixture);
+
+    for name in ["mdmd.css", "mdmd.js"] {
+        let source =
+            fs::read_to_string(format!("{}/src/assets/{name}", env!("CARGO_MANIFEST_DIR")))
+                .unwrap();
+        let url = server.url(&format!("/assets/{name}"));
+        for encoding in ["br", "gzip"] {
+            let raw = fetch_with_headers(
+                &client_no_auto_decode(),
+                &url,
+                &[("accept-encoding", encoding)],
+            );
+            assert_status(&raw, 200);
+            assert_header_eq(&raw, "content-encoding", encoding);
+            assert!(raw.body.len() < source.len(), "{}", raw.context());
+
+            let decoded = fetch_with_headers(&client(), &url, &[("accept-encoding", encoding)]);
+            assert_eq!(decoded.body_text(), source, "{name} as {encoding}");
+        }
+
+        let plain = fetch_with_headers(
+            &client_no_auto_decode(),
+            &url,
+            &[("accept-encoding", "identity")],
+        );
+        assert_status(&plain, 200);
+        assert!(
+            plain.header("content-encoding").is_none(),
+            "{}",
+            plain.context()
+        );
+        assert_eq!(plain.body_text(), source);
+    }
+}
+
 #[test]
 fn test_serve_file_too_large() {
     let fixture = Fixture::new(FixtureOptions {
@@ -1077,122 +1388,466 @@ fn test_serve_assets_js() {
     assert_header_contains(&resp, "content-type", "text/javascript");
 }
 
+/// Pages link content-hashed asset URLs, which are cached for good; the
+/// plain URLs are revalidated.  Neither relies on a Last-Modified date.
 #[test]
-fn test_serve_frontmatter_rendering_and_ordering() {
-    eprintln!("scenario: serve frontmatter rendering and ordering");
-    let fixture = Fixture::frontmatter();
-    let server = ServerHandle::new("test_serve_frontmatter_rendering_and_ordering", &fixture);
+fn test_serve_hashed_asset_urls() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new("test_serve_hashed_asset_urls", &fixture);
+    let c = client();
 
-    let resp = fetch(&client(), &server.url("/README.md"));
-    assert_status(&resp, 200);
-    assert_header_contains(&resp, "content-type", "text/html");
+    let page = fetch(&c, &server.url("/guide")).body_text();
+    for (prefix, ext) in [("href=\"", "css"), ("src=\"", "js")] {
+        let start = page.find(&format!("{prefix}/assets/mdmd.")).expect(ext) + prefix.len();
+        let url = &page[start..start + page[start..].find('"').unwrap()];
+        let hash = url
+            .strip_prefix("/assets/mdmd.")
+            .and_then(|rest| rest.strip_suffix(&format!(".{ext}")))
+            .unwrap_or_else(|| panic!("unexpected asset URL {url}"));
+        assert_eq!(hash.len(), 16, "{url}");
+
+        let hashed = fetch(&c, &server.url(url));
+        assert_status(&hashed, 200);
+        assert_header_contains(&hashed, "cache-control", "immutable");
+        assert!(
+            hashed.header("last-modified").is_none(),
+            "{}",
+            hashed.context()
+        );
+        let etag = hashed.header("etag").expect("hashed asset ETag");
+        assert_eq!(etag, format!("\"{hash}\""));
 
-    eprintln!("assertion: frontmatter panel and title override are present");
-    assert_body_contains(
-        &resp,
-        "<details class=\"frontmatter-panel\" aria-label=\"Document metadata\">",
-        "frontmatter panel missing",
-    );
-    assert_body_contains(
-        &resp,
-        "<title>Browser Title · mdmd serve</title>",
-        "frontmatter title did not override browser title",
-    );
-    assert_body_contains(
-        &resp,
-        "<h1 id=\"article-heading\">Article Heading</h1>",
-        "markdown heading must remain visible in article body",
-    );
+        let plain = fetch(&c, &server.url(&format!("/assets/mdmd.{ext}")));
+        assert_status(&plain, 200);
+        assert_header_eq(&plain, "cache-control", "no-cache");
+        assert_eq!(plain.body, hashed.body);
 
-    eprintln!("assertion: raw yaml is stripped while structured values stay visible");
-    assert_body_not_contains(
-        &resp,
-        "title: Browser Title",
-        "raw title yaml leaked into HTML mode",
-    );
-    assert_body_not_contains(
-        &resp,
-        "summary: summary text",
-        "raw summary yaml leaked into HTML mode",
-    );
-    assert_body_contains(
-        &resp,
-        "<span class=\"meta-tag\">alpha</span>",
-        "sequence values must render as pills",
-    );
-    assert_body_contains(
-        &resp,
-        "<span class=\"meta-tag\">beta</span>",
-        "all scalar sequence values must render as pills",
-    );
-    assert_body_contains(
-        &resp,
-        "<span class=\"val-null\">null</span>",
-        "null values must render with null styling",
-    );
-    assert_body_contains(&resp, "<dt>owner</dt>", "nested mapping field missing");
-    assert_body_contains(&resp, "team docs", "nested mapping value missing");
+        let revalidated = fetch_with_headers(&c, &server.url(url), &[("if-none-match", &etag)]);
+        assert_status(&revalidated, 304);
+    }
+}
 
-    eprintln!("assertion: hostile keys and values are escaped");
-    assert_body_contains(
-        &resp,
-        "&lt;script&gt;alert(1)&lt;/script&gt;",
-        "hostile frontmatter key must be escaped",
-    );
-    assert_body_contains(
-        &resp,
-        "&quot;quoted&quot; &amp; &lt;tag&gt;",
-        "hostile frontmatter value must be escaped",
+/// `--dev` serves the stylesheet and script from the source tree, uncached.
+#[test]
+fn test_serve_dev_assets_from_source_tree() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new_with_env(
+        "test_serve_dev_assets_from_source_tree",
+        &fixture,
+        &["--dev"],
+        &[],
+        &[],
     );
 
-    eprintln!("assertion: panel precedes article body and backlinks stay last");
-    assert_body_contains(
-        &resp,
-        "<section class=\"backlinks-panel\" aria-label=\"Backlinks\">",
-        "backlinks panel missing",
-    );
-    assert_body_order(
-        &resp,
-        "<details class=\"frontmatter-panel\"",
-        "<h1 id=\"article-heading\">",
-        "frontmatter panel must appear before the article heading",
-    );
-    assert_body_order(
-        &resp,
-        "<h1 id=\"article-heading\">",
-        "<section class=\"backlinks-panel\"",
-        "backlinks panel must appear after the article body",
-    );
+    for name in ["mdmd.css", "mdmd.js"] {
+        let resp = fetch(&client(), &server.url(&format!("/assets/{name}")));
+        assert_status(&resp, 200);
+        assert_header_eq(&resp, "cache-control", "no-cache");
+        assert!(resp.header("etag").is_none(), "{}", resp.context());
+        let source =
+            fs::read_to_string(format!("{}/src/assets/{name}", env!("CARGO_MANIFEST_DIR")))
+                .unwrap();
+        assert_eq!(
+            resp.body_text(),
+            source,
+            "{name} differs from the source tree"
+        );
+    }
 }
 
 #[test]
-fn test_serve_frontmatter_fallbacks_and_plain_markdown() {
-    eprintln!("scenario: serve frontmatter fallbacks");
-    let fixture = Fixture::frontmatter();
-    let server = ServerHandle::new(
-        "test_serve_frontmatter_fallbacks_and_plain_markdown",
+fn test_serve_cache_control_policy() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new_with_env(
+        "test_serve_cache_control_policy",
         &fixture,
+        &["--page-max-age", "30", "--asset-max-age", "86400"],
+        &[],
+        &[],
     );
+    let c = client();
 
-    let cases = [
-        (
-            "empty frontmatter",
-            "/empty.md",
-            "<h1 id=\"empty-heading\">Empty Heading</h1>",
-            None,
-            None,
-            true,
-        ),
-        (
-            "malformed yaml",
-            "/malformed.md",
-            "title: [oops",
-            Some("---"),
-            Some("Malformed Heading"),
-            false,
-        ),
-        (
-            "unterminated block",
+    let resp = fetch(&c, &server.url("/guide"));
+    assert_status(&resp, 200);
+    assert_header_eq(&resp, "cache-control", "max-age=30, must-revalidate");
+
+    let resp = fetch(&c, &server.url("/image.png"));
+    assert_status(&resp, 200);
+    assert_header_eq(&resp, "cache-control", "max-age=86400");
+
+    let resp = fetch(&c, &server.url("/_mdmd/v1/tree"));
+    assert_status(&resp, 200);
+    assert_header_eq(&resp, "cache-control", "no-store");
+
+    let resp = fetch(&c, &server.url("/no-such-page"));
+    assert_status(&resp, 404);
+    assert_header_eq(&resp, "cache-control", "no-store");
+}
+
+#[test]
+fn test_serve_request_limits() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Fixture::new(FixtureOptions::default());
+    // A filter that stalls on guide.md only, so readiness probes of `/` pass.
+    let slow = fixture.root.join("slow-filter.sh");
+    fs::write(
+        &slow,
+        "#!/bin/sh\ncase \"$MDMD_FILE\" in *guide.md) sleep 3 ;; esac\ncat\n",
+    )
+    .unwrap();
+    fs::set_permissions(&slow, fs::Permissions::from_mode(0o755)).unwrap();
+    let filter = format!("markdown={}", slow.display());
+    let server = ServerHandle::new_with_env(
+        "test_serve_request_limits",
+        &fixture,
+        &[
+            "--filter",
+            &filter,
+            "--request-timeout",
+            "1",
+            "--max-in-flight",
+            "1",
+            "--max-body-bytes",
+            "16",
+        ],
+        &[],
+        &[],
+    );
+
+    // A page whose filter outlives the timeout is cut off with 503.
+    let slow_url = server.url("/guide");
+    let slow = thread::spawn(move || {
+        let started = std::time::Instant::now();
+        (fetch(&client(), &slow_url), started.elapsed())
+    });
+
+    // While it holds the only in-flight slot, other requests are refused.
+    thread::sleep(Duration::from_millis(300));
+    let busy = fetch(&client(), &server.url("/image.png"));
+    assert_status(&busy, 503);
+    assert_header_eq(&busy, "retry-after", "1");
+
+    let (resp, elapsed) = slow.join().unwrap();
+    assert_status(&resp, 503);
+    assert!(elapsed < Duration::from_millis(2500), "took {elapsed:?}");
+
+    let resp = post_json(
+        &client(),
+        &server.url("/_mdmd/v1/toggle-task?path=guide.md"),
+        &"x".repeat(64),
+    );
+    assert_status(&resp, 413);
+}
+
+#[test]
+fn test_serve_errors_follow_accept_header() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new("test_serve_errors_follow_accept_header", &fixture);
+    let c = client();
+
+    // A missing page is the rich HTML 404 by default, JSON or text on request.
+    let resp = fetch(&c, &server.url("/nope.md"));
+    assert_status(&resp, 404);
+    assert_header_contains(&resp, "content-type", "text/html");
+    let resp = fetch_with_headers(
+        &c,
+        &server.url("/nope.md"),
+        &[("accept", "application/json")],
+    );
+    assert_status(&resp, 404);
+    assert_header_eq(&resp, "content-type", "application/json");
+    assert_body_contains(&resp, "\"path\":\"/nope.md\"", "JSON 404 names the path");
+    let resp = fetch_with_headers(&c, &server.url("/nope.md"), &[("accept", "text/plain")]);
+    assert_header_contains(&resp, "content-type", "text/plain");
+    assert_body_contains(&resp, "Not Found: /nope.md", "text 404 names the path");
+
+    // A denial stays terse in every format.
+    let resp = fetch_with_headers(
+        &c,
+        &server.url("/secret%00.md"),
+        &[("accept", "text/html")],
+    );
+    assert_status(&resp, 404);
+    assert_header_contains(&resp, "content-type", "text/html");
+    assert_body_not_contains(&resp, "secret", "denial must not echo the path");
+
+    // API errors are JSON by default and HTML for a browser.
+    let resp = fetch(&c, &server.url("/_mdmd/v1/doc?path=nope.md"));
+    assert_status(&resp, 404);
+    assert_header_eq(&resp, "content-type", "application/json");
+    assert_body_contains(&resp, "{\"error\":\"not found\"}", "API 404 body");
+    let resp = fetch_with_headers(
+        &c,
+        &server.url("/_mdmd/v1/doc?path=nope.md"),
+        &[("accept", "text/html,*/*;q=0.8")],
+    );
+    assert_header_contains(&resp, "content-type", "text/html");
+    assert_body_contains(&resp, "<h1>404 Not Found</h1>", "HTML API 404");
+
+    // Errors from outside the handlers are rendered the same way.
+    let resp = fetch(&c, &server.url("/_mdmd/v1/toggle-task"));
+    assert_status(&resp, 405);
+    assert_header_eq(&resp, "content-type", "application/json");
+    assert_body_contains(&resp, "{\"error\":\"method not allowed\"}", "405 body");
+}
+
+#[test]
+fn test_serve_json_errors_carry_path_and_suggestions() {
+    let fixture = Fixture::new(FixtureOptions {
+        include_large_file: true,
+        ..Default::default()
+    });
+    let server = ServerHandle::new(
+        "test_serve_json_errors_carry_path_and_suggestions",
+        &fixture,
+    );
+    let json = [("accept", "application/json")];
+
+    let resp = fetch_with_headers(&client(), &server.url("/subdir/nope.md"), &json);
+    assert_status(&resp, 404);
+    let body: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+    assert_eq!(body["error"], "not found");
+    assert_eq!(body["path"], "/subdir/nope.md");
+    let suggestions: Vec<&str> = body["suggestions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter_map(|s| s.as_str())
+        .collect();
+    assert_eq!(suggestions.first(), Some(&"/subdir/"), "{suggestions:?}");
+    assert!(suggestions.contains(&"/subdir/index.md"), "{suggestions:?}");
+
+    let resp = fetch_with_headers(&client(), &server.url("/oversized.md"), &json);
+    assert_status(&resp, 413);
+    let body: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+    assert_eq!(body["error"], "content too large");
+    assert_eq!(body["path"], "/oversized.md");
+    assert!(body["size"].as_u64() > body["limit"].as_u64(), "{body}");
+}
+
+#[test]
+fn test_serve_404_did_you_mean() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new("test_serve_404_did_you_mean", &fixture);
+
+    let resp = fetch(&client(), &server.url("/gide.md"));
+    assert_status(&resp, 404);
+    assert_body_contains(
+        &resp,
+        "<h2 id=\"did-you-mean\">Did you mean</h2><ul><li><a href=\"/guide.md\">guide.md</a></li></ul>",
+        "typo of guide.md should be suggested",
+    );
+
+    let resp = fetch_with_headers(
+        &client(),
+        &server.url("/subdr/index.md"),
+        &[("accept", "application/json")],
+    );
+    let body: serde_json::Value = serde_json::from_slice(&resp.body).unwrap();
+    assert_eq!(body["suggestions"][0], "/subdir/", "{body}");
+
+    let resp = fetch(&client(), &server.url("/unrelated-name.md"));
+    assert_status(&resp, 404);
+    assert_body_not_contains(&resp, "Did you mean", "no near miss, no suggestions");
+}
+
+#[test]
+fn test_serve_case_insensitive_resolution() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let strict = ServerHandle::new("test_serve_case_insensitive_strict", &fixture);
+    assert_status(&fetch(&client(), &strict.url("/GUIDE.md")), 404);
+
+    let server = ServerHandle::new_with_env(
+        "test_serve_case_insensitive_resolution",
+        &fixture,
+        &["--case-insensitive"],
+        &[],
+        &[],
+    );
+    for path in ["/GUIDE.md", "/Guide", "/guide.md"] {
+        let resp = fetch(&client(), &server.url(path));
+        assert_status(&resp, 200);
+        assert_header_contains(&resp, "content-type", "text/html");
+    }
+    assert_status(&fetch(&client(), &server.url("/SUBDIR/index.md")), 200);
+    assert_status(&fetch(&client(), &server.url("/nope.md")), 404);
+}
+
+#[test]
+fn test_serve_directory_pages_redirect_to_trailing_slash() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new(
+        "test_serve_directory_pages_redirect_to_trailing_slash",
+        &fixture,
+    );
+    let no_follow = Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .expect("build reqwest client");
+
+    for (path, location) in [
+        ("/subdir", "/subdir/"),
+        ("/subdir?toc=0", "/subdir/?toc=0"),
+        ("/subdir/README.md", "/subdir/"),
+    ] {
+        let resp = fetch(&no_follow, &server.url(path));
+        assert_status(&resp, 301);
+        assert_header_eq(&resp, "location", location);
+    }
+    // README.md wins, so index.md is a page of its own; at the root, `/`
+    // is the directory listing, so README.md is too.
+    assert_status(&fetch(&no_follow, &server.url("/subdir/index.md")), 200);
+    assert_status(&fetch(&no_follow, &server.url("/README.md")), 200);
+
+    let resp = fetch(&no_follow, &server.url("/subdir/"));
+    assert_status(&resp, 200);
+    assert_body_contains(
+        &resp,
+        &format!(
+            "<link rel=\"canonical\" href=\"{}\">",
+            server.url("/subdir/")
+        ),
+        "directory page canonical link",
+    );
+    let resp = fetch(&no_follow, &server.url("/guide"));
+    assert_status(&resp, 200);
+    assert_body_contains(
+        &resp,
+        &format!(
+            "<link rel=\"canonical\" href=\"{}\">",
+            server.url("/guide.md")
+        ),
+        "extensionless page canonical link",
+    );
+
+    let no_readme = Fixture::new(FixtureOptions {
+        include_subdir_readme: false,
+        ..Default::default()
+    });
+    let server = ServerHandle::new("test_serve_index_redirects_to_directory", &no_readme);
+    let resp = fetch(&no_follow, &server.url("/subdir/index.md"));
+    assert_status(&resp, 301);
+    assert_header_eq(&resp, "location", "/subdir/");
+}
+
+#[test]
+fn test_serve_frontmatter_rendering_and_ordering() {
+    eprintln!("scenario: serve frontmatter rendering and ordering");
+    let fixture = Fixture::frontmatter();
+    let server = ServerHandle::new("test_serve_frontmatter_rendering_and_ordering", &fixture);
+
+    let resp = fetch(&client(), &server.url("/README.md"));
+    assert_status(&resp, 200);
+    assert_header_contains(&resp, "content-type", "text/html");
+
+    eprintln!("assertion: frontmatter panel and title override are present");
+    assert_body_contains(
+        &resp,
+        "<details class=\"frontmatter-panel\" aria-label=\"Document metadata\">",
+        "frontmatter panel missing",
+    );
+    assert_body_contains(
+        &resp,
+        "<title>Browser Title · mdmd serve</title>",
+        "frontmatter title did not override browser title",
+    );
+    assert_body_contains(
+        &resp,
+        "<h1 id=\"article-heading\">Article Heading</h1>",
+        "markdown heading must remain visible in article body",
+    );
+
+    eprintln!("assertion: raw yaml is stripped while structured values stay visible");
+    assert_body_not_contains(
+        &resp,
+        "title: Browser Title",
+        "raw title yaml leaked into HTML mode",
+    );
+    assert_body_not_contains(
+        &resp,
+        "summary: summary text",
+        "raw summary yaml leaked into HTML mode",
+    );
+    assert_body_contains(
+        &resp,
+        "<span class=\"meta-tag\">alpha</span>",
+        "sequence values must render as pills",
+    );
+    assert_body_contains(
+        &resp,
+        "<span class=\"meta-tag\">beta</span>",
+        "all scalar sequence values must render as pills",
+    );
+    assert_body_contains(
+        &resp,
+        "<span class=\"val-null\">null</span>",
+        "null values must render with null styling",
+    );
+    assert_body_contains(&resp, "<dt>owner</dt>", "nested mapping field missing");
+    assert_body_contains(&resp, "team docs", "nested mapping value missing");
+
+    eprintln!("assertion: hostile keys and values are escaped");
+    assert_body_contains(
+        &resp,
+        "&lt;script&gt;alert(1)&lt;/script&gt;",
+        "hostile frontmatter key must be escaped",
+    );
+    assert_body_contains(
+        &resp,
+        "&quot;quoted&quot; &amp; &lt;tag&gt;",
+        "hostile frontmatter value must be escaped",
+    );
+
+    eprintln!("assertion: panel precedes article body and backlinks stay last");
+    assert_body_contains(
+        &resp,
+        "<section class=\"backlinks-panel\" aria-label=\"Backlinks\">",
+        "backlinks panel missing",
+    );
+    assert_body_order(
+        &resp,
+        "<details class=\"frontmatter-panel\"",
+        "<h1 id=\"article-heading\">",
+        "frontmatter panel must appear before the article heading",
+    );
+    assert_body_order(
+        &resp,
+        "<h1 id=\"article-heading\">",
+        "<section class=\"backlinks-panel\"",
+        "backlinks panel must appear after the article body",
+    );
+}
+
+#[test]
+fn test_serve_frontmatter_fallbacks_and_plain_markdown() {
+    eprintln!("scenario: serve frontmatter fallbacks");
+    let fixture = Fixture::frontmatter();
+    let server = ServerHandle::new(
+        "test_serve_frontmatter_fallbacks_and_plain_markdown",
+        &fixture,
+    );
+
+    let cases = [
+        (
+            "empty frontmatter",
+            "/empty.md",
+            "<h1 id=\"empty-heading\">Empty Heading</h1>",
+            None,
+            None,
+            true,
+        ),
+        (
+            "malformed yaml",
+            "/malformed.md",
+            "title: [oops",
+            Some("---"),
+            Some("Malformed Heading"),
+            false,
+        ),
+        (
+            "unterminated block",
             "/unterminated.md",
             "title: Broken",
             Some("---"),
@@ -3035,27 +3690,113 @@ fn test_headless_ci_env_suppresses_browser_attempt() {
     );
 }
 
-/// Verify that a headed environment with `--verbose` logs `[browser] open failed:`
-/// when the opener command fails.
-///
-/// Simulates a headed environment by setting `DISPLAY=:99` and stripping
-/// `CI`/`GITHUB_ACTIONS`/`SSH_CONNECTION`/`SSH_TTY`.  Uses
-/// `MDMD_OPEN_CMD=__mdmd_no_such_open_cmd__` as a deterministic failing stub.
-///
-/// The test locks the "verbose failure path": when verbose is enabled and the
-/// headed-environment check passes, a spawn failure must produce a
-/// `[browser] open failed:` line on stderr.
+/// Verify that `--open-path` changes the URL handed to the browser-open
+/// command, here a `MDMD_OPEN_CMD` stub that saves its arguments, in a
+/// simulated headed Linux environment.
 #[cfg(unix)]
 #[test]
-fn test_headed_env_verbose_logs_browser_open_failure() {
+fn test_open_path_selects_opened_url() {
+    use std::os::unix::fs::PermissionsExt;
+
     let fixture = Fixture::new(FixtureOptions::default());
+    let opened = fixture.root.join("opened.txt");
+    let stub = fixture.root.join("open.sh");
+    fs::write(
+        &stub,
+        format!("#!/bin/sh\necho \"$@\" > '{}'\n", opened.display()),
+    )
+    .unwrap();
+    fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+
     let server = ServerHandle::new_with_env(
-        "test_headed_env_verbose_logs_browser_open_failure",
+        "test_open_path_selects_opened_url",
         &fixture,
-        &["--verbose"],
+        &["--open-path", "subdir/", "--open-browser", "firefox"],
         &[
-            // Simulate a headed environment (DISPLAY satisfies Linux headed check;
-            // harmless on macOS where DISPLAY is ignored for headed detection).
+            ("DISPLAY", ":99"),
+            ("MDMD_OPEN_CMD", stub.to_str().unwrap()),
+        ],
+        &["CI", "GITHUB_ACTIONS", "SSH_CONNECTION", "SSH_TTY"],
+    );
+    let _ = fetch(&client(), &server.url("/"));
+    // The open command is not waited on; give the stub time to run.
+    for _ in 0..250 {
+        if fs::read_to_string(&opened).is_ok_and(|a| a.ends_with('\n')) {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    let args = fs::read_to_string(&opened).expect("open stub ran");
+    assert_eq!(args.trim(), server.url("/subdir/"));
+}
+
+/// Verify that `--copy-url` pipes the printed URL to the clipboard command,
+/// here a `MDMD_COPY_CMD` stub that saves its stdin, and that a failing
+/// command only warns.
+#[cfg(unix)]
+#[test]
+fn test_copy_url_uses_clipboard_command() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let fixture = Fixture::new(FixtureOptions::default());
+    let copied = fixture.root.join("copied.txt");
+    let stub = fixture.root.join("copy.sh");
+    fs::write(&stub, format!("#!/bin/sh\ncat > '{}'\n", copied.display())).unwrap();
+    fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let server = ServerHandle::new_with_env(
+        "test_copy_url_uses_clipboard_command",
+        &fixture,
+        &["--copy-url", "--verbose"],
+        &[("MDMD_COPY_CMD", stub.to_str().unwrap())],
+        &[],
+    );
+    let _ = fetch(&client(), &server.url("/"));
+    let output = server.shutdown_with_sigint();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let url = fs::read_to_string(&copied).expect("clipboard stub ran");
+    assert!(url.ends_with("/README.md"), "{url}");
+    assert_eq!(stdout.lines().next(), Some(url.as_str()));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("[clipboard] copied"), "{stderr}");
+
+    let server = ServerHandle::new_with_env(
+        "test_copy_url_failure_warns",
+        &fixture,
+        &["--copy-url"],
+        &[("MDMD_COPY_CMD", "false")],
+        &[],
+    );
+    assert_status(&fetch(&client(), &server.url("/")), 200);
+    let output = server.shutdown_with_sigint();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Warning: --copy-url: could not copy the URL"),
+        "{stderr}"
+    );
+}
+
+/// Verify that a headed environment with `--verbose` logs `[browser] open failed:`
+/// when the opener command fails.
+///
+/// Simulates a headed environment by setting `DISPLAY=:99` and stripping
+/// `CI`/`GITHUB_ACTIONS`/`SSH_CONNECTION`/`SSH_TTY`.  Uses
+/// `MDMD_OPEN_CMD=__mdmd_no_such_open_cmd__` as a deterministic failing stub.
+///
+/// The test locks the "verbose failure path": when verbose is enabled and the
+/// headed-environment check passes, a spawn failure must produce a
+/// `[browser] open failed:` line on stderr.
+#[cfg(unix)]
+#[test]
+fn test_headed_env_verbose_logs_browser_open_failure() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new_with_env(
+        "test_headed_env_verbose_logs_browser_open_failure",
+        &fixture,
+        &["--verbose"],
+        &[
+            // Simulate a headed environment (DISPLAY satisfies Linux headed check;
+            // harmless on macOS where DISPLAY is ignored for headed detection).
             ("DISPLAY", ":99"),
             // Deterministic failing stub: spawn will fail, triggering the error path.
             ("MDMD_OPEN_CMD", "__mdmd_no_such_open_cmd__"),
@@ -3129,3 +3870,1161 @@ fn test_headed_env_no_verbose_suppresses_browser_failure_log() {
         "without --verbose, [browser] diagnostics must be suppressed even on open failure\nstderr:\n{stderr}"
     );
 }
+
+// ---------------------------------------------------------------------------
+// Latency budget warnings
+// ---------------------------------------------------------------------------
+
+/// With `--verbose` and a zero request budget, every rendered page logs a
+/// `[latency] WARN` line naming the exceeded budget and dominant phase.
+#[test]
+fn test_latency_budget_warning_logged_in_verbose_mode() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new_with_env(
+        "test_latency_budget_warning_logged_in_verbose_mode",
+        &fixture,
+        &["--verbose", "--request-budget-ms", "0"],
+        &[],
+        &[],
+    );
+
+    let resp = fetch(&client(), &server.url("/README.md"));
+    assert_status(&resp, 200);
+
+    let output = server.shutdown_with_sigint();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("[latency] WARN path=README.md mode=rendered exceeded=total"),
+        "zero request budget must log a latency warning\nstderr:\n{stderr}"
+    );
+    assert!(
+        stderr.contains("dominant="),
+        "latency warning must name the dominant phase\nstderr:\n{stderr}"
+    );
+}
+
+/// Without `--verbose`, budget overruns stay silent.
+#[test]
+fn test_latency_budget_warning_suppressed_without_verbose() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new_with_env(
+        "test_latency_budget_warning_suppressed_without_verbose",
+        &fixture,
+        &["--request-budget-ms", "0"],
+        &[],
+        &[],
+    );
+
+    let resp = fetch(&client(), &server.url("/README.md"));
+    assert_status(&resp, 200);
+
+    let output = server.shutdown_with_sigint();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        !stderr.contains("[latency]"),
+        "latency warnings must be verbose-only\nstderr:\n{stderr}"
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Site search endpoint
+// ---------------------------------------------------------------------------
+
+/// Pages marked `draft: true` are left out of the directory index, search,
+/// and other pages' backlinks, but still served by URL with a draft banner;
+/// `--drafts` lists them again.
+#[test]
+fn test_drafts_are_hidden_unless_drafts_flag() {
+    let fixture = make_freshness_fixture();
+    fs::write(
+        fixture.root.join("wip.md"),
+        "---\ndraft: true\n---\n# Wip\n\nSee [the fixture](fixture.md), zanzibar.\n",
+    )
+    .expect("write wip.md");
+    let c = client();
+
+    let server = ServerHandle::new("test_drafts_are_hidden_unless_drafts_flag", &fixture);
+    let index = fetch(&c, &server.url("/"));
+    assert_body_not_contains(&index, "wip.md", "draft left out of the index");
+    let search = fetch(&c, &server.url("/_mdmd/search?q=zanzibar"));
+    assert_body_not_contains(&search, "/wip.md", "draft left out of search");
+    let target = fetch(&c, &server.url("/fixture.md"));
+    assert_body_not_contains(&target, "/wip.md", "draft left out of backlinks");
+    let draft = fetch(&c, &server.url("/wip.md"));
+    assert_status(&draft, 200);
+    assert_body_contains(&draft, "class=\"draft-notice\"", "draft banner");
+    assert_body_not_contains(&target, "class=\"draft-notice\"", "no banner");
+    drop(server);
+
+    let server = ServerHandle::new_with_env(
+        "test_drafts_are_hidden_unless_drafts_flag --drafts",
+        &fixture,
+        &["--drafts"],
+        &[],
+        &[],
+    );
+    let index = fetch(&c, &server.url("/"));
+    assert_body_contains(&index, "wip.md", "draft listed with --drafts");
+    let search = fetch(&c, &server.url("/_mdmd/search?q=zanzibar"));
+    assert_body_contains(&search, "/wip.md", "draft searched with --drafts");
+    let target = fetch(&c, &server.url("/fixture.md"));
+    assert_body_contains(&target, "/wip.md", "draft backlink with --drafts");
+}
+
+/// GET /_mdmd/search?q=... returns matching lines across files with
+/// text-fragment hrefs.
+#[test]
+fn test_search_endpoint_returns_hits_across_files() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir_all(fixture.root.join("docs")).expect("create docs dir");
+    fs::write(
+        fixture.root.join("docs/guide.md"),
+        "# Guide\n\nThe quick brown fox.\n",
+    )
+    .expect("write docs/guide.md");
+    let server = ServerHandle::new("test_search_endpoint_returns_hits_across_files", &fixture);
+    let c = client();
+
+    let resp = fetch(&c, &server.url("/_mdmd/search?q=Quick+BROWN"));
+    assert_status(&resp, 200);
+    assert_header_contains(&resp, "content-type", "application/json");
+
+    let json: serde_json::Value =
+        serde_json::from_str(&resp.body_text()).expect("search response must be valid JSON");
+    assert_eq!(json["query"], "Quick BROWN");
+    assert_eq!(json["truncated"], false);
+    let results = json["results"].as_array().expect("results array");
+    assert_eq!(results.len(), 1, "one hit expected, got {results:?}");
+    assert_eq!(results[0]["path"], "/docs/guide.md");
+    assert_eq!(results[0]["title"], "Guide");
+    assert_eq!(results[0]["line"], 3);
+    assert_eq!(results[0]["href"], "/docs/guide.md#:~:text=quick%20brown");
+
+    let empty = fetch(&c, &server.url("/_mdmd/search?q="));
+    assert_status(&empty, 200);
+    let json: serde_json::Value = serde_json::from_str(&empty.body_text()).expect("empty JSON");
+    assert!(json["results"]
+        .as_array()
+        .expect("results array")
+        .is_empty());
+}
+
+// ---------------------------------------------------------------------------
+// Document structure endpoint
+// ---------------------------------------------------------------------------
+
+/// GET /_mdmd/doc?path=... returns headings, links, code blocks, and
+/// frontmatter of a markdown file as JSON.
+#[test]
+fn test_doc_endpoint_returns_structure() {
+    let fixture = make_freshness_fixture();
+    fs::write(
+        fixture.root.join("structured.md"),
+        "---\ntitle: Structured\n---\n# Top\n\n## Child\n\nSee [fixture](fixture.md).\n\n```sh\nls\n```\n",
+    )
+    .expect("write structured.md");
+    let server = ServerHandle::new("test_doc_endpoint_returns_structure", &fixture);
+    let c = client();
+
+    let resp = fetch(&c, &server.url("/_mdmd/doc?path=structured.md"));
+    assert_status(&resp, 200);
+    assert_header_contains(&resp, "content-type", "application/json");
+    let json: serde_json::Value =
+        serde_json::from_str(&resp.body_text()).expect("doc response must be valid JSON");
+    assert_eq!(json["path"], "/structured.md");
+    assert_eq!(json["title"], "Structured");
+    assert_eq!(json["frontmatter"]["title"], "Structured");
+    assert_eq!(json["headings"][0]["anchor"], "top");
+    assert_eq!(json["headings"][0]["children"][0]["text"], "Child");
+    assert_eq!(json["headings"][0]["children"][0]["line"], 6);
+    assert_eq!(json["links"][0]["url"], "fixture.md");
+    assert_eq!(json["code_blocks"][0]["language"], "sh");
+
+    for bad in [
+        "/_mdmd/doc?path=missing.md",
+        "/_mdmd/doc?path=..%2F..%2Fetc%2Fpasswd",
+        "/_mdmd/doc",
+    ] {
+        let resp = fetch(&c, &server.url(bad));
+        assert_status(&resp, 404);
+        assert_header_contains(&resp, "content-type", "application/json");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// API description and versioning
+// ---------------------------------------------------------------------------
+
+/// GET /_mdmd/api.json serves an OpenAPI document, and every versioned GET
+/// path it lists is routed to a JSON endpoint.
+#[test]
+fn test_api_description_lists_routed_endpoints() {
+    let fixture = make_freshness_fixture();
+    let server = ServerHandle::new("test_api_description_lists_routed_endpoints", &fixture);
+    let c = client();
+
+    let resp = fetch(&c, &server.url("/_mdmd/api.json"));
+    assert_status(&resp, 200);
+    assert_header_contains(&resp, "content-type", "application/json");
+    let spec: serde_json::Value =
+        serde_json::from_str(&resp.body_text()).expect("api.json must be valid JSON");
+    assert!(spec["openapi"].as_str().unwrap_or("").starts_with("3."));
+
+    let paths = spec["paths"].as_object().expect("paths object");
+    let versioned: Vec<&String> = paths
+        .keys()
+        .filter(|p| p.starts_with("/_mdmd/v1/") && paths[*p].get("get").is_some())
+        .collect();
+    assert_eq!(versioned.len(), 4, "versioned paths: {versioned:?}");
+    assert!(paths["/_mdmd/v1/toggle-task"].get("post").is_some());
+    for path in versioned {
+        let url = format!("{path}?path=fixture.md&q=test");
+        let resp = fetch(&c, &server.url(&url));
+        assert_status(&resp, 200);
+        assert_header_contains(&resp, "content-type", "application/json");
+
+        // The unversioned alias answers identically.
+        let alias = url.replace("/_mdmd/v1/", "/_mdmd/");
+        assert_eq!(fetch(&c, &server.url(&alias)).body_text(), resp.body_text());
+    }
+}
+
+#[test]
+fn test_serve_pages_have_accessible_landmarks() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new("test_serve_pages_have_accessible_landmarks", &fixture);
+    let c = client();
+
+    let page = fetch(&c, &server.url("/README.md"));
+    assert_status(&page, 200);
+    assert_accessible(&page);
+    assert_body_contains(
+        &page,
+        "<a class=\"skip-link\" href=\"#mdmd-content\">",
+        "skip link",
+    );
+    assert_body_order(
+        &page,
+        "class=\"skip-link\"",
+        "<nav class=\"toc-sidebar\"",
+        "skip link comes first",
+    );
+    assert_body_contains(&page, "aria-label=\"Table of contents\"", "labelled TOC");
+    assert_body_contains(
+        &page,
+        "id=\"theme-toggle\" class=\"theme-toggle\" aria-label=\"Toggle dark mode\" aria-pressed=",
+        "theme toggle state",
+    );
+
+    let index = fetch(&c, &server.url("/"));
+    assert_status(&index, 200);
+    assert_accessible(&index);
+
+    let not_found = fetch(&c, &server.url("/missing.md"));
+    assert_status(&not_found, 404);
+    assert_accessible(&not_found);
+}
+
+#[test]
+fn test_serve_open_graph_canonical_url_uses_request_host() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new(
+        "test_serve_open_graph_canonical_url_uses_request_host",
+        &fixture,
+    );
+
+    let resp = fetch(&client(), &server.url("/README.md"));
+    assert_status(&resp, 200);
+    let canonical = format!("{}/README.md", server.base_url);
+    assert_body_contains(
+        &resp,
+        &format!("<link rel=\"canonical\" href=\"{canonical}\">"),
+        "canonical link",
+    );
+    assert_body_contains(
+        &resp,
+        &format!("<meta property=\"og:url\" content=\"{canonical}\">"),
+        "og:url",
+    );
+    assert_body_contains(
+        &resp,
+        "<meta property=\"og:title\" content=\"Home\">",
+        "og:title",
+    );
+}
+
+#[test]
+fn test_serve_default_favicon_and_site_title() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let server = ServerHandle::new_with_env(
+        "test_serve_default_favicon_and_site_title",
+        &fixture,
+        &["--site-title", "Team <Docs>"],
+        &[],
+        &[],
+    );
+    let c = client();
+
+    let icon = fetch(&c, &server.url("/favicon.ico"));
+    assert_status(&icon, 200);
+    assert_header_eq(&icon, "content-type", "image/svg+xml");
+    assert!(icon.header("etag").is_some(), "{}", icon.context());
+    assert!(icon.body_text().starts_with("<svg"), "{}", icon.context());
+
+    let page = fetch(&c, &server.url("/README.md"));
+    assert_body_contains(
+        &page,
+        "<title>Home · Team &lt;Docs&gt;</title>",
+        "page title",
+    );
+    assert_body_contains(
+        &page,
+        "<link rel=\"icon\" href=\"/favicon.ico\">",
+        "icon link",
+    );
+    assert_body_contains(
+        &page,
+        "<a class=\"site-title\" href=\"/\">Team &lt;Docs&gt;</a>",
+        "page header",
+    );
+
+    let index = fetch(&c, &server.url("/"));
+    assert_body_contains(
+        &index,
+        "<title>Index of / · Team &lt;Docs&gt;</title>",
+        "index title",
+    );
+    assert_body_contains(&index, "class=\"site-header\"", "index header");
+    assert_accessible(&index);
+}
+
+#[test]
+fn test_serve_custom_favicon() {
+    let fixture = Fixture::new(FixtureOptions::default());
+    let icon_path = fixture.root.join("brand.png");
+    fs::write(&icon_path, b"\x89PNG\r\n\x1a\nnot-really").expect("write icon");
+    let server = ServerHandle::new_with_env(
+        "test_serve_custom_favicon",
+        &fixture,
+        &["--favicon", icon_path.to_str().unwrap()],
+        &[],
+        &[],
+    );
+
+    let icon = fetch(&client(), &server.url("/favicon.ico"));
+    assert_status(&icon, 200);
+    assert_header_eq(&icon, "content-type", "image/png");
+    assert_eq!(icon.body, b"\x89PNG\r\n\x1a\nnot-really");
+}
+
+#[test]
+fn test_serve_directory_index_details_sort_and_readme_preview() {
+    let tmp = tempfile::tempdir().expect("create tempdir");
+    let root = tmp.path().to_path_buf();
+    let entry = root.join("README.md");
+    fs::write(&entry, "# Project Root\n\nWelcome text.\n").expect("write README");
+    fs::write(root.join("big.txt"), "x".repeat(4096)).expect("write big.txt");
+    fs::write(
+        root.join("a-notes.md"),
+        "---\ntags: [x]\n---\n## Meeting Notes\n",
+    )
+    .expect("write notes");
+    let fixture = Fixture {
+        _tmp: tmp,
+        root,
+        entry,
+    };
+    let server = ServerHandle::new("test_serve_directory_index_details", &fixture);
+    let c = client();
+
+    let index = fetch(&c, &server.url("/"));
+    assert_status(&index, 200);
+    assert_body_contains(&index, "<td>Meeting Notes</td>", "first-heading title");
+    assert_body_contains(&index, "<td>4.0 KiB</td>", "file size");
+    assert_body_contains(&index, "<time datetime=\"", "modification time");
+    assert_body_contains(
+        &index,
+        "<th scope=\"col\" aria-sort=\"ascending\">",
+        "sorted column",
+    );
+    assert_body_order(&index, "a-notes.md", "big.txt", "name order");
+    assert_body_order(
+        &index,
+        "</table>",
+        "class=\"readme-preview\"",
+        "preview after listing",
+    );
+    assert_body_contains(&index, "Welcome text.", "README preview");
+    assert_accessible(&index);
+
+    let by_size = fetch(&c, &server.url("/?sort=size"));
+    assert_status(&by_size, 200);
+    assert_body_contains(
+        &by_size,
+        "aria-sort=\"descending\"><a href=\"?sort=size&amp;order=asc\">Size",
+        "size header",
+    );
+    assert_body_order(&by_size, ">big.txt<", ">a-notes.md<", "largest first");
+}
+
+#[test]
+fn test_tree_endpoint_lists_markdown_files_as_json_and_html() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir_all(fixture.root.join("docs/api")).expect("create docs/api");
+    fs::create_dir_all(fixture.root.join("images")).expect("create images");
+    fs::write(fixture.root.join("docs/api/user guide.md"), "# Guide\n").expect("write guide");
+    fs::write(fixture.root.join("images/logo.png"), "png").expect("write logo");
+    let server = ServerHandle::new("test_tree_endpoint", &fixture);
+    let c = client();
+
+    let resp = fetch(&c, &server.url("/_mdmd/v1/tree"));
+    assert_status(&resp, 200);
+    assert_header_contains(&resp, "content-type", "application/json");
+    assert_header_contains(&resp, "cache-control", "no-store");
+    let tree: serde_json::Value =
+        serde_json::from_str(&resp.body_text()).expect("tree must be valid JSON");
+    let top: Vec<&str> = tree["children"]
+        .as_array()
+        .expect("children array")
+        .iter()
+        .map(|n| n["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(top, ["docs", "fixture.md"], "images/ has no markdown");
+    let guide = &tree["children"][0]["children"][0]["children"][0];
+    assert_eq!(guide["type"], "file");
+    assert_eq!(guide["path"], "/docs/api/user guide.md");
+    assert_eq!(guide["href"], "/docs/api/user%20guide.md");
+
+    let html = fetch(&c, &server.url("/_mdmd/tree?format=html"));
+    assert_status(&html, 200);
+    assert_header_contains(&html, "content-type", "text/html");
+    assert_body_contains(
+        &html,
+        "<li class=\"site-tree-dir\"><details><summary>docs</summary>",
+        "collapsible directory",
+    );
+    assert_body_contains(
+        &html,
+        "<a href=\"/docs/api/user%20guide.md\">user guide.md</a>",
+        "file link",
+    );
+
+    let page = fetch(&c, &server.url("/fixture.md"));
+    assert_body_contains(
+        &page,
+        "data-src=\"/_mdmd/tree?format=html\"",
+        "sidebar tree placeholder",
+    );
+}
+
+/// GET /_mdmd/archive zips a directory: markdown rendered to HTML (or as
+/// sources with raw=1) beside its other files, leaving out what directory
+/// indexes leave out.
+#[test]
+fn test_archive_zips_a_directory() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir_all(fixture.root.join("docs/img")).expect("create docs/img");
+    fs::write(fixture.root.join("docs/guide.md"), "# Guide\n\nRead me.\n").expect("write guide");
+    fs::write(fixture.root.join("docs/img/logo.png"), "png").expect("write logo");
+    fs::write(fixture.root.join("docs/.env"), "SECRET=1\n").expect("write dotfile");
+    fs::write(
+        fixture.root.join("docs/wip.md"),
+        "---\ndraft: true\n---\n# WIP\n",
+    )
+    .expect("write draft");
+    let server = ServerHandle::new("test_archive_zips_a_directory", &fixture);
+    let c = client();
+    let has = |resp: &ResponseSnapshot, needle: &[u8]| {
+        resp.body.windows(needle.len()).any(|w| w == needle)
+    };
+
+    let html = fetch(&c, &server.url("/_mdmd/v1/archive?path=docs"));
+    assert_status(&html, 200);
+    assert_header_contains(&html, "content-type", "application/zip");
+    assert_header_contains(&html, "content-disposition", "filename=\"docs.zip\"");
+    assert!(html.body.starts_with(b"PK\x03\x04"), "zip signature");
+    assert!(has(&html, b"docs/guide.html"), "page rendered to HTML");
+    assert!(has(&html, b"<h1 id=\"guide\">"), "rendered markup");
+    assert!(has(&html, b"docs/img/logo.png"), "subdirectory files");
+    assert!(!has(&html, b"docs/.env"), "dotfiles left out");
+    assert!(!has(&html, b"docs/wip"), "drafts left out");
+
+    let raw = fetch(&c, &server.url("/_mdmd/archive?path=docs&raw=1"));
+    assert_status(&raw, 200);
+    assert!(has(&raw, b"docs/guide.md"), "markdown source");
+    assert!(has(&raw, b"# Guide\n\nRead me.\n"), "source text");
+
+    assert_status(&fetch(&c, &server.url("/_mdmd/archive?path=missing")), 404);
+    assert_status(
+        &fetch(&c, &server.url("/_mdmd/archive?path=fixture.md")),
+        404,
+    );
+
+    let index = fetch(&c, &server.url("/docs/"));
+    assert_body_contains(
+        &index,
+        "href=\"/_mdmd/archive?path=/docs/\" download>",
+        "archive link",
+    );
+}
+
+/// Serving several paths indexes exactly those: other markdown files under
+/// the common root are left out of listings and the tree and answer 404.
+#[test]
+fn test_serving_several_paths_covers_only_those() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir_all(fixture.root.join("docs")).expect("create docs");
+    fs::write(
+        fixture.root.join("docs/guide.md"),
+        "# Guide\n\nInstall it first.\n",
+    )
+    .expect("write guide");
+    fs::write(fixture.root.join("docs/other.md"), "# Other\n").expect("write other");
+    let server = ServerHandle::new_with_env(
+        "test_serving_several_paths_covers_only_those",
+        &fixture,
+        &["docs/guide.md"],
+        &[],
+        &[],
+    );
+    let c = client();
+
+    assert_status(&fetch(&c, &server.url("/docs/guide.md")), 200);
+    assert_status(&fetch(&c, &server.url("/fixture.md")), 200);
+    assert_status(&fetch(&c, &server.url("/docs/other.md")), 404);
+
+    let index = fetch(&c, &server.url("/docs/"));
+    assert_status(&index, 200);
+    assert_body_contains(&index, "guide.md", "selected file listed");
+    assert!(
+        !index.body_text().contains("other.md"),
+        "unselected file listed:\n{}",
+        index.context()
+    );
+
+    let tree = fetch(&c, &server.url("/_mdmd/v1/tree"));
+    assert_body_contains(&tree, "docs/guide.md", "selected file in tree");
+    assert!(
+        !tree.body_text().contains("other.md"),
+        "unselected file in tree:\n{}",
+        tree.context()
+    );
+
+    // The entry is the collection page, grouped by directory.
+    let collection = fetch(&c, &server.url("/_mdmd/collection"));
+    assert_status(&collection, 200);
+    assert_body_contains(&collection, "2 markdown files", "file count");
+    assert_body_contains(
+        &collection,
+        "<h2><a href=\"/docs/\">/docs/</a></h2>",
+        "directory group",
+    );
+    assert_body_contains(
+        &collection,
+        "<a href=\"/docs/guide.md\">Guide</a>",
+        "title links the page",
+    );
+    assert_body_contains(&collection, "<p>Install it first.</p>", "summary");
+    assert!(
+        !collection.body_text().contains("other.md"),
+        "unselected file collected:\n{}",
+        collection.context()
+    );
+}
+
+#[test]
+fn test_combined_page_joins_a_directory() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir_all(fixture.root.join("docs/api")).expect("create docs/api");
+    fs::write(
+        fixture.root.join("docs/README.md"),
+        "# Docs\n\nSee the [guide](guide.md#setup).\n",
+    )
+    .expect("write readme");
+    fs::write(fixture.root.join("docs/guide.md"), "# Guide\n\n## Setup\n").expect("write guide");
+    fs::write(fixture.root.join("docs/api/ref.md"), "# Reference\n").expect("write ref");
+    let server = ServerHandle::new("test_combined_page_joins_a_directory", &fixture);
+    let c = client();
+
+    let page = fetch(&c, &server.url("/_mdmd/combined?dir=docs"));
+    assert_status(&page, 200);
+    assert_header_contains(&page, "content-type", "text/html");
+    let body = page.body_text();
+    let at = |needle: &str| {
+        body.find(needle)
+            .unwrap_or_else(|| panic!("missing {needle}:\n{}", page.context()))
+    };
+    assert!(
+        at("id=\"docs-readme-md\"") < at("id=\"docs-guide-md\"")
+            && at("id=\"docs-guide-md\"") < at("id=\"docs-api-ref-md\""),
+        "README first, subdirectories last"
+    );
+    assert_body_contains(&page, "href=\"#docs-guide-md--setup\"", "link jumps");
+    assert_body_contains(&page, "id=\"docs-guide-md--setup\"", "prefixed anchor");
+    assert!(!body.contains("fixture-md"), "files outside dir left out");
+
+    assert_status(&fetch(&c, &server.url("/_mdmd/v1/combined")), 200);
+    assert_status(
+        &fetch(&c, &server.url("/_mdmd/combined?dir=fixture.md")),
+        404,
+    );
+}
+
+fn post_json(client: &Client, url: &str, body: &str) -> ResponseSnapshot {
+    let resp = client
+        .post(url)
+        .header("content-type", "application/json")
+        .body(body.to_owned())
+        .send()
+        .unwrap_or_else(|e| panic!("POST {} failed: {e}", url));
+    let status = resp.status().as_u16();
+    let headers = resp.headers().clone();
+    let body = resp
+        .bytes()
+        .unwrap_or_else(|e| panic!("read body for {} failed: {e}", url))
+        .to_vec();
+
+    ResponseSnapshot {
+        status,
+        headers,
+        body,
+    }
+}
+
+#[test]
+fn test_toggle_task_requires_allow_edit_and_rewrites_the_file() {
+    let fixture = make_freshness_fixture();
+    let todo = fixture.root.join("todo.md");
+    fs::write(&todo, "---\ntitle: Todo\n---\n- [ ] milk\n- [x] eggs\n").expect("write todo");
+    let c = client();
+
+    let locked = ServerHandle::new("test_toggle_task_locked", &fixture);
+    let page = fetch(&c, &locked.url("/todo.md"));
+    assert_body_not_contains(&page, "task-toggle", "checkboxes stay disabled");
+    let resp = post_json(
+        &c,
+        &locked.url("/_mdmd/v1/toggle-task?path=todo.md"),
+        "{\"line\": 4, \"checked\": false}",
+    );
+    assert_status(&resp, 403);
+    drop(locked);
+
+    let server = ServerHandle::new_with_env(
+        "test_toggle_task_allow_edit",
+        &fixture,
+        &["--allow-edit"],
+        &[],
+        &[],
+    );
+    let page = fetch(&c, &server.url("/todo.md"));
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" class=\"task-toggle\" data-line=\"4\" />",
+        "line counts frontmatter",
+    );
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" class=\"task-toggle\" data-line=\"5\" checked=\"\" />",
+        "checked task",
+    );
+
+    let url = server.url("/_mdmd/toggle-task?path=todo.md");
+    let resp = post_json(&c, &url, "{\"line\": 4, \"checked\": false}");
+    assert_status(&resp, 200);
+    let json: serde_json::Value = serde_json::from_str(&resp.body_text()).expect("valid JSON");
+    assert_eq!(json["checked"], true);
+    assert!(json["mtime"].as_u64().unwrap_or(0) > 0, "{json}");
+    assert_eq!(
+        fs::read_to_string(&todo).expect("read todo"),
+        "---\ntitle: Todo\n---\n- [x] milk\n- [x] eggs\n"
+    );
+
+    // The page's state is stale now: refuse rather than flip it back.
+    let resp = post_json(&c, &url, "{\"line\": 4, \"checked\": false}");
+    assert_status(&resp, 409);
+    let resp = post_json(&c, &url, "{\"line\": 1, \"checked\": false}");
+    assert_status(&resp, 409);
+    let resp = post_json(&c, &url, "{\"line\": 0}");
+    assert_status(&resp, 400);
+    let resp = c
+        .post(&url)
+        .header("content-type", "application/x-www-form-urlencoded")
+        .body("line=4&checked=true")
+        .send()
+        .expect("form POST");
+    assert_eq!(resp.status().as_u16(), 415);
+    let resp = post_json(
+        &c,
+        &server.url("/_mdmd/toggle-task?path=../escape.md"),
+        "{\"line\": 1, \"checked\": false}",
+    );
+    assert_status(&resp, 404);
+}
+
+/// An editor's POST /_mdmd/v1/scroll is mapped onto the file's headings and
+/// handed to pages waiting on the same path.
+#[test]
+fn test_scroll_request_reaches_waiting_pages() {
+    let fixture = make_freshness_fixture();
+    fs::write(
+        fixture.root.join("guide.md"),
+        "# Intro\n\none\ntwo\nthree\n\n# Usage\n\nend\n",
+    )
+    .expect("write guide");
+    let server = ServerHandle::new("test_scroll_request_reaches_waiting_pages", &fixture);
+    let c = client();
+
+    let url = server.url("/_mdmd/v1/scroll?path=guide.md");
+    let resp = post_json(&c, &url, "{\"line\": 4}");
+    assert_status(&resp, 200);
+    let json: serde_json::Value = serde_json::from_str(&resp.body_text()).expect("valid JSON");
+    assert_eq!(json["seq"], 1);
+    assert_eq!(json["anchor"], "intro");
+    assert_eq!(json["next"], "usage");
+    assert_eq!(json["fraction"], 0.5);
+
+    // A page that has not seen it yet gets it without waiting.
+    let waited = fetch(&c, &server.url("/_mdmd/scroll?path=guide.md&after=0"));
+    assert_status(&waited, 200);
+    assert_eq!(waited.body_text(), resp.body_text());
+
+    assert_status(&post_json(&c, &url, "{\"line\": 0}"), 400);
+    let resp = c
+        .post(&url)
+        .header("content-type", "text/plain")
+        .body("{\"line\": 4}")
+        .send()
+        .expect("text POST");
+    assert_eq!(resp.status().as_u16(), 415);
+    let missing = server.url("/_mdmd/v1/scroll?path=missing.md");
+    assert_status(&post_json(&c, &missing, "{\"line\": 1}"), 404);
+}
+
+/// Commands written to the --control socket reach the pages waiting on
+/// /_mdmd/v1/scroll, and `quit` stops the server.
+#[cfg(unix)]
+#[test]
+fn test_control_socket_drives_pages_and_quits() {
+    use std::os::unix::net::UnixStream;
+
+    let fixture = make_freshness_fixture();
+    let socket = fixture.root.join("control.sock");
+    let mut server = ServerHandle::new_with_env(
+        "test_control_socket_drives_pages_and_quits",
+        &fixture,
+        &["--control", socket.to_str().expect("UTF-8 temp path")],
+        &[],
+        &[],
+    );
+    let c = client();
+    let mut control = UnixStream::connect(&socket).expect("connect control socket");
+
+    control.write_all(b"reload\n").expect("send reload");
+    let waited = fetch(&c, &server.url("/_mdmd/v1/scroll?path=fixture.md&after=0"));
+    assert_status(&waited, 200);
+    let json: serde_json::Value = serde_json::from_str(&waited.body_text()).expect("valid JSON");
+    assert_eq!(json["reload"], true);
+    assert_eq!(json["seq"], 1);
+
+    // Without a file, `scroll` is for the entry file.
+    control.write_all(b"scroll 3\n").expect("send scroll");
+    let waited = fetch(&c, &server.url("/_mdmd/v1/scroll?path=fixture.md&after=1"));
+    assert_status(&waited, 200);
+    let json: serde_json::Value = serde_json::from_str(&waited.body_text()).expect("valid JSON");
+    assert_eq!(json["seq"], 2);
+    assert_eq!(json["anchor"], "test");
+
+    control.write_all(b"quit\n").expect("send quit");
+    let mut child = server.child.take().expect("server child exists");
+    wait_with_timeout(&mut child, Duration::from_secs(5));
+    assert!(
+        child.try_wait().expect("try_wait child").is_some(),
+        "server still running after quit"
+    );
+    assert!(!socket.exists(), "control socket removed on exit");
+}
+
+/// --audit-log records each request and the shutdown summary says who
+/// read what.
+#[cfg(unix)]
+#[test]
+fn test_audit_log_records_requests_and_summarizes_on_shutdown() {
+    use std::os::unix::net::UnixStream;
+
+    let fixture = make_freshness_fixture();
+    let socket = fixture.root.join("control.sock");
+    let log = fixture.root.join("logs/audit.log");
+    let mut server = ServerHandle::new_with_env(
+        "test_audit_log_records_requests_and_summarizes_on_shutdown",
+        &fixture,
+        &[
+            "--control",
+            socket.to_str().expect("UTF-8 temp path"),
+            "--audit-log",
+            log.to_str().expect("UTF-8 temp path"),
+        ],
+        &[],
+        &[],
+    );
+    let page = fetch(&client(), &server.url("/fixture.md"));
+    assert_status(&page, 200);
+    assert_status(&fetch(&client(), &server.url("/missing.md")), 404);
+
+    let mut control = UnixStream::connect(&socket).expect("connect control socket");
+    control.write_all(b"quit\n").expect("send quit");
+    let mut child = server.child.take().expect("server child exists");
+    wait_with_timeout(&mut child, Duration::from_secs(5));
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .expect("piped stderr")
+        .read_to_string(&mut stderr)
+        .expect("read stderr");
+
+    let text = fs::read_to_string(&log).expect("read audit log");
+    assert!(text.starts_with("# "), "run header\n{text}");
+    let bytes = page.body.len();
+    assert!(
+        text.contains(&format!(" 127.0.0.1 GET /fixture.md 200 {bytes}\n")),
+        "page request logged\n{text}"
+    );
+    assert!(
+        text.contains(" 127.0.0.1 GET /missing.md 404 "),
+        "failed request logged\n{text}"
+    );
+    assert!(stderr.contains("audit: "), "summary printed\n{stderr}");
+    assert!(
+        stderr.contains("  127.0.0.1: ") && stderr.contains("\n    /fixture.md\n"),
+        "summary lists what was read\n{stderr}"
+    );
+    assert!(
+        !stderr.contains("    /missing.md"),
+        "failed reads are not listed\n{stderr}"
+    );
+}
+
+/// With --share only requests carrying the printed link's token are
+/// answered, the first one leaves a cookie for the rest, and the server
+/// exits when the share expires.
+#[test]
+fn test_share_link_requires_token_and_expires() {
+    use std::io::{BufRead, BufReader};
+
+    let fixture = make_freshness_fixture();
+    let mut server = ServerHandle::new_with_env(
+        "test_share_link_requires_token_and_expires",
+        &fixture,
+        &["--share", "5s"],
+        &[],
+        &[],
+    );
+    let mut child = server.child.take().expect("server child exists");
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().expect("piped stdout"))
+        .read_line(&mut line)
+        .expect("read startup URL");
+    let token = line
+        .trim()
+        .split_once("?share=")
+        .map(|(_, token)| token.to_owned())
+        .unwrap_or_else(|| panic!("startup URL carries the token: {line:?}"));
+
+    let c = client();
+    let refused = fetch(&c, &server.url("/fixture.md"));
+    assert_status(&refused, 403);
+    assert_body_contains(&refused, "share link", "403 names the share link");
+
+    let shared = fetch(&c, &server.url(&format!("/fixture.md?share={token}")));
+    assert_status(&shared, 200);
+    let cookie = shared.header("set-cookie").unwrap_or_default();
+    assert!(
+        cookie.starts_with(&format!("mdmd_share={token};")),
+        "token kept in a cookie: {cookie}"
+    );
+    let with_cookie = fetch_with_headers(
+        &c,
+        &server.url("/fixture.md"),
+        &[("cookie", &format!("mdmd_share={token}"))],
+    );
+    assert_status(&with_cookie, 200);
+
+    wait_with_timeout(&mut child, Duration::from_secs(15));
+    assert!(
+        child.try_wait().expect("try_wait server").is_some(),
+        "server exits when the share expires"
+    );
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .expect("piped stderr")
+        .read_to_string(&mut stderr)
+        .expect("read stderr");
+    assert!(stderr.contains("Share link expired"), "{stderr}");
+}
+
+/// Unlinked mentions are listed under the backlinks; with --allow-edit one
+/// can be linked, rewriting the mentioning file.
+#[test]
+fn test_link_mention_rewrites_the_mentioning_file() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir_all(fixture.root.join("docs")).expect("mkdir docs");
+    fs::write(
+        fixture.root.join("docs/deploy.md"),
+        "# Deploy Guide\n\nSteps.\n",
+    )
+    .expect("write deploy");
+    let notes = fixture.root.join("notes.md");
+    fs::write(&notes, "# Notes\n\nRead the deploy guide first.\n").expect("write notes");
+    let c = client();
+
+    let locked = ServerHandle::new("test_link_mention_locked", &fixture);
+    let page = fetch(&c, &locked.url("/docs/deploy.md"));
+    assert_body_contains(&page, ">Mentions (1)<", "mentions section");
+    assert_body_contains(
+        &page,
+        "Read the <mark class=\"backlinks-link-text\">deploy guide</mark> first.",
+        "mention snippet",
+    );
+    assert_body_not_contains(&page, "mention-link", "no button without --allow-edit");
+    let body =
+        "{\"target\": \"/docs/deploy.md\", \"line\": 3, \"column\": 9, \"text\": \"deploy guide\"}";
+    let resp = post_json(
+        &c,
+        &locked.url("/_mdmd/v1/link-mention?path=notes.md"),
+        body,
+    );
+    assert_status(&resp, 403);
+    drop(locked);
+
+    let server = ServerHandle::new_with_env(
+        "test_link_mention_allow_edit",
+        &fixture,
+        &["--allow-edit"],
+        &[],
+        &[],
+    );
+    let page = fetch(&c, &server.url("/docs/deploy.md"));
+    assert_body_contains(
+        &page,
+        "data-source=\"/notes.md\" data-line=\"3\" data-column=\"9\" data-text=\"deploy guide\"",
+        "link button",
+    );
+
+    // Only indexed mentions can be linked.
+    let resp = post_json(
+        &c,
+        &server.url("/_mdmd/v1/link-mention?path=fixture.md"),
+        body,
+    );
+    assert_status(&resp, 404);
+
+    let url = server.url("/_mdmd/link-mention?path=notes.md");
+    let resp = post_json(&c, &url, body);
+    assert_status(&resp, 200);
+    let json: serde_json::Value = serde_json::from_str(&resp.body_text()).expect("valid JSON");
+    assert!(json["mtime"].as_u64().unwrap_or(0) > 0, "{json}");
+    assert_eq!(
+        fs::read_to_string(&notes).expect("read notes"),
+        "# Notes\n\nRead the [deploy guide](docs/deploy.md) first.\n"
+    );
+
+    // The mention is gone from the file now.
+    let resp = post_json(&c, &url, body);
+    assert_status(&resp, 409);
+    let resp = post_json(&c, &url, "{\"target\": \"/docs/deploy.md\"}");
+    assert_status(&resp, 400);
+}
+
+/// Rendered pages end with their outgoing links and each link's status.
+#[test]
+fn test_page_lists_outgoing_links_with_status() {
+    let fixture = make_freshness_fixture();
+    fs::write(
+        fixture.root.join("links.md"),
+        "# Links\n\n[Home](fixture.md) [Gone](gone.md) [Web](https://example.com)\n",
+    )
+    .expect("write links");
+    let server = ServerHandle::new("test_page_lists_outgoing_links", &fixture);
+    let c = client();
+
+    let page = fetch(&c, &server.url("/links.md"));
+    assert_body_contains(&page, ">Outgoing links (3)<", "outgoing header");
+    assert_body_contains(
+        &page,
+        "<li class=\"backlinks-item outgoing-broken\">\n<a class=\"backlinks-source\" href=\"gone.md\">Gone</a>",
+        "broken link",
+    );
+    assert_body_contains(&page, "outgoing-ok", "resolved link");
+    assert_body_contains(&page, "outgoing-external", "external link");
+}
+
+/// Include directives are expanded in served pages; a checkbox from an
+/// included file stays disabled, and ones after the include keep their lines.
+#[test]
+fn test_page_expands_includes() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir(fixture.root.join("parts")).expect("mkdir parts");
+    fs::write(
+        fixture.root.join("parts/steps.md"),
+        "---\ntitle: Steps\n---\n- [ ] included step\n",
+    )
+    .expect("write steps");
+    fs::write(
+        fixture.root.join("guide.md"),
+        "# Guide\n\n![[parts/steps]]\n\n{{include: missing.md}}\n\n- [ ] own step\n",
+    )
+    .expect("write guide");
+    let server = ServerHandle::new_with_env(
+        "test_page_expands_includes",
+        &fixture,
+        &["--allow-edit"],
+        &[],
+        &[],
+    );
+    let c = client();
+
+    let page = fetch(&c, &server.url("/guide.md"));
+    assert_body_contains(&page, "included step", "included text");
+    assert_body_not_contains(&page, "title: Steps", "included frontmatter");
+    assert_body_contains(
+        &page,
+        "<code>missing.md</code>: no such file",
+        "failed include",
+    );
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" disabled=\"\" /> included step",
+        "included checkbox stays disabled",
+    );
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" class=\"task-toggle\" data-line=\"7\" /> own step",
+        "own checkbox keeps its source line",
+    );
+}
+
+/// A task list from an included file stays disabled, and toggling a task
+/// after it changes that task's own line, not one shifted by the include.
+#[test]
+fn test_toggle_task_after_included_task_list() {
+    let fixture = make_freshness_fixture();
+    fs::create_dir(fixture.root.join("parts")).expect("mkdir parts");
+    let list = fixture.root.join("parts/list.md");
+    fs::write(&list, "- [ ] included one\n- [x] included two\n").expect("write list");
+    let plan = fixture.root.join("plan.md");
+    let source = "- [ ] before\n\nSteps:\n\n![[parts/list]]\n\nThen:\n\n- [ ] after\n";
+    fs::write(&plan, source).expect("write plan");
+    let server = ServerHandle::new_with_env(
+        "test_toggle_task_after_included_task_list",
+        &fixture,
+        &["--allow-edit"],
+        &[],
+        &[],
+    );
+    let c = client();
+
+    let page = fetch(&c, &server.url("/plan.md"));
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" class=\"task-toggle\" data-line=\"1\" /> before",
+        "task before the include",
+    );
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" disabled=\"\" /> included one",
+        "included task stays disabled",
+    );
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" checked=\"\" disabled=\"\" /> included two",
+        "included checked task stays disabled",
+    );
+    assert_body_contains(
+        &page,
+        "<input type=\"checkbox\" class=\"task-toggle\" data-line=\"9\" /> after",
+        "task after the include",
+    );
+    assert_body_not_contains(&page, "data-task", "tags are removed");
+
+    let url = server.url("/_mdmd/toggle-task?path=plan.md");
+    let resp = post_json(&c, &url, "{\"line\": 9, \"checked\": false}");
+    assert_status(&resp, 200);
+    assert_eq!(
+        fs::read_to_string(&plan).expect("read plan"),
+        source.replace("- [ ] after", "- [x] after")
+    );
+    assert_eq!(
+        fs::read_to_string(&list).expect("read list"),
+        "- [ ] included one\n- [x] included two\n"
+    );
+}
+
+/// `{{var.name}}` placeholders are filled from `.mdmd.toml` and frontmatter
+/// `vars:`, escaped ones are kept, and `--no-vars` leaves them all as written.
+#[test]
+fn test_page_substitutes_vars() {
+    let fixture = make_freshness_fixture();
+    fs::write(
+        fixture.root.join(".mdmd.toml"),
+        "[vars]\nproduct = \"Widget\"\nversion = \"1.0\"\n",
+    )
+    .expect("write config");
+    fs::write(
+        fixture.root.join("release.md"),
+        "---\nvars:\n  version: \"2.0\"\n---\n# {{var.product}} {{var.version}}\n\nWrite \\{{var.product}} for the name.\n",
+    )
+    .expect("write release");
+    let c = client();
+
+    let server = ServerHandle::new("test_page_substitutes_vars", &fixture);
+    let page = fetch(&c, &server.url("/release.md"));
+    assert_body_contains(&page, ">Widget 2.0</h1>", "substituted heading");
+    assert_body_contains(&page, "Write {{var.product}} for", "escaped placeholder");
+    drop(server);
+
+    let server = ServerHandle::new_with_env(
+        "test_page_substitutes_vars_disabled",
+        &fixture,
+        &["--no-vars"],
+        &[],
+        &[],
+    );
+    let page = fetch(&c, &server.url("/release.md"));
+    assert_body_contains(&page, "{{var.product}} {{var.version}}", "placeholders kept");
+}
+
+#[test]
+fn test_page_query_overrides_render_options() {
+    let fixture = make_freshness_fixture();
+    fs::write(fixture.root.join("guide.md"), "# Guide\n\n## Install\n").expect("write guide");
+    let c = client();
+    let server = ServerHandle::new("test_page_query_overrides_render_options", &fixture);
+
+    let page = fetch(&c, &server.url("/guide.md"));
+    assert_body_contains(&page, "class=\"toc-h2\"", "sidebar toc by default");
+    assert_body_contains(
+        &page,
+        "localStorage.getItem('mdmd-theme')",
+        "saved theme restored",
+    );
+
+    let page = fetch(&c, &server.url("/guide.md?toc=0&numbered=1&theme=dark"));
+    assert_body_not_contains(&page, "class=\"toc-h2\"", "toc=0 hides the sidebar toc");
+    assert_body_contains(
+        &page,
+        "<html lang=\"en\" data-theme=\"dark\">",
+        "theme=dark",
+    );
+    assert_body_not_contains(
+        &page,
+        "localStorage.getItem('mdmd-theme')",
+        "a forced theme skips the saved one",
+    );
+    assert_body_contains(
+        &page,
+        "<span class=\"heading-number\">1.1</span> Install",
+        "numbered=1",
+    );
+}